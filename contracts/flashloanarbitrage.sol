@@ -16,13 +16,26 @@ interface IFastLaneSender {
         returns (bytes32);
 }
 
+interface IAavePool {
+    function supply(address asset, uint256 amount, address onBehalfOf, uint16 referralCode) external;
+    function withdraw(address asset, uint256 amount, address to) external returns (uint256);
+}
+
+interface IAToken {
+    function UNDERLYING_ASSET_ADDRESS() external view returns (address);
+}
+
 contract FlashLoanArbitrage is IUniswapV3FlashCallback, Ownable {
     ISwapRouter public immutable swapRouter;
     address public immutable WETH;
     address public immutable factory;
     address public fastLaneSender;
     uint256 public maxDelayBlocks = 5;
+    // Hops routed to this address wrap or unwrap an aToken instead of swapping
+    address public aavePool;
     uint24 public constant DEFAULT_FEE = 3000;
+    // Deadlines below this are block numbers, from it on unix timestamps
+    uint256 public constant TIMESTAMP_DEADLINE_FLOOR = 1e9;
 
     struct FlashCallbackData {
         address token0;
@@ -99,6 +112,19 @@ contract FlashLoanArbitrage is IUniswapV3FlashCallback, Ownable {
         maxDelayBlocks = _maxDelayBlocks;
     }
 
+    function setAavePool(address _aavePool) external onlyOwner {
+        aavePool = _aavePool;
+    }
+
+    modifier beforeDeadline(uint256 deadline) {
+        if (deadline < TIMESTAMP_DEADLINE_FLOOR) {
+            require(block.number <= deadline, "Deadline block passed");
+        } else {
+            require(block.timestamp <= deadline, "Deadline passed");
+        }
+        _;
+    }
+
     function executeFlashLoanArbitrage(
         address token0,
         address token1,
@@ -107,8 +133,9 @@ contract FlashLoanArbitrage is IUniswapV3FlashCallback, Ownable {
         uint24 fee,
         address[] calldata path,
         uint256[] calldata amounts,
-        address[] calldata routers
-    ) external onlyOwner {
+        address[] calldata routers,
+        uint256 deadline
+    ) external onlyOwner beforeDeadline(deadline) {
         _executeFlashLoanArbitrage(
             token0,
             token1,
@@ -250,6 +277,11 @@ contract FlashLoanArbitrage is IUniswapV3FlashCallback, Ownable {
             uint256 amountIn = amounts[i];
             address router = routers[i];
 
+            if (router != address(0) && router == aavePool) {
+                _wrapStep(tokenIn, tokenOut, amountIn);
+                continue;
+            }
+
             // Reset and approve token spending
             IERC20(tokenIn).approve(router, 0);
             IERC20(tokenIn).approve(router, amountIn);
@@ -269,17 +301,36 @@ contract FlashLoanArbitrage is IUniswapV3FlashCallback, Ownable {
         }
     }
 
+    // Supplies the underlying for its aToken, or redeems an aToken 1:1 for the underlying
+    function _wrapStep(address tokenIn, address tokenOut, uint256 amountIn) internal {
+        bool wrapping;
+        try IAToken(tokenOut).UNDERLYING_ASSET_ADDRESS() returns (address underlying) {
+            wrapping = underlying == tokenIn;
+        } catch {}
+
+        if (wrapping) {
+            IERC20(tokenIn).approve(aavePool, 0);
+            IERC20(tokenIn).approve(aavePool, amountIn);
+            IAavePool(aavePool).supply(tokenIn, amountIn, address(this), 0);
+        } else {
+            require(IAToken(tokenIn).UNDERLYING_ASSET_ADDRESS() == tokenOut, "Not a wrap step");
+            IAavePool(aavePool).withdraw(tokenOut, amountIn, address(this));
+        }
+    }
+
     function executeArbitrageWithFastLane(
         ArbitrageOpportunity memory opportunity,
-        uint256 targetBlock
-    ) external payable onlyOwner returns (bytes32) {
+        uint256 targetBlock,
+        uint256 deadline
+    ) external payable onlyOwner beforeDeadline(deadline) returns (bytes32) {
         require(targetBlock > block.number, "Invalid block number");
         require(targetBlock <= block.number + maxDelayBlocks, "Block too far");
         require(fastLaneSender != address(0), "FastLane sender not set");
 
         FastLaneBundle memory bundle = prepareFastLaneBundle(
             opportunity,
-            targetBlock
+            targetBlock,
+            deadline
         );
 
         // ⬇️ call the renamed method
@@ -293,7 +344,8 @@ contract FlashLoanArbitrage is IUniswapV3FlashCallback, Ownable {
 
     function prepareFastLaneBundle(
         ArbitrageOpportunity memory opportunity,
-        uint256 targetBlock
+        uint256 targetBlock,
+        uint256 deadline
     ) internal pure returns (FastLaneBundle memory) {
         bytes memory callData = abi.encodeWithSelector(
             FlashLoanArbitrage.executeFlashLoanArbitrage.selector,
@@ -304,7 +356,8 @@ contract FlashLoanArbitrage is IUniswapV3FlashCallback, Ownable {
             opportunity.fee,
             opportunity.path,
             opportunity.amounts,
-            opportunity.routers
+            opportunity.routers,
+            deadline
         );
 
         return FastLaneBundle({data: callData, targetBlock: targetBlock});
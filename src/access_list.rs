@@ -0,0 +1,159 @@
+// src/access_list.rs
+use anyhow::{anyhow, Result};
+use ethers::{
+    providers::Middleware,
+    types::{
+        transaction::{
+            eip2718::TypedTransaction,
+            eip2930::{AccessList, AccessListItem},
+        },
+        Address, BlockId, H256,
+    },
+};
+use log::{debug, warn};
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::Arc;
+
+use crate::preflight::{address_word, mapping_slot, u64_word, TokenStorageLayout};
+
+// UniswapV2Pair slots touched by swap(): token0, token1, reserves,
+// price0/price1 cumulative and the reentrancy lock
+const V2_PAIR_SWAP_SLOTS: [u64; 6] = [6, 7, 8, 9, 10, 12];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AccessListMode {
+    // Send transactions without an access list
+    Off,
+    // Derive the list from the route's pools and tokens
+    Static,
+    // Ask the node via eth_createAccessList, falling back to the static list
+    #[default]
+    Rpc,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct AccessListSettings {
+    #[serde(default)]
+    pub mode: AccessListMode,
+}
+
+// Warms every V2 pair slot swap() reads and each holder's balance slot on every token
+pub fn static_access_list(
+    pools: &[Address],
+    tokens: &[Address],
+    holders: &[Address],
+    layouts: &HashMap<Address, TokenStorageLayout>,
+) -> AccessList {
+    let mut entries: BTreeMap<Address, BTreeSet<H256>> = BTreeMap::new();
+
+    for pool in pools {
+        entries
+            .entry(*pool)
+            .or_default()
+            .extend(V2_PAIR_SWAP_SLOTS.iter().map(|&slot| u64_word(slot)));
+    }
+    for token in tokens {
+        let layout = layouts.get(token).copied().unwrap_or_default();
+        entries.entry(*token).or_default().extend(
+            holders
+                .iter()
+                .map(|&holder| mapping_slot(address_word(holder), u64_word(layout.balance_slot))),
+        );
+    }
+
+    AccessList(
+        entries
+            .into_iter()
+            .map(|(address, keys)| AccessListItem {
+                address,
+                storage_keys: keys.into_iter().collect(),
+            })
+            .collect(),
+    )
+}
+
+#[derive(Debug, Clone)]
+pub struct AccessListBuilder<M> {
+    provider: Arc<M>,
+    settings: AccessListSettings,
+    layouts: HashMap<Address, TokenStorageLayout>,
+}
+
+impl<M: Middleware + 'static> AccessListBuilder<M> {
+    pub fn new(provider: Arc<M>, settings: AccessListSettings) -> Self {
+        Self {
+            provider,
+            settings,
+            layouts: HashMap::new(),
+        }
+    }
+
+    pub fn with_layout(mut self, token: Address, layout: TokenStorageLayout) -> Self {
+        self.layouts.insert(token, layout);
+        self
+    }
+
+    async fn generate(&self, tx: &TypedTransaction, block: Option<BlockId>) -> Result<AccessList> {
+        let generated = self.provider
+            .create_access_list(tx, block)
+            .await
+            .map_err(|e| anyhow!("eth_createAccessList failed: {e}"))?;
+        debug!(
+            "eth_createAccessList: {} entries, gas used {}",
+            generated.access_list.0.len(),
+            generated.gas_used
+        );
+        Ok(generated.access_list)
+    }
+
+    // `pools` and `tokens` describe the route; `holders` are the accounts whose token
+    // balances move (executor and pools)
+    pub async fn attach(
+        &self,
+        tx: &mut TypedTransaction,
+        block: Option<BlockId>,
+        pools: &[Address],
+        tokens: &[Address],
+        holders: &[Address],
+    ) {
+        let list = match self.settings.mode {
+            AccessListMode::Off => return,
+            AccessListMode::Static => static_access_list(pools, tokens, holders, &self.layouts),
+            AccessListMode::Rpc => match self.generate(tx, block).await {
+                Ok(list) => list,
+                Err(e) => {
+                    warn!("{}; using static access list", e);
+                    static_access_list(pools, tokens, holders, &self.layouts)
+                }
+            },
+        };
+        tx.set_access_list(list);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_static_list_covers_pair_and_balance_slots() {
+        let pool = Address::from_low_u64_be(1);
+        let token = Address::from_low_u64_be(2);
+        let executor = Address::from_low_u64_be(3);
+        let mut layouts = HashMap::new();
+        layouts.insert(token, TokenStorageLayout { balance_slot: 9, allowance_slot: 10 });
+
+        let list = static_access_list(&[pool, pool], &[token], &[executor, pool], &layouts);
+
+        assert_eq!(list.0.len(), 2);
+        let pool_entry = list.0.iter().find(|item| item.address == pool).unwrap();
+        assert_eq!(pool_entry.storage_keys.len(), V2_PAIR_SWAP_SLOTS.len());
+        let token_entry = list.0.iter().find(|item| item.address == token).unwrap();
+        assert!(token_entry
+            .storage_keys
+            .contains(&mapping_slot(address_word(executor), u64_word(9))));
+        assert_eq!(token_entry.storage_keys.len(), 2);
+    }
+}
@@ -0,0 +1,270 @@
+// src/admin_watch.rs
+use anyhow::{anyhow, Result};
+use ethers::{
+    providers::Middleware,
+    types::{Address, Filter, H256},
+    utils::keccak256,
+};
+use log::{debug, warn};
+use serde::Deserialize;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+use crate::alerts::{AlertLevel, Alerts};
+use crate::metrics::ADMIN_EVENTS_TOTAL;
+use crate::operator_controls::OperatorControls;
+
+// Watches the executor and FastLane contracts for admin events: an owner or proxy admin
+// change, a pause, or an implementation upgrade. Each one raises a critical alert and, unless
+// disabled, holds execution until the operator acknowledges it, so no trade sends funds
+// into a contract that changed hands or code under the bot
+#[derive(Debug, Clone, Deserialize)]
+pub struct AdminWatchSettings {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+    // Blocks scanned per check; after a longer outage only the most recent are looked at
+    #[serde(default = "default_max_blocks")]
+    pub max_blocks: u64,
+    #[serde(default = "default_pause_until_acknowledged")]
+    pub pause_until_acknowledged: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_interval_secs() -> u64 {
+    5
+}
+
+fn default_max_blocks() -> u64 {
+    1_000
+}
+
+fn default_pause_until_acknowledged() -> bool {
+    true
+}
+
+impl Default for AdminWatchSettings {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            interval_secs: default_interval_secs(),
+            max_blocks: default_max_blocks(),
+            pause_until_acknowledged: default_pause_until_acknowledged(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AdminEvent {
+    OwnerChanged,
+    // ERC-1967 proxy admin
+    AdminChanged,
+    Paused,
+    // ERC-1967 implementation or beacon
+    Upgraded,
+}
+
+const SIGNATURES: [(&str, AdminEvent); 5] = [
+    ("OwnershipTransferred(address,address)", AdminEvent::OwnerChanged),
+    ("AdminChanged(address,address)", AdminEvent::AdminChanged),
+    ("Paused(address)", AdminEvent::Paused),
+    ("Upgraded(address)", AdminEvent::Upgraded),
+    ("BeaconUpgraded(address)", AdminEvent::Upgraded),
+];
+
+impl AdminEvent {
+    pub fn from_topic(topic: H256) -> Option<Self> {
+        SIGNATURES
+            .iter()
+            .find(|(signature, _)| H256(keccak256(signature)) == topic)
+            .map(|&(_, event)| event)
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AdminEvent::OwnerChanged => "owner_changed",
+            AdminEvent::AdminChanged => "admin_changed",
+            AdminEvent::Paused => "paused",
+            AdminEvent::Upgraded => "upgraded",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdminEventLog {
+    pub contract: &'static str,
+    pub address: Address,
+    pub event: AdminEvent,
+    pub block_number: u64,
+    pub tx_hash: Option<H256>,
+}
+
+pub struct AdminWatcher<M> {
+    provider: Arc<M>,
+    // Watched contracts by name, e.g. ("executor", ..)
+    contracts: Vec<(&'static str, Address)>,
+    settings: AdminWatchSettings,
+    alerts: Alerts,
+    controls: OperatorControls,
+    // Last block scanned; the first check starts at the head
+    scanned: Mutex<Option<u64>>,
+}
+
+impl<M: Middleware + 'static> AdminWatcher<M> {
+    pub fn new(
+        provider: Arc<M>,
+        contracts: Vec<(&'static str, Address)>,
+        settings: AdminWatchSettings,
+        alerts: Alerts,
+        controls: OperatorControls,
+    ) -> Self {
+        Self {
+            provider,
+            contracts,
+            settings,
+            alerts,
+            controls,
+            scanned: Mutex::new(None),
+        }
+    }
+
+    pub async fn run(&self) {
+        let mut interval = tokio::time::interval(Duration::from_secs(self.settings.interval_secs.max(1)));
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.check_once().await {
+                warn!("Admin event check failed: {:?}", e);
+            }
+        }
+    }
+
+    // Returns the admin events emitted since the last check
+    pub async fn check_once(&self) -> Result<Vec<AdminEventLog>> {
+        let head = self
+            .provider
+            .get_block_number()
+            .await
+            .map_err(|e| anyhow!("Failed to fetch block number: {e}"))?
+            .as_u64();
+        let mut scanned = self.scanned.lock().await;
+        let Some(last) = *scanned else {
+            *scanned = Some(head);
+            return Ok(Vec::new());
+        };
+        if head <= last {
+            return Ok(Vec::new());
+        }
+        let from = (last + 1).max(head.saturating_sub(self.settings.max_blocks.max(1)) + 1);
+
+        let filter = Filter::new()
+            .address(self.contracts.iter().map(|&(_, address)| address).collect::<Vec<_>>())
+            .topic0(SIGNATURES.iter().map(|(signature, _)| H256(keccak256(signature))).collect::<Vec<_>>())
+            .from_block(from)
+            .to_block(head);
+        let logs = self
+            .provider
+            .get_logs(&filter)
+            .await
+            .map_err(|e| anyhow!("Admin event query {}..={} failed: {e}", from, head))?;
+        *scanned = Some(head);
+
+        let mut events = Vec::new();
+        for log in logs {
+            let (Some(&(contract, address)), Some(event)) = (
+                self.contracts.iter().find(|(_, address)| *address == log.address),
+                log.topics.first().and_then(|&topic| AdminEvent::from_topic(topic)),
+            ) else {
+                continue;
+            };
+            events.push(AdminEventLog {
+                contract,
+                address,
+                event,
+                block_number: log.block_number.map_or(head, |number| number.as_u64()),
+                tx_hash: log.transaction_hash,
+            });
+        }
+        if events.is_empty() {
+            debug!("No admin events on the watched contracts in blocks {}..={}", from, head);
+        }
+        for event in &events {
+            self.raise(event);
+        }
+        Ok(events)
+    }
+
+    fn raise(&self, event: &AdminEventLog) {
+        ADMIN_EVENTS_TOTAL.with_label_values(&[event.contract, event.event.as_str()]).inc();
+        let description = format!(
+            "{} on the {} contract {:?} in block {} (tx {:?})",
+            event.event.as_str(),
+            event.contract,
+            event.address,
+            event.block_number,
+            event.tx_hash.unwrap_or_default()
+        );
+        let message = if self.settings.pause_until_acknowledged {
+            self.controls.hold(description.clone());
+            format!("{description}; execution paused until `flashwich ctl acknowledge`")
+        } else {
+            description
+        };
+        self.alerts.emit(AlertLevel::Critical, "admin_watch", message);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{address, quantity, MockChain};
+    use ethers::types::Log;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_admin_events_alert_and_hold_execution_until_acknowledged() {
+        let chain = MockChain::new();
+        let controls = OperatorControls::default();
+        let (executor, fastlane) = (address(1), address(2));
+        let watcher = AdminWatcher::new(
+            chain.provider(),
+            vec![("executor", executor), ("fastlane", fastlane)],
+            AdminWatchSettings::default(),
+            Alerts::default(),
+            controls.clone(),
+        );
+        let mut alerts = watcher.alerts.subscribe();
+
+        chain.script(vec![quantity(100u64)]);
+        assert!(watcher.check_once().await.unwrap().is_empty());
+
+        let log = |address, signature: &str, block: u64| Log {
+            address,
+            topics: vec![H256(keccak256(signature))],
+            block_number: Some(block.into()),
+            transaction_hash: Some(H256::repeat_byte(block as u8)),
+            ..Default::default()
+        };
+        let logs = vec![
+            log(executor, "OwnershipTransferred(address,address)", 101),
+            log(fastlane, "Upgraded(address)", 102),
+            // Same signature on a contract we do not watch
+            log(address(3), "Paused(address)", 102),
+        ];
+        chain.script(vec![quantity(102u64), json!(logs)]);
+        let events = watcher.check_once().await.unwrap();
+        assert_eq!(events.len(), 2);
+        assert_eq!((events[0].contract, events[0].event), ("executor", AdminEvent::OwnerChanged));
+        assert_eq!((events[1].contract, events[1].event), ("fastlane", AdminEvent::Upgraded));
+        assert!(alerts.try_recv().unwrap().message.contains("owner_changed on the executor contract"));
+
+        assert!(controls.is_paused());
+        assert_eq!(controls.status().admin_holds.len(), 2);
+        assert_eq!(controls.acknowledge(), 2);
+        assert!(!controls.is_paused());
+    }
+}
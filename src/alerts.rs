@@ -0,0 +1,58 @@
+// src/alerts.rs
+use log::{error, info, warn};
+use tokio::sync::broadcast;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertLevel {
+    Info,
+    Warning,
+    Critical,
+}
+
+#[derive(Debug, Clone)]
+pub struct Alert {
+    pub level: AlertLevel,
+    pub source: &'static str,
+    pub message: String,
+}
+
+// Fan-out point for operator alerts. Every alert is logged under the `alert` target;
+// other sinks (webhooks, the TUI, ...) subscribe to the broadcast channel.
+#[derive(Debug, Clone)]
+pub struct Alerts {
+    sender: broadcast::Sender<Alert>,
+}
+
+impl Default for Alerts {
+    fn default() -> Self {
+        Self::new(256)
+    }
+}
+
+impl Alerts {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        Self { sender }
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Alert> {
+        self.sender.subscribe()
+    }
+
+    pub fn emit(&self, level: AlertLevel, source: &'static str, message: impl Into<String>) {
+        let alert = Alert {
+            level,
+            source,
+            message: message.into(),
+        };
+
+        match alert.level {
+            AlertLevel::Info => info!(target: "alert", "[{}] {}", alert.source, alert.message),
+            AlertLevel::Warning => warn!(target: "alert", "[{}] {}", alert.source, alert.message),
+            AlertLevel::Critical => error!(target: "alert", "[{}] {}", alert.source, alert.message),
+        }
+
+        // No subscribers is fine, the log line above is the baseline sink
+        let _ = self.sender.send(alert);
+    }
+}
@@ -0,0 +1,289 @@
+// src/amm_math.rs
+use ethers::types::{Address, U256};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+// Fees use the same units as the routers' DEFAULT_FEE: hundredths of a bip (3000 = 0.3%)
+pub const FEE_DENOMINATOR: u32 = 1_000_000;
+pub const DEFAULT_V2_FEE: u32 = 3000;
+
+// Swap fee per V2 fork. Resolution order: pool override, factory, default
+#[derive(Debug, Clone, Deserialize)]
+pub struct FeeSettings {
+    #[serde(default = "default_fee")]
+    pub default: u32,
+    #[serde(default = "default_factory_fees")]
+    pub factories: HashMap<Address, u32>,
+    #[serde(default)]
+    pub pools: HashMap<Address, u32>,
+}
+
+fn default_fee() -> u32 {
+    DEFAULT_V2_FEE
+}
+
+fn default_factory_fees() -> HashMap<Address, u32> {
+    [
+        ("0x5757371414417b8C6CAad45bAeF941aBc7d3Ab32", 3000), // QuickSwap
+        ("0xc35DADB65012eC5796536bD9864eD8773aBc74C4", 3000), // SushiSwap
+        ("0xCf083Be4164828f00cAE704EC15a36D711491284", 2000), // ApeSwap
+    ]
+    .iter()
+    .map(|(factory, fee)| (factory.parse().unwrap(), *fee))
+    .collect()
+}
+
+impl Default for FeeSettings {
+    fn default() -> Self {
+        Self {
+            default: default_fee(),
+            factories: default_factory_fees(),
+            pools: HashMap::new(),
+        }
+    }
+}
+
+impl FeeSettings {
+    // `factory` is None for pools whose factory is unknown, e.g. allowlisted ones
+    pub fn fee(&self, pool: &Address, factory: Option<&Address>) -> u32 {
+        let fee = self
+            .pools
+            .get(pool)
+            .or_else(|| factory.and_then(|factory| self.factories.get(factory)))
+            .copied()
+            .unwrap_or(self.default);
+        // A fee of 100% or more would underflow the math below
+        fee.min(FEE_DENOMINATOR - 1)
+    }
+}
+
+// Dual-asset flash loans borrow both tokens of a two-pool cycle from the V3 pool of this
+// fee tier and sell each side into the pool that prices it highest
+#[derive(Debug, Clone, Deserialize)]
+pub struct DualAssetSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_flash_pool_fee")]
+    pub flash_pool_fee: u32,
+}
+
+fn default_flash_pool_fee() -> u32 {
+    500
+}
+
+impl Default for DualAssetSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            flash_pool_fee: default_flash_pool_fee(),
+        }
+    }
+}
+
+// Constant-product output for an exact input, UniswapV2 `getAmountOut` with a configurable fee
+pub fn get_amount_out(amount_in: U256, reserve_in: U256, reserve_out: U256, fee: u32) -> U256 {
+    if amount_in.is_zero() || reserve_in.is_zero() || reserve_out.is_zero() {
+        return U256::zero();
+    }
+
+    let amount_in_with_fee = amount_in * U256::from(FEE_DENOMINATOR - fee);
+    let numerator = amount_in_with_fee * reserve_out;
+    let denominator = reserve_in * U256::from(FEE_DENOMINATOR) + amount_in_with_fee;
+    numerator / denominator
+}
+
+// Input required to receive exactly `amount_out`, UniswapV2 `getAmountIn`
+pub fn get_amount_in(amount_out: U256, reserve_in: U256, reserve_out: U256, fee: u32) -> Option<U256> {
+    if amount_out.is_zero() || reserve_in.is_zero() || amount_out >= reserve_out {
+        return None;
+    }
+
+    let numerator = reserve_in * amount_out * U256::from(FEE_DENOMINATOR);
+    let denominator = (reserve_out - amount_out) * U256::from(FEE_DENOMINATOR - fee);
+    Some(numerator / denominator + 1)
+}
+
+// Chain get_amount_out over (reserve_in, reserve_out, fee) hops
+pub fn get_amounts_out(amount_in: U256, hops: &[(U256, U256, u32)]) -> Vec<U256> {
+    let mut amounts = Vec::with_capacity(hops.len() + 1);
+    amounts.push(amount_in);
+
+    let mut current = amount_in;
+    for &(reserve_in, reserve_out, fee) in hops {
+        current = get_amount_out(current, reserve_in, reserve_out, fee);
+        amounts.push(current);
+    }
+
+    amounts
+}
+
+// Output minus input of a cyclic route, zero when it loses. `adjust` corrects each hop's
+// quoted output (e.g. for observed drift); None abandons the route
+pub fn route_profit<F>(amount_in: U256, hops: &[(U256, U256, u32)], adjust: F) -> U256
+where
+    F: Fn(usize, U256) -> Option<U256>,
+{
+    let mut current = amount_in;
+    for (i, &(reserve_in, reserve_out, fee)) in hops.iter().enumerate() {
+        current = get_amount_out(current, reserve_in, reserve_out, fee);
+        current = match adjust(i, current) {
+            Some(adjusted) => adjusted,
+            None => return U256::zero(),
+        };
+    }
+    current.saturating_sub(amount_in)
+}
+
+// Most profitable of the candidate input sizes as (amount, profit); zero amount when none pays
+pub fn best_amount<F>(candidates: &[U256], profit: F) -> (U256, U256)
+where
+    F: Fn(U256) -> U256,
+{
+    candidates
+        .iter()
+        .map(|&amount| (amount, profit(amount)))
+        .filter(|(_, profit)| !profit.is_zero())
+        .max_by_key(|(_, profit)| *profit)
+        .unwrap_or_default()
+}
+
+// Dual-asset flash loan over one two-pool imbalance: `amount0` of token0 is sold on `sell0`
+// (token0 -> token1) and `amount1` of token1 on `sell1` (token1 -> token0) in the same call.
+// Each borrowed side is repaid from the other leg's output plus the flash fee, so the result
+// is the profit per token, or None when either side falls short
+pub fn dual_leg_profit(
+    amount0: U256,
+    amount1: U256,
+    sell0: (U256, U256, u32),
+    sell1: (U256, U256, u32),
+    flash_fee: u32,
+) -> Option<(U256, U256)> {
+    let owed = |amount: U256| amount + amount * U256::from(flash_fee) / U256::from(FEE_DENOMINATOR);
+    let out1 = get_amount_out(amount0, sell0.0, sell0.1, sell0.2);
+    let out0 = get_amount_out(amount1, sell1.0, sell1.1, sell1.2);
+    let profit0 = out0.checked_sub(owed(amount0))?;
+    let profit1 = out1.checked_sub(owed(amount1))?;
+    Some((profit0, profit1))
+}
+
+// Best (amount0, amount1) of the candidate grid as (amount0, amount1, value), with token1
+// profit valued in token0 at `sell1`'s mid price; zero amounts when no pair pays
+pub fn best_dual_amounts(
+    candidates0: &[U256],
+    candidates1: &[U256],
+    sell0: (U256, U256, u32),
+    sell1: (U256, U256, u32),
+    flash_fee: u32,
+) -> (U256, U256, U256) {
+    if sell1.0.is_zero() {
+        return Default::default();
+    }
+    let mut best = (U256::zero(), U256::zero(), U256::zero());
+    for &amount0 in candidates0 {
+        for &amount1 in candidates1 {
+            let Some((profit0, profit1)) = dual_leg_profit(amount0, amount1, sell0, sell1, flash_fee) else {
+                continue;
+            };
+            let value = profit0 + profit1 * sell1.1 / sell1.0;
+            if value > best.2 {
+                best = (amount0, amount1, value);
+            }
+        }
+    }
+    best
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_amount_out_matches_uniswap_v2() {
+        // 1 token in against 100/200 reserves at 0.3%: 997 * 200 / (100_000 + 997)
+        let out = get_amount_out(
+            U256::from(1_000u64),
+            U256::from(100_000u64),
+            U256::from(200_000u64),
+            DEFAULT_V2_FEE,
+        );
+        assert_eq!(out, U256::from(1_974u64));
+    }
+
+    #[test]
+    fn test_get_amount_in_round_trips() {
+        let reserve_in = U256::from(1_000_000u64);
+        let reserve_out = U256::from(2_000_000u64);
+        let amount_in = get_amount_in(U256::from(10_000u64), reserve_in, reserve_out, DEFAULT_V2_FEE).unwrap();
+        let out = get_amount_out(amount_in, reserve_in, reserve_out, DEFAULT_V2_FEE);
+        assert!(out >= U256::from(10_000u64));
+    }
+
+    #[test]
+    fn test_fee_resolution_order() {
+        let mut fees = FeeSettings::default();
+        let apeswap: Address = "0xCf083Be4164828f00cAE704EC15a36D711491284".parse().unwrap();
+        let (pool, other) = (Address::from_low_u64_be(1), Address::from_low_u64_be(2));
+
+        assert_eq!(fees.fee(&pool, None), DEFAULT_V2_FEE);
+        assert_eq!(fees.fee(&pool, Some(&apeswap)), 2000);
+        assert_eq!(fees.fee(&pool, Some(&other)), DEFAULT_V2_FEE);
+
+        fees.pools.insert(pool, 2500);
+        assert_eq!(fees.fee(&pool, Some(&apeswap)), 2500);
+
+        // Lower fee, more output for the same input
+        let (reserve_in, reserve_out) = (U256::from(1_000_000u64), U256::from(1_000_000u64));
+        let amount_in = U256::from(10_000u64);
+        assert!(
+            get_amount_out(amount_in, reserve_in, reserve_out, 2000)
+                > get_amount_out(amount_in, reserve_in, reserve_out, DEFAULT_V2_FEE)
+        );
+    }
+
+    #[test]
+    fn test_route_profit_and_best_amount_offline() {
+        use crate::fixtures::{address, PoolSet};
+
+        let e18 = U256::exp10(18);
+        // Two pools quoting token 1 -> 2 -> 1 about 4% apart
+        let pools = PoolSet::new()
+            .pool(address(10), address(1), address(2), e18 * 1_000, e18 * 2_000)
+            .pool(address(11), address(2), address(1), e18 * 2_000, e18 * 1_040);
+        let fees = FeeSettings::default();
+        let hops = pools.hops(&fees);
+
+        let small = route_profit(e18, &hops, |_, out| Some(out));
+        assert!(small > U256::zero());
+        // Blacklisted hop kills the route
+        assert!(route_profit(e18, &hops, |i, out| (i == 0).then_some(out)).is_zero());
+
+        let candidates = [e18, e18 * 5, e18 * 10, e18 * 500];
+        let (amount, profit) = best_amount(&candidates, |amount| route_profit(amount, &hops, |_, out| Some(out)));
+        // Price impact makes the largest size lose, the optimum sits in between
+        assert!(route_profit(e18 * 500, &hops, |_, out| Some(out)).is_zero());
+        assert_eq!(amount, e18 * 10);
+        assert!(profit > small);
+    }
+
+    #[test]
+    fn test_dual_asset_trades_both_sides_of_the_imbalance() {
+        let e18 = U256::exp10(18);
+        // token0 is dear on the first pool, token1 on the second
+        let sell0 = (e18 * 1_000, e18 * 2_100, DEFAULT_V2_FEE);
+        let sell1 = (e18 * 2_000, e18 * 1_050, DEFAULT_V2_FEE);
+
+        let (profit0, profit1) = dual_leg_profit(e18, e18 * 2, sell0, sell1, 500).unwrap();
+        assert!(profit0 > U256::zero() && profit1 > U256::zero());
+        // Borrowing more token1 than the first leg returns can't be repaid
+        assert!(dual_leg_profit(e18, e18 * 3, sell0, sell1, 500).is_none());
+
+        let candidates0 = [e18, e18 * 5, e18 * 10];
+        let candidates1 = [e18 * 2, e18 * 10, e18 * 20];
+        let (amount0, amount1, value) = best_dual_amounts(&candidates0, &candidates1, sell0, sell1, 500);
+        assert!(!amount0.is_zero() && !amount1.is_zero());
+        assert!(value > profit0 + profit1 * sell1.1 / sell1.0);
+        // Both legs on the same pool is no imbalance at all
+        let same_pool = (sell0.1, sell0.0, DEFAULT_V2_FEE);
+        assert_eq!(best_dual_amounts(&candidates0, &candidates1, sell0, same_pool, 500).2, U256::zero());
+    }
+}
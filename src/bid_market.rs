@@ -0,0 +1,252 @@
+// src/bid_market.rs
+use anyhow::{anyhow, Result};
+use ethers::{
+    providers::Middleware,
+    types::{Address, Transaction, TransactionReceipt, U256, U64},
+};
+use log::debug;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+
+use crate::gas_oracle::GasSnapshot;
+use crate::metrics::{BID_MARKET_BID, BID_MARKET_PRIORITY_FEE_GWEI, BID_MARKET_WINNERS_TOTAL};
+
+const BPS: u64 = 10_000;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct BidMarketSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    // FastLane auction / Atlas contracts whose successful txs are the auction winners
+    #[serde(default)]
+    pub contracts: Vec<Address>,
+    // Weight of each new winner in the moving estimate
+    #[serde(default = "default_alpha")]
+    pub alpha: f64,
+    // Winners seen before the estimate is trusted
+    #[serde(default = "default_min_samples")]
+    pub min_samples: u64,
+    // How far above the learned clearing bid we bid
+    #[serde(default = "default_outbid_bps")]
+    pub outbid_bps: u64,
+}
+
+fn default_alpha() -> f64 {
+    0.2
+}
+
+fn default_min_samples() -> u64 {
+    10
+}
+
+fn default_outbid_bps() -> u64 {
+    500
+}
+
+impl Default for BidMarketSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            contracts: Vec::new(),
+            alpha: default_alpha(),
+            min_samples: default_min_samples(),
+            outbid_bps: default_outbid_bps(),
+        }
+    }
+}
+
+// Moving estimate of what the auction winners paid: tip per gas and bid (tx value)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ClearingLevel {
+    pub priority_fee: U256,
+    pub bid: U256,
+    pub samples: u64,
+}
+
+impl ClearingLevel {
+    // Exponential average with weight `alpha` (in thousandths, as for the profit margin)
+    pub fn observe(&mut self, priority_fee: U256, bid: U256, alpha: f64) {
+        let weight = U256::from((alpha.clamp(0.0, 1.0) * 1_000.0).round() as u64);
+        let keep = U256::from(1_000u64) - weight;
+        let blend = |average: U256, sample: U256| (average * keep + sample * weight) / U256::from(1_000u64);
+        if self.samples == 0 {
+            self.priority_fee = priority_fee;
+            self.bid = bid;
+        } else {
+            self.priority_fee = blend(self.priority_fee, priority_fee);
+            self.bid = blend(self.bid, bid);
+        }
+        self.samples += 1;
+    }
+}
+
+// Tip per gas the winner actually paid on top of the block's base fee
+pub fn effective_priority_fee(tx: &Transaction, base_fee: U256) -> U256 {
+    match (tx.max_fee_per_gas, tx.max_priority_fee_per_gas) {
+        (Some(max_fee), Some(max_tip)) => max_tip.min(max_fee.saturating_sub(base_fee)),
+        _ => tx.gas_price.unwrap_or_default().saturating_sub(base_fee),
+    }
+}
+
+// Whether a winner's receipt touched any of `pools`; no pools means every winner counts
+pub fn touches_pools(receipt: &TransactionReceipt, pools: &HashSet<Address>) -> bool {
+    pools.is_empty() || receipt.logs.iter().any(|log| pools.contains(&log.address))
+}
+
+// Learns the market-clearing FastLane bid and priority fee from the auction winners in
+// each included block that touched our pools, so our own bids track the market instead of
+// handing the whole expected profit to the validator
+#[derive(Debug, Clone)]
+pub struct BidMarket<M> {
+    provider: Arc<M>,
+    settings: BidMarketSettings,
+    pools: HashSet<Address>,
+    level: Arc<Mutex<ClearingLevel>>,
+}
+
+impl<M: Middleware + 'static> BidMarket<M> {
+    pub fn new(provider: Arc<M>, settings: BidMarketSettings, pools: &[Address]) -> Self {
+        Self {
+            provider,
+            settings,
+            pools: pools.iter().copied().collect(),
+            level: Arc::default(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.settings.enabled && !self.settings.contracts.is_empty()
+    }
+
+    pub fn level(&self) -> ClearingLevel {
+        *self.level.lock().unwrap()
+    }
+
+    // The learned level once enough winners were seen
+    fn trusted(&self) -> Option<ClearingLevel> {
+        Some(self.level()).filter(|level| level.samples >= self.settings.min_samples.max(1))
+    }
+
+    // Scans the winners of one included block; returns how many were learned from
+    pub async fn observe_block(&self, number: U64) -> Result<usize> {
+        if !self.is_enabled() {
+            return Ok(0);
+        }
+        let block = self
+            .provider
+            .get_block_with_txs(number)
+            .await
+            .map_err(|e| anyhow!("Failed to fetch block {number}: {e}"))?
+            .ok_or_else(|| anyhow!("Block {number} not found"))?;
+        let base_fee = block.base_fee_per_gas.unwrap_or_default();
+
+        let mut learned = 0;
+        for tx in &block.transactions {
+            if !tx.to.is_some_and(|to| self.settings.contracts.contains(&to)) {
+                continue;
+            }
+            // Only a mined, successful call through our pools says what clearing cost
+            let receipt = match self.provider.get_transaction_receipt(tx.hash).await {
+                Ok(Some(receipt)) => receipt,
+                Ok(None) => continue,
+                Err(e) => {
+                    debug!("Receipt of auction tx {:?} unavailable: {:?}", tx.hash, e);
+                    continue;
+                }
+            };
+            if receipt.status != Some(1u64.into()) || !touches_pools(&receipt, &self.pools) {
+                continue;
+            }
+
+            let priority_fee = effective_priority_fee(tx, base_fee);
+            let level = {
+                let mut level = self.level.lock().unwrap();
+                level.observe(priority_fee, tx.value, self.settings.alpha);
+                *level
+            };
+            BID_MARKET_WINNERS_TOTAL.inc();
+            BID_MARKET_PRIORITY_FEE_GWEI.set(level.priority_fee.as_u128() as f64 / 1e9);
+            BID_MARKET_BID.set(level.bid.as_u128() as f64 / 1e18);
+            learned += 1;
+        }
+        if learned > 0 {
+            debug!("Block {}: learned from {} auction winners, now {:?}", number, learned, self.level());
+        }
+        Ok(learned)
+    }
+
+    // Our tip is raised to the clearing level, never lowered below the oracle's
+    pub fn apply(&self, gas: GasSnapshot) -> GasSnapshot {
+        match self.trusted() {
+            Some(level) => GasSnapshot {
+                priority_fee: gas.priority_fee.max(level.priority_fee),
+                ..gas
+            },
+            None => gas,
+        }
+    }
+
+    // Clearing bid plus the outbid margin, zero until the estimate is trusted
+    pub fn market_bid(&self) -> U256 {
+        self.trusted()
+            .map(|level| level.bid * U256::from(BPS + self.settings.outbid_bps) / U256::from(BPS))
+            .unwrap_or_default()
+    }
+
+    // What to bid on an opportunity: the market bid, never more than its expected profit.
+    // Without a trusted estimate the whole expected profit is bid, as before
+    pub fn bid(&self, expected_profit: U256) -> U256 {
+        match self.trusted() {
+            Some(_) => self.market_bid().min(expected_profit),
+            None => expected_profit,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{address, MockChain};
+
+    #[test]
+    fn test_bids_track_learned_clearing_level() {
+        let gwei = |n: u64| U256::from(n) * U256::exp10(9);
+        let mut tx = Transaction {
+            max_fee_per_gas: Some(gwei(100)),
+            max_priority_fee_per_gas: Some(gwei(40)),
+            ..Default::default()
+        };
+        assert_eq!(effective_priority_fee(&tx, gwei(80)), gwei(20));
+        tx.max_fee_per_gas = None;
+        tx.gas_price = Some(gwei(110));
+        assert_eq!(effective_priority_fee(&tx, gwei(80)), gwei(30));
+
+        let mut level = ClearingLevel::default();
+        level.observe(gwei(30), U256::from(1_000u64), 0.5);
+        level.observe(gwei(50), U256::from(3_000u64), 0.5);
+        assert_eq!(level.priority_fee, gwei(40));
+        assert_eq!(level.bid, U256::from(2_000u64));
+
+        let settings = BidMarketSettings {
+            enabled: true,
+            contracts: vec![address(9)],
+            min_samples: 2,
+            ..Default::default()
+        };
+        let market = BidMarket::new(MockChain::new().provider(), settings, &[]);
+        let gas = GasSnapshot {
+            block_number: 1,
+            base_fee: gwei(80),
+            priority_fee: gwei(35),
+        };
+        // Untrusted: bid everything, tip from the oracle
+        assert_eq!(market.bid(U256::from(5_000u64)), U256::from(5_000u64));
+        assert_eq!(market.apply(gas), gas);
+
+        *market.level.lock().unwrap() = level;
+        assert_eq!(market.apply(gas).priority_fee, gwei(40));
+        assert_eq!(market.bid(U256::from(5_000u64)), U256::from(2_100u64));
+        assert_eq!(market.bid(U256::from(1_500u64)), U256::from(1_500u64));
+    }
+}
@@ -0,0 +1,328 @@
+// src/bin/flashwich_lab.rs
+//
+// Read-only strategy sandbox: runs every detection path in dry-run against the live
+// mempool and blocks and logs a periodic report of what would have been executed.
+use anyhow::{anyhow, Result};
+use clap::Parser;
+use ethers::{
+    providers::{Middleware, Provider},
+    types::{Address, BlockId, H256, U256},
+};
+use log::{debug, error, info, warn};
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use polygon_mev_bot::{
+    callback_gas::FlashProvider,
+    control_api::{self, ControlState},
+    dedup::{DedupKey, OpportunityDedup},
+    execution_mode::{ExecutionMode, ExecutionModes},
+    exposure::ExposureLimit,
+    self_trade::{route_venues, SelfTradeGuard},
+    gas_oracle::GasOracle,
+    lab::{LabRecorder, Strategy},
+    logging,
+    mempool,
+    operator_controls::OperatorControls,
+    opportunity_id::OpportunityId,
+    pool_kind::PoolClassifier,
+    routers::{quickswap::QuickswapRouter, sushiswap::SushiswapRouter, uniswap_v3::UniswapV3Router},
+    rpc_usage::{self, MeteredWs, RpcCostEstimator},
+    settings::{Settings, DEFAULT_CONFIG_PATH},
+    simulation_engine::AdvancedSimulationEngine,
+    spread_predictor::HeuristicPredictor,
+    store::Store,
+    token_registry::{TokenRegistry, DEFAULT_TOKENS_PATH},
+    top_of_book::TopOfBook,
+    validation,
+    volatility::VolatilityTracker,
+    MevBot,
+};
+
+#[derive(Debug, Parser)]
+#[command(name = "flashwich-lab", version, about = "Dry-run every strategy against live data")]
+struct Args {
+    #[arg(long, default_value = DEFAULT_CONFIG_PATH)]
+    config: String,
+    /// Seconds between reports
+    #[arg(long, default_value_t = 3600)]
+    report_interval: u64,
+    /// Execution profile to start in, overriding `execution_mode.mode`
+    #[arg(long, value_enum)]
+    mode: Option<ExecutionMode>,
+}
+
+fn env_address(name: &str) -> Address {
+    std::env::var(name)
+        .ok()
+        .and_then(|value| Address::from_str(&value).ok())
+        .unwrap_or_default()
+}
+
+// Mempool backrun strategy: simulate every pending tx, count what clears the threshold
+#[allow(clippy::too_many_arguments)]
+async fn run_mempool(
+    provider: Arc<Provider<MeteredWs>>,
+    engine: AdvancedSimulationEngine,
+    settings: &Settings,
+    tokens: Arc<TokenRegistry>,
+    lab: LabRecorder,
+    dedup: OpportunityDedup,
+    modes: ExecutionModes,
+    self_trade: SelfTradeGuard,
+) -> Result<()> {
+    let gas_oracle = Arc::new(GasOracle::new(provider.clone()).with_forecast(settings.gas_forecast.clone()));
+    let headers = gas_oracle.clone();
+    tokio::spawn(async move {
+        if let Err(e) = headers.follow_headers().await {
+            warn!("Gas oracle header feed stopped: {:?}", e);
+        }
+    });
+    let mut feed = mempool::spawn(&settings.mempool, provider.clone());
+
+    while let Some(pending) = feed.recv().await {
+        let started = Instant::now();
+        let tx = pending.tx;
+        let tx_hash = tx.hash;
+        let block_number = provider.get_block_number().await?;
+        if gas_oracle.is_stale(block_number.as_u64()) {
+            if let Err(e) = gas_oracle.refresh().await {
+                warn!("Gas oracle refresh failed: {:?}", e);
+            }
+        }
+
+        lab.record_evaluation(Strategy::MempoolBackrun);
+        let block = BlockId::Number(block_number.into());
+        match engine.simulate_arbitrage_opportunity(&tx, block).await {
+            Ok(Some(opportunity)) => {
+                let hops = opportunity.routers.len();
+                let flash_provider = FlashProvider::for_amounts(opportunity.amount0, opportunity.amount1);
+                let gas = gas_oracle.snapshot();
+                let min_profit = settings.profit_threshold.min_profit(&gas, flash_provider, hops, U256::zero());
+                let min_profit = settings.classes.scale_min_profit(opportunity.class, min_profit);
+                let profile = modes.profile();
+                let min_profit = profile.scale_min_profit(min_profit);
+                if !settings.classes.is_enabled(opportunity.class)
+                    || !profile.allows(opportunity.class, hops)
+                    || opportunity.expected_profit < min_profit
+                {
+                    continue;
+                }
+
+                // The block strategy may already have counted this spread
+                let number = block_number.as_u64();
+                let key = DedupKey::new(OpportunityId::of(&opportunity, number + 1), number);
+                let Some(ticket) = dedup.claim(key, Strategy::MempoolBackrun.as_str(), number) else {
+                    continue;
+                };
+                if !dedup.commit(&ticket) {
+                    continue;
+                }
+                // Would trade a pool the block strategy already takes in the next block
+                let venues = route_venues(&opportunity.routers, &opportunity.path);
+                let class = opportunity.class.as_str();
+                match self_trade.reserve(&venues, number + 1, class, opportunity.expected_profit) {
+                    Some(reservation) if self_trade.commit(&reservation) => {}
+                    _ => continue,
+                }
+
+                let pair = [opportunity.token0, opportunity.token1];
+                if let Err(e) = tokens.resolve(provider.clone(), &pair).await {
+                    debug!("Token metadata lookup failed: {:?}", e);
+                }
+                let gas_cost = settings.profit_threshold.route_cost(&gas, flash_provider, hops, U256::zero());
+                info!(
+                    "[dry-run] backrun of {:?} would earn {}",
+                    tx_hash,
+                    tokens.format_amount(&opportunity.token0, opportunity.expected_profit)
+                );
+                lab.record_opportunity(
+                    Strategy::MempoolBackrun,
+                    opportunity.token0,
+                    opportunity.expected_profit,
+                    gas_cost,
+                    started.elapsed(),
+                );
+            }
+            Ok(None) => {}
+            Err(e) => debug!("Simulation of {:?} failed: {:?}", tx_hash, e),
+        }
+    }
+
+    Err(anyhow!("All mempool sources stopped"))
+}
+
+async fn run_reports(lab: LabRecorder, tokens: Arc<TokenRegistry>, interval: Duration) {
+    let mut ticker = tokio::time::interval(interval);
+    ticker.tick().await; // first tick fires immediately
+    loop {
+        ticker.tick().await;
+        info!("{}", lab.take_report().render(&tokens));
+    }
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    let args = Args::parse();
+    dotenv::dotenv().ok();
+    validation::validate_config(&args.config, DEFAULT_TOKENS_PATH)?;
+    let settings = Settings::load(&args.config)?;
+    let log_handle = Arc::new(logging::init(&settings.logging)?);
+
+    let ws_url = std::env::var("POLYGON_WS_URL").expect("POLYGON_WS_URL must be set in .env");
+    let rpc_url = std::env::var("POLYGON_RPC_URL").expect("POLYGON_RPC_URL must be set in .env");
+    let provider = Arc::new(rpc_usage::connect_ws(&ws_url, "ws").await?);
+
+    let store = Store::open(&settings.store.path)?;
+    let tokens = Arc::new(TokenRegistry::from_json_file(DEFAULT_TOKENS_PATH)?.with_store(store.clone())?);
+    // Callback gas as measured by the live bot's executions
+    let window = settings.profit_threshold.callback_gas.settings().window;
+    settings.profit_threshold.calibrate(&store.recent_callback_gas(window)?);
+    let lab = LabRecorder::new();
+    let dedup = OpportunityDedup::new();
+    let self_trade = SelfTradeGuard::new(settings.self_trade.clone());
+    let modes = ExecutionModes::new(settings.execution_mode.clone());
+    let controls = OperatorControls::default();
+    if let Some(mode) = args.mode {
+        modes.set(mode);
+    }
+
+    let engine = AdvancedSimulationEngine::new(
+        provider.clone(),
+        QuickswapRouter::new(provider.clone()),
+        SushiswapRouter::new(provider.clone()),
+        UniswapV3Router::new(provider.clone()),
+    )
+    .with_profit_tokens(settings.profit_tokens.clone())
+    .with_pool_classifier(Arc::new(PoolClassifier::new(
+        provider.clone(),
+        settings.pool_detection.clone(),
+        settings.fees.clone(),
+        settings.v3.clone(),
+    )));
+
+    // Dry-run never signs; a throwaway key keeps any real key out of the lab entirely
+    let throwaway_key = format!("{:x}", H256::random());
+    let mut block_bot = MevBot::new(
+        &rpc_url,
+        &throwaway_key,
+        env_address("FLASH_LOAN_CONTRACT"),
+        env_address("FASTLANE_SENDER_CONTRACT"),
+    )
+    .await
+    .map_err(|e| anyhow!("Failed to start block strategy: {e}"))?
+    .with_discovery(settings.discovery.clone())
+    .with_profit_threshold(settings.profit_threshold.clone())
+    .with_gas_ceiling(settings.gas_ceiling.clone())
+    .with_route_search(settings.route_search.clone())
+    .with_fees(settings.fees.clone())
+    .with_fee_watch(settings.fee_watch.clone())
+    .with_deadline(settings.executor.deadline.clone())
+    .with_two_phase(settings.two_phase.clone())
+    .with_sim_cache(settings.sim_cache.clone())
+    .with_pair_focus(settings.pair_focus.clone())
+    .with_slippage_curves(settings.slippage.clone())
+    .with_shared_reserves(settings.shared_reserves.clone())
+    .map_err(|e| anyhow!("Shared reserve cache: {e}"))?
+    .with_dual_asset(settings.dual_asset.clone())
+    .with_round_trip(settings.round_trip.clone())
+    .with_optimizer(settings.optimizer.clone())
+    .with_shadow(settings.shadow.clone())
+    .with_token_taxes(settings.token_tax.clone(), tokens.clone())
+    .with_spread_filter(settings.spread_filter.clone(), tokens.clone())
+    .with_toxic_flow(settings.toxic_flow.clone())
+    .with_gas_forecast(settings.gas_forecast.clone())
+    .with_block_pipeline(settings.block_pipeline.clone())
+    .with_yield_wraps(settings.yield_wrap.clone())
+    .with_profit_tokens(settings.profit_tokens.clone())
+    .with_classes(settings.classes.clone())
+    .with_cooldown(settings.cooldown.clone())
+    .with_reserve_watchdog(settings.reserve_watchdog.clone())
+    .with_v3_discovery(settings.v3.clone(), settings.v3_discovery.clone())
+    .with_dedup(dedup.clone())
+    .with_execution_modes(modes.clone())
+    .with_controls(controls.clone())
+    .with_self_trade_guard(self_trade.clone())
+    .with_exposure(ExposureLimit::new(settings.exposure.clone(), tokens.clone()))
+    .with_spread_predictor(Arc::new(HeuristicPredictor::new(settings.spread_predictor.clone())))
+    .with_executor_events(settings.executor_events.clone(), store.clone())
+    .with_dry_run(lab.clone());
+    block_bot
+        .prefetch_pairs(&settings.prefetch, &tokens)
+        .await
+        .map_err(|e| anyhow!("Pair prefetch failed: {e}"))?;
+    block_bot
+        .load_executor_whitelist(&settings.executor_whitelist, settings.executor.load_abi()?, &tokens)
+        .await
+        .map_err(|e| anyhow!("Executor whitelist lookup failed: {e}"))?;
+    if settings.volatility.enabled {
+        let tracker = VolatilityTracker::new(store.clone(), settings.volatility.clone());
+        block_bot = block_bot.with_volatility(Arc::new(tracker));
+    }
+
+    // Best bid/ask of the configured pairs, served on the control API at /book and /book/ws
+    let book = settings.top_of_book.enabled.then(|| TopOfBook::new(&settings.top_of_book));
+    if let Some(book) = &book {
+        let tokens_in_book: Vec<Address> = book.pairs().iter().flat_map(|pair| [pair.base, pair.quote]).collect();
+        if let Err(e) = tokens.resolve(provider.clone(), &tokens_in_book).await {
+            warn!("Token metadata lookup for the book failed: {:?}", e);
+        }
+        for token in tokens_in_book {
+            if let Some(info) = tokens.get(&token) {
+                book.set_decimals(token, info.decimals);
+            }
+        }
+        block_bot = block_bot.with_top_of_book(book.clone());
+    }
+    if settings.rpc_usage.enabled {
+        tokio::spawn(RpcCostEstimator::new(settings.rpc_usage.clone()).run());
+    }
+    if settings.control_api.enabled {
+        let state = ControlState {
+            log_handle,
+            book,
+            modes: modes.clone(),
+            controls,
+        };
+        let listen = settings.control_api.listen;
+        tokio::spawn(async move {
+            if let Err(e) = control_api::serve(listen, state).await {
+                error!("Control API failed: {:?}", e);
+            }
+        });
+    }
+
+    info!(
+        "flashwich-lab running read-only, reporting every {} s. Press CTRL+C to exit.",
+        args.report_interval
+    );
+
+    tokio::select! {
+        result = run_mempool(
+            provider.clone(),
+            engine,
+            &settings,
+            tokens.clone(),
+            lab.clone(),
+            dedup,
+            modes,
+            self_trade,
+        ) => {
+            if let Err(e) = result {
+                error!("Mempool strategy stopped: {:?}", e);
+            }
+        }
+        result = block_bot.monitor_blocks() => {
+            if let Err(e) = result {
+                error!("Block strategy stopped: {}", e);
+            }
+        }
+        _ = run_reports(lab.clone(), tokens.clone(), Duration::from_secs(args.report_interval)) => {}
+        _ = tokio::signal::ctrl_c() => {}
+    }
+
+    // Whatever was collected since the last report is not lost on exit
+    info!("{}", lab.take_report().render(&tokens));
+    Ok(())
+}
@@ -0,0 +1,21 @@
+// src/bindings.rs
+use ethers::contract::abigen;
+
+// Abigen! generated structs of the contracts the bot and its modules call
+abigen!(
+    FlashLoanArbitrage,
+    "./abis/FlashLoanArbitrage.json",
+    event_derives(serde::Serialize, serde::Deserialize)
+);
+
+abigen!(
+    FastLaneSender,
+    "./abis/FastLaneSender.json",
+    event_derives(serde::Serialize, serde::Deserialize)
+);
+
+abigen!(
+    IUniswapV2Pair,
+    "./abis/IUniswapV2Pair.json",
+    event_derives(serde::Serialize, serde::Deserialize)
+);
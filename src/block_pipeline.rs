@@ -0,0 +1,174 @@
+// src/block_pipeline.rs
+use ethers::types::{Address, U256};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::amm_math::get_amounts_out;
+use crate::metrics::BLOCK_PIPELINE_TOTAL;
+
+// Executions of block N run as tasks while block N+1 is scanned. Until a trade lands, the
+// pools it swaps through are priced at the reserves it will leave behind, so the next scan
+// neither re-detects the spread being taken nor sizes against a price about to move
+#[derive(Debug, Clone, Deserialize)]
+pub struct BlockPipelineSettings {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    // Executions allowed in flight at once; past this one is awaited inline, as before
+    #[serde(default = "default_max_in_flight")]
+    pub max_in_flight: usize,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_max_in_flight() -> usize {
+    4
+}
+
+impl Default for BlockPipelineSettings {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            max_in_flight: default_max_in_flight(),
+        }
+    }
+}
+
+// Reserves a pool has before a trade swaps through it, and the ones the trade leaves behind
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolMove {
+    pub pool: Address,
+    pub before: (U256, U256),
+    pub after: (U256, U256),
+}
+
+// `hops` are (pool, reserve_in, reserve_out, fee), swapped through in order
+pub fn swap_through(hops: &[(Address, U256, U256, u32)], amount_in: U256) -> Vec<PoolMove> {
+    let quotes: Vec<_> = hops
+        .iter()
+        .map(|&(_, reserve_in, reserve_out, fee)| (reserve_in, reserve_out, fee))
+        .collect();
+    let amounts = get_amounts_out(amount_in, &quotes);
+    hops.iter()
+        .enumerate()
+        .map(|(i, &(pool, reserve_in, reserve_out, _))| PoolMove {
+            pool,
+            before: (reserve_in, reserve_out),
+            after: (reserve_in + amounts[i], reserve_out.saturating_sub(amounts[i + 1])),
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Prediction {
+    execution: u64,
+    base: (U256, U256),
+    after: (U256, U256),
+}
+
+#[derive(Debug, Clone)]
+pub struct BlockPipeline {
+    settings: BlockPipelineSettings,
+    predictions: Arc<Mutex<HashMap<Address, Prediction>>>,
+    in_flight: Arc<AtomicUsize>,
+    next_execution: Arc<AtomicU64>,
+}
+
+impl BlockPipeline {
+    pub fn new(settings: BlockPipelineSettings) -> Self {
+        Self {
+            settings,
+            predictions: Arc::new(Mutex::new(HashMap::new())),
+            in_flight: Arc::new(AtomicUsize::new(0)),
+            next_execution: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    // Whether the next execution may run off the block loop
+    pub fn admits(&self) -> bool {
+        let admitted = self.settings.enabled && self.in_flight.load(Ordering::SeqCst) < self.settings.max_in_flight;
+        if self.settings.enabled && !admitted {
+            BLOCK_PIPELINE_TOTAL.with_label_values(&["inline"]).inc();
+        }
+        admitted
+    }
+
+    pub fn in_flight(&self) -> usize {
+        self.in_flight.load(Ordering::SeqCst)
+    }
+
+    // Runs `execution` as a task; the pools it `moves` read as predicted until it is done
+    pub fn launch<F>(&self, moves: Vec<PoolMove>, execution: F)
+    where
+        F: Future<Output = ()> + Send + 'static,
+    {
+        let id = self.next_execution.fetch_add(1, Ordering::SeqCst);
+        {
+            let mut predictions = self.predictions.lock().unwrap();
+            for pool_move in moves {
+                let prediction = Prediction { execution: id, base: pool_move.before, after: pool_move.after };
+                predictions.insert(pool_move.pool, prediction);
+            }
+        }
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        BLOCK_PIPELINE_TOTAL.with_label_values(&["launched"]).inc();
+
+        let pipeline = self.clone();
+        tokio::spawn(async move {
+            execution.await;
+            pipeline.predictions.lock().unwrap().retain(|_, prediction| prediction.execution != id);
+            pipeline.in_flight.fetch_sub(1, Ordering::SeqCst);
+        });
+    }
+
+    // The reserves to price `pool` at, given what the chain reads now. Once the chain moved
+    // off the launch-time reserves, by our trade or anyone's, the chain is right again
+    pub fn overlay(&self, pool: Address, reserves: (U256, U256)) -> (U256, U256) {
+        let predictions = self.predictions.lock().unwrap();
+        match predictions.get(&pool) {
+            Some(prediction) if prediction.base == reserves => {
+                BLOCK_PIPELINE_TOTAL.with_label_values(&["predicted"]).inc();
+                prediction.after
+            }
+            _ => reserves,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::address;
+    use tokio::sync::oneshot;
+
+    #[tokio::test]
+    async fn test_in_flight_trade_prices_its_pools_until_done() {
+        let pipeline = BlockPipeline::new(BlockPipelineSettings { enabled: true, max_in_flight: 1 });
+        let (pool_a, pool_b) = (address(1), address(2));
+        let reserves = (U256::exp10(21), U256::exp10(21));
+        let (done, finished) = oneshot::channel::<()>();
+        let hops = [(pool_a, reserves.0, reserves.1, 30), (pool_b, reserves.0, reserves.1, 30)];
+        pipeline.launch(swap_through(&hops, U256::exp10(18)), async move {
+            let _ = finished.await;
+        });
+
+        assert!(!pipeline.admits());
+        let (reserve_in, reserve_out) = pipeline.overlay(pool_a, reserves);
+        assert_eq!(reserve_in, reserves.0 + U256::exp10(18));
+        assert!(reserve_out < reserves.1);
+        // Another trade moved the pool first: the chain's reserves win
+        let moved = (reserves.0 + 1, reserves.1 - 1);
+        assert_eq!(pipeline.overlay(pool_b, moved), moved);
+
+        done.send(()).unwrap();
+        while pipeline.in_flight() > 0 {
+            tokio::task::yield_now().await;
+        }
+        assert_eq!(pipeline.overlay(pool_a, reserves), reserves);
+        assert!(pipeline.admits());
+    }
+}
@@ -0,0 +1,124 @@
+// src/bundle_fanout.rs
+use ethers::types::{U256, U64};
+use serde::Deserialize;
+
+use crate::simulation_engine::ArbitrageOpportunity;
+
+const BPS: u64 = 10_000;
+
+// The same trade offered for several consecutive target blocks, so a short-lived spread
+// still lands when the first block is missed. The variants share a nonce, so at most one
+// is ever mined, and the executor's on-chain minProfit reverts a variant whose spread is
+// gone; without that guarantee the fan-out is not used
+#[derive(Debug, Clone, Deserialize)]
+pub struct FanoutSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    // Target blocks per trade, the first one included
+    #[serde(default = "default_blocks")]
+    pub blocks: u64,
+    // Relay method taking {txs, blockNumber}; relays without it get a single submission
+    #[serde(default = "default_bundle_method")]
+    pub bundle_method: String,
+    // Each later block's minimum outputs are lowered by this much, spreads tend to narrow
+    #[serde(default = "default_decay_bps_per_block")]
+    pub decay_bps_per_block: u64,
+}
+
+fn default_blocks() -> u64 {
+    3
+}
+
+fn default_bundle_method() -> String {
+    "eth_sendBundle".to_string()
+}
+
+fn default_decay_bps_per_block() -> u64 {
+    1_000
+}
+
+impl Default for FanoutSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            blocks: default_blocks(),
+            bundle_method: default_bundle_method(),
+            decay_bps_per_block: default_decay_bps_per_block(),
+        }
+    }
+}
+
+impl FanoutSettings {
+    // `first` and the blocks after it; only `first` when the fan-out is off
+    pub fn targets(&self, first: U64) -> Vec<U64> {
+        let blocks = if self.enabled { self.blocks.max(1) } else { 1 };
+        (0..blocks).map(|offset| first + offset).collect()
+    }
+
+    // As `targets`, but no more blocks than the spread is expected to stay open
+    pub fn targets_within(&self, first: U64, persistence_blocks: f64) -> Vec<U64> {
+        let mut targets = self.targets(first);
+        targets.truncate((persistence_blocks.ceil() as usize).max(1));
+        targets
+    }
+
+    // The trade as submitted for `offset` blocks after the first target: every hop's
+    // minimum output and the expected profit scaled down, the input amounts untouched
+    pub fn variant(&self, opportunity: &ArbitrageOpportunity, offset: u64) -> ArbitrageOpportunity {
+        let kept = BPS.saturating_sub(self.decay_bps_per_block.min(BPS));
+        let scale = |mut amount: U256| {
+            for _ in 0..offset {
+                amount = amount * U256::from(kept) / U256::from(BPS);
+            }
+            amount
+        };
+        let mut variant = opportunity.clone();
+        for amount in variant.amounts.iter_mut().skip(1) {
+            *amount = scale(*amount);
+        }
+        variant.expected_profit = scale(variant.expected_profit);
+        variant
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::address;
+    use crate::opportunity_class::OpportunityClass;
+
+    #[test]
+    fn test_fanout_targets_consecutive_blocks_with_decaying_minimums() {
+        let opportunity = ArbitrageOpportunity {
+            token0: address(1),
+            token1: address(2),
+            amount0: U256::from(1_000u64),
+            amount1: U256::zero(),
+            fee: 3000,
+            path: vec![address(1), address(2), address(1)],
+            amounts: vec![U256::from(1_000u64), U256::from(2_000u64), U256::from(1_100u64)],
+            routers: vec![address(10), address(11)],
+            expected_profit: U256::from(100u64),
+            optimal_path: Vec::new(),
+            class: OpportunityClass::CrossDex,
+        };
+        let off = FanoutSettings::default();
+        assert_eq!(off.targets(U64::from(50u64)), vec![U64::from(50u64)]);
+
+        let fanout = FanoutSettings {
+            enabled: true,
+            ..Default::default()
+        };
+        assert_eq!(
+            fanout.targets(U64::from(50u64)),
+            vec![U64::from(50u64), U64::from(51u64), U64::from(52u64)]
+        );
+        assert_eq!(fanout.targets_within(U64::from(50u64), 1.4), vec![U64::from(50u64), U64::from(51u64)]);
+        assert_eq!(fanout.targets_within(U64::from(50u64), 8.0).len(), 3);
+        assert_eq!(fanout.variant(&opportunity, 0).amounts, opportunity.amounts);
+        let later = fanout.variant(&opportunity, 2);
+        assert_eq!(later.amounts[0], opportunity.amounts[0]);
+        assert_eq!(later.amounts[2], U256::from(891u64));
+        assert_eq!(later.expected_profit, U256::from(81u64));
+    }
+}
@@ -0,0 +1,339 @@
+// src/bundle_simulation.rs
+use anyhow::{anyhow, Result};
+use ethers::{
+    providers::Provider,
+    types::{transaction::eip2718::TypedTransaction, Address, Bytes, Transaction, U256, U64},
+};
+use log::{debug, warn};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+use crate::metrics::BUNDLE_SIMULATIONS_TOTAL;
+use crate::rpc_usage::{self, MeteredHttp};
+
+const BPS: u64 = 10_000;
+
+// Second validation layer for high-value trades: before bidding, the complete bundle (the
+// victim, then our execution) is simulated by a hosted simulate API, Tenderly's
+// `tenderly_simulateBundle` by default, whose decoded traces and asset changes show what
+// the bundle really does. Cheaper trades rely on the local preflight alone
+#[derive(Debug, Clone, Deserialize)]
+pub struct BundleSimulationSettings {
+    // JSON-RPC endpoint serving `method`, e.g. https://polygon.gateway.tenderly.co/<key>;
+    // unset disables the check
+    #[serde(default)]
+    pub url: Option<String>,
+    #[serde(default = "default_method")]
+    pub method: String,
+    // Expected profit, in gwei of the borrowed token, from which trades are simulated
+    #[serde(default = "default_min_profit_gwei")]
+    pub min_profit_gwei: u64,
+    // Share of the expected profit the simulated balance changes have to show
+    #[serde(default = "default_min_profit_share_bps")]
+    pub min_profit_share_bps: u64,
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+    // Hold the trade when the API fails instead of sending it on the preflight alone
+    #[serde(default)]
+    pub fail_closed: bool,
+}
+
+fn default_method() -> String {
+    "tenderly_simulateBundle".to_string()
+}
+
+fn default_min_profit_gwei() -> u64 {
+    5_000_000_000
+}
+
+fn default_min_profit_share_bps() -> u64 {
+    9_000
+}
+
+fn default_timeout_ms() -> u64 {
+    1_500
+}
+
+impl Default for BundleSimulationSettings {
+    fn default() -> Self {
+        Self {
+            url: None,
+            method: default_method(),
+            min_profit_gwei: default_min_profit_gwei(),
+            min_profit_share_bps: default_min_profit_share_bps(),
+            timeout_ms: default_timeout_ms(),
+            fail_closed: false,
+        }
+    }
+}
+
+// One transaction of the bundle as the simulate API takes it
+#[derive(Debug, Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulationCall {
+    pub from: Address,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub to: Option<Address>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gas: Option<U256>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gas_price: Option<U256>,
+    pub value: U256,
+    pub data: Bytes,
+}
+
+impl From<&TypedTransaction> for SimulationCall {
+    fn from(tx: &TypedTransaction) -> Self {
+        Self {
+            from: tx.from().copied().unwrap_or_default(),
+            to: tx.to_addr().copied(),
+            gas: tx.gas().copied(),
+            gas_price: tx.gas_price(),
+            value: tx.value().copied().unwrap_or_default(),
+            data: tx.data().cloned().unwrap_or_default(),
+        }
+    }
+}
+
+impl From<&Transaction> for SimulationCall {
+    fn from(tx: &Transaction) -> Self {
+        Self {
+            from: tx.from,
+            to: tx.to,
+            gas: Some(tx.gas),
+            gas_price: tx.max_fee_per_gas.or(tx.gas_price),
+            value: tx.value,
+            data: tx.input.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TraceCall {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub from: Address,
+    #[serde(default)]
+    pub to: Option<Address>,
+    // Decoded function name, when the contract is verified
+    #[serde(default)]
+    pub method: Option<String>,
+    #[serde(default)]
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AssetInfo {
+    #[serde(default)]
+    pub contract_address: Option<Address>,
+    #[serde(default)]
+    pub symbol: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AssetChange {
+    #[serde(default)]
+    pub asset_info: AssetInfo,
+    #[serde(default)]
+    pub from: Option<Address>,
+    #[serde(default)]
+    pub to: Option<Address>,
+    pub raw_amount: U256,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SimulatedTx {
+    pub status: bool,
+    #[serde(default)]
+    pub gas_used: U256,
+    #[serde(default)]
+    pub trace: Vec<TraceCall>,
+    #[serde(default)]
+    pub asset_changes: Vec<AssetChange>,
+}
+
+impl SimulatedTx {
+    // Innermost failing call of the trace, the revert reason when it was decoded
+    pub fn failure(&self) -> Option<String> {
+        self.trace.iter().rev().find_map(|call| {
+            let error = call.error.as_ref()?;
+            Some(format!("{} in {}", error, call.method.as_deref().unwrap_or(&call.kind)))
+        })
+    }
+
+    // Net `token` moved to `holders`, saturating at zero
+    pub fn net_received(&self, token: Address, holders: &[Address]) -> U256 {
+        let (mut received, mut sent) = (U256::zero(), U256::zero());
+        for change in self.asset_changes.iter().filter(|change| change.asset_info.contract_address == Some(token)) {
+            let held = |address: Option<Address>| address.is_some_and(|address| holders.contains(&address));
+            match (held(change.from), held(change.to)) {
+                (false, true) => received += change.raw_amount,
+                (true, false) => sent += change.raw_amount,
+                _ => {}
+            }
+        }
+        received.saturating_sub(sent)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum BundleVerdict {
+    Confirmed { profit: U256 },
+    // Transaction `index` of the bundle failed
+    Reverted { index: usize, reason: String },
+    ShortProfit { simulated: U256 },
+}
+
+impl BundleVerdict {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BundleVerdict::Confirmed { .. } => "confirmed",
+            BundleVerdict::Reverted { .. } => "reverted",
+            BundleVerdict::ShortProfit { .. } => "short_profit",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BundleSimulator {
+    provider: Provider<MeteredHttp>,
+    settings: BundleSimulationSettings,
+}
+
+impl BundleSimulator {
+    // None without a url
+    pub fn new(settings: BundleSimulationSettings) -> Result<Option<Self>> {
+        let Some(url) = &settings.url else {
+            return Ok(None);
+        };
+        let provider = rpc_usage::http(url, "bundle_simulation")?;
+        Ok(Some(Self { provider, settings }))
+    }
+
+    // Whether a trade expected to pay `expected_profit` goes through the simulate API
+    pub fn covers(&self, expected_profit: U256) -> bool {
+        expected_profit >= U256::from(self.settings.min_profit_gwei) * U256::exp10(9)
+    }
+
+    pub async fn simulate(&self, calls: &[SimulationCall], block: U64) -> Result<Vec<SimulatedTx>> {
+        let request = self.provider.request::<_, Vec<SimulatedTx>>(&self.settings.method, (calls, block));
+        let simulated = tokio::time::timeout(Duration::from_millis(self.settings.timeout_ms), request)
+            .await
+            .map_err(|_| anyhow!("{} timed out", self.settings.method))??;
+        if simulated.len() != calls.len() {
+            let method = &self.settings.method;
+            return Err(anyhow!("{method} simulated {} of {} transactions", simulated.len(), calls.len()));
+        }
+        Ok(simulated)
+    }
+
+    // Every transaction has to succeed and the last one, ours, has to bring `holders` at
+    // least the configured share of `expected_profit` in `token`
+    pub fn verdict(
+        &self,
+        simulated: &[SimulatedTx],
+        token: Address,
+        holders: &[Address],
+        expected_profit: U256,
+    ) -> BundleVerdict {
+        if let Some((index, tx)) = simulated.iter().enumerate().find(|(_, tx)| !tx.status) {
+            let reason = tx.failure().unwrap_or_else(|| "reverted".to_string());
+            return BundleVerdict::Reverted { index, reason };
+        }
+        let profit = simulated.last().map_or(U256::zero(), |tx| tx.net_received(token, holders));
+        let required = expected_profit * U256::from(self.settings.min_profit_share_bps) / U256::from(BPS);
+        if profit < required {
+            return BundleVerdict::ShortProfit { simulated: profit };
+        }
+        BundleVerdict::Confirmed { profit }
+    }
+
+    // Simulates `calls` at `block` and judges them; Err only when the API could not, and
+    // `fail_closed` is not set, so the trade goes on with the preflight's word
+    pub async fn validate(
+        &self,
+        calls: &[SimulationCall],
+        block: U64,
+        token: Address,
+        holders: &[Address],
+        expected_profit: U256,
+    ) -> Result<Option<BundleVerdict>> {
+        match self.simulate(calls, block).await {
+            Ok(simulated) => {
+                let verdict = self.verdict(&simulated, token, holders, expected_profit);
+                BUNDLE_SIMULATIONS_TOTAL.with_label_values(&[verdict.as_str()]).inc();
+                debug!("Bundle simulation at block {}: {:?}", block, verdict);
+                Ok(Some(verdict))
+            }
+            Err(e) => {
+                BUNDLE_SIMULATIONS_TOTAL.with_label_values(&["unavailable"]).inc();
+                if self.settings.fail_closed {
+                    return Err(e);
+                }
+                warn!("Bundle simulation unavailable, relying on the preflight: {:?}", e);
+                Ok(None)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::address;
+
+    #[test]
+    fn test_verdict_reads_reverts_and_profit_from_simulated_asset_changes() {
+        let simulator = BundleSimulator::new(BundleSimulationSettings {
+            url: Some("http://127.0.0.1:1".into()),
+            ..Default::default()
+        })
+        .unwrap()
+        .unwrap();
+        assert!(simulator.covers(U256::from(5u64) * U256::exp10(18)));
+        assert!(!simulator.covers(U256::exp10(18)));
+
+        let (token, executor, owner, pool) = (address(1), address(2), address(3), address(4));
+        let response = serde_json::json!([
+            { "status": true, "gasUsed": "0x5208", "trace": [], "assetChanges": [] },
+            {
+                "status": true,
+                "gasUsed": "0x3d090",
+                "trace": [{ "type": "CALL", "from": owner, "to": executor, "method": "executeFlashLoanArbitrage" }],
+                "assetChanges": [
+                    { "assetInfo": { "contractAddress": token, "symbol": "WMATIC" }, "type": "Transfer",
+                      "from": pool, "to": executor, "rawAmount": "0x64" },
+                    { "assetInfo": { "contractAddress": token }, "from": executor, "to": pool, "rawAmount": "0x5a" },
+                    { "assetInfo": { "contractAddress": token }, "from": executor, "to": owner, "rawAmount": "0xa" },
+                    { "assetInfo": { "contractAddress": address(9) }, "from": pool, "to": executor, "rawAmount": "0x1" }
+                ]
+            }
+        ]);
+        let simulated: Vec<SimulatedTx> = serde_json::from_value(response).unwrap();
+        let holders = [executor, owner];
+        assert_eq!(simulated[1].net_received(token, &holders), U256::from(10u64));
+        assert_eq!(
+            simulator.verdict(&simulated, token, &holders, U256::from(11u64)),
+            BundleVerdict::Confirmed { profit: U256::from(10u64) }
+        );
+        assert_eq!(
+            simulator.verdict(&simulated, token, &holders, U256::from(13u64)),
+            BundleVerdict::ShortProfit { simulated: U256::from(10u64) }
+        );
+
+        // A victim that already moved the price makes our execution revert
+        let mut reverted = simulated.clone();
+        reverted[1].status = false;
+        reverted[1].trace.push(serde_json::from_value(serde_json::json!({
+            "type": "CALL", "from": executor, "to": pool, "method": "swap", "error": "execution reverted: K"
+        })).unwrap());
+        assert_eq!(
+            simulator.verdict(&reverted, token, &holders, U256::from(10u64)),
+            BundleVerdict::Reverted { index: 1, reason: "execution reverted: K in swap".into() }
+        );
+    }
+}
@@ -0,0 +1,210 @@
+// src/callback_gas.rs
+use ethers::types::U256;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+// Where the executor borrows from; each has its own callback (approvals, transfers, repayment)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FlashProvider {
+    // Uniswap V3 `flash` of a single token
+    UniswapV3,
+    // Uniswap V3 `flash` of both tokens, repaid on both sides
+    UniswapV3Dual,
+}
+
+impl FlashProvider {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FlashProvider::UniswapV3 => "uniswap_v3",
+            FlashProvider::UniswapV3Dual => "uniswap_v3_dual",
+        }
+    }
+
+    // Loans with both amounts set borrow both sides of the pool
+    pub fn for_amounts(amount0: U256, amount1: U256) -> Self {
+        if amount0.is_zero() || amount1.is_zero() {
+            FlashProvider::UniswapV3
+        } else {
+            FlashProvider::UniswapV3Dual
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        [FlashProvider::UniswapV3, FlashProvider::UniswapV3Dual]
+            .into_iter()
+            .find(|provider| provider.as_str() == value)
+    }
+}
+
+// Configured callback gas before receipts have calibrated it: `base + per_hop * hops`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct CallbackOverhead {
+    pub base: u64,
+    // Router approvals and transfers per swap inside the callback
+    pub per_hop: u64,
+}
+
+impl CallbackOverhead {
+    pub fn gas(&self, hops: usize) -> u64 {
+        self.base + self.per_hop * hops as u64
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CallbackGasSettings {
+    #[serde(default = "default_providers")]
+    pub providers: HashMap<FlashProvider, CallbackOverhead>,
+    // Receipts of a provider and route length needed before they replace the configured value
+    #[serde(default = "default_min_samples")]
+    pub min_samples: u64,
+    // Weight of each new receipt in the calibrated average
+    #[serde(default = "default_smoothing")]
+    pub smoothing: f64,
+    // Journaled executions replayed into the model at startup
+    #[serde(default = "default_window")]
+    pub window: usize,
+}
+
+fn default_providers() -> HashMap<FlashProvider, CallbackOverhead> {
+    HashMap::from([
+        (
+            FlashProvider::UniswapV3,
+            CallbackOverhead {
+                base: 90_000,
+                per_hop: 25_000,
+            },
+        ),
+        (
+            FlashProvider::UniswapV3Dual,
+            CallbackOverhead {
+                base: 120_000,
+                per_hop: 25_000,
+            },
+        ),
+    ])
+}
+
+fn default_min_samples() -> u64 {
+    3
+}
+
+fn default_smoothing() -> f64 {
+    0.2
+}
+
+fn default_window() -> usize {
+    200
+}
+
+impl Default for CallbackGasSettings {
+    fn default() -> Self {
+        Self {
+            providers: default_providers(),
+            min_samples: default_min_samples(),
+            smoothing: default_smoothing(),
+            window: default_window(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+struct Calibration {
+    samples: u64,
+    gas: f64,
+}
+
+// Flash-loan callback gas per provider and route length. Starts from the configured
+// overheads and follows what receipts show once enough of them came in. Clones share the
+// calibration, so every copy of the profit threshold prices with the latest receipts
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(from = "CallbackGasSettings")]
+pub struct CallbackGasModel {
+    settings: CallbackGasSettings,
+    calibrated: Arc<RwLock<HashMap<(FlashProvider, usize), Calibration>>>,
+}
+
+impl From<CallbackGasSettings> for CallbackGasModel {
+    fn from(settings: CallbackGasSettings) -> Self {
+        Self {
+            settings,
+            calibrated: Arc::default(),
+        }
+    }
+}
+
+impl CallbackGasModel {
+    pub fn settings(&self) -> &CallbackGasSettings {
+        &self.settings
+    }
+
+    pub fn configured(&self, provider: FlashProvider, hops: usize) -> u64 {
+        self.settings
+            .providers
+            .get(&provider)
+            .or_else(|| self.settings.providers.get(&FlashProvider::UniswapV3))
+            .map_or(0, |overhead| overhead.gas(hops))
+    }
+
+    pub fn overhead(&self, provider: FlashProvider, hops: usize) -> u64 {
+        match self.calibrated.read().unwrap().get(&(provider, hops)) {
+            Some(calibration) if calibration.samples >= self.settings.min_samples => calibration.gas.round() as u64,
+            _ => self.configured(provider, hops),
+        }
+    }
+
+    // One receipt's callback gas. The average starts at the configured value so the first
+    // few receipts nudge it rather than replace it
+    pub fn observe(&self, provider: FlashProvider, hops: usize, gas: u64) {
+        let configured = self.configured(provider, hops) as f64;
+        let mut calibrated = self.calibrated.write().unwrap();
+        let calibration = calibrated.entry((provider, hops)).or_insert(Calibration {
+            samples: 0,
+            gas: configured,
+        });
+        let weight = self.settings.smoothing.clamp(0.0, 1.0);
+        calibration.gas += weight * (gas as f64 - calibration.gas);
+        calibration.samples += 1;
+    }
+
+    pub fn samples(&self, provider: FlashProvider, hops: usize) -> u64 {
+        self.calibrated
+            .read()
+            .unwrap()
+            .get(&(provider, hops))
+            .map_or(0, |calibration| calibration.samples)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_receipts_calibrate_callback_gas() {
+        let model = CallbackGasModel::default();
+        assert_eq!(model.overhead(FlashProvider::UniswapV3, 2), 140_000);
+        assert_eq!(model.overhead(FlashProvider::UniswapV3Dual, 2), 170_000);
+        assert_eq!(
+            FlashProvider::for_amounts(U256::from(5u64), U256::zero()),
+            FlashProvider::UniswapV3
+        );
+        assert_eq!(FlashProvider::parse("uniswap_v3_dual"), Some(FlashProvider::UniswapV3Dual));
+
+        // Below min_samples the configured value still applies; clones see the receipts
+        let shared = model.clone();
+        shared.observe(FlashProvider::UniswapV3, 2, 200_000);
+        shared.observe(FlashProvider::UniswapV3, 2, 200_000);
+        assert_eq!(model.overhead(FlashProvider::UniswapV3, 2), 140_000);
+        shared.observe(FlashProvider::UniswapV3, 2, 200_000);
+        assert_eq!(model.samples(FlashProvider::UniswapV3, 2), 3);
+
+        // 140k moved 20% towards 200k three times
+        let calibrated = model.overhead(FlashProvider::UniswapV3, 2);
+        assert_eq!(calibrated, 169_280);
+        // Other route lengths and providers are calibrated separately
+        assert_eq!(model.overhead(FlashProvider::UniswapV3, 3), 165_000);
+        assert_eq!(model.overhead(FlashProvider::UniswapV3Dual, 2), 170_000);
+    }
+}
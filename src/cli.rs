@@ -0,0 +1,104 @@
+// src/cli.rs
+use clap::{Parser, Subcommand};
+use ethers::types::Address;
+use std::net::SocketAddr;
+
+use polygon_mev_bot::execution_mode::ExecutionMode;
+use polygon_mev_bot::graph_export::GraphFormat;
+use polygon_mev_bot::report::ReportFormat;
+use polygon_mev_bot::settings::DEFAULT_CONFIG_PATH;
+
+#[derive(Debug, Parser)]
+#[command(name = "flashwich", version, about = "Polygon flash-loan arbitrage bot")]
+pub struct Cli {
+    #[arg(long, global = true, default_value = DEFAULT_CONFIG_PATH)]
+    pub config: String,
+    /// Execution profile to start in, overriding `execution_mode.mode`; switchable live via PUT /mode
+    #[arg(long, global = true, value_enum)]
+    pub mode: Option<ExecutionMode>,
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Monitor the mempool and execute arbitrage (default)
+    Run {
+        /// Show the live terminal dashboard instead of logging to stdout
+        #[arg(long)]
+        tui: bool,
+    },
+    /// Execute opportunities published by detector instances instead of watching the mempool
+    Execute {
+        /// Queue to consume (redis://… or nats://…), defaults to `queue.url`
+        #[arg(long)]
+        queue: Option<String>,
+    },
+    /// Execute one opportunity from a JSON file (a serialized ArbitrageOpportunity, amounts as
+    /// 0x-prefixed hex) after the usual threshold, gas and preflight checks, then exit
+    ExecuteFile {
+        file: String,
+    },
+    /// Send a command to the running bot's control API
+    Ctl {
+        /// Control API address, defaults to `control_api.listen`
+        #[arg(long)]
+        api: Option<SocketAddr>,
+        #[command(subcommand)]
+        action: CtlCommand,
+    },
+    /// One-time executor setup: ownership check, router approvals, token whitelist
+    Setup,
+    /// Read-only self-test: RPC and WS, ABI and files, contract code, wallet balance, approvals and
+    /// relay reachability, printed as a pass/fail checklist
+    Doctor,
+    /// Deploy the executor contract from its artifact and record its address in the config
+    Deploy,
+    /// Summarize the opportunity journal: per-pair heatmap, PnL, failures, gas, inclusion
+    Report {
+        /// Hours of history to include
+        #[arg(long, default_value_t = 24)]
+        hours: u64,
+        #[arg(long, value_enum, default_value_t = ReportFormat::Html)]
+        format: ReportFormat,
+        /// File to write instead of stdout
+        #[arg(long)]
+        output: Option<String>,
+    },
+    /// Copy journal and index tables to one Parquet file each, from a read-only connection
+    Export {
+        /// Directory to write to, defaults to `parquet_export.dir`
+        #[arg(long)]
+        dir: Option<String>,
+        /// Table to export (repeatable), defaults to `parquet_export.tables`
+        #[arg(long = "table")]
+        tables: Vec<String>,
+    },
+    /// Dump the current token/pool graph (fees and reserves per pool) for visualization
+    Graph {
+        #[arg(long, value_enum, default_value_t = GraphFormat::Dot)]
+        format: GraphFormat,
+        /// File to write instead of stdout
+        #[arg(long)]
+        output: Option<String>,
+    },
+}
+
+// `flashwich ctl`: interventions on the running bot through its control API
+#[derive(Debug, Subcommand)]
+pub enum CtlCommand {
+    /// Stop executing; detection and journaling go on
+    Pause,
+    /// Execute again after a pause
+    Resume,
+    /// Release the hold raised by an admin event on the executor or FastLane contract
+    Acknowledge,
+    /// Floor under the dynamic profit threshold in MATIC, or "auto" to clear it
+    SetThreshold { min_profit: String },
+    /// Never trade through this token, pool or router
+    BlacklistAdd { address: Address },
+    /// Pay out accrued treasury balances at the next block
+    Sweep,
+    /// Show the current pause, threshold floor and blacklist
+    Status,
+}
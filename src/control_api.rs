@@ -0,0 +1,211 @@
+// src/control_api.rs
+use anyhow::Result;
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        State,
+    },
+    http::StatusCode,
+    response::{IntoResponse, Response},
+    routing::{get, post, put},
+    Json, Router,
+};
+use ethers::types::Address;
+use log::{debug, info};
+use serde::Deserialize;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use tokio::sync::broadcast;
+
+use crate::execution_mode::{ExecutionMode, ExecutionModes};
+use crate::logging::LogHandle;
+use crate::metrics;
+use crate::operator_controls::OperatorControls;
+use crate::top_of_book::TopOfBook;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ControlApiSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_listen")]
+    pub listen: SocketAddr,
+}
+
+fn default_listen() -> SocketAddr {
+    SocketAddr::from(([127, 0, 0, 1], 8088))
+}
+
+impl Default for ControlApiSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            listen: default_listen(),
+        }
+    }
+}
+
+// Shared handles the operator endpoints act on
+#[derive(Debug, Clone)]
+pub struct ControlState {
+    pub log_handle: Arc<LogHandle>,
+    // Published by binaries that scan block reserves (flashwich-lab)
+    pub book: Option<TopOfBook>,
+    pub modes: ExecutionModes,
+    // Pause, profit floor, blacklist and sweep requests (`flashwich ctl`)
+    pub controls: OperatorControls,
+}
+
+pub fn router(state: ControlState) -> Router {
+    Router::new()
+        .route("/health", get(health))
+        .route("/log", get(get_log_filter).put(set_log_filter))
+        .route("/metrics", get(get_metrics))
+        .route("/mode", get(get_mode).put(set_mode))
+        .route("/book", get(get_book))
+        .route("/book/ws", get(stream_book))
+        .route("/controls", get(get_controls))
+        .route("/pause", post(pause))
+        .route("/resume", post(resume))
+        .route("/acknowledge", post(acknowledge))
+        .route("/threshold", put(set_threshold))
+        .route("/blacklist", post(add_to_blacklist))
+        .route("/sweep", post(sweep))
+        .with_state(state)
+}
+
+pub async fn serve(listen: SocketAddr, state: ControlState) -> Result<()> {
+    let listener = tokio::net::TcpListener::bind(listen).await?;
+    info!("Control API listening on {}", listen);
+    axum::serve(listener, router(state)).await?;
+    Ok(())
+}
+
+async fn health() -> &'static str {
+    "ok"
+}
+
+async fn get_metrics() -> String {
+    metrics::render()
+}
+
+async fn get_log_filter(State(state): State<ControlState>) -> String {
+    state.log_handle.current_filter()
+}
+
+// Body is a plain EnvFilter directive string, e.g. "info,polygon_mev_bot::routers=trace"
+async fn set_log_filter(
+    State(state): State<ControlState>,
+    body: String,
+) -> (StatusCode, String) {
+    match state.log_handle.set_filter(body.trim()) {
+        Ok(()) => {
+            info!("Log filter changed to '{}'", body.trim());
+            (StatusCode::OK, state.log_handle.current_filter())
+        }
+        Err(e) => (StatusCode::BAD_REQUEST, e.to_string()),
+    }
+}
+
+async fn get_mode(State(state): State<ControlState>) -> Response {
+    Json(serde_json::json!({
+        "mode": state.modes.mode(),
+        "profile": state.modes.profile(),
+    }))
+    .into_response()
+}
+
+// Body is the mode name, e.g. "conservative"; applies from the next detection on
+async fn set_mode(State(state): State<ControlState>, body: String) -> Response {
+    let Some(mode) = ExecutionMode::parse(&body) else {
+        return (StatusCode::BAD_REQUEST, format!("unknown mode '{}'", body.trim())).into_response();
+    };
+    state.modes.set(mode);
+    get_mode(State(state)).await
+}
+
+async fn get_controls(State(state): State<ControlState>) -> Response {
+    Json(state.controls.status()).into_response()
+}
+
+async fn pause(State(state): State<ControlState>) -> Response {
+    state.controls.set_paused(true);
+    get_controls(State(state)).await
+}
+
+async fn resume(State(state): State<ControlState>) -> Response {
+    state.controls.set_paused(false);
+    get_controls(State(state)).await
+}
+
+// Releases the holds raised by executor/FastLane admin events; an operator pause stays
+async fn acknowledge(State(state): State<ControlState>) -> Response {
+    state.controls.acknowledge();
+    get_controls(State(state)).await
+}
+
+// Body is a MATIC amount, e.g. "0.5", or "auto" to clear the floor
+async fn set_threshold(State(state): State<ControlState>, body: String) -> Response {
+    let min_profit = match body.trim() {
+        "auto" => None,
+        amount => match ethers::utils::parse_ether(amount) {
+            Ok(wei) => Some(wei),
+            Err(e) => return (StatusCode::BAD_REQUEST, format!("bad threshold '{amount}': {e}")).into_response(),
+        },
+    };
+    state.controls.set_min_profit(min_profit);
+    get_controls(State(state)).await
+}
+
+// Body is a token, pool or router address
+async fn add_to_blacklist(State(state): State<ControlState>, body: String) -> Response {
+    let Ok(address) = body.trim().parse::<Address>() else {
+        return (StatusCode::BAD_REQUEST, format!("bad address '{}'", body.trim())).into_response();
+    };
+    state.controls.blacklist(address);
+    get_controls(State(state)).await
+}
+
+async fn sweep(State(state): State<ControlState>) -> Response {
+    state.controls.request_sweep();
+    (StatusCode::ACCEPTED, "sweep requested for the next block").into_response()
+}
+
+async fn get_book(State(state): State<ControlState>) -> Response {
+    match state.book.as_ref().map(TopOfBook::latest) {
+        Some(Some(snapshot)) => Json(snapshot.as_ref().clone()).into_response(),
+        Some(None) => (StatusCode::SERVICE_UNAVAILABLE, "no snapshot yet").into_response(),
+        None => (StatusCode::NOT_FOUND, "top-of-book is not published").into_response(),
+    }
+}
+
+// One JSON snapshot per text frame: the latest on connect, then one per scanned block
+async fn stream_book(State(state): State<ControlState>, upgrade: WebSocketUpgrade) -> Response {
+    let Some(book) = state.book else {
+        return (StatusCode::NOT_FOUND, "top-of-book is not published").into_response();
+    };
+    upgrade.on_upgrade(move |socket| push_book(socket, book))
+}
+
+async fn push_book(mut socket: WebSocket, book: TopOfBook) {
+    let mut feed = book.subscribe();
+    let mut next = book.latest();
+    loop {
+        if let Some(snapshot) = next.take() {
+            let Ok(text) = serde_json::to_string(snapshot.as_ref()) else {
+                continue;
+            };
+            if socket.send(Message::Text(text)).await.is_err() {
+                break;
+            }
+        }
+        next = match feed.recv().await {
+            Ok(snapshot) => Some(snapshot),
+            // Slow client: skip to the newest snapshot instead of replaying stale ones
+            Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                debug!("Book subscriber lagged by {} snapshots", skipped);
+                book.latest()
+            }
+            Err(broadcast::error::RecvError::Closed) => break,
+        };
+    }
+}
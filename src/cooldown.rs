@@ -0,0 +1,88 @@
+// src/cooldown.rs
+use ethers::types::Address;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CooldownSettings {
+    // Blocks a pool stays off limits after we executed through it; 0 disables
+    #[serde(default = "default_blocks")]
+    pub blocks: u64,
+}
+
+fn default_blocks() -> u64 {
+    3
+}
+
+impl Default for CooldownSettings {
+    fn default() -> Self {
+        Self {
+            blocks: default_blocks(),
+        }
+    }
+}
+
+// Per-pool throttle: right after our own trade a pool's spread is mostly ours to compete
+// with, so routes through it wait out the window instead of bidding against ourselves
+#[derive(Debug, Clone, Default)]
+pub struct PoolCooldown {
+    blocks: u64,
+    executed: Arc<Mutex<HashMap<Address, u64>>>,
+}
+
+impl PoolCooldown {
+    pub fn new(settings: &CooldownSettings) -> Self {
+        Self {
+            blocks: settings.blocks,
+            executed: Arc::default(),
+        }
+    }
+
+    // Starts the window on every pool of an executed route
+    pub fn start(&self, pools: &[Address], block_number: u64) {
+        if self.blocks == 0 {
+            return;
+        }
+        let mut executed = self.executed.lock().unwrap();
+        executed.retain(|_, &mut at| block_number.saturating_sub(at) < self.blocks);
+        for &pool in pools {
+            executed.insert(pool, block_number);
+        }
+    }
+
+    // First pool of the route still cooling down at `block_number`
+    pub fn cooling(&self, pools: &[Address], block_number: u64) -> Option<Address> {
+        let executed = self.executed.lock().unwrap();
+        pools.iter().copied().find(|pool| {
+            executed
+                .get(pool)
+                .is_some_and(|&at| block_number.saturating_sub(at) < self.blocks)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pools_cool_down_for_configured_blocks() {
+        let (pool_a, pool_b, pool_c) = (
+            Address::from_low_u64_be(1),
+            Address::from_low_u64_be(2),
+            Address::from_low_u64_be(3),
+        );
+        let cooldown = PoolCooldown::new(&CooldownSettings { blocks: 3 });
+        cooldown.start(&[pool_a, pool_b], 100);
+
+        // Any shared pool blocks the route, disjoint routes are unaffected
+        assert_eq!(cooldown.cooling(&[pool_c, pool_b], 102), Some(pool_b));
+        assert_eq!(cooldown.cooling(&[pool_c], 101), None);
+        assert_eq!(cooldown.cooling(&[pool_a], 103), None);
+
+        let disabled = PoolCooldown::new(&CooldownSettings { blocks: 0 });
+        disabled.start(&[pool_a], 100);
+        assert_eq!(disabled.cooling(&[pool_a], 100), None);
+    }
+}
@@ -0,0 +1,379 @@
+// src/dashboard.rs
+use anyhow::Result;
+use crossterm::{
+    event::{self, Event, KeyCode, KeyModifiers},
+    execute,
+    terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
+};
+use ethers::types::{H256, U256};
+use ratatui::{
+    backend::{Backend, CrosstermBackend},
+    layout::{Constraint, Direction, Layout},
+    style::{Color, Modifier, Style},
+    widgets::{Block, Borders, Paragraph, Row, Table},
+    Frame, Terminal,
+};
+use serde::Deserialize;
+use std::cmp::Reverse;
+use std::collections::VecDeque;
+use std::io;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DashboardSettings {
+    #[serde(default = "default_refresh_ms")]
+    pub refresh_ms: u64,
+    // Rows kept per table
+    #[serde(default = "default_rows")]
+    pub rows: usize,
+    // Opportunities older than this many blocks drop out of the top list
+    #[serde(default = "default_window_blocks")]
+    pub window_blocks: u64,
+    #[serde(default = "default_balance_interval_secs")]
+    pub balance_interval_secs: u64,
+}
+
+fn default_refresh_ms() -> u64 {
+    250
+}
+
+fn default_rows() -> usize {
+    10
+}
+
+fn default_window_blocks() -> u64 {
+    50
+}
+
+fn default_balance_interval_secs() -> u64 {
+    30
+}
+
+impl Default for DashboardSettings {
+    fn default() -> Self {
+        Self {
+            refresh_ms: default_refresh_ms(),
+            rows: default_rows(),
+            window_blocks: default_window_blocks(),
+            balance_interval_secs: default_balance_interval_secs(),
+        }
+    }
+}
+
+// Pending-tx throughput is averaged over this window
+const THROUGHPUT_WINDOW: Duration = Duration::from_secs(10);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpportunityRow {
+    pub block_number: u64,
+    pub class: String,
+    pub pair: String,
+    // Raw amount for ranking, `profit_display` in the token's units for the table
+    pub profit: U256,
+    pub profit_display: String,
+    pub status: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecutionRow {
+    pub block_number: u64,
+    pub pair: String,
+    pub status: String,
+    pub tx_hash: Option<H256>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    block_number: Option<u64>,
+    block_seen: Option<Instant>,
+    block_times: VecDeque<Instant>,
+    pending: VecDeque<Instant>,
+    pending_total: u64,
+    opportunities: Vec<OpportunityRow>,
+    executions: VecDeque<ExecutionRow>,
+    balances: Vec<(String, String)>,
+}
+
+// Live state behind `flashwich run --tui`. The bot records into it whether or not the
+// terminal view is running; every list is bounded so recording is always cheap.
+#[derive(Debug, Clone)]
+pub struct Dashboard {
+    settings: DashboardSettings,
+    state: Arc<Mutex<State>>,
+}
+
+impl Dashboard {
+    pub fn new(settings: DashboardSettings) -> Self {
+        Self {
+            settings,
+            state: Arc::default(),
+        }
+    }
+
+    pub fn settings(&self) -> &DashboardSettings {
+        &self.settings
+    }
+
+    pub fn record_block(&self, block_number: u64) {
+        self.record_block_at(block_number, Instant::now());
+    }
+
+    fn record_block_at(&self, block_number: u64, now: Instant) {
+        let mut state = self.state.lock().unwrap();
+        if state.block_number.is_some_and(|latest| latest >= block_number) {
+            return;
+        }
+        state.block_number = Some(block_number);
+        state.block_seen = Some(now);
+        state.block_times.push_back(now);
+        if state.block_times.len() > self.settings.rows {
+            state.block_times.pop_front();
+        }
+        let oldest = block_number.saturating_sub(self.settings.window_blocks);
+        state.opportunities.retain(|row| row.block_number >= oldest);
+    }
+
+    pub fn record_pending(&self) {
+        self.record_pending_at(Instant::now());
+    }
+
+    fn record_pending_at(&self, now: Instant) {
+        let mut state = self.state.lock().unwrap();
+        state.pending_total += 1;
+        state.pending.push_back(now);
+        while state
+            .pending
+            .front()
+            .is_some_and(|&seen| now.duration_since(seen) > THROUGHPUT_WINDOW)
+        {
+            state.pending.pop_front();
+        }
+    }
+
+    // Keeps the `rows` most profitable opportunities of the recent window
+    pub fn record_opportunity(&self, row: OpportunityRow) {
+        let mut state = self.state.lock().unwrap();
+        state.opportunities.push(row);
+        state.opportunities.sort_by_key(|row| Reverse(row.profit));
+        state.opportunities.truncate(self.settings.rows);
+    }
+
+    pub fn record_execution(&self, row: ExecutionRow) {
+        let mut state = self.state.lock().unwrap();
+        state.executions.push_front(row);
+        state.executions.truncate(self.settings.rows);
+    }
+
+    // (label, formatted balance) per wallet and token, replaced on every refresh
+    pub fn set_balances(&self, balances: Vec<(String, String)>) {
+        self.state.lock().unwrap().balances = balances;
+    }
+
+    pub fn pending_per_sec(&self) -> f64 {
+        self.pending_per_sec_at(Instant::now())
+    }
+
+    fn pending_per_sec_at(&self, now: Instant) -> f64 {
+        let state = self.state.lock().unwrap();
+        let recent = state
+            .pending
+            .iter()
+            .filter(|&&seen| now.duration_since(seen) <= THROUGHPUT_WINDOW)
+            .count();
+        recent as f64 / THROUGHPUT_WINDOW.as_secs_f64()
+    }
+
+    pub fn opportunities(&self) -> Vec<OpportunityRow> {
+        self.state.lock().unwrap().opportunities.clone()
+    }
+
+    pub fn executions(&self) -> Vec<ExecutionRow> {
+        self.state.lock().unwrap().executions.iter().cloned().collect()
+    }
+
+    fn header(&self) -> String {
+        let per_sec = self.pending_per_sec();
+        let state = self.state.lock().unwrap();
+        let block = state
+            .block_number
+            .map_or_else(|| "-".to_string(), |number| number.to_string());
+        let age = state
+            .block_seen
+            .map_or_else(|| "-".to_string(), |seen| format!("{:.1}s", seen.elapsed().as_secs_f64()));
+        let block_time = match (state.block_times.front(), state.block_times.back()) {
+            (Some(first), Some(last)) if state.block_times.len() > 1 => format!(
+                "{:.2}s",
+                last.duration_since(*first).as_secs_f64() / (state.block_times.len() - 1) as f64
+            ),
+            _ => "-".to_string(),
+        };
+        format!(
+            "block {}  (seen {} ago, avg {})   pending {:.1} tx/s  ({} total)   q to quit",
+            block, age, block_time, per_sec, state.pending_total
+        )
+    }
+
+    pub fn render(&self, frame: &mut Frame) {
+        let rows = self.settings.rows as u16 + 3;
+        let layout = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(3), Constraint::Length(rows), Constraint::Min(rows)])
+            .split(frame.size());
+        let lower = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Percentage(65), Constraint::Percentage(35)])
+            .split(layout[2]);
+
+        let titled = |title: &'static str| Block::default().borders(Borders::ALL).title(title);
+        let heading = Style::default().add_modifier(Modifier::BOLD);
+
+        frame.render_widget(Paragraph::new(self.header()).block(titled(" flashwich ")), layout[0]);
+
+        let opportunities = self.opportunities().into_iter().map(|row| {
+            Row::new(vec![
+                row.block_number.to_string(),
+                row.class,
+                row.pair,
+                row.profit_display,
+                row.status,
+            ])
+        });
+        let widths = [
+            Constraint::Length(10),
+            Constraint::Length(12),
+            Constraint::Min(16),
+            Constraint::Length(20),
+            Constraint::Length(16),
+        ];
+        frame.render_widget(
+            Table::new(opportunities, widths)
+                .header(Row::new(vec!["block", "class", "pair", "profit", "status"]).style(heading))
+                .block(titled(" top opportunities ")),
+            layout[1],
+        );
+
+        let executions = self.executions().into_iter().map(|row| {
+            let color = match row.status.as_str() {
+                "submitted" | "included" => Color::Green,
+                "failed" | "reverted" | "reorged" | "victim_dropped" | "outbid" | "stale_spread" => Color::Red,
+                _ => Color::Yellow,
+            };
+            let hash = row.tx_hash.map_or_else(|| "-".to_string(), |hash| format!("{:?}", hash));
+            Row::new(vec![row.block_number.to_string(), row.pair, row.status, hash]).style(Style::default().fg(color))
+        });
+        let widths = [
+            Constraint::Length(10),
+            Constraint::Min(16),
+            Constraint::Length(10),
+            Constraint::Length(66),
+        ];
+        frame.render_widget(
+            Table::new(executions, widths)
+                .header(Row::new(vec!["block", "pair", "status", "tx"]).style(heading))
+                .block(titled(" recent executions ")),
+            lower[0],
+        );
+
+        let balances = self.state.lock().unwrap().balances.clone();
+        let balances = balances
+            .into_iter()
+            .map(|(label, amount)| Row::new(vec![label, amount]));
+        frame.render_widget(
+            Table::new(balances, [Constraint::Min(12), Constraint::Min(12)]).block(titled(" balances ")),
+            lower[1],
+        );
+    }
+}
+
+// Draws the dashboard until q / Esc / Ctrl-C. Blocking: run it on a dedicated thread
+// (spawn_blocking) so terminal input polling never stalls the runtime.
+pub fn run(dashboard: &Dashboard) -> Result<()> {
+    enable_raw_mode()?;
+    execute!(io::stdout(), EnterAlternateScreen)?;
+    let result = Terminal::new(CrosstermBackend::new(io::stdout()))
+        .map_err(Into::into)
+        .and_then(|mut terminal| draw_loop(&mut terminal, dashboard));
+
+    // Restore the terminal even if drawing failed
+    disable_raw_mode()?;
+    execute!(io::stdout(), LeaveAlternateScreen)?;
+    result
+}
+
+fn draw_loop<B: Backend>(terminal: &mut Terminal<B>, dashboard: &Dashboard) -> Result<()> {
+    let refresh = Duration::from_millis(dashboard.settings.refresh_ms.max(10));
+    loop {
+        terminal.draw(|frame| dashboard.render(frame))?;
+        if !event::poll(refresh)? {
+            continue;
+        }
+        if let Event::Key(key) = event::read()? {
+            let ctrl_c = key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL);
+            if matches!(key.code, KeyCode::Char('q') | KeyCode::Esc) || ctrl_c {
+                return Ok(());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::backend::TestBackend;
+
+    fn opportunity(block_number: u64, profit: u64) -> OpportunityRow {
+        OpportunityRow {
+            block_number,
+            class: "cyclic".to_string(),
+            pair: "WMATIC/USDC".to_string(),
+            profit: U256::from(profit),
+            profit_display: profit.to_string(),
+            status: "detected".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_tables_stay_bounded_and_ranked() {
+        let dashboard = Dashboard::new(DashboardSettings {
+            rows: 2,
+            window_blocks: 5,
+            ..Default::default()
+        });
+        let start = Instant::now();
+        for i in 0..20 {
+            dashboard.record_pending_at(start + Duration::from_secs(i));
+        }
+        // Only the last 10s count towards throughput
+        assert_eq!(dashboard.pending_per_sec_at(start + Duration::from_secs(19)), 1.1);
+
+        dashboard.record_block_at(100, start);
+        dashboard.record_opportunity(opportunity(100, 5));
+        dashboard.record_opportunity(opportunity(100, 9));
+        dashboard.record_opportunity(opportunity(100, 1));
+        let profits: Vec<_> = dashboard.opportunities().iter().map(|row| row.profit).collect();
+        assert_eq!(profits, vec![U256::from(9u64), U256::from(5u64)]);
+
+        // Out of the window once the chain moves on
+        dashboard.record_block_at(106, start);
+        assert!(dashboard.opportunities().is_empty());
+
+        for block_number in 0..3 {
+            dashboard.record_execution(ExecutionRow {
+                block_number,
+                pair: "WMATIC/USDC".to_string(),
+                status: "submitted".to_string(),
+                tx_hash: None,
+            });
+        }
+        let executions = dashboard.executions();
+        assert_eq!(executions.len(), 2);
+        assert_eq!(executions[0].block_number, 2);
+
+        let mut terminal = Terminal::new(TestBackend::new(120, 30)).unwrap();
+        terminal.draw(|frame| dashboard.render(frame)).unwrap();
+        let screen: String = terminal.backend().buffer().content().iter().map(|cell| cell.symbol()).collect();
+        assert!(screen.contains("block 106"));
+        assert!(screen.contains("recent executions"));
+    }
+}
@@ -0,0 +1,162 @@
+// src/dedup.rs
+use log::debug;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::metrics::DEDUP_TOTAL;
+use crate::opportunity_id::OpportunityId;
+
+// Claims older than this many blocks can no longer collide and are pruned
+const HORIZON_BLOCKS: u64 = 4;
+
+// The same spread as seen by any detector: its opportunity id, and the block it was
+// detected in, which decides when the claim is pruned
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct DedupKey {
+    id: OpportunityId,
+    block_number: u64,
+}
+
+impl DedupKey {
+    pub fn new(id: OpportunityId, block_number: u64) -> Self {
+        Self { id, block_number }
+    }
+}
+
+// Proof of a won claim, checked again right before sending
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DedupTicket {
+    key: DedupKey,
+    generation: u64,
+}
+
+impl DedupTicket {
+    pub fn id(&self) -> OpportunityId {
+        self.key.id
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Holder {
+    source: &'static str,
+    // Block whose state the detection was priced against
+    state_block: u64,
+    generation: u64,
+    committed: bool,
+}
+
+// Shared by the back-run and block-scan detectors so one spread gets one execution attempt.
+// The earliest detection holds the claim; a later one only takes over if it priced the
+// spread from strictly fresher state and the holder has not committed to sending yet
+#[derive(Debug, Clone, Default)]
+pub struct OpportunityDedup {
+    claims: Arc<Mutex<HashMap<DedupKey, Holder>>>,
+    generations: Arc<AtomicU64>,
+}
+
+impl OpportunityDedup {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn claim(&self, key: DedupKey, source: &'static str, state_block: u64) -> Option<DedupTicket> {
+        let generation = self.generations.fetch_add(1, Ordering::Relaxed);
+        let mut claims = self.claims.lock().unwrap();
+        let newest = key.block_number;
+        claims.retain(|claimed, _| newest.saturating_sub(claimed.block_number) <= HORIZON_BLOCKS);
+
+        if let Some(holder) = claims.get(&key) {
+            if holder.committed || state_block <= holder.state_block {
+                debug!(
+                    "{} detection of {} is a duplicate of the {} one",
+                    source,
+                    key.id.short(),
+                    holder.source
+                );
+                DEDUP_TOTAL.with_label_values(&["duplicate"]).inc();
+                return None;
+            }
+            debug!(
+                "{} detection of {} supersedes the {} one (state block {} > {})",
+                source,
+                key.id.short(),
+                holder.source,
+                state_block,
+                holder.state_block
+            );
+            DEDUP_TOTAL.with_label_values(&["superseded"]).inc();
+        }
+
+        claims.insert(
+            key.clone(),
+            Holder {
+                source,
+                state_block,
+                generation,
+                committed: false,
+            },
+        );
+        Some(DedupTicket { key, generation })
+    }
+
+    // Locks the claim in before sending; false if a fresher detection took it over
+    pub fn commit(&self, ticket: &DedupTicket) -> bool {
+        match self.claims.lock().unwrap().get_mut(&ticket.key) {
+            Some(holder) if holder.generation == ticket.generation => {
+                holder.committed = true;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    // Gives up a claim that never reached the network, so another detection may execute
+    pub fn release(&self, ticket: &DedupTicket) {
+        let mut claims = self.claims.lock().unwrap();
+        if claims.get(&ticket.key).is_some_and(|holder| holder.generation == ticket.generation) {
+            claims.remove(&ticket.key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::types::{Address, U256};
+
+    #[test]
+    fn test_earliest_detection_wins_unless_outpriced_by_fresher_state() {
+        let dedup = OpportunityDedup::new();
+        let (pool_a, pool_b, token) = (
+            Address::from_low_u64_be(1),
+            Address::from_low_u64_be(2),
+            Address::from_low_u64_be(10),
+        );
+        let spread = |pools: &[Address], first: Address, block: u64| {
+            let id = OpportunityId::new(pools, first, token, U256::exp10(18), block + 1);
+            DedupKey::new(id, block)
+        };
+        // Pool order does not matter, direction does
+        let key = || spread(&[pool_b, pool_a], pool_a, 100);
+        assert_eq!(key(), spread(&[pool_a, pool_b], pool_a, 100));
+
+        let backrun = dedup.claim(key(), "mempool_backrun", 99).unwrap();
+        assert!(dedup.claim(key(), "block_cycle", 99).is_none());
+        assert!(dedup
+            .claim(spread(&[pool_a, pool_b], pool_b, 100), "block_cycle", 99)
+            .is_some());
+
+        // Fresher state takes over an uncommitted claim
+        let scan = dedup.claim(key(), "block_cycle", 100).unwrap();
+        assert!(!dedup.commit(&backrun));
+        assert!(dedup.commit(&scan));
+        assert!(dedup.claim(key(), "mempool_backrun", 101).is_none());
+
+        // A released claim frees the spread again
+        let next = spread(&[pool_a], pool_a, 101);
+        let ticket = dedup.claim(next.clone(), "block_cycle", 101).unwrap();
+        dedup.release(&ticket);
+        assert!(dedup.claim(next, "mempool_backrun", 101).is_some());
+    }
+}
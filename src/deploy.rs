@@ -0,0 +1,341 @@
+// src/deploy.rs
+use anyhow::{anyhow, bail, Result};
+use ethers::{
+    abi::{
+        token::{LenientTokenizer, Tokenizer},
+        Abi, ParamType, Token,
+    },
+    contract::{Contract, ContractFactory},
+    providers::Middleware,
+    types::{Address, Bytes},
+};
+use log::info;
+use serde::Deserialize;
+use serde_json::Value;
+use std::sync::Arc;
+
+use crate::setup::{CheckStatus, ReadinessReport};
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct DeploySettings {
+    // Compiler artifact with `abi` and `bytecode` (forge, hardhat or solc --combined-json)
+    #[serde(default = "default_artifact")]
+    pub artifact: String,
+    // Constructor arguments in declaration order, parsed against the artifact's ABI
+    #[serde(default)]
+    pub constructor_args: Vec<String>,
+    // Ownership is handed over after verification; unset keeps the deployer as owner
+    #[serde(default)]
+    pub owner: Option<Address>,
+    #[serde(default = "default_confirmations")]
+    pub confirmations: usize,
+    // Dotted key the deployed address is written to in the config file
+    #[serde(default = "default_config_key")]
+    pub config_key: String,
+}
+
+fn default_artifact() -> String {
+    "artifacts/FlashLoanArbitrage.json".to_string()
+}
+
+fn default_confirmations() -> usize {
+    3
+}
+
+fn default_config_key() -> String {
+    "contracts.flash_loan".to_string()
+}
+
+impl Default for DeploySettings {
+    fn default() -> Self {
+        Self {
+            artifact: default_artifact(),
+            constructor_args: Vec::new(),
+            owner: None,
+            confirmations: default_confirmations(),
+            config_key: default_config_key(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Artifact {
+    pub abi: Abi,
+    pub bytecode: Bytes,
+}
+
+impl Artifact {
+    pub fn load(path: &str) -> Result<Self> {
+        let content =
+            std::fs::read_to_string(path).map_err(|e| anyhow!("Failed to read artifact {path}: {e}"))?;
+        Self::parse(&content)
+    }
+
+    // `bytecode` is a hex string (hardhat), `{ "object": hex }` (forge) or `bin` (solc)
+    pub fn parse(content: &str) -> Result<Self> {
+        let value: Value = serde_json::from_str(content)?;
+        let abi = value.get("abi").ok_or_else(|| anyhow!("Artifact has no abi"))?;
+        let abi: Abi = match abi {
+            Value::String(encoded) => serde_json::from_str(encoded)?,
+            abi => serde_json::from_value(abi.clone())?,
+        };
+        let bytecode = match value.get("bytecode").or_else(|| value.get("bin")) {
+            Some(Value::String(hex)) => hex.as_str(),
+            Some(Value::Object(object)) => object
+                .get("object")
+                .and_then(Value::as_str)
+                .ok_or_else(|| anyhow!("Artifact bytecode has no object"))?,
+            _ => bail!("Artifact has no bytecode"),
+        };
+        let bytecode: Bytes = if bytecode.starts_with("0x") {
+            bytecode.parse()?
+        } else {
+            format!("0x{bytecode}").parse()?
+        };
+        if bytecode.is_empty() {
+            bail!("Artifact bytecode is empty (abstract contract or interface?)");
+        }
+        Ok(Self { abi, bytecode })
+    }
+
+    // Tokenizes `args` against the constructor inputs
+    pub fn constructor_tokens(&self, args: &[String]) -> Result<Vec<Token>> {
+        let inputs = self.abi.constructor().map(|c| c.inputs.as_slice()).unwrap_or_default();
+        if inputs.len() != args.len() {
+            bail!("Constructor takes {} arguments, {} configured", inputs.len(), args.len());
+        }
+        inputs
+            .iter()
+            .zip(args)
+            .map(|(input, arg)| {
+                LenientTokenizer::tokenize(&input.kind, arg)
+                    .map_err(|e| anyhow!("Invalid constructor argument {} = {arg}: {e}", input.name))
+            })
+            .collect()
+    }
+}
+
+// Sets `key_path` (dotted, e.g. "contracts.flash_loan") in YAML text in place, leaving
+// comments and formatting of every other line untouched
+pub fn set_config_value(content: &str, key_path: &str, value: &str) -> Result<String> {
+    let keys: Vec<&str> = key_path.split('.').collect();
+    let mut parents: Vec<(usize, String)> = Vec::new();
+    let mut lines: Vec<String> = content.lines().map(str::to_string).collect();
+
+    for line in lines.iter_mut() {
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with('-') {
+            continue;
+        }
+        let Some((key, rest)) = trimmed.split_once(':') else {
+            continue;
+        };
+        let indent = line.len() - trimmed.len();
+        while parents.last().is_some_and(|&(parent, _)| parent >= indent) {
+            parents.pop();
+        }
+
+        let key = key.trim().to_string();
+        let depth = parents.len();
+        let on_path = depth < keys.len()
+            && key == keys[depth]
+            && parents.iter().zip(&keys).all(|((_, parent), &expected)| parent == expected);
+        if on_path && depth == keys.len() - 1 {
+            let comment = rest.find(" #").map_or("", |at| &rest[at..]);
+            *line = format!("{}{}: \"{}\"{}", " ".repeat(indent), key, value, comment);
+            let mut updated = lines.join("\n");
+            if content.ends_with('\n') {
+                updated.push('\n');
+            }
+            return Ok(updated);
+        }
+        parents.push((indent, key));
+    }
+    bail!("{key_path} not found in config")
+}
+
+// Deploys the executor from its artifact and checks the result the way `flashwich setup`
+// reports readiness; `client` signs as the deployer
+pub struct ExecutorDeployer<M> {
+    client: Arc<M>,
+    deployer: Address,
+    settings: DeploySettings,
+}
+
+impl<M: Middleware + 'static> ExecutorDeployer<M> {
+    pub fn new(client: Arc<M>, deployer: Address, settings: DeploySettings) -> Self {
+        Self {
+            client,
+            deployer,
+            settings,
+        }
+    }
+
+    pub async fn run(&self) -> Result<(Address, ReadinessReport)> {
+        let artifact = Artifact::load(&self.settings.artifact)?;
+        let args = artifact.constructor_tokens(&self.settings.constructor_args)?;
+
+        let factory = ContractFactory::new(artifact.abi.clone(), artifact.bytecode.clone(), self.client.clone());
+        let deployment = factory
+            .deploy_tokens(args.clone())
+            .map_err(|e| anyhow!("Failed to encode deployment: {e}"))?
+            .confirmations(self.settings.confirmations);
+        info!(
+            "Deploying executor from {}, waiting for {} confirmations",
+            self.settings.artifact, self.settings.confirmations
+        );
+        let (contract, receipt) = deployment
+            .send_with_receipt()
+            .await
+            .map_err(|e| anyhow!("Deployment failed: {e}"))?;
+        let address = contract.address();
+
+        let mut report = ReadinessReport::default();
+        report.push(
+            "deployment",
+            CheckStatus::Ok,
+            format!("{:?} in {:?}", address, receipt.transaction_hash),
+        );
+        self.verify_constructor_args(&contract, &artifact.abi, &args, &mut report).await;
+        self.verify_owner(&contract, &mut report).await?;
+        Ok((address, report))
+    }
+
+    // Reads every constructor argument back through its public getter (`_router` -> `router()`,
+    // arrays element by element) so a misconfigured address is caught before the first trade
+    async fn verify_constructor_args(
+        &self,
+        contract: &Contract<M>,
+        abi: &Abi,
+        args: &[Token],
+        report: &mut ReadinessReport,
+    ) {
+        let inputs = abi.constructor().map(|c| c.inputs.clone()).unwrap_or_default();
+        let mut verified = 0;
+        let mut failures = Vec::new();
+        for (input, expected) in inputs.iter().zip(args) {
+            let getter = input.name.trim_start_matches('_');
+            let Ok(function) = abi.function(getter) else {
+                continue;
+            };
+            let mismatch = match (&input.kind, expected) {
+                (ParamType::Array(_), Token::Array(elements))
+                    if matches!(function.inputs.as_slice(), [index] if matches!(index.kind, ParamType::Uint(_))) =>
+                {
+                    let mut mismatch = None;
+                    for (i, element) in elements.iter().enumerate() {
+                        match self.read(contract, getter, vec![Token::Uint(i.into())]).await {
+                            Ok(actual) if &actual == element => {}
+                            Ok(actual) => mismatch = Some(format!("{getter}({i}) = {actual}")),
+                            Err(e) => mismatch = Some(format!("{getter}({i}): {e}")),
+                        }
+                    }
+                    mismatch
+                }
+                _ if function.inputs.is_empty() => match self.read(contract, getter, Vec::new()).await {
+                    Ok(actual) if &actual == expected => None,
+                    Ok(actual) => Some(format!("{getter}() = {actual}, expected {expected}")),
+                    Err(e) => Some(format!("{getter}(): {e}")),
+                },
+                _ => continue,
+            };
+            match mismatch {
+                None => verified += 1,
+                Some(detail) => failures.push(detail),
+            }
+        }
+
+        if failures.is_empty() {
+            let status = if verified > 0 { CheckStatus::Ok } else { CheckStatus::Skipped };
+            report.push("constructor", status, format!("{} of {} arguments verified", verified, args.len()));
+        } else {
+            report.push("constructor", CheckStatus::Failed, failures.join("; "));
+        }
+    }
+
+    async fn verify_owner(&self, contract: &Contract<M>, report: &mut ReadinessReport) -> Result<()> {
+        if contract.abi().function("owner").is_err() {
+            report.push("ownership", CheckStatus::Skipped, "executor has no owner()");
+            return Ok(());
+        }
+        let owner: Address = contract
+            .method("owner", ())?
+            .call()
+            .await
+            .map_err(|e| anyhow!("owner() failed: {e}"))?;
+        if owner != self.deployer {
+            report.push(
+                "ownership",
+                CheckStatus::Failed,
+                format!("owned by {:?}, deployed by {:?}", owner, self.deployer),
+            );
+            return Ok(());
+        }
+
+        let Some(new_owner) = self.settings.owner.filter(|&new_owner| new_owner != owner) else {
+            report.push("ownership", CheckStatus::Ok, format!("owned by {:?}", owner));
+            return Ok(());
+        };
+        info!("Transferring executor ownership to {:?}", new_owner);
+        let call = contract.method::<_, ()>("transferOwnership", new_owner)?;
+        let receipt = call
+            .send()
+            .await
+            .map_err(|e| anyhow!("transferOwnership failed: {e}"))?
+            .confirmations(self.settings.confirmations)
+            .await?
+            .ok_or_else(|| anyhow!("transferOwnership dropped from mempool"))?;
+        let status = if receipt.status == Some(1.into()) { CheckStatus::Ok } else { CheckStatus::Failed };
+        report.push(
+            "ownership",
+            status,
+            format!("transferred to {:?} in {:?}", new_owner, receipt.transaction_hash),
+        );
+        Ok(())
+    }
+
+    async fn read(&self, contract: &Contract<M>, getter: &str, args: Vec<Token>) -> Result<Token> {
+        let function = contract.abi().function(getter)?;
+        contract
+            .method_hash::<_, Token>(function.short_signature(), args.as_slice())?
+            .call()
+            .await
+            .map_err(|e| anyhow!("{e}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_artifact_args_and_config_write_back() {
+        let forge = r#"{
+            "abi": [{"type": "constructor", "stateMutability": "nonpayable", "inputs": [
+                {"name": "_swapRouter", "type": "address", "internalType": "address"},
+                {"name": "_routers", "type": "address[]", "internalType": "address[]"}
+            ]}],
+            "bytecode": {"object": "0x6080604052"}
+        }"#;
+        let artifact = Artifact::parse(forge).unwrap();
+        assert_eq!(artifact.bytecode.len(), 5);
+        let router = "0xE592427A0AEce92De3Edee1F18E0157C05861564".to_string();
+        let tokens = artifact
+            .constructor_tokens(&[router.clone(), format!("[{router},{router}]")])
+            .unwrap();
+        assert_eq!(tokens[0], Token::Address(router.parse().unwrap()));
+        assert!(matches!(&tokens[1], Token::Array(routers) if routers.len() == 2));
+        assert!(artifact.constructor_tokens(&[router]).is_err());
+
+        let hardhat = r#"{"abi": [], "bytecode": "0x"}"#;
+        assert!(Artifact::parse(hardhat).is_err());
+
+        let config = "contracts:\n  solver: \"0x01\" # Atlas\n  flash_loan: \"0x02\" # executor\nflash_loan: 1\n";
+        let updated = set_config_value(config, "contracts.flash_loan", "0xabc").unwrap();
+        assert_eq!(
+            updated,
+            "contracts:\n  solver: \"0x01\" # Atlas\n  flash_loan: \"0xabc\" # executor\nflash_loan: 1\n"
+        );
+        assert!(set_config_value(config, "contracts.executor", "0xabc").is_err());
+    }
+}
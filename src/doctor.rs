@@ -0,0 +1,292 @@
+// src/doctor.rs
+use ethers::{
+    providers::{Middleware, ProviderError, PubsubClient, RpcError, StreamExt},
+    types::{Address, U256},
+    utils::format_units,
+};
+use serde::Deserialize;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::erc20::Erc20;
+use crate::executor::ExecutorSettings;
+use crate::rpc_usage;
+use crate::setup::{CheckStatus, ReadinessReport};
+use crate::token_registry::DEFAULT_TOKENS_PATH;
+
+// `flashwich doctor`: read-only checks of every subsystem the bot leans on, printed as a
+// pass/fail checklist before a first run or after an infrastructure change. Nothing is
+// signed or sent
+#[derive(Debug, Clone, Deserialize)]
+pub struct DoctorSettings {
+    // A block number read slower than this fails the rpc check
+    #[serde(default = "default_max_rpc_latency_ms")]
+    pub max_rpc_latency_ms: u64,
+    // Wait for the first subscribed header, and for each HTTP endpoint's answer
+    #[serde(default = "default_timeout_ms")]
+    pub timeout_ms: u64,
+    // Gas money the signing wallet needs, in gwei of MATIC
+    #[serde(default = "default_min_balance_gwei")]
+    pub min_balance_gwei: u64,
+    #[serde(default = "default_relay_url")]
+    pub relay_url: String,
+    // Env vars holding contract addresses that must have code; unset ones are skipped
+    #[serde(default = "default_contract_envs")]
+    pub contract_envs: Vec<String>,
+    // Files read at startup besides the config and the executor ABI
+    #[serde(default = "default_files")]
+    pub files: Vec<String>,
+}
+
+fn default_max_rpc_latency_ms() -> u64 {
+    1_000
+}
+
+fn default_timeout_ms() -> u64 {
+    10_000
+}
+
+fn default_min_balance_gwei() -> u64 {
+    1_000_000_000
+}
+
+fn default_relay_url() -> String {
+    "https://relay.fastlane.tools".to_string()
+}
+
+fn default_contract_envs() -> Vec<String> {
+    ["FLASH_LOAN_CONTRACT", "FASTLANE_CONTRACT", "FASTLANE_SENDER_CONTRACT", "ARBITRAGE_EXECUTOR_CONTRACT"]
+        .map(String::from)
+        .to_vec()
+}
+
+fn default_files() -> Vec<String> {
+    vec![DEFAULT_TOKENS_PATH.to_string()]
+}
+
+impl Default for DoctorSettings {
+    fn default() -> Self {
+        Self {
+            max_rpc_latency_ms: default_max_rpc_latency_ms(),
+            timeout_ms: default_timeout_ms(),
+            min_balance_gwei: default_min_balance_gwei(),
+            relay_url: default_relay_url(),
+            contract_envs: default_contract_envs(),
+            files: default_files(),
+        }
+    }
+}
+
+pub struct Doctor<M> {
+    client: Arc<M>,
+    settings: DoctorSettings,
+}
+
+impl<M: Middleware + 'static> Doctor<M> {
+    pub fn new(client: Arc<M>, settings: DoctorSettings) -> Self {
+        Self { client, settings }
+    }
+
+    fn timeout(&self) -> Duration {
+        Duration::from_millis(self.settings.timeout_ms)
+    }
+
+    pub async fn check_rpc(&self, report: &mut ReadinessReport) {
+        let started = Instant::now();
+        match self.client.get_block_number().await {
+            Ok(number) => {
+                let latency = started.elapsed().as_millis() as u64;
+                let status = if latency <= self.settings.max_rpc_latency_ms {
+                    CheckStatus::Ok
+                } else {
+                    CheckStatus::Failed
+                };
+                report.push("rpc", status, format!("block {} in {} ms", number, latency));
+            }
+            Err(e) => report.push("rpc", CheckStatus::Failed, format!("eth_blockNumber failed: {}", e)),
+        }
+    }
+
+    pub fn check_files(&self, executor: &ExecutorSettings, report: &mut ReadinessReport) {
+        match executor.load_abi() {
+            Ok(abi) if abi.function(&executor.method).is_ok() => {
+                report.push("abi", CheckStatus::Ok, format!("executor ABI has {}", executor.method))
+            }
+            Ok(_) => report.push("abi", CheckStatus::Failed, format!("executor ABI lacks {}", executor.method)),
+            Err(e) => report.push("abi", CheckStatus::Failed, format!("{:#}", e)),
+        }
+        for file in &self.settings.files {
+            if Path::new(file).is_file() {
+                report.push("file", CheckStatus::Ok, file.clone());
+            } else {
+                report.push("file", CheckStatus::Failed, format!("{} missing", file));
+            }
+        }
+    }
+
+    // Every `contracts` entry is (label, address) and must be a deployed contract
+    pub async fn check_code(&self, contracts: &[(String, Address)], report: &mut ReadinessReport) {
+        for (label, address) in contracts {
+            match self.client.get_code(*address, None).await {
+                Ok(code) if !code.is_empty() => report.push(
+                    "contract",
+                    CheckStatus::Ok,
+                    format!("{} {:?} ({} bytes)", label, address, code.len()),
+                ),
+                Ok(_) => report.push("contract", CheckStatus::Failed, format!("{} {:?} has no code", label, address)),
+                Err(e) => report.push("contract", CheckStatus::Failed, format!("{} {:?}: {}", label, address, e)),
+            }
+        }
+    }
+
+    pub async fn check_balance(&self, wallet: Option<Address>, report: &mut ReadinessReport) {
+        let Some(wallet) = wallet else {
+            report.push("balance", CheckStatus::Skipped, "no signing key configured");
+            return;
+        };
+        let minimum = U256::from(self.settings.min_balance_gwei) * U256::exp10(9);
+        match self.client.get_balance(wallet, None).await {
+            Ok(balance) => {
+                let status = if balance >= minimum { CheckStatus::Ok } else { CheckStatus::Failed };
+                let matic = format_units(balance, "ether").unwrap_or_default();
+                let matic = matic.trim_end_matches('0').trim_end_matches('.');
+                report.push("balance", status, format!("{:?} holds {} MATIC", wallet, matic));
+            }
+            Err(e) => report.push("balance", CheckStatus::Failed, format!("{:?}: {}", wallet, e)),
+        }
+    }
+
+    // The executor's allowance for every router on every token, as `flashwich setup` grants it
+    pub async fn check_approvals(
+        &self,
+        executor: Address,
+        tokens: &[Address],
+        routers: &[Address],
+        report: &mut ReadinessReport,
+    ) {
+        if tokens.is_empty() || routers.is_empty() {
+            report.push("approvals", CheckStatus::Skipped, "no setup.tokens or setup.routers");
+            return;
+        }
+        let mut missing = Vec::new();
+        for token in tokens {
+            let erc20 = Erc20::new(*token, self.client.clone());
+            for router in routers {
+                match erc20.allowance(executor, *router).await {
+                    Ok(allowance) if !allowance.is_zero() => {}
+                    Ok(_) => missing.push(format!("{:?}->{:?}", token, router)),
+                    Err(e) => missing.push(format!("{:?}->{:?} ({})", token, router, e)),
+                }
+            }
+        }
+        let pairs = tokens.len() * routers.len();
+        if missing.is_empty() {
+            report.push("approvals", CheckStatus::Ok, format!("{}/{} token-router allowances set", pairs, pairs));
+        } else {
+            let detail = format!("{}/{} missing: {}", missing.len(), pairs, missing.join(", "));
+            report.push("approvals", CheckStatus::Failed, detail);
+        }
+    }
+
+    // A JSON-RPC endpoint is reachable when it answers at all: relays reject most read
+    // methods, and an error response still proves the round trip
+    pub async fn check_endpoint(&self, name: &'static str, url: Option<&str>, report: &mut ReadinessReport) {
+        let Some(url) = url.filter(|url| !url.is_empty()) else {
+            report.push(name, CheckStatus::Skipped, "not configured");
+            return;
+        };
+        let provider = match rpc_usage::http(url, "doctor") {
+            Ok(provider) => provider,
+            Err(e) => return report.push(name, CheckStatus::Failed, format!("{}: {:#}", url, e)),
+        };
+        let started = Instant::now();
+        let answer = tokio::time::timeout(self.timeout(), provider.request::<_, U256>("eth_chainId", ())).await;
+        let latency = started.elapsed().as_millis();
+        match answer {
+            Ok(Ok(_)) => report.push(name, CheckStatus::Ok, format!("{} answered in {} ms", url, latency)),
+            Ok(Err(e)) if reachable(&e) => {
+                report.push(name, CheckStatus::Ok, format!("{} answered in {} ms ({})", url, latency, e))
+            }
+            Ok(Err(e)) => report.push(name, CheckStatus::Failed, format!("{}: {}", url, e)),
+            Err(_) => report.push(name, CheckStatus::Failed, format!("{} silent for {} ms", url, latency)),
+        }
+    }
+}
+
+impl<M> Doctor<M>
+where
+    M: Middleware + 'static,
+    M::Provider: PubsubClient,
+{
+    pub async fn check_subscription(&self, report: &mut ReadinessReport) {
+        let started = Instant::now();
+        let header = tokio::time::timeout(self.timeout(), async {
+            let mut blocks = self.client.subscribe_blocks().await.map_err(|e| e.to_string())?;
+            Ok::<_, String>(blocks.next().await)
+        })
+        .await;
+        let elapsed = started.elapsed().as_millis();
+        match header {
+            Ok(Ok(Some(header))) => report.push(
+                "ws",
+                CheckStatus::Ok,
+                format!("header {} after {} ms", header.number.unwrap_or_default(), elapsed),
+            ),
+            Ok(Ok(None)) => report.push("ws", CheckStatus::Failed, "subscription closed before a header"),
+            Ok(Err(e)) => report.push("ws", CheckStatus::Failed, format!("eth_subscribe failed: {}", e)),
+            Err(_) => report.push("ws", CheckStatus::Failed, format!("no header within {} ms", elapsed)),
+        }
+    }
+}
+
+fn reachable(error: &ProviderError) -> bool {
+    error.as_error_response().is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{address, call_output, quantity, MockChain};
+    use ethers::abi::Token;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_chain_checks_fail_on_missing_code_balance_and_allowance() {
+        let chain = MockChain::new();
+        let doctor = Doctor::new(chain.provider(), DoctorSettings::default());
+        let (executor, router) = (address(1), address(2));
+        chain.script(vec![
+            quantity(1_000u64),
+            json!("0x6080"),
+            json!("0x"),
+            quantity(U256::exp10(17)),
+            call_output(&[Token::Uint(U256::MAX)]),
+            call_output(&[Token::Uint(U256::zero())]),
+        ]);
+
+        let mut report = ReadinessReport::default();
+        doctor.check_rpc(&mut report).await;
+        let contracts = [("executor".to_string(), executor), ("router".to_string(), router)];
+        doctor.check_code(&contracts, &mut report).await;
+        doctor.check_balance(Some(address(3)), &mut report).await;
+        doctor.check_approvals(executor, &[address(10), address(11)], &[router], &mut report).await;
+        doctor.check_balance(None, &mut report).await;
+
+        let statuses: Vec<_> = report.checks.iter().map(|check| (check.name, check.status)).collect();
+        assert_eq!(
+            statuses,
+            vec![
+                ("rpc", CheckStatus::Ok),
+                ("contract", CheckStatus::Ok),
+                ("contract", CheckStatus::Failed),
+                ("balance", CheckStatus::Failed),
+                ("approvals", CheckStatus::Failed),
+                ("balance", CheckStatus::Skipped),
+            ]
+        );
+        assert!(report.checks[3].detail.contains("0.1 MATIC"));
+        assert!(report.checks[4].detail.starts_with("1/2 missing"));
+        assert!(!report.is_ready());
+    }
+}
@@ -0,0 +1,240 @@
+// src/drift_monitor.rs
+use ethers::{
+    contract::parse_log,
+    types::{Address, TransactionReceipt, U256},
+};
+use log::{info, warn};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Mutex;
+
+use crate::pool_events::V2SwapEvent;
+
+// One simulated hop of a route, kept around until the receipt comes back
+#[derive(Debug, Clone)]
+pub struct SimulatedHop {
+    pub pool: Address,
+    pub zero_for_one: bool,
+    pub simulated_out: U256,
+}
+
+#[derive(Debug, Clone)]
+pub struct DriftConfig {
+    pub window: usize,
+    pub min_samples: usize,
+    pub correction_tolerance: f64, // |mean ratio - 1| above this corrects quotes
+    pub blacklist_tolerance: f64,  // |mean ratio - 1| above this blacklists the pool
+}
+
+impl Default for DriftConfig {
+    fn default() -> Self {
+        Self {
+            window: 20,
+            min_samples: 5,
+            correction_tolerance: 0.001, // 0.1%
+            blacklist_tolerance: 0.05,   // 5%
+        }
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+pub struct PoolDrift {
+    ratios: VecDeque<f64>, // realized / simulated, most recent last
+}
+
+impl PoolDrift {
+    pub fn mean_ratio(&self) -> Option<f64> {
+        if self.ratios.is_empty() {
+            return None;
+        }
+        Some(self.ratios.iter().sum::<f64>() / self.ratios.len() as f64)
+    }
+
+    pub fn samples(&self) -> usize {
+        self.ratios.len()
+    }
+}
+
+#[derive(Debug, Default)]
+struct DriftState {
+    pools: HashMap<Address, PoolDrift>,
+    blacklist: HashSet<Address>,
+}
+
+#[derive(Debug, Default)]
+pub struct DriftMonitor {
+    config: DriftConfig,
+    state: Mutex<DriftState>,
+}
+
+impl DriftMonitor {
+    pub fn new(config: DriftConfig) -> Self {
+        Self {
+            config,
+            state: Mutex::new(DriftState::default()),
+        }
+    }
+
+    // Record one realized-vs-simulated observation for a pool
+    pub fn record_hop(&self, pool: Address, simulated_out: U256, realized_out: U256) {
+        let simulated = to_f64(simulated_out);
+        if simulated == 0.0 {
+            return;
+        }
+
+        let ratio = to_f64(realized_out) / simulated;
+        let mut state = self.state.lock().unwrap();
+
+        let drift = state.pools.entry(pool).or_default();
+        drift.ratios.push_back(ratio);
+        while drift.ratios.len() > self.config.window {
+            drift.ratios.pop_front();
+        }
+
+        let samples = drift.samples();
+        let mean = drift.mean_ratio().unwrap_or(1.0);
+
+        if samples >= self.config.min_samples
+            && (mean - 1.0).abs() > self.config.blacklist_tolerance
+            && state.blacklist.insert(pool)
+        {
+            warn!(
+                "Pool {:?} blacklisted: realized/simulated ratio {:.4} over {} samples",
+                pool, mean, samples
+            );
+        }
+    }
+
+    // Match each simulated hop against the Swap log emitted by its pool
+    pub fn observe_receipt(&self, receipt: &TransactionReceipt, hops: &[SimulatedHop]) {
+        for hop in hops {
+            let realized = receipt
+                .logs
+                .iter()
+                .filter(|log| log.address == hop.pool)
+                .find_map(|log| parse_log::<V2SwapEvent>(log.clone()).ok());
+
+            match realized {
+                Some(swap) => {
+                    let realized_out = if hop.zero_for_one {
+                        swap.amount_1_out
+                    } else {
+                        swap.amount_0_out
+                    };
+                    self.record_hop(hop.pool, hop.simulated_out, realized_out);
+                }
+                None => {
+                    info!(
+                        "No Swap log for pool {:?} in tx {:?}",
+                        hop.pool, receipt.transaction_hash
+                    );
+                }
+            }
+        }
+    }
+
+    pub fn is_blacklisted(&self, pool: &Address) -> bool {
+        self.state.lock().unwrap().blacklist.contains(pool)
+    }
+
+    // Correction factor to apply to quotes through this pool, if drift is persistent
+    pub fn correction_factor(&self, pool: &Address) -> Option<f64> {
+        let state = self.state.lock().unwrap();
+        let drift = state.pools.get(pool)?;
+        if drift.samples() < self.config.min_samples {
+            return None;
+        }
+
+        let mean = drift.mean_ratio()?;
+        if (mean - 1.0).abs() > self.config.correction_tolerance {
+            Some(mean)
+        } else {
+            None
+        }
+    }
+
+    // Adjust a simulated hop output; None means the pool must not be routed through
+    pub fn adjust_quote(&self, pool: &Address, amount_out: U256) -> Option<U256> {
+        if self.is_blacklisted(pool) {
+            return None;
+        }
+
+        match self.correction_factor(pool) {
+            Some(factor) => {
+                let scaled = (factor * 1_000_000.0).round() as u64;
+                Some(amount_out * U256::from(scaled) / U256::from(1_000_000u64))
+            }
+            None => Some(amount_out),
+        }
+    }
+
+    pub fn snapshot(&self) -> HashMap<Address, PoolDrift> {
+        self.state.lock().unwrap().pools.clone()
+    }
+}
+
+// Keeps the top 64 significant bits, so amounts of any size convert without panicking
+fn to_f64(value: U256) -> f64 {
+    let shift = value.bits().saturating_sub(64);
+    (value >> shift).as_u64() as f64 * 2f64.powi(shift as i32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::abi::{self, Token};
+    use ethers::contract::EthEvent;
+    use ethers::types::{Log, H256};
+
+    #[test]
+    fn test_persistent_drift_corrects_then_blacklists() {
+        let monitor = DriftMonitor::new(DriftConfig::default());
+        let pool = Address::from_low_u64_be(1);
+
+        // 0.5% short on every hop: corrected but not blacklisted
+        for _ in 0..5 {
+            monitor.record_hop(pool, U256::from(1_000_000u64), U256::from(995_000u64));
+        }
+        assert!(!monitor.is_blacklisted(&pool));
+        assert_eq!(
+            monitor.adjust_quote(&pool, U256::from(1_000_000u64)),
+            Some(U256::from(995_000u64))
+        );
+
+        // Rebasing-style 10% shortfall pushes the rolling mean past the blacklist bound
+        for _ in 0..20 {
+            monitor.record_hop(pool, U256::from(1_000_000u64), U256::from(900_000u64));
+        }
+        assert!(monitor.is_blacklisted(&pool));
+        assert_eq!(monitor.adjust_quote(&pool, U256::from(1_000_000u64)), None);
+    }
+
+    #[test]
+    fn test_receipts_read_each_hops_own_output_side() {
+        let monitor = DriftMonitor::new(DriftConfig::default());
+        let (pool, to) = (Address::from_low_u64_be(1), Address::from_low_u64_be(2));
+        // A token1 -> token0 hop whose output is far above u128
+        let out = U256::exp10(45);
+        let data = abi::encode(&[
+            Token::Uint(U256::zero()),
+            Token::Uint(U256::exp10(18)),
+            Token::Uint(out),
+            Token::Uint(U256::zero()),
+        ]);
+        let log = Log {
+            address: pool,
+            topics: vec![V2SwapEvent::signature(), H256::from(to), H256::from(to)],
+            data: data.into(),
+            ..Default::default()
+        };
+        let receipt = TransactionReceipt { logs: vec![log], ..Default::default() };
+        let hops = [SimulatedHop { pool, zero_for_one: false, simulated_out: out }];
+
+        for _ in 0..5 {
+            monitor.observe_receipt(&receipt, &hops);
+        }
+        assert_eq!(monitor.snapshot()[&pool].mean_ratio(), Some(1.0));
+        assert!(!monitor.is_blacklisted(&pool));
+        monitor.record_hop(pool, U256::zero(), out);
+        assert_eq!(monitor.snapshot()[&pool].samples(), 5);
+    }
+}
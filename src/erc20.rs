@@ -0,0 +1,144 @@
+// src/erc20.rs
+use anyhow::{anyhow, bail, Result};
+use ethers::{
+    contract::{abigen, ContractCall},
+    providers::Middleware,
+    types::{Address, TransactionReceipt, U256},
+};
+use log::{debug, info};
+use std::sync::Arc;
+
+abigen!(
+    IERC20,
+    r#"[
+        function name() external view returns (string)
+        function symbol() external view returns (string)
+        function decimals() external view returns (uint8)
+        function totalSupply() external view returns (uint256)
+        function balanceOf(address owner) external view returns (uint256)
+        function allowance(address owner, address spender) external view returns (uint256)
+        function approve(address spender, uint256 amount) external returns (bool)
+        function transfer(address to, uint256 amount) external returns (bool)
+        function transferFrom(address from, address to, uint256 amount) external returns (bool)
+    ]"#
+);
+
+// Approvals needed to move an allowance from `current` to `target`. USDT-style tokens
+// revert when changing a non-zero allowance to another non-zero value, so reset first.
+pub fn approval_steps(current: U256, target: U256) -> Vec<U256> {
+    if current == target {
+        Vec::new()
+    } else if current.is_zero() || target.is_zero() {
+        vec![target]
+    } else {
+        vec![U256::zero(), target]
+    }
+}
+
+// Thin wrapper over an ERC20 with checked writes
+#[derive(Debug, Clone)]
+pub struct Erc20<M> {
+    contract: IERC20<M>,
+}
+
+impl<M: Middleware + 'static> Erc20<M> {
+    pub fn new(token: Address, client: Arc<M>) -> Self {
+        Self {
+            contract: IERC20::new(token, client),
+        }
+    }
+
+    pub fn address(&self) -> Address {
+        self.contract.address()
+    }
+
+    // Raw binding, e.g. for adding calls to a multicall
+    pub fn contract(&self) -> &IERC20<M> {
+        &self.contract
+    }
+
+    pub async fn balance_of(&self, owner: Address) -> Result<U256> {
+        self.contract
+            .balance_of(owner)
+            .call()
+            .await
+            .map_err(|e| anyhow!("balanceOf on {:?} failed: {e}", self.address()))
+    }
+
+    pub async fn allowance(&self, owner: Address, spender: Address) -> Result<U256> {
+        self.contract
+            .allowance(owner, spender)
+            .call()
+            .await
+            .map_err(|e| anyhow!("allowance on {:?} failed: {e}", self.address()))
+    }
+
+    pub async fn decimals(&self) -> Result<u8> {
+        self.contract
+            .decimals()
+            .call()
+            .await
+            .map_err(|e| anyhow!("decimals on {:?} failed: {e}", self.address()))
+    }
+
+    pub async fn symbol(&self) -> Result<String> {
+        self.contract
+            .symbol()
+            .call()
+            .await
+            .map_err(|e| anyhow!("symbol on {:?} failed: {e}", self.address()))
+    }
+
+    // Tokens that return nothing from approve/transfer are fine: success is judged by the
+    // receipt status, not by decoding a bool
+    async fn send_checked(&self, call: ContractCall<M, bool>, what: &str) -> Result<TransactionReceipt> {
+        let pending = call
+            .send()
+            .await
+            .map_err(|e| anyhow!("{what} on {:?} failed: {e}", self.address()))?;
+        let receipt = pending
+            .await?
+            .ok_or_else(|| anyhow!("{what} on {:?} dropped from the mempool", self.address()))?;
+        if receipt.status != Some(1u64.into()) {
+            bail!("{what} on {:?} reverted in {:?}", self.address(), receipt.transaction_hash);
+        }
+        Ok(receipt)
+    }
+
+    // Sets `spender`'s allowance from `owner` to exactly `amount`; no-op when already set
+    pub async fn safe_approve(
+        &self,
+        owner: Address,
+        spender: Address,
+        amount: U256,
+    ) -> Result<Vec<TransactionReceipt>> {
+        let current = self.allowance(owner, spender).await?;
+        let mut receipts = Vec::new();
+        for step in approval_steps(current, amount) {
+            debug!("approve({:?}, {}) on {:?}", spender, step, self.address());
+            receipts.push(self.send_checked(self.contract.approve(spender, step), "approve").await?);
+        }
+        if !receipts.is_empty() {
+            info!("Allowance of {:?} on {:?} set to {}", spender, self.address(), amount);
+        }
+        Ok(receipts)
+    }
+
+    pub async fn safe_transfer(&self, to: Address, amount: U256) -> Result<TransactionReceipt> {
+        self.send_checked(self.contract.transfer(to, amount), "transfer").await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_approval_steps_reset_non_zero_allowances() {
+        let (zero, five, nine) = (U256::zero(), U256::from(5u64), U256::from(9u64));
+        assert!(approval_steps(five, five).is_empty());
+        assert_eq!(approval_steps(zero, nine), vec![nine]);
+        assert_eq!(approval_steps(five, zero), vec![zero]);
+        assert_eq!(approval_steps(five, nine), vec![zero, nine]);
+    }
+}
@@ -0,0 +1,249 @@
+// src/execution_mode.rs
+use clap::ValueEnum;
+use ethers::types::U256;
+use log::info;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use crate::amm_math::get_amounts_out;
+use crate::metrics::EXECUTION_MODE;
+use crate::opportunity_class::OpportunityClass;
+
+const BPS: u64 = 10_000;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum ExecutionMode {
+    Aggressive,
+    #[default]
+    Balanced,
+    Conservative,
+}
+
+impl ExecutionMode {
+    pub const ALL: [ExecutionMode; 3] = [
+        ExecutionMode::Aggressive,
+        ExecutionMode::Balanced,
+        ExecutionMode::Conservative,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ExecutionMode::Aggressive => "aggressive",
+            ExecutionMode::Balanced => "balanced",
+            ExecutionMode::Conservative => "conservative",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|mode| mode.as_str().eq_ignore_ascii_case(value.trim()))
+    }
+}
+
+// Thresholds one mode applies on top of the regular settings
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExecutionProfile {
+    // Multiplier on the dynamic minimum profit
+    pub min_profit_multiplier: f64,
+    // Largest share of the expected profit handed to the validator as FastLane bid
+    pub max_bid_fraction: f64,
+    pub max_hops: usize,
+    // Largest share of a pool's input reserve a trade may consume, in basis points
+    pub max_pool_impact_bps: u64,
+    // Strategies this mode executes, further limited by `classes.enabled`
+    pub classes: Vec<OpportunityClass>,
+}
+
+impl Default for ExecutionProfile {
+    fn default() -> Self {
+        Self {
+            min_profit_multiplier: 1.0,
+            max_bid_fraction: 0.7,
+            max_hops: 3,
+            max_pool_impact_bps: 100,
+            classes: vec![
+                OpportunityClass::CrossDex,
+                OpportunityClass::Triangular,
+                OpportunityClass::StableDepeg,
+                OpportunityClass::Backrun,
+            ],
+        }
+    }
+}
+
+impl ExecutionProfile {
+    pub fn allows(&self, class: OpportunityClass, hops: usize) -> bool {
+        self.classes.contains(&class) && hops <= self.max_hops
+    }
+
+    pub fn scale_min_profit(&self, min_profit: U256) -> U256 {
+        let ppm = (self.min_profit_multiplier.max(0.0) * 1_000_000.0).round() as u64;
+        min_profit * U256::from(ppm) / U256::from(1_000_000u64)
+    }
+
+    pub fn max_bid(&self, expected_profit: U256) -> U256 {
+        let bps = (self.max_bid_fraction.clamp(0.0, 1.0) * BPS as f64).round() as u64;
+        expected_profit * U256::from(bps) / U256::from(BPS)
+    }
+
+    // `amount_in` through (reserve_in, reserve_out, fee) hops stays within the impact cap
+    pub fn within_impact(&self, amount_in: U256, hops: &[(U256, U256, u32)]) -> bool {
+        pool_impact_bps(amount_in, hops) <= self.max_pool_impact_bps
+    }
+}
+
+// Largest share of a hop's input reserve the trade adds, in basis points
+pub fn pool_impact_bps(amount_in: U256, hops: &[(U256, U256, u32)]) -> u64 {
+    let amounts = get_amounts_out(amount_in, hops);
+    hops.iter()
+        .zip(&amounts)
+        .map(|(&(reserve_in, _, _), &amount)| {
+            if reserve_in.is_zero() {
+                return u64::MAX;
+            }
+            (amount.saturating_mul(U256::from(BPS)) / reserve_in).min(U256::from(u64::MAX)).as_u64()
+        })
+        .max()
+        .unwrap_or_default()
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExecutionModeSettings {
+    // Mode at startup; `--mode` overrides it and the control API switches it live
+    #[serde(default)]
+    pub mode: ExecutionMode,
+    // Per-mode overrides of the built-in presets
+    #[serde(default = "default_profiles")]
+    pub profiles: HashMap<ExecutionMode, ExecutionProfile>,
+}
+
+fn default_profiles() -> HashMap<ExecutionMode, ExecutionProfile> {
+    HashMap::from([
+        (
+            ExecutionMode::Aggressive,
+            ExecutionProfile {
+                min_profit_multiplier: 0.8,
+                max_bid_fraction: 0.9,
+                max_hops: 4,
+                max_pool_impact_bps: 300,
+                ..Default::default()
+            },
+        ),
+        (ExecutionMode::Balanced, ExecutionProfile::default()),
+        (
+            ExecutionMode::Conservative,
+            ExecutionProfile {
+                min_profit_multiplier: 1.5,
+                max_bid_fraction: 0.5,
+                max_hops: 2,
+                max_pool_impact_bps: 30,
+                classes: vec![OpportunityClass::CrossDex, OpportunityClass::StableDepeg],
+            },
+        ),
+    ])
+}
+
+impl Default for ExecutionModeSettings {
+    fn default() -> Self {
+        Self {
+            mode: ExecutionMode::default(),
+            profiles: default_profiles(),
+        }
+    }
+}
+
+impl ExecutionModeSettings {
+    // Configured profile, else the built-in preset
+    pub fn profile(&self, mode: ExecutionMode) -> ExecutionProfile {
+        match self.profiles.get(&mode) {
+            Some(profile) => profile.clone(),
+            None => default_profiles().remove(&mode).unwrap_or_default(),
+        }
+    }
+}
+
+// The active mode, shared by the detection loops and the control API
+#[derive(Debug, Clone)]
+pub struct ExecutionModes {
+    settings: Arc<ExecutionModeSettings>,
+    current: Arc<RwLock<ExecutionMode>>,
+}
+
+impl Default for ExecutionModes {
+    fn default() -> Self {
+        Self::new(ExecutionModeSettings::default())
+    }
+}
+
+impl ExecutionModes {
+    pub fn new(settings: ExecutionModeSettings) -> Self {
+        let modes = Self {
+            current: Arc::new(RwLock::new(settings.mode)),
+            settings: Arc::new(settings),
+        };
+        modes.publish(modes.mode());
+        modes
+    }
+
+    pub fn mode(&self) -> ExecutionMode {
+        *self.current.read().unwrap()
+    }
+
+    pub fn profile(&self) -> ExecutionProfile {
+        self.settings.profile(self.mode())
+    }
+
+    // Switches the mode for every following detection; returns the previous one
+    pub fn set(&self, mode: ExecutionMode) -> ExecutionMode {
+        let previous = std::mem::replace(&mut *self.current.write().unwrap(), mode);
+        if previous != mode {
+            info!("Execution mode switched from {} to {}", previous.as_str(), mode.as_str());
+        }
+        self.publish(mode);
+        previous
+    }
+
+    fn publish(&self, mode: ExecutionMode) {
+        for candidate in ExecutionMode::ALL {
+            EXECUTION_MODE
+                .with_label_values(&[candidate.as_str()])
+                .set((candidate == mode) as i64);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_modes_bundle_thresholds_and_switch_live() {
+        let modes = ExecutionModes::new(ExecutionModeSettings {
+            mode: ExecutionMode::Conservative,
+            ..Default::default()
+        });
+        let shared = modes.clone();
+        let profile = modes.profile();
+        assert!(profile.allows(OpportunityClass::CrossDex, 2));
+        assert!(!profile.allows(OpportunityClass::Triangular, 2));
+        assert!(!profile.allows(OpportunityClass::CrossDex, 3));
+        assert_eq!(profile.scale_min_profit(U256::from(1_000u64)), U256::from(1_500u64));
+        assert_eq!(profile.max_bid(U256::from(1_000u64)), U256::from(500u64));
+
+        // 1% of the first pool's reserve, then ~1% of the second's
+        let reserve = U256::exp10(20);
+        let hops = [(reserve, reserve, 3000), (reserve, reserve, 3000)];
+        assert_eq!(pool_impact_bps(U256::exp10(18), &hops), 100);
+        assert!(!profile.within_impact(U256::exp10(18), &hops));
+
+        assert_eq!(shared.set(ExecutionMode::parse("Aggressive").unwrap()), ExecutionMode::Conservative);
+        let profile = modes.profile();
+        assert_eq!(modes.mode(), ExecutionMode::Aggressive);
+        assert!(profile.allows(OpportunityClass::Triangular, 4));
+        assert!(profile.within_impact(U256::exp10(18), &hops));
+        assert_eq!(EXECUTION_MODE.with_label_values(&["aggressive"]).get(), 1);
+        assert_eq!(ExecutionMode::parse("reckless"), None);
+    }
+}
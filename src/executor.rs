@@ -0,0 +1,770 @@
+// src/executor.rs
+use anyhow::{anyhow, bail, Result};
+use ethers::{
+    abi::{Abi, Function, ParamType, Token},
+    types::{Address, Bytes, U256, U64},
+};
+use log::debug;
+use serde::Deserialize;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::flash_source::FlashLeg;
+use crate::permit::Permit;
+use crate::simulation_engine::ArbitrageOpportunity;
+use crate::split_route::SplitLeg;
+
+// ABI of the bundled FlashLoanArbitrage executor, used when no custom ABI is configured
+const DEFAULT_EXECUTOR_ABI: &str = include_str!("../abis/FlashLoanArbitrage.json");
+
+// Where each argument of the executor method comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ArgSource {
+    Token0,
+    Token1,
+    Amount0,
+    Amount1,
+    Fee,
+    Path,
+    Amounts,
+    Routers,
+    ExpectedProfit,
+    TargetBlock,
+    // Last block or timestamp the trade may execute at, per `deadline`
+    Deadline,
+    // (token0, token1, amount0, amount1, fee, path, amounts, routers) as one tuple
+    Opportunity,
+    // Path and routers packed as indices into the executor's registered tables (bytes)
+    CompactRoute,
+    // Signed EIP-2612/Permit2 allowances for the executor, (uint8,address,address,uint256,uint256,uint256,bytes)[]
+    Permits,
+    // Flash borrows split over providers, (address lender, uint256 amount)[]
+    FlashLegs,
+    // First hop split over a V2 and a V3 pool, (address router, address pool, uint256 amountIn,
+    // uint256 amountOutMin)[]; empty when the hop goes through one pool
+    SplitLegs,
+}
+
+impl ArgSource {
+    fn accepts(&self, param: &ParamType) -> bool {
+        let address_array = ParamType::Array(Box::new(ParamType::Address));
+        match self {
+            ArgSource::Token0 | ArgSource::Token1 => *param == ParamType::Address,
+            ArgSource::Amount0
+            | ArgSource::Amount1
+            | ArgSource::Fee
+            | ArgSource::ExpectedProfit
+            | ArgSource::TargetBlock
+            | ArgSource::Deadline => matches!(param, ParamType::Uint(_)),
+            ArgSource::Path | ArgSource::Routers => *param == address_array,
+            ArgSource::Amounts => matches!(param, ParamType::Array(inner) if matches!(**inner, ParamType::Uint(_))),
+            ArgSource::Opportunity => match param {
+                ParamType::Tuple(fields) if fields.len() == 8 => {
+                    [
+                        ArgSource::Token0,
+                        ArgSource::Token1,
+                        ArgSource::Amount0,
+                        ArgSource::Amount1,
+                        ArgSource::Fee,
+                        ArgSource::Path,
+                        ArgSource::Amounts,
+                        ArgSource::Routers,
+                    ]
+                    .iter()
+                    .zip(fields)
+                    .all(|(source, field)| source.accepts(field))
+                }
+                _ => false,
+            },
+            ArgSource::CompactRoute => *param == ParamType::Bytes,
+            ArgSource::Permits => *param == ParamType::Array(Box::new(Permit::param_type())),
+            ArgSource::FlashLegs => {
+                *param == ParamType::Array(Box::new(ParamType::Tuple(vec![ParamType::Address, ParamType::Uint(256)])))
+            }
+            ArgSource::SplitLegs => *param == ParamType::Array(Box::new(SplitLeg::param_type())),
+        }
+    }
+
+    // None when the value can't be produced, i.e. a route outside the compact tables
+    #[allow(clippy::too_many_arguments)]
+    fn token(
+        &self,
+        opportunity: &ArbitrageOpportunity,
+        target_block: U64,
+        deadline: U256,
+        table: &RouteTable,
+        permits: &[Permit],
+        flash_legs: &[FlashLeg],
+        split_legs: &[SplitLeg],
+    ) -> Option<Token> {
+        let addresses = |list: &[ethers::types::Address]| {
+            Token::Array(list.iter().map(|&a| Token::Address(a)).collect())
+        };
+        let token = match self {
+            ArgSource::Token0 => Token::Address(opportunity.token0),
+            ArgSource::Token1 => Token::Address(opportunity.token1),
+            ArgSource::Amount0 => Token::Uint(opportunity.amount0),
+            ArgSource::Amount1 => Token::Uint(opportunity.amount1),
+            ArgSource::Fee => Token::Uint(U256::from(opportunity.fee)),
+            ArgSource::Path => addresses(&opportunity.path),
+            ArgSource::Amounts => Token::Array(opportunity.amounts.iter().map(|&a| Token::Uint(a)).collect()),
+            ArgSource::Routers => addresses(&opportunity.routers),
+            ArgSource::ExpectedProfit => Token::Uint(opportunity.expected_profit),
+            ArgSource::TargetBlock => Token::Uint(U256::from(target_block.as_u64())),
+            ArgSource::Deadline => Token::Uint(deadline),
+            ArgSource::Opportunity => Token::Tuple(vec![
+                Token::Address(opportunity.token0),
+                Token::Address(opportunity.token1),
+                Token::Uint(opportunity.amount0),
+                Token::Uint(opportunity.amount1),
+                Token::Uint(U256::from(opportunity.fee)),
+                addresses(&opportunity.path),
+                Token::Array(opportunity.amounts.iter().map(|&a| Token::Uint(a)).collect()),
+                addresses(&opportunity.routers),
+            ]),
+            ArgSource::CompactRoute => Token::Bytes(table.pack(&opportunity.path, &opportunity.routers)?.to_vec()),
+            ArgSource::Permits => Token::Array(permits.iter().map(Permit::to_token).collect()),
+            ArgSource::FlashLegs => Token::Array(
+                flash_legs
+                    .iter()
+                    .map(|leg| Token::Tuple(vec![Token::Address(leg.lender), Token::Uint(leg.amount)]))
+                    .collect(),
+            ),
+            ArgSource::SplitLegs => Token::Array(split_legs.iter().map(SplitLeg::to_token).collect()),
+        };
+        Some(token)
+    }
+}
+
+// Mirror of the router/token tables registered on the executor; index i here must be
+// index i on-chain
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct RouteTable {
+    #[serde(default)]
+    pub routers: Vec<Address>,
+    #[serde(default)]
+    pub tokens: Vec<Address>,
+}
+
+impl RouteTable {
+    // [token count][token index..][router count][router index..], one byte each
+    pub fn pack(&self, path: &[Address], routers: &[Address]) -> Option<Bytes> {
+        let index = |table: &[Address], address: &Address| {
+            table.iter().position(|entry| entry == address).and_then(|i| u8::try_from(i).ok())
+        };
+        let mut packed = Vec::with_capacity(path.len() + routers.len() + 2);
+        packed.push(u8::try_from(path.len()).ok()?);
+        for token in path {
+            packed.push(index(&self.tokens, token)?);
+        }
+        packed.push(u8::try_from(routers.len()).ok()?);
+        for router in routers {
+            packed.push(index(&self.routers, router)?);
+        }
+        Some(packed.into())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RouteEncoding {
+    // Full address arrays via `method`
+    #[default]
+    Verbose,
+    // `compact.method` whenever every hop is in the tables, verbose otherwise
+    Compact,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CompactSettings {
+    #[serde(default = "default_compact_method")]
+    pub method: String,
+    #[serde(default = "default_compact_arguments")]
+    pub arguments: Vec<ArgSource>,
+    #[serde(flatten)]
+    pub table: RouteTable,
+}
+
+fn default_compact_method() -> String {
+    "executeCompact".to_string()
+}
+
+fn default_compact_arguments() -> Vec<ArgSource> {
+    vec![ArgSource::CompactRoute, ArgSource::Amounts, ArgSource::TargetBlock]
+}
+
+impl Default for CompactSettings {
+    fn default() -> Self {
+        Self {
+            method: default_compact_method(),
+            arguments: default_compact_arguments(),
+            table: RouteTable::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DeadlineKind {
+    // A block number, counted from the target block
+    #[default]
+    Block,
+    // A unix timestamp, counted from when the calldata is built
+    Timestamp,
+}
+
+// Validity window written into the calldata, so a trade included late reverts instead of
+// executing against a market that has moved. The bundled executor reads values below 1e9
+// as block numbers and larger ones as timestamps
+#[derive(Debug, Clone, Deserialize)]
+pub struct DeadlineSettings {
+    #[serde(default)]
+    pub kind: DeadlineKind,
+    // Blocks after the target block that may still include the trade
+    #[serde(default = "default_deadline_blocks")]
+    pub blocks: u64,
+    #[serde(default = "default_deadline_secs")]
+    pub secs: u64,
+}
+
+fn default_deadline_blocks() -> u64 {
+    1
+}
+
+fn default_deadline_secs() -> u64 {
+    6
+}
+
+impl Default for DeadlineSettings {
+    fn default() -> Self {
+        Self {
+            kind: DeadlineKind::default(),
+            blocks: default_deadline_blocks(),
+            secs: default_deadline_secs(),
+        }
+    }
+}
+
+impl DeadlineSettings {
+    pub fn deadline(&self, target_block: U64, now_secs: u64) -> U256 {
+        match self.kind {
+            DeadlineKind::Block => U256::from(target_block.as_u64() + self.blocks),
+            DeadlineKind::Timestamp => U256::from(now_secs + self.secs),
+        }
+    }
+
+    pub fn deadline_now(&self, target_block: U64) -> U256 {
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|now| now.as_secs()).unwrap_or_default();
+        self.deadline(target_block, now)
+    }
+}
+
+// "name(type,..)", the form a user writes to pick one overload
+fn input_signature(function: &Function) -> String {
+    let params: Vec<String> = function.inputs.iter().map(|input| input.kind.to_string()).collect();
+    format!("{}({})", function.name, params.join(","))
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExecutorSettings {
+    // Path to the executor ABI (bare array or Hardhat/Foundry artifact); bundled ABI if unset
+    #[serde(default)]
+    pub abi: Option<String>,
+    // Method name, or full signature when the name is overloaded
+    #[serde(default = "default_method")]
+    pub method: String,
+    #[serde(default = "default_arguments")]
+    pub arguments: Vec<ArgSource>,
+    #[serde(default)]
+    pub encoding: RouteEncoding,
+    #[serde(default)]
+    pub compact: CompactSettings,
+    #[serde(default)]
+    pub deadline: DeadlineSettings,
+}
+
+fn default_method() -> String {
+    "executeArbitrageWithFastLane".to_string()
+}
+
+fn default_arguments() -> Vec<ArgSource> {
+    vec![ArgSource::Opportunity, ArgSource::TargetBlock, ArgSource::Deadline]
+}
+
+impl ExecutorSettings {
+    pub fn load_abi(&self) -> Result<Abi> {
+        let content = match &self.abi {
+            Some(path) => std::fs::read_to_string(path)
+                .map_err(|e| anyhow!("Failed to read executor ABI {path}: {e}"))?,
+            None => DEFAULT_EXECUTOR_ABI.to_string(),
+        };
+
+        // Accept both a bare ABI array and a compiler artifact with an "abi" field
+        let value: serde_json::Value = serde_json::from_str(&content)?;
+        let abi: Abi = match value.get("abi") {
+            Some(abi) => serde_json::from_value(abi.clone())?,
+            None => serde_json::from_value(value)?,
+        };
+        Ok(abi)
+    }
+}
+
+impl Default for ExecutorSettings {
+    fn default() -> Self {
+        Self {
+            abi: None,
+            method: default_method(),
+            arguments: default_arguments(),
+            encoding: RouteEncoding::default(),
+            compact: CompactSettings::default(),
+            deadline: DeadlineSettings::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct EncodedMethod {
+    function: Function,
+    arguments: Vec<ArgSource>,
+}
+
+impl EncodedMethod {
+    fn new(abi: &Abi, method: &str, arguments: Vec<ArgSource>) -> Result<Self> {
+        let function = if method.contains('(') {
+            abi.functions()
+                .find(|f| input_signature(f) == method.replace(' ', ""))
+                .cloned()
+                .ok_or_else(|| anyhow!("Executor ABI has no function with signature {method}"))?
+        } else {
+            let overloads = abi.functions_by_name(method)
+                .map_err(|_| anyhow!("Executor ABI has no function named {method}"))?;
+            if overloads.len() > 1 {
+                bail!("Executor method {method} is overloaded; configure its full signature");
+            }
+            overloads[0].clone()
+        };
+
+        if function.inputs.len() != arguments.len() {
+            bail!(
+                "Executor method {} takes {} arguments but {} are mapped",
+                function.name,
+                function.inputs.len(),
+                arguments.len()
+            );
+        }
+        for (input, source) in function.inputs.iter().zip(&arguments) {
+            if !source.accepts(&input.kind) {
+                bail!(
+                    "Executor argument '{}' ({}) cannot be filled from {:?}",
+                    input.name,
+                    input.kind,
+                    source
+                );
+            }
+        }
+
+        Ok(Self { function, arguments })
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn encode(
+        &self,
+        opportunity: &ArbitrageOpportunity,
+        target_block: U64,
+        deadline: U256,
+        table: &RouteTable,
+        permits: &[Permit],
+        flash_legs: &[FlashLeg],
+        split_legs: &[SplitLeg],
+    ) -> Option<Result<Bytes>> {
+        let tokens: Option<Vec<Token>> = self
+            .arguments
+            .iter()
+            .map(|source| source.token(opportunity, target_block, deadline, table, permits, flash_legs, split_legs))
+            .collect();
+        let tokens = tokens?;
+        Some(self.function.encode_input(&tokens).map(Bytes::from).map_err(Into::into))
+    }
+}
+
+// Encodes an ArbitrageOpportunity into the calldata shape of the configured executor
+#[derive(Debug, Clone)]
+pub struct ExecutorAdapter {
+    verbose: EncodedMethod,
+    compact: Option<(EncodedMethod, RouteTable)>,
+    deadline: DeadlineSettings,
+}
+
+impl ExecutorAdapter {
+    pub fn new(abi: &Abi, method: &str, arguments: Vec<ArgSource>) -> Result<Self> {
+        if arguments.contains(&ArgSource::CompactRoute) {
+            bail!("compact_route is only available to the compact method");
+        }
+        Ok(Self {
+            verbose: EncodedMethod::new(abi, method, arguments)?,
+            compact: None,
+            deadline: DeadlineSettings::default(),
+        })
+    }
+
+    pub fn with_deadline(mut self, deadline: DeadlineSettings) -> Self {
+        self.deadline = deadline;
+        self
+    }
+
+    // Routes the tables can express use `method`; the rest keep the verbose encoding
+    pub fn with_compact(mut self, abi: &Abi, method: &str, arguments: Vec<ArgSource>, table: RouteTable) -> Result<Self> {
+        self.compact = Some((EncodedMethod::new(abi, method, arguments)?, table));
+        Ok(self)
+    }
+
+    pub fn from_settings(settings: &ExecutorSettings) -> Result<Self> {
+        let abi = settings.load_abi()?;
+        let adapter = Self::new(&abi, &settings.method, settings.arguments.clone())?.with_deadline(settings.deadline.clone());
+        match settings.encoding {
+            RouteEncoding::Verbose => Ok(adapter),
+            RouteEncoding::Compact => adapter.with_compact(
+                &abi,
+                &settings.compact.method,
+                settings.compact.arguments.clone(),
+                settings.compact.table.clone(),
+            ),
+        }
+    }
+
+    pub fn method(&self) -> &str {
+        &self.verbose.function.name
+    }
+
+    // Whether either method takes signed permits, i.e. whether they are worth signing
+    pub fn uses_permits(&self) -> bool {
+        let mut methods = std::iter::once(&self.verbose).chain(self.compact.as_ref().map(|(method, _)| method));
+        methods.any(|method| method.arguments.contains(&ArgSource::Permits))
+    }
+
+    // Whether either method takes split flash borrows; without it a trade is sized to one provider
+    pub fn uses_flash_legs(&self) -> bool {
+        let mut methods = std::iter::once(&self.verbose).chain(self.compact.as_ref().map(|(method, _)| method));
+        methods.any(|method| method.arguments.contains(&ArgSource::FlashLegs))
+    }
+
+    // Whether either method takes split first-hop legs; without it a hop uses a single pool
+    pub fn uses_split_legs(&self) -> bool {
+        let mut methods = std::iter::once(&self.verbose).chain(self.compact.as_ref().map(|(method, _)| method));
+        methods.any(|method| method.arguments.contains(&ArgSource::SplitLegs))
+    }
+
+    pub fn encode(&self, opportunity: &ArbitrageOpportunity, target_block: U64) -> Result<Bytes> {
+        self.encode_with_permits(opportunity, target_block, &[])
+    }
+
+    pub fn encode_with_permits(
+        &self,
+        opportunity: &ArbitrageOpportunity,
+        target_block: U64,
+        permits: &[Permit],
+    ) -> Result<Bytes> {
+        self.encode_with(opportunity, target_block, permits, &[], &[])
+    }
+
+    pub fn encode_with(
+        &self,
+        opportunity: &ArbitrageOpportunity,
+        target_block: U64,
+        permits: &[Permit],
+        flash_legs: &[FlashLeg],
+        split_legs: &[SplitLeg],
+    ) -> Result<Bytes> {
+        let deadline = self.deadline.deadline_now(target_block);
+        if let Some((compact, table)) = &self.compact {
+            match compact.encode(opportunity, target_block, deadline, table, permits, flash_legs, split_legs) {
+                Some(data) => return data,
+                None => debug!("Route not in the compact tables; using verbose encoding"),
+            }
+        }
+        self.verbose
+            .encode(opportunity, target_block, deadline, &RouteTable::default(), permits, flash_legs, split_legs)
+            .ok_or_else(|| anyhow!("Verbose executor arguments could not be filled"))?
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::{abi::parse_abi, types::Address};
+
+    fn opportunity() -> ArbitrageOpportunity {
+        ArbitrageOpportunity {
+            token0: Address::from_low_u64_be(1),
+            token1: Address::from_low_u64_be(2),
+            amount0: U256::from(100u64),
+            amount1: U256::zero(),
+            fee: 3000,
+            path: vec![Address::from_low_u64_be(1), Address::from_low_u64_be(2)],
+            amounts: vec![U256::from(100u64)],
+            routers: vec![Address::from_low_u64_be(3)],
+            expected_profit: U256::from(5u64),
+            optimal_path: Vec::new(),
+            class: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_default_executor_encodes_bundled_abi() {
+        let adapter = ExecutorAdapter::from_settings(&ExecutorSettings::default()).unwrap();
+        let data = adapter.encode(&opportunity(), U64::from(10)).unwrap();
+        assert_eq!(adapter.method(), "executeArbitrageWithFastLane");
+        assert_eq!(data[..4], adapter.verbose.function.short_signature());
+        let decoded = adapter.verbose.function.decode_input(&data[4..]).unwrap();
+        assert_eq!(decoded[2], Token::Uint(U256::from(11u64)));
+    }
+
+    #[test]
+    fn test_deadline_policies() {
+        let blocks = DeadlineSettings {
+            blocks: 3,
+            ..Default::default()
+        };
+        assert_eq!(blocks.deadline(U64::from(100), 1_700_000_000), U256::from(103u64));
+        let timestamp = DeadlineSettings {
+            kind: DeadlineKind::Timestamp,
+            ..Default::default()
+        };
+        assert_eq!(timestamp.deadline(U64::from(100), 1_700_000_000), U256::from(1_700_000_006u64));
+    }
+
+    #[test]
+    fn test_custom_executor_mapping() {
+        let abi = parse_abi(&["function run(address[] pools, address asset, uint256 amount, uint256 minProfit)"]).unwrap();
+        let arguments = vec![ArgSource::Path, ArgSource::Token0, ArgSource::Amount0, ArgSource::ExpectedProfit];
+        let adapter = ExecutorAdapter::new(&abi, "run", arguments).unwrap();
+
+        let data = adapter.encode(&opportunity(), U64::zero()).unwrap();
+        let decoded = adapter.verbose.function.decode_input(&data[4..]).unwrap();
+        assert_eq!(decoded[1], Token::Address(Address::from_low_u64_be(1)));
+        assert_eq!(decoded[3], Token::Uint(U256::from(5u64)));
+
+        // Type mismatches are reported at startup, not at send time
+        assert!(ExecutorAdapter::new(&abi, "run", vec![ArgSource::Token0; 4]).is_err());
+    }
+
+    #[test]
+    fn test_permits_argument() {
+        let abi = parse_abi(&[
+            "struct Permit { uint8 kind; address owner; address token; uint256 amount; uint256 deadline; uint256 nonce; bytes signature; }",
+            "function run(address[] path, uint256[] amounts, Permit[] permits)",
+        ])
+        .unwrap();
+        let adapter = ExecutorAdapter::new(&abi, "run", vec![ArgSource::Path, ArgSource::Amounts, ArgSource::Permits]).unwrap();
+        assert!(adapter.uses_permits());
+
+        let permit = Permit {
+            kind: crate::permit::PermitKind::Permit2,
+            owner: Address::from_low_u64_be(4),
+            token: Address::from_low_u64_be(1),
+            amount: U256::from(100u64),
+            deadline: U256::from(1_000u64),
+            nonce: U256::zero(),
+            signature: vec![1u8; 65].into(),
+        };
+        let data = adapter.encode_with_permits(&opportunity(), U64::zero(), std::slice::from_ref(&permit)).unwrap();
+        let decoded = adapter.verbose.function.decode_input(&data[4..]).unwrap();
+        assert_eq!(decoded[2], Token::Array(vec![permit.to_token()]));
+    }
+
+    #[test]
+    fn test_split_legs_argument() {
+        let abi = parse_abi(&[
+            "struct Leg { address router; address pool; uint256 amountIn; uint256 amountOutMin; }",
+            "function run(address[] path, Leg[] legs)",
+        ])
+        .unwrap();
+        let adapter = ExecutorAdapter::new(&abi, "run", vec![ArgSource::Path, ArgSource::SplitLegs]).unwrap();
+        assert!(adapter.uses_split_legs() && !adapter.uses_flash_legs());
+
+        let leg = |n: u64| SplitLeg {
+            router: Address::from_low_u64_be(n),
+            pool: Address::from_low_u64_be(n + 10),
+            amount_in: U256::from(n * 100),
+            amount_out_min: U256::from(n * 99),
+        };
+        let legs = [leg(1), leg(2)];
+        let data = adapter.encode_with(&opportunity(), U64::zero(), &[], &[], &legs).unwrap();
+        let decoded = adapter.verbose.function.decode_input(&data[4..]).unwrap();
+        assert_eq!(decoded[1], Token::Array(vec![legs[0].to_token(), legs[1].to_token()]));
+    }
+
+    #[test]
+    fn test_compact_route_encoding_with_verbose_fallback() {
+        let abi = parse_abi(&[
+            "function run(address[] path, address[] routers, uint256[] amounts)",
+            "function runCompact(bytes route, uint256[] amounts)",
+        ])
+        .unwrap();
+        let opportunity = opportunity();
+        let table = RouteTable {
+            routers: vec![Address::from_low_u64_be(9), Address::from_low_u64_be(3)],
+            tokens: vec![Address::from_low_u64_be(2), Address::from_low_u64_be(1)],
+        };
+        assert_eq!(table.pack(&opportunity.path, &opportunity.routers).unwrap().to_vec(), vec![2, 1, 0, 1, 1]);
+
+        let verbose = ExecutorAdapter::new(&abi, "run", vec![ArgSource::Path, ArgSource::Routers, ArgSource::Amounts]).unwrap();
+        let adapter = verbose
+            .clone()
+            .with_compact(&abi, "runCompact", vec![ArgSource::CompactRoute, ArgSource::Amounts], table)
+            .unwrap();
+        let compact = adapter.encode(&opportunity, U64::zero()).unwrap();
+        let full = verbose.encode(&opportunity, U64::zero()).unwrap();
+        assert!(compact.len() < full.len());
+
+        // A token outside the table falls back to the verbose method
+        let unknown = ArbitrageOpportunity { path: vec![Address::from_low_u64_be(77)], ..opportunity };
+        assert_eq!(
+            adapter.encode(&unknown, U64::zero()).unwrap(),
+            verbose.encode(&unknown, U64::zero()).unwrap()
+        );
+    }
+
+    // Intrinsic gas of calldata: 4 per zero byte, 16 per non-zero byte (EIP-2028)
+    fn calldata_gas(data: &[u8]) -> u64 {
+        data.iter().map(|&byte| if byte == 0 { 4 } else { 16 }).sum()
+    }
+
+    // A route of `hops` swaps between real-looking tokens and routers, sized like a live trade
+    fn route(hops: usize) -> ArbitrageOpportunity {
+        let token = |i: usize| Address::repeat_byte(0x10 + i as u8);
+        let mut path: Vec<Address> = (0..hops).map(token).collect();
+        path.push(token(0));
+        ArbitrageOpportunity {
+            token0: token(0),
+            token1: token(1),
+            amount0: U256::exp10(21) + 12_345,
+            amount1: U256::zero(),
+            fee: 3000,
+            amounts: (0..hops).map(|i| U256::exp10(21) * (i + 1) + 6_789).collect(),
+            routers: (0..hops).map(|i| Address::repeat_byte(0xa0 + i as u8)).collect(),
+            path,
+            expected_profit: U256::exp10(18),
+            optimal_path: Vec::new(),
+            class: Default::default(),
+        }
+    }
+
+    // (route shape, executor method, hops, calldata gas budget), recorded from each shape's
+    // encoding with 5% headroom. Raise a budget only for an intended calldata change
+    const GAS_BUDGETS: [(&str, &str, usize, u64); 4] = [
+        ("fastlane_2_hop", "executeArbitrageWithFastLane", 2, 5_220),
+        ("fastlane_3_hop", "executeArbitrageWithFastLane", 3, 6_240),
+        ("fastlane_4_hop", "executeArbitrageWithFastLane", 4, 7_260),
+        ("flash_loan_3_hop", "executeFlashLoanArbitrage", 3, 5_940),
+    ];
+
+    fn budget_adapter(method: &str) -> ExecutorAdapter {
+        let abi = ExecutorSettings::default().load_abi().unwrap();
+        let arguments = match method {
+            "executeFlashLoanArbitrage" => vec![
+                ArgSource::Token0,
+                ArgSource::Token1,
+                ArgSource::Amount0,
+                ArgSource::Amount1,
+                ArgSource::Fee,
+                ArgSource::Path,
+                ArgSource::Amounts,
+                ArgSource::Routers,
+                ArgSource::Deadline,
+            ],
+            _ => default_arguments(),
+        };
+        ExecutorAdapter::new(&abi, method, arguments).unwrap()
+    }
+
+    #[test]
+    fn test_calldata_gas_stays_within_recorded_budgets() {
+        for (shape, method, hops, budget) in GAS_BUDGETS {
+            let data = budget_adapter(method).encode(&route(hops), U64::from(60_000_000u64)).unwrap();
+            let gas = calldata_gas(&data);
+            assert!(gas <= budget, "{shape}: calldata costs {gas} gas, budget {budget} ({} bytes)", data.len());
+        }
+    }
+
+    // Swaps of a recorded executor call: `path`, the sixth field of both the flat arguments and
+    // the FastLane opportunity tuple, lists one token more than the route has hops
+    fn recorded_hops(tokens: &[Token]) -> Option<usize> {
+        let fields = match tokens.first()? {
+            Token::Tuple(fields) => fields.as_slice(),
+            _ => tokens,
+        };
+        fields.get(5)?.clone().into_array()?.len().checked_sub(1)
+    }
+
+    // Blocks searched back from the fork head for executions to replay
+    const FORK_LOOKBACK_BLOCKS: u64 = 50_000;
+    // How far the fork's estimate may stray from the gas the receipt recorded
+    const FORK_GAS_TOLERANCE_BPS: u64 = 1_000;
+
+    // Against an anvil fork of Polygon from an archive node (`anvil --fork-url $POLYGON_RPC_URL`,
+    // then `ANVIL_URL=http://127.0.0.1:8545 FLASH_LOAN_CONTRACT=0x.. cargo test -- --ignored`).
+    // Every execution the deployed executor recorded lately is estimated again, as it was sent,
+    // on the state its block started from: the estimate has to agree with its receipt, and its
+    // calldata has to fit the budget recorded for its shape
+    #[tokio::test]
+    #[ignore]
+    async fn test_fork_estimates_match_recorded_executions() {
+        use crate::bindings::{ArbitrageExecutedFilter, FlashLoanArbitrage};
+        use ethers::providers::{Http, Middleware, Provider};
+        use ethers::types::{transaction::eip2718::TypedTransaction, BlockId, Bytes, TransactionRequest};
+        use std::sync::Arc;
+
+        let url = std::env::var("ANVIL_URL").expect("ANVIL_URL must point at an anvil fork");
+        let executor: Address = std::env::var("FLASH_LOAN_CONTRACT")
+            .expect("FLASH_LOAN_CONTRACT must be the deployed executor")
+            .parse()
+            .unwrap();
+        let provider = Arc::new(Provider::<Http>::try_from(url).unwrap());
+        let abi = ExecutorSettings::default().load_abi().unwrap();
+        let head = provider.get_block_number().await.unwrap().as_u64();
+        let executions = FlashLoanArbitrage::new(executor, provider.clone())
+            .event::<ArbitrageExecutedFilter>()
+            .from_block(head.saturating_sub(FORK_LOOKBACK_BLOCKS))
+            .to_block(head)
+            .query_with_meta()
+            .await
+            .unwrap();
+        assert!(!executions.is_empty(), "no executions of {executor:?} to replay");
+
+        for (_, meta) in executions {
+            let hash = meta.transaction_hash;
+            let sent = provider.get_transaction(hash).await.unwrap().unwrap();
+            let receipt = provider.get_transaction_receipt(hash).await.unwrap().unwrap();
+            let tx: TypedTransaction = TransactionRequest::new()
+                .from(sent.from)
+                .to(executor)
+                .data(sent.input.clone())
+                .value(sent.value)
+                .into();
+            let parent = BlockId::Number((meta.block_number.as_u64() - 1).into());
+            let estimate = provider.estimate_gas(&tx, Some(parent)).await.unwrap();
+            let used = receipt.gas_used.unwrap();
+            let gap = if estimate > used { estimate - used } else { used - estimate };
+            assert!(
+                gap * U256::from(10_000u64) <= used * U256::from(FORK_GAS_TOLERANCE_BPS),
+                "{hash:?}: fork estimates {estimate} gas, the receipt used {used}"
+            );
+
+            // Executions sent before the current ABI (e.g. without a deadline) have no calldata budget
+            let selector = Bytes::from(sent.input[..4].to_vec());
+            let Some(function) = abi.functions().find(|function| function.short_signature() == selector[..]) else {
+                eprintln!("{hash:?}: selector {selector} is not in the executor ABI, skipping its calldata budget");
+                continue;
+            };
+            let hops = recorded_hops(&function.decode_input(&sent.input[4..]).unwrap()).unwrap();
+            let budget = GAS_BUDGETS.iter().find(|(_, method, shape_hops, _)| {
+                *method == function.name && *shape_hops == hops
+            });
+            if let Some((shape, _, _, budget)) = budget {
+                let gas = calldata_gas(&sent.input);
+                assert!(gas <= *budget, "{hash:?} ({shape}): calldata costs {gas} gas, budget {budget}");
+            }
+        }
+    }
+}
@@ -0,0 +1,433 @@
+// src/executor_events.rs
+use anyhow::{anyhow, Result};
+use ethers::{
+    contract::LogMeta,
+    providers::Middleware,
+    types::{Address, H256, U256},
+};
+use futures::StreamExt;
+use log::{debug, info, warn};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::metrics::{EXECUTOR_EVENTS_REORGED_TOTAL, EXECUTOR_EVENTS_TOTAL};
+use crate::store::{ExecutionRecord, Store};
+use crate::treasury::Treasury;
+use crate::bindings::{FlashLoanArbitrage, FlashLoanArbitrageEvents};
+
+// Key under which the listener checkpoints its progress in the store
+const CHECKPOINT: &str = "executor_events";
+
+// How often events awaiting their confirmation depth are checked
+const FINALITY_POLL: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExecutorEventSettings {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    // How far back to start when the store has no checkpoint yet
+    #[serde(default = "default_backfill_blocks")]
+    pub backfill_blocks: u64,
+    // Block range per eth_getLogs request during backfill
+    #[serde(default = "default_chunk_size")]
+    pub chunk_size: u64,
+    // Blocks on top of an event's block before it is booked; until then it waits in
+    // `pending_executions` and is dropped again if its block is reorged out. 0 books at once
+    #[serde(default = "default_confirmations")]
+    pub confirmations: u64,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_backfill_blocks() -> u64 {
+    5_000
+}
+
+fn default_chunk_size() -> u64 {
+    2_000
+}
+
+fn default_confirmations() -> u64 {
+    30
+}
+
+impl Default for ExecutorEventSettings {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            backfill_blocks: default_backfill_blocks(),
+            chunk_size: default_chunk_size(),
+            confirmations: default_confirmations(),
+        }
+    }
+}
+
+// Ledger row for an executor event, None for events that carry no PnL
+pub fn execution_record(event: &FlashLoanArbitrageEvents, meta: &LogMeta) -> Option<ExecutionRecord> {
+    let (status, token0, token1, amount0, amount1, profit, reason) = match event {
+        FlashLoanArbitrageEvents::ArbitrageExecutedFilter(e) => {
+            ("executed", e.token_0, e.token_1, e.amount_0, e.amount_1, e.profit, None)
+        }
+        // The failing pool is kept as token0; nothing was earned
+        FlashLoanArbitrageEvents::FlashLoanFailedFilter(e) => (
+            "failed",
+            e.pool,
+            Address::zero(),
+            e.amount_0,
+            e.amount_1,
+            U256::zero(),
+            Some(e.reason.clone()),
+        ),
+        _ => return None,
+    };
+    Some(ExecutionRecord {
+        block_number: meta.block_number.as_u64(),
+        log_index: meta.log_index.as_u64(),
+        tx_hash: meta.transaction_hash,
+        status: status.to_string(),
+        token0,
+        token1,
+        amount0,
+        amount1,
+        profit,
+        reason,
+    })
+}
+
+// Follows the executor's own events (ArbitrageExecuted, FlashLoanFailed, ...) through the
+// abigen bindings and books them in the store; this ledger, not receipt parsing, is what
+// realized PnL and payout accrual are based on. The bundled ABI has no ProfitWithdrawn
+// event, withdrawals are plain token transfers. Events are booked once `confirmations` deep,
+// which also moves their journal entries from submitted to included (or reorged)
+#[derive(Debug, Clone)]
+pub struct ExecutorEvents<M> {
+    provider: Arc<M>,
+    contract: FlashLoanArbitrage<M>,
+    store: Store,
+    settings: ExecutorEventSettings,
+    treasury: Option<Treasury<M>>,
+}
+
+impl<M: Middleware + 'static> ExecutorEvents<M> {
+    pub fn new(provider: Arc<M>, executor: Address, store: Store, settings: ExecutorEventSettings) -> Self {
+        Self {
+            contract: FlashLoanArbitrage::new(executor, provider.clone()),
+            provider,
+            store,
+            settings,
+            treasury: None,
+        }
+    }
+
+    // Realized profits accrue payee shares as their events are booked
+    pub fn with_treasury(mut self, treasury: Option<Treasury<M>>) -> Self {
+        self.treasury = treasury;
+        self
+    }
+
+    pub async fn run(&self) -> Result<()> {
+        let head = self.backfill().await?;
+        self.finalize().await?;
+        info!("Executor event listener caught up to block {}, following live events", head);
+
+        let events = self.contract.events().from_block(head + 1);
+        let mut stream = events
+            .stream_with_meta()
+            .await
+            .map_err(|e| anyhow!("Failed to watch executor events: {e}"))?;
+        let mut finality = tokio::time::interval(FINALITY_POLL);
+
+        loop {
+            tokio::select! {
+                event = stream.next() => match event {
+                    Some(Ok((event, meta))) => {
+                        self.apply(&event, &meta)?;
+                        if self.settings.confirmations == 0 {
+                            self.store.set_last_indexed_block(CHECKPOINT, meta.block_number.as_u64())?;
+                        }
+                    }
+                    Some(Err(e)) => warn!("Undecodable executor event: {:?}", e),
+                    None => break,
+                },
+                _ = finality.tick(), if self.settings.confirmations > 0 => {
+                    if let Err(e) = self.finalize().await {
+                        warn!("Executor event finality check failed: {:?}", e);
+                    }
+                }
+            }
+        }
+
+        Err(anyhow!("Executor event stream ended"))
+    }
+
+    // Books everything between the checkpoint and the current head; returns the head
+    async fn backfill(&self) -> Result<u64> {
+        let head = self.head().await?;
+        let from = match self.store.last_indexed_block(CHECKPOINT)? {
+            Some(last) => last + 1,
+            None => head.saturating_sub(self.settings.backfill_blocks),
+        };
+        self.apply_range(from, head, true).await?;
+        Ok(head)
+    }
+
+    async fn head(&self) -> Result<u64> {
+        Ok(self
+            .provider
+            .get_block_number()
+            .await
+            .map_err(|e| anyhow!("Failed to fetch block number: {e}"))?
+            .as_u64())
+    }
+
+    // Applies the events of `from..=to` chunk by chunk, checkpointing final chunks when asked
+    async fn apply_range(&self, mut from: u64, to: u64, checkpoint: bool) -> Result<()> {
+        while from <= to {
+            let chunk_end = (from + self.settings.chunk_size.max(1) - 1).min(to);
+            let events = self
+                .contract
+                .events()
+                .from_block(from)
+                .to_block(chunk_end)
+                .query_with_meta()
+                .await
+                .map_err(|e| anyhow!("Failed to query executor events {from}..{chunk_end}: {e}"))?;
+            for (event, meta) in &events {
+                self.apply(event, meta)?;
+            }
+            // With a confirmation depth the checkpoint is left to `finalize`
+            if checkpoint && self.settings.confirmations == 0 {
+                self.store.set_last_indexed_block(CHECKPOINT, chunk_end)?;
+            }
+            from = chunk_end + 1;
+        }
+        Ok(())
+    }
+
+    // Books pending events that reached the confirmation depth in the block they were seen
+    // in, and drops those whose block was reorged out; the range from the earliest dropped
+    // one is read again so re-included events wait anew. Returns (booked, dropped)
+    pub async fn finalize(&self) -> Result<(usize, usize)> {
+        let head = self.head().await?;
+        let Some(final_block) = head.checked_sub(self.settings.confirmations) else {
+            return Ok((0, 0));
+        };
+
+        let mut canonical: HashMap<u64, Option<H256>> = HashMap::new();
+        let (mut booked, mut dropped) = (0, 0);
+        let mut reread_from: Option<u64> = None;
+        for (record, block_hash) in self.store.pending_executions()? {
+            if record.block_number > final_block {
+                break;
+            }
+            let hash = match canonical.get(&record.block_number) {
+                Some(hash) => *hash,
+                None => {
+                    let hash = self
+                        .provider
+                        .get_block(record.block_number)
+                        .await
+                        .map_err(|e| anyhow!("Failed to fetch block {}: {e}", record.block_number))?
+                        .and_then(|block| block.hash);
+                    canonical.insert(record.block_number, hash);
+                    hash
+                }
+            };
+            self.store.remove_pending_execution(record.tx_hash, record.log_index)?;
+            if hash == Some(block_hash) {
+                booked += self.book(&record)? as usize;
+                continue;
+            }
+
+            dropped += 1;
+            EXECUTOR_EVENTS_REORGED_TOTAL.inc();
+            warn!(
+                "Executor event in {:?} at block {} was reorged out (seen in {:?}, canonical {:?})",
+                record.tx_hash, record.block_number, block_hash, hash
+            );
+            self.store.set_opportunity_status_by_tx(record.tx_hash, &["submitted"], "reorged")?;
+            reread_from = Some(reread_from.map_or(record.block_number, |from| from.min(record.block_number)));
+        }
+
+        if let Some(from) = reread_from {
+            self.apply_range(from, head, false).await?;
+        }
+        let checkpoint = reread_from.map_or(final_block, |from| final_block.min(from.saturating_sub(1)));
+        if self.store.last_indexed_block(CHECKPOINT)?.is_none_or(|last| checkpoint > last) {
+            self.store.set_last_indexed_block(CHECKPOINT, checkpoint)?;
+        }
+        Ok((booked, dropped))
+    }
+
+    fn apply(&self, event: &FlashLoanArbitrageEvents, meta: &LogMeta) -> Result<()> {
+        let Some(record) = execution_record(event, meta) else {
+            match event {
+                FlashLoanArbitrageEvents::BundleSubmittedFilter(e) => {
+                    EXECUTOR_EVENTS_TOTAL.with_label_values(&["bundle_submitted"]).inc();
+                    debug!(
+                        "Executor bundle {:?} submitted for block {}",
+                        H256::from(e.bundle_hash),
+                        e.target_block
+                    );
+                }
+                FlashLoanArbitrageEvents::OwnershipTransferredFilter(e) => {
+                    EXECUTOR_EVENTS_TOTAL.with_label_values(&["ownership_transferred"]).inc();
+                    warn!("Executor ownership moved from {:?} to {:?}", e.previous_owner, e.new_owner);
+                }
+                _ => {}
+            }
+            return Ok(());
+        };
+
+        if self.settings.confirmations > 0 {
+            debug!("Executor event in {:?} pending finality at block {}", record.tx_hash, record.block_number);
+            return self.store.upsert_pending_execution(&record, meta.block_hash);
+        }
+        self.book(&record)?;
+        Ok(())
+    }
+
+    // Returns false when the event was already booked
+    fn book(&self, record: &ExecutionRecord) -> Result<bool> {
+        // Events are booked once; a replayed range must not accrue twice
+        if !self.store.insert_execution(record)? {
+            return Ok(false);
+        }
+        EXECUTOR_EVENTS_TOTAL.with_label_values(&[record.status.as_str()]).inc();
+        match &record.reason {
+            None => {
+                info!(
+                    "Executor realized {} of {:?} in {:?}",
+                    record.profit, record.token0, record.tx_hash
+                );
+                self.store
+                    .set_opportunity_status_by_tx(record.tx_hash, &["submitted", "reorged"], "included")?;
+                if let Some(treasury) = &self.treasury {
+                    treasury.record_profit(record.token0, record.profit)?;
+                }
+            }
+            Some(reason) => warn!(
+                "Executor flash loan from {:?} failed in {:?}: {}",
+                record.token0, record.tx_hash, reason
+            ),
+        }
+        Ok(true)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{address, quantity, MockChain};
+    use crate::store::OpportunityRecord;
+    use crate::bindings::{ArbitrageExecutedFilter, FlashLoanFailedFilter};
+    use ethers::types::{Block, U64};
+    use serde_json::json;
+
+    fn meta(log_index: u64) -> LogMeta {
+        LogMeta {
+            address: Address::from_low_u64_be(99),
+            block_number: U64::from(100),
+            block_hash: H256::from_low_u64_be(1),
+            transaction_hash: H256::from_low_u64_be(2),
+            transaction_index: U64::zero(),
+            log_index: U256::from(log_index),
+        }
+    }
+
+    #[test]
+    fn test_events_book_realized_pnl_once() {
+        let store = Store::open_in_memory().unwrap();
+        let (usdc, wmatic) = (Address::from_low_u64_be(10), Address::from_low_u64_be(11));
+        let executed = FlashLoanArbitrageEvents::ArbitrageExecutedFilter(ArbitrageExecutedFilter {
+            token_0: usdc,
+            token_1: wmatic,
+            amount_0: U256::from(1_000u64),
+            amount_1: U256::zero(),
+            profit: U256::from(7u64),
+            bundle_hash: [0u8; 32],
+        });
+        let failed = FlashLoanArbitrageEvents::FlashLoanFailedFilter(FlashLoanFailedFilter {
+            pool: Address::from_low_u64_be(5),
+            amount_0: U256::from(1_000u64),
+            amount_1: U256::zero(),
+            reason: "insufficient output".to_string(),
+        });
+
+        let record = execution_record(&executed, &meta(0)).unwrap();
+        assert!(store.insert_execution(&record).unwrap());
+        assert!(!store.insert_execution(&record).unwrap());
+        let failure = execution_record(&failed, &meta(1)).unwrap();
+        assert_eq!(failure.reason.as_deref(), Some("insufficient output"));
+        assert!(store.insert_execution(&failure).unwrap());
+
+        // Only executed events count towards PnL
+        assert_eq!(store.realized_profit(0).unwrap(), vec![(usdc, U256::from(7u64))]);
+        assert!(store.realized_profit(101).unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_events_are_booked_at_depth_and_dropped_when_reorged_out() {
+        let chain = MockChain::new();
+        let store = Store::open_in_memory().unwrap();
+        let events = ExecutorEvents::new(chain.provider(), address(99), store.clone(), ExecutorEventSettings::default());
+        let (kept, reorged) = (H256::from_low_u64_be(2), H256::from_low_u64_be(3));
+        let record = |block_number: u64, tx_hash: H256| ExecutionRecord {
+            block_number,
+            log_index: 0,
+            tx_hash,
+            status: "executed".to_string(),
+            token0: address(10),
+            token1: address(11),
+            amount0: U256::from(1_000u64),
+            amount1: U256::zero(),
+            profit: U256::from(7u64),
+            reason: None,
+        };
+        store.upsert_pending_execution(&record(100, kept), H256::repeat_byte(0xa1)).unwrap();
+        store.upsert_pending_execution(&record(101, reorged), H256::repeat_byte(0xa2)).unwrap();
+        store.upsert_pending_execution(&record(120, H256::from_low_u64_be(4)), H256::repeat_byte(0xa3)).unwrap();
+        for tx_hash in [kept, reorged] {
+            let id = store
+                .record_opportunity(&OpportunityRecord {
+                    detected_at: 1_700_000_000,
+                    block_number: 99,
+                    class: "backrun".to_string(),
+                    token0: address(10),
+                    token1: address(11),
+                    expected_profit: U256::from(7u64),
+                    min_profit: U256::zero(),
+                    status: "detected".to_string(),
+                    tx_hash: None,
+                    opportunity_id: None,
+                })
+                .unwrap();
+            store.set_opportunity_status(id, "submitted", Some(tx_hash)).unwrap();
+        }
+
+        // Not deep enough yet: nothing is booked or fetched
+        chain.script(vec![quantity(129u64)]);
+        assert_eq!(events.finalize().await.unwrap(), (0, 0));
+        assert!(store.realized_profit(0).unwrap().is_empty());
+
+        // Block 101 now has another hash; its range is read again and comes back empty
+        let block = |hash: u8| {
+            serde_json::to_value(Block::<H256> {
+                hash: Some(H256::repeat_byte(hash)),
+                ..Default::default()
+            })
+            .unwrap()
+        };
+        chain.script(vec![quantity(131u64), block(0xa1), block(0xb2), json!([])]);
+        assert_eq!(events.finalize().await.unwrap(), (1, 1));
+        assert_eq!(store.realized_profit(0).unwrap(), vec![(address(10), U256::from(7u64))]);
+        assert_eq!(store.pending_executions().unwrap().len(), 1);
+        assert_eq!(store.last_indexed_block(CHECKPOINT).unwrap(), Some(100));
+        let statuses: Vec<String> = store.opportunities_since(0, None).unwrap().into_iter().map(|o| o.status).collect();
+        assert_eq!(statuses, vec!["included", "reorged"]);
+    }
+}
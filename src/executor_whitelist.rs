@@ -0,0 +1,160 @@
+// src/executor_whitelist.rs
+use anyhow::{anyhow, Result};
+use ethers::{
+    abi::{Abi, ParamType},
+    contract::Contract,
+    providers::Middleware,
+    types::Address,
+};
+use log::info;
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+use crate::metrics::EXECUTOR_WHITELIST_DENIED;
+
+// An executor that only trades through whitelisted routers and tokens reverts every other
+// route on-chain. Its whitelist is read once at startup and the route search skips pools
+// outside it; a view the executor ABI lacks leaves that side unrestricted
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExecutorWhitelistSettings {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    // (address router) -> bool
+    #[serde(default = "default_router_method")]
+    pub router_method: String,
+    // (address token) -> bool
+    #[serde(default = "default_token_method")]
+    pub token_method: String,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_router_method() -> String {
+    "whitelistedRouters".to_string()
+}
+
+fn default_token_method() -> String {
+    "whitelistedTokens".to_string()
+}
+
+impl Default for ExecutorWhitelistSettings {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            router_method: default_router_method(),
+            token_method: default_token_method(),
+        }
+    }
+}
+
+// The routers and tokens the executor accepts; None where it accepts anything. Only the
+// candidates asked at startup can be allowed, so a token first seen later counts as refused
+#[derive(Debug, Clone, Default)]
+pub struct ExecutorWhitelist {
+    routers: Option<HashSet<Address>>,
+    tokens: Option<HashSet<Address>>,
+}
+
+impl ExecutorWhitelist {
+    // Asks `executor` about every candidate router and token
+    pub async fn load<M: Middleware + 'static>(
+        client: Arc<M>,
+        executor: Address,
+        abi: Abi,
+        settings: &ExecutorWhitelistSettings,
+        routers: &[Address],
+        tokens: &[Address],
+    ) -> Result<Self> {
+        if !settings.enabled {
+            return Ok(Self::default());
+        }
+        let contract = Contract::new(executor, abi, client);
+        let whitelist = Self {
+            routers: allowed(&contract, &settings.router_method, routers).await?,
+            tokens: allowed(&contract, &settings.token_method, tokens).await?,
+        };
+        let sides = [("router", &whitelist.routers, routers), ("token", &whitelist.tokens, tokens)];
+        for (kind, list, candidates) in sides {
+            let denied = list.as_ref().map_or(0, |list| candidates.iter().filter(|c| !list.contains(c)).count());
+            EXECUTOR_WHITELIST_DENIED.with_label_values(&[kind]).set(denied as i64);
+            match list {
+                Some(list) => info!("Executor whitelists {} {}s, refuses {}", list.len(), kind, denied),
+                None => info!("Executor has no {} whitelist", kind),
+            }
+        }
+        Ok(whitelist)
+    }
+
+    pub fn allows_router(&self, router: &Address) -> bool {
+        self.routers.as_ref().is_none_or(|routers| routers.contains(router))
+    }
+
+    pub fn allows_token(&self, token: &Address) -> bool {
+        self.tokens.as_ref().is_none_or(|tokens| tokens.contains(token))
+    }
+
+    pub fn allows_pool(&self, router: &Address, token0: &Address, token1: &Address) -> bool {
+        self.allows_router(router) && self.allows_token(token0) && self.allows_token(token1)
+    }
+
+    pub fn allows_route(&self, routers: &[Address], tokens: &[Address]) -> bool {
+        routers.iter().all(|router| self.allows_router(router)) && tokens.iter().all(|token| self.allows_token(token))
+    }
+}
+
+// The candidates `method(address) -> bool` answers true for; None if the ABI lacks that view
+async fn allowed<M: Middleware + 'static>(
+    contract: &Contract<M>,
+    method: &str,
+    candidates: &[Address],
+) -> Result<Option<HashSet<Address>>> {
+    let is_view = contract.abi().function(method).is_ok_and(|function| {
+        matches!(function.inputs.as_slice(), [input] if input.kind == ParamType::Address)
+            && matches!(function.outputs.as_slice(), [output] if output.kind == ParamType::Bool)
+    });
+    if !is_view {
+        return Ok(None);
+    }
+    let mut allowed = HashSet::new();
+    for &candidate in candidates {
+        let listed: bool = contract
+            .method(method, candidate)?
+            .call()
+            .await
+            .map_err(|e| anyhow!("{}({:?}) failed: {e}", method, candidate))?;
+        if listed {
+            allowed.insert(candidate);
+        }
+    }
+    Ok(Some(allowed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{address, call_output, MockChain};
+    use ethers::abi::{parse_abi, Token};
+
+    #[tokio::test]
+    async fn test_routes_outside_the_router_whitelist_are_refused() {
+        let chain = MockChain::new();
+        let abi = parse_abi(&["function whitelistedRouters(address router) view returns (bool)"]).unwrap();
+        let (quickswap, sushiswap, token0, token1) = (address(1), address(2), address(10), address(11));
+        chain.script(vec![call_output(&[Token::Bool(true)]), call_output(&[Token::Bool(false)])]);
+
+        let settings = ExecutorWhitelistSettings::default();
+        let whitelist =
+            ExecutorWhitelist::load(chain.provider(), address(9), abi, &settings, &[quickswap, sushiswap], &[token0])
+                .await
+                .unwrap();
+
+        assert!(whitelist.allows_pool(&quickswap, &token0, &token1));
+        assert!(!whitelist.allows_pool(&sushiswap, &token0, &token1));
+        // No token view in the ABI: any token goes
+        assert!(whitelist.allows_route(&[quickswap], &[token0, token1, address(12)]));
+        assert!(ExecutorWhitelist::default().allows_route(&[sushiswap], &[token1]));
+    }
+}
@@ -0,0 +1,187 @@
+// src/exposure.rs
+use ethers::types::{Address, U256};
+use log::debug;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::metrics::{IN_FLIGHT_NOTIONAL_USD, IN_FLIGHT_TOTAL};
+use crate::token_registry::{format_units, TokenRegistry};
+
+// While a flash-loan execution is pending its borrowed notional is open interest: a trade
+// that lands badly can lose up to what it moved. Every strategy opens its executions here,
+// and past max_in_flight_usd of simultaneous notional the next one is refused
+#[derive(Debug, Clone, Deserialize)]
+pub struct ExposureSettings {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default = "default_max_in_flight_usd")]
+    pub max_in_flight_usd: f64,
+    // Borrowable tokens' USD value per whole token; err high. Unpriced tokens are refused
+    #[serde(default)]
+    pub prices_usd: HashMap<Address, f64>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_max_in_flight_usd() -> f64 {
+    250_000.0
+}
+
+impl Default for ExposureSettings {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            max_in_flight_usd: default_max_in_flight_usd(),
+            prices_usd: HashMap::new(),
+        }
+    }
+}
+
+type Open = HashMap<u64, (&'static str, f64)>;
+
+#[derive(Debug, Clone, Default)]
+pub struct ExposureLimit {
+    settings: ExposureSettings,
+    tokens: Arc<TokenRegistry>,
+    open: Arc<Mutex<Open>>,
+    next_id: Arc<AtomicU64>,
+}
+
+// Why an execution was not opened, as its journal status
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ExposureRefused(pub &'static str);
+
+impl fmt::Display for ExposureRefused {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "in-flight exposure refused: {}", self.0)
+    }
+}
+
+impl std::error::Error for ExposureRefused {}
+
+// One pending execution's notional, closed when dropped
+#[derive(Debug)]
+pub struct InFlight {
+    id: u64,
+    open: Option<Arc<Mutex<Open>>>,
+    legs: Vec<(Address, U256)>,
+}
+
+impl InFlight {
+    // The (token, amount) legs booked, which the execution must borrow exactly
+    pub fn borrowed(&self) -> &[(Address, U256)] {
+        &self.legs
+    }
+}
+
+impl Drop for InFlight {
+    fn drop(&mut self) {
+        if let Some(open) = self.open.take() {
+            let mut open = open.lock().unwrap();
+            open.remove(&self.id);
+            IN_FLIGHT_NOTIONAL_USD.set(open.values().map(|(_, usd)| usd).sum());
+        }
+    }
+}
+
+impl ExposureLimit {
+    pub fn new(settings: ExposureSettings, tokens: Arc<TokenRegistry>) -> Self {
+        Self {
+            settings,
+            tokens,
+            ..Default::default()
+        }
+    }
+
+    // USD value of `amount` of `token`, None without a price or decimals for it
+    fn value_usd(&self, token: &Address, amount: U256) -> Option<f64> {
+        let price = self.settings.prices_usd.get(token)?;
+        let decimals = self.tokens.get(token)?.decimals;
+        Some(format_units(amount, decimals).parse::<f64>().ok()? * price)
+    }
+
+    // Opens an execution borrowing `legs` as (token, amount); refused when its notional
+    // cannot be priced or would take the total past the cap
+    pub fn open(&self, strategy: &'static str, legs: &[(Address, U256)]) -> Result<InFlight, ExposureRefused> {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        if !self.settings.enabled {
+            return Ok(InFlight { id, open: None, legs: legs.to_vec() });
+        }
+        let mut notional = 0.0;
+        for (token, amount) in legs.iter().filter(|(_, amount)| !amount.is_zero()) {
+            let Some(usd) = self.value_usd(token, *amount) else {
+                debug!("{} execution refused: no USD price for {:?}", strategy, token);
+                IN_FLIGHT_TOTAL.with_label_values(&["unpriced"]).inc();
+                return Err(ExposureRefused("unpriced"));
+            };
+            notional += usd;
+        }
+
+        let mut open = self.open.lock().unwrap();
+        let in_flight: f64 = open.values().map(|(_, usd)| usd).sum();
+        if in_flight + notional > self.settings.max_in_flight_usd {
+            debug!(
+                "{} execution of ${:.0} refused: ${:.0} of {} open executions already in flight",
+                strategy,
+                notional,
+                in_flight,
+                open.len()
+            );
+            IN_FLIGHT_TOTAL.with_label_values(&["capped"]).inc();
+            return Err(ExposureRefused("exposure_cap"));
+        }
+        open.insert(id, (strategy, notional));
+        IN_FLIGHT_NOTIONAL_USD.set(in_flight + notional);
+        IN_FLIGHT_TOTAL.with_label_values(&["opened"]).inc();
+        Ok(InFlight {
+            id,
+            open: Some(self.open.clone()),
+            legs: legs.to_vec(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::address;
+    use crate::token_registry::TokenInfo;
+
+    #[test]
+    fn test_notional_past_the_cap_waits_for_an_open_execution_to_close() {
+        let (usdc, wmatic, unknown) = (address(10), address(11), address(12));
+        let tokens = TokenRegistry::new();
+        for (token, decimals) in [(usdc, 6), (wmatic, 18), (unknown, 18)] {
+            let info = TokenInfo {
+                address: token,
+                name: String::new(),
+                symbol: String::new(),
+                decimals,
+                tags: Vec::new(),
+            };
+            tokens.insert(info).unwrap();
+        }
+        let settings = ExposureSettings {
+            max_in_flight_usd: 1_000.0,
+            prices_usd: HashMap::from([(usdc, 1.0), (wmatic, 0.5)]),
+            ..Default::default()
+        };
+        let exposure = ExposureLimit::new(settings, Arc::new(tokens));
+
+        // $600 in USDC, then $400 in WMATIC: exactly at the cap
+        let first = exposure.open("cycle", &[(usdc, U256::from(600_000_000u64))]).unwrap();
+        let second = exposure.open("sandwich", &[(wmatic, U256::exp10(18) * 800)]).unwrap();
+        assert_eq!(exposure.open("cycle", &[(usdc, U256::from(1_000_000u64))]).unwrap_err().0, "exposure_cap");
+        assert_eq!(exposure.open("cycle", &[(unknown, U256::one())]).unwrap_err().0, "unpriced");
+
+        assert_eq!(first.borrowed(), &[(usdc, U256::from(600_000_000u64))]);
+        drop(first);
+        assert!(exposure.open("cycle", &[(usdc, U256::from(500_000_000u64)), (wmatic, U256::zero())]).is_ok());
+        drop(second);
+    }
+}
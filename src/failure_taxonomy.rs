@@ -0,0 +1,196 @@
+// src/failure_taxonomy.rs
+use crate::metrics::{FAILURES_TOTAL, OPPORTUNITIES_TOTAL};
+use crate::post_mortem::{MissReason, MissedBundle};
+
+// Fixed taxonomy of why an opportunity did not make money, for tuning: every journal status
+// of a trade that was not sent maps to one skip reason, every failed execution to one
+// failure reason, so dashboards can compare them without knowing each detector's statuses
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkipReason {
+    BelowThreshold,
+    // Profit under the route's bare gas and flash costs, not just under the margin
+    EvNegative,
+    GasTooHigh,
+    Stale,
+    Blacklist,
+    // Another searcher, detection or strategy is on the same spread
+    Competition,
+    // Switched off by configuration, the execution mode or the operator
+    Held,
+}
+
+impl SkipReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SkipReason::BelowThreshold => "below_threshold",
+            SkipReason::EvNegative => "ev_negative",
+            SkipReason::GasTooHigh => "gas_too_high",
+            SkipReason::Stale => "stale",
+            SkipReason::Blacklist => "blacklist",
+            SkipReason::Competition => "competition",
+            SkipReason::Held => "held",
+        }
+    }
+
+    // None for statuses of opportunities that went on, or that failed in execution
+    pub fn from_status(status: &str) -> Option<Self> {
+        Some(match status {
+            "below_threshold" => SkipReason::BelowThreshold,
+            "ev_negative" => SkipReason::EvNegative,
+            "gas_ceiling" => SkipReason::GasTooHigh,
+            "stale" | "stale_at_commit" => SkipReason::Stale,
+            "blacklisted" | "not_whitelisted" => SkipReason::Blacklist,
+            "toxic_flow" | "superseded" | "venue_taken" => SkipReason::Competition,
+            "disabled" | "mode_filtered" | "cooldown" | "paused" | "read_only" | "exposure_cap" | "unpriced" => {
+                SkipReason::Held
+            }
+            _ => return None,
+        })
+    }
+}
+
+// Revert reasons grouped by what would have avoided them
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RevertClass {
+    // Output below a minimum, or the pool invariant broken by a moved price
+    Slippage,
+    Liquidity,
+    // The executor's minProfit or repayment check
+    Unprofitable,
+    Deadline,
+    Transfer,
+    OutOfGas,
+    // Reverted without a reason we could read
+    Unknown,
+}
+
+impl RevertClass {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RevertClass::Slippage => "slippage",
+            RevertClass::Liquidity => "liquidity",
+            RevertClass::Unprofitable => "unprofitable",
+            RevertClass::Deadline => "deadline",
+            RevertClass::Transfer => "transfer",
+            RevertClass::OutOfGas => "out_of_gas",
+            RevertClass::Unknown => "unknown",
+        }
+    }
+
+    // From a revert reason or an error message carrying one
+    pub fn classify(reason: &str) -> Self {
+        let reason = reason.to_ascii_lowercase();
+        let has = |needles: &[&str]| needles.iter().any(|needle| reason.contains(needle));
+        if has(&["out of gas", "intrinsic gas"]) {
+            RevertClass::OutOfGas
+        } else if has(&["deadline", "expired", "too far"]) {
+            RevertClass::Deadline
+        } else if has(&["insufficient_output", "too little received", "uniswapv2: k", "reverted: k", "slippage"]) {
+            RevertClass::Slippage
+        } else if has(&["insufficient_liquidity", "insufficient liquidity"]) {
+            RevertClass::Liquidity
+        } else if has(&["profit", "repay"]) {
+            RevertClass::Unprofitable
+        } else if has(&["stf", "transfer", "allowance", "balance"]) {
+            RevertClass::Transfer
+        } else {
+            RevertClass::Unknown
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FailureReason {
+    Reverted(RevertClass),
+    // Another transaction took the spread in the targeted blocks
+    Outbid,
+    // Never mined: the victim did not land, the bundle or tx was dropped
+    Dropped,
+    // The spread was gone by the time the trade landed
+    Stale,
+    // Failed before reaching the chain: RPC, signing, simulation
+    Error,
+}
+
+impl FailureReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FailureReason::Reverted(_) => "revert",
+            FailureReason::Outbid => "outbid",
+            FailureReason::Dropped => "dropped",
+            FailureReason::Stale => "stale",
+            FailureReason::Error => "error",
+        }
+    }
+
+    pub fn detail(&self) -> &'static str {
+        match self {
+            FailureReason::Reverted(class) => class.as_str(),
+            _ => "none",
+        }
+    }
+
+    pub fn from_error(error: &anyhow::Error) -> Self {
+        if let Some(missed) = error.downcast_ref::<MissedBundle>() {
+            return match missed.reason {
+                MissReason::Outbid => FailureReason::Outbid,
+                MissReason::VictimDropped => FailureReason::Dropped,
+                MissReason::StaleSpread => FailureReason::Stale,
+            };
+        }
+        let message = format!("{:#}", error);
+        if message.contains("revert") {
+            FailureReason::Reverted(RevertClass::classify(&message))
+        } else if message.contains("No receipt") || message.contains("dropped") {
+            FailureReason::Dropped
+        } else {
+            FailureReason::Error
+        }
+    }
+}
+
+// Counts an opportunity's journal status, and its skip reason when it was not sent
+pub fn record_outcome(class: &str, status: &str) {
+    OPPORTUNITIES_TOTAL.with_label_values(&[class, status]).inc();
+    if let Some(reason) = SkipReason::from_status(status) {
+        FAILURES_TOTAL.with_label_values(&[class, "skipped", reason.as_str(), "none"]).inc();
+    }
+}
+
+pub fn record_failure(class: &str, reason: FailureReason) {
+    FAILURES_TOTAL.with_label_values(&[class, "failed", reason.as_str(), reason.detail()]).inc();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::anyhow;
+
+    #[test]
+    fn test_statuses_and_errors_map_onto_the_fixed_taxonomy() {
+        assert_eq!(SkipReason::from_status("gas_ceiling"), Some(SkipReason::GasTooHigh));
+        assert_eq!(SkipReason::from_status("stale_at_commit"), Some(SkipReason::Stale));
+        assert_eq!(SkipReason::from_status("cooldown"), Some(SkipReason::Held));
+        assert_eq!(SkipReason::from_status("submitted"), None);
+        assert_eq!(SkipReason::from_status("outbid"), None);
+
+        let missed = MissedBundle {
+            first_block: 10,
+            last_block: 12,
+            reason: MissReason::Outbid,
+            victim_block: Some(10),
+            competitors: Vec::new(),
+        };
+        assert_eq!(FailureReason::from_error(&anyhow::Error::new(missed)), FailureReason::Outbid);
+        let reverted = anyhow!("(code: 3, message: execution reverted: UniswapV2: K)").context("preflight");
+        assert_eq!(FailureReason::from_error(&reverted), FailureReason::Reverted(RevertClass::Slippage));
+        let deadline = anyhow!("execution reverted: Deadline block passed");
+        assert_eq!(FailureReason::from_error(&deadline).detail(), "deadline");
+        assert_eq!(FailureReason::from_error(&anyhow!("No receipt returned")), FailureReason::Dropped);
+        assert_eq!(FailureReason::from_error(&anyhow!("connection reset")), FailureReason::Error);
+
+        record_outcome("block_cycle", "below_threshold");
+        let skipped = FAILURES_TOTAL.with_label_values(&["block_cycle", "skipped", "below_threshold", "none"]);
+        assert!(skipped.get() >= 1);
+    }
+}
@@ -1,50 +1,266 @@
-    async fn execute_multi_leg_arbitrage(
-        &self,
-        opportunity: &ArbitrageOpportunity
-    ) -> Result<TransactionReceipt> {
-        // Validate arbitrage route
-        if opportunity.routers.is_empty() {
-            bail!("No arbitrage routes found");
+// src/fastlane_integration.rs
+use anyhow::{anyhow, Result};
+use ethers::{
+    abi::{encode, Token},
+    providers::{Middleware, Provider},
+    signers::{LocalWallet, Signer},
+    types::{Address, Bytes, Signature, H256, U256, U64},
+    utils::keccak256,
+};
+use std::sync::Arc;
+
+use crate::rpc_usage::MeteredWs;
+
+// Gas limit granted to the solver call inside the Atlas metacall
+const SOLVER_GAS_LIMIT: u64 = 1_500_000;
+
+const SOLVER_OPERATION_TYPE: &str = "SolverOperation(address from,address to,uint256 value,uint256 gas,uint256 maxFeePerGas,uint256 deadline,address solver,address control,bytes32 userOpHash,address bidToken,uint256 bidAmount,bytes data)";
+const DOMAIN_TYPE: &str = "EIP712Domain(string name,string version,uint256 chainId,address verifyingContract)";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SolverOperation {
+    pub from: Address,
+    pub to: Address,
+    pub value: U256,
+    pub gas: U256,
+    pub max_fee_per_gas: U256,
+    pub deadline: U256,
+    pub solver: Address,
+    pub control: Address,
+    pub user_op_hash: H256,
+    pub bid_token: Address,
+    pub bid_amount: U256,
+    pub data: Bytes,
+}
+
+impl SolverOperation {
+    fn struct_hash(&self) -> H256 {
+        H256::from(keccak256(encode(&[
+            Token::FixedBytes(keccak256(SOLVER_OPERATION_TYPE).to_vec()),
+            Token::Address(self.from),
+            Token::Address(self.to),
+            Token::Uint(self.value),
+            Token::Uint(self.gas),
+            Token::Uint(self.max_fee_per_gas),
+            Token::Uint(self.deadline),
+            Token::Address(self.solver),
+            Token::Address(self.control),
+            Token::FixedBytes(self.user_op_hash.as_bytes().to_vec()),
+            Token::Address(self.bid_token),
+            Token::Uint(self.bid_amount),
+            Token::FixedBytes(keccak256(&self.data).to_vec()),
+        ])))
+    }
+
+    // EIP-712 digest the searcher key signs
+    pub fn digest(&self, domain_separator: H256) -> H256 {
+        let mut message = Vec::with_capacity(66);
+        message.extend_from_slice(b"\x19\x01");
+        message.extend_from_slice(domain_separator.as_bytes());
+        message.extend_from_slice(self.struct_hash().as_bytes());
+        H256::from(keccak256(message))
+    }
+
+    pub fn sign(&self, searcher: &LocalWallet, domain_separator: H256) -> Result<Signature> {
+        if self.from != searcher.address() {
+            return Err(anyhow!(
+                "SolverOperation from {:?} cannot be signed by searcher {:?}",
+                self.from,
+                searcher.address()
+            ));
+        }
+        Ok(searcher.sign_hash(self.digest(domain_separator))?)
+    }
+}
+
+pub fn domain_separator(chain_id: u64, verifying_contract: Address) -> H256 {
+    H256::from(keccak256(encode(&[
+        Token::FixedBytes(keccak256(DOMAIN_TYPE).to_vec()),
+        Token::FixedBytes(keccak256("AtlasVerification").to_vec()),
+        Token::FixedBytes(keccak256("1.0").to_vec()),
+        Token::Uint(chain_id.into()),
+        Token::Address(verifying_contract),
+    ])))
+}
+
+#[derive(Debug, Clone)]
+pub struct FastLaneBundle {
+    pub solver_op: SolverOperation,
+    pub signature: Signature,
+    pub target_block: U64,
+}
+
+// Builds and signs SolverOperations. Two keys are involved:
+//   searcher:    hot key, signs SolverOperations and pays gas
+//   beneficiary: profit wallet, only its address is ever known to the bot
+#[derive(Debug, Clone)]
+pub struct FastLaneClient<M = Provider<MeteredWs>> {
+    provider: Arc<M>,
+    searcher: LocalWallet,
+    beneficiary: Address,
+    fastlane_address: Address,
+    fastlane_sender_address: Address,
+    solver_address: Address,
+    max_delay_blocks: U256,
+    min_priority_fee: U256,
+}
+
+impl<M: Middleware + 'static> FastLaneClient<M> {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        provider: Arc<M>,
+        searcher: LocalWallet,
+        beneficiary: Address,
+        fastlane_address: Address,
+        fastlane_sender_address: Address,
+        solver_address: Address,
+        max_delay_blocks: U256,
+        min_priority_fee: U256,
+    ) -> Self {
+        Self {
+            provider,
+            searcher,
+            beneficiary,
+            fastlane_address,
+            fastlane_sender_address,
+            solver_address,
+            max_delay_blocks,
+            min_priority_fee,
         }
+    }
+
+    pub fn searcher_address(&self) -> Address {
+        self.searcher.address()
+    }
+
+    pub fn beneficiary(&self) -> Address {
+        self.beneficiary
+    }
+
+    // `data` is the executor calldata the solver contract runs inside the metacall, `bid`
+    // what we offer the auction for it
+    pub async fn create_fastlane_bundle(
+        &self,
+        data: Bytes,
+        target_block: U64,
+        bid: U256,
+    ) -> Result<FastLaneBundle> {
+        let base_fee = self.provider.get_gas_price().await
+            .map_err(|e| anyhow!("Failed to fetch gas price: {e}"))?;
+        let chain_id = self.searcher.chain_id();
+
+        let solver_op = SolverOperation {
+            from: self.searcher.address(),
+            to: self.fastlane_address,
+            value: U256::zero(),
+            gas: U256::from(SOLVER_GAS_LIMIT),
+            max_fee_per_gas: base_fee + self.min_priority_fee,
+            deadline: U256::from(target_block.as_u64()) + self.max_delay_blocks,
+            solver: self.solver_address,
+            control: self.fastlane_sender_address,
+            user_op_hash: H256::zero(),
+            bid_token: Address::zero(), // bids are paid in MATIC
+            bid_amount: bid,
+            data,
+        };
+
+        let signature = solver_op.sign(&self.searcher, domain_separator(chain_id, self.fastlane_address))?;
+
+        Ok(FastLaneBundle {
+            solver_op,
+            signature,
+            target_block,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{address, quantity, MockChain};
+    use ethers::types::RecoveryMessage;
+
+    #[tokio::test]
+    async fn test_bundle_prices_and_signs_the_solver_operation() {
+        let chain = MockChain::new();
+        let searcher: LocalWallet = "0000000000000000000000000000000000000000000000000000000000000001"
+            .parse::<LocalWallet>()
+            .unwrap()
+            .with_chain_id(137u64);
+        let (fastlane, sender, solver) = (address(9), address(6), address(5));
+        let client = FastLaneClient::new(
+            chain.provider(),
+            searcher.clone(),
+            address(7),
+            fastlane,
+            sender,
+            solver,
+            U256::from(3u64),
+            U256::from(30_000_000_000u64),
+        );
+        chain.script(vec![quantity(100_000_000_000u64)]);
+
+        let data = Bytes::from(vec![0x12, 0x34, 0x56, 0x78]);
+        let bundle = client
+            .create_fastlane_bundle(data.clone(), U64::from(50_000_000u64), U256::exp10(16))
+            .await
+            .unwrap();
+        chain.assert_request("eth_gasPrice", ());
+
+        assert_eq!(
+            bundle.solver_op,
+            SolverOperation {
+                from: searcher.address(),
+                to: fastlane,
+                value: U256::zero(),
+                gas: U256::from(SOLVER_GAS_LIMIT),
+                max_fee_per_gas: U256::from(130_000_000_000u64),
+                deadline: U256::from(50_000_003u64),
+                solver,
+                control: sender,
+                user_op_hash: H256::zero(),
+                bid_token: Address::zero(),
+                bid_amount: U256::exp10(16),
+                data,
+            }
+        );
+        assert_eq!(bundle.target_block, U64::from(50_000_000u64));
+        let digest = bundle.solver_op.digest(domain_separator(137, fastlane));
+        assert_eq!(bundle.signature.recover(RecoveryMessage::Hash(digest)).unwrap(), searcher.address());
+    }
+
+    #[test]
+    fn test_solver_op_is_signed_by_searcher_key_only() {
+        let searcher: LocalWallet = "0000000000000000000000000000000000000000000000000000000000000001"
+            .parse::<LocalWallet>()
+            .unwrap()
+            .with_chain_id(137u64);
+        let funds: LocalWallet = "0000000000000000000000000000000000000000000000000000000000000002"
+            .parse()
+            .unwrap();
+        let domain = domain_separator(137, Address::from_low_u64_be(9));
 
-        // Get current block for targeting
-        let current_block = self.provider.get_block(BlockNumber::Latest)
-            .await?
-            .ok_or_else(|| anyhow::anyhow!("Could not fetch current block"))?
-            .number
-            .ok_or_else(|| anyhow::anyhow!("Block number not available"))?;
-
-        let target_block = U64::from(current_block.as_u64() + 1);
-
-        // Create FastLane bundle
-        let _bundle = self.fastlane_client
-            .create_fastlane_bundle(opportunity, target_block)
-            .await?;
-
-        // Corrected method call - using the proper function signature from ABI
-        let contract = FlashLoanContract::new(self.flash_loan_contract, Arc::clone(&self.provider));
-        
-        // Create the ArbitrageOpportunity struct expected by the contract
-        let arbitrage_opportunity = FlashLoanContractArbitrageOpportunity {
-            token0: opportunity.token0,
-            token1: opportunity.token1,
-            amount0: opportunity.amount0,
-            amount1: opportunity.amount1,
-            fee: opportunity.fee.unwrap_or(3000), // Default fee if not specified
-            path: opportunity.path.clone(),
-            amounts: opportunity.amounts.clone(),
-            routers: opportunity.routers.clone(),
+        let mut op = SolverOperation {
+            from: searcher.address(),
+            to: Address::from_low_u64_be(9),
+            value: U256::zero(),
+            gas: U256::from(SOLVER_GAS_LIMIT),
+            max_fee_per_gas: U256::from(30_000_000_000u64),
+            deadline: U256::from(100u64),
+            solver: Address::from_low_u64_be(5),
+            control: Address::from_low_u64_be(6),
+            user_op_hash: H256::zero(),
+            bid_token: Address::zero(),
+            bid_amount: U256::from(1u64),
+            data: Bytes::from(vec![0xab]),
         };
 
-        let tx = contract.execute_arbitrage_with_fast_lane(
-            arbitrage_opportunity,
-            target_block
-        )
-        .value(opportunity.expected_profit.unwrap_or(U256::zero())) // Add value for FastLane bid
-        .send()
-        .await?
-        .await?
-        .ok_or_else(|| anyhow::anyhow!("No receipt returned"))?;
+        let signature = op.sign(&searcher, domain).unwrap();
+        let signer = signature.recover(RecoveryMessage::Hash(op.digest(domain))).unwrap();
+        assert_eq!(signer, searcher.address());
 
-        Ok(tx)
+        // A SolverOperation on behalf of another account is refused
+        op.from = funds.address();
+        assert!(op.sign(&searcher, domain).is_err());
     }
+}
@@ -0,0 +1,200 @@
+// src/fee_watch.rs
+use ethers::{
+    providers::Middleware,
+    types::{transaction::eip2718::TypedTransaction, Address, BlockId, Bytes, TransactionRequest, U256},
+    utils::id,
+};
+use log::{debug, info};
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use crate::amm_math::FEE_DENOMINATOR;
+use crate::metrics::POOL_FEE_CHANGES_TOTAL;
+
+const WORD: usize = 32;
+
+// Fees some pools can change after deployment: V2 forks with an owner-set swap fee, and
+// Algebra pools whose dynamic fee follows volatility. Every interval_blocks their current
+// fee is read and a changed one replaces the quoting tables' entry, so no quote goes on
+// with a stale fee. Uniswap V3 pools, whose fee is fixed, are left alone after one read
+#[derive(Debug, Clone, Deserialize)]
+pub struct FeeWatchSettings {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default = "default_interval_blocks")]
+    pub interval_blocks: u64,
+    // Fee view of each V2 factory whose pools can change fees; others keep their configured fee
+    #[serde(default)]
+    pub v2_factories: HashMap<Address, FeeView>,
+}
+
+// A `method() -> uint` view answering a fee out of `denominator`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct FeeView {
+    pub method: String,
+    pub denominator: u32,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_interval_blocks() -> u64 {
+    100
+}
+
+impl Default for FeeWatchSettings {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            interval_blocks: default_interval_blocks(),
+            v2_factories: HashMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FeeKind {
+    V2,
+    Algebra,
+}
+
+impl FeeKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FeeKind::V2 => "v2",
+            FeeKind::Algebra => "algebra",
+        }
+    }
+}
+
+// A pool's fee moved from `old` to `new`, both in FEE_DENOMINATOR units
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FeeChange {
+    pub pool: Address,
+    pub kind: FeeKind,
+    pub old: u32,
+    pub new: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct FeeWatch<M> {
+    provider: Arc<M>,
+    settings: FeeWatchSettings,
+    next_block: u64,
+    // Concentrated-liquidity pools without globalState, i.e. fixed-fee Uniswap V3 ones
+    fixed: HashSet<Address>,
+}
+
+impl<M: Middleware + 'static> FeeWatch<M> {
+    pub fn new(provider: Arc<M>, settings: FeeWatchSettings) -> Self {
+        Self {
+            provider,
+            settings,
+            next_block: 0,
+            fixed: HashSet::new(),
+        }
+    }
+
+    pub fn due(&self, block_number: u64) -> bool {
+        block_number >= self.next_block
+    }
+
+    // Reads the fees of `v2` (pool, factory) and `concentrated` pools at `block_number`;
+    // `current` is what the quoting tables hold. Unreadable fees are left as they are
+    pub async fn poll(
+        &mut self,
+        v2: &[(Address, Address)],
+        concentrated: &[Address],
+        current: impl Fn(&Address) -> u32,
+        block_number: u64,
+    ) -> Vec<FeeChange> {
+        self.next_block = block_number + self.settings.interval_blocks.max(1);
+        let block = BlockId::Number(block_number.into());
+        let mut changes = Vec::new();
+        for (pool, factory) in v2 {
+            let Some(view) = self.settings.v2_factories.get(factory) else {
+                continue;
+            };
+            let fee = self.read(*pool, &view.method, block).await.and_then(|out| v2_fee(&out, view.denominator));
+            if let Some(fee) = fee {
+                changes.push(FeeChange { pool: *pool, kind: FeeKind::V2, old: current(pool), new: fee });
+            }
+        }
+        for pool in concentrated {
+            if self.fixed.contains(pool) {
+                continue;
+            }
+            // globalState() -> (uint160 price, int24 tick, uint16 fee, ..), seven words
+            match self.read(*pool, "globalState()", block).await.filter(|out| out.len() == 7 * WORD) {
+                Some(out) => {
+                    let fee = U256::from_big_endian(&out[2 * WORD..3 * WORD]).low_u32();
+                    changes.push(FeeChange { pool: *pool, kind: FeeKind::Algebra, old: current(pool), new: fee });
+                }
+                None => {
+                    self.fixed.insert(*pool);
+                }
+            }
+        }
+
+        changes.retain(|change| change.old != change.new);
+        for change in &changes {
+            POOL_FEE_CHANGES_TOTAL.with_label_values(&[change.kind.as_str()]).inc();
+            info!(
+                "Fee of {} pool {:?} changed from {} to {} (per {})",
+                change.kind.as_str(),
+                change.pool,
+                change.old,
+                change.new,
+                FEE_DENOMINATOR
+            );
+        }
+        debug!("Fee watch read {} V2 and {} concentrated pools", v2.len(), concentrated.len());
+        changes
+    }
+
+    // Reverts, missing methods and transport errors alike read as "no answer"
+    async fn read(&self, to: Address, signature: &str, block: BlockId) -> Option<Bytes> {
+        let call: TypedTransaction = TransactionRequest::new().to(to).data(id(signature).to_vec()).into();
+        self.provider.call(&call, Some(block)).await.ok()
+    }
+}
+
+// One uint word below `denominator`, scaled to FEE_DENOMINATOR
+fn v2_fee(out: &[u8], denominator: u32) -> Option<u32> {
+    if out.len() != WORD || denominator == 0 {
+        return None;
+    }
+    let fee = U256::from_big_endian(out);
+    (fee < U256::from(denominator)).then(|| (fee.as_u64() * FEE_DENOMINATOR as u64 / denominator as u64) as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{address, call_output, MockChain};
+    use ethers::abi::Token;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_changed_v2_and_algebra_fees_are_reported_and_fixed_pools_dropped() {
+        let chain = MockChain::new();
+        let (v2_pool, factory, algebra, uniswap) = (address(1), address(2), address(3), address(4));
+        let settings = FeeWatchSettings {
+            v2_factories: HashMap::from([(factory, FeeView { method: "swapFee()".to_string(), denominator: 10_000 })]),
+            ..Default::default()
+        };
+        let uint = |value: u64| Token::Uint(U256::from(value));
+        let global_state = [uint(1), uint(0), uint(500), uint(0), uint(0), uint(0), Token::Bool(true)];
+        chain.script(vec![call_output(&[uint(25)]), call_output(&global_state), json!("0x")]);
+
+        let mut watch = FeeWatch::new(chain.provider(), settings);
+        let current = |pool: &Address| if *pool == algebra { 500 } else { 3_000 };
+        let changes = watch.poll(&[(v2_pool, factory), (address(5), address(6))], &[algebra, uniswap], current, 10).await;
+
+        assert_eq!(changes, vec![FeeChange { pool: v2_pool, kind: FeeKind::V2, old: 3_000, new: 2_500 }]);
+        assert!(!watch.due(109) && watch.due(110));
+        assert!(watch.fixed.contains(&uniswap) && !watch.fixed.contains(&algebra));
+    }
+}
@@ -0,0 +1,180 @@
+// src/fixtures.rs
+//
+// Offline test fixtures: a scripted mock Middleware and builders for pools and pending
+// swaps, so RPC-driven logic can be unit tested without a node.
+use ethers::{
+    abi::{self, Token},
+    providers::{MockProvider, Provider},
+    types::{Address, Bytes, Transaction, H256, U256},
+    utils::id,
+};
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::io::Write;
+use std::net::TcpListener;
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+use crate::amm_math::FeeSettings;
+use crate::mempool::{MempoolSourceKind, PendingSwap};
+use crate::reserve_cache::ReserveCache;
+use crate::route_search::TokenGraph;
+
+pub fn address(n: u64) -> Address {
+    Address::from_low_u64_be(n)
+}
+
+// Provider<MockProvider> answering from a script. MockProvider pops responses LIFO, so
+// `script` takes them in request order and pushes them reversed; call it once per exercise.
+pub struct MockChain {
+    provider: Arc<Provider<MockProvider>>,
+    mock: MockProvider,
+}
+
+impl MockChain {
+    pub fn new() -> Self {
+        let (provider, mock) = Provider::mocked();
+        Self {
+            provider: Arc::new(provider.interval(Duration::from_millis(1))),
+            mock,
+        }
+    }
+
+    pub fn provider(&self) -> Arc<Provider<MockProvider>> {
+        self.provider.clone()
+    }
+
+    pub fn script(&self, responses: Vec<Value>) {
+        for response in responses.into_iter().rev() {
+            self.mock.push::<Value, _>(response).unwrap();
+        }
+    }
+
+    // Checks the next request sent, in request order
+    pub fn assert_request<T: Serialize + Send + Sync>(&self, method: &str, params: T) {
+        self.mock.assert_request(method, params).unwrap();
+    }
+}
+
+// JSON-RPC hex quantity, e.g. for eth_blockNumber or eth_getTransactionCount
+pub fn quantity(value: impl Into<U256>) -> Value {
+    json!(format!("{:#x}", value.into()))
+}
+
+// eth_call result returning `tokens`
+pub fn call_output(tokens: &[Token]) -> Value {
+    json!(Bytes::from(abi::encode(tokens)))
+}
+
+// getReserves() -> (uint112, uint112, uint32)
+pub fn reserves_output(reserve0: u128, reserve1: u128) -> Value {
+    call_output(&[
+        Token::Uint(reserve0.into()),
+        Token::Uint(reserve1.into()),
+        Token::Uint(U256::zero()),
+    ])
+}
+
+// One-shot Redis server on a local port, answering the commands it reads with `replies` in
+// order; the CLIENT SETINFO calls a connection opens with are acknowledged aside. Joining it
+// yields the answered commands, each as its arguments
+pub fn redis_server(replies: Vec<Vec<u8>>) -> (u16, JoinHandle<Vec<Vec<Vec<u8>>>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let port = listener.local_addr().unwrap().port();
+    let server = thread::spawn(move || {
+        let (mut socket, _) = listener.accept().unwrap();
+        let reader = socket.try_clone().unwrap();
+        let mut parser = redis::Parser::new();
+        let mut commands = Vec::new();
+        for reply in replies {
+            loop {
+                let command: Vec<Vec<u8>> = redis::from_redis_value(&parser.parse_value(&reader).unwrap()).unwrap();
+                if command[0] == b"CLIENT" {
+                    socket.write_all(b"+OK\r\n").unwrap();
+                    continue;
+                }
+                socket.write_all(&reply).unwrap();
+                commands.push(command);
+                break;
+            }
+        }
+        commands
+    });
+    (port, server)
+}
+
+// V2 pools with given reserves, materialized as a reserve cache, token map or search graph
+#[derive(Debug, Default)]
+pub struct PoolSet {
+    pools: Vec<(Address, Address, Address, U256, U256)>,
+}
+
+impl PoolSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn pool(mut self, pool: Address, token0: Address, token1: Address, reserve0: U256, reserve1: U256) -> Self {
+        self.pools.push((pool, token0, token1, reserve0, reserve1));
+        self
+    }
+
+    pub fn cache(&self, block_number: u64) -> ReserveCache {
+        let cache = ReserveCache::new();
+        for &(pool, _, _, reserve0, reserve1) in &self.pools {
+            cache.update(pool, reserve0, reserve1, block_number);
+        }
+        cache
+    }
+
+    pub fn tokens(&self) -> HashMap<Address, (Address, Address)> {
+        self.pools
+            .iter()
+            .map(|&(pool, token0, token1, _, _)| (pool, (token0, token1)))
+            .collect()
+    }
+
+    pub fn graph(&self, fees: &FeeSettings) -> TokenGraph {
+        let mut graph = TokenGraph::new();
+        for &(pool, token0, token1, reserve0, reserve1) in &self.pools {
+            graph.add_pool(pool, token0, token1, reserve0, reserve1, fees.fee(&pool, None));
+        }
+        graph
+    }
+
+    // (reserve_in, reserve_out, fee) per hop, reserve0 as the input side
+    pub fn hops(&self, fees: &FeeSettings) -> Vec<(U256, U256, u32)> {
+        self.pools
+            .iter()
+            .map(|&(pool, _, _, reserve0, reserve1)| (reserve0, reserve1, fees.fee(&pool, None)))
+            .collect()
+    }
+}
+
+// A pending V2 router swapExactTokensForTokens
+pub fn swap_tx(hash: H256, router: Address, amount_in: U256, path: &[Address]) -> Transaction {
+    let selector = &id("swapExactTokensForTokens(uint256,uint256,address[],address,uint256)");
+    let args = abi::encode(&[
+        Token::Uint(amount_in),
+        Token::Uint(U256::zero()),
+        Token::Array(path.iter().map(|&token| Token::Address(token)).collect()),
+        Token::Address(address(0xbeef)),
+        Token::Uint(U256::MAX),
+    ]);
+    Transaction {
+        hash,
+        to: Some(router),
+        input: [selector.as_slice(), &args].concat().into(),
+        ..Default::default()
+    }
+}
+
+pub fn pending_swap(tx: Transaction, source: MempoolSourceKind, first_seen_ms: u64) -> PendingSwap {
+    PendingSwap {
+        tx,
+        source,
+        first_seen_ms,
+    }
+}
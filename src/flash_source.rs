@@ -0,0 +1,358 @@
+// src/flash_source.rs
+use anyhow::{anyhow, Result};
+use ethers::{
+    abi::{self, Token},
+    providers::Middleware,
+    types::{transaction::eip2718::TypedTransaction, Address, BlockId, Bytes, TransactionRequest, U256},
+    utils::id,
+};
+use log::{debug, info};
+use serde::Deserialize;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use crate::metrics::FLASH_PLANS_TOTAL;
+use crate::simulation_engine::ArbitrageOpportunity;
+
+const AAVE_V3_POOL: &str = "0x794a61358D6845594F94dc1DB02A252b5b4814aD";
+const BALANCER_VAULT: &str = "0xBA12222222228d8Ba445958a75a0704d566BF2C8";
+
+const BPS: u64 = 10_000;
+const WORD: usize = 32;
+// aTokenAddress within Aave V3's getReserveData(asset) struct
+const A_TOKEN_WORD: usize = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FlashSource {
+    Aave,
+    Balancer,
+}
+
+impl FlashSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FlashSource::Aave => "aave",
+            FlashSource::Balancer => "balancer",
+        }
+    }
+}
+
+// Flash loan sources a trade borrows from. When the first provider cannot lend the optimal
+// size, the borrow is split over the next ones (for executors taking a `flash_legs`
+// argument) or the trade is downsized to what a single provider can lend
+#[derive(Debug, Clone, Deserialize)]
+pub struct FlashSourceSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    // Borrow order; the first is the primary source
+    #[serde(default = "default_providers")]
+    pub providers: Vec<FlashSource>,
+    #[serde(default = "default_aave_pool")]
+    pub aave_pool: Address,
+    #[serde(default = "default_balancer_vault")]
+    pub balancer_vault: Address,
+    // Aave V3 FLASHLOAN_PREMIUM_TOTAL; Balancer flash loans are free
+    #[serde(default = "default_aave_premium_bps")]
+    pub aave_premium_bps: u64,
+    // Share of a provider's liquidity left unborrowed, for withdrawals landing first
+    #[serde(default = "default_headroom_bps")]
+    pub headroom_bps: u64,
+    // Smallest downsized trade as a share of the optimal size; below it the trade is dropped
+    #[serde(default = "default_min_size_bps")]
+    pub min_size_bps: u64,
+}
+
+fn default_providers() -> Vec<FlashSource> {
+    vec![FlashSource::Aave, FlashSource::Balancer]
+}
+
+fn default_aave_pool() -> Address {
+    Address::from_str(AAVE_V3_POOL).expect("valid Aave pool address")
+}
+
+fn default_balancer_vault() -> Address {
+    Address::from_str(BALANCER_VAULT).expect("valid Balancer vault address")
+}
+
+fn default_aave_premium_bps() -> u64 {
+    5
+}
+
+fn default_headroom_bps() -> u64 {
+    100
+}
+
+fn default_min_size_bps() -> u64 {
+    2_500
+}
+
+impl Default for FlashSourceSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            providers: default_providers(),
+            aave_pool: default_aave_pool(),
+            balancer_vault: default_balancer_vault(),
+            aave_premium_bps: default_aave_premium_bps(),
+            headroom_bps: default_headroom_bps(),
+            min_size_bps: default_min_size_bps(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FlashLeg {
+    pub source: FlashSource,
+    // Contract the executor borrows from
+    pub lender: Address,
+    pub amount: U256,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FlashPlan {
+    pub legs: Vec<FlashLeg>,
+    pub requested: U256,
+}
+
+impl FlashPlan {
+    pub fn amount(&self) -> U256 {
+        self.legs.iter().fold(U256::zero(), |total, leg| total + leg.amount)
+    }
+
+    pub fn is_downsized(&self) -> bool {
+        self.amount() < self.requested
+    }
+
+    pub fn outcome(&self) -> &'static str {
+        if self.is_downsized() {
+            "downsized"
+        } else if self.legs.len() > 1 {
+            "split"
+        } else {
+            "single"
+        }
+    }
+}
+
+impl FlashSourceSettings {
+    fn lender(&self, source: FlashSource) -> Address {
+        match source {
+            FlashSource::Aave => self.aave_pool,
+            FlashSource::Balancer => self.balancer_vault,
+        }
+    }
+
+    fn usable(&self, liquidity: U256) -> U256 {
+        liquidity - liquidity * U256::from(self.headroom_bps.min(BPS)) / U256::from(BPS)
+    }
+
+    // Borrows `amount` from `available` (liquidity per provider, in borrow order). With
+    // `split` the providers are filled in order, else the first able to lend it all is used,
+    // or the one lending the most. None when less than `min_size_bps` of it can be borrowed
+    pub fn plan(&self, amount: U256, available: &[(FlashSource, U256)], split: bool) -> Option<FlashPlan> {
+        let mut legs = Vec::new();
+        if split {
+            let mut missing = amount;
+            for &(source, liquidity) in available {
+                let take = self.usable(liquidity).min(missing);
+                if take.is_zero() {
+                    continue;
+                }
+                legs.push(FlashLeg {
+                    source,
+                    lender: self.lender(source),
+                    amount: take,
+                });
+                missing -= take;
+            }
+        } else {
+            let single = available
+                .iter()
+                .find(|&&(_, liquidity)| self.usable(liquidity) >= amount)
+                .or_else(|| available.iter().max_by_key(|&&(_, liquidity)| self.usable(liquidity)));
+            if let Some(&(source, liquidity)) = single {
+                legs.push(FlashLeg {
+                    source,
+                    lender: self.lender(source),
+                    amount: self.usable(liquidity).min(amount),
+                });
+            }
+        }
+        let plan = FlashPlan { legs, requested: amount };
+        let min_size = amount * U256::from(self.min_size_bps) / U256::from(BPS);
+        (!plan.amount().is_zero() && plan.amount() >= min_size).then_some(plan)
+    }
+
+    // Premiums owed on the plan's legs
+    pub fn fee(&self, plan: &FlashPlan) -> U256 {
+        plan.legs
+            .iter()
+            .filter(|leg| leg.source == FlashSource::Aave)
+            .fold(U256::zero(), |fee, leg| {
+                fee + leg.amount * U256::from(self.aave_premium_bps) / U256::from(BPS)
+            })
+    }
+}
+
+// The opportunity at a smaller input; hop amounts and expected profit scale linearly, which
+// understates the profit of a concave route at the smaller size
+pub fn downsize(opportunity: &ArbitrageOpportunity, amount: U256) -> ArbitrageOpportunity {
+    if opportunity.amount0.is_zero() || amount >= opportunity.amount0 {
+        return opportunity.clone();
+    }
+    let scale = |value: U256| value * amount / opportunity.amount0;
+    ArbitrageOpportunity {
+        amount0: amount,
+        amounts: opportunity.amounts.iter().map(|&value| scale(value)).collect(),
+        expected_profit: scale(opportunity.expected_profit),
+        ..opportunity.clone()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FlashSources<M> {
+    provider: Arc<M>,
+    settings: FlashSourceSettings,
+}
+
+impl<M: Middleware + 'static> FlashSources<M> {
+    pub fn new(provider: Arc<M>, settings: FlashSourceSettings) -> Self {
+        Self { provider, settings }
+    }
+
+    pub fn settings(&self) -> &FlashSourceSettings {
+        &self.settings
+    }
+
+    // What `source` can lend of `token` at `block`: Aave the underlying held by the
+    // reserve's aToken, Balancer the vault's balance. Zero for assets Aave does not list
+    pub async fn available(&self, source: FlashSource, token: Address, block: BlockId) -> Result<U256> {
+        let holder = match source {
+            FlashSource::Aave => {
+                let reserve = self
+                    .call(self.settings.aave_pool, "getReserveData(address)", &[Token::Address(token)], block)
+                    .await?;
+                let Some(word) = reserve.get(A_TOKEN_WORD * WORD..(A_TOKEN_WORD + 1) * WORD) else {
+                    return Err(anyhow!("Short getReserveData answer for {:?}", token));
+                };
+                let a_token = Address::from_slice(&word[12..]);
+                if a_token.is_zero() {
+                    return Ok(U256::zero());
+                }
+                a_token
+            }
+            FlashSource::Balancer => self.settings.balancer_vault,
+        };
+        let balance = self
+            .call(token, "balanceOf(address)", &[Token::Address(holder)], block)
+            .await?;
+        if balance.len() < WORD {
+            return Err(anyhow!("Short balanceOf answer from {:?}", token));
+        }
+        Ok(U256::from_big_endian(&balance[..WORD]))
+    }
+
+    // Borrow plan for `amount` of `token` at the providers' current liquidity; a provider
+    // whose liquidity cannot be read counts as empty
+    pub async fn plan(&self, token: Address, amount: U256, split: bool, block: BlockId) -> Option<FlashPlan> {
+        let mut available = Vec::with_capacity(self.settings.providers.len());
+        for &source in &self.settings.providers {
+            let liquidity = match self.available(source, token, block).await {
+                Ok(liquidity) => liquidity,
+                Err(e) => {
+                    debug!("{} liquidity of {:?} unavailable: {:?}", source.as_str(), token, e);
+                    U256::zero()
+                }
+            };
+            available.push((source, liquidity));
+        }
+        let plan = self.settings.plan(amount, &available, split);
+        let outcome = plan.as_ref().map_or("insufficient", FlashPlan::outcome);
+        FLASH_PLANS_TOTAL.with_label_values(&[outcome]).inc();
+        if outcome != "single" {
+            info!("Flash borrow of {} {:?}: {} over {:?}", amount, token, outcome, available);
+        }
+        plan
+    }
+
+    // The opportunity sized to what can be borrowed, with its legs; None when too little is
+    pub async fn fit(
+        &self,
+        opportunity: &ArbitrageOpportunity,
+        split: bool,
+        block: BlockId,
+    ) -> Option<(ArbitrageOpportunity, Vec<FlashLeg>)> {
+        let plan = self.plan(opportunity.token0, opportunity.amount0, split, block).await?;
+        let mut fitted = downsize(opportunity, plan.amount());
+        fitted.expected_profit = fitted.expected_profit.saturating_sub(self.settings.fee(&plan));
+        Some((fitted, plan.legs))
+    }
+
+    async fn call(&self, to: Address, signature: &str, args: &[Token], block: BlockId) -> Result<Bytes> {
+        let data = [&id(signature)[..], &abi::encode(args)].concat();
+        let call: TypedTransaction = TransactionRequest::new().to(to).data(data).into();
+        self.provider
+            .call(&call, Some(block))
+            .await
+            .map_err(|e| anyhow!("{} on {:?} failed: {e}", signature, to))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{address, call_output, MockChain};
+    use ethers::types::BlockNumber;
+
+    #[tokio::test]
+    async fn test_borrows_split_over_providers_or_downsize_the_trade() {
+        let settings = FlashSourceSettings {
+            enabled: true,
+            headroom_bps: 0,
+            ..Default::default()
+        };
+        let amount = U256::from(100u64);
+        let (aave, balancer) = (FlashSource::Aave, FlashSource::Balancer);
+        let available = [(aave, U256::from(60u64)), (balancer, U256::from(100u64))];
+
+        let plan = settings.plan(amount, &available, true).unwrap();
+        let legs: Vec<_> = plan.legs.iter().map(|leg| (leg.source, leg.amount.as_u64())).collect();
+        assert_eq!(legs, vec![(aave, 60), (balancer, 40)]);
+        assert_eq!((plan.outcome(), settings.fee(&plan)), ("split", U256::zero()));
+        // One provider only: Balancer can lend it all
+        let plan = settings.plan(amount, &available, false).unwrap();
+        assert_eq!((plan.legs[0].source, plan.legs[0].lender), (balancer, settings.balancer_vault));
+        // Neither can: the biggest lender sets the size, unless it is too small
+        let shallow = [(aave, U256::from(30u64)), (balancer, U256::from(10u64))];
+        let plan = settings.plan(amount, &shallow, false).unwrap();
+        assert_eq!((plan.outcome(), plan.amount().as_u64()), ("downsized", 30));
+        assert!(settings.plan(amount, &[(aave, U256::from(20u64))], true).is_none());
+
+        let opportunity = ArbitrageOpportunity {
+            token0: address(1),
+            token1: address(2),
+            amount0: amount,
+            amount1: U256::zero(),
+            fee: 3000,
+            path: vec![address(1), address(2)],
+            amounts: vec![amount, U256::from(210u64)],
+            routers: vec![address(3)],
+            expected_profit: U256::from(8u64),
+            optimal_path: Vec::new(),
+            class: Default::default(),
+        };
+        let smaller = downsize(&opportunity, U256::from(50u64));
+        assert_eq!(smaller.amounts, vec![U256::from(50u64), U256::from(105u64)]);
+        assert_eq!(smaller.expected_profit, U256::from(4u64));
+
+        // Aave's liquidity is the underlying held by the reserve's aToken
+        let chain = MockChain::new();
+        let sources = FlashSources::new(chain.provider(), settings);
+        let mut reserve: Vec<Token> = (0..15).map(|_| Token::Uint(U256::zero())).collect();
+        reserve[A_TOKEN_WORD] = Token::Address(address(9));
+        chain.script(vec![call_output(&reserve), call_output(&[Token::Uint(U256::from(75u64))])]);
+        let block = BlockId::Number(BlockNumber::Latest);
+        assert_eq!(sources.available(aave, address(1), block).await.unwrap(), U256::from(75u64));
+    }
+}
@@ -0,0 +1,375 @@
+// src/gas_oracle.rs
+use anyhow::{anyhow, Result};
+use ethers::{
+    providers::{Middleware, PubsubClient, StreamExt},
+    types::{Block, BlockNumber, U256},
+};
+use log::{debug, info, warn};
+use serde::Deserialize;
+use std::fmt;
+use std::ops::{Add, AddAssign};
+use std::sync::{Arc, RwLock};
+
+use crate::metrics::{BASE_FEE_FORECAST_GWEI, GAS_CEILING_SKIPPED_PROFIT, GAS_CEILING_SKIPPED_TOTAL};
+use crate::token_registry::format_units;
+
+const GWEI: u64 = 1_000_000_000;
+
+// Global cap on what a submission may pay per gas; during spikes opportunities are only
+// logged. 0 leaves a fee uncapped
+#[derive(Debug, Clone, Deserialize)]
+pub struct GasCeilingSettings {
+    #[serde(default = "default_max_base_fee_gwei")]
+    pub max_base_fee_gwei: u64,
+    #[serde(default = "default_max_priority_fee_gwei")]
+    pub max_priority_fee_gwei: u64,
+}
+
+fn default_max_base_fee_gwei() -> u64 {
+    1_000
+}
+
+fn default_max_priority_fee_gwei() -> u64 {
+    300
+}
+
+impl Default for GasCeilingSettings {
+    fn default() -> Self {
+        Self {
+            max_base_fee_gwei: default_max_base_fee_gwei(),
+            max_priority_fee_gwei: default_max_priority_fee_gwei(),
+        }
+    }
+}
+
+impl GasCeilingSettings {
+    // The fee above its cap, if any
+    pub fn exceeded(&self, gas: &GasSnapshot) -> Option<&'static str> {
+        let over = |fee: U256, cap_gwei: u64| cap_gwei > 0 && fee > U256::from(cap_gwei) * U256::from(GWEI);
+        if over(gas.base_fee, self.max_base_fee_gwei) {
+            Some("base_fee")
+        } else if over(gas.priority_fee, self.max_priority_fee_gwei) {
+            Some("priority_fee")
+        } else {
+            None
+        }
+    }
+
+    // False if gas is above the ceiling; the skipped opportunity is then logged and counted
+    pub fn admit(&self, gas: &GasSnapshot, expected_profit: U256) -> bool {
+        let Some(fee) = self.exceeded(gas) else {
+            return true;
+        };
+        info!(
+            "Skipping execution at block {}: {} above ceiling (base {} gwei, tip {} gwei), expected profit {}",
+            gas.block_number,
+            fee,
+            format_units(gas.base_fee, 9),
+            format_units(gas.priority_fee, 9),
+            format_units(expected_profit, 18)
+        );
+        GAS_CEILING_SKIPPED_TOTAL.with_label_values(&[fee]).inc();
+        GAS_CEILING_SKIPPED_PROFIT.inc_by(expected_profit.low_u128() as f64 / 1e18);
+        false
+    }
+}
+
+// EIP-1559 on Polygon: trades sent now land in the next block, so the base fee they burn is
+// forecast from the latest header's gas used against its target instead of read off
+// eth_gasPrice
+#[derive(Debug, Clone, Deserialize)]
+pub struct GasForecastSettings {
+    // Off prices at the latest header's own base fee
+    #[serde(default = "default_forecast_enabled")]
+    pub enabled: bool,
+    // Gas target is gas limit / elasticity
+    #[serde(default = "default_elasticity")]
+    pub elasticity: u64,
+    // Max base fee change per block is 1 / denominator; 16 on Polygon since Delhi
+    #[serde(default = "default_change_denominator")]
+    pub change_denominator: u64,
+    // Tip assumed where the RPC has no eth_maxPriorityFeePerGas; Polygon's floor is 30 gwei
+    #[serde(default = "default_min_priority_fee_gwei")]
+    pub min_priority_fee_gwei: u64,
+}
+
+fn default_forecast_enabled() -> bool {
+    true
+}
+
+fn default_elasticity() -> u64 {
+    2
+}
+
+fn default_change_denominator() -> u64 {
+    16
+}
+
+fn default_min_priority_fee_gwei() -> u64 {
+    30
+}
+
+impl Default for GasForecastSettings {
+    fn default() -> Self {
+        Self {
+            enabled: default_forecast_enabled(),
+            elasticity: default_elasticity(),
+            change_denominator: default_change_denominator(),
+            min_priority_fee_gwei: default_min_priority_fee_gwei(),
+        }
+    }
+}
+
+impl GasForecastSettings {
+    // Base fee of the block after one with `base_fee`, `gas_used` and `gas_limit`
+    pub fn next_base_fee(&self, base_fee: U256, gas_used: U256, gas_limit: U256) -> U256 {
+        let target = gas_limit / U256::from(self.elasticity.max(1));
+        if !self.enabled || target.is_zero() || gas_used == target {
+            return base_fee;
+        }
+        let denominator = U256::from(self.change_denominator.max(1));
+        if gas_used > target {
+            let delta = base_fee * (gas_used - target) / target / denominator;
+            base_fee + delta.max(U256::one())
+        } else {
+            base_fee.saturating_sub(base_fee * (target - gas_used) / target / denominator)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GasSnapshot {
+    pub block_number: u64,
+    // Forecast for the block after `block_number`, the one a trade sent now lands in
+    pub base_fee: U256,
+    pub priority_fee: U256,
+}
+
+impl GasSnapshot {
+    pub fn effective_gas_price(&self) -> U256 {
+        self.base_fee + self.priority_fee
+    }
+
+    // Refunds are capped at a fifth of the gas used (EIP-3529, live on Polygon since London)
+    pub fn cost(&self, gas_used: U256, refund: U256, bid: U256) -> GasCost {
+        let gas = gas_used - refund.min(gas_used / 5);
+        GasCost {
+            burned: gas * self.base_fee,
+            validator_tip: gas * self.priority_fee,
+            fastlane_bid: bid,
+        }
+    }
+}
+
+// Where the MATIC spent on a submission goes: the base fee is burned, the priority fee
+// and the FastLane auction bid both end up with the validator
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct GasCost {
+    pub burned: U256,
+    pub validator_tip: U256,
+    pub fastlane_bid: U256,
+}
+
+impl GasCost {
+    pub fn total(&self) -> U256 {
+        self.burned + self.validator_tip + self.fastlane_bid
+    }
+
+    pub fn to_validator(&self) -> U256 {
+        self.validator_tip + self.fastlane_bid
+    }
+}
+
+impl Add for GasCost {
+    type Output = GasCost;
+
+    fn add(self, other: GasCost) -> GasCost {
+        GasCost {
+            burned: self.burned + other.burned,
+            validator_tip: self.validator_tip + other.validator_tip,
+            fastlane_bid: self.fastlane_bid + other.fastlane_bid,
+        }
+    }
+}
+
+impl AddAssign for GasCost {
+    fn add_assign(&mut self, other: GasCost) {
+        *self = *self + other;
+    }
+}
+
+impl fmt::Display for GasCost {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} MATIC (burned {}, validator tips {}, FastLane bids {})",
+            format_units(self.total(), 18),
+            format_units(self.burned, 18),
+            format_units(self.validator_tip, 18),
+            format_units(self.fastlane_bid, 18),
+        )
+    }
+}
+
+// Per-block view of base fee and priority fee, refreshed by the block loop or fed by a
+// header subscription
+#[derive(Debug)]
+pub struct GasOracle<M> {
+    provider: Arc<M>,
+    settings: GasForecastSettings,
+    snapshot: RwLock<GasSnapshot>,
+}
+
+impl<M: Middleware + 'static> GasOracle<M> {
+    pub fn new(provider: Arc<M>) -> Self {
+        Self {
+            provider,
+            settings: GasForecastSettings::default(),
+            snapshot: RwLock::new(GasSnapshot::default()),
+        }
+    }
+
+    pub fn with_forecast(mut self, settings: GasForecastSettings) -> Self {
+        self.settings = settings;
+        self
+    }
+
+    pub fn snapshot(&self) -> GasSnapshot {
+        *self.snapshot.read().unwrap()
+    }
+
+    pub fn is_stale(&self, block_number: u64) -> bool {
+        self.snapshot().block_number < block_number
+    }
+
+    pub async fn refresh(&self) -> Result<GasSnapshot> {
+        let block = self
+            .provider
+            .get_block(BlockNumber::Latest)
+            .await?
+            .ok_or_else(|| anyhow!("Could not fetch latest block"))?;
+        let priority_fee = self.priority_fee().await;
+        self.observe(&block, priority_fee).ok_or_else(|| anyhow!("Block number not available"))
+    }
+
+    // Not every RPC exposes eth_maxPriorityFeePerGas; the configured floor stands in
+    async fn priority_fee(&self) -> U256 {
+        match self
+            .provider
+            .provider()
+            .request::<_, U256>("eth_maxPriorityFeePerGas", ())
+            .await
+        {
+            Ok(fee) => fee,
+            Err(_) => U256::from(self.settings.min_priority_fee_gwei) * U256::from(GWEI),
+        }
+    }
+
+    // Forecasts from a header; headers older than the snapshot's are ignored
+    pub fn observe<T>(&self, header: &Block<T>, priority_fee: U256) -> Option<GasSnapshot> {
+        let block_number = header.number?.as_u64();
+        let observed = header.base_fee_per_gas.unwrap_or_default();
+        let base_fee = self.settings.next_base_fee(observed, header.gas_used, header.gas_limit);
+        let snapshot = GasSnapshot {
+            block_number,
+            base_fee,
+            priority_fee,
+        };
+
+        let mut current = self.snapshot.write().unwrap();
+        if block_number < current.block_number {
+            return Some(*current);
+        }
+        debug!("Gas oracle updated: {:?} (header base fee {})", snapshot, observed);
+        BASE_FEE_FORECAST_GWEI.set(base_fee.low_u128() as f64 / GWEI as f64);
+        *current = snapshot;
+        Some(snapshot)
+    }
+}
+
+impl<M> GasOracle<M>
+where
+    M: Middleware + 'static,
+    M::Provider: PubsubClient,
+{
+    // Keeps the snapshot one header fresh, so block loops find it current without a refresh
+    pub async fn follow_headers(&self) -> Result<()> {
+        let mut headers = self.provider.subscribe_blocks().await?;
+        while let Some(header) = headers.next().await {
+            let priority_fee = self.priority_fee().await;
+            if self.observe(&header, priority_fee).is_none() {
+                warn!("Skipping a pending header without a number");
+            }
+        }
+        Err(anyhow!("Header subscription ended"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cost_splits_burn_tip_and_bid() {
+        let gas = GasSnapshot {
+            block_number: 1,
+            base_fee: U256::from(30u64),
+            priority_fee: U256::from(2u64),
+        };
+
+        let cost = gas.cost(U256::from(100_000u64), U256::zero(), U256::from(7u64));
+        assert_eq!(cost.burned, U256::from(3_000_000u64));
+        assert_eq!(cost.to_validator(), U256::from(200_007u64));
+        assert_eq!(cost.total(), U256::from(3_200_007u64));
+
+        // A 50k refund on 100k gas only credits 20k
+        let refunded = gas.cost(U256::from(100_000u64), U256::from(50_000u64), U256::zero());
+        assert_eq!(refunded.burned, U256::from(2_400_000u64));
+        assert_eq!((cost + refunded).validator_tip, U256::from(360_000u64));
+    }
+
+    #[test]
+    fn test_base_fee_follows_gas_used_against_target() {
+        let settings = GasForecastSettings::default();
+        let (base_fee, limit) = (U256::from(160 * GWEI), U256::from(30_000_000u64));
+        let next = |used: u64| settings.next_base_fee(base_fee, U256::from(used), limit);
+
+        assert_eq!(next(15_000_000), base_fee);
+        // Full blocks raise it by a sixteenth, empty ones lower it by as much
+        assert_eq!(next(30_000_000), U256::from(170 * GWEI));
+        assert_eq!(next(0), U256::from(150 * GWEI));
+        assert_eq!(next(22_500_000), U256::from(165 * GWEI));
+
+        let off = GasForecastSettings { enabled: false, ..settings };
+        assert_eq!(off.next_base_fee(base_fee, limit, limit), base_fee);
+    }
+
+    #[test]
+    fn test_ceiling_rejects_spiking_fees() {
+        let ceiling = GasCeilingSettings {
+            max_base_fee_gwei: 500,
+            max_priority_fee_gwei: 0,
+        };
+        let calm = GasSnapshot {
+            block_number: 1,
+            base_fee: U256::from(500 * GWEI),
+            priority_fee: U256::from(10_000 * GWEI),
+        };
+        // At the cap is fine, and an uncapped tip never blocks
+        assert_eq!(ceiling.exceeded(&calm), None);
+        assert!(ceiling.admit(&calm, U256::exp10(18)));
+
+        let spike = GasSnapshot {
+            base_fee: calm.base_fee + 1,
+            ..calm
+        };
+        assert_eq!(ceiling.exceeded(&spike), Some("base_fee"));
+        assert!(!ceiling.admit(&spike, U256::exp10(18)));
+
+        let tipped = GasSnapshot {
+            priority_fee: U256::from(301 * GWEI),
+            ..calm
+        };
+        assert_eq!(GasCeilingSettings::default().exceeded(&tipped), Some("priority_fee"));
+    }
+}
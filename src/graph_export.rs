@@ -0,0 +1,230 @@
+// src/graph_export.rs
+use anyhow::Result;
+use clap::ValueEnum;
+use ethers::types::{Address, U256};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Write;
+
+use crate::amm_math::FeeSettings;
+use crate::prefetch::PrefetchedPair;
+use crate::reserve_cache::ReserveSnapshot;
+use crate::token_registry::{format_units, TokenRegistry};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum GraphFormat {
+    Dot,
+    Json,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GraphNode {
+    pub address: Address,
+    pub symbol: String,
+    pub decimals: Option<u8>,
+    // Pools with reserves on both sides; a token of the universe without any is a missing
+    // liquidity source
+    pub pools: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct GraphEdge {
+    pub pool: Address,
+    pub factory: Address,
+    pub token0: Address,
+    pub token1: Address,
+    pub reserve0: U256,
+    pub reserve1: U256,
+    // Hundredths of a bip, as in `fees`
+    pub fee: u32,
+}
+
+// The token/pool graph the route search walks, behind `flashwich graph`
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct GraphExport {
+    pub block_number: u64,
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+}
+
+impl GraphExport {
+    // Every token of `universe` becomes a node, linked or not; pools without reserves in
+    // `reserves` are left out like the route search leaves them out
+    pub fn build(
+        universe: &[Address],
+        pairs: &[PrefetchedPair],
+        reserves: &ReserveSnapshot,
+        fees: &FeeSettings,
+        tokens: &TokenRegistry,
+        block_number: u64,
+    ) -> Self {
+        let mut edges: Vec<GraphEdge> = pairs
+            .iter()
+            .filter_map(|pair| {
+                let pool = reserves.get(&pair.pair)?;
+                (!pool.reserve0.is_zero() && !pool.reserve1.is_zero()).then(|| GraphEdge {
+                    pool: pair.pair,
+                    factory: pair.factory,
+                    token0: pair.token0,
+                    token1: pair.token1,
+                    reserve0: pool.reserve0,
+                    reserve1: pool.reserve1,
+                    fee: fees.fee(&pair.pair, Some(&pair.factory)),
+                })
+            })
+            .collect();
+        edges.sort_by_key(|edge| (edge.token0, edge.token1, edge.pool));
+
+        let mut degree: BTreeMap<Address, usize> = universe.iter().map(|&token| (token, 0)).collect();
+        for edge in &edges {
+            *degree.entry(edge.token0).or_default() += 1;
+            *degree.entry(edge.token1).or_default() += 1;
+        }
+        let nodes = degree
+            .into_iter()
+            .map(|(address, pools)| GraphNode {
+                address,
+                symbol: tokens.symbol(&address),
+                decimals: tokens.get(&address).map(|info| info.decimals),
+                pools,
+            })
+            .collect();
+        Self {
+            block_number,
+            nodes,
+            edges,
+        }
+    }
+
+    pub fn isolated(&self) -> impl Iterator<Item = &GraphNode> {
+        self.nodes.iter().filter(|node| node.pools == 0)
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    // Undirected, one edge per pool labelled with fee and reserves; thicker edges hold more
+    // of their tokens' liquidity, tokens without pools are drawn dashed in red
+    pub fn to_dot(&self) -> String {
+        let decimals: BTreeMap<Address, Option<u8>> =
+            self.nodes.iter().map(|node| (node.address, node.decimals)).collect();
+        let reserve = |token: &Address, amount: U256| match decimals.get(token).copied().flatten() {
+            Some(decimals) => compact(format_units(amount, decimals).parse().unwrap_or(f64::MAX)),
+            None => amount.to_string(),
+        };
+        // Widths relative to the deepest pool of each token
+        let mut deepest: BTreeMap<Address, U256> = BTreeMap::new();
+        for edge in &self.edges {
+            for (token, amount) in [(edge.token0, edge.reserve0), (edge.token1, edge.reserve1)] {
+                let max = deepest.entry(token).or_default();
+                *max = (*max).max(amount);
+            }
+        }
+
+        let mut out = String::new();
+        writeln!(out, "graph flashwich {{").unwrap();
+        writeln!(out, "  label=\"token/pool graph at block {}\";", self.block_number).unwrap();
+        writeln!(out, "  node [shape=ellipse];").unwrap();
+        for node in &self.nodes {
+            let style = if node.pools == 0 { ", style=dashed, color=red" } else { "" };
+            writeln!(out, "  \"{:?}\" [label=\"{}\"{}];", node.address, escape(&node.symbol), style).unwrap();
+        }
+        for edge in &self.edges {
+            let share = |token: &Address, amount: U256| {
+                let max = deepest.get(token).copied().unwrap_or_default();
+                if max.is_zero() {
+                    return 0.0;
+                }
+                // Shifted so the ratio fits an f64
+                let shift = max.bits().saturating_sub(64);
+                (amount >> shift).low_u64() as f64 / (max >> shift).low_u64().max(1) as f64
+            };
+            let width = 1.0 + 4.0 * share(&edge.token0, edge.reserve0).max(share(&edge.token1, edge.reserve1));
+            writeln!(
+                out,
+                "  \"{:?}\" -- \"{:?}\" [label=\"{:.2}%\\n{} / {}\", penwidth={:.1}, tooltip=\"{:?}\"];",
+                edge.token0,
+                edge.token1,
+                edge.fee as f64 / 10_000.0,
+                reserve(&edge.token0, edge.reserve0),
+                reserve(&edge.token1, edge.reserve1),
+                width,
+                edge.pool
+            )
+            .unwrap();
+        }
+        writeln!(out, "}}").unwrap();
+        out
+    }
+}
+
+// 1234567.8 -> "1.23M"
+fn compact(value: f64) -> String {
+    match value {
+        v if v >= 1e9 => format!("{:.2}B", v / 1e9),
+        v if v >= 1e6 => format!("{:.2}M", v / 1e6),
+        v if v >= 1e3 => format!("{:.2}k", v / 1e3),
+        v if v >= 1.0 => format!("{:.2}", v),
+        v => format!("{:.4}", v),
+    }
+}
+
+fn escape(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::address;
+    use crate::reserve_cache::ReserveCache;
+    use crate::token_registry::TokenInfo;
+
+    #[test]
+    fn test_graph_is_built_from_pairs_with_reserves_and_rendered() {
+        let (usdc, wmatic, weth, dust) = (address(1), address(2), address(3), address(4));
+        let tokens = TokenRegistry::new();
+        for (token, symbol, decimals) in [(usdc, "USDC", 6), (wmatic, "WMATIC", 18)] {
+            tokens
+                .insert(TokenInfo {
+                    address: token,
+                    name: symbol.to_string(),
+                    symbol: symbol.to_string(),
+                    decimals,
+                    tags: Vec::new(),
+                })
+                .unwrap();
+        }
+        let pair = |pair, factory, token0, token1| PrefetchedPair { pair, factory, token0, token1 };
+        let pairs = [
+            pair(address(10), address(20), usdc, wmatic),
+            pair(address(11), address(21), usdc, wmatic),
+            // Drained pool, not part of the search space
+            pair(address(12), address(20), usdc, weth),
+        ];
+        let cache = ReserveCache::new();
+        cache.update(address(10), U256::from(2_000_000_000_000u64), U256::exp10(24) * 2, 100);
+        cache.update(address(11), U256::from(500_000_000u64), U256::exp10(20) * 5, 100);
+        cache.update(address(12), U256::zero(), U256::exp10(18), 100);
+        let fees = FeeSettings {
+            factories: [(address(21), 2000)].into(),
+            ..Default::default()
+        };
+
+        let graph = GraphExport::build(&[usdc, wmatic, weth, dust], &pairs, &cache.snapshot(), &fees, &tokens, 100);
+        assert_eq!(graph.edges.len(), 2);
+        assert_eq!(graph.edges.iter().map(|edge| edge.fee).collect::<Vec<_>>(), vec![3000, 2000]);
+        assert_eq!(graph.nodes.iter().map(|node| node.pools).collect::<Vec<_>>(), vec![2, 2, 0, 0]);
+        assert_eq!(graph.isolated().map(|node| node.address).collect::<Vec<_>>(), vec![weth, dust]);
+
+        let dot = graph.to_dot();
+        assert!(dot.contains("[label=\"0.30%\\n2.00M / 2.00M\", penwidth=5.0"), "{dot}");
+        assert!(dot.contains("[label=\"0.20%\\n500.00 / 500.00\", penwidth=1.0"), "{dot}");
+        assert!(dot.contains(&format!("\"{:?}\" [label=\"{}\", style=dashed, color=red];", weth, tokens.symbol(&weth))));
+
+        let json: serde_json::Value = serde_json::from_str(&graph.to_json().unwrap()).unwrap();
+        assert_eq!(json["edges"][1]["fee"], 2000);
+        assert_eq!(json["nodes"][0]["symbol"], "USDC");
+    }
+}
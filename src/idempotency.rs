@@ -0,0 +1,106 @@
+// src/idempotency.rs
+use ethers::{
+    abi::{encode, Token},
+    types::{Address, H256},
+    utils::keccak256,
+};
+use log::warn;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+// Blocks that share one idempotency bucket (Polygon: ~2s each)
+pub const DEFAULT_WINDOW_BLOCKS: u64 = 3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct IdempotencyKey(pub H256);
+
+impl IdempotencyKey {
+    // keccak256(abi.encode(pools, tokenIn, tokenOut, block / window))
+    pub fn new(
+        pools: &[Address],
+        token_in: Address,
+        token_out: Address,
+        block: u64,
+        window_blocks: u64,
+    ) -> Self {
+        let bucket = block / window_blocks.max(1);
+        let encoded = encode(&[
+            Token::Array(pools.iter().map(|&pool| Token::Address(pool)).collect()),
+            Token::Address(token_in),
+            Token::Address(token_out),
+            Token::Uint(bucket.into()),
+        ]);
+        Self(H256::from(keccak256(encoded)))
+    }
+}
+
+// Remembers which opportunities were already submitted so that two detectors
+// (mempool monitor, block polling) never send the same arb twice
+#[derive(Debug)]
+pub struct IdempotencyGuard {
+    window_blocks: u64,
+    submitted: Mutex<HashMap<IdempotencyKey, u64>>,
+}
+
+impl Default for IdempotencyGuard {
+    fn default() -> Self {
+        Self::new(DEFAULT_WINDOW_BLOCKS)
+    }
+}
+
+impl IdempotencyGuard {
+    pub fn new(window_blocks: u64) -> Self {
+        Self {
+            window_blocks,
+            submitted: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn key(&self, pools: &[Address], token_in: Address, token_out: Address, block: u64) -> IdempotencyKey {
+        IdempotencyKey::new(pools, token_in, token_out, block, self.window_blocks)
+    }
+
+    // Returns false if the key was already claimed; the caller must then skip execution
+    pub fn try_acquire(&self, key: IdempotencyKey, block: u64) -> bool {
+        let mut submitted = self.submitted.lock().unwrap();
+
+        // Keys from buckets long gone can never collide again
+        let horizon = self.window_blocks * 2;
+        submitted.retain(|_, &mut seen| block.saturating_sub(seen) <= horizon);
+
+        if submitted.contains_key(&key) {
+            warn!("Skipping duplicate opportunity {:?}", key.0);
+            return false;
+        }
+
+        submitted.insert(key, block);
+        true
+    }
+
+    // Release a key whose submission failed before reaching the network
+    pub fn release(&self, key: &IdempotencyKey) {
+        self.submitted.lock().unwrap().remove(key);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_same_spread_in_window_is_rejected() {
+        let guard = IdempotencyGuard::new(3);
+        let pools = [Address::from_low_u64_be(1), Address::from_low_u64_be(2)];
+        let (token_in, token_out) = (Address::from_low_u64_be(10), Address::from_low_u64_be(11));
+
+        let key = guard.key(&pools, token_in, token_out, 99);
+        assert!(guard.try_acquire(key, 99));
+        assert!(!guard.try_acquire(guard.key(&pools, token_in, token_out, 100), 100));
+
+        // Opposite direction is a different trade
+        assert!(guard.try_acquire(guard.key(&pools, token_out, token_in, 100), 100));
+
+        // Next bucket may execute again
+        assert!(guard.try_acquire(guard.key(&pools, token_in, token_out, 102), 102));
+    }
+}
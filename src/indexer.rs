@@ -0,0 +1,201 @@
+// src/indexer.rs
+use anyhow::{anyhow, Result};
+use ethers::{
+    providers::{Middleware, PubsubClient},
+    types::{Address, Filter, Log, U64},
+};
+use futures::StreamExt;
+use log::{debug, info, warn};
+use serde::Deserialize;
+use std::collections::{hash_map::Entry, HashMap};
+use std::sync::Arc;
+
+use crate::pool_events::{decode_pool_event, event_signatures, PoolEvent};
+use crate::reserve_cache::{PoolReserves, ReserveCache};
+use crate::shared_reserves::SharedReserves;
+use crate::store::{PoolEventRecord, Store};
+
+// Key under which the indexer checkpoints its progress in the store
+const CHECKPOINT: &str = "pool_events";
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct IndexerSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    // How far back to start when the store has no checkpoint yet
+    #[serde(default = "default_backfill_blocks")]
+    pub backfill_blocks: u64,
+    // Block range per eth_getLogs request during backfill
+    #[serde(default = "default_chunk_size")]
+    pub chunk_size: u64,
+}
+
+fn default_backfill_blocks() -> u64 {
+    5_000
+}
+
+fn default_chunk_size() -> u64 {
+    2_000
+}
+
+impl Default for IndexerSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            backfill_blocks: default_backfill_blocks(),
+            chunk_size: default_chunk_size(),
+        }
+    }
+}
+
+// Ingests Swap/Sync/Mint/Burn logs of the watched pools into the store:
+// backfills from the last checkpoint, then follows new logs live
+pub struct PoolEventIndexer<M> {
+    provider: Arc<M>,
+    store: Store,
+    pools: Vec<Address>,
+    settings: IndexerSettings,
+    reserve_cache: Option<ReserveCache>,
+    shared: Option<Arc<SharedReserves>>,
+}
+
+impl<M> PoolEventIndexer<M>
+where
+    M: Middleware + 'static,
+    M::Provider: PubsubClient,
+{
+    pub fn new(provider: Arc<M>, store: Store, pools: Vec<Address>, settings: IndexerSettings) -> Self {
+        Self {
+            provider,
+            store,
+            pools,
+            settings,
+            reserve_cache: None,
+            shared: None,
+        }
+    }
+
+    // Sync events also refresh the shared reserve cache
+    pub fn with_reserve_cache(mut self, cache: ReserveCache) -> Self {
+        self.reserve_cache = Some(cache);
+        self
+    }
+
+    // Sync events are written through to the cache other instances pull from
+    pub fn with_shared_reserves(mut self, shared: Option<Arc<SharedReserves>>) -> Self {
+        self.shared = shared;
+        self
+    }
+
+    fn filter(&self) -> Filter {
+        Filter::new()
+            .address(self.pools.clone())
+            .topic0(event_signatures())
+    }
+
+    pub async fn run(&self) -> Result<()> {
+        if self.pools.is_empty() {
+            warn!("Pool event indexer has no pools to watch");
+            return Ok(());
+        }
+
+        let head = self.backfill().await?;
+        info!("Pool event indexer caught up to block {}, following live logs", head);
+
+        let filter = self.filter();
+        let mut stream = self.provider.subscribe_logs(&filter).await
+            .map_err(|e| anyhow!("Failed to subscribe to pool logs: {e}"))?;
+
+        while let Some(log) = stream.next().await {
+            // Logs of a reorged block are replaced by their canonical versions
+            if log.removed == Some(true) {
+                continue;
+            }
+            let block = log.block_number.map(|b| b.as_u64()).unwrap_or_default();
+            self.ingest(&[log]).await?;
+            self.store.set_last_indexed_block(CHECKPOINT, block)?;
+        }
+
+        Err(anyhow!("Pool log subscription ended"))
+    }
+
+    // Fetch everything between the checkpoint and the current head; returns the head
+    async fn backfill(&self) -> Result<u64> {
+        let head = self.provider.get_block_number().await
+            .map_err(|e| anyhow!("Failed to fetch block number: {e}"))?
+            .as_u64();
+        let mut from = match self.store.last_indexed_block(CHECKPOINT)? {
+            Some(last) => last + 1,
+            None => head.saturating_sub(self.settings.backfill_blocks),
+        };
+        let chunk = self.settings.chunk_size.max(1);
+
+        while from <= head {
+            let to = (from + chunk - 1).min(head);
+            let filter = self.filter().from_block(from).to_block(to);
+            let logs = self.provider.get_logs(&filter).await
+                .map_err(|e| anyhow!("Failed to fetch pool logs {from}..{to}: {e}"))?;
+
+            let inserted = self.ingest(&logs).await?;
+            self.store.set_last_indexed_block(CHECKPOINT, to)?;
+            debug!("Indexed blocks {}..{}: {} new pool events", from, to, inserted);
+            from = to + 1;
+        }
+
+        Ok(head)
+    }
+
+    async fn ingest(&self, logs: &[Log]) -> Result<usize> {
+        let mut timestamps: HashMap<U64, Option<u64>> = HashMap::new();
+        let mut records = Vec::with_capacity(logs.len());
+        let mut synced = Vec::new();
+
+        for log in logs {
+            let (Some(block_number), Some(tx_hash), Some(log_index)) =
+                (log.block_number, log.transaction_hash, log.log_index)
+            else {
+                continue;
+            };
+            let Some(event) = decode_pool_event(log) else {
+                continue;
+            };
+
+            if let Entry::Vacant(slot) = timestamps.entry(block_number) {
+                let timestamp = self.provider.get_block(block_number).await
+                    .ok()
+                    .flatten()
+                    .map(|block| block.timestamp.as_u64());
+                slot.insert(timestamp);
+            }
+
+            if let PoolEvent::Sync(sync) = &event {
+                let reserves = PoolReserves {
+                    reserve0: sync.reserve_0.into(),
+                    reserve1: sync.reserve_1.into(),
+                    block_number: block_number.as_u64(),
+                };
+                if let Some(cache) = &self.reserve_cache {
+                    cache.update(log.address, reserves.reserve0, reserves.reserve1, reserves.block_number);
+                }
+                synced.push((log.address, reserves));
+            }
+
+            records.push(PoolEventRecord::from_event(
+                &event,
+                log.address,
+                block_number.as_u64(),
+                log_index.as_u64(),
+                tx_hash,
+                timestamps[&block_number],
+            ));
+        }
+
+        // Instances fall back to their own reads while the shared cache is unreachable
+        if let Some(shared) = &self.shared {
+            if let Err(e) = shared.publish(&synced).await {
+                warn!("Shared reserve write-through failed: {:?}", e);
+            }
+        }
+        self.store.insert_pool_events(&records)
+    }
+}
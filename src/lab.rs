@@ -0,0 +1,207 @@
+// src/lab.rs
+use ethers::types::{Address, U256};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::gas_oracle::GasCost;
+use crate::token_registry::TokenRegistry;
+
+// Detection paths evaluated by the lab
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Strategy {
+    MempoolBackrun,
+    BlockCycle,
+}
+
+impl Strategy {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Strategy::MempoolBackrun => "mempool_backrun",
+            Strategy::BlockCycle => "block_cycle",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+struct StrategyStats {
+    evaluated: u64,
+    opportunities: u64,
+    profit: HashMap<Address, U256>,
+    gas_cost: GasCost,
+    latencies_ms: Vec<u64>,
+}
+
+#[derive(Debug)]
+struct LabWindow {
+    started: Instant,
+    stats: BTreeMap<Strategy, StrategyStats>,
+}
+
+impl Default for LabWindow {
+    fn default() -> Self {
+        Self {
+            started: Instant::now(),
+            stats: BTreeMap::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct LatencySummary {
+    pub p50_ms: u64,
+    pub p95_ms: u64,
+    pub max_ms: u64,
+}
+
+impl LatencySummary {
+    pub fn from_samples(samples: &[u64]) -> Self {
+        if samples.is_empty() {
+            return Self::default();
+        }
+        let mut sorted = samples.to_vec();
+        sorted.sort_unstable();
+        let percentile = |p: usize| sorted[((sorted.len() - 1) * p) / 100];
+        Self {
+            p50_ms: percentile(50),
+            p95_ms: percentile(95),
+            max_ms: *sorted.last().unwrap(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct StrategyReport {
+    pub strategy: Strategy,
+    pub evaluated: u64,
+    pub opportunities: u64,
+    pub profit: Vec<(Address, U256)>,
+    // Gas the hypothetical submissions would have paid, split into burn / tips / bids
+    pub gas_cost: GasCost,
+    pub latency: LatencySummary,
+}
+
+#[derive(Debug, Clone)]
+pub struct LabReport {
+    pub period: Duration,
+    pub strategies: Vec<StrategyReport>,
+}
+
+impl LabReport {
+    pub fn render(&self, tokens: &TokenRegistry) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "Lab report for the last {} min", self.period.as_secs() / 60);
+        if self.strategies.is_empty() {
+            let _ = write!(out, "  nothing evaluated");
+            return out;
+        }
+
+        for report in &self.strategies {
+            let _ = writeln!(
+                out,
+                "  {}: {} evaluated, {} opportunities, gas {}, latency p50 {} ms / p95 {} ms / max {} ms",
+                report.strategy.as_str(),
+                report.evaluated,
+                report.opportunities,
+                report.gas_cost,
+                report.latency.p50_ms,
+                report.latency.p95_ms,
+                report.latency.max_ms,
+            );
+            for (token, profit) in &report.profit {
+                let _ = writeln!(out, "    gross profit {}", tokens.format_amount(token, *profit));
+            }
+        }
+        out.trim_end().to_string()
+    }
+}
+
+// Collects what the strategies would have done in dry-run; shared by all detection loops
+#[derive(Debug, Clone, Default)]
+pub struct LabRecorder {
+    inner: Arc<Mutex<LabWindow>>,
+}
+
+impl LabRecorder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record_evaluation(&self, strategy: Strategy) {
+        self.inner.lock().unwrap().stats.entry(strategy).or_default().evaluated += 1;
+    }
+
+    pub fn record_opportunity(
+        &self,
+        strategy: Strategy,
+        token: Address,
+        profit: U256,
+        gas_cost: GasCost,
+        latency: Duration,
+    ) {
+        let mut window = self.inner.lock().unwrap();
+        let stats = window.stats.entry(strategy).or_default();
+        stats.opportunities += 1;
+        *stats.profit.entry(token).or_default() += profit;
+        stats.gas_cost += gas_cost;
+        stats.latencies_ms.push(latency.as_millis() as u64);
+    }
+
+    // Closes the current window and starts a new one
+    pub fn take_report(&self) -> LabReport {
+        let window = std::mem::take(&mut *self.inner.lock().unwrap());
+        LabReport {
+            period: window.started.elapsed(),
+            strategies: window
+                .stats
+                .into_iter()
+                .map(|(strategy, stats)| {
+                    let mut profit: Vec<(Address, U256)> = stats.profit.into_iter().collect();
+                    profit.sort();
+                    StrategyReport {
+                        strategy,
+                        evaluated: stats.evaluated,
+                        opportunities: stats.opportunities,
+                        profit,
+                        gas_cost: stats.gas_cost,
+                        latency: LatencySummary::from_samples(&stats.latencies_ms),
+                    }
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_report_aggregates_and_resets() {
+        let lab = LabRecorder::new();
+        let token = Address::from_low_u64_be(1);
+        for ms in [10, 20, 30, 40, 1_000] {
+            lab.record_evaluation(Strategy::BlockCycle);
+            lab.record_opportunity(
+                Strategy::BlockCycle,
+                token,
+                U256::from(2u64),
+                GasCost { burned: U256::from(1u64), ..Default::default() },
+                Duration::from_millis(ms),
+            );
+        }
+        lab.record_evaluation(Strategy::MempoolBackrun);
+
+        let report = lab.take_report();
+        assert_eq!(report.strategies.len(), 2);
+        let cycle = &report.strategies[1];
+        assert_eq!(cycle.strategy, Strategy::BlockCycle);
+        assert_eq!(cycle.opportunities, 5);
+        assert_eq!(cycle.profit, vec![(token, U256::from(10u64))]);
+        assert_eq!(cycle.gas_cost.total(), U256::from(5u64));
+        assert_eq!(cycle.latency, LatencySummary { p50_ms: 30, p95_ms: 40, max_ms: 1_000 });
+
+        assert!(lab.take_report().strategies.is_empty());
+    }
+}
@@ -1,43 +1,175 @@
 // Modules
 pub mod simulation_engine;
 pub mod fastlane_integration;
+pub mod bindings;
 pub mod routers;
+pub mod drift_monitor;
+pub mod settings;
+pub mod preflight;
+pub mod amm_math;
+pub mod reserve_cache;
+pub mod opportunity_tracker;
+pub mod logging;
+pub mod control_api;
+pub mod idempotency;
+pub mod gas_oracle;
+pub mod profit_threshold;
+pub mod route_search;
+pub mod pool_events;
+pub mod store;
+pub mod indexer;
+pub mod token_registry;
+pub mod executor;
+pub mod metrics;
+pub mod alerts;
+pub mod reserve_watchdog;
+pub mod setup;
+pub mod lab;
+pub mod volatility;
+pub mod tx_sender;
+pub mod access_list;
+pub mod mempool;
+pub mod propagation;
+pub mod opportunity_class;
+pub mod submission;
+pub mod erc20;
+pub mod nonce_watchdog;
+pub mod permit;
+pub mod top_of_book;
+pub mod v3_math;
+pub mod v3_state;
+pub mod v3_discovery;
+pub mod treasury;
+pub mod dashboard;
+pub mod cooldown;
+pub mod deploy;
+pub mod dedup;
+pub mod executor_events;
+pub mod bid_market;
+pub mod report;
+pub mod validation;
+pub mod profit_token;
+pub mod prefetch;
+pub mod callback_gas;
+pub mod execution_mode;
+pub mod reconciliation;
+pub mod self_trade;
+pub mod optimizer;
+pub mod token_tax;
+pub mod bundle_fanout;
+pub mod opportunity_codec;
+pub mod opportunity_queue;
+pub mod rpc_usage;
+pub mod operator_controls;
+pub mod pool_kind;
+pub mod two_phase;
+pub mod sim_cache;
+pub mod wallet_watcher;
+pub mod graph_export;
+pub mod post_mortem;
+pub mod pipeline;
+pub mod prefilter;
+pub mod rpc_batch;
+pub mod pair_focus;
+pub mod slippage;
+pub mod admin_watch;
+pub mod flash_source;
+pub mod spread_filter;
+pub mod round_trip;
+pub mod shared_reserves;
+pub mod toxic_flow;
+pub mod v3_positions;
+pub mod yield_wrap;
+pub mod bundle_simulation;
+pub mod failure_taxonomy;
+pub mod doctor;
+pub mod split_route;
+pub mod block_pipeline;
+pub mod opportunity_id;
+pub mod executor_whitelist;
+pub mod exposure;
+pub mod parquet;
+pub mod parquet_export;
+pub mod spread_predictor;
+pub mod shadow;
+pub mod fee_watch;
+#[cfg(test)]
+pub(crate) mod fixtures;
 
 // Contract bindings via abigen!
-// These generate structs in the current crate, so we can re-export them
-pub use fastlane_integration::{FlashLoanArbitrage, FastLaneSender};
+// These generate structs in the bindings module, so we can re-export them
+pub use bindings::*;
 
 // Ethers imports
 use ethers::{
     prelude::*,
-    core::types::{BlockNumber, Filter, U256, U64, Address, TransactionReceipt},
-    providers::{Provider, Http, Middleware},
+    core::types::{BlockId, BlockNumber, Filter, U256, U64, Address, TransactionReceipt},
+    core::types::transaction::eip2718::TypedTransaction,
+    providers::{Provider, Middleware},
     signers::LocalWallet,
 };
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::error::Error;
 use std::collections::HashMap;
-use std::time::Duration;
-use ethers_contract::abigen;
-
-// Abigen! generated contract structs (they live in this crate)
-abigen!(
-    FlashLoanArbitrage,
-    "./abis/FlashLoanArbitrage.json",
-    event_derives(serde::Serialize, serde::Deserialize)
-);
-
-abigen!(
-    FastLaneSender,
-    "./abis/FastLaneSender.json",
-    event_derives(serde::Serialize, serde::Deserialize)
-);
-
-abigen!(
-    IUniswapV2Pair,
-    "./abis/IUniswapV2Pair.json",
-    event_derives(serde::Serialize, serde::Deserialize)
-);
+use std::time::{Duration, Instant};
+use log::{debug, info, warn};
+
+use drift_monitor::{DriftConfig, DriftMonitor, SimulatedHop};
+use settings::{DiscoveryMode, DiscoverySettings};
+use reserve_cache::{ReserveCache, ReserveSnapshot};
+use opportunity_tracker::OpportunityTracker;
+use cooldown::{CooldownSettings, PoolCooldown};
+use idempotency::IdempotencyGuard;
+use dedup::{DedupKey, DedupTicket, OpportunityDedup};
+use opportunity_id::OpportunityId;
+use executor_events::{ExecutorEventSettings, ExecutorEvents};
+use executor::DeadlineSettings;
+use two_phase::TwoPhaseSettings;
+use sim_cache::{SimCacheSettings, SimKey, SimulationCache};
+use slippage::{curve_profit, SlippageCurves, SlippageSettings};
+use spread_filter::{SpreadCheck, SpreadFilter, SpreadFilterSettings};
+use round_trip::{RoundTrip, RoundTripSettings};
+use shared_reserves::{SharedReserveSettings, SharedReserves};
+use toxic_flow::{ToxicFlow, ToxicFlowSettings};
+use block_pipeline::{swap_through, BlockPipeline, BlockPipelineSettings};
+use executor_whitelist::{ExecutorWhitelist, ExecutorWhitelistSettings};
+use exposure::{ExposureLimit, InFlight};
+use spread_predictor::{HeuristicPredictor, SpreadFeatures, SpreadPredictor};
+use shadow::{EnginePick, ShadowEngine, ShadowSettings};
+use fee_watch::{FeeKind, FeeWatch, FeeWatchSettings};
+use yield_wrap::{YieldWrapSettings, YieldWraps};
+use gas_oracle::{GasCeilingSettings, GasForecastSettings, GasOracle};
+use profit_threshold::ProfitThresholdSettings;
+use route_search::{RouteSearch, RouteSearchSettings, SpreadHistory, TokenGraph};
+use amm_math::{best_dual_amounts, get_amount_out, route_profit, DualAssetSettings, FeeSettings};
+use alerts::Alerts;
+use lab::{LabRecorder, Strategy};
+use volatility::{RiskAdjustment, VolatilityTracker};
+use reserve_watchdog::{ReserveWatchdog, ReserveWatchdogSettings};
+use nonce_watchdog::{NonceWatchdog, NonceWatchdogSettings};
+use wallet_watcher::{WalletWatcher, WalletWatcherSettings};
+use pair_focus::{FocusSettings, PairFocus};
+use top_of_book::TopOfBook;
+use v3_discovery::{V3Discovery, V3DiscoverySettings};
+use v3_state::{V3Settings, V3StateMirror};
+use tx_sender::TxSender;
+use treasury::{Treasury, TreasurySettings};
+use store::{GasSpendRecord, Store};
+use access_list::{AccessListBuilder, AccessListSettings};
+use rpc_usage::MeteredHttp;
+use operator_controls::OperatorControls;
+use opportunity_class::ClassSettings;
+use profit_token::ProfitTokenSettings;
+use prefetch::{PairPrefetcher, PrefetchSettings};
+use callback_gas::FlashProvider;
+use execution_mode::ExecutionModes;
+use reconciliation::{BalanceReconciler, ReconciliationSettings};
+use self_trade::{SelfTradeGuard, Venue};
+use optimizer::OptimizerSettings;
+use token_tax::{TaxProbe, TokenTaxSettings};
+use token_registry::{TokenRegistry, TokenTax};
+use metrics::{SIZING_SOURCE_TOTAL, SPREAD_FILTERED_TOTAL, TOXIC_FLOW_TOTAL};
+use failure_taxonomy::{record_failure, record_outcome, FailureReason, RevertClass};
 
 // Constants
 const QUICKSWAP_FACTORY: &str = "0x5757371414417b8C6CAad45bAeF941aBc7d3Ab32";
@@ -50,19 +182,137 @@ const SUSHISWAP_ROUTER: &str = "0x1b02dA8Cb0d097eB8D57A175b88c7D8b47997506";
 // Default V3 fee tier (if you hit V2-only hops it’s ignored on-chain)
 const DEFAULT_FEE_U24: u32 = 3000;
 
-// Minimum perceived profit in wei to consider (your existing constant)
-const MINIMUM_PROFIT_WEI: u128 = 50_000_000_000_000_000; // 0.05 MATIC
+// A single-borrow route as re-priced in the commit phase of `send_execution`
+struct CommitPlan<'a> {
+    // The route's hops as simulated; the commit re-quotes them and keeps what it sent
+    hops: &'a mut [SimulatedHop],
+    amount_in: U256,
+    // Executor calldata for the given hop inputs
+    encode: &'a (dyn Fn(&[U256]) -> Option<Bytes> + Sync),
+}
+
+// The executeFlashLoanArbitrage call of a route but for its hop inputs; the first hop's
+// input is the borrowed amount0
+struct FlashLoanCall {
+    token0: Address,
+    token1: Address,
+    amount1: U256,
+    fee: u32,
+    path: Vec<Address>,
+    routers: Vec<Address>,
+    deadline: U256,
+}
+
+impl FlashLoanCall {
+    fn calldata<M: Middleware>(&self, contract: &FlashLoanArbitrage<M>, amounts: &[U256]) -> Option<Bytes> {
+        contract
+            .method::<_, ()>(
+                "executeFlashLoanArbitrage",
+                (
+                    self.token0,
+                    self.token1,
+                    *amounts.first()?,
+                    self.amount1,
+                    self.fee,
+                    self.path.clone(),
+                    amounts.to_vec(),
+                    self.routers.clone(),
+                    self.deadline,
+                ),
+            )
+            .ok()?
+            .calldata()
+    }
+}
+
+// How long a detected-but-unexecuted spread is carried across blocks
+const TRACKED_OPPORTUNITY_MAX_AGE_BLOCKS: u64 = 10;
 
 
 #[derive(Debug, Clone)]
-pub struct MevBot {
-    provider: Arc<Provider<Http>>,
-    flash_loan_contract: FlashLoanArbitrage<Provider<Http>>,
-    fast_lane_sender: FastLaneSender<Provider<Http>>,
-    wallet: LocalWallet,
+pub struct MevBot<T = MeteredHttp> {
+    provider: Arc<Provider<T>>,
+    flash_loan_contract: FlashLoanArbitrage<Provider<T>>,
+    fast_lane_sender: FastLaneSender<Provider<T>>,
+    tx_sender: TxSender<Provider<T>>,
+    access_lists: AccessListBuilder<Provider<T>>,
     dex_factories: Vec<Address>,
     token_pairs: HashMap<Address, Vec<Address>>,
     last_block: U64,
+    drift_monitor: Arc<DriftMonitor>,
+    discovery: DiscoverySettings,
+    reserve_cache: ReserveCache,
+    // Reserves other instances write through, pulled into `reserve_cache`
+    shared_reserves: Option<Arc<SharedReserves>>,
+    tracker: OpportunityTracker,
+    idempotency: Arc<IdempotencyGuard>,
+    dedup: OpportunityDedup,
+    cooldown: PoolCooldown,
+    gas_oracle: Arc<GasOracle<Provider<T>>>,
+    gas_ceiling: GasCeilingSettings,
+    profit_threshold: ProfitThresholdSettings,
+    pool_tokens: HashMap<Address, (Address, Address)>,
+    // Factory each discovered pool came from, for its swap fee
+    pool_factories: HashMap<Address, Address>,
+    fees: FeeSettings,
+    // Re-reads fees V2 forks and Algebra pools can change, into `fees` and `v3_pools`
+    fee_watch: Option<FeeWatch<Provider<T>>>,
+    // Validity window written into executor calldata
+    deadline: DeadlineSettings,
+    two_phase: TwoPhaseSettings,
+    sim_cache: Arc<SimulationCache>,
+    dual_asset: DualAssetSettings,
+    // Flash premium and repayment of single-token loans
+    round_trip: RoundTripSettings,
+    optimizer: OptimizerSettings,
+    tax_probe: Option<TaxProbe<Provider<T>>>,
+    profit_tokens: ProfitTokenSettings,
+    // Pairs of the token universe or the allowlist were looked up once, no per-block rediscovery
+    prefetched: bool,
+    modes: ExecutionModes,
+    // Pause, profit floor, blacklist and sweep requests from the control API
+    controls: OperatorControls,
+    route_search: Arc<Mutex<RouteSearch>>,
+    route_search_settings: RouteSearchSettings,
+    spread_history: Arc<Mutex<SpreadHistory>>,
+    // Second optimizer/route search configuration compared against the live one, never executed
+    shadow: Option<ShadowEngine>,
+    // Which pool pairs' price gaps count as spreads
+    spread_filter: SpreadFilter,
+    // Pools hit by a large swap in the last blocks, whose spreads next-block trades rarely win
+    toxic_flow: ToxicFlow,
+    // Executions running while the next block is scanned, and the reserves they leave behind
+    pipeline: BlockPipeline,
+    // Routers and tokens the executor contract accepts; pools outside it are never routed
+    whitelist: ExecutorWhitelist,
+    // aToken wrap/unwrap steps the route search may take, as pseudo-pools
+    yield_wraps: YieldWraps,
+    // Pairs scanned every block; the rest of the universe less often
+    focus: Arc<PairFocus>,
+    // Per-pool output against input size of the current block, for sizing
+    slippage: SlippageCurves,
+    alerts: Alerts,
+    watchdog: ReserveWatchdogSettings,
+    nonce_watchdog: NonceWatchdogSettings,
+    wallet_watcher: WalletWatcherSettings,
+    dry_run: Option<LabRecorder>,
+    round_started: Instant,
+    volatility: Option<Arc<VolatilityTracker>>,
+    classes: ClassSettings,
+    book: Option<TopOfBook>,
+    v3_pools: V3StateMirror,
+    v3: V3Settings,
+    v3_discovery: Option<V3Discovery<Provider<T>>>,
+    treasury: Option<Treasury<Provider<T>>>,
+    executor_events: Option<ExecutorEvents<Provider<T>>>,
+    reconciler: Option<BalanceReconciler<Provider<T>>>,
+    self_trade: SelfTradeGuard,
+    // Borrowed notional of pending executions, capped across strategies
+    exposure: ExposureLimit,
+    // How long tracked spreads are expected to stay open, for their execution order
+    spread_predictor: Arc<dyn SpreadPredictor>,
+    // Gas of mined executions, the wallet side of the reconciliation ledger
+    gas_journal: Option<Store>,
 }
 
 impl MevBot {
@@ -72,12 +322,25 @@ impl MevBot {
         flash_loan_address: Address,
         fast_lane_address: Address,
     ) -> Result<Self, Box<dyn Error>> {
-        let provider = Provider::<Http>::try_from(rpc_url)?;
-        let provider = Arc::new(provider);
+        let provider = Arc::new(rpc_usage::http(rpc_url, "http")?);
 
         let wallet = private_key.parse::<LocalWallet>()?;
         let wallet = wallet.with_chain_id(137u64); // Polygon Mainnet
 
+        let last_block = provider.get_block_number().await?;
+        Self::with_provider(provider, wallet, flash_loan_address, fast_lane_address, last_block)
+    }
+}
+
+impl<T: JsonRpcClient + Clone + 'static> MevBot<T> {
+    // Everything `new` does but connecting, so tests can run the bot on a mocked transport
+    fn with_provider(
+        provider: Arc<Provider<T>>,
+        wallet: LocalWallet,
+        flash_loan_address: Address,
+        fast_lane_address: Address,
+        last_block: U64,
+    ) -> Result<Self, Box<dyn Error>> {
         let flash_loan_contract = FlashLoanArbitrage::new(flash_loan_address, provider.clone());
         let fast_lane_sender = FastLaneSender::new(fast_lane_address, provider.clone());
 
@@ -86,65 +349,962 @@ impl MevBot {
             SUSHISWAP_FACTORY.parse::<Address>()?,
         ];
 
-        let last_block = provider.get_block_number().await?;
+        let gas_oracle = Arc::new(GasOracle::new(provider.clone()));
+        let route_search_settings = RouteSearchSettings::default();
+        let tx_sender = TxSender::new(provider.clone(), wallet);
+        let access_lists = AccessListBuilder::new(provider.clone(), AccessListSettings::default());
 
         Ok(Self {
             provider,
             flash_loan_contract,
             fast_lane_sender,
-            wallet,
+            tx_sender,
+            access_lists,
             dex_factories,
             token_pairs: HashMap::new(),
             last_block,
+            drift_monitor: Arc::new(DriftMonitor::new(DriftConfig::default())),
+            discovery: DiscoverySettings::default(),
+            reserve_cache: ReserveCache::new(),
+            shared_reserves: None,
+            tracker: OpportunityTracker::new(TRACKED_OPPORTUNITY_MAX_AGE_BLOCKS),
+            idempotency: Arc::new(IdempotencyGuard::default()),
+            dedup: OpportunityDedup::new(),
+            cooldown: PoolCooldown::new(&CooldownSettings::default()),
+            gas_oracle,
+            gas_ceiling: GasCeilingSettings::default(),
+            profit_threshold: ProfitThresholdSettings::default(),
+            pool_tokens: HashMap::new(),
+            pool_factories: HashMap::new(),
+            fees: FeeSettings::default(),
+            fee_watch: None,
+            deadline: DeadlineSettings::default(),
+            two_phase: TwoPhaseSettings::default(),
+            sim_cache: Arc::new(SimulationCache::new(SimCacheSettings::default())),
+            dual_asset: DualAssetSettings::default(),
+            round_trip: RoundTripSettings::default(),
+            optimizer: OptimizerSettings::default(),
+            tax_probe: None,
+            profit_tokens: ProfitTokenSettings::default(),
+            prefetched: false,
+            modes: ExecutionModes::default(),
+            controls: OperatorControls::default(),
+            route_search: Arc::new(Mutex::new(RouteSearch::new(
+                route_search_settings.max_hops,
+                route_search_settings.max_nodes_per_block,
+            ))),
+            route_search_settings,
+            spread_history: Arc::new(Mutex::new(SpreadHistory::default())),
+            shadow: None,
+            spread_filter: SpreadFilter::default(),
+            toxic_flow: ToxicFlow::new(ToxicFlowSettings::default()),
+            pipeline: BlockPipeline::new(BlockPipelineSettings::default()),
+            whitelist: ExecutorWhitelist::default(),
+            yield_wraps: YieldWraps::default(),
+            focus: Arc::new(PairFocus::new(FocusSettings::default())),
+            slippage: SlippageCurves::new(SlippageSettings::default()),
+            alerts: Alerts::default(),
+            watchdog: ReserveWatchdogSettings::default(),
+            nonce_watchdog: NonceWatchdogSettings::default(),
+            wallet_watcher: WalletWatcherSettings::default(),
+            dry_run: None,
+            round_started: Instant::now(),
+            volatility: None,
+            classes: ClassSettings::default(),
+            book: None,
+            v3_pools: V3StateMirror::new(),
+            v3: V3Settings::default(),
+            v3_discovery: None,
+            treasury: None,
+            executor_events: None,
+            reconciler: None,
+            self_trade: SelfTradeGuard::default(),
+            exposure: ExposureLimit::default(),
+            spread_predictor: Arc::new(HeuristicPredictor::default()),
+            gas_journal: None,
         })
     }
 
+    // Hop limit and per-block expansion budget of the best-first route search
+    pub fn with_route_search(mut self, settings: RouteSearchSettings) -> Self {
+        self.route_search = Arc::new(Mutex::new(RouteSearch::new(
+            settings.max_hops,
+            settings.max_nodes_per_block,
+        )));
+        self.route_search_settings = settings;
+        self
+    }
+
+    // Pull reserves from the Redis cache the indexing instance writes through to
+    pub fn with_shared_reserves(mut self, settings: SharedReserveSettings) -> Result<Self, Box<dyn Error>> {
+        self.shared_reserves = SharedReserves::new(settings)?.map(Arc::new);
+        Ok(self)
+    }
+
+    // Periodic spot-check of cached reserves against fresh RPC reads
+    pub fn with_reserve_watchdog(mut self, settings: ReserveWatchdogSettings) -> Self {
+        self.watchdog = settings;
+        self
+    }
+
+    // Nonce gap detection and filling on the signing wallet
+    pub fn with_nonce_watchdog(mut self, settings: NonceWatchdogSettings) -> Self {
+        self.nonce_watchdog = settings;
+        self
+    }
+
+    // Alerts on, and resyncs after, transactions from the signing wallet the bot did not send
+    pub fn with_wallet_watcher(mut self, settings: WalletWatcherSettings) -> Self {
+        self.wallet_watcher = settings;
+        self
+    }
+
+    // Claims shared with the other detectors, so a spread they also found executes once
+    pub fn with_dedup(mut self, dedup: OpportunityDedup) -> Self {
+        self.dedup = dedup;
+        self
+    }
+
+    // Venue reservations shared with the mempool strategies, so they never trade a pool this
+    // bot arbitrages in the same target block
+    pub fn with_self_trade_guard(mut self, guard: SelfTradeGuard) -> Self {
+        self.self_trade = guard;
+        self
+    }
+
+    // Shared with the other strategies, so the in-flight cap covers all of them
+    pub fn with_exposure(mut self, exposure: ExposureLimit) -> Self {
+        self.exposure = exposure;
+        self
+    }
+
+    // Persistence model ordering tracked executions; the heuristic one unless replaced
+    pub fn with_spread_predictor(mut self, predictor: Arc<dyn SpreadPredictor>) -> Self {
+        self.spread_predictor = predictor;
+        self
+    }
+
+    // Blocks each pool is left alone after we executed through it
+    pub fn with_cooldown(mut self, settings: CooldownSettings) -> Self {
+        self.cooldown = PoolCooldown::new(&settings);
+        self
+    }
+
+    // Price gas at the base fee forecast for the target block rather than the latest header
+    pub fn with_gas_forecast(mut self, settings: GasForecastSettings) -> Self {
+        self.gas_oracle = Arc::new(GasOracle::new(self.provider.clone()).with_forecast(settings));
+        self
+    }
+
+    pub fn with_access_lists(mut self, settings: AccessListSettings) -> Self {
+        self.access_lists = AccessListBuilder::new(self.provider.clone(), settings);
+        self
+    }
+
+    // Record profitable routes into the lab instead of executing them
+    pub fn with_dry_run(mut self, lab: LabRecorder) -> Self {
+        self.dry_run = Some(lab);
+        self
+    }
+
+    // Shrink sizes and widen margins on pairs with high realized volatility
+    pub fn with_volatility(mut self, tracker: Arc<VolatilityTracker>) -> Self {
+        self.volatility = Some(tracker);
+        self
+    }
+
+    // Which opportunity classes run, and their profit margins
+    pub fn with_classes(mut self, classes: ClassSettings) -> Self {
+        self.classes = classes;
+        self
+    }
+
+    // Publish best bid/ask of the book's pairs from the reserves scanned each block
+    pub fn with_top_of_book(mut self, book: TopOfBook) -> Self {
+        self.book = Some(book);
+        self
+    }
+
+    // Follow V3/Algebra factory PoolCreated events and mirror new pools once they have liquidity
+    pub fn with_v3_discovery(mut self, v3: V3Settings, discovery: V3DiscoverySettings) -> Self {
+        self.v3 = v3.clone();
+        self.v3_discovery = discovery
+            .enabled
+            .then(|| V3Discovery::new(self.provider.clone(), discovery, v3, self.v3_pools.clone()));
+        self
+    }
+
+    // Split realized profits with the configured payees; owed shares are kept in `store`
+    pub fn with_treasury(mut self, settings: TreasurySettings, store: Store) -> Result<Self, Box<dyn Error>> {
+        if settings.enabled {
+            self.treasury = Some(Treasury::new(self.tx_sender.clone(), store, settings)?);
+        }
+        Ok(self)
+    }
+
+    // Book the executor's events in `store` as the realized PnL ledger (and payout source)
+    pub fn with_executor_events(mut self, settings: ExecutorEventSettings, store: Store) -> Self {
+        self.executor_events = settings.enabled.then(|| {
+            ExecutorEvents::new(self.provider.clone(), self.flash_loan_contract.address(), store, settings)
+        });
+        self
+    }
+
+    // Compare wallet and executor balances against the ledger in `store` (executor events,
+    // gas spend, payouts); the gas of every mined execution is journaled there too
+    pub fn with_reconciliation(mut self, settings: ReconciliationSettings, store: Store) -> Self {
+        self.reconciler = settings.enabled.then(|| {
+            BalanceReconciler::new(
+                self.provider.clone(),
+                store.clone(),
+                settings,
+                self.tx_sender.address(),
+                self.tx_sender.address(),
+                self.flash_loan_contract.address(),
+                self.alerts.clone(),
+            )
+        });
+        self.gas_journal = Some(store);
+        self
+    }
+
+    // Concentrated-liquidity pool states, shared with the V3 quoter
+    pub fn v3_pools(&self) -> V3StateMirror {
+        self.v3_pools.clone()
+    }
+
+    fn risk_adjustment(&self, pools: &[Address]) -> RiskAdjustment {
+        self.volatility
+            .as_ref()
+            .map(|tracker| tracker.route_adjustment(pools))
+            .unwrap_or_default()
+    }
+
+    // Alert channel shared with the watchdog and other background tasks
+    pub fn alerts(&self) -> Alerts {
+        self.alerts.clone()
+    }
+
+    // Margin and gas assumptions behind the per-block minimum profit
+    pub fn with_profit_threshold(mut self, profit_threshold: ProfitThresholdSettings) -> Self {
+        self.profit_threshold = profit_threshold;
+        self
+    }
+
+    // Minimum profit for a route with `hops` swaps borrowed from `provider` at the current gas regime
+    fn min_profit(&self, provider: FlashProvider, hops: usize) -> U256 {
+        let min_profit = self
+            .profit_threshold
+            .min_profit(&self.gas_oracle.snapshot(), provider, hops, U256::zero());
+        self.controls.apply_min_profit(min_profit)
+    }
+
+    // Base and priority fee caps above which opportunities are only logged
+    pub fn with_gas_ceiling(mut self, gas_ceiling: GasCeilingSettings) -> Self {
+        self.gas_ceiling = gas_ceiling;
+        self
+    }
+
+    // Per-factory and per-pool swap fees for V2 forks that don't charge 0.3%
+    pub fn with_fees(mut self, fees: FeeSettings) -> Self {
+        self.fees = fees;
+        self
+    }
+
+    // Re-reads the swap fees V2 fork owners and Algebra pools can change every interval_blocks
+    pub fn with_fee_watch(mut self, settings: FeeWatchSettings) -> Self {
+        self.fee_watch = settings.enabled.then(|| FeeWatch::new(self.provider.clone(), settings));
+        self
+    }
+
+    pub fn with_deadline(mut self, deadline: DeadlineSettings) -> Self {
+        self.deadline = deadline;
+        self
+    }
+
+    // Re-price routes from the reserve cache right before signing
+    pub fn with_two_phase(mut self, two_phase: TwoPhaseSettings) -> Self {
+        self.two_phase = two_phase;
+        self
+    }
+
+    pub fn with_sim_cache(mut self, settings: SimCacheSettings) -> Self {
+        self.sim_cache = Arc::new(SimulationCache::new(settings));
+        self
+    }
+
+    pub fn with_pair_focus(mut self, settings: FocusSettings) -> Self {
+        self.focus = Arc::new(PairFocus::new(settings));
+        self
+    }
+
+    pub fn with_slippage_curves(mut self, settings: SlippageSettings) -> Self {
+        self.slippage = SlippageCurves::new(settings);
+        self
+    }
+
+    // Price single-token routes net of the flash loan's premium and repayment
+    pub fn with_round_trip(mut self, round_trip: RoundTripSettings) -> Self {
+        self.round_trip = round_trip;
+        self
+    }
+
+    // Two-pool cycles may borrow both tokens at once when that pays more
+    pub fn with_dual_asset(mut self, dual_asset: DualAssetSettings) -> Self {
+        self.dual_asset = dual_asset;
+        self
+    }
+
+    // Search bounds, precision and evaluation budget of trade sizing
+    pub fn with_optimizer(mut self, optimizer: OptimizerSettings) -> Self {
+        self.optimizer = optimizer;
+        self
+    }
+
+    // Second optimizer and route search compared against the live ones, never executing.
+    // After `with_optimizer` and `with_route_search`: sections the shadow leaves out copy them
+    pub fn with_shadow(mut self, settings: ShadowSettings) -> Self {
+        self.shadow = settings
+            .enabled
+            .then(|| ShadowEngine::new(settings, &self.optimizer, &self.route_search_settings));
+        self
+    }
+
+    // Measures transfer taxes of pool tokens and prices them into quotes
+    pub fn with_token_taxes(mut self, settings: TokenTaxSettings, tokens: Arc<TokenRegistry>) -> Self {
+        self.tax_probe = Some(TaxProbe::new(self.provider.clone(), tokens, settings));
+        self
+    }
+
+    // Same-pair, spread bound and peg checks on the spreads that start a route search
+    pub fn with_spread_filter(mut self, settings: SpreadFilterSettings, tokens: Arc<TokenRegistry>) -> Self {
+        self.spread_filter = SpreadFilter::new(settings, Some(tokens));
+        self
+    }
+
+    // Discount on routes through pools a large swap just moved
+    pub fn with_toxic_flow(mut self, settings: ToxicFlowSettings) -> Self {
+        self.toxic_flow = ToxicFlow::new(settings);
+        self
+    }
+
+    pub fn with_block_pipeline(mut self, settings: BlockPipelineSettings) -> Self {
+        self.pipeline = BlockPipeline::new(settings);
+        self
+    }
+
+    pub fn with_yield_wraps(mut self, settings: YieldWrapSettings) -> Self {
+        self.yield_wraps = YieldWraps::new(settings);
+        self
+    }
+
+    // Adapters are tracked like pools, with their constant reserves in the cache
+    fn register_yield_wraps(&mut self) {
+        for (pool, underlying, a_token) in self.yield_wraps.adapters() {
+            self.pool_tokens.insert(pool, (underlying, a_token));
+            self.reserve_cache
+                .update(pool, YieldWraps::depth(), YieldWraps::depth(), self.last_block.as_u64());
+        }
+    }
+
+    // Gas cost of a route's wrap/unwrap steps beyond the swap hops they are counted as
+    fn yield_wrap_cost(&self, path: &[Address]) -> U256 {
+        let Some((&token_in, pools)) = path.split_last() else {
+            return U256::zero();
+        };
+        let mut token = token_in;
+        let mut gas = 0;
+        for pool in pools {
+            let Some(&(token0, token1)) = self.pool_tokens.get(pool) else {
+                break;
+            };
+            gas += self.yield_wraps.step_gas(pool, &token);
+            token = if token == token0 { token1 } else { token0 };
+        }
+        if gas == 0 {
+            return U256::zero();
+        }
+        self.gas_oracle.snapshot().cost(U256::from(gas), U256::zero(), U256::zero()).total()
+    }
+
+    // Which token each class borrows, i.e. which start token its cycles are executed from
+    pub fn with_profit_tokens(mut self, profit_tokens: ProfitTokenSettings) -> Self {
+        self.profit_tokens = profit_tokens;
+        self
+    }
+
+    fn pool_fee(&self, pool: &Address) -> u32 {
+        if self.yield_wraps.is_adapter(pool) {
+            return 0;
+        }
+        self.fees.fee(pool, self.pool_factories.get(pool))
+    }
+
+    // Preset thresholds of the active execution mode, switchable live through the control API
+    pub fn with_execution_modes(mut self, modes: ExecutionModes) -> Self {
+        self.modes = modes;
+        self
+    }
+
+    pub fn with_controls(mut self, controls: OperatorControls) -> Self {
+        self.controls = controls;
+        self
+    }
+
+    // Restrict pair discovery to an explicit pool allowlist (see `discovery` in config.yaml)
+    pub fn with_discovery(mut self, discovery: DiscoverySettings) -> Self {
+        self.discovery = discovery;
+        self
+    }
+
+    // Parallel startup warm-up of the pair metadata and reserves of the token universe. The
+    // allowlist mode already knows its pools and keeps loading them itself
+    pub async fn prefetch_pairs(
+        &mut self,
+        settings: &PrefetchSettings,
+        tokens: &TokenRegistry,
+    ) -> Result<(), Box<dyn Error>> {
+        if !settings.enabled || self.discovery.mode == DiscoveryMode::Allowlist {
+            return Ok(());
+        }
+
+        let prefetcher = PairPrefetcher::new(self.provider.clone(), settings.clone());
+        let report = prefetcher
+            .run(&self.dex_factories, tokens, &self.reserve_cache, self.last_block.as_u64())
+            .await?;
+        for pair in &report.pairs {
+            self.pool_tokens.insert(pair.pair, (pair.token0, pair.token1));
+            self.pool_factories.insert(pair.pair, pair.factory);
+            self.token_pairs.entry(pair.token0).or_default().push(pair.pair);
+            self.token_pairs.entry(pair.token1).or_default().push(pair.pair);
+        }
+        // With failed lookups the universe is incomplete; keep discovering every block
+        self.prefetched = report.failed == 0;
+        info!(
+            "Prefetched {} pairs in {:.1}s ({} lookups, {} failed)",
+            report.pairs.len(),
+            report.elapsed.as_secs_f64(),
+            report.lookups,
+            report.failed
+        );
+        Ok(())
+    }
+
+    // Reads the executor's router and token whitelist for the routers the bot trades through
+    // and every registry or prefetched token; run after `prefetch_pairs`
+    pub async fn load_executor_whitelist(
+        &mut self,
+        settings: &ExecutorWhitelistSettings,
+        abi: ethers::abi::Abi,
+        tokens: &TokenRegistry,
+    ) -> Result<(), Box<dyn Error>> {
+        let mut routers: Vec<Address> = vec![QUICKSWAP_ROUTER.parse()?, SUSHISWAP_ROUTER.parse()?];
+        for (pool, _, _) in self.yield_wraps.adapters() {
+            routers.extend(self.yield_wraps.router(&pool));
+        }
+        let mut candidates = tokens.addresses();
+        candidates.extend(self.pool_tokens.values().flat_map(|&(token0, token1)| [token0, token1]));
+        candidates.sort();
+        candidates.dedup();
+
+        let executor = self.flash_loan_contract.address();
+        self.whitelist =
+            ExecutorWhitelist::load(self.provider.clone(), executor, abi, settings, &routers, &candidates).await?;
+        Ok(())
+    }
+
     pub async fn monitor_blocks(&mut self) -> Result<(), Box<dyn Error>> {
         let _filter = Filter::new().from_block(BlockNumber::Latest);
 
+        if let Some(shared) = self.shared_reserves.clone() {
+            let cache = self.reserve_cache.clone();
+            tokio::spawn(async move { shared.run(cache).await });
+        }
+        if self.watchdog.enabled {
+            let watchdog = ReserveWatchdog::new(
+                self.provider.clone(),
+                self.reserve_cache.clone(),
+                self.watchdog.clone(),
+                self.alerts.clone(),
+            );
+            tokio::spawn(async move { watchdog.run().await });
+        }
+        // Dry runs never sign, so there is no wallet state to heal
+        if self.nonce_watchdog.enabled && self.dry_run.is_none() {
+            let watchdog = NonceWatchdog::new(
+                self.tx_sender.clone(),
+                self.nonce_watchdog.clone(),
+                self.alerts.clone(),
+            );
+            tokio::spawn(async move { watchdog.run().await });
+        }
+        if self.wallet_watcher.enabled && self.dry_run.is_none() {
+            let watcher = WalletWatcher::new(
+                self.tx_sender.clone(),
+                self.wallet_watcher.clone(),
+                self.alerts.clone(),
+            )
+            .with_reconciler(self.reconciler.clone());
+            tokio::spawn(async move { watcher.run().await });
+        }
+        // Dry runs never trade, there is nothing to reconcile
+        if let Some(reconciler) = self.reconciler.clone().filter(|_| self.dry_run.is_none()) {
+            tokio::spawn(async move { reconciler.run().await });
+        }
+        if let Some(events) = &self.executor_events {
+            let events = events.clone().with_treasury(self.treasury.clone());
+            tokio::spawn(async move {
+                if let Err(e) = events.run().await {
+                    warn!("Executor event listener failed: {:?}", e);
+                }
+            });
+        }
+
         loop {
             let block_number = self.provider.get_block_number().await?;
 
             if block_number > self.last_block {
                 // New block, update pairs and check for opportunities
                 self.update_token_pairs().await?;
-                self.check_opportunities().await?;
+                self.register_yield_wraps();
+                if let Some(probe) = &self.tax_probe {
+                    // Only tokens without a fresh rate are probed, normally none
+                    probe.refresh(&self.pool_tokens).await;
+                }
+                if let Some(discovery) = &mut self.v3_discovery {
+                    // A failed log query is retried from the same block next round
+                    if let Err(e) = discovery.poll(block_number.as_u64()).await {
+                        warn!("V3 pool discovery failed: {:?}", e);
+                    }
+                }
+                if let Some(watch) = self.fee_watch.as_mut().filter(|watch| watch.due(block_number.as_u64())) {
+                    let (fees, factories, v3_pools) = (&self.fees, &self.pool_factories, &self.v3_pools);
+                    let v2: Vec<(Address, Address)> = factories.iter().map(|(&pool, &factory)| (pool, factory)).collect();
+                    let current = |pool: &Address| {
+                        v3_pools.get(pool).map_or_else(|| fees.fee(pool, factories.get(pool)), |state| state.fee)
+                    };
+                    let changes = watch.poll(&v2, &v3_pools.pools(), current, block_number.as_u64()).await;
+                    for change in changes {
+                        match change.kind {
+                            FeeKind::V2 => {
+                                self.fees.pools.insert(change.pool, change.new);
+                            }
+                            FeeKind::Algebra => self.v3_pools.set_fee(&change.pool, change.new),
+                        }
+                    }
+                }
+                self.gas_oracle.refresh().await?;
+                if self.controls.take_sweep() {
+                    match &self.treasury {
+                        Some(treasury) => treasury.request_settlement(),
+                        None => warn!("Sweep requested, but no treasury is configured"),
+                    }
+                }
+                if let Some(treasury) = &self.treasury {
+                    if treasury.settlement_due(block_number.as_u64()) {
+                        // Payout receipts are awaited off the block loop
+                        let (treasury, gas) = (treasury.clone(), self.gas_oracle.snapshot());
+                        let block_number = block_number.as_u64();
+                        tokio::spawn(async move {
+                            if let Err(e) = treasury.settle(block_number, &gas).await {
+                                warn!("Payout settlement failed: {:?}", e);
+                            }
+                        });
+                    }
+                }
+                self.route_search.lock().unwrap().begin_block(block_number.as_u64());
+                if let Some(shadow) = &self.shadow {
+                    shadow.route_search.lock().unwrap().begin_block(block_number.as_u64());
+                }
+                self.focus.expire(block_number.as_u64());
+                self.slippage.rebuild(
+                    &self.reserve_cache.pools(),
+                    &self.reserve_cache.snapshot(),
+                    |pool| self.pool_fee(pool),
+                    &self.v3_pools,
+                    &self.v3,
+                );
                 self.last_block = block_number;
+                self.round_started = Instant::now();
+                if let Some(lab) = &self.dry_run {
+                    lab.record_evaluation(Strategy::BlockCycle);
+                }
+
+                // Every read in this round is pinned to the same block state
+                let block = BlockId::Number(block_number.into());
+
+                // Spreads carried over from earlier blocks are re-priced first
+                self.execute_tracked_opportunities(block).await?;
+
+                for (path, amount_in) in self.check_opportunities(block).await? {
+                    let pools = path[..path.len() - 1].to_vec();
+                    self.tracker.track(path, pools, amount_in, U256::zero(), block_number.as_u64());
+                }
+
+                if let Some(book) = &self.book {
+                    let snapshot = book.build(block_number.as_u64(), &self.pool_tokens, &self.reserve_cache.snapshot(), |pool| {
+                        self.pool_fee(pool)
+                    });
+                    book.publish(snapshot);
+                }
             }
 
             tokio::time::sleep(Duration::from_secs(1)).await;
         }
     }
 
-    async fn check_opportunities(&self) -> Result<(), Box<dyn Error>> {
-        let empty_vec: Vec<Address> = Vec::new();
+    // Executes profitable routes and returns the detected-but-unprofitable ones for tracking
+    async fn check_opportunities(
+        &self,
+        block: BlockId,
+    ) -> Result<Vec<(Vec<Address>, U256)>, Box<dyn Error>> {
+        let mut deferred = Vec::new();
+
+        // Focus pairs first and every block, the rest of the universe when due
+        let block_number = self.last_block.as_u64();
+        let mut candidates = Vec::new();
+        for &token_a in self.token_pairs.keys() {
+            for &token_b in self.token_pairs.keys() {
+                if token_a != token_b {
+                    candidates.push((token_a, token_b));
+                }
+            }
+        }
+        for (_token_a, _token_b) in self.focus.schedule(candidates, block_number) {
+            let (pairs_a, pairs_b) = (&self.token_pairs[&_token_a], &self.token_pairs[&_token_b]);
+
+            if self.analyze_opportunity(_token_a, _token_b, pairs_a, pairs_b, block).await? {
+                self.focus.record_spread(_token_a, _token_b, block_number);
+                let optimal_route = self.find_optimal_route(_token_a, _token_b, block).await?;
+                let amount = self.calculate_optimal_amount(&optimal_route, block).await?;
+                self.shadow_compare(_token_a, _token_b, &optimal_route, amount);
+                if optimal_route.len() < 2 {
+                    continue;
+                }
 
-        for (&_token_a, pairs_a) in &self.token_pairs {
-            for (&_token_b, pairs_b) in &self.token_pairs {
-                if _token_a == _token_b {
+                let class = self.classes.classify(&optimal_route, false);
+                if !self.classes.is_enabled(class) {
+                    record_outcome(class.as_str(), "disabled");
+                    continue;
+                }
+                // A cycle is found once per token on it; only the rotation starting at the
+                // class's borrow token is executed
+                let tokens = self.route_tokens(&optimal_route);
+                if !tokens.is_empty() && self.profit_tokens.borrow_token(class, &tokens, None) != Some(_token_a) {
+                    continue;
+                }
+                let hops = optimal_route.len() - 1;
+                let profile = self.modes.profile();
+                if !profile.allows(class, hops) {
+                    record_outcome(class.as_str(), "mode_filtered");
                     continue;
                 }
+                record_outcome(class.as_str(), "detected");
+
+                let risk = self.risk_adjustment(&optimal_route[..hops]);
+                let amount = risk.scale_size(amount);
+
+                let profit = if amount > U256::zero() {
+                    self.simulate_trade_with_amount(&optimal_route, amount, block).await?
+                } else {
+                    U256::zero()
+                };
+                // What the executor has left for the owner once the lender is repaid
+                let profit = self
+                    .round_trip(&optimal_route, amount, amount + profit)
+                    .map_or(profit, |trip| trip.profit());
+
+                // Both sides of a two-pool imbalance can be borrowed and sold at once
+                let dual = self
+                    .dual_asset_plan(&optimal_route, block)
+                    .await?
+                    .filter(|&(_, _, value)| value > profit);
+                let profit = dual.map_or(profit, |(_, _, value)| value);
+
+                let provider = if dual.is_some() {
+                    FlashProvider::UniswapV3Dual
+                } else {
+                    FlashProvider::UniswapV3
+                };
+                let min_profit = self.classes.scale_min_profit(class, self.min_profit(provider, hops));
+                let min_profit = profile.scale_min_profit(min_profit) + self.yield_wrap_cost(&optimal_route);
+                let cooling = self.cooldown.cooling(&optimal_route[..hops], self.last_block.as_u64());
+                let sized = amount > U256::zero() || dual.is_some();
+                // Dual loans are sized against the pools' own reserves
+                let quotes =
+                    Self::cached_hop_quotes(&self.reserve_cache.snapshot(), &optimal_route[..hops], |pool| {
+                        self.pool_fee(pool)
+                    });
+                let gentle = dual.is_some() || profile.within_impact(amount, &quotes);
+                // Block trades land a block after the spread opened, so fresh disturbances score lower
+                let margin = risk.scale_margin(min_profit);
+                let target_block = self.last_block.as_u64() + 1;
+                let scored = self.toxic_flow.score(&optimal_route[..hops], target_block, false, profit);
+                if profit >= margin && scored < margin {
+                    TOXIC_FLOW_TOTAL.with_label_values(&["discounted"]).inc();
+                    record_outcome(class.as_str(), "toxic_flow");
+                }
+                if sized && gentle && scored >= margin && cooling.is_none() {
+                    if let Some(ticket) = self.claim_execution(&optimal_route, amount) {
+                        let dual = dual.map(|(amount0, amount1, _)| (amount0, amount1));
+                        self.submit(optimal_route, amount, profit, block, ticket, dual).await?;
+                    }
+                } else {
+                    // Throttled routes are tracked and re-priced once the window is over
+                    if let Some(pool) = cooling {
+                        record_outcome(class.as_str(), "cooldown");
+                        debug!("Deferring route through {:?}: pool cooling down", pool);
+                    } else if profit < margin {
+                        // Short of the bare costs is negative EV, short of the margin only unattractive
+                        let cost = self
+                            .profit_threshold
+                            .route_cost(&self.gas_oracle.snapshot(), provider, hops, U256::zero())
+                            .total();
+                        let status = if profit < cost { "ev_negative" } else { "below_threshold" };
+                        record_outcome(class.as_str(), status);
+                    }
+                    // 1 MATIC, the same probe size the simulations use
+                    let amount_in = if amount > U256::zero() {
+                        amount
+                    } else {
+                        U256::from(1_000_000_000_000_000_000u64)
+                    };
+                    deferred.push((optimal_route, amount_in));
+                }
+            }
+        }
 
-                if self.analyze_opportunity(_token_a, _token_b, pairs_a, pairs_b).await? {
-                    let optimal_route = self.find_optimal_route(_token_a, _token_b).await?;
-                    let amount = self.calculate_optimal_amount(&optimal_route).await?;
+        Ok(deferred)
+    }
 
-                    if amount > U256::zero() {
-                        self.execute_arbitrage(optimal_route).await?;
-                    }
+    async fn execute_tracked_opportunities(&mut self, block: BlockId) -> Result<(), Box<dyn Error>> {
+        if self.tracker.is_empty() {
+            return Ok(());
+        }
+
+        // Only the pools of tracked routes are refreshed; everything else stays cached
+        for pool in self.tracker.watched_pools() {
+            self.get_reserves(pool, block).await?;
+        }
+        let fees = &self.fees;
+        let factories = &self.pool_factories;
+        let reserves = self.reserve_cache.snapshot();
+        self.tracker.revalidate(&reserves, self.last_block.as_u64(), |pool| {
+            fees.fee(pool, factories.get(pool))
+        });
+
+        let gas = self.gas_oracle.snapshot();
+        let profit_threshold = self.profit_threshold.clone();
+        let volatility = self.volatility.clone();
+        let classes = self.classes.clone();
+        let profile = self.modes.profile();
+        let controls = &self.controls;
+        let cooldown = &self.cooldown;
+        let executable = self.tracker.take_executable(
+            |opportunity| {
+                let hops = opportunity.pools.len();
+                let class = classes.classify(&opportunity.path, false);
+                // Held back for as long as the active mode rules the route out
+                let quotes = Self::cached_hop_quotes(&reserves, &opportunity.pools, |pool| {
+                    fees.fee(pool, factories.get(pool))
+                });
+                if !profile.allows(class, hops) || !profile.within_impact(opportunity.amount_in, &quotes) {
+                    return U256::MAX;
                 }
+                let min_profit = profit_threshold.min_profit(&gas, FlashProvider::UniswapV3, hops, U256::zero());
+                let min_profit = controls.apply_min_profit(min_profit);
+                let min_profit = profile.scale_min_profit(classes.scale_min_profit(class, min_profit));
+                volatility
+                    .as_ref()
+                    .map(|tracker| tracker.route_adjustment(&opportunity.pools))
+                    .unwrap_or_default()
+                    .scale_margin(min_profit)
+            },
+            cooldown,
+        );
+
+        // Spreads expected to close soonest first, while the exposure cap still has room
+        let mut executable: Vec<_> = executable
+            .into_iter()
+            .map(|opportunity| {
+                let features = SpreadFeatures::new(
+                    classes.classify(&opportunity.path, false),
+                    opportunity.pools.len(),
+                    opportunity.amount_in,
+                    opportunity.last_profit,
+                    opportunity.last_validated_block.saturating_sub(opportunity.first_seen_block),
+                );
+                let persistence = spread_predictor::predict(self.spread_predictor.as_ref(), &features);
+                (spread_predictor::priority(opportunity.last_profit, persistence), opportunity)
+            })
+            .collect();
+        executable.sort_by(|(a, _), (b, _)| b.total_cmp(a));
+
+        for (_, opportunity) in executable {
+            if let Some(ticket) = self.claim_execution(&opportunity.path, opportunity.amount_in) {
+                let (path, amount_in) = (opportunity.path, opportunity.amount_in);
+                self.submit(path, amount_in, opportunity.last_profit, block, ticket, None).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    // Executes a claimed route, or only records it when running in dry-run. `dual` holds the
+    // (token0, token1) amounts when both sides are borrowed
+    #[allow(clippy::too_many_arguments)]
+    async fn submit(
+        &self,
+        path: Vec<Address>,
+        amount_in: U256,
+        profit: U256,
+        block: BlockId,
+        ticket: DedupTicket,
+        dual: Option<(U256, U256)>,
+    ) -> Result<(), Box<dyn Error>> {
+        let class = self.classes.classify(&path, false);
+        if !self.gas_ceiling.admit(&self.gas_oracle.snapshot(), profit) {
+            self.dedup.release(&ticket);
+            record_outcome(class.as_str(), "gas_ceiling");
+            return Ok(());
+        }
+        // Operator interventions: a pause holds every trade, the blacklist the routes through it
+        let hold = if self.controls.is_paused() {
+            Some("paused")
+        } else {
+            let touched: Vec<Address> = path.iter().copied().chain(self.route_tokens(&path)).collect();
+            self.controls.touches_blacklist(&touched).then_some("blacklisted")
+        };
+        if let Some(status) = hold {
+            self.dedup.release(&ticket);
+            record_outcome(class.as_str(), status);
+            return Ok(());
+        }
+        let target_block = self.last_block.as_u64() + 1;
+        let venues = self.route_venues(&path);
+        let Some(reservation) = self.self_trade.reserve(&venues, target_block, class.as_str(), profit) else {
+            self.dedup.release(&ticket);
+            record_outcome(class.as_str(), "venue_taken");
+            return Ok(());
+        };
+        let in_flight = match self.exposure.open(class.as_str(), &self.borrowed(&path, amount_in, dual)) {
+            Ok(in_flight) => in_flight,
+            Err(refused) => {
+                self.dedup.release(&ticket);
+                self.self_trade.release(&reservation);
+                record_outcome(class.as_str(), refused.0);
+                return Ok(());
             }
+        };
+        if !self.dedup.commit(&ticket) {
+            self.self_trade.release(&reservation);
+            record_outcome(class.as_str(), "superseded");
+            debug!("Opportunity {} taken over by a fresher detection", ticket.id().short());
+            return Ok(());
+        }
+        if !self.self_trade.commit(&reservation) {
+            record_outcome(class.as_str(), "venue_taken");
+            debug!("Venues of {} taken over by a more profitable strategy", ticket.id().short());
+            return Ok(());
+        }
+        self.cooldown.start(&path[..path.len() - 1], self.last_block.as_u64());
+        let hops = path.len() - 1;
+        let provider = match dual {
+            Some((amount0, amount1)) => FlashProvider::for_amounts(amount0, amount1),
+            None => FlashProvider::UniswapV3,
+        };
+        if let Some(lab) = &self.dry_run {
+            let gas_cost = self
+                .profit_threshold
+                .route_cost(&self.gas_oracle.snapshot(), provider, hops, U256::zero());
+            let token = path[path.len() - 1];
+            lab.record_opportunity(Strategy::BlockCycle, token, profit, gas_cost, self.round_started.elapsed());
+            return Ok(());
         }
 
+        if !self.pipeline.admits() {
+            return self.execute(path, block, in_flight).await;
+        }
+        // The receipt is awaited off the block loop; meanwhile the route's pools read as
+        // this trade leaves them
+        let reserves = self.reserve_cache.snapshot();
+        let quotes = Self::cached_hop_quotes(&reserves, &path[..hops], |pool| self.pool_fee(pool));
+        let legs: Vec<_> = path[..hops]
+            .iter()
+            .zip(quotes)
+            .map(|(&pool, (reserve0, reserve1, fee))| (pool, reserve0, reserve1, fee))
+            .collect();
+        let moves = match dual {
+            // Each side is sold into its own pool
+            Some((amount0, amount1)) => legs
+                .iter()
+                .zip([amount0, amount1])
+                .flat_map(|(leg, amount)| swap_through(std::slice::from_ref(leg), amount))
+                .collect(),
+            None => swap_through(&legs, amount_in),
+        };
+        let (bot, id) = (self.clone(), ticket.id());
+        self.pipeline.launch(moves, async move {
+            if let Err(e) = bot.execute(path, block, in_flight).await {
+                warn!("Block-cycle execution {} failed: {}", id.short(), e);
+            }
+        });
         Ok(())
     }
 
+    // Sends a committed route and waits for its receipt; its notional stays in flight until then.
+    // The amounts encoded are the ones `in_flight` booked: one leg, or one per dual-asset side
+    async fn execute(&self, path: Vec<Address>, block: BlockId, in_flight: InFlight) -> Result<(), Box<dyn Error>> {
+        let class = self.classes.classify(&path, false);
+        let hops = path.len() - 1;
+        let (provider, receipt) = match *in_flight.borrowed() {
+            [(_, amount0), (_, amount1)] => (
+                FlashProvider::for_amounts(amount0, amount1),
+                self.execute_dual_arbitrage(path, (amount0, amount1), block).await?,
+            ),
+            [(_, amount_in)] => (FlashProvider::UniswapV3, self.execute_arbitrage(path, amount_in, block).await?),
+            _ => return Err("Execution booked no borrow".into()),
+        };
+        let Some(receipt) = receipt else {
+            return Ok(());
+        };
+        if receipt.status.map(|status| status.as_u64()) == Some(0) {
+            record_failure(class.as_str(), FailureReason::Reverted(RevertClass::Unknown));
+        }
+        // Reverted executions stop short of the callback's repayment, so only successes calibrate
+        if let (Some(1), Some(gas_used)) = (receipt.status.map(|status| status.as_u64()), receipt.gas_used) {
+            self.profit_threshold.observe_receipt(provider, hops, gas_used);
+        }
+        Ok(())
+    }
+
+    // Idempotency and cross-detector dedup shared by every execution path; None means the
+    // spread was already submitted or is claimed by another detector
+    fn claim_execution(&self, path: &[Address], amount_in: U256) -> Option<DedupTicket> {
+        if path.len() < 2 {
+            return None;
+        }
+
+        // Every route is priced from reserves read at the current block, for the next one
+        let block = self.last_block.as_u64();
+        let (pools, first, last) = (&path[..path.len() - 1], path[0], path[path.len() - 1]);
+        let id = OpportunityId::new(pools, first, last, amount_in, block + 1);
+        let ticket = self.dedup.claim(DedupKey::new(id, block), Strategy::BlockCycle.as_str(), block)?;
+        let key = self.idempotency.key(pools, first, last, block);
+        if !self.idempotency.try_acquire(key, block) {
+            self.dedup.release(&ticket);
+            return None;
+        }
+        Some(ticket)
+    }
+
     async fn analyze_opportunity(
         &self,
         _token_a: Address,
         _token_b: Address,
         pairs_a: &[Address],
         pairs_b: &[Address],
+        block: BlockId,
     ) -> Result<bool, Box<dyn Error>> {
         for &pair_a in pairs_a {
             for &pair_b in pairs_b {
@@ -152,13 +1312,30 @@ impl MevBot {
                     continue;
                 }
 
-                let (reserve_a0, reserve_a1) = self.get_reserves(pair_a).await?;
-                let (reserve_b0, reserve_b1) = self.get_reserves(pair_b).await?;
+                // Only pools of the same ordered pair quote comparable prices
+                let tokens = self.pool_tokens.get(&pair_a);
+                if !self.spread_filter.comparable(tokens, self.pool_tokens.get(&pair_b)) {
+                    continue;
+                }
+                let pair = tokens.copied().unwrap_or((_token_a, _token_b));
+
+                let reserves_a = self.get_reserves(pair_a, block).await?;
+                let reserves_b = self.get_reserves(pair_b, block).await?;
+                let check = self.spread_filter.check(pair, reserves_a, reserves_b);
 
-                let price_a = reserve_a0.as_u128() as f64 / reserve_a1.as_u128() as f64;
-                let price_b = reserve_b0.as_u128() as f64 / reserve_b1.as_u128() as f64;
+                // Spread activity steers the route search towards recently dislocated pools
+                let Some(spread_bps) = check.spread_bps() else {
+                    SPREAD_FILTERED_TOTAL.with_label_values(&[check.as_str()]).inc();
+                    debug!("Ignoring {} spread between {:?} and {:?}", check.as_str(), pair_a, pair_b);
+                    continue;
+                };
+                {
+                    let mut history = self.spread_history.lock().unwrap();
+                    history.record(pair_a, spread_bps);
+                    history.record(pair_b, spread_bps);
+                }
 
-                if (price_a - price_b).abs() / price_a > 0.01 {
+                if matches!(check, SpreadCheck::Spread(_)) {
                     return Ok(true);
                 }
             }
@@ -167,7 +1344,19 @@ impl MevBot {
     }
 
     async fn update_token_pairs(&mut self) -> Result<(), Box<dyn Error>> {
+        if self.prefetched {
+            return Ok(());
+        }
         self.token_pairs.clear();
+        self.pool_tokens.clear();
+        self.pool_factories.clear();
+
+        if self.discovery.mode == DiscoveryMode::Allowlist {
+            // The allowlist is fixed, so its pools are looked up once
+            self.load_allowlisted_pairs().await?;
+            self.prefetched = true;
+            return Ok(());
+        }
 
         for &factory in &self.dex_factories {
             let factory_contract = IUniswapV2Pair::new(factory, self.provider.clone());
@@ -178,12 +1367,14 @@ impl MevBot {
                     let pair_contract = IUniswapV2Pair::new(pair_address, self.provider.clone());
                     let token0 = pair_contract.token_0().call().await?;
                     let token1 = pair_contract.token_1().call().await?;
+                    self.pool_tokens.insert(pair_address, (token0, token1));
+                    self.pool_factories.insert(pair_address, factory);
 
                     self.token_pairs.entry(token0)
-                        .or_insert_with(Vec::new)
+                        .or_default()
                         .push(pair_address);
                     self.token_pairs.entry(token1)
-                        .or_insert_with(Vec::new)
+                        .or_default()
                         .push(pair_address);
                 }
             }
@@ -191,10 +1382,34 @@ impl MevBot {
         Ok(())
     }
 
+    async fn load_allowlisted_pairs(&mut self) -> Result<(), Box<dyn Error>> {
+        for &pair_address in &self.discovery.allowlist {
+            let pair_contract = IUniswapV2Pair::new(pair_address, self.provider.clone());
+            let token0 = pair_contract.token_0().call().await?;
+            let token1 = pair_contract.token_1().call().await?;
+            // The factory picks the pool's router and fee, allowlists mix DEXes
+            let factory = pair_contract.factory().call().await?;
+            self.pool_tokens.insert(pair_address, (token0, token1));
+            self.pool_factories.insert(pair_address, factory);
+
+            self.token_pairs.entry(token0)
+                .or_default()
+                .push(pair_address);
+            self.token_pairs.entry(token1)
+                .or_default()
+                .push(pair_address);
+        }
+        Ok(())
+    }
+
+    // Borrows the sized `amount_in` for the first hop; each later hop trades the previous
+    // one's output. None when the route no longer paid at the commit-time re-check
     async fn execute_arbitrage(
         &self,
         path: Vec<Address>,
-    ) -> Result<TransactionReceipt, Box<dyn Error>> {
+        amount_in: U256,
+        block: BlockId,
+    ) -> Result<Option<TransactionReceipt>, Box<dyn Error>> {
         if path.len() < 2 {
             return Err("Path must have at least 2 tokens".into());
         }
@@ -203,75 +1418,261 @@ impl MevBot {
         let token0 = path.first().unwrap();
         let token1 = path.last().unwrap();
 
-        // Calculate optimal amounts per hop dynamically
+        // Chain the sized input through the hops, walking the route's tokens from the start
+        // token so each hop trades in the direction it actually goes
         let mut amounts: Vec<U256> = Vec::with_capacity(path.len() - 1);
-        for i in 0..path.len() - 1 {
-            let (reserve_in, reserve_out) = self.get_reserves(path[i]).await?;
-            // Basic formula: simulate trade with 1 MATIC per hop
-            let amount_in = U256::from(1_000_000_000_000_000_000u64);
-            let amount_out = (amount_in * reserve_out) / (reserve_in + amount_in);
-            amounts.push(amount_in); // input for each hop
+        let mut simulated_hops: Vec<SimulatedHop> = Vec::with_capacity(path.len() - 1);
+        let mut hop_in = amount_in;
+        let mut token = *token1;
+        for &pool in &path[..path.len() - 1] {
+            let &(pool_token0, pool_token1) = self.pool_tokens.get(&pool).ok_or("Unknown pool in route")?;
+            let zero_for_one = token == pool_token0;
+            token = if zero_for_one { pool_token1 } else { pool_token0 };
+            let (reserve0, reserve1) = self.get_reserves(pool, block).await?;
+            let (reserve_in, reserve_out) = if zero_for_one { (reserve0, reserve1) } else { (reserve1, reserve0) };
+            let amount_out = get_amount_out(hop_in, reserve_in, reserve_out, self.pool_fee(&pool));
+            amounts.push(hop_in); // input for each hop
+            simulated_hops.push(SimulatedHop {
+                pool,
+                zero_for_one,
+                simulated_out: amount_out,
+            });
+            hop_in = amount_out;
         }
 
-        // routers aligned with path hops (example: Quick + Sushi + Uni)
-        let routers: Vec<Address> = path
-            .iter()
-            .enumerate()
-            .take(path.len() - 1)
-            .map(|(i, _)| match i {
-                0 => "0xa5E0829CaCEd8fFDD4De3c43696c57F7D7A678ff".parse::<Address>().unwrap(), // QuickSwap
-                1 => "0x1b02dA8Cb0d097eB8D57A175b88c7D8b47997506".parse::<Address>().unwrap(), // SushiSwap
-                _ => "0xE592427A0AEce92De3Edee1F18E0157C05861564".parse::<Address>().unwrap(), // UniV3
-            })
-            .collect();
+        // Routers aligned with path hops, each the one its pool's factory trades through
+        let Some(routers) = self.executor_routers(&path)? else {
+            return Ok(None);
+        };
 
         // Borrow amount = first hop input, second token 0
-        let amount0 = amounts[0];
         let amount1 = U256::zero();
-        let fee = 3000u32; // default fee as per contract
+        // Pool the loan is flashed from, the same tier its premium was priced with
+        let fee = self.round_trip.flash_pool_fee;
+
+        // Calldata from the binding; nonce, fees and signing happen locally. The commit
+        // phase re-encodes it with the hop amounts re-priced just before signing
+        let call = FlashLoanCall {
+            token0: *token0,
+            token1: *token1,
+            amount1,
+            fee,
+            path: path.clone(),
+            routers,
+            deadline: self.deadline.deadline_now(self.last_block + 1),
+        };
+        let encode = |amounts: &[U256]| call.calldata(&self.flash_loan_contract, amounts);
+        let data = encode(&amounts).ok_or("executeFlashLoanArbitrage produced no calldata")?;
+        let plan = CommitPlan {
+            hops: &mut simulated_hops,
+            // The booked borrow; the re-check only re-prices the later hops' inputs
+            amount_in,
+            encode: &encode,
+        };
+        let Some(receipt) = self.send_execution(data, &path, block, Some(plan)).await? else {
+            return Ok(None);
+        };
+
+        // Feed realized hop amounts back into the drift statistics
+        self.drift_monitor.observe_receipt(&receipt, &simulated_hops);
+
+        Ok(Some(receipt))
+    }
 
-        // Gas & nonce
-        let gas_price = self.provider.get_gas_price().await?;
-        let nonce = self.provider.get_transaction_count(self.wallet.address(), None).await?;
+    // Borrows both tokens of a two-pool cycle [pool0, pool1, token0]: `amount0` of token0 is
+    // sold on pool0 and `amount1` of token1 on pool1, each side repaid by the other's output
+    async fn execute_dual_arbitrage(
+        &self,
+        path: Vec<Address>,
+        (amount0, amount1): (U256, U256),
+        block: BlockId,
+    ) -> Result<Option<TransactionReceipt>, Box<dyn Error>> {
+        if path.len() != 3 {
+            return Err("Dual-asset loans need a two-pool cycle".into());
+        }
+        let (pool0, token0) = (path[0], path[2]);
+        let (a, b) = *self.pool_tokens.get(&pool0).ok_or("Unknown pool in dual-asset route")?;
+        let token1 = if a == token0 { b } else { a };
+
+        let Some(routers) = self.executor_routers(&path)? else {
+            return Ok(None);
+        };
+        // One hop per borrowed side, each with its own input amount
+        let call = FlashLoanCall {
+            token0,
+            token1,
+            amount1,
+            fee: self.dual_asset.flash_pool_fee,
+            path: vec![token0, token1, token0],
+            routers,
+            deadline: self.deadline.deadline_now(self.last_block + 1),
+        };
+        let data = call
+            .calldata(&self.flash_loan_contract, &[amount0, amount1])
+            .ok_or("executeFlashLoanArbitrage produced no calldata")?;
+        // Both legs start from borrowed funds, there is no hop chain to re-price
+        self.send_execution(data, &path, block, None).await
+    }
 
-        // Build transaction dynamically
-        let tx_request = self.flash_loan_contract
-            .method::<_, ()>(
-                "executeFlashLoanArbitrage",
-                (
-                    *token0,
-                    *token1,
-                    amount0,
-                    amount1,
-                    fee,
-                    path.clone(),
-                    amounts.clone(),
-                    routers.clone(),
-                ),
-            )?
-            .from(self.wallet.address())
-            .gas_price(gas_price)
-            .nonce(nonce);
+    // The pools of `path` as the routers and token pairs the mempool strategies see
+    fn route_venues(&self, path: &[Address]) -> Vec<Venue> {
+        path[..path.len().saturating_sub(1)]
+            .iter()
+            .filter_map(|pool| {
+                let &(token0, token1) = self.pool_tokens.get(pool)?;
+                Some(Venue::new(self.router_of(pool).ok()?, token0, token1))
+            })
+            .collect()
+    }
 
-        // Send tx and await receipt
-        let pending_tx = tx_request.send().await?;
-        let receipt = pending_tx.await?;
+    // (token, amount) flash-borrowed to trade `path`: the start token, or one per dual-asset side
+    fn borrowed(&self, path: &[Address], amount_in: U256, dual: Option<(U256, U256)>) -> Vec<(Address, U256)> {
+        let token0 = path[path.len() - 1];
+        match dual {
+            Some((amount0, amount1)) => {
+                let (a, b) = self.pool_tokens.get(&path[0]).copied().unwrap_or_default();
+                let token1 = if a == token0 { b } else { a };
+                vec![(token0, amount0), (token1, amount1)]
+            }
+            None => vec![(token0, amount_in)],
+        }
+    }
+
+    // Tokens traded by the pools of `path`, in route order
+    fn route_tokens(&self, path: &[Address]) -> Vec<Address> {
+        let mut tokens: Vec<Address> = Vec::new();
+        for pool in &path[..path.len().saturating_sub(1)] {
+            if let Some(&(token0, token1)) = self.pool_tokens.get(pool) {
+                for token in [token0, token1] {
+                    if !tokens.contains(&token) {
+                        tokens.push(token);
+                    }
+                }
+            }
+        }
+        tokens
+    }
+
+    // The routers the executor is called with for the pools of `path`; None, and counted,
+    // when it refuses one of them or a token they trade
+    fn executor_routers(&self, path: &[Address]) -> Result<Option<Vec<Address>>, Box<dyn Error>> {
+        let routers = path[..path.len() - 1]
+            .iter()
+            .map(|pool| self.router_of(pool))
+            .collect::<Result<Vec<_>, _>>()?;
+        if !self.whitelist.allows_route(&routers, &self.route_tokens(path)) {
+            record_outcome(self.classes.classify(path, false).as_str(), "not_whitelisted");
+            debug!("Executor refuses a router or token of {:?}", path);
+            return Ok(None);
+        }
+        Ok(Some(routers))
+    }
+
+    fn router_of(&self, pool: &Address) -> Result<Address, Box<dyn Error>> {
+        if let Some(lending_pool) = self.yield_wraps.router(pool) {
+            return Ok(lending_pool);
+        }
+        let router = match self.pool_factories.get(pool) {
+            Some(factory) if *factory == SUSHISWAP_FACTORY.parse::<Address>()? => SUSHISWAP_ROUTER,
+            _ => QUICKSWAP_ROUTER,
+        };
+        Ok(router.parse()?)
+    }
 
-        Ok(receipt.expect("Transaction failed or reverted"))
+    // Signs and broadcasts executor calldata for `path`, with its pools' slots pre-warmed.
+    // With a `plan` the route is re-priced between the node round trips and the signature;
+    // None when it no longer clears the threshold there
+    async fn send_execution(
+        &self,
+        data: Bytes,
+        path: &[Address],
+        block: BlockId,
+        plan: Option<CommitPlan<'_>>,
+    ) -> Result<Option<TransactionReceipt>, Box<dyn Error>> {
+        let gas = self.gas_oracle.snapshot();
+        let mut tx = self.tx_sender.request(self.flash_loan_contract.address(), data, U256::zero(), &gas, None);
+
+        // Pre-warm the route's pair and balance slots
+        let pools: Vec<Address> = path.iter().copied().filter(|pool| self.pool_tokens.contains_key(pool)).collect();
+        let mut tokens: Vec<Address> = pools
+            .iter()
+            .flat_map(|pool| {
+                let (token0, token1) = self.pool_tokens[pool];
+                [token0, token1]
+            })
+            .collect();
+        tokens.sort();
+        tokens.dedup();
+        let mut holders = pools.clone();
+        holders.push(self.flash_loan_contract.address());
+        self.access_lists.attach(&mut tx, Some(block), &pools, &tokens, &holders).await;
+        self.tx_sender.prepare(&mut tx).await?;
+
+        // Commit phase: no await from the cache read to the signature
+        if let Some(plan) = plan.filter(|_| self.two_phase.enabled) {
+            let pools: Vec<Address> = plan.hops.iter().map(|hop| hop.pool).collect();
+            let mut hops = Self::cached_hop_quotes(&self.reserve_cache.snapshot(), &pools, |pool| self.pool_fee(pool));
+            for (quote, hop) in hops.iter_mut().zip(plan.hops.iter()) {
+                if !hop.zero_for_one {
+                    (quote.0, quote.1) = (quote.1, quote.0);
+                }
+            }
+            let min_profit = self.min_profit(FlashProvider::UniswapV3, hops.len());
+            let repayment = self.round_trip(path, plan.amount_in, plan.amount_in);
+            let min_profit = min_profit + repayment.map_or(U256::zero(), |trip| trip.cost());
+            let min_profit = min_profit + self.yield_wrap_cost(path);
+            let gas_limit = tx.gas().copied().unwrap_or_default();
+            let Some(commit) = self.two_phase.recheck(plan.amount_in, &hops, gas_limit, gas.priority_fee, min_profit)
+            else {
+                let class = self.classes.classify(path, false);
+                record_outcome(class.as_str(), "stale_at_commit");
+                debug!("Route {:?} no longer clears the threshold at commit", path);
+                return Ok(None);
+            };
+            tx.set_data((plan.encode)(&commit.amounts).ok_or("executeFlashLoanArbitrage produced no calldata")?);
+            // Drift is measured against the amounts actually sent, not the pre-commit quote
+            for (hop, &out) in plan.hops.iter_mut().zip(&commit.outputs) {
+                hop.simulated_out = out;
+            }
+            if let TypedTransaction::Eip1559(request) = &mut tx {
+                request.max_priority_fee_per_gas = Some(commit.priority_fee);
+                request.max_fee_per_gas = Some(gas.base_fee * 2 + commit.priority_fee);
+            }
+        }
+        let raw = self.tx_sender.sign_prepared(&tx)?;
+
+        // Broadcast via eth_sendRawTransaction and await receipt
+        let pending_tx = self.tx_sender.broadcast(raw).await?;
+        let receipt = pending_tx.await?;
+        let receipt = receipt.expect("Transaction failed or reverted");
+        if let (Some(store), Some(gas_used), Some(number)) = (&self.gas_journal, receipt.gas_used, receipt.block_number) {
+            let spend = GasSpendRecord {
+                tx_hash: receipt.transaction_hash,
+                block_number: number.as_u64(),
+                gas_used,
+                cost: gas_used * receipt.effective_gas_price.unwrap_or_default(),
+            };
+            if let Err(e) = store.record_gas_spend(&spend) {
+                warn!("Failed to journal gas spend: {:?}", e);
+            }
+        }
+        Ok(Some(receipt))
     }
 
     async fn find_optimal_route(
         &self,
         token_in: Address,
         token_out: Address,
+        block: BlockId,
     ) -> Result<Vec<Address>, Box<dyn Error>> {
         let mut best_route = vec![];
         let mut best_profit = U256::zero();
 
-        let routes = self.get_all_routes(token_in, token_out)?;
+        let routes = self.search_routes(token_in, token_out);
 
-        for route in routes {
-            let profit = self.simulate_trade(&route).await?;
+        // Only the best local candidates are re-simulated against the pinned block
+        for route in routes.into_iter().take(self.route_search_settings.verify_top) {
+            let profit = self.simulate_trade(&route, block).await?;
             if profit > best_profit {
                 best_profit = profit;
                 best_route = route;
@@ -281,93 +1682,266 @@ impl MevBot {
         Ok(best_route)
     }
 
-    async fn get_reserves(&self, pair: Address) -> Result<(U256, U256), Box<dyn Error>> {
-        let pair_contract = IUniswapV2Pair::new(pair, self.provider.clone());
-        let (reserve0, reserve1, _) = pair_contract.get_reserves().call().await?;
-        Ok((reserve0.into(), reserve1.into()))
+    // Runs the shadow engine on the pair the live engine picked `route` and `amount` for. Both
+    // picks are priced on the cached reserves, so only the engines' choices differ
+    fn shadow_compare(&self, token_in: Address, token_out: Address, route: &[Address], amount: U256) {
+        let Some(shadow) = &self.shadow else {
+            return;
+        };
+        let reserves = self.reserve_cache.snapshot();
+        let quotes = |route: &[Address]| {
+            Self::cached_hop_quotes(&reserves, &route[..route.len().saturating_sub(1)], |pool| self.pool_fee(pool))
+        };
+        let live = EnginePick {
+            route: route.to_vec(),
+            amount,
+            profit: self.drift_adjusted_profit(route, &quotes(route), amount),
+        };
+
+        let graph = self.route_graph();
+        let history = self.spread_history.lock().unwrap().clone();
+        let max_hops = shadow.optimizer.max_hops(
+            shadow.route_search_settings.max_hops,
+            history.strongest(&graph, &token_in),
+            |hops| self.min_profit(FlashProvider::UniswapV3, hops),
+        );
+        let probe = U256::from(1_000_000_000_000_000_000u64); // 1 MATIC
+        let best = Self::cycles(&shadow.route_search, &graph, &history, max_hops, token_in, token_out)
+            .into_iter()
+            .take(shadow.route_search_settings.verify_top)
+            .map(|route| (route_profit(probe, &quotes(&route), |_, out| Some(out)), route))
+            .filter(|(profit, _)| !profit.is_zero())
+            .max_by_key(|(profit, _)| *profit);
+        let pick = best.map_or_else(EnginePick::default, |(_, route)| {
+            let hops = quotes(&route);
+            let optimum = shadow.optimizer.optimum(|amount| self.drift_adjusted_profit(&route, &hops, amount));
+            EnginePick {
+                route,
+                amount: optimum.amount,
+                profit: optimum.profit,
+            }
+        });
+        shadow.record(token_in, &live, &pick);
     }
 
-    fn get_all_routes(
+    async fn get_reserves(
         &self,
+        pair: Address,
+        block: BlockId,
+    ) -> Result<(U256, U256), Box<dyn Error>> {
+        if let Some(reserves) = self.yield_wraps.reserves(&pair) {
+            return Ok(reserves);
+        }
+        let pair_contract = IUniswapV2Pair::new(pair, self.provider.clone());
+        let (reserve0, reserve1, _) = pair_contract.get_reserves().block(block).call().await?;
+        let (reserve0, reserve1): (U256, U256) = (reserve0.into(), reserve1.into());
+        self.reserve_cache.update(pair, reserve0, reserve1, self.last_block.as_u64());
+        self.toxic_flow.observe(pair, reserve0, self.last_block.as_u64());
+        // The cache keeps the chain's reserves; discovery sees in-flight trades as landed
+        Ok(self.pipeline.overlay(pair, (reserve0, reserve1)))
+    }
+
+    // Best-first cycles through `token_in` that also touch `token_out`, priced from the
+    // reserve cache. Routes are returned as [pool_1, .., pool_n, token_in].
+    fn search_routes(&self, token_in: Address, token_out: Address) -> Vec<Vec<Address>> {
+        let graph = self.route_graph();
+        let history = self.spread_history.lock().unwrap().clone();
+        // Longer routes only when gas is cheap against the spreads around `token_in`
+        let max_hops = self.optimizer.hop_limit(
+            self.route_search_settings.max_hops,
+            history.strongest(&graph, &token_in),
+            |hops| self.min_profit(FlashProvider::UniswapV3, hops),
+        );
+        Self::cycles(&self.route_search, &graph, &history, max_hops, token_in, token_out)
+    }
+
+    // Cached pools the executor can route through
+    fn route_graph(&self) -> TokenGraph {
+        let mut graph = TokenGraph::new();
+        let reserves = self.reserve_cache.snapshot();
+        for (&pool, &(token0, token1)) in &self.pool_tokens {
+            // A route through a router or token the executor refuses would revert on-chain
+            let allowed = self
+                .router_of(&pool)
+                .is_ok_and(|router| self.whitelist.allows_pool(&router, &token0, &token1));
+            if !allowed {
+                continue;
+            }
+            if let Some(reserves) = reserves.get(&pool) {
+                graph.add_pool(pool, token0, token1, reserves.reserve0, reserves.reserve1, self.pool_fee(&pool));
+            }
+        }
+        graph
+    }
+
+    fn cycles(
+        route_search: &Mutex<RouteSearch>,
+        graph: &TokenGraph,
+        history: &SpreadHistory,
+        max_hops: usize,
         token_in: Address,
         token_out: Address,
-    ) -> Result<Vec<Vec<Address>>, Box<dyn Error>> {
-        let mut routes = Vec::new();
-        let pairs = self.token_pairs.get(&token_in)
-            .ok_or("No pairs found for input token")?;
-
-        for &pair in pairs {
-            let mut route = vec![token_in, pair];
-            if pair == token_out {
-                routes.push(route);
-            } else if let Some(next_pairs) = self.token_pairs.get(&pair) {
-                for &next_pair in next_pairs {
-                    if next_pair == token_out {
-                        route.push(next_pair);
-                        routes.push(route.clone());
-                    }
-                }
-            }
+    ) -> Vec<Vec<Address>> {
+        let probe = U256::from(1_000_000_000_000_000_000u64); // 1 MATIC
+        let (candidates, _) = {
+            let mut search = route_search.lock().unwrap();
+            search.max_hops = max_hops;
+            search.search(graph, history, token_in, probe)
+        };
+
+        candidates
+            .into_iter()
+            .filter(|candidate| candidate.tokens.contains(&token_out))
+            .map(|candidate| {
+                let mut route = candidate.pools;
+                route.push(token_in);
+                route
+            })
+            .collect()
+    }
+
+    // (reserve_in, reserve_out, fee) for every hop of `path`, read at `block`
+    async fn hop_quotes(&self, path: &[Address], block: BlockId) -> Result<Vec<(U256, U256, u32)>, Box<dyn Error>> {
+        let mut hops = Vec::with_capacity(path.len().saturating_sub(1));
+        for pool in &path[..path.len().saturating_sub(1)] {
+            let (reserve_in, reserve_out) = self.get_reserves(*pool, block).await?;
+            hops.push((reserve_in, reserve_out, self.pool_fee(pool)));
         }
+        Ok(hops)
+    }
 
-        Ok(routes)
+    // (reserve_in, reserve_out, fee) per pool from the reserve cache; unknown pools count as empty
+    fn cached_hop_quotes(
+        reserves: &ReserveSnapshot,
+        pools: &[Address],
+        fee: impl Fn(&Address) -> u32,
+    ) -> Vec<(U256, U256, u32)> {
+        pools
+            .iter()
+            .map(|pool| {
+                let cached = reserves.get(pool);
+                (
+                    cached.map_or(U256::zero(), |cached| cached.reserve0),
+                    cached.map_or(U256::zero(), |cached| cached.reserve1),
+                    fee(pool),
+                )
+            })
+            .collect()
     }
 
-    async fn simulate_trade(&self, path: &[Address]) -> Result<U256, Box<dyn Error>> {
+    async fn simulate_trade(&self, path: &[Address], block: BlockId) -> Result<U256, Box<dyn Error>> {
         let amount = U256::from(1_000_000_000_000_000_000u64); // 1 MATIC
-        let mut current_amount = amount;
-
-        for i in 0..path.len() - 1 {
-            let (reserve_in, reserve_out) = self.get_reserves(path[i]).await?;
-            current_amount = (current_amount * reserve_out) / (reserve_in + current_amount);
+        let key = self.simulation_key(path, amount, false);
+        if let Some(profit) = self.sim_cache.get(self.last_block.as_u64(), &key) {
+            return Ok(profit);
         }
+        let hops = self.hop_quotes(path, block).await?;
+        let profit = route_profit(amount, &hops, |_, out| Some(out));
+        self.sim_cache.insert(self.last_block.as_u64(), key, profit);
+        Ok(profit)
+    }
 
-        Ok(if current_amount > amount {
-            current_amount - amount
-        } else {
-            U256::zero()
-        })
+    // Routes are [pool_1, .., pool_n, token_in]
+    fn simulation_key(&self, path: &[Address], amount: U256, adjusted: bool) -> SimKey {
+        let (pools, token_in) = path.split_at(path.len().saturating_sub(1));
+        self.sim_cache.key(pools, token_in.first().copied().unwrap_or_default(), amount, adjusted)
     }
 
-    async fn calculate_optimal_amount(&self, path: &[Address]) -> Result<U256, Box<dyn Error>> {
-        let mut optimal_amount = U256::zero();
-        let mut max_profit = U256::zero();
+    async fn calculate_optimal_amount(
+        &self,
+        path: &[Address],
+        block: BlockId,
+    ) -> Result<U256, Box<dyn Error>> {
+        // With fresh curves for every hop no reserves are read at all
+        let pools = &path[..path.len().saturating_sub(1)];
+        if let Some(curves) = self.slippage.route(pools, self.last_block.as_u64()) {
+            SIZING_SOURCE_TOTAL.with_label_values(&["curves"]).inc();
+            let optimum = self.optimizer.optimize(|amount| {
+                curve_profit(amount, &curves, |i, out| {
+                    self.drift_monitor.adjust_quote(&path[i], self.after_taxes(&path[i], out))
+                })
+            });
+            return Ok(optimum.amount);
+        }
+        // Reserves are read once; every size is priced offline against them
+        SIZING_SOURCE_TOTAL.with_label_values(&["reserves"]).inc();
+        let hops = self.hop_quotes(path, block).await?;
+        let optimum = self.optimizer.optimize(|amount| self.drift_adjusted_profit(path, &hops, amount));
+        Ok(optimum.amount)
+    }
 
-        let amounts = vec![
+    // Best dual-asset sizing of a two-pool cycle as (amount0, amount1, value in token0)
+    async fn dual_asset_plan(
+        &self,
+        path: &[Address],
+        block: BlockId,
+    ) -> Result<Option<(U256, U256, U256)>, Box<dyn Error>> {
+        if !self.dual_asset.enabled || path.len() != 3 {
+            return Ok(None);
+        }
+        let hops = self.hop_quotes(path, block).await?;
+        let amounts0 = [
             U256::from(1_000_000_000_000_000_000u64), // 1 MATIC
             U256::from(5_000_000_000_000_000_000u64), // 5 MATIC
             U256::from(10_000_000_000_000_000_000u64), // 10 MATIC
         ];
-
-        for &amount in &amounts {
-            let profit = self.simulate_trade_with_amount(path, amount).await?;
-            if profit > max_profit {
-                max_profit = profit;
-                optimal_amount = amount;
-            }
+        // token1 sizes worth the same at the first pool's mid price
+        let (reserve_in, reserve_out, _) = hops[0];
+        if reserve_in.is_zero() {
+            return Ok(None);
         }
+        let amounts1: Vec<U256> = amounts0.iter().map(|&amount| amount * reserve_out / reserve_in).collect();
 
-        Ok(optimal_amount)
+        let (amount0, amount1, value) =
+            best_dual_amounts(&amounts0, &amounts1, hops[0], hops[1], self.dual_asset.flash_pool_fee);
+        Ok((!amount0.is_zero()).then_some((amount0, amount1, value)))
     }
 
     async fn simulate_trade_with_amount(
         &self,
         path: &[Address],
-        amount: U256
+        amount: U256,
+        block: BlockId,
     ) -> Result<U256, Box<dyn Error>> {
-        let mut current_amount = amount;
+        let key = self.simulation_key(path, amount, true);
+        if let Some(profit) = self.sim_cache.get(self.last_block.as_u64(), &key) {
+            return Ok(profit);
+        }
+        let hops = self.hop_quotes(path, block).await?;
+        let profit = self.drift_adjusted_profit(path, &hops, amount);
+        self.sim_cache.insert(self.last_block.as_u64(), key, profit);
+        Ok(profit)
+    }
 
-        for i in 0..path.len() - 1 {
-            let (reserve_in, reserve_out) = self.get_reserves(path[i]).await?;
-            current_amount = (current_amount * reserve_out) / (reserve_in + current_amount);
+    // Settlement of borrowing `amount` of the route's start token and ending the hops with
+    // `end_balance` of it; None when round trips are not priced
+    fn round_trip(&self, path: &[Address], amount: U256, end_balance: U256) -> Option<RoundTrip> {
+        if !self.round_trip.enabled {
+            return None;
         }
+        let tax = match (&self.tax_probe, path.last()) {
+            (Some(probe), Some(token)) => probe.tokens().tax(token).unwrap_or_default(),
+            _ => TokenTax::default(),
+        };
+        Some(RoundTrip::new(amount, end_balance, self.round_trip.flash_pool_fee, tax))
+    }
 
-        Ok(if current_amount > amount {
-            current_amount - amount
-        } else {
-            U256::zero()
+    // Apply drift correction; blacklisted pools make the route worthless
+    fn drift_adjusted_profit(&self, path: &[Address], hops: &[(U256, U256, u32)], amount: U256) -> U256 {
+        route_profit(amount, hops, |i, out| {
+            self.drift_monitor.adjust_quote(&path[i], self.after_taxes(&path[i], out))
         })
     }
+
+    // Transfer taxes of a hop, reserve0's token going in and reserve1's coming out. The input
+    // tax is taken off the output, slightly overstating it since output is concave in input
+    fn after_taxes(&self, pool: &Address, out: U256) -> U256 {
+        let (Some(probe), Some(&(token_in, token_out))) = (&self.tax_probe, self.pool_tokens.get(pool)) else {
+            return out;
+        };
+        let tax = |token: &Address| probe.tokens().tax(token).unwrap_or_default();
+        tax(&token_out).after_buy(tax(&token_in).after_sell(out))
+    }
 }
 
 #[derive(Debug)]
@@ -393,6 +1967,201 @@ pub struct Config {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::access_list::AccessListMode;
+    use crate::fixtures::{address, call_output, quantity, reserves_output, MockChain};
+    use crate::pool_events::V2SwapEvent;
+    use ethers::abi::{self, Token};
+    use ethers::contract::EthEvent;
+    use ethers::utils::id;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_execute_arbitrage_calldata_and_gas_fields() {
+        let chain = MockChain::new();
+        let wallet = "0000000000000000000000000000000000000000000000000000000000000001"
+            .parse::<LocalWallet>()
+            .unwrap()
+            .with_chain_id(137u64);
+        let (executor, pool_a, pool_b, token) = (address(99), address(10), address(11), address(1));
+        let mut bot = MevBot::with_provider(chain.provider(), wallet.clone(), executor, address(98), U64::from(100))
+            .unwrap()
+            .with_access_lists(AccessListSettings { mode: AccessListMode::Off })
+            .with_two_phase(TwoPhaseSettings { enabled: false, ..Default::default() });
+        bot.pool_factories.insert(pool_b, SUSHISWAP_FACTORY.parse().unwrap());
+        // token -> other on pool_a, then back other -> token on pool_b, its token1 -> token0 side
+        bot.pool_tokens.insert(pool_a, (token, address(2)));
+        bot.pool_tokens.insert(pool_b, (token, address(2)));
+        // A header exactly at its gas target keeps the base fee for the next block
+        let header = Block::<H256> {
+            number: Some(100.into()),
+            base_fee_per_gas: Some(U256::from(100_000_000_000u64)),
+            gas_used: U256::from(15_000_000u64),
+            gas_limit: U256::from(30_000_000u64),
+            ..Default::default()
+        };
+        let gas = bot.gas_oracle.observe(&header, U256::from(30_000_000_000u64)).unwrap();
+
+        let (amount_in, hash) = (U256::exp10(18) * 5, H256::repeat_byte(0xab));
+        let mined = Transaction { hash, block_number: Some(101.into()), ..Default::default() };
+        let receipt = TransactionReceipt {
+            transaction_hash: hash,
+            block_number: Some(101.into()),
+            status: Some(1.into()),
+            ..Default::default()
+        };
+        chain.script(vec![
+            reserves_output(1_000 * 10u128.pow(18), 2_000 * 10u128.pow(18)),
+            reserves_output(1_000 * 10u128.pow(18), 2_000 * 10u128.pow(18)),
+            quantity(7u64),
+            quantity(350_000u64),
+            json!(hash),
+            json!(mined),
+            json!(receipt),
+        ]);
+        let block = BlockId::Number(100.into());
+        let path = vec![pool_a, pool_b, token];
+        let sent = bot.execute_arbitrage(path.clone(), amount_in, block).await.unwrap().unwrap();
+        assert_eq!(sent.transaction_hash, hash);
+
+        for pool in [pool_a, pool_b] {
+            let call = IUniswapV2Pair::new(pool, chain.provider()).get_reserves().block(block);
+            chain.assert_request("eth_call", (&call.tx, block));
+        }
+        chain.assert_request("eth_getTransactionCount", (wallet.address(), "pending"));
+
+        // The sized input is borrowed and chained through the hops net of the 0.3% QuickSwap fee,
+        // each hop through its factory's router
+        let hop_b_in = get_amount_out(amount_in, U256::exp10(21), U256::exp10(21) * 2, amm_math::DEFAULT_V2_FEE);
+        assert!(hop_b_in < amount_in * U256::exp10(21) * 2 / (U256::exp10(21) + amount_in));
+        let routers: Vec<Address> = [QUICKSWAP_ROUTER, SUSHISWAP_ROUTER].iter().map(|r| r.parse().unwrap()).collect();
+        let addresses = |list: &[Address]| Token::Array(list.iter().map(|&a| Token::Address(a)).collect());
+        let args = abi::encode(&[
+            Token::Address(pool_a),
+            Token::Address(token),
+            Token::Uint(amount_in),
+            Token::Uint(U256::zero()),
+            Token::Uint(U256::from(3_000u32)),
+            addresses(&path),
+            Token::Array(vec![Token::Uint(amount_in), Token::Uint(hop_b_in)]),
+            addresses(&routers),
+            Token::Uint(U256::from(102u64)),
+        ]);
+        let selector = id("executeFlashLoanArbitrage(address,address,uint256,uint256,uint24,address[],uint256[],address[],uint256)");
+
+        // No value, nonce and gas limit from the node, fees from the gas snapshot
+        let mut tx: TypedTransaction = Eip1559TransactionRequest::new()
+            .from(wallet.address())
+            .to(executor)
+            .data([selector.as_slice(), &args].concat())
+            .value(U256::zero())
+            .chain_id(137u64)
+            .nonce(7u64)
+            .max_priority_fee_per_gas(gas.priority_fee)
+            .max_fee_per_gas(U256::from(230_000_000_000u64))
+            .into();
+        chain.assert_request("eth_estimateGas", [&tx]);
+        tx.set_gas(350_000u64);
+        let raw = tx.rlp_signed(&wallet.sign_transaction_sync(&tx).unwrap());
+        chain.assert_request("eth_sendRawTransaction", [raw]);
+        chain.assert_request("eth_getTransactionByHash", [hash]);
+        chain.assert_request("eth_getTransactionReceipt", [hash]);
+    }
+
+    #[tokio::test]
+    async fn test_allowlisted_pools_are_loaded_once_with_their_factory() {
+        let chain = MockChain::new();
+        let wallet = "0000000000000000000000000000000000000000000000000000000000000001"
+            .parse::<LocalWallet>()
+            .unwrap();
+        let (pool, token0, token1) = (address(10), address(1), address(2));
+        let sushi: Address = SUSHISWAP_FACTORY.parse().unwrap();
+        let mut bot = MevBot::with_provider(chain.provider(), wallet, address(99), address(98), U64::from(100))
+            .unwrap()
+            .with_discovery(DiscoverySettings { mode: DiscoveryMode::Allowlist, allowlist: vec![pool] });
+        chain.script(vec![
+            call_output(&[Token::Address(token0)]),
+            call_output(&[Token::Address(token1)]),
+            call_output(&[Token::Address(sushi)]),
+        ]);
+        bot.update_token_pairs().await.unwrap();
+        // Nothing left scripted, a second lookup would fail
+        bot.update_token_pairs().await.unwrap();
+
+        let pair = IUniswapV2Pair::new(pool, chain.provider());
+        for call in [pair.token_0(), pair.token_1()] {
+            chain.assert_request("eth_call", (&call.tx, "latest"));
+        }
+        chain.assert_request("eth_call", (&pair.factory().tx, "latest"));
+        assert_eq!(bot.pool_tokens[&pool], (token0, token1));
+        assert_eq!(bot.token_pairs[&token0], vec![pool]);
+        assert_eq!(bot.router_of(&pool).unwrap(), SUSHISWAP_ROUTER.parse::<Address>().unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_two_phase_commit_measures_drift_against_the_sent_amounts() {
+        let chain = MockChain::new();
+        let wallet = "0000000000000000000000000000000000000000000000000000000000000001"
+            .parse::<LocalWallet>()
+            .unwrap()
+            .with_chain_id(137u64);
+        let (pool_a, pool_b, token, other) = (address(10), address(11), address(1), address(2));
+        let mut bot = MevBot::with_provider(chain.provider(), wallet, address(99), address(98), U64::from(100))
+            .unwrap()
+            .with_access_lists(AccessListSettings { mode: AccessListMode::Off });
+        bot.pool_tokens.insert(pool_a, (token, other));
+        bot.pool_tokens.insert(pool_b, (token, other));
+        let header = Block::<H256> {
+            number: Some(100.into()),
+            base_fee_per_gas: Some(U256::from(30_000_000_000u64)),
+            gas_used: U256::from(15_000_000u64),
+            gas_limit: U256::from(30_000_000u64),
+            ..Default::default()
+        };
+        bot.gas_oracle.observe(&header, U256::from(30_000_000_000u64)).unwrap();
+
+        // token -> other at 1:2 on pool_a, other -> token at 1:1 on pool_b
+        let e18 = U256::exp10(18);
+        let (amount_in, hash) = (e18 * 5, H256::repeat_byte(0xab));
+        let out_a = get_amount_out(amount_in, e18 * 1_000, e18 * 2_000, amm_math::DEFAULT_V2_FEE);
+        let sent_b = out_a * 9_970 / 10_000;
+        let out_b = get_amount_out(sent_b, e18 * 2_000, e18 * 2_000, amm_math::DEFAULT_V2_FEE);
+        let swap = |pool: Address, amount_0_out: U256, amount_1_out: U256| Log {
+            address: pool,
+            topics: vec![V2SwapEvent::signature(), H256::zero(), H256::zero()],
+            data: abi::encode(&[
+                Token::Uint(U256::zero()),
+                Token::Uint(U256::zero()),
+                Token::Uint(amount_0_out),
+                Token::Uint(amount_1_out),
+            ])
+            .into(),
+            ..Default::default()
+        };
+        let receipt = TransactionReceipt {
+            transaction_hash: hash,
+            block_number: Some(101.into()),
+            status: Some(1.into()),
+            logs: vec![swap(pool_a, U256::zero(), out_a), swap(pool_b, out_b, U256::zero())],
+            ..Default::default()
+        };
+        let mined = Transaction { hash, block_number: Some(101.into()), ..Default::default() };
+        chain.script(vec![
+            reserves_output(1_000 * 10u128.pow(18), 2_000 * 10u128.pow(18)),
+            reserves_output(2_000 * 10u128.pow(18), 2_000 * 10u128.pow(18)),
+            quantity(7u64),
+            quantity(350_000u64),
+            json!(hash),
+            json!(mined),
+            json!(receipt),
+        ]);
+        let block = BlockId::Number(100.into());
+        bot.execute_arbitrage(vec![pool_a, pool_b, token], amount_in, block).await.unwrap().unwrap();
+
+        // pool_b was sent the haircut amount; its Swap matches the quote for exactly that
+        let drift = bot.drift_monitor.snapshot();
+        assert_eq!(drift[&pool_a].mean_ratio(), Some(1.0));
+        assert_eq!(drift[&pool_b].mean_ratio(), Some(1.0));
+    }
 
     #[tokio::test]
     async fn test_analyze_opportunity() {
@@ -423,7 +2192,11 @@ mod tests {
         let pairs_a = vec![];
         let pairs_b = vec![];
 
-        let result = bot.analyze_opportunity(_token_a, _token_b, &pairs_a, &pairs_b).await.unwrap();
+        let block = BlockId::Number(BlockNumber::Latest);
+        let result = bot
+            .analyze_opportunity(_token_a, _token_b, &pairs_a, &pairs_b, block)
+            .await
+            .unwrap();
         assert!(result == true || result == false);
     }
 }
@@ -0,0 +1,174 @@
+// src/logging.rs
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use std::sync::Mutex;
+use tracing_appender::{non_blocking::WorkerGuard, rolling};
+use tracing_subscriber::{
+    fmt,
+    layer::{Layered, SubscriberExt},
+    reload, util::SubscriberInitExt, EnvFilter, Layer, Registry,
+};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StdoutFormat {
+    #[default]
+    Pretty,
+    Json,
+    Off,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FileRotation {
+    Minutely,
+    Hourly,
+    #[default]
+    Daily,
+    Never,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FileSinkSettings {
+    pub directory: String,
+    #[serde(default = "default_file_prefix")]
+    pub prefix: String,
+    #[serde(default)]
+    pub rotation: FileRotation,
+    #[serde(default)]
+    pub json: bool,
+}
+
+fn default_file_prefix() -> String {
+    "flashwich.log".to_string()
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LogSettings {
+    // EnvFilter directives, e.g. "info,polygon_mev_bot::simulation_engine=debug"
+    #[serde(default = "default_level")]
+    pub level: String,
+    #[serde(default)]
+    pub stdout: StdoutFormat,
+    #[serde(default)]
+    pub file: Option<FileSinkSettings>,
+    #[serde(default)]
+    pub syslog: bool,
+}
+
+fn default_level() -> String {
+    "info".to_string()
+}
+
+impl Default for LogSettings {
+    fn default() -> Self {
+        Self {
+            level: default_level(),
+            stdout: StdoutFormat::default(),
+            file: None,
+            syslog: false,
+        }
+    }
+}
+
+type FilteredRegistry = Layered<reload::Layer<EnvFilter, Registry>, Registry>;
+type BoxedLayer = Box<dyn Layer<FilteredRegistry> + Send + Sync>;
+
+// Keeps the non-blocking writers alive and allows swapping the filter at runtime
+pub struct LogHandle {
+    filter: reload::Handle<EnvFilter, Registry>,
+    current: Mutex<String>,
+    _guards: Vec<WorkerGuard>,
+}
+
+impl std::fmt::Debug for LogHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("LogHandle")
+            .field("current", &self.current_filter())
+            .finish()
+    }
+}
+
+impl LogHandle {
+    pub fn current_filter(&self) -> String {
+        self.current.lock().unwrap().clone()
+    }
+
+    // Replace the active filter directives (used by the control API)
+    pub fn set_filter(&self, directives: &str) -> Result<()> {
+        let filter = EnvFilter::try_new(directives)
+            .map_err(|e| anyhow!("Invalid log filter '{directives}': {e}"))?;
+        self.filter.reload(filter)?;
+        *self.current.lock().unwrap() = directives.to_string();
+        Ok(())
+    }
+}
+
+// Install the global subscriber. RUST_LOG, when set, overrides the configured level.
+// `log` macros used across the crate are bridged into tracing.
+pub fn init(settings: &LogSettings) -> Result<LogHandle> {
+    let directives = std::env::var("RUST_LOG").unwrap_or_else(|_| settings.level.clone());
+    let filter = EnvFilter::try_new(&directives)
+        .map_err(|e| anyhow!("Invalid log filter '{directives}': {e}"))?;
+    let (filter_layer, filter_handle) = reload::Layer::new(filter);
+
+    let mut layers: Vec<BoxedLayer> = Vec::new();
+    let mut guards = Vec::new();
+
+    match settings.stdout {
+        StdoutFormat::Pretty => layers.push(fmt::layer().boxed()),
+        StdoutFormat::Json => layers.push(fmt::layer().json().boxed()),
+        StdoutFormat::Off => {}
+    }
+
+    if let Some(file) = &settings.file {
+        let appender = match file.rotation {
+            FileRotation::Minutely => rolling::minutely(&file.directory, &file.prefix),
+            FileRotation::Hourly => rolling::hourly(&file.directory, &file.prefix),
+            FileRotation::Daily => rolling::daily(&file.directory, &file.prefix),
+            FileRotation::Never => rolling::never(&file.directory, &file.prefix),
+        };
+        let (writer, guard) = tracing_appender::non_blocking(appender);
+        guards.push(guard);
+
+        let layer = fmt::layer().with_ansi(false).with_writer(writer);
+        if file.json {
+            layers.push(layer.json().boxed());
+        } else {
+            layers.push(layer.boxed());
+        }
+    }
+
+    if settings.syslog {
+        layers.push(syslog_layer()?);
+    }
+
+    tracing_subscriber::registry()
+        .with(filter_layer)
+        .with(layers)
+        .try_init()
+        .map_err(|e| anyhow!("Failed to install log subscriber: {e}"))?;
+
+    Ok(LogHandle {
+        filter: filter_handle,
+        current: Mutex::new(directives),
+        _guards: guards,
+    })
+}
+
+#[cfg(feature = "syslog")]
+fn syslog_layer() -> Result<BoxedLayer> {
+    let identity = std::ffi::CStr::from_bytes_with_nul(b"flashwich\0")?;
+    let syslog = syslog_tracing::Syslog::new(
+        identity,
+        syslog_tracing::Options::LOG_PID,
+        syslog_tracing::Facility::Daemon,
+    )
+    .ok_or_else(|| anyhow!("Syslog is already initialized"))?;
+    Ok(fmt::layer().with_ansi(false).with_writer(syslog).boxed())
+}
+
+#[cfg(not(feature = "syslog"))]
+fn syslog_layer() -> Result<BoxedLayer> {
+    Err(anyhow!("Syslog sink requested but the crate was built without the `syslog` feature"))
+}
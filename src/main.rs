@@ -1,41 +1,88 @@
 // src/main.rs
-mod simulation_engine;
-mod fastlane_integration;
-pub mod routers;
+mod cli;
 
 use anyhow::{Result, bail};
 use ethers::{
-    middleware::Middleware,
-    providers::{Provider, StreamExt, Ws},
-    types::{Address, U256, BlockNumber, U64, TransactionReceipt},
+    middleware::{Middleware, SignerMiddleware},
+    providers::{Provider, StreamExt},
+    types::{
+        transaction::eip2718::TypedTransaction, Address, U256, BlockId, BlockNumber, U64, H256, TransactionReceipt,
+    },
     signers::{LocalWallet, Signer},
-    contract::abigen,
 };
 use log::{info, warn, debug, error};
 use std::str::FromStr;
 use std::sync::Arc;
-use std::collections::HashMap;
 use std::convert::From;
+use clap::Parser;
 
-// Import token data
-use serde_json::Value;
-use std::fs;
-
-// Simulation and routing modules
-use simulation_engine::{
-    ArbitrageOpportunity,
-    AdvancedSimulationEngine,
-};
-use fastlane_integration::FastLaneClient;
-use routers::{
-    quickswap::QuickswapRouter,
-    uniswap_v3::UniswapV3Router,
-    sushiswap::SushiswapRouter,
+use cli::{Cli, Command, CtlCommand};
+use polygon_mev_bot::{
+    // Simulation and routing modules
+    simulation_engine::{ArbitrageOpportunity, AdvancedSimulationEngine},
+    fastlane_integration::FastLaneClient,
+    preflight::PreflightSimulator,
+    settings::Settings,
+    control_api::{self, ControlState},
+    idempotency::IdempotencyGuard,
+    gas_oracle::{GasCeilingSettings, GasOracle, GasSnapshot},
+    profit_threshold::ProfitThresholdSettings,
+    callback_gas::FlashProvider,
+    store::{CallbackGasRecord, GasSpendRecord, OpportunityRecord, Store},
+    indexer::PoolEventIndexer,
+    token_registry::{self, TokenRegistry, DEFAULT_TOKENS_PATH},
+    executor::ExecutorAdapter,
+    setup::{CheckStatus, ExecutorSetup, ReadinessReport},
+    deploy::{self, ExecutorDeployer},
+    tx_sender::TxSender,
+    submission::Submitter,
+    access_list::AccessListBuilder,
+    mempool::{self, MempoolSettings},
+    propagation::PropagationTracker,
+    bid_market::BidMarket,
+    opportunity_class::ClassSettings,
+    execution_mode::ExecutionModes,
+    self_trade::{route_venues, SelfTradeGuard},
+    alerts::Alerts,
+    nonce_watchdog::NonceWatchdog,
+    wallet_watcher::WalletWatcher,
+    admin_watch::AdminWatcher,
+    flash_source::{FlashLeg, FlashSources},
+    reconciliation::BalanceReconciler,
+    permit::{Permit, PermitSigner},
+    bundle_fanout::FanoutSettings,
+    spread_predictor::{self, HeuristicPredictor, SpreadFeatures, SpreadPredictor},
+    post_mortem::{BundlePostMortem, MissedBundle},
+    opportunity_codec::{self, QueuedOpportunity},
+    opportunity_id::OpportunityId,
+    opportunity_queue::OpportunityQueue,
+    rpc_usage::{self, MeteredWs, RpcCostEstimator},
+    failure_taxonomy::{record_failure, record_outcome, FailureReason, RevertClass},
+    operator_controls::{self, OperatorControls},
+    pool_kind::PoolClassifier,
+    report::{Report, ReportFormat},
+    graph_export::{GraphExport, GraphFormat},
+    prefetch::PairPrefetcher,
+    reserve_cache::ReserveCache,
+    shared_reserves::SharedReserves,
+    bundle_simulation::{BundleSimulator, BundleVerdict, SimulationCall},
+    validation::{self, env_address},
+    doctor::Doctor,
+    split_route::{SplitLeg, SplitPlanner},
+    executor_whitelist::ExecutorWhitelist,
+    exposure::{ExposureLimit, ExposureRefused},
+    dashboard::{self, Dashboard, ExecutionRow, OpportunityRow},
+    erc20::Erc20,
+    routers::{
+        quickswap::{QuickswapRouter, QUICKSWAP_ROUTER},
+        uniswap_v3::{UniswapV3Router, UNISWAP_V3_ROUTER},
+        sushiswap::{SushiswapRouter, SUSHISWAP_ROUTER},
+    },
+    logging,
+    parquet_export,
+    pipeline,
 };
 
-// Define the contract ABI for the Flash Loan contract
-abigen!(FlashLoanContract, "abis/FlashLoanArbitrage.json",);
-
 // Constants for common tokens on Polygon
 const WETH: &str = "0x0d500B1d8E8eF31E21C99d1Db9A6444d3ADf1270"; // WMATIC
 const USDC: &str = "0x2791Bca1f2de4661ED88A30C99A7a9449Aa84174";
@@ -43,29 +90,102 @@ const USDT: &str = "0xc2132D05D31c914a87C6611C10748AEb04B58e8F";
 
 // Flash Loan Arbitrage Struct
 struct FlashLoanArbitrage {
-    provider: Arc<Provider<Ws>>,
+    provider: Arc<Provider<MeteredWs>>,
     engine: AdvancedSimulationEngine,
     fastlane_client: FastLaneClient,
+    preflight: PreflightSimulator,
+    idempotency: IdempotencyGuard,
+    gas_oracle: GasOracle<Provider<MeteredWs>>,
+    profit_threshold: ProfitThresholdSettings,
+    gas_ceiling: GasCeilingSettings,
+    flash_loan_contract: Address,
+    // Execution txs are signed locally by the searcher key and sent privately if possible
+    submitter: Submitter<Provider<MeteredWs>>,
+    access_lists: AccessListBuilder<Provider<MeteredWs>>,
+    // Signs allowances passed in calldata when the executor takes a `permits` argument
+    permits: Option<PermitSigner<Provider<MeteredWs>>>,
+    propagation: PropagationTracker,
+    // Market-clearing bid and tip learned from the FastLane auction winners
+    bids: BidMarket<Provider<MeteredWs>>,
+    tokens: Arc<TokenRegistry>,
+    // Which opportunity classes trade, and how each scales the profit floor
+    classes: ClassSettings,
+    // Journal of opportunities, their outcomes and the gas they spent
+    store: Store,
+    executor: ExecutorAdapter,
+    dashboard: Dashboard,
+    // Active aggressive/balanced/conservative thresholds, switched live via the control API
+    modes: ExecutionModes,
+    // Pause, profit floor and blacklist set by the operator through the control API
+    controls: OperatorControls,
+    // Keeps classes from trading the same pools for the same target block
+    self_trade: SelfTradeGuard,
+    // Offers each trade for the next few target blocks at once
+    fanout: FanoutSettings,
+    // Classifies why a bundle missed its blocks
+    post_mortem: BundlePostMortem<Provider<MeteredWs>>,
+    // Lender liquidity checks that split or downsize a borrow
+    flash_sources: Option<FlashSources<Provider<MeteredWs>>>,
+    // Detections are published here for execution workers
+    queue: Option<tokio::sync::Mutex<OpportunityQueue>>,
+    execute_locally: bool,
+    // Hosted simulation of the whole bundle for high-value trades
+    bundle_simulator: Option<BundleSimulator>,
+    // Splits a two-hop trade's first hop over the pair's V2 and V3 pools
+    split_planner: Option<SplitPlanner<Provider<MeteredWs>>>,
+    // Routers and tokens the executor contract accepts, read at startup
+    whitelist: ExecutorWhitelist,
+    // Borrowed notional of pending executions, capped across classes
+    exposure: ExposureLimit,
+    // How many blocks a detected spread is expected to stay open, bounding the fan-out
+    spread_predictor: Arc<dyn SpreadPredictor>,
+}
+
+// Contracts and accounts the bot trades through, from the environment
+struct Accounts {
     flash_loan_contract: Address,
-    wallet: LocalWallet,
-    tokens: HashMap<String, Value>,
+    fastlane_address: Address,
+    fastlane_sender_address: Address,
+    solver_address: Address,
+    searcher: LocalWallet,
+    beneficiary: Address,
+}
+
+// State the bot shares with the tasks main runs next to it, or that main built before it
+struct SharedState {
+    tokens: Arc<TokenRegistry>,
+    store: Store,
+    dashboard: Dashboard,
+    modes: ExecutionModes,
+    controls: OperatorControls,
+    queue: Option<OpportunityQueue>,
+    whitelist: ExecutorWhitelist,
+}
+
+// What `encode_with` takes besides the opportunity and target block, re-encoded per variant
+struct CalldataParts<'a> {
+    permits: &'a [Permit],
+    flash_legs: &'a [FlashLeg],
+    split_legs: &'a [SplitLeg],
 }
 
 impl FlashLoanArbitrage {
-    fn new(
-        provider: Arc<Provider<Ws>>,
-        flash_loan_contract: Address,
-        fastlane_address: Address,
-        fastlane_sender_address: Address,
-        solver_address: Address,
-        wallet: LocalWallet,
-        max_delay_blocks: U256,
-        min_priority_fee: U256,
+    async fn new(
+        provider: Arc<Provider<MeteredWs>>,
+        settings: &Settings,
+        accounts: Accounts,
+        shared: SharedState,
     ) -> Result<Self> {
-        // Load tokens from JSON
-        let tokens_path = "./src/tokens.json";
-        let tokens_content = fs::read_to_string(tokens_path)?;
-        let tokens: HashMap<String, Value> = serde_json::from_str(&tokens_content)?;
+        let Accounts {
+            flash_loan_contract,
+            fastlane_address,
+            fastlane_sender_address,
+            solver_address,
+            searcher,
+            beneficiary,
+        } = accounts;
+        let SharedState { tokens, store, dashboard, modes, controls, queue, whitelist } = shared;
+        let executor = ExecutorAdapter::from_settings(&settings.executor)?;
 
         // Initialize routers
         let quickswap_router = QuickswapRouter::new(provider.clone());
@@ -77,11 +197,23 @@ impl FlashLoanArbitrage {
             quickswap_router,
             sushiswap_router,
             uniswap_v3_router,
-        );
+        )
+        .with_profit_tokens(settings.profit_tokens.clone())
+        .with_pool_classifier(Arc::new(PoolClassifier::new(
+            provider.clone(),
+            settings.pool_detection.clone(),
+            settings.fees.clone(),
+            settings.v3.clone(),
+        )));
+
+        // Configuration parameters
+        let max_delay_blocks = U256::from(3);
+        let min_priority_fee = U256::from(1_000_000_000u64); // 1 gwei
 
         let fastlane_client = FastLaneClient::new(
             provider.clone(),
-            wallet.clone(),
+            searcher.clone(),
+            beneficiary,
             fastlane_address,
             fastlane_sender_address,
             solver_address,
@@ -89,21 +221,77 @@ impl FlashLoanArbitrage {
             min_priority_fee,
         );
 
+        let preflight = settings
+            .preflight
+            .layouts
+            .iter()
+            .fold(PreflightSimulator::new(provider.clone()), |simulator, (token, layout)| {
+                simulator.with_layout(*token, *layout)
+            });
+        let gas_oracle = GasOracle::new(provider.clone()).with_forecast(settings.gas_forecast.clone());
+        let permits = (settings.permits.enabled && executor.uses_permits())
+            .then(|| PermitSigner::new(provider.clone(), searcher.clone(), settings.permits.clone()));
+        let tx_sender = TxSender::new(provider.clone(), searcher);
+        let submitter = Submitter::new(provider.clone(), tx_sender, flash_loan_contract, &settings.submission).await?;
+        let access_lists = AccessListBuilder::new(provider.clone(), settings.access_list.clone());
+        let bids = BidMarket::new(provider.clone(), settings.bid_market.clone(), &settings.discovery.allowlist);
+        let post_mortem = BundlePostMortem::new(provider.clone(), settings.post_mortem.clone());
+        let flash_sources = settings
+            .flash_sources
+            .enabled
+            .then(|| FlashSources::new(provider.clone(), settings.flash_sources.clone()));
+        let bundle_simulator = BundleSimulator::new(settings.bundle_simulation.clone())?;
+        let split_planner = SplitPlanner::new(
+            provider.clone(),
+            settings.split_route.clone(),
+            settings.fees.clone(),
+            settings.v3.clone(),
+        );
+        let split_planner = (split_planner.enabled() && executor.uses_split_legs()).then_some(split_planner);
+        let exposure = ExposureLimit::new(settings.exposure.clone(), tokens.clone());
+
         Ok(Self {
             provider,
             engine,
             fastlane_client,
+            preflight,
+            idempotency: IdempotencyGuard::default(),
+            gas_oracle,
+            profit_threshold: settings.profit_threshold.clone(),
+            gas_ceiling: settings.gas_ceiling.clone(),
             flash_loan_contract,
-            wallet,
+            submitter,
+            access_lists,
+            permits,
+            propagation: PropagationTracker::new(settings.propagation.clone()),
+            bids,
+            classes: settings.classes.clone(),
+            store,
             tokens,
+            executor,
+            dashboard,
+            modes,
+            controls,
+            self_trade: SelfTradeGuard::new(settings.self_trade.clone()),
+            fanout: settings.bundle_fanout.clone(),
+            post_mortem,
+            flash_sources,
+            queue: queue.map(tokio::sync::Mutex::new),
+            execute_locally: settings.queue.execute_locally,
+            bundle_simulator,
+            split_planner,
+            whitelist,
+            exposure,
+            spread_predictor: Arc::new(HeuristicPredictor::new(settings.spread_predictor.clone())),
         })
     }
 
 
-    // Enhanced multi-leg arbitrage method
+    // Enhanced multi-leg arbitrage method; `victim` is the pending tx the trade backruns
     async fn execute_multi_leg_arbitrage(
         &self,
-        opportunity: &ArbitrageOpportunity
+        opportunity: &ArbitrageOpportunity,
+        victim: Option<H256>,
     ) -> Result<TransactionReceipt> {
         // Validate arbitrage route
         if opportunity.routers.is_empty() {
@@ -117,80 +305,634 @@ impl FlashLoanArbitrage {
             .number
             .ok_or_else(|| anyhow::anyhow!("Block number not available"))?;
 
-        let target_block = U64::from(current_block.as_u64() + 1);
+        // Measured propagation latency decides how far ahead the bundle has to land
+        let target_block = U64::from(current_block.as_u64() + self.propagation.blocks_ahead());
+
+        // Mempool detections of the same spread must not be submitted twice
+        let key = self.idempotency.key(
+            &opportunity.routers,
+            opportunity.token0,
+            opportunity.token1,
+            current_block.as_u64(),
+        );
+        if !self.idempotency.try_acquire(key, current_block.as_u64()) {
+            bail!("Opportunity {:?} already submitted in this block window", key.0);
+        }
+
+        // Borrow only what the flash lenders hold: split over them or downsize the trade
+        let mut flash_legs = Vec::new();
+        let fitted;
+        let opportunity = match &self.flash_sources {
+            Some(sources) => {
+                let split = self.executor.uses_flash_legs();
+                let block = BlockId::Number(current_block.into());
+                let Some((resized, legs)) = sources.fit(opportunity, split, block).await else {
+                    self.idempotency.release(&key);
+                    bail!("Flash lenders cannot fund {} of {:?}", opportunity.amount0, opportunity.token0);
+                };
+                flash_legs = legs;
+                fitted = resized;
+                &fitted
+            }
+            None => opportunity,
+        };
+
+        // The input leg is authorized by signature rather than a standing approval. Until the
+        // broadcast nothing reached the network, so every failure below releases the key
+        let mut permits = Vec::new();
+        if let Some(signer) = &self.permits {
+            let permit = signer
+                .sign(opportunity.token0, self.flash_loan_contract, opportunity.amount0)
+                .await
+                .inspect_err(|_| self.idempotency.release(&key))?;
+            permits.extend(permit);
+        }
+
+        // A two-hop trade may sell its first hop into the pair's V2 and V3 pools at once
+        let mut split_legs = Vec::new();
+        if let (Some(planner), [token_in, token_out]) = (&self.split_planner, opportunity.path.as_slice()) {
+            match planner.plan(*token_in, *token_out, opportunity.amount0, current_block.as_u64()).await {
+                Ok(Some(plan)) => {
+                    debug!(
+                        "Splitting the first hop of {:?} {} bps into V2 for {} over {} single-pool",
+                        key.0,
+                        plan.ratio_bps(),
+                        plan.amount_out,
+                        plan.single_out
+                    );
+                    split_legs = plan.legs;
+                }
+                Ok(None) => {}
+                Err(e) => debug!("Split planning failed, keeping one pool per hop: {:?}", e),
+            }
+        }
+
+        // Calldata shape comes from the configured executor ABI
+        let data = self
+            .executor
+            .encode_with(opportunity, target_block, &permits, &flash_legs, &split_legs)
+            .inspect_err(|_| self.idempotency.release(&key))?;
+
+        // Bid and tip follow what recently won the auction, capped by the execution mode
+        let max_bid = self.modes.profile().max_bid(opportunity.expected_profit);
+        let bid = self.bids.bid(opportunity.expected_profit).min(max_bid);
+        let gas = self.bids.apply(self.gas_oracle.snapshot());
 
-        // Create FastLane bundle
+        // Create FastLane bundle, signed by the searcher key
         let _bundle = self.fastlane_client
-            .create_fastlane_bundle(opportunity, target_block)
-            .await?;
-
-        // Corrected method call - using the proper function signature from ABI
-        let contract = FlashLoanContract::new(self.flash_loan_contract, Arc::clone(&self.provider));
-        
-        // Create the ArbitrageOpportunity struct expected by the contract
-        let arbitrage_opportunity = FlashLoanContractArbitrageOpportunity {
+            .create_fastlane_bundle(data.clone(), target_block, bid)
+            .await
+            .inspect_err(|_| self.idempotency.release(&key))?;
+
+        let mut tx = self.submitter.sender().request(
+            self.flash_loan_contract,
+            data,
+            bid, // Add value for FastLane bid
+            &gas,
+            None,
+        );
+        let block = Some(BlockId::Number(current_block.into()));
+        self.access_lists
+            .attach(&mut tx, block, &[], &opportunity.path, &[self.flash_loan_contract])
+            .await;
+
+        // Verify profitability with faked router approvals before spending gas on real ones
+        if let Err(e) = self.preflight
+            .simulate_without_approvals(
+                &tx,
+                block,
+                self.flash_loan_contract,
+                &opportunity.path,
+                &opportunity.routers,
+            )
+            .await
+        {
+            // A later detection may retry
+            self.idempotency.release(&key);
+            return Err(e);
+        }
+
+        // High-value trades are simulated once more as the full bundle, behind the victim
+        if let Some(simulator) = self.bundle_simulator.as_ref().filter(|s| s.covers(opportunity.expected_profit)) {
+            let mut calls = Vec::new();
+            if let Some(hash) = victim {
+                match self.provider.get_transaction(hash).await {
+                    Ok(Some(victim_tx)) => calls.push(SimulationCall::from(&victim_tx)),
+                    _ => debug!("Victim {:?} not found, simulating the execution alone", hash),
+                }
+            }
+            calls.push(SimulationCall::from(&tx));
+            let holders = [self.flash_loan_contract, tx.from().copied().unwrap_or_default()];
+            let verdict = simulator
+                .validate(&calls, current_block, opportunity.token0, &holders, opportunity.expected_profit)
+                .await
+                .inspect_err(|_| self.idempotency.release(&key))?;
+            if let Some(verdict) = verdict.filter(|verdict| !matches!(verdict, BundleVerdict::Confirmed { .. })) {
+                self.idempotency.release(&key);
+                bail!("Bundle simulation rejected {:?}: {:?}", key.0, verdict);
+            }
+        }
+
+        // The borrowed notional counts as in flight until the receipt is in
+        let borrowed = [(opportunity.token0, opportunity.amount0), (opportunity.token1, opportunity.amount1)];
+        let _in_flight = self
+            .exposure
+            .open(opportunity.class.as_str(), &borrowed)
+            .inspect_err(|_| self.idempotency.release(&key))?;
+
+        // Another strategy may already trade one of these pools in the target block
+        let venues = route_venues(&opportunity.routers, &opportunity.path);
+        let reserve = |target: U64| {
+            self.self_trade
+                .reserve(&venues, target.as_u64(), opportunity.class.as_str(), opportunity.expected_profit)
+                .is_some_and(|reservation| self.self_trade.commit(&reservation))
+        };
+        if !reserve(target_block) {
+            self.idempotency.release(&key);
+            bail!("Venues of {:?} are taken by another strategy for block {}", key.0, target_block);
+        }
+
+        // Private RPC first; the public mempool only when the executor enforces minProfit
+        let receipt = if self.fanout.enabled && self.submitter.can_fan_out() {
+            let parts = CalldataParts { permits: &permits, flash_legs: &flash_legs, split_legs: &split_legs };
+            self.submit_fanout(opportunity, victim, tx, target_block, parts, reserve).await?
+        } else {
+            self.submitter
+                .submit(tx)
+                .await?
+                .await?
+                .ok_or_else(|| anyhow::anyhow!("No receipt returned"))?
+        };
+
+        // Actual spend at the inclusion block's base fee; the tx value is the FastLane bid
+        if let (Some(gas_used), Some(price), Some(number)) =
+            (receipt.gas_used, receipt.effective_gas_price, receipt.block_number)
+        {
+            let base_fee = match self.provider.get_block(number).await {
+                Ok(Some(block)) => block.base_fee_per_gas.unwrap_or_default(),
+                _ => self.gas_oracle.snapshot().base_fee,
+            };
+            let paid = GasSnapshot {
+                block_number: number.as_u64(),
+                base_fee: base_fee.min(price),
+                priority_fee: price.saturating_sub(base_fee),
+            };
+            let cost = paid.cost(gas_used, U256::zero(), bid);
+            info!("Gas spent: {}", cost);
+            let spend = GasSpendRecord {
+                tx_hash: receipt.transaction_hash,
+                block_number: number.as_u64(),
+                gas_used,
+                cost: cost.total(),
+            };
+            if let Err(e) = self.store.record_gas_spend(&spend) {
+                warn!("Failed to journal gas spend: {:?}", e);
+            }
+
+            // Successful executions calibrate the flash-loan callback gas for this provider
+            // and route length; reverts stop short of the repayment
+            if receipt.status == Some(1u64.into()) {
+                let provider = FlashProvider::for_amounts(opportunity.amount0, opportunity.amount1);
+                let hops = opportunity.routers.len();
+                self.profit_threshold.observe_receipt(provider, hops, gas_used);
+                let sample = CallbackGasRecord {
+                    tx_hash: receipt.transaction_hash,
+                    block_number: number.as_u64(),
+                    provider: provider.as_str().to_string(),
+                    hops: hops as u64,
+                    gas_used,
+                };
+                if let Err(e) = self.store.record_callback_gas(&sample) {
+                    warn!("Failed to journal callback gas: {:?}", e);
+                }
+            }
+        }
+
+        Ok(receipt)
+    }
+
+    // Bundles `tx` for `target_block` plus variants for the following blocks the spread is
+    // expected to last, each with its own target block and lowered minimums; blocks another
+    // strategy holds are left out. A relay taking no bundles gets the single tx instead. A miss
+    // fails with the `MissedBundle` found by the post-mortem
+    async fn submit_fanout(
+        &self,
+        opportunity: &ArbitrageOpportunity,
+        victim: Option<H256>,
+        mut tx: TypedTransaction,
+        target_block: U64,
+        parts: CalldataParts<'_>,
+        reserve: impl Fn(U64) -> bool,
+    ) -> Result<TransactionReceipt> {
+        // Estimated once: the variants only differ in calldata an estimate at the current
+        // block may reject for targeting a later one
+        tx.set_gas(self.provider.estimate_gas(&tx, None).await?);
+        let mut variants = vec![(target_block, tx.clone())];
+        let features = SpreadFeatures::new(
+            opportunity.class,
+            opportunity.routers.len(),
+            opportunity.amount0.max(opportunity.amount1),
+            opportunity.expected_profit,
+            0,
+        );
+        let persistence = spread_predictor::predict(self.spread_predictor.as_ref(), &features);
+        let targets = self.fanout.targets_within(target_block, persistence);
+        for (offset, target) in targets.into_iter().enumerate().skip(1) {
+            if !reserve(target) {
+                continue;
+            }
+            let variant = self.fanout.variant(opportunity, offset as u64);
+            let mut variant_tx = tx.clone();
+            let data = self.executor.encode_with(&variant, target, parts.permits, parts.flash_legs, parts.split_legs)?;
+            variant_tx.set_data(data);
+            variants.push((target, variant_tx));
+        }
+
+        let last_block = variants.last().map_or(target_block, |(target, _)| *target);
+        let hashes = match self.submitter.submit_fanout(variants, &self.fanout.bundle_method).await {
+            Ok(hashes) => hashes,
+            Err(e) => {
+                warn!("Bundle fan-out unavailable ({}), submitting for block {} only", e, target_block);
+                return self.submitter
+                    .submit(tx)
+                    .await?
+                    .await?
+                    .ok_or_else(|| anyhow::anyhow!("No receipt returned"));
+            }
+        };
+        info!("Trade offered for blocks {}..={} in {} bundles", target_block, last_block, hashes.len());
+        if let Some(receipt) = self.submitter.await_any(&hashes, last_block).await? {
+            return Ok(receipt);
+        }
+        if self.post_mortem.is_enabled() {
+            let (first, last) = (target_block.as_u64(), last_block.as_u64());
+            match self.post_mortem.analyze(&opportunity.path, victim, &hashes, first, last).await {
+                Ok(missed) => return Err(missed.into()),
+                Err(e) => warn!("Post-mortem of the missed bundles failed: {:?}", e),
+            }
+        }
+        bail!("None of the {} bundles landed by block {}", hashes.len(), last_block)
+    }
+
+    // Journal status of a failed execution: the miss reason for a bundle that missed its blocks
+    fn failure_status(error: &anyhow::Error) -> &'static str {
+        error.downcast_ref::<MissedBundle>().map_or("failed", |missed| missed.reason.as_str())
+    }
+
+    // Mined but reverted; the receipt carries no reason
+    fn record_reverted(class: &str, receipt: &TransactionReceipt) {
+        if receipt.status == Some(0u64.into()) {
+            record_failure(class, FailureReason::Reverted(RevertClass::Unknown));
+        }
+    }
+
+    // Dynamic profit threshold at the current gas regime, scaled for the class and mode
+    fn min_profit(&self, opportunity: &ArbitrageOpportunity) -> U256 {
+        let provider = FlashProvider::for_amounts(opportunity.amount0, opportunity.amount1);
+        let min_profit = self.profit_threshold.min_profit(
+            &self.bids.apply(self.gas_oracle.snapshot()),
+            provider,
+            opportunity.routers.len(),
+            self.bids.market_bid(),
+        );
+        let min_profit = self.classes.scale_min_profit(opportunity.class, min_profit);
+        self.controls.apply_min_profit(self.modes.profile().scale_min_profit(min_profit))
+    }
+
+    // Operator interventions: a pause holds every trade, the blacklist the routes through it
+    fn operator_hold(&self, opportunity: &ArbitrageOpportunity) -> Option<&'static str> {
+        if self.controls.is_read_only() {
+            return Some("read_only");
+        }
+        if self.controls.is_paused() {
+            return Some("paused");
+        }
+        let touched: Vec<Address> = opportunity.path.iter().chain(&opportunity.routers).copied().collect();
+        self.controls.touches_blacklist(&touched).then_some("blacklisted")
+    }
+
+    fn pair(&self, opportunity: &ArbitrageOpportunity) -> String {
+        format!("{}/{}", self.tokens.symbol(&opportunity.token0), self.tokens.symbol(&opportunity.token1))
+    }
+
+    // Executes a journaled opportunity and records the outcome
+    async fn execute_detected(
+        &self,
+        opportunity: &ArbitrageOpportunity,
+        id: OpportunityId,
+        victim: Option<H256>,
+        entry: Option<i64>,
+        number: u64,
+        pair: String,
+    ) {
+        let class = opportunity.class.as_str();
+        let (status, tx_hash) = match self.execute_multi_leg_arbitrage(opportunity, victim).await {
+            Ok(receipt) => {
+                info!("Arbitrage {} executed successfully. Tx Hash: {:?}", id.short(), receipt.transaction_hash);
+                Self::record_reverted(class, &receipt);
+                ("submitted", Some(receipt.transaction_hash))
+            }
+            Err(e) => match e.downcast_ref::<ExposureRefused>() {
+                Some(refused) => {
+                    debug!("Arbitrage {} held back: {}", id.short(), refused);
+                    (refused.0, None)
+                }
+                None => {
+                    warn!("Arbitrage execution {} failed: {:?}", id.short(), e);
+                    record_failure(class, FailureReason::from_error(&e));
+                    (Self::failure_status(&e), None)
+                }
+            },
+        };
+        self.update_journal(entry, class, status, tx_hash);
+        self.dashboard.record_execution(ExecutionRow {
+            block_number: number,
+            pair,
+            status: status.to_string(),
+            tx_hash,
+        });
+    }
+
+    // Journals the opportunity and counts it per class; journal failures never block execution
+    fn journal(
+        &self,
+        opportunity: &ArbitrageOpportunity,
+        id: OpportunityId,
+        block_number: u64,
+        min_profit: U256,
+        status: &str,
+    ) -> Option<i64> {
+        let class = opportunity.class.as_str();
+        record_outcome(class, status);
+        let record = OpportunityRecord {
+            detected_at: std::time::SystemTime::now()
+                .duration_since(std::time::UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or_default(),
+            block_number,
+            class: class.to_string(),
             token0: opportunity.token0,
             token1: opportunity.token1,
-            amount0: opportunity.amount0,
-            amount1: opportunity.amount1,
-            fee: opportunity.fee.unwrap_or(3000), // Default fee if not specified
-            path: opportunity.path.clone(),
-            amounts: opportunity.amounts.clone(),
-            routers: opportunity.routers.clone(),
+            expected_profit: opportunity.expected_profit,
+            min_profit,
+            status: status.to_string(),
+            tx_hash: None,
+            opportunity_id: Some(id),
         };
+        match self.store.record_opportunity(&record) {
+            Ok(id) => Some(id),
+            Err(e) => {
+                warn!("Failed to journal opportunity: {:?}", e);
+                None
+            }
+        }
+    }
 
-        let tx = contract.execute_arbitrage_with_fast_lane(
-            arbitrage_opportunity,
-            target_block
-        )
-        .value(opportunity.expected_profit.unwrap_or(U256::zero())) // Add value for FastLane bid
-        .send()
-        .await?
-        .await?
-        .ok_or_else(|| anyhow::anyhow!("No receipt returned"))?;
+    fn update_journal(&self, id: Option<i64>, class: &str, status: &str, tx_hash: Option<ethers::types::H256>) {
+        record_outcome(class, status);
+        if let Some(id) = id {
+            if let Err(e) = self.store.set_opportunity_status(id, status, tx_hash) {
+                warn!("Failed to update journal entry {}: {:?}", id, e);
+            }
+        }
+    }
 
-        Ok(tx)
+    // Matches each new block against first-seen pending txs for latency statistics
+    async fn track_inclusions(&self) -> Result<()> {
+        let mut blocks = self.provider.subscribe_blocks().await?;
+        while let Some(header) = blocks.next().await {
+            let Some(number) = header.number else {
+                continue;
+            };
+            match self.provider.get_block(number).await {
+                Ok(Some(block)) => {
+                    self.propagation.observe_block(&block);
+                    self.dashboard.record_block(number.as_u64());
+                }
+                Ok(None) => {}
+                Err(e) => debug!("Error fetching block {}: {:?}", number, e),
+            }
+            if let Err(e) = self.bids.observe_block(number).await {
+                debug!("Auction winners of block {} not learned: {:?}", number, e);
+            }
+        }
+        Err(anyhow::anyhow!("Block subscription ended"))
     }
 
-    // Mempool monitoring method
-    async fn start_monitoring(&self) -> Result<()> {
-        let mut stream = self.provider.subscribe_pending_txs().await?;
+    // Execution worker: opportunities published by detector instances are checked against this
+    // process's view (age, class and mode, its own threshold at the current gas regime) and
+    // then executed, where the preflight re-simulates the route at the current block
+    async fn consume_queue(&self, mut queue: OpportunityQueue, max_age_blocks: u64) -> Result<()> {
+        info!("Execution worker started. Waiting for queued opportunities...");
 
-        info!("Mempool monitor started. Listening for pending transactions...");
+        loop {
+            let queued = queue.next().await?;
+            let (opportunity, id) = (queued.opportunity, queued.id);
+            let class = opportunity.class;
+            let number = self.provider.get_block_number().await?.as_u64();
+            if self.gas_oracle.is_stale(number) {
+                if let Err(e) = self.gas_oracle.refresh().await {
+                    warn!("Gas oracle refresh failed: {:?}", e);
+                }
+            }
 
-        while let Some(tx_hash) = stream.next().await {
-            debug!("Received new pending tx: {:?}", tx_hash);
+            // The spread was priced at the detector's block; later blocks have likely closed it
+            let age = number.saturating_sub(queued.block_number);
+            if age > max_age_blocks {
+                self.journal(&opportunity, id, number, queued.min_profit, "stale");
+                debug!("Dropping {} opportunity detected {} blocks ago", class.as_str(), age);
+                continue;
+            }
+            let min_profit = match self.screen(&opportunity) {
+                Ok(min_profit) => min_profit,
+                Err((status, min_profit)) => {
+                    self.journal(&opportunity, id, number, min_profit, status);
+                    continue;
+                }
+            };
 
-            // Fetch the full transaction object from the hash
-            let tx_result = self.provider.get_transaction(tx_hash).await;
+            info!(
+                "Executing queued {} opportunity {} from block {}, profit {}",
+                class.as_str(),
+                id.short(),
+                queued.block_number,
+                self.tokens.format_amount(&opportunity.token0, opportunity.expected_profit)
+            );
+            let entry = self.journal(&opportunity, id, number, min_profit, "detected");
+            let pair = self.pair(&opportunity);
+            self.execute_detected(&opportunity, id, None, entry, number, pair).await;
+        }
+    }
 
-            // Check if the transaction was found
-            let tx = match tx_result {
-                Ok(Some(t)) => t,
-                Ok(None) => {
-                    debug!("Transaction with hash {:?} not found in mempool.", tx_hash);
-                    continue;
-                },
+    // The checks every opportunity passes before execution, whether detected here, queued or
+    // loaded from a file: class and mode, the executor's router whitelist, this process's
+    // threshold at the current gas regime, the gas ceiling and operator holds. Returns the
+    // threshold, or the journal status of the check that failed with it
+    fn screen(&self, opportunity: &ArbitrageOpportunity) -> std::result::Result<U256, (&'static str, U256)> {
+        let class = opportunity.class;
+        if !self.classes.is_enabled(class) {
+            return Err(("disabled", U256::zero()));
+        }
+        if !self.modes.profile().allows(class, opportunity.routers.len()) {
+            return Err(("mode_filtered", U256::zero()));
+        }
+        if !self.whitelist.allows_route(&opportunity.routers, &opportunity.path) {
+            return Err(("not_whitelisted", U256::zero()));
+        }
+        let min_profit = self.min_profit(opportunity);
+        if opportunity.expected_profit < min_profit {
+            debug!(
+                "{} opportunity below local threshold: profit {} < {} MATIC",
+                class.as_str(),
+                self.tokens.format_amount(&opportunity.token0, opportunity.expected_profit),
+                token_registry::format_units(min_profit, 18)
+            );
+            return Err(("below_threshold", min_profit));
+        }
+        if !self.gas_ceiling.admit(&self.gas_oracle.snapshot(), opportunity.expected_profit) {
+            return Err(("gas_ceiling", min_profit));
+        }
+        match self.operator_hold(opportunity) {
+            Some(status) => Err((status, min_profit)),
+            None => Ok(min_profit),
+        }
+    }
+
+    // `flashwich execute-file`: an opportunity loaded from disk goes through the same checks as
+    // a queued one, then the normal execution path, whose preflight re-simulates it at the
+    // current block
+    async fn execute_file(&self, opportunity: ArbitrageOpportunity) -> Result<()> {
+        let number = self.provider.get_block_number().await?.as_u64();
+        self.gas_oracle.refresh().await?;
+        let class = opportunity.class.as_str();
+        let id = OpportunityId::of(&opportunity, number + 1);
+        let min_profit = match self.screen(&opportunity) {
+            Ok(min_profit) => min_profit,
+            Err((status, min_profit)) => {
+                self.journal(&opportunity, id, number, min_profit, status);
+                bail!(
+                    "{} opportunity not executed: {} (profit {}, threshold {} MATIC)",
+                    class,
+                    status,
+                    self.tokens.format_amount(&opportunity.token0, opportunity.expected_profit),
+                    token_registry::format_units(min_profit, 18)
+                );
+            }
+        };
+
+        info!(
+            "Executing {} opportunity from file at block {}, profit {}",
+            class,
+            number,
+            self.tokens.format_amount(&opportunity.token0, opportunity.expected_profit)
+        );
+        let entry = self.journal(&opportunity, id, number, min_profit, "detected");
+        match self.execute_multi_leg_arbitrage(&opportunity, None).await {
+            Ok(receipt) => {
+                Self::record_reverted(class, &receipt);
+                self.update_journal(entry, class, "submitted", Some(receipt.transaction_hash));
+                info!("Arbitrage {} executed successfully. Tx Hash: {:?}", id.short(), receipt.transaction_hash);
+                Ok(())
+            }
+            Err(e) => {
+                record_failure(class, FailureReason::from_error(&e));
+                self.update_journal(entry, class, Self::failure_status(&e), None);
+                Err(e.context("Arbitrage execution failed"))
+            }
+        }
+    }
+
+    // Mempool monitoring method: the simulate stage of the pending-tx pipeline
+    async fn start_monitoring(&self, mempool: &MempoolSettings) -> Result<()> {
+        let feed = mempool::spawn(mempool, self.provider.clone());
+        let (propagation, dashboard) = (self.propagation.clone(), self.dashboard.clone());
+        let queue = pipeline::spawn(&mempool.pipeline, feed, move |pending| {
+            debug!("Received new pending tx {:?} from {}", pending.tx.hash, pending.source.as_str());
+            propagation.observe_pending(pending);
+            dashboard.record_pending();
+        });
+
+        info!("Mempool monitor started. Listening for pending transactions...");
+
+        while let Some(decoded) = queue.pop_fresh(mempool.pipeline.simulate_max_age_ms).await {
+            let tx = decoded.pending.tx;
+
+            // Pin the whole evaluation of this tx to one block
+            let block_number = match self.provider.get_block_number().await {
+                Ok(number) => number,
                 Err(e) => {
-                    error!("Error fetching transaction {:?}: {:?}", tx_hash, e);
+                    error!("Error fetching block number: {:?}", e);
                     continue;
                 }
             };
+            let block = BlockId::Number(block_number.into());
+
+            // Gas regime (and with it the profit threshold) moves once per block
+            if self.gas_oracle.is_stale(block_number.as_u64()) {
+                if let Err(e) = self.gas_oracle.refresh().await {
+                    warn!("Gas oracle refresh failed: {:?}", e);
+                }
+            }
 
             // Simulate potential arbitrage
-            match self.engine.simulate_arbitrage_opportunity(&tx).await {
+            match self.engine.simulate_arbitrage_opportunity(&tx, block).await {
                 Ok(Some(opportunity)) => {
-                    info!("Profitable arbitrage found! Profit: {:?}", opportunity.expected_profit);
+                    // Unknown tokens are resolved once so amounts log in human units
+                    let pair = [opportunity.token0, opportunity.token1];
+                    if let Err(e) = self.tokens.resolve(self.provider.clone(), &pair).await {
+                        warn!("Token metadata lookup failed: {:?}", e);
+                    }
 
-                    // Execute multi-leg arbitrage
-                    match self.execute_multi_leg_arbitrage(&opportunity).await {
-                        Ok(receipt) => {
-                            info!("Arbitrage executed successfully. Tx Hash: {:?}", receipt.transaction_hash);
+                    let class = opportunity.class;
+                    let number = block_number.as_u64();
+                    let id = OpportunityId::of(&opportunity, number + 1);
+                    let min_profit = match self.screen(&opportunity) {
+                        Ok(min_profit) => min_profit,
+                        Err((status, min_profit)) => {
+                            self.journal(&opportunity, id, number, min_profit, status);
+                            debug!("Skipping {} opportunity: {}", class.as_str(), status);
+                            continue;
                         }
-                        Err(e) => {
-                            warn!("Arbitrage execution failed: {:?}", e);
+                    };
+
+                    info!(
+                        "Profitable {} arbitrage found! Profit: {}",
+                        class.as_str(),
+                        self.tokens.format_amount(&opportunity.token0, opportunity.expected_profit)
+                    );
+                    let entry = self.journal(&opportunity, id, number, min_profit, "detected");
+                    let pair = self.pair(&opportunity);
+                    self.dashboard.record_opportunity(OpportunityRow {
+                        block_number: number,
+                        class: class.as_str().to_string(),
+                        pair: pair.clone(),
+                        profit: opportunity.expected_profit,
+                        profit_display: self.tokens.format_amount(&opportunity.token0, opportunity.expected_profit),
+                        status: "detected".to_string(),
+                    });
+
+                    // Execution workers in other processes pick it up from the queue
+                    if let Some(queue) = &self.queue {
+                        let queued = QueuedOpportunity {
+                            block_number: number,
+                            detected_at_ms: std::time::SystemTime::now()
+                                .duration_since(std::time::UNIX_EPOCH)
+                                .map(|d| d.as_millis() as u64)
+                                .unwrap_or_default(),
+                            min_profit,
+                            opportunity: opportunity.clone(),
+                            id,
+                        };
+                        if let Err(e) = queue.lock().await.publish(&queued).await {
+                            warn!("Failed to queue {} opportunity: {:?}", class.as_str(), e);
+                        }
+                        if !self.execute_locally {
+                            self.update_journal(entry, class.as_str(), "queued", None);
+                            continue;
                         }
                     }
+
+                    self.execute_detected(&opportunity, id, Some(tx.hash), entry, number, pair).await;
                 }
                 Ok(None) => {
                     debug!("No profitable arbitrage opportunity found.");
@@ -201,96 +943,578 @@ impl FlashLoanArbitrage {
             }
         }
 
-        Ok(())
+        Err(anyhow::anyhow!("All mempool sources stopped"))
     }
 }
 
 
+// Native and stablecoin balances of the searcher and profit wallets for the dashboard
+async fn refresh_balances(
+    provider: Arc<Provider<MeteredWs>>,
+    tokens: Arc<TokenRegistry>,
+    dashboard: Dashboard,
+    wallets: Vec<(&'static str, Address)>,
+) {
+    let erc20s = [USDC, USDT].map(|token| Erc20::new(Address::from_str(token).unwrap(), provider.clone()));
+    let interval = std::time::Duration::from_secs(dashboard.settings().balance_interval_secs.max(1));
+    loop {
+        let mut balances = Vec::new();
+        for &(label, wallet) in &wallets {
+            match provider.get_balance(wallet, None).await {
+                Ok(balance) => {
+                    let matic = format!("{} MATIC", token_registry::format_units(balance, 18));
+                    balances.push((label.to_string(), matic));
+                }
+                Err(e) => debug!("Balance read for {:?} failed: {:?}", wallet, e),
+            }
+            for erc20 in &erc20s {
+                match erc20.balance_of(wallet).await {
+                    Ok(balance) if !balance.is_zero() => {
+                        balances.push((label.to_string(), tokens.format_amount(&erc20.address(), balance)))
+                    }
+                    Ok(_) => {}
+                    Err(e) => debug!("Balance read for {:?} failed: {:?}", wallet, e),
+                }
+            }
+        }
+        dashboard.set_balances(balances);
+        tokio::time::sleep(interval).await;
+    }
+}
+
+// `flashwich setup`: prepares the executor contract and prints a readiness report
+async fn run_setup(settings: &Settings) -> Result<()> {
+    let ws_url = std::env::var("POLYGON_WS_URL")
+        .expect("POLYGON_WS_URL must be set in .env");
+    let provider = rpc_usage::connect_ws(&ws_url, "ws").await?;
+
+    // Setup transactions are sent by the executor owner
+    let owner: LocalWallet = std::env::var("WALLET_PRIVATE_KEY")
+        .expect("WALLET_PRIVATE_KEY must be set in .env for setup")
+        .parse()?;
+    let owner = owner.with_chain_id(137u64); // Polygon Mainnet
+    let operator = owner.address();
+    let client = Arc::new(SignerMiddleware::new(provider, owner));
+
+    let executor = env_address("FLASH_LOAN_CONTRACT")?;
+
+    let setup = ExecutorSetup::new(
+        client,
+        operator,
+        executor,
+        settings.executor.load_abi()?,
+        settings.setup.clone(),
+    );
+    let report = setup.run().await?;
+    println!("{}", report);
+
+    if !report.is_ready() {
+        bail!("Executor setup incomplete");
+    }
+    Ok(())
+}
+
+// `flashwich doctor`: read-only checks of every subsystem, printed as a pass/fail checklist
+async fn run_doctor(settings: &Settings) -> Result<()> {
+    let ws_url = std::env::var("POLYGON_WS_URL")
+        .expect("POLYGON_WS_URL must be set in .env");
+    let mut report = ReadinessReport::default();
+    let provider = match rpc_usage::connect_ws(&ws_url, "ws").await {
+        Ok(provider) => Arc::new(provider),
+        Err(e) => {
+            report.push("ws", CheckStatus::Failed, format!("connect failed: {}", e));
+            println!("{}", report);
+            bail!("Doctor found failing checks");
+        }
+    };
+
+    let doctor = Doctor::new(provider.clone(), settings.doctor.clone());
+    doctor.check_rpc(&mut report).await;
+    doctor.check_subscription(&mut report).await;
+    doctor.check_files(&settings.executor, &mut report);
+
+    let mut contracts = Vec::new();
+    for name in &settings.doctor.contract_envs {
+        match env_address(name) {
+            Ok(address) => contracts.push((name.clone(), address)),
+            Err(_) if std::env::var(name).is_err() => {
+                report.push("contract", CheckStatus::Skipped, format!("{} not set", name))
+            }
+            Err(e) => report.push("contract", CheckStatus::Failed, format!("{:#}", e)),
+        }
+    }
+    let executor = contracts.iter().find(|(name, _)| name == "FLASH_LOAN_CONTRACT").map(|(_, address)| *address);
+    contracts.extend(settings.setup.routers.iter().map(|router| ("router".to_string(), *router)));
+    doctor.check_code(&contracts, &mut report).await;
+
+    // Only the signing key's address is derived; nothing is signed
+    let key = ["SEARCHER_PRIVATE_KEY", "WALLET_PRIVATE_KEY"]
+        .iter()
+        .find_map(|name| std::env::var(name).ok().filter(|key| !key.is_empty()));
+    let wallet = match key.map(|key| key.parse::<LocalWallet>()) {
+        Some(Ok(wallet)) => Some(wallet.address()),
+        Some(Err(e)) => {
+            report.push("balance", CheckStatus::Failed, format!("signing key does not parse: {}", e));
+            None
+        }
+        None => None,
+    };
+    doctor.check_balance(wallet, &mut report).await;
+
+    match executor {
+        Some(executor) => {
+            let (tokens, routers) = (&settings.setup.tokens, &settings.setup.routers);
+            doctor.check_approvals(executor, tokens, routers, &mut report).await;
+            // Ownership, pause and router whitelist, as `flashwich run` checks them at startup
+            if let (Some(operator), Ok(abi)) = (wallet, settings.executor.load_abi()) {
+                let setup = ExecutorSetup::new(provider.clone(), operator, executor, abi, settings.setup.clone());
+                match setup.verify(&settings.startup_checks).await {
+                    Ok(checks) => report.checks.extend(checks.checks),
+                    Err(e) => report.push("executor", CheckStatus::Failed, format!("{:#}", e)),
+                }
+            }
+        }
+        None => report.push("approvals", CheckStatus::Skipped, "FLASH_LOAN_CONTRACT not set"),
+    }
+
+    let private_rpc = std::env::var(&settings.submission.private_rpc_env).ok();
+    doctor.check_endpoint("private_rpc", private_rpc.as_deref(), &mut report).await;
+    doctor.check_endpoint("relay", Some(&settings.doctor.relay_url), &mut report).await;
+    println!("{}", report);
+
+    if !report.is_ready() {
+        bail!("Doctor found failing checks");
+    }
+    Ok(())
+}
+
+// `flashwich deploy`: deploys the executor and writes its address back to the config file
+async fn run_deploy(settings: &Settings, config_path: &str) -> Result<()> {
+    let ws_url = std::env::var("POLYGON_WS_URL")
+        .expect("POLYGON_WS_URL must be set in .env");
+    let provider = rpc_usage::connect_ws(&ws_url, "ws").await?;
+
+    // The deployer becomes the initial owner
+    let deployer: LocalWallet = std::env::var("WALLET_PRIVATE_KEY")
+        .expect("WALLET_PRIVATE_KEY must be set in .env for deploy")
+        .parse()?;
+    let deployer = deployer.with_chain_id(137u64); // Polygon Mainnet
+    let address = deployer.address();
+    let client = Arc::new(SignerMiddleware::new(provider, deployer));
+
+    let (executor, report) = ExecutorDeployer::new(client, address, settings.deploy.clone())
+        .run()
+        .await?;
+    println!("{}", report);
+
+    let executor = ethers::utils::to_checksum(&executor, None);
+    let config = std::fs::read_to_string(config_path)?;
+    std::fs::write(config_path, deploy::set_config_value(&config, &settings.deploy.config_key, &executor)?)?;
+    println!("Wrote {} = {} to {}", settings.deploy.config_key, executor, config_path);
+    println!("Set FLASH_LOAN_CONTRACT={} in .env, then run `flashwich setup`", executor);
+
+    if !report.is_ready() {
+        bail!("Executor deployed but failed verification");
+    }
+    Ok(())
+}
+
+// `flashwich report`: aggregates the journal of the last `hours` into an HTML or CSV report
+fn run_report(settings: &Settings, hours: u64, format: ReportFormat, output: Option<&str>) -> Result<()> {
+    let store = Store::open(&settings.store.path)?;
+    let tokens = TokenRegistry::from_json_file(DEFAULT_TOKENS_PATH)?.with_store(store.clone())?;
+    let now = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+
+    let report = Report::build(&store, now.saturating_sub(hours * 3_600))?;
+    let rendered = match format {
+        ReportFormat::Html => report.to_html(&tokens),
+        ReportFormat::Csv => report.to_csv(&tokens),
+    };
+    match output {
+        Some(path) => {
+            std::fs::write(path, rendered)?;
+            println!("Wrote report of {} opportunities to {}", report.opportunities, path);
+        }
+        None => print!("{}", rendered),
+    }
+    Ok(())
+}
+
+// `flashwich export`: the configured tables, or the ones named, as Parquet files
+fn run_export(settings: &Settings, dir: Option<String>, tables: Vec<String>) -> Result<()> {
+    let dir = dir.unwrap_or_else(|| settings.parquet_export.dir.clone());
+    let tables = if tables.is_empty() { settings.parquet_export.tables.clone() } else { tables };
+    for (path, rows) in parquet_export::export(&settings.store.path, &dir, &tables)? {
+        println!("Wrote {} rows to {}", rows, path.display());
+    }
+    Ok(())
+}
+
+// `flashwich graph`: the factory pairs of the prefetch universe with their current reserves,
+// i.e. the graph the route search starts from
+async fn run_graph(settings: &Settings, format: GraphFormat, output: Option<&str>) -> Result<()> {
+    let ws_url = std::env::var("POLYGON_WS_URL").expect("POLYGON_WS_URL must be set in .env");
+    let provider = Arc::new(rpc_usage::connect_ws(&ws_url, "ws").await?);
+    let store = Store::open(&settings.store.path)?;
+    let tokens = TokenRegistry::from_json_file(DEFAULT_TOKENS_PATH)?.with_store(store)?;
+
+    let block_number = provider.get_block_number().await?.as_u64();
+    let factories: Vec<Address> = settings.fees.factories.keys().copied().collect();
+    let prefetcher = PairPrefetcher::new(provider, settings.prefetch.clone());
+    let cache = ReserveCache::new();
+    let report = prefetcher.run(&factories, &tokens, &cache, block_number).await?;
+    if report.failed > 0 {
+        warn!("{} of {} pair lookups failed, the graph is incomplete", report.failed, report.lookups);
+    }
+
+    let graph = GraphExport::build(
+        &prefetcher.universe(&tokens),
+        &report.pairs,
+        &cache.snapshot(),
+        &settings.fees,
+        &tokens,
+        block_number,
+    );
+    let rendered = match format {
+        GraphFormat::Dot => graph.to_dot(),
+        GraphFormat::Json => graph.to_json()?,
+    };
+    match output {
+        Some(path) => {
+            std::fs::write(path, rendered)?;
+            println!("Wrote graph of {} tokens and {} pools to {}", graph.nodes.len(), graph.edges.len(), path);
+        }
+        None => print!("{}", rendered),
+    }
+    for node in graph.isolated() {
+        warn!("{} ({:?}) has no pool with liquidity on the configured factories", node.symbol, node.address);
+    }
+    Ok(())
+}
+
+// `flashwich ctl`: one request to the running bot's control API, printing its answer
+async fn run_ctl(api: std::net::SocketAddr, action: CtlCommand) -> Result<()> {
+    let (method, path, body) = match action {
+        CtlCommand::Pause => ("POST", "/pause", String::new()),
+        CtlCommand::Resume => ("POST", "/resume", String::new()),
+        CtlCommand::Acknowledge => ("POST", "/acknowledge", String::new()),
+        CtlCommand::SetThreshold { min_profit } => ("PUT", "/threshold", min_profit),
+        CtlCommand::BlacklistAdd { address } => ("POST", "/blacklist", format!("{:?}", address)),
+        CtlCommand::Sweep => ("POST", "/sweep", String::new()),
+        CtlCommand::Status => ("GET", "/controls", String::new()),
+    };
+    println!("{}", operator_controls::call(api, method, path, &body).await?);
+    Ok(())
+}
+
 #[tokio::main]
 async fn main() -> Result<()> {
-    // Initialize logging and environment variables
-    env_logger::init();
+    let cli = Cli::parse();
+
+    // Initialize environment variables, configuration and logging
     dotenv::dotenv().ok();
+    // Bad addresses fail here with their file and line, before anything connects
+    validation::validate_config(&cli.config, DEFAULT_TOKENS_PATH)?;
+    let mut settings = Settings::load(&cli.config)?;
+
+    // The dashboard owns the terminal; logs still reach the file and syslog sinks
+    let tui = matches!(cli.command, Some(Command::Run { tui: true }));
+    if tui {
+        settings.logging.stdout = logging::StdoutFormat::Off;
+    }
+    let log_handle = Arc::new(logging::init(&settings.logging)?);
+
+    match cli.command {
+        Some(Command::Setup) => return run_setup(&settings).await,
+        Some(Command::Doctor) => return run_doctor(&settings).await,
+        Some(Command::Deploy) => return run_deploy(&settings, &cli.config).await,
+        Some(Command::Report { hours, format, output }) => {
+            return run_report(&settings, hours, format, output.as_deref())
+        }
+        Some(Command::Graph { format, output }) => return run_graph(&settings, format, output.as_deref()).await,
+        Some(Command::Export { dir, tables }) => return run_export(&settings, dir, tables),
+        Some(Command::Ctl { api, action }) => {
+            return run_ctl(api.unwrap_or(settings.control_api.listen), action).await
+        }
+        _ => {}
+    }
+
+    let modes = ExecutionModes::new(settings.execution_mode.clone());
+    let controls = OperatorControls::default();
+    if let Some(mode) = cli.mode {
+        modes.set(mode);
+    }
+
+    // Operator control API (runtime log levels, execution mode, ...)
+    if settings.control_api.enabled {
+        let state = ControlState {
+            log_handle: log_handle.clone(),
+            book: None,
+            modes: modes.clone(),
+            controls: controls.clone(),
+        };
+        let listen = settings.control_api.listen;
+        tokio::spawn(async move {
+            if let Err(e) = control_api::serve(listen, state).await {
+                error!("Control API failed: {:?}", e);
+            }
+        });
+    }
+
+    // Projected RPC usage against the providers' plans, on /metrics and in the log
+    if settings.rpc_usage.enabled {
+        tokio::spawn(RpcCostEstimator::new(settings.rpc_usage.clone()).run());
+    }
 
     // WebSocket provider setup
     let ws_url = std::env::var("POLYGON_WS_URL")
         .expect("POLYGON_WS_URL must be set in .env");
-    let provider = Provider::connect(&ws_url).await?;
+    let provider = rpc_usage::connect_ws(&ws_url, "ws").await?;
     let provider = Arc::new(provider);
 
-    // Contract addresses from environment
-    let flash_loan_contract = Address::from_str(
-        &std::env::var("FLASH_LOAN_CONTRACT")
-            .expect("FLASH_LOAN_CONTRACT must be set in .env")
-    )?;
-
-    let fastlane_address = Address::from_str(
-        &std::env::var("FASTLANE_CONTRACT")
-            .expect("FASTLANE_CONTRACT must be set in .env")
-    )?;
-
-    let fastlane_sender_address = Address::from_str(
-        &std::env::var("FASTLANE_SENDER_CONTRACT")
-            .expect("FASTLANE_SENDER_CONTRACT must be set in .env")
-    )?;
-
-    let solver_address = Address::from_str(
-        &std::env::var("ARBITRAGE_EXECUTOR_CONTRACT")
-            .expect("ARBITRAGE_EXECUTOR_CONTRACT must be set in .env")
-    )?;
-
-    let solver_contract = ISolverContract::new(
-        config.solver_contract_address,
-        Arc::new(provider.clone()),
-    );
-    
-    let fastlane_contract = FastLaneContract::new(
-        config.fastlane_contract_address,
-        Arc::new(provider.clone()),
-    );
-    
-    let pfl_dapp_contract = PFLDAppContract::new(
-        config.pfl_dapp_address,
-        Arc::new(provider.clone()),
-    );
-    
-    let dapp_signer_contract = DAppSignerContract::new(
-        config.dapp_signer_address,
-        Arc::new(provider.clone()),
-    );
+    // Persistent store and token metadata (tokens.json, then cached on-chain lookups)
+    let store = Store::open(&settings.store.path)?;
+    let tokens = Arc::new(TokenRegistry::from_json_file(DEFAULT_TOKENS_PATH)?.with_store(store.clone())?);
+
+    // Callback gas calibrated by earlier runs' executions
+    let window = settings.profit_threshold.callback_gas.settings().window;
+    settings.profit_threshold.calibrate(&store.recent_callback_gas(window)?);
+
+    // Event indexer for the allowlisted pools (backtesting, drift, analytics)
+    if settings.indexer.enabled {
+        let indexer = PoolEventIndexer::new(
+            provider.clone(),
+            store.clone(),
+            settings.discovery.allowlist.clone(),
+            settings.indexer.clone(),
+        )
+        .with_shared_reserves(SharedReserves::new(settings.shared_reserves.clone())?.map(Arc::new));
+        tokio::spawn(async move {
+            if let Err(e) = indexer.run().await {
+                error!("Pool event indexer failed: {:?}", e);
+            }
+        });
+    }
 
-    // Wallet setup
-    let private_key = std::env::var("WALLET_PRIVATE_KEY")
-        .expect("WALLET_PRIVATE_KEY must be set in .env");
-    let wallet: LocalWallet = private_key.parse()?;
+    // Research copies of the journal, refreshed while the bot runs
+    if settings.parquet_export.enabled {
+        tokio::spawn(parquet_export::run(settings.store.path.clone(), settings.parquet_export.clone()));
+    }
+
+    // Key setup: SolverOps are signed by a hot searcher key, profits go to WALLET_ADDRESS
+    // whose private key stays in cold storage. Without any key the bot runs read-only:
+    // discovery, simulation, metrics and the APIs run, nothing is signed or sent
+    let searcher_key = std::env::var("SEARCHER_PRIVATE_KEY").ok().filter(|key| !key.is_empty());
+    let wallet_key = std::env::var("WALLET_PRIVATE_KEY").ok().filter(|key| !key.is_empty());
+    let read_only = searcher_key.is_none() && wallet_key.is_none();
+    let searcher: LocalWallet = match (searcher_key, wallet_key) {
+        (Some(key), _) => key.parse()?,
+        (None, Some(key)) => {
+            warn!("SEARCHER_PRIVATE_KEY not set, signing with WALLET_PRIVATE_KEY");
+            key.parse()?
+        }
+        (None, None) => {
+            warn!("No SEARCHER_PRIVATE_KEY or WALLET_PRIVATE_KEY set: running read-only, execution disabled");
+            controls.set_read_only();
+            // Never signs anything; it only fills the sender slots
+            format!("{:x}", H256::random()).parse()?
+        }
+    };
+    let searcher = searcher.with_chain_id(137u64); // Polygon Mainnet
+
+    // Contract and wallet addresses from environment; a read-only run may leave them unset
+    let configured_address = |name: &str| match env_address(name) {
+        Err(_) if read_only && std::env::var(name).is_err() => Ok(Address::zero()),
+        address => address,
+    };
+    let flash_loan_contract = configured_address("FLASH_LOAN_CONTRACT")?;
+
+    let fastlane_address = configured_address("FASTLANE_CONTRACT")?;
+
+    let fastlane_sender_address = configured_address("FASTLANE_SENDER_CONTRACT")?;
+
+    let solver_address = configured_address("ARBITRAGE_EXECUTOR_CONTRACT")?;
 
-    // Configuration parameters
-    let max_delay_blocks = U256::from(3);
-    let min_priority_fee = U256::from(1_000_000_000u64); // 1 gwei
+    let beneficiary = configured_address("WALLET_ADDRESS")?;
+    if beneficiary == searcher.address() {
+        warn!("Searcher key controls the profit wallet; use a separate SEARCHER_PRIVATE_KEY");
+    }
+
+    let dashboard = Dashboard::new(settings.dashboard.clone());
+    let wallets = vec![("searcher", searcher.address()), ("profit", beneficiary)];
+
+    // Fail fast on a misconfigured executor instead of reverting on the first trade
+    if settings.startup_checks.enabled && !read_only {
+        let executor = ExecutorSetup::new(
+            provider.clone(),
+            searcher.address(),
+            flash_loan_contract,
+            settings.executor.load_abi()?,
+            settings.setup.clone(),
+        );
+        let report = executor.verify(&settings.startup_checks).await?;
+        if !report.is_ready() {
+            bail!("Executor {:?} failed startup checks\n{}", flash_loan_contract, report);
+        }
+        info!("{}", report);
+    }
+
+    // Routes the executor would reject on-chain are screened out before execution
+    let whitelist = {
+        let mut routers = [QUICKSWAP_ROUTER, SUSHISWAP_ROUTER, UNISWAP_V3_ROUTER]
+            .iter()
+            .map(|router| router.parse())
+            .collect::<std::result::Result<Vec<Address>, _>>()?;
+        routers.extend(&settings.setup.routers);
+        let mut candidates = tokens.addresses();
+        candidates.extend(&settings.setup.tokens);
+        ExecutorWhitelist::load(
+            provider.clone(),
+            flash_loan_contract,
+            settings.executor.load_abi()?,
+            &settings.executor_whitelist,
+            &routers,
+            &candidates,
+        )
+        .await?
+    };
+
+
+    // Detector instances feed execution workers through the opportunity queue; a worker
+    // consumes it and publishes nothing
+    let (queue, worker_queue) = match &cli.command {
+        Some(Command::Execute { queue }) => {
+            let url = queue
+                .as_ref()
+                .or(settings.queue.url.as_ref())
+                .ok_or_else(|| anyhow::anyhow!("execute needs --queue or queue.url"))?;
+            (None, Some(OpportunityQueue::connect(url).await?))
+        }
+        _ => match &settings.queue.url {
+            Some(url) => (Some(OpportunityQueue::connect(url).await?), None),
+            None => (None, None),
+        },
+    };
 
     // Initialize arbitrage bot
     let arbitrage_bot = FlashLoanArbitrage::new(
         provider.clone(),
-        flash_loan_contract,
-        fastlane_address,
-        fastlane_sender_address,
-        solver_address,
-        wallet.clone(),
-        max_delay_blocks,
-        min_priority_fee,
-    )?;
-
-    // Start monitoring in a separate task
+        &settings,
+        Accounts {
+            flash_loan_contract,
+            fastlane_address,
+            fastlane_sender_address,
+            solver_address,
+            searcher,
+            beneficiary,
+        },
+        SharedState {
+            tokens: tokens.clone(),
+            store: store.clone(),
+            dashboard: dashboard.clone(),
+            modes,
+            controls: controls.clone(),
+            queue,
+            whitelist,
+        },
+    )
+    .await?;
+
+    // A single opportunity from disk, executed in the foreground
+    if let Some(Command::ExecuteFile { file }) = &cli.command {
+        let opportunity = opportunity_codec::load_json(file)?;
+        return arbitrage_bot.execute_file(opportunity).await;
+    }
+
+    // Start monitoring (or consuming the queue, for an execution worker) in a separate task
     let bot_clone = Arc::new(arbitrage_bot);
     let _monitoring_task = {
         let bot = bot_clone.clone();
+        let mempool = settings.mempool.clone();
+        let max_age_blocks = settings.queue.max_age_blocks;
         tokio::spawn(async move {
-            if let Err(e) = bot.start_monitoring().await {
+            let result = match worker_queue {
+                Some(queue) => bot.consume_queue(queue, max_age_blocks).await,
+                None => bot.start_monitoring(&mempool).await,
+            };
+            if let Err(e) = result {
                 error!("Monitoring failed: {:?}", e);
             }
         })
     };
 
+    // The next block's base fee is forecast from every new header
+    let _gas_task = {
+        let bot = bot_clone.clone();
+        tokio::spawn(async move {
+            if let Err(e) = bot.gas_oracle.follow_headers().await {
+                error!("Gas oracle header feed failed: {:?}", e);
+            }
+        })
+    };
+
+    let _inclusion_task = {
+        let bot = bot_clone.clone();
+        tokio::spawn(async move {
+            if let Err(e) = bot.track_inclusions().await {
+                error!("Inclusion tracking failed: {:?}", e);
+            }
+        })
+    };
+
+    // The wallet-side watchers need a wallet; a read-only run has none
+    let _nonce_task = (settings.nonce_watchdog.enabled && !read_only).then(|| {
+        let watchdog = NonceWatchdog::new(
+            bot_clone.submitter.sender().clone(),
+            settings.nonce_watchdog.clone(),
+            Alerts::default(),
+        );
+        tokio::spawn(async move { watchdog.run().await })
+    });
+
+    // Searcher pays the gas, the executor forwards profits to the beneficiary
+    let reconciler = (settings.reconciliation.enabled && !read_only).then(|| {
+        BalanceReconciler::new(
+            provider.clone(),
+            store.clone(),
+            settings.reconciliation.clone(),
+            bot_clone.submitter.sender().address(),
+            beneficiary,
+            flash_loan_contract,
+            Alerts::default(),
+        )
+    });
+    let _wallet_task = (settings.wallet_watcher.enabled && !read_only).then(|| {
+        let watcher = WalletWatcher::new(
+            bot_clone.submitter.sender().clone(),
+            settings.wallet_watcher.clone(),
+            Alerts::default(),
+        )
+        .with_reconciler(reconciler.clone());
+        tokio::spawn(async move { watcher.run().await })
+    });
+    // Trades stop as soon as the contracts they send funds into change owner or code
+    let _admin_task = settings.admin_watch.enabled.then(|| {
+        let watcher = AdminWatcher::new(
+            provider.clone(),
+            vec![("executor", flash_loan_contract), ("fastlane", fastlane_address)],
+            settings.admin_watch.clone(),
+            Alerts::default(),
+            controls.clone(),
+        );
+        tokio::spawn(async move { watcher.run().await })
+    });
+    let _reconciliation_task = reconciler.map(|reconciler| tokio::spawn(async move { reconciler.run().await }));
+
+    if tui {
+        tokio::spawn(refresh_balances(provider.clone(), tokens.clone(), dashboard.clone(), wallets));
+        info!("Polygon Flash Arbitrage Bot initialized with dashboard. Press q to exit.");
+        return tokio::task::spawn_blocking(move || dashboard::run(&dashboard)).await?;
+    }
+
     info!("Polygon Flash Arbitrage Bot initialized. Press CTRL+C to exit.");
 
     // Wait for termination signal
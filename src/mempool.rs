@@ -0,0 +1,435 @@
+// src/mempool.rs
+use anyhow::{anyhow, Result};
+use ethers::{
+    providers::{Middleware, Provider, StreamExt},
+    types::{Transaction, H256},
+};
+use futures::SinkExt;
+use log::{debug, info, warn};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::collections::{HashSet, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc;
+use tokio_tungstenite::tungstenite::{client::IntoClientRequest, http::HeaderValue, Message};
+
+use crate::pipeline::PipelineSettings;
+use crate::prefilter::{PrefilterSettings, TxPrefilter};
+use crate::rpc_batch::{BatchClient, BatchSettings, BatchSizer};
+use crate::rpc_usage::MeteredWs;
+
+// Hashes remembered for cross-source dedup
+const SEEN_CAPACITY: usize = 50_000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MempoolSourceKind {
+    // eth_subscribe("newPendingTransactions") on POLYGON_WS_URL
+    Node,
+    // Dedicated stream provider (bloXroute gateway/cloud API or compatible)
+    Stream,
+}
+
+impl MempoolSourceKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MempoolSourceKind::Node => "node",
+            MempoolSourceKind::Stream => "stream",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct StreamSourceSettings {
+    #[serde(default)]
+    pub url: String,
+    // Env var holding the Authorization header value, kept out of config.yaml
+    #[serde(default = "default_auth_env")]
+    pub auth_env: String,
+    #[serde(default = "default_subscribe_method")]
+    pub subscribe_method: String,
+    #[serde(default = "default_subscribe_params")]
+    pub subscribe_params: Value,
+    #[serde(default = "default_reconnect_secs")]
+    pub reconnect_secs: u64,
+}
+
+fn default_auth_env() -> String {
+    "MEMPOOL_STREAM_AUTH".to_string()
+}
+
+fn default_subscribe_method() -> String {
+    "subscribe".to_string()
+}
+
+fn default_subscribe_params() -> Value {
+    json!(["newTxs", { "include": ["tx_hash", "tx_contents"] }])
+}
+
+fn default_reconnect_secs() -> u64 {
+    5
+}
+
+impl Default for StreamSourceSettings {
+    fn default() -> Self {
+        Self {
+            url: String::new(),
+            auth_env: default_auth_env(),
+            subscribe_method: default_subscribe_method(),
+            subscribe_params: default_subscribe_params(),
+            reconnect_secs: default_reconnect_secs(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MempoolSettings {
+    #[serde(default = "default_sources")]
+    pub sources: Vec<MempoolSourceKind>,
+    #[serde(default)]
+    pub stream: StreamSourceSettings,
+    // Pending transactions buffered between the sources and the simulator
+    #[serde(default = "default_buffer")]
+    pub buffer: usize,
+    // Subscribe the node source to full pending txs, so the prefilter runs without fetching
+    // every body by hash; needs a node supporting newPendingTransactions with `true`
+    #[serde(default)]
+    pub full_bodies: bool,
+    #[serde(default)]
+    pub batch: BatchSettings,
+    #[serde(default)]
+    pub prefilter: PrefilterSettings,
+    #[serde(default)]
+    pub pipeline: PipelineSettings,
+}
+
+fn default_sources() -> Vec<MempoolSourceKind> {
+    vec![MempoolSourceKind::Node]
+}
+
+fn default_buffer() -> usize {
+    4096
+}
+
+impl Default for MempoolSettings {
+    fn default() -> Self {
+        Self {
+            sources: default_sources(),
+            stream: StreamSourceSettings::default(),
+            buffer: default_buffer(),
+            full_bodies: false,
+            batch: BatchSettings::default(),
+            prefilter: PrefilterSettings::default(),
+            pipeline: PipelineSettings::default(),
+        }
+    }
+}
+
+// A pending transaction as delivered to the simulation pipeline
+#[derive(Debug, Clone)]
+pub struct PendingSwap {
+    pub tx: Transaction,
+    pub source: MempoolSourceKind,
+    // Unix ms at which the first source delivered this hash
+    pub first_seen_ms: u64,
+}
+
+pub fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+// Bounded FIFO set; the first source to deliver a hash wins
+#[derive(Debug, Default)]
+struct SeenHashes {
+    set: HashSet<H256>,
+    order: VecDeque<H256>,
+}
+
+impl SeenHashes {
+    fn insert(&mut self, hash: H256) -> bool {
+        if !self.set.insert(hash) {
+            return false;
+        }
+        self.order.push_back(hash);
+        if self.order.len() > SEEN_CAPACITY {
+            if let Some(oldest) = self.order.pop_front() {
+                self.set.remove(&oldest);
+            }
+        }
+        true
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Forwarder {
+    sender: mpsc::Sender<PendingSwap>,
+    seen: Arc<Mutex<SeenHashes>>,
+    prefilter: Arc<TxPrefilter>,
+}
+
+impl Forwarder {
+    // Err once the pipeline has gone away
+    async fn forward(&self, source: MempoolSourceKind, tx: Transaction) -> Result<()> {
+        if !self.seen.lock().unwrap().insert(tx.hash) || !self.prefilter.admit(&tx) {
+            return Ok(());
+        }
+        let pending = PendingSwap {
+            tx,
+            source,
+            first_seen_ms: now_ms(),
+        };
+        self.sender
+            .send(pending)
+            .await
+            .map_err(|_| anyhow!("Mempool pipeline closed"))
+    }
+
+    fn is_closed(&self) -> bool {
+        self.sender.is_closed()
+    }
+}
+
+// Stream notifications carry either full contents or just the hash
+#[derive(Debug, PartialEq)]
+pub enum StreamItem {
+    Contents(Box<Transaction>),
+    Hash(H256),
+}
+
+// bloXroute-style notification: {"params": {"result": {"txHash", "txContents"}}}
+pub fn parse_stream_message(text: &str) -> Option<StreamItem> {
+    let message: Value = serde_json::from_str(text).ok()?;
+    let result = message.get("params")?.get("result")?;
+
+    if let Some(contents) = result.get("txContents") {
+        if let Ok(tx) = serde_json::from_value::<Transaction>(contents.clone()) {
+            return Some(StreamItem::Contents(Box::new(tx)));
+        }
+    }
+    let hash = result.get("txHash").or_else(|| result.get("hash"))?;
+    serde_json::from_value(hash.clone()).ok().map(StreamItem::Hash)
+}
+
+async fn run_node_source(
+    provider: Arc<Provider<MeteredWs>>,
+    out: Forwarder,
+    full_bodies: bool,
+    batch: BatchSettings,
+) -> Result<()> {
+    if full_bodies {
+        let mut stream = provider.subscribe_full_pending_txs().await?;
+        info!("Mempool source 'node' subscribed to full pending txs");
+        while let Some(tx) = stream.next().await {
+            out.forward(MempoolSourceKind::Node, tx).await?;
+        }
+        return Err(anyhow!("Node pending transaction stream ended"));
+    }
+    let mut stream = provider.subscribe_pending_txs().await?;
+    info!("Mempool source 'node' subscribed");
+
+    if batch.enabled {
+        forward_batched(&provider, &mut stream, &out, batch).await?;
+        return Err(anyhow!("Node pending transaction stream ended"));
+    }
+
+    while let Some(tx_hash) = stream.next().await {
+        match provider.get_transaction(tx_hash).await {
+            Ok(Some(tx)) => out.forward(MempoolSourceKind::Node, tx).await?,
+            Ok(None) => debug!("Transaction with hash {:?} not found in mempool.", tx_hash),
+            Err(e) => debug!("Error fetching transaction {:?}: {:?}", tx_hash, e),
+        }
+    }
+    Err(anyhow!("Node pending transaction stream ended"))
+}
+
+// Fetches the bodies of the `pending` hashes in adaptive JSON-RPC batches; Ok once the stream ends
+async fn forward_batched(
+    provider: &Provider<MeteredWs>,
+    pending: &mut (impl futures::Stream<Item = H256> + Unpin),
+    out: &Forwarder,
+    batch: BatchSettings,
+) -> Result<()> {
+    let url = match batch.url.as_str() {
+        "" => std::env::var("POLYGON_WS_URL").map_err(|_| anyhow!("POLYGON_WS_URL is not set"))?,
+        url => url.to_string(),
+    };
+    let client = BatchClient::new(url);
+    let max_wait = Duration::from_millis(batch.max_wait_ms);
+    let mut sizer = BatchSizer::new(batch);
+    while let Some(first) = pending.next().await {
+        // Hashes arriving while the batch fills ride along, up to the current size
+        let mut hashes = vec![first];
+        let deadline = tokio::time::sleep(max_wait);
+        tokio::pin!(deadline);
+        while hashes.len() < sizer.size() {
+            tokio::select! {
+                next = pending.next() => match next {
+                    Some(hash) => hashes.push(hash),
+                    None => break,
+                },
+                _ = &mut deadline => break,
+            }
+        }
+
+        let started = std::time::Instant::now();
+        let bodies = match client.get_transactions(&hashes).await {
+            Ok(bodies) => {
+                sizer.observe(hashes.len(), started.elapsed());
+                bodies
+            }
+            Err(e) => {
+                debug!("Batch of {} pending txs failed ({:?}), fetching one by one", hashes.len(), e);
+                let mut bodies = Vec::with_capacity(hashes.len());
+                for hash in &hashes {
+                    bodies.push(provider.get_transaction(*hash).await.ok().flatten());
+                }
+                bodies
+            }
+        };
+        for tx in bodies.into_iter().flatten() {
+            out.forward(MempoolSourceKind::Node, tx).await?;
+        }
+    }
+    Ok(())
+}
+
+async fn stream_session(
+    settings: &StreamSourceSettings,
+    provider: &Provider<MeteredWs>,
+    out: &Forwarder,
+) -> Result<()> {
+    let mut request = settings.url.as_str().into_client_request()?;
+    if let Ok(auth) = std::env::var(&settings.auth_env) {
+        request.headers_mut().insert("Authorization", HeaderValue::from_str(&auth)?);
+    }
+    let (mut socket, _) = tokio_tungstenite::connect_async(request).await?;
+
+    let subscribe = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": settings.subscribe_method,
+        "params": settings.subscribe_params,
+    });
+    socket.send(Message::Text(subscribe.to_string())).await?;
+    info!("Mempool source 'stream' subscribed at {}", settings.url);
+
+    while let Some(message) = socket.next().await {
+        let text = match message? {
+            Message::Text(text) => text,
+            Message::Ping(payload) => {
+                socket.send(Message::Pong(payload)).await?;
+                continue;
+            }
+            Message::Close(frame) => return Err(anyhow!("Stream closed: {:?}", frame)),
+            _ => continue,
+        };
+        match parse_stream_message(&text) {
+            Some(StreamItem::Contents(tx)) => out.forward(MempoolSourceKind::Stream, *tx).await?,
+            // Hash-only feeds still need the body from our node
+            Some(StreamItem::Hash(hash)) => {
+                if let Ok(Some(tx)) = provider.get_transaction(hash).await {
+                    out.forward(MempoolSourceKind::Stream, tx).await?;
+                }
+            }
+            None => debug!("Ignoring stream message: {}", text),
+        }
+    }
+    Err(anyhow!("Stream ended"))
+}
+
+// Reconnects until the pipeline closes
+async fn run_stream_source(
+    settings: StreamSourceSettings,
+    provider: Arc<Provider<MeteredWs>>,
+    out: Forwarder,
+) -> Result<()> {
+    if settings.url.is_empty() {
+        return Err(anyhow!("mempool.stream.url is not set"));
+    }
+    loop {
+        if let Err(e) = stream_session(&settings, &provider, &out).await {
+            if out.is_closed() {
+                return Ok(());
+            }
+            warn!("Mempool stream dropped: {:?}; reconnecting in {} s", e, settings.reconnect_secs);
+        }
+        tokio::time::sleep(Duration::from_secs(settings.reconnect_secs)).await;
+    }
+}
+
+// Starts every configured source and merges them, deduplicated, into one channel
+pub fn spawn(settings: &MempoolSettings, provider: Arc<Provider<MeteredWs>>) -> mpsc::Receiver<PendingSwap> {
+    let (sender, receiver) = mpsc::channel(settings.buffer.max(1));
+    let out = Forwarder {
+        sender,
+        seen: Arc::new(Mutex::new(SeenHashes::default())),
+        prefilter: Arc::new(TxPrefilter::new(settings.prefilter.clone())),
+    };
+
+    let mut sources = settings.sources.clone();
+    sources.dedup();
+    for source in sources {
+        let out = out.clone();
+        let provider = provider.clone();
+        let stream_settings = settings.stream.clone();
+        let full_bodies = settings.full_bodies;
+        let batch = settings.batch.clone();
+        tokio::spawn(async move {
+            let result = match source {
+                MempoolSourceKind::Node => run_node_source(provider, out, full_bodies, batch).await,
+                MempoolSourceKind::Stream => run_stream_source(stream_settings, provider, out).await,
+            };
+            if let Err(e) = result {
+                warn!("Mempool source '{}' stopped: {:?}", source.as_str(), e);
+            }
+        });
+    }
+    receiver
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_stream_message() {
+        let hash = "0x5e2b6d8b2a9f4b7c1c5c8b7e0c8e0a9b3f1d2c3b4a5968778695a4b3c2d1e0f1";
+        let contents = json!({
+            "params": {
+                "subscription": "b3e5d7e2-5bd8-4f1a-b0f1-1c2d3e4f5a6b",
+                "result": {
+                    "txHash": hash,
+                    "txContents": {
+                        "hash": hash,
+                        "nonce": "0x1",
+                        "from": "0x0000000000000000000000000000000000000001",
+                        "to": "0xa5e0829caced8ffdd4de3c43696c57f7d7a678ff",
+                        "value": "0x0",
+                        "gas": "0x5208",
+                        "gasPrice": "0x3b9aca00",
+                        "input": "0x",
+                        "v": "0x135",
+                        "r": "0x1",
+                        "s": "0x1"
+                    }
+                }
+            }
+        });
+        let Some(StreamItem::Contents(tx)) = parse_stream_message(&contents.to_string()) else {
+            panic!("expected full contents");
+        };
+        assert_eq!(tx.hash, hash.parse().unwrap());
+        assert_eq!(tx.gas, 21_000u64.into());
+
+        let hash_only = json!({ "params": { "result": { "txHash": hash } } });
+        assert_eq!(
+            parse_stream_message(&hash_only.to_string()),
+            Some(StreamItem::Hash(hash.parse().unwrap()))
+        );
+        assert_eq!(parse_stream_message(r#"{"id":1,"result":"sub-id"}"#), None);
+    }
+}
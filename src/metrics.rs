@@ -0,0 +1,344 @@
+// src/metrics.rs
+use lazy_static::lazy_static;
+use prometheus::{
+    register_counter, register_gauge, register_histogram, register_histogram_vec, register_int_counter,
+    register_gauge_vec, register_int_counter_vec, register_int_gauge_vec, Counter, Encoder, Gauge, GaugeVec, Histogram,
+    HistogramVec, IntCounter, IntCounterVec, IntGaugeVec, TextEncoder,
+};
+
+lazy_static! {
+    pub static ref RESERVE_CHECKS_TOTAL: IntCounter = register_int_counter!(
+        "flashwich_reserve_checks_total",
+        "Cached pool reserves compared against a fresh RPC read"
+    )
+    .unwrap();
+    pub static ref RESERVE_DIVERGENCES_TOTAL: IntCounter = register_int_counter!(
+        "flashwich_reserve_divergences_total",
+        "Cached pool reserves that diverged beyond tolerance"
+    )
+    .unwrap();
+    pub static ref RESERVE_RESYNCS_TOTAL: IntCounter = register_int_counter!(
+        "flashwich_reserve_resyncs_total",
+        "Forced full reserve cache resyncs"
+    )
+    .unwrap();
+    pub static ref RESERVE_DIVERGENCE_BPS: Histogram = register_histogram!(
+        "flashwich_reserve_divergence_bps",
+        "Divergence between cached and fresh reserves in basis points",
+        vec![0.0, 1.0, 5.0, 10.0, 50.0, 100.0, 500.0, 1_000.0]
+    )
+    .unwrap();
+    pub static ref PROPAGATION_LATENCY_MS: HistogramVec = register_histogram_vec!(
+        "flashwich_propagation_latency_ms",
+        "Time from first seen in the mempool to inclusion, per source",
+        &["source"],
+        vec![250.0, 500.0, 1_000.0, 2_000.0, 4_000.0, 8_000.0, 16_000.0, 60_000.0]
+    )
+    .unwrap();
+    pub static ref OPPORTUNITIES_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "flashwich_opportunities_total",
+        "Opportunities by class and outcome (detected, below_threshold, ev_negative, gas_ceiling, disabled, mode_filtered, cooldown, toxic_flow, paused, blacklisted, superseded, venue_taken, queued, stale, stale_at_commit, submitted, failed)",
+        &["class", "outcome"]
+    )
+    .unwrap();
+    pub static ref NONCE_GAPS_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "flashwich_nonce_gaps_total",
+        "Wallet nonce gaps by outcome (detected, filled, failed)",
+        &["outcome"]
+    )
+    .unwrap();
+    pub static ref BALANCE_DISCREPANCIES_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "flashwich_balance_discrepancies_total",
+        "Balances that differ from what the journal explains, by holder (wallet, executor) and direction",
+        &["holder", "direction"]
+    )
+    .unwrap();
+    pub static ref SELF_TRADE_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "flashwich_self_trade_total",
+        "Venue reservations across strategies by outcome (reserved, blocked, preempted)",
+        &["outcome"]
+    )
+    .unwrap();
+    pub static ref GAS_CEILING_SKIPPED_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "flashwich_gas_ceiling_skipped_total",
+        "Profitable opportunities not executed because gas was above the ceiling, by fee",
+        &["fee"]
+    )
+    .unwrap();
+    pub static ref GAS_CEILING_SKIPPED_PROFIT: Counter = register_counter!(
+        "flashwich_gas_ceiling_skipped_profit",
+        "Expected profit of the opportunities skipped for gas, in 18-decimal units"
+    )
+    .unwrap();
+    pub static ref DEDUP_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "flashwich_dedup_total",
+        "Detections of an already claimed spread, dropped (duplicate) or taking over (superseded)",
+        &["outcome"]
+    )
+    .unwrap();
+    pub static ref EXECUTOR_EVENTS_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "flashwich_executor_events_total",
+        "Executor contract events booked, by event (executed, failed, bundle_submitted, ownership_transferred)",
+        &["event"]
+    )
+    .unwrap();
+    pub static ref BID_MARKET_WINNERS_TOTAL: IntCounter = register_int_counter!(
+        "flashwich_bid_market_winners_total",
+        "FastLane auction winners touching our pools that the bid estimate learned from"
+    )
+    .unwrap();
+    pub static ref BID_MARKET_PRIORITY_FEE_GWEI: Gauge = register_gauge!(
+        "flashwich_bid_market_priority_fee_gwei",
+        "Moving estimate of the auction winners' priority fee, in gwei"
+    )
+    .unwrap();
+    pub static ref BID_MARKET_BID: Gauge = register_gauge!(
+        "flashwich_bid_market_bid",
+        "Moving estimate of the auction winners' bid, in MATIC"
+    )
+    .unwrap();
+    pub static ref V3_EXHAUSTED_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "flashwich_v3_exhausted_total",
+        "V3 quotes that ran out of liquidity, by reason (depleted, tick_limit)",
+        &["reason"]
+    )
+    .unwrap();
+    pub static ref V3_POOLS_DISCOVERED_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "flashwich_v3_pools_discovered_total",
+        "New V3 pools from factory events by outcome (created, seeded, expired)",
+        &["outcome"]
+    )
+    .unwrap();
+    pub static ref POOL_KINDS_DETECTED_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "flashwich_pool_kinds_detected_total",
+        "Pools classified by interface probing, by detected kind (unknown when nothing matched)",
+        &["kind"]
+    )
+    .unwrap();
+    pub static ref SIM_CACHE_LOOKUPS_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "flashwich_sim_cache_lookups_total",
+        "Route simulations looked up in the per-block cache, by result (hit, miss)",
+        &["result"]
+    )
+    .unwrap();
+    pub static ref FOREIGN_TXS_TOTAL: IntCounter = register_int_counter!(
+        "flashwich_foreign_txs_total",
+        "Mined transactions from the signing wallet that the bot did not sign"
+    )
+    .unwrap();
+    pub static ref EXECUTOR_EVENTS_REORGED_TOTAL: IntCounter = register_int_counter!(
+        "flashwich_executor_events_reorged_total",
+        "Executor events dropped before reaching their confirmation depth because their block was reorged out"
+    )
+    .unwrap();
+    pub static ref BUNDLE_MISSES_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "flashwich_bundle_misses_total",
+        "Bundles not included in their target blocks, by classified reason (victim_dropped, outbid, stale_spread)",
+        &["reason"]
+    )
+    .unwrap();
+    pub static ref PIPELINE_DROPS_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "flashwich_pipeline_drops_total",
+        "Pending txs dropped by the mempool pipeline, by stage (decode, filter, simulate) and reason (overflow, stale, filtered)",
+        &["stage", "reason"]
+    )
+    .unwrap();
+    pub static ref PIPELINE_QUEUE_DEPTH: IntGaugeVec = register_int_gauge_vec!(
+        "flashwich_pipeline_queue_depth",
+        "Pending txs queued in front of each mempool pipeline stage",
+        &["stage"]
+    )
+    .unwrap();
+    pub static ref PREFILTER_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "flashwich_prefilter_total",
+        "Pending txs by prefilter verdict (passed, or the check that rejected them)",
+        &["verdict"]
+    )
+    .unwrap();
+    pub static ref RPC_BATCH_SIZE: Gauge = register_gauge!(
+        "flashwich_rpc_batch_size",
+        "Current size of the adaptive pending-tx body batches"
+    )
+    .unwrap();
+    pub static ref FOCUS_PAIR_CHANGES_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "flashwich_focus_pair_changes_total",
+        "Token pairs promoted into or demoted from the focus list by their spread activity",
+        &["change"]
+    )
+    .unwrap();
+    pub static ref SLIPPAGE_CURVES: IntGaugeVec = register_int_gauge_vec!(
+        "flashwich_slippage_curves",
+        "Pools with a slippage curve sampled for the current block, by pool kind",
+        &["kind"]
+    )
+    .unwrap();
+    pub static ref SIZING_SOURCE_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "flashwich_sizing_source_total",
+        "Trade sizings priced from slippage curves or from reserves read for the route",
+        &["source"]
+    )
+    .unwrap();
+    pub static ref ADMIN_EVENTS_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "flashwich_admin_events_total",
+        "Owner, admin, pause and upgrade events seen on the executor and FastLane contracts",
+        &["contract", "event"]
+    )
+    .unwrap();
+    pub static ref FLASH_PLANS_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "flashwich_flash_plans_total",
+        "Flash borrows by how provider liquidity served them: single, split, downsized or insufficient",
+        &["outcome"]
+    )
+    .unwrap();
+    pub static ref SPREAD_FILTERED_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "flashwich_spread_filtered_total",
+        "Pool price spreads dropped before the route search as implausible or off a correlated pair's peg",
+        &["reason"]
+    )
+    .unwrap();
+    pub static ref SHARED_RESERVES_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "flashwich_shared_reserves_total",
+        "Pool reserves written to or pulled from the shared Redis cache, and failed round trips",
+        &["outcome"]
+    )
+    .unwrap();
+    pub static ref TOXIC_FLOW_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "flashwich_toxic_flow_total",
+        "Pools disturbed by a large reserve move, and routes held back by the discount on them",
+        &["event"]
+    )
+    .unwrap();
+    pub static ref BUNDLE_SIMULATIONS_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "flashwich_bundle_simulations_total",
+        "High-value bundles run through the simulate API, by verdict (confirmed, reverted, short_profit, unavailable)",
+        &["verdict"]
+    )
+    .unwrap();
+    pub static ref FAILURES_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "flashwich_failures_total",
+        "Opportunities not executed (stage skipped) and failed executions (stage failed) by fixed reason; detail is the revert class",
+        &["class", "stage", "reason", "detail"]
+    )
+    .unwrap();
+    pub static ref SPLIT_ROUTES_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "flashwich_split_routes_total",
+        "Two-hop first legs planned over the pair's V2 and V3 pools: split, single or no_pair",
+        &["outcome"]
+    )
+    .unwrap();
+    pub static ref BLOCK_PIPELINE_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "flashwich_block_pipeline_total",
+        "Executions launched off the block loop, awaited inline at the in-flight cap, and pool reads priced as predicted",
+        &["event"]
+    )
+    .unwrap();
+    pub static ref BASE_FEE_FORECAST_GWEI: Gauge = register_gauge!(
+        "flashwich_base_fee_forecast_gwei",
+        "Base fee forecast for the next block from the latest header's gas used against its target"
+    )
+    .unwrap();
+    pub static ref EXECUTOR_WHITELIST_DENIED: IntGaugeVec = register_int_gauge_vec!(
+        "flashwich_executor_whitelist_denied",
+        "Known routers and tokens the executor contract's whitelist refuses, as read at startup",
+        &["kind"]
+    )
+    .unwrap();
+    pub static ref ROUTE_HOP_LIMIT: Gauge = register_gauge!(
+        "flashwich_route_hop_limit",
+        "Longest route the last search allowed, from the expected spread against per-hop gas"
+    )
+    .unwrap();
+    pub static ref IN_FLIGHT_NOTIONAL_USD: Gauge = register_gauge!(
+        "flashwich_in_flight_notional_usd",
+        "Borrowed notional of executions sent and not yet mined, in USD"
+    )
+    .unwrap();
+    pub static ref IN_FLIGHT_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "flashwich_in_flight_total",
+        "Executions opened against the in-flight notional cap, refused at it, or refused unpriced",
+        &["event"]
+    )
+    .unwrap();
+    pub static ref PARQUET_EXPORT_ROWS_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "flashwich_parquet_export_rows_total",
+        "Rows written to Parquet exports per table",
+        &["table"]
+    )
+    .unwrap();
+    pub static ref SPREAD_PERSISTENCE_BLOCKS: Histogram = register_histogram!(
+        "flashwich_spread_persistence_blocks",
+        "Blocks detected spreads were predicted to stay open",
+        vec![1.0, 2.0, 3.0, 4.0, 6.0, 8.0, 12.0]
+    )
+    .unwrap();
+    pub static ref SHADOW_COMPARISONS_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "flashwich_shadow_comparisons_total",
+        "Shadow engine picks against the live ones (agree, live_only, shadow_only, route, profit)",
+        &["outcome"]
+    )
+    .unwrap();
+    pub static ref POOL_FEE_CHANGES_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "flashwich_pool_fee_changes_total",
+        "Pool fee changes picked up by the fee watch, per pool kind (v2, algebra)",
+        &["kind"]
+    )
+    .unwrap();
+    pub static ref EXECUTION_MODE: IntGaugeVec = register_int_gauge_vec!(
+        "flashwich_execution_mode",
+        "1 for the active execution mode (aggressive, balanced, conservative), 0 for the others",
+        &["mode"]
+    )
+    .unwrap();
+    pub static ref TOKEN_TAX_PROBES_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "flashwich_token_tax_probes_total",
+        "Transfer tax measurements by outcome (taxed, untaxed, failed)",
+        &["outcome"]
+    )
+    .unwrap();
+    pub static ref FANOUT_BUNDLES_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "flashwich_fanout_bundles_total",
+        "Multi-block bundle variants by outcome (sent, rejected) and trades by outcome (landed, missed)",
+        &["outcome"]
+    )
+    .unwrap();
+    pub static ref QUEUE_MESSAGES_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "flashwich_queue_messages_total",
+        "Opportunity queue traffic by outcome (published, publish_failed, consumed, decode_failed)",
+        &["outcome"]
+    )
+    .unwrap();
+    pub static ref OPTIMIZER_RUNS_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "flashwich_optimizer_runs_total",
+        "Trade sizings by the shape of the sampled profit curve (concave, non_concave)",
+        &["shape"]
+    )
+    .unwrap();
+    pub static ref RPC_CALLS_TOTAL: IntCounterVec = register_int_counter_vec!(
+        "flashwich_rpc_calls_total",
+        "JSON-RPC requests by provider (ws, http, private) and method",
+        &["provider", "method"]
+    )
+    .unwrap();
+    pub static ref RPC_PROJECTED_MONTHLY_UNITS: GaugeVec = register_gauge_vec!(
+        "flashwich_rpc_projected_monthly_units",
+        "RPC usage extrapolated to a 30-day month, in the units of the provider's plan",
+        &["provider"]
+    )
+    .unwrap();
+    pub static ref RPC_PROJECTED_MONTHLY_COST: GaugeVec = register_gauge_vec!(
+        "flashwich_rpc_projected_monthly_cost",
+        "Projected monthly RPC cost per provider: plan price plus overage",
+        &["provider"]
+    )
+    .unwrap();
+}
+
+// Prometheus text exposition of every registered metric
+pub fn render() -> String {
+    let mut buffer = Vec::new();
+    let encoder = TextEncoder::new();
+    if encoder.encode(&prometheus::gather(), &mut buffer).is_err() {
+        return String::new();
+    }
+    String::from_utf8(buffer).unwrap_or_default()
+}
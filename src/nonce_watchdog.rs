@@ -0,0 +1,265 @@
+// src/nonce_watchdog.rs
+use anyhow::{anyhow, Result};
+use ethers::{
+    providers::Middleware,
+    types::{transaction::eip2718::TypedTransaction, BlockNumber, Eip1559TransactionRequest, H256, U256},
+};
+use log::{debug, warn};
+use serde::Deserialize;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+use crate::alerts::{AlertLevel, Alerts};
+use crate::metrics::NONCE_GAPS_TOTAL;
+use crate::tx_sender::TxSender;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NonceWatchdogSettings {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+    // How long a gap must persist before it is filled; private submissions are not in
+    // our node's mempool, so short-lived gaps are expected
+    #[serde(default = "default_stuck_secs")]
+    pub stuck_secs: u64,
+    // Fee bump over the current estimate, enough to replace a stuck tx at the same nonce
+    #[serde(default = "default_bump_percent")]
+    pub bump_percent: u64,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_interval_secs() -> u64 {
+    15
+}
+
+fn default_stuck_secs() -> u64 {
+    60
+}
+
+fn default_bump_percent() -> u64 {
+    20
+}
+
+impl Default for NonceWatchdogSettings {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            interval_secs: default_interval_secs(),
+            stuck_secs: default_stuck_secs(),
+            bump_percent: default_bump_percent(),
+        }
+    }
+}
+
+// The nonce blocking the wallet, if any. `latest` and `pending` are the mined and
+// mempool transaction counts; `highest_signed` is the highest nonce we signed.
+// A signed nonce the node has never seen means a dropped tx left a hole; pending txs
+// above the mined count mean the next one is not getting included.
+pub fn find_gap(latest: U256, pending: U256, highest_signed: Option<U256>) -> Option<U256> {
+    match highest_signed {
+        Some(highest) if highest >= pending => Some(pending),
+        _ if pending > latest => Some(latest),
+        _ => None,
+    }
+}
+
+pub fn bump(value: U256, percent: u64) -> U256 {
+    value * U256::from(100 + percent) / U256::from(100u64)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct OpenGap {
+    nonce: U256,
+    since: Instant,
+}
+
+// Watches the submission wallet for nonce gaps and fills them with a zero-value
+// self-transfer once they have been stuck for `stuck_secs`
+pub struct NonceWatchdog<M> {
+    sender: TxSender<M>,
+    settings: NonceWatchdogSettings,
+    alerts: Alerts,
+    open: Mutex<Option<OpenGap>>,
+}
+
+impl<M: Middleware + 'static> NonceWatchdog<M> {
+    pub fn new(sender: TxSender<M>, settings: NonceWatchdogSettings, alerts: Alerts) -> Self {
+        Self {
+            sender,
+            settings,
+            alerts,
+            open: Mutex::new(None),
+        }
+    }
+
+    pub async fn run(&self) {
+        let mut interval = tokio::time::interval(Duration::from_secs(self.settings.interval_secs.max(1)));
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.check_once().await {
+                warn!("Nonce watchdog check failed: {:?}", e);
+            }
+        }
+    }
+
+    async fn transaction_count(&self, block: BlockNumber) -> Result<U256> {
+        self.sender
+            .provider()
+            .get_transaction_count(self.sender.address(), Some(block.into()))
+            .await
+            .map_err(|e| anyhow!("Failed to fetch {block} nonce: {e}"))
+    }
+
+    // Returns the hash of the filler tx when one was sent
+    pub async fn check_once(&self) -> Result<Option<H256>> {
+        let latest = self.transaction_count(BlockNumber::Latest).await?;
+        let pending = self.transaction_count(BlockNumber::Pending).await?;
+        let gap = find_gap(latest, pending, self.sender.highest_nonce());
+        debug!("Nonces: latest {}, pending {}, gap {:?}", latest, pending, gap);
+
+        let mut open = self.open.lock().await;
+        let Some(nonce) = gap else {
+            if let Some(resolved) = open.take() {
+                self.alerts.emit(
+                    AlertLevel::Info,
+                    "nonce_watchdog",
+                    format!("Nonce gap at {} resolved", resolved.nonce),
+                );
+            }
+            return Ok(None);
+        };
+
+        match *open {
+            Some(current) if current.nonce == nonce => {
+                if current.since.elapsed() < Duration::from_secs(self.settings.stuck_secs) {
+                    return Ok(None);
+                }
+            }
+            _ => {
+                NONCE_GAPS_TOTAL.with_label_values(&["detected"]).inc();
+                self.alerts.emit(
+                    AlertLevel::Warning,
+                    "nonce_watchdog",
+                    format!(
+                        "Nonce gap at {} (latest {}, pending {}, highest signed {:?})",
+                        nonce, latest, pending, self.sender.highest_nonce()
+                    ),
+                );
+                *open = Some(OpenGap { nonce, since: Instant::now() });
+                return Ok(None);
+            }
+        }
+
+        // Restart the clock so a filler that also gets stuck is retried later
+        *open = Some(OpenGap { nonce, since: Instant::now() });
+        match self.fill(nonce).await {
+            Ok(hash) => {
+                NONCE_GAPS_TOTAL.with_label_values(&["filled"]).inc();
+                self.alerts.emit(
+                    AlertLevel::Info,
+                    "nonce_watchdog",
+                    format!("Sent cancel tx {:?} to fill nonce {}", hash, nonce),
+                );
+                Ok(Some(hash))
+            }
+            Err(e) => {
+                NONCE_GAPS_TOTAL.with_label_values(&["failed"]).inc();
+                self.alerts.emit(
+                    AlertLevel::Critical,
+                    "nonce_watchdog",
+                    format!("Failed to fill nonce gap at {}: {}", nonce, e),
+                );
+                Err(e)
+            }
+        }
+    }
+
+    async fn fill(&self, nonce: U256) -> Result<H256> {
+        let (max_fee, priority_fee) = self
+            .sender
+            .provider()
+            .estimate_eip1559_fees(None)
+            .await
+            .map_err(|e| anyhow!("Fee estimation failed: {e}"))?;
+        let address = self.sender.address();
+        let cancel: TypedTransaction = Eip1559TransactionRequest::new()
+            .from(address)
+            .to(address)
+            .value(U256::zero())
+            .nonce(nonce)
+            .gas(21_000u64)
+            .max_priority_fee_per_gas(bump(priority_fee, self.settings.bump_percent))
+            .max_fee_per_gas(bump(max_fee, self.settings.bump_percent))
+            .into();
+
+        // Nothing to extract from a self-transfer, so the public mempool is fine
+        let raw = self.sender.sign(cancel).await?;
+        let pending = self.sender.broadcast(raw).await?;
+        Ok(pending.tx_hash())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{quantity, MockChain};
+    use ethers::signers::{LocalWallet, Signer};
+    use ethers::types::{Block, FeeHistory};
+    use serde_json::json;
+
+    #[test]
+    fn test_find_gap() {
+        let n = |value: u64| U256::from(value);
+        // In sync, nothing pending
+        assert_eq!(find_gap(n(5), n(5), Some(n(4))), None);
+        assert_eq!(find_gap(n(5), n(5), None), None);
+        // Nonce 5 was dropped while 6 and 7 were signed
+        assert_eq!(find_gap(n(5), n(5), Some(n(7))), Some(n(5)));
+        // Nonce 5 is sitting in the mempool without being mined
+        assert_eq!(find_gap(n(5), n(7), Some(n(6))), Some(n(5)));
+        assert_eq!(bump(n(100), 20), n(120));
+    }
+
+    #[tokio::test]
+    async fn test_stuck_gap_is_filled_with_cancel_tx() {
+        let chain = MockChain::new();
+        let wallet: LocalWallet = "0000000000000000000000000000000000000000000000000000000000000001"
+            .parse::<LocalWallet>()
+            .unwrap()
+            .with_chain_id(137u64);
+        let settings = NonceWatchdogSettings {
+            stuck_secs: 0,
+            ..Default::default()
+        };
+        let watchdog = NonceWatchdog::new(TxSender::new(chain.provider(), wallet), settings, Alerts::default());
+
+        // Nonce 5 sits in the mempool: first check only opens the gap
+        chain.script(vec![quantity(5u64), quantity(7u64)]);
+        assert_eq!(watchdog.check_once().await.unwrap(), None);
+
+        let block = Block::<H256> {
+            base_fee_per_gas: Some(U256::from(30_000_000_000u64)),
+            ..Default::default()
+        };
+        let fee_history = FeeHistory {
+            base_fee_per_gas: vec![U256::from(30_000_000_000u64)],
+            gas_used_ratio: vec![0.5],
+            oldest_block: U256::from(1u64),
+            reward: vec![vec![U256::from(30_000_000_000u64)]],
+        };
+        let hash = H256::repeat_byte(0xcc);
+        chain.script(vec![
+            quantity(5u64),
+            quantity(7u64),
+            serde_json::to_value(block).unwrap(),
+            serde_json::to_value(fee_history).unwrap(),
+            json!(hash),
+        ]);
+        assert_eq!(watchdog.check_once().await.unwrap(), Some(hash));
+        assert_eq!(watchdog.sender.highest_nonce(), Some(U256::from(5u64)));
+    }
+}
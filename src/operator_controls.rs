@@ -0,0 +1,192 @@
+// src/operator_controls.rs
+use anyhow::{bail, Context, Result};
+use ethers::types::{Address, U256};
+use log::{info, warn};
+use serde::Serialize;
+use std::collections::BTreeSet;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+use crate::token_registry::format_units;
+
+// Runtime interventions set through the control API (`flashwich ctl`) and read by the
+// detection loops. They live in memory only: a restart goes back to the config
+#[derive(Debug, Clone, Default)]
+pub struct OperatorControls {
+    state: Arc<RwLock<ControlsState>>,
+    sweep: Arc<AtomicBool>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ControlsState {
+    pub paused: bool,
+    // Floor under the dynamic profit threshold, in wei of the native token
+    pub min_profit: Option<U256>,
+    // Tokens, pools or routers never traded
+    pub blacklist: BTreeSet<Address>,
+    // Unacknowledged admin events on the executor or FastLane; any of them holds execution
+    pub admin_holds: Vec<String>,
+    // Started without a signing key: everything but execution runs, and resuming cannot change that
+    pub read_only: bool,
+}
+
+impl OperatorControls {
+    pub fn status(&self) -> ControlsState {
+        self.state.read().unwrap().clone()
+    }
+
+    pub fn set_paused(&self, paused: bool) {
+        let previous = std::mem::replace(&mut self.state.write().unwrap().paused, paused);
+        if previous != paused {
+            info!("Execution {} by the operator", if paused { "paused" } else { "resumed" });
+        }
+    }
+
+    pub fn is_paused(&self) -> bool {
+        let state = self.state.read().unwrap();
+        state.paused || !state.admin_holds.is_empty()
+    }
+
+    pub fn set_read_only(&self) {
+        self.state.write().unwrap().read_only = true;
+    }
+
+    pub fn is_read_only(&self) -> bool {
+        self.state.read().unwrap().read_only
+    }
+
+    // Holds execution until the operator acknowledges `reason`
+    pub fn hold(&self, reason: String) {
+        warn!("Execution held: {}", reason);
+        self.state.write().unwrap().admin_holds.push(reason);
+    }
+
+    // Clears every admin hold; returns how many there were
+    pub fn acknowledge(&self) -> usize {
+        let holds = std::mem::take(&mut self.state.write().unwrap().admin_holds);
+        if !holds.is_empty() {
+            info!("{} admin event hold(s) acknowledged by the operator", holds.len());
+        }
+        holds.len()
+    }
+
+    // None hands the threshold back to the dynamic calculation alone
+    pub fn set_min_profit(&self, min_profit: Option<U256>) {
+        self.state.write().unwrap().min_profit = min_profit;
+        match min_profit {
+            Some(floor) => info!("Minimum profit floor set to {} MATIC", format_units(floor, 18)),
+            None => info!("Minimum profit floor cleared"),
+        }
+    }
+
+    pub fn apply_min_profit(&self, min_profit: U256) -> U256 {
+        self.state.read().unwrap().min_profit.map_or(min_profit, |floor| floor.max(min_profit))
+    }
+
+    // Returns false when the address was already blacklisted
+    pub fn blacklist(&self, address: Address) -> bool {
+        let added = self.state.write().unwrap().blacklist.insert(address);
+        if added {
+            info!("{:?} blacklisted by the operator", address);
+        }
+        added
+    }
+
+    pub fn touches_blacklist(&self, addresses: &[Address]) -> bool {
+        let state = self.state.read().unwrap();
+        addresses.iter().any(|address| state.blacklist.contains(address))
+    }
+
+    // Asks the bot to pay out accrued treasury balances at the next block
+    pub fn request_sweep(&self) {
+        self.sweep.store(true, Ordering::SeqCst);
+    }
+
+    pub fn take_sweep(&self) -> bool {
+        self.sweep.swap(false, Ordering::SeqCst)
+    }
+}
+
+// Client side of `flashwich ctl`: one plain HTTP/1.1 request to the control API, returning
+// the response body; non-2xx answers become errors carrying the body
+pub async fn call(api: SocketAddr, method: &str, path: &str, body: &str) -> Result<String> {
+    let mut stream = TcpStream::connect(api)
+        .await
+        .with_context(|| format!("control API at {api} unreachable, is control_api.enabled set?"))?;
+    let request = format!(
+        "{method} {path} HTTP/1.1\r\nHost: {api}\r\nContent-Type: text/plain\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+        body.len()
+    );
+    stream.write_all(request.as_bytes()).await?;
+    let mut response = Vec::new();
+    stream.read_to_end(&mut response).await?;
+
+    let response = String::from_utf8_lossy(&response);
+    let (head, body) = response.split_once("\r\n\r\n").unwrap_or((&response, ""));
+    let status: u16 = head
+        .split_whitespace()
+        .nth(1)
+        .and_then(|code| code.parse().ok())
+        .with_context(|| format!("malformed response from the control API: {head:?}"))?;
+    if !(200..300).contains(&status) {
+        bail!("{method} {path} failed with {status}: {}", body.trim());
+    }
+    Ok(body.trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::address;
+    use tokio::io::{AsyncBufReadExt, BufReader};
+    use tokio::net::TcpListener;
+
+    #[tokio::test]
+    async fn test_controls_apply_and_ctl_requests_reach_the_api() {
+        let controls = OperatorControls::default();
+        let shared = controls.clone();
+        shared.set_paused(true);
+        assert!(controls.is_paused());
+
+        shared.set_min_profit(Some(U256::from(500u64)));
+        assert_eq!(controls.apply_min_profit(U256::from(200u64)), U256::from(500u64));
+        assert_eq!(controls.apply_min_profit(U256::from(900u64)), U256::from(900u64));
+        shared.set_min_profit(None);
+        assert_eq!(controls.apply_min_profit(U256::from(200u64)), U256::from(200u64));
+
+        assert!(shared.blacklist(address(7)));
+        assert!(!shared.blacklist(address(7)));
+        assert!(controls.touches_blacklist(&[address(1), address(7)]));
+        assert!(!controls.touches_blacklist(&[address(1), address(2)]));
+
+        // Resuming does not lift read-only
+        shared.set_read_only();
+        shared.set_paused(false);
+        assert!(controls.is_read_only() && controls.status().read_only);
+
+        shared.request_sweep();
+        assert!(controls.take_sweep());
+        assert!(!controls.take_sweep());
+
+        // A one-shot API refusing the request
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let api = listener.local_addr().unwrap();
+        let server = tokio::spawn(async move {
+            let (socket, _) = listener.accept().await.unwrap();
+            let mut stream = BufReader::new(socket);
+            let mut request_line = String::new();
+            stream.read_line(&mut request_line).await.unwrap();
+            stream
+                .write_all(b"HTTP/1.1 400 Bad Request\r\ncontent-length: 13\r\n\r\nbad threshold")
+                .await
+                .unwrap();
+            request_line
+        });
+        let error = call(api, "PUT", "/threshold", "lots").await.unwrap_err();
+        assert!(error.to_string().contains("400: bad threshold"), "{error}");
+        assert_eq!(server.await.unwrap(), "PUT /threshold HTTP/1.1\r\n");
+    }
+}
@@ -0,0 +1,164 @@
+// src/opportunity_class.rs
+use ethers::{
+    abi::{AbiType, InvalidOutputType, ParamType, Token, Tokenizable},
+    types::{Address, U256},
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OpportunityClass {
+    // Same pair priced differently on two pools
+    #[default]
+    CrossDex,
+    // Cycle through three or more tokens
+    Triangular,
+    // Every leg is a stablecoin, i.e. a peg deviation
+    StableDepeg,
+    // Triggered by a pending transaction and landing right after it
+    Backrun,
+    // Wraps a pending transaction; no shipped strategy produces these yet
+    Sandwich,
+}
+
+impl OpportunityClass {
+    pub const ALL: [OpportunityClass; 5] = [
+        OpportunityClass::CrossDex,
+        OpportunityClass::Triangular,
+        OpportunityClass::StableDepeg,
+        OpportunityClass::Backrun,
+        OpportunityClass::Sandwich,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            OpportunityClass::CrossDex => "cross_dex",
+            OpportunityClass::Triangular => "triangular",
+            OpportunityClass::StableDepeg => "stable_depeg",
+            OpportunityClass::Backrun => "backrun",
+            OpportunityClass::Sandwich => "sandwich",
+        }
+    }
+}
+
+// Lets ArbitrageOpportunity keep deriving EthAbiType; encoded as its index
+impl AbiType for OpportunityClass {
+    fn param_type() -> ParamType {
+        ParamType::Uint(8)
+    }
+}
+
+impl Tokenizable for OpportunityClass {
+    fn from_token(token: Token) -> Result<Self, InvalidOutputType> {
+        let index = u8::from_token(token)?;
+        Self::ALL
+            .get(index as usize)
+            .copied()
+            .ok_or_else(|| InvalidOutputType(format!("Unknown opportunity class {index}")))
+    }
+
+    fn into_token(self) -> Token {
+        let index = Self::ALL.iter().position(|class| *class == self).unwrap_or_default();
+        Token::Uint(U256::from(index))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ClassSettings {
+    #[serde(default = "default_enabled")]
+    pub enabled: Vec<OpportunityClass>,
+    // Multiplier on the dynamic minimum profit; classes not listed use 1.0
+    #[serde(default)]
+    pub margin: HashMap<OpportunityClass, f64>,
+    // Tokens treated as stablecoins for `stable_depeg`
+    #[serde(default = "default_stables")]
+    pub stables: Vec<Address>,
+}
+
+fn default_enabled() -> Vec<OpportunityClass> {
+    vec![
+        OpportunityClass::CrossDex,
+        OpportunityClass::Triangular,
+        OpportunityClass::StableDepeg,
+        OpportunityClass::Backrun,
+    ]
+}
+
+fn default_stables() -> Vec<Address> {
+    [
+        "0x2791Bca1f2de4661ED88A30C99A7a9449Aa84174", // USDC.e
+        "0x3c499c542cEF5E3811e1192ce70d8cC03d5c3359", // USDC
+        "0xc2132D05D31c914a87C6611C10748AEb04B58e8F", // USDT
+        "0x8f3Cf7ad23Cd3CaDbD9735AFf958023239c6A063", // DAI
+    ]
+    .iter()
+    .map(|address| address.parse().unwrap())
+    .collect()
+}
+
+impl Default for ClassSettings {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            margin: HashMap::new(),
+            stables: default_stables(),
+        }
+    }
+}
+
+impl ClassSettings {
+    pub fn is_enabled(&self, class: OpportunityClass) -> bool {
+        self.enabled.contains(&class)
+    }
+
+    pub fn scale_min_profit(&self, class: OpportunityClass, min_profit: U256) -> U256 {
+        let factor = self.margin.get(&class).copied().unwrap_or(1.0);
+        // Parts-per-million keeps the arithmetic in U256
+        let ppm = (factor.max(0.0) * 1_000_000.0).round() as u64;
+        min_profit * U256::from(ppm) / U256::from(1_000_000u64)
+    }
+
+    // `tokens` is the token path of the route; mempool-triggered routes are backruns
+    pub fn classify(&self, tokens: &[Address], backrun: bool) -> OpportunityClass {
+        if backrun {
+            return OpportunityClass::Backrun;
+        }
+        let distinct: HashSet<&Address> = tokens.iter().collect();
+        let stables: HashSet<&Address> = self.stables.iter().collect();
+        if !distinct.is_empty() && distinct.is_subset(&stables) {
+            OpportunityClass::StableDepeg
+        } else if distinct.len() <= 2 {
+            OpportunityClass::CrossDex
+        } else {
+            OpportunityClass::Triangular
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_classify_and_per_class_margin() {
+        let mut settings = ClassSettings::default();
+        let wmatic: Address = "0x0d500B1d8E8eF31E21C99d1Db9A6444d3ADf1270".parse().unwrap();
+        let weth: Address = "0x7ceB23fD6bC0adD59E62ac25578270cFf1b9f619".parse().unwrap();
+        let (usdc, usdt) = (settings.stables[0], settings.stables[2]);
+
+        assert_eq!(settings.classify(&[wmatic, usdc, wmatic], false), OpportunityClass::CrossDex);
+        assert_eq!(settings.classify(&[wmatic, usdc, weth, wmatic], false), OpportunityClass::Triangular);
+        assert_eq!(settings.classify(&[usdc, usdt, usdc], false), OpportunityClass::StableDepeg);
+        assert_eq!(settings.classify(&[usdc, usdt], true), OpportunityClass::Backrun);
+        assert!(!settings.is_enabled(OpportunityClass::Sandwich));
+
+        settings.margin.insert(OpportunityClass::Triangular, 2.0);
+        let min_profit = U256::from(1_000u64);
+        assert_eq!(settings.scale_min_profit(OpportunityClass::Triangular, min_profit), U256::from(2_000u64));
+        assert_eq!(settings.scale_min_profit(OpportunityClass::CrossDex, min_profit), min_profit);
+
+        let token = OpportunityClass::StableDepeg.into_token();
+        assert_eq!(OpportunityClass::from_token(token).unwrap(), OpportunityClass::StableDepeg);
+    }
+}
@@ -0,0 +1,169 @@
+// src/opportunity_codec.rs
+use anyhow::{bail, Context, Result};
+use ethers::types::U256;
+use serde::{Deserialize, Serialize};
+
+use crate::opportunity_id::OpportunityId;
+use crate::simulation_engine::ArbitrageOpportunity;
+
+// Bumped whenever the layout changes. Bincode writes fields in declaration order, so one
+// appended to QueuedOpportunity keeps the version: older readers ignore the trailing bytes
+pub const SCHEMA_VERSION: u8 = 2;
+const MAGIC: &[u8; 2] = b"FW";
+
+// An opportunity as handed from a detector to an execution worker
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct QueuedOpportunity {
+    // Block the detector priced the opportunity against
+    pub block_number: u64,
+    pub detected_at_ms: u64,
+    // The detector's profit threshold at that block
+    pub min_profit: U256,
+    pub opportunity: ArbitrageOpportunity,
+    // The detector's id, so its journal entry and the worker's share it
+    pub id: OpportunityId,
+}
+
+// [magic][version][bincode of the QueuedOpportunity]
+pub fn encode(queued: &QueuedOpportunity) -> Result<Vec<u8>> {
+    let mut out = MAGIC.to_vec();
+    out.push(SCHEMA_VERSION);
+    bincode::serialize_into(&mut out, queued)?;
+    Ok(out)
+}
+
+pub fn decode(bytes: &[u8]) -> Result<QueuedOpportunity> {
+    if !bytes.starts_with(MAGIC) {
+        bail!("not an opportunity payload");
+    }
+    match bytes.get(MAGIC.len()) {
+        Some(&SCHEMA_VERSION) => Ok(bincode::deserialize(&bytes[MAGIC.len() + 1..])?),
+        Some(version) => {
+            bail!("opportunity schema version {version} is not supported (this build reads {SCHEMA_VERSION})")
+        }
+        None => bail!("opportunity payload has no schema version"),
+    }
+}
+
+// An opportunity written by hand or exported from a log, for `flashwich execute-file`
+pub fn load_json(path: &str) -> Result<ArbitrageOpportunity> {
+    let json = std::fs::read_to_string(path).with_context(|| format!("Failed to read {path}"))?;
+    let opportunity: ArbitrageOpportunity =
+        serde_json::from_str(&json).with_context(|| format!("{path} is not an ArbitrageOpportunity"))?;
+    validate(&opportunity).with_context(|| format!("{path} holds an unusable opportunity"))?;
+    Ok(opportunity)
+}
+
+// Shape checks only; profitability is for the threshold and the preflight simulation
+pub fn validate(opportunity: &ArbitrageOpportunity) -> Result<()> {
+    if opportunity.path.len() < 2 {
+        bail!("path needs at least two tokens, has {}", opportunity.path.len());
+    }
+    if opportunity.path[0] != opportunity.token0 {
+        bail!("path starts at {:?}, not at token0 {:?}", opportunity.path[0], opportunity.token0);
+    }
+    if opportunity.routers.is_empty() {
+        bail!("no routers");
+    }
+    if let Some(zero) = opportunity
+        .path
+        .iter()
+        .chain(&opportunity.routers)
+        .chain([&opportunity.token1])
+        .find(|address| address.is_zero())
+    {
+        bail!("zero address {:?} in the route", zero);
+    }
+    if opportunity.amount0.is_zero() && opportunity.amount1.is_zero() {
+        bail!("nothing borrowed: amount0 and amount1 are both zero");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::address;
+    use crate::opportunity_class::OpportunityClass;
+    use ethers::types::{Address, H256};
+
+    #[test]
+    fn test_opportunity_round_trips_and_rejects_unknown_versions() {
+        let queued = QueuedOpportunity {
+            block_number: 55_000_000,
+            detected_at_ms: 1_700_000_000_123,
+            min_profit: U256::exp10(16),
+            opportunity: ArbitrageOpportunity {
+                token0: address(1),
+                token1: address(2),
+                amount0: U256::exp10(18) * 5,
+                amount1: U256::zero(),
+                fee: 3000,
+                path: vec![address(1), address(2), address(1)],
+                amounts: vec![U256::exp10(18) * 5, U256::from(9_876_543u64), U256::exp10(18) * 6],
+                routers: vec![address(10), address(11)],
+                expected_profit: U256::exp10(18),
+                optimal_path: Vec::new(),
+                class: OpportunityClass::Triangular,
+            },
+            id: OpportunityId(H256::repeat_byte(7)),
+        };
+        let bytes = encode(&queued).unwrap();
+        // Below the JSON form, which repeats every field name
+        assert!(bytes.len() < serde_json::to_vec(&queued).unwrap().len(), "{} bytes", bytes.len());
+
+        let decoded = decode(&bytes).unwrap();
+        assert_eq!(decoded.block_number, queued.block_number);
+        assert_eq!(decoded.min_profit, queued.min_profit);
+        assert_eq!(decoded.opportunity.class, OpportunityClass::Triangular);
+        assert_eq!(decoded.opportunity.amounts, queued.opportunity.amounts);
+        assert_eq!(decoded.opportunity.routers, queued.opportunity.routers);
+        assert_eq!(decoded.id, queued.id);
+        assert_eq!(encode(&decoded).unwrap(), bytes);
+
+        // Fields appended by a newer writer of the same version are skipped
+        let mut extended = bytes.clone();
+        extended.extend_from_slice(&[7, 7, 7]);
+        assert_eq!(encode(&decode(&extended).unwrap()).unwrap(), bytes);
+
+        let mut newer = bytes.clone();
+        newer[2] = SCHEMA_VERSION + 1;
+        assert!(decode(&newer).unwrap_err().to_string().contains("not supported"));
+        assert!(decode(&bytes[..bytes.len() - 1]).is_err());
+        assert!(decode(b"{\"json\":1}").is_err());
+    }
+
+    #[test]
+    fn test_opportunity_files_are_parsed_and_shape_checked() {
+        let json = serde_json::json!({
+            "token0": address(1),
+            "token1": address(2),
+            "amount0": "0xde0b6b3a7640000",
+            "amount1": "0x0",
+            "fee": 3000,
+            "path": [address(1), address(2), address(1)],
+            "amounts": [],
+            "routers": [address(10), address(11)],
+            "expected_profit": "0x2386f26fc10000",
+            "optimal_path": [],
+        });
+        let file = std::env::temp_dir().join(format!("flashwich-opportunity-{}.json", std::process::id()));
+        std::fs::write(&file, json.to_string()).unwrap();
+        let opportunity = load_json(file.to_str().unwrap()).unwrap();
+        std::fs::remove_file(&file).unwrap();
+        assert_eq!(opportunity.amount0, U256::exp10(18));
+        assert_eq!(opportunity.class, OpportunityClass::default());
+
+        let broken = |edit: fn(&mut ArbitrageOpportunity)| {
+            let mut opportunity = opportunity.clone();
+            edit(&mut opportunity);
+            validate(&opportunity).unwrap_err().to_string()
+        };
+        assert!(broken(|o| o.path.truncate(1)).contains("at least two"));
+        assert!(broken(|o| o.token0 = address(3)).contains("not at token0"));
+        assert!(broken(|o| o.routers.clear()).contains("no routers"));
+        assert!(broken(|o| o.routers[1] = Address::zero()).contains("zero address"));
+        assert!(broken(|o| o.amount0 = U256::zero()).contains("nothing borrowed"));
+        assert!(load_json("/nonexistent/opportunity.json").is_err());
+    }
+}
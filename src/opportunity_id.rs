@@ -0,0 +1,90 @@
+// src/opportunity_id.rs
+use ethers::{
+    abi::{encode, Token},
+    types::{Address, H256, U256},
+    utils::keccak256,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::simulation_engine::ArbitrageOpportunity;
+
+// The one name of an opportunity across the journal, the dedup claims, queue messages and
+// log lines: keccak256(abi.encode(pools, tokenIn, tokenOut, amountBucket, targetBlock)).
+// Pools are sorted, so detectors that list a route's pools differently agree; the amount
+// only counts by its power of two, so two sizings of the same spread agree too
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct OpportunityId(pub H256);
+
+impl OpportunityId {
+    pub fn new(pools: &[Address], token_in: Address, token_out: Address, amount_in: U256, target_block: u64) -> Self {
+        let mut pools = pools.to_vec();
+        pools.sort();
+        pools.dedup();
+        let encoded = encode(&[
+            Token::Array(pools.into_iter().map(Token::Address).collect()),
+            Token::Address(token_in),
+            Token::Address(token_out),
+            Token::Uint(amount_bucket(amount_in).into()),
+            Token::Uint(target_block.into()),
+        ]);
+        Self(H256::from(keccak256(encoded)))
+    }
+
+    // A detected opportunity aimed at `target_block`; its routers stand for the pools
+    pub fn of(opportunity: &ArbitrageOpportunity, target_block: u64) -> Self {
+        Self::new(
+            &opportunity.routers,
+            opportunity.token0,
+            opportunity.token1,
+            opportunity.amount0.max(opportunity.amount1),
+            target_block,
+        )
+    }
+
+    // First four bytes, enough to follow one opportunity through the logs
+    pub fn short(&self) -> String {
+        format!("{}", self).chars().take(10).collect()
+    }
+}
+
+// Bit length: 0 for nothing, then one bucket per doubling
+fn amount_bucket(amount: U256) -> u64 {
+    amount.bits() as u64
+}
+
+impl fmt::Display for OpportunityId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#x}", self.0)
+    }
+}
+
+impl FromStr for OpportunityId {
+    type Err = <H256 as FromStr>::Err;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(Self(s.parse()?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::address;
+
+    #[test]
+    fn test_id_ignores_pool_order_and_size_within_a_doubling() {
+        let (pool_a, pool_b, token0, token1) = (address(1), address(2), address(10), address(11));
+        let amount = U256::exp10(18);
+        let id = OpportunityId::new(&[pool_a, pool_b], token0, token1, amount, 100);
+
+        assert_eq!(id, OpportunityId::new(&[pool_b, pool_a], token0, token1, amount * 11 / 10, 100));
+        assert_ne!(id, OpportunityId::new(&[pool_a, pool_b], token1, token0, amount, 100));
+        assert_ne!(id, OpportunityId::new(&[pool_a, pool_b], token0, token1, amount * 2, 100));
+        assert_ne!(id, OpportunityId::new(&[pool_a, pool_b], token0, token1, amount, 101));
+
+        assert_eq!(id.to_string().parse::<OpportunityId>().unwrap(), id);
+        assert_eq!(id.short().len(), 10);
+    }
+}
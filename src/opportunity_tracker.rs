@@ -0,0 +1,179 @@
+// src/opportunity_tracker.rs
+use ethers::types::{Address, U256};
+use log::debug;
+use std::collections::HashMap;
+
+use crate::amm_math::get_amount_out;
+use crate::cooldown::PoolCooldown;
+use crate::reserve_cache::ReserveSnapshot;
+
+// A spread that was detected but not executed, carried over to later blocks
+#[derive(Debug, Clone)]
+pub struct TrackedOpportunity {
+    pub path: Vec<Address>,  // route handed to execution
+    pub pools: Vec<Address>, // pools priced per hop, reserve0 treated as the input side
+    pub amount_in: U256,
+    pub first_seen_block: u64,
+    pub last_validated_block: u64,
+    pub last_profit: U256,
+}
+
+#[derive(Debug, Clone)]
+pub struct OpportunityTracker {
+    entries: HashMap<Vec<Address>, TrackedOpportunity>,
+    max_age_blocks: u64,
+}
+
+impl OpportunityTracker {
+    pub fn new(max_age_blocks: u64) -> Self {
+        Self {
+            entries: HashMap::new(),
+            max_age_blocks,
+        }
+    }
+
+    pub fn track(
+        &mut self,
+        path: Vec<Address>,
+        pools: Vec<Address>,
+        amount_in: U256,
+        profit: U256,
+        block: u64,
+    ) {
+        let entry = self.entries.entry(path.clone()).or_insert(TrackedOpportunity {
+            path,
+            pools,
+            amount_in,
+            first_seen_block: block,
+            last_validated_block: block,
+            last_profit: profit,
+        });
+        entry.amount_in = amount_in;
+        entry.last_validated_block = block;
+        entry.last_profit = profit;
+    }
+
+    // Pools whose reserves need refreshing before `revalidate`
+    pub fn watched_pools(&self) -> Vec<Address> {
+        let mut pools: Vec<Address> = self
+            .entries
+            .values()
+            .flat_map(|opportunity| opportunity.pools.iter().copied())
+            .collect();
+        pools.sort();
+        pools.dedup();
+        pools
+    }
+
+    // Re-price every tracked path from cached reserves, dropping expired or vanished spreads.
+    // `fee` gives each pool's swap fee
+    pub fn revalidate<F>(&mut self, cache: &ReserveSnapshot, block: u64, fee: F)
+    where
+        F: Fn(&Address) -> u32,
+    {
+        let max_age = self.max_age_blocks;
+
+        self.entries.retain(|_, opportunity| {
+            if block.saturating_sub(opportunity.first_seen_block) > max_age {
+                debug!("Dropping tracked opportunity {:?}: expired", opportunity.path);
+                return false;
+            }
+
+            match quote_from_cache(cache, &opportunity.pools, opportunity.amount_in, &fee) {
+                Some(amount_out) if amount_out > opportunity.amount_in => {
+                    opportunity.last_profit = amount_out - opportunity.amount_in;
+                    opportunity.last_validated_block = block;
+                    true
+                }
+                _ => {
+                    debug!("Dropping tracked opportunity {:?}: spread closed", opportunity.path);
+                    false
+                }
+            }
+        });
+    }
+
+    // Remove and return opportunities whose profit now clears their required minimum.
+    // Routes through a cooling pool stay tracked until its window has passed
+    pub fn take_executable<F>(&mut self, min_profit: F, cooldown: &PoolCooldown) -> Vec<TrackedOpportunity>
+    where
+        F: Fn(&TrackedOpportunity) -> U256,
+    {
+        let ready: Vec<Vec<Address>> = self
+            .entries
+            .iter()
+            .filter(|(_, opportunity)| opportunity.last_profit > min_profit(opportunity))
+            .filter(|(_, opportunity)| {
+                cooldown
+                    .cooling(&opportunity.pools, opportunity.last_validated_block)
+                    .is_none()
+            })
+            .map(|(key, _)| key.clone())
+            .collect();
+
+        ready
+            .into_iter()
+            .filter_map(|key| self.entries.remove(&key))
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+fn quote_from_cache<F>(cache: &ReserveSnapshot, pools: &[Address], amount_in: U256, fee: &F) -> Option<U256>
+where
+    F: Fn(&Address) -> u32,
+{
+    let mut current = amount_in;
+    for pool in pools {
+        let reserves = cache.get(pool)?;
+        current = get_amount_out(current, reserves.reserve0, reserves.reserve1, fee(pool));
+    }
+    Some(current)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::amm_math::DEFAULT_V2_FEE;
+    use crate::cooldown::CooldownSettings;
+    use crate::reserve_cache::ReserveCache;
+
+    #[test]
+    fn test_tracked_spread_executes_once_cost_drops() {
+        let cache = ReserveCache::new();
+        let pool_a = Address::from_low_u64_be(1);
+        let pool_b = Address::from_low_u64_be(2);
+        cache.update(pool_a, U256::from(1_000_000u64), U256::from(2_000_000u64), 10);
+        cache.update(pool_b, U256::from(1_000_000u64), U256::from(600_000u64), 10);
+
+        let mut tracker = OpportunityTracker::new(5);
+        tracker.track(
+            vec![pool_a, pool_b],
+            vec![pool_a, pool_b],
+            U256::from(1_000u64),
+            U256::zero(),
+            10,
+        );
+
+        tracker.revalidate(&cache.snapshot(), 11, |_| DEFAULT_V2_FEE);
+        assert_eq!(tracker.len(), 1);
+        let cooldown = PoolCooldown::new(&CooldownSettings { blocks: 2 });
+        assert!(tracker.take_executable(|_| U256::from(1_000u64), &cooldown).is_empty());
+
+        // Our own trade through pool_b at block 10 holds the route back for this block
+        cooldown.start(&[pool_b], 10);
+        assert!(tracker.take_executable(|_| U256::from(10u64), &cooldown).is_empty());
+
+        tracker.revalidate(&cache.snapshot(), 12, |_| DEFAULT_V2_FEE);
+        let executable = tracker.take_executable(|_| U256::from(10u64), &cooldown);
+        assert_eq!(executable.len(), 1);
+        assert!(tracker.is_empty());
+    }
+}
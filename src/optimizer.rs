@@ -0,0 +1,309 @@
+// src/optimizer.rs
+use ethers::types::U256;
+use log::debug;
+use serde::Deserialize;
+
+use crate::metrics::{OPTIMIZER_RUNS_TOTAL, ROUTE_HOP_LIMIT};
+
+const BPS: u64 = 10_000;
+
+// Trade sizing. A coarse geometric grid over [min_amount, max_amount] is checked for a
+// single peak; constant-product routes have one and are refined by binary search on the
+// slope around it, routes crossing V3 tick boundaries may not and have every local peak
+// of the grid refined instead. It also caps route length: another hop is only searched
+// while the expected spread, taken on `spread_amount`, still pays that many hops' gas
+#[derive(Debug, Clone, Deserialize)]
+pub struct OptimizerSettings {
+    // Bounds of the search in whole start tokens (18 decimals)
+    #[serde(default = "default_min_amount")]
+    pub min_amount: f64,
+    #[serde(default = "default_max_amount")]
+    pub max_amount: f64,
+    #[serde(default = "default_grid_points")]
+    pub grid_points: usize,
+    // Refinement stops once the bracket is this narrow relative to the amount
+    #[serde(default = "default_precision_bps")]
+    pub precision_bps: u64,
+    // Profit evaluations the refinement may spend on top of the grid
+    #[serde(default = "default_max_iterations")]
+    pub max_iterations: usize,
+    // Early exit once the rest of the bracket can add less than this share of the best profit
+    #[serde(default = "default_min_delta_bps")]
+    pub min_delta_bps: u64,
+    // Routes of up to this many hops are searched whatever the gas price
+    #[serde(default = "default_min_hops")]
+    pub min_hops: usize,
+    // Trade size in whole start tokens the expected spread is taken on
+    #[serde(default = "default_spread_amount")]
+    pub spread_amount: f64,
+}
+
+fn default_min_amount() -> f64 {
+    0.1
+}
+
+fn default_max_amount() -> f64 {
+    1_000.0
+}
+
+fn default_grid_points() -> usize {
+    12
+}
+
+fn default_precision_bps() -> u64 {
+    10
+}
+
+fn default_max_iterations() -> usize {
+    48
+}
+
+fn default_min_delta_bps() -> u64 {
+    1
+}
+
+fn default_min_hops() -> usize {
+    2
+}
+
+fn default_spread_amount() -> f64 {
+    100.0
+}
+
+impl Default for OptimizerSettings {
+    fn default() -> Self {
+        Self {
+            min_amount: default_min_amount(),
+            max_amount: default_max_amount(),
+            grid_points: default_grid_points(),
+            precision_bps: default_precision_bps(),
+            max_iterations: default_max_iterations(),
+            min_delta_bps: default_min_delta_bps(),
+            min_hops: default_min_hops(),
+            spread_amount: default_spread_amount(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Optimum {
+    // Zero when no size pays
+    pub amount: U256,
+    pub profit: U256,
+    pub evaluations: usize,
+    // Whether the grid showed a single peak
+    pub concave: bool,
+}
+
+impl Optimum {
+    fn offer(&mut self, amount: U256, profit: U256) {
+        if profit > self.profit {
+            self.amount = amount;
+            self.profit = profit;
+        }
+    }
+}
+
+impl OptimizerSettings {
+    // Most profitable input size for `profit`, which returns zero for losing sizes
+    pub fn optimize<F>(&self, profit: F) -> Optimum
+    where
+        F: Fn(U256) -> U256,
+    {
+        let optimum = self.optimum(profit);
+        let shape = if optimum.concave { "concave" } else { "non_concave" };
+        OPTIMIZER_RUNS_TOTAL.with_label_values(&[shape]).inc();
+        debug!(
+            "Optimizer picked {} for profit {} after {} evaluations ({})",
+            optimum.amount, optimum.profit, optimum.evaluations, shape
+        );
+        optimum
+    }
+
+    // `optimize` without counting the run, for the shadow engine
+    pub fn optimum<F>(&self, profit: F) -> Optimum
+    where
+        F: Fn(U256) -> U256,
+    {
+        let grid = self.grid();
+        let samples: Vec<U256> = grid.iter().map(|&amount| profit(amount)).collect();
+        let mut optimum = Optimum {
+            evaluations: samples.len(),
+            concave: single_peak(&samples),
+            ..Default::default()
+        };
+        for (&amount, &sample) in grid.iter().zip(&samples) {
+            optimum.offer(amount, sample);
+        }
+
+        // A single peak only needs its own neighbourhood refined
+        let mut peaks = local_peaks(&samples);
+        peaks.sort_by_key(|&i| std::cmp::Reverse(samples[i]));
+        if optimum.concave {
+            peaks.truncate(1);
+        }
+
+        let budget = optimum.evaluations + self.max_iterations;
+        for i in peaks {
+            let lo = if i == 0 { U256::zero() } else { grid[i - 1] };
+            let hi = grid.get(i + 1).copied().unwrap_or(grid[i]);
+            self.refine(&profit, lo, hi, budget, &mut optimum);
+            if optimum.evaluations >= budget {
+                break;
+            }
+        }
+        optimum
+    }
+
+    // Longest route worth searching, at most `ceiling`: a longer one while the spread of
+    // `spread_bps` still clears `min_profit` for its hop count at the current gas price
+    pub fn hop_limit<F>(&self, ceiling: usize, spread_bps: f64, min_profit: F) -> usize
+    where
+        F: Fn(usize) -> U256,
+    {
+        let limit = self.max_hops(ceiling, spread_bps, min_profit);
+        ROUTE_HOP_LIMIT.set(limit as f64);
+        limit
+    }
+
+    // `hop_limit` without publishing it, for the shadow engine
+    pub fn max_hops<F>(&self, ceiling: usize, spread_bps: f64, min_profit: F) -> usize
+    where
+        F: Fn(usize) -> U256,
+    {
+        let expected = to_wei(self.spread_amount * spread_bps.max(0.0) / BPS as f64);
+        let floor = self.min_hops.clamp(1, ceiling.max(1));
+        (floor + 1..=ceiling)
+            .take_while(|&hops| min_profit(hops) <= expected)
+            .last()
+            .unwrap_or(floor)
+    }
+
+    // Binary search on the sign of the slope between `lo` and `hi`
+    fn refine<F>(&self, profit: &F, mut lo: U256, mut hi: U256, budget: usize, optimum: &mut Optimum)
+    where
+        F: Fn(U256) -> U256,
+    {
+        let precision = U256::from(self.precision_bps.max(1));
+        while optimum.evaluations + 2 <= budget && (hi - lo) * U256::from(BPS) > hi * precision {
+            let mid = lo + (hi - lo) / 2;
+            let step = (mid * precision / U256::from(BPS) / 2).max(U256::one());
+            let (left, right) = (profit(mid), profit(mid + step));
+            optimum.evaluations += 2;
+            optimum.offer(mid, left);
+            optimum.offer(mid + step, right);
+
+            if left.is_zero() && right.is_zero() {
+                // Past break-even, the peak is at smaller sizes
+                hi = mid;
+                continue;
+            }
+            let delta = if right > left { right - left } else { left - right };
+            if right > left {
+                lo = mid + step;
+            } else {
+                hi = mid;
+            }
+            // On a concave stretch the slope bounds what the rest of the bracket can still add
+            if delta * (hi - lo) * U256::from(BPS) <= optimum.profit * U256::from(self.min_delta_bps) * step {
+                break;
+            }
+        }
+    }
+
+    fn grid(&self) -> Vec<U256> {
+        let points = self.grid_points.max(3);
+        let min = self.min_amount.max(1e-9);
+        let max = self.max_amount.max(min);
+        let ratio = (max / min).powf(1.0 / (points - 1) as f64);
+        let mut grid: Vec<U256> = (0..points).map(|i| to_wei(min * ratio.powi(i as i32))).collect();
+        grid.dedup();
+        grid
+    }
+}
+
+fn to_wei(amount: f64) -> U256 {
+    U256::from((amount * 1e9) as u128) * U256::exp10(9)
+}
+
+// Profits rise, then fall, with flat stretches allowed
+fn single_peak(samples: &[U256]) -> bool {
+    let mut falling = false;
+    for pair in samples.windows(2) {
+        if pair[1] < pair[0] {
+            falling = true;
+        } else if pair[1] > pair[0] && falling {
+            return false;
+        }
+    }
+    true
+}
+
+// Profitable samples at least as good as both neighbours, first of a plateau
+fn local_peaks(samples: &[U256]) -> Vec<usize> {
+    (0..samples.len())
+        .filter(|&i| {
+            !samples[i].is_zero()
+                && (i == 0 || samples[i] > samples[i - 1])
+                && samples.get(i + 1).is_none_or(|&next| samples[i] >= next)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::amm_math::{best_amount, route_profit, FeeSettings};
+    use crate::fixtures::{address, PoolSet};
+
+    #[test]
+    fn test_optimizer_refines_single_peak_and_searches_every_peak_otherwise() {
+        let e18 = U256::exp10(18);
+        let pools = PoolSet::new()
+            .pool(address(10), address(1), address(2), e18 * 1_000, e18 * 2_000)
+            .pool(address(11), address(2), address(1), e18 * 2_000, e18 * 1_040);
+        let hops = pools.hops(&FeeSettings::default());
+        let profit = |amount| route_profit(amount, &hops, |_, out| Some(out));
+
+        let settings = OptimizerSettings::default();
+        let optimum = settings.optimize(profit);
+        assert!(optimum.concave);
+        assert!(optimum.evaluations <= settings.grid_points + settings.max_iterations);
+        // Beats the old fixed sizes and sits on the peak: nudging it either way loses
+        let (_, fixed) = best_amount(&[e18, e18 * 5, e18 * 10], profit);
+        assert!(optimum.profit > fixed);
+        let nudge = optimum.amount / 100;
+        assert!(profit(optimum.amount + nudge) <= optimum.profit);
+        assert!(profit(optimum.amount - nudge) <= optimum.profit);
+
+        // Two humps, the taller one narrow and far from the first grid peak
+        let humps = |amount: U256| {
+            let tokens = (amount / U256::exp10(15)).as_u64() as f64 / 1_000.0;
+            let hump = |center: f64, width: f64, height: f64| height * (1.0 - ((tokens - center) / width).powi(2));
+            let value = hump(2.0, 2.0, 1.0).max(hump(300.0, 150.0, 3.0)).max(0.0);
+            U256::from((value * 1e6) as u64)
+        };
+        let optimum = settings.optimize(humps);
+        assert!(!optimum.concave);
+        let tokens = (optimum.amount / U256::exp10(18)).as_u64();
+        assert!((290..=310).contains(&tokens), "refined to {tokens}");
+
+        // Nothing pays, nothing is picked
+        assert_eq!(settings.optimize(|_| U256::zero()).amount, U256::zero());
+    }
+
+    #[test]
+    fn test_hop_limit_grows_with_the_spread_and_shrinks_with_gas() {
+        let settings = OptimizerSettings::default();
+        // 0.1 MATIC of gas per hop
+        let cheap = |hops: usize| U256::exp10(17) * hops;
+        let dear = |hops: usize| U256::exp10(18) * hops;
+
+        // 30 bps on 100 MATIC is 0.3 MATIC: three hops at cheap gas, none past the floor at dear gas
+        assert_eq!(settings.hop_limit(4, 30.0, cheap), 3);
+        assert_eq!(settings.hop_limit(4, 30.0, dear), 2);
+        assert_eq!(settings.hop_limit(4, 500.0, dear), 4);
+        assert_eq!(settings.hop_limit(3, 500.0, cheap), 3);
+        assert_eq!(settings.hop_limit(4, 0.0, cheap), 2);
+    }
+}
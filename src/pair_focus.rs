@@ -0,0 +1,211 @@
+// src/pair_focus.rs
+use ethers::types::Address;
+use log::info;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Mutex;
+
+use crate::metrics::FOCUS_PAIR_CHANGES_TOTAL;
+
+// Token pairs the block scan checks every block and first; the rest of the universe only
+// every `background_interval_blocks`. Background pairs whose pools keep showing spreads are
+// promoted into the focus list and leave it again once they go quiet
+#[derive(Debug, Clone, Deserialize)]
+pub struct FocusSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub pairs: Vec<[Address; 2]>,
+    #[serde(default = "default_background_interval_blocks")]
+    pub background_interval_blocks: u64,
+    // Spreads within `promote_window_blocks` that promote a background pair
+    #[serde(default = "default_promote_after")]
+    pub promote_after: usize,
+    #[serde(default = "default_promote_window_blocks")]
+    pub promote_window_blocks: u64,
+    // A promoted pair without a spread for this long is demoted
+    #[serde(default = "default_demote_after_blocks")]
+    pub demote_after_blocks: u64,
+    #[serde(default = "default_max_promoted")]
+    pub max_promoted: usize,
+}
+
+fn default_background_interval_blocks() -> u64 {
+    5
+}
+
+fn default_promote_after() -> usize {
+    3
+}
+
+fn default_promote_window_blocks() -> u64 {
+    100
+}
+
+fn default_demote_after_blocks() -> u64 {
+    1_000
+}
+
+fn default_max_promoted() -> usize {
+    20
+}
+
+impl Default for FocusSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            pairs: Vec::new(),
+            background_interval_blocks: default_background_interval_blocks(),
+            promote_after: default_promote_after(),
+            promote_window_blocks: default_promote_window_blocks(),
+            demote_after_blocks: default_demote_after_blocks(),
+            max_promoted: default_max_promoted(),
+        }
+    }
+}
+
+type PairKey = (Address, Address);
+
+fn key(a: Address, b: Address) -> PairKey {
+    (a.min(b), a.max(b))
+}
+
+#[derive(Debug, Default)]
+struct FocusState {
+    // Blocks of recent spreads per background pair
+    spreads: HashMap<PairKey, VecDeque<u64>>,
+    // Promoted pairs and the block of their latest spread
+    promoted: HashMap<PairKey, u64>,
+}
+
+#[derive(Debug)]
+pub struct PairFocus {
+    settings: FocusSettings,
+    configured: HashSet<PairKey>,
+    state: Mutex<FocusState>,
+}
+
+impl PairFocus {
+    pub fn new(settings: FocusSettings) -> Self {
+        Self {
+            configured: settings.pairs.iter().map(|&[a, b]| key(a, b)).collect(),
+            settings,
+            state: Mutex::new(FocusState::default()),
+        }
+    }
+
+    pub fn is_focus(&self, a: Address, b: Address) -> bool {
+        let pair = key(a, b);
+        self.configured.contains(&pair) || self.state.lock().unwrap().promoted.contains_key(&pair)
+    }
+
+    // Whether the pair is scanned at `block`; background pairs are staggered over the
+    // interval so each block scans a slice of them
+    pub fn due(&self, a: Address, b: Address, block: u64) -> bool {
+        if !self.settings.enabled || self.is_focus(a, b) {
+            return true;
+        }
+        let interval = self.settings.background_interval_blocks.max(1);
+        let (low, high) = key(a, b);
+        let offset = low.to_low_u64_be() ^ high.to_low_u64_be();
+        (block.wrapping_add(offset)) % interval == 0
+    }
+
+    // `pairs` in scan order, focus pairs first, without those not due at `block`
+    pub fn schedule(&self, pairs: Vec<(Address, Address)>, block: u64) -> Vec<(Address, Address)> {
+        let (mut focus, background): (Vec<_>, Vec<_>) = pairs
+            .into_iter()
+            .filter(|&(a, b)| self.due(a, b, block))
+            .partition(|&(a, b)| self.is_focus(a, b));
+        focus.extend(background);
+        focus
+    }
+
+    // Counts a spread on the pair at `block`; true when it got the pair promoted
+    pub fn record_spread(&self, a: Address, b: Address, block: u64) -> bool {
+        if !self.settings.enabled {
+            return false;
+        }
+        let pair = key(a, b);
+        if self.configured.contains(&pair) {
+            return false;
+        }
+        let mut state = self.state.lock().unwrap();
+        if let Some(last) = state.promoted.get_mut(&pair) {
+            *last = block;
+            return false;
+        }
+        let window = self.settings.promote_window_blocks;
+        let spreads = state.spreads.entry(pair).or_default();
+        spreads.push_back(block);
+        while spreads.front().is_some_and(|&first| first + window < block) {
+            spreads.pop_front();
+        }
+        if spreads.len() < self.settings.promote_after.max(1) || state.promoted.len() >= self.settings.max_promoted {
+            return false;
+        }
+        state.spreads.remove(&pair);
+        state.promoted.insert(pair, block);
+        FOCUS_PAIR_CHANGES_TOTAL.with_label_values(&["promoted"]).inc();
+        info!("Promoted pair {:?}/{:?} to the focus list after repeated spreads", pair.0, pair.1);
+        true
+    }
+
+    // Demotes promoted pairs quiet since `demote_after_blocks`; returns how many
+    pub fn expire(&self, block: u64) -> usize {
+        let mut state = self.state.lock().unwrap();
+        let before = state.promoted.len();
+        let quiet = self.settings.demote_after_blocks;
+        state.promoted.retain(|pair, &mut last| {
+            let keep = last + quiet >= block;
+            if !keep {
+                info!("Demoted pair {:?}/{:?} from the focus list", pair.0, pair.1);
+            }
+            keep
+        });
+        // Spread counts outside the window can no longer promote
+        let window = self.settings.promote_window_blocks;
+        state.spreads.retain(|_, spreads| spreads.back().is_some_and(|&last| last + window >= block));
+        let demoted = before - state.promoted.len();
+        FOCUS_PAIR_CHANGES_TOTAL.with_label_values(&["demoted"]).inc_by(demoted as u64);
+        demoted
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::address;
+
+    #[test]
+    fn test_focus_pairs_scan_every_block_and_spreads_promote_background_pairs() {
+        let (wmatic, usdc, weth, dai) = (address(1), address(2), address(3), address(4));
+        let focus = PairFocus::new(FocusSettings {
+            enabled: true,
+            pairs: vec![[usdc, wmatic]],
+            background_interval_blocks: 4,
+            ..Default::default()
+        });
+        assert!(focus.is_focus(wmatic, usdc));
+        // Background pairs come up once per interval, focus pairs every block and first
+        let due: Vec<u64> = (100..108).filter(|&block| focus.due(weth, dai, block)).collect();
+        assert_eq!(due.len(), 2);
+        assert_eq!(due[1] - due[0], 4);
+        let pairs = vec![key(weth, dai), key(wmatic, usdc)];
+        assert_eq!(focus.schedule(pairs.clone(), due[0]), vec![key(wmatic, usdc), key(weth, dai)]);
+        assert_eq!(focus.schedule(pairs, due[0] + 1), vec![key(wmatic, usdc)]);
+
+        // Three spreads inside the window promote; spaced-out ones do not
+        assert!(!focus.record_spread(weth, usdc, 100));
+        assert!(!focus.record_spread(weth, usdc, 300));
+        assert!(!focus.record_spread(weth, usdc, 350));
+        assert!(focus.record_spread(usdc, weth, 380));
+        assert!(focus.is_focus(weth, usdc));
+        assert!(focus.due(weth, usdc, 381));
+        assert!(!focus.record_spread(wmatic, usdc, 381));
+
+        assert_eq!(focus.expire(1_380), 0);
+        assert_eq!(focus.expire(1_381), 1);
+        assert!(!focus.is_focus(weth, usdc));
+    }
+}
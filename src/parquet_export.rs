@@ -0,0 +1,159 @@
+// src/parquet_export.rs
+use anyhow::{anyhow, Result};
+use log::{error, info};
+use rusqlite::types::Value;
+use serde::Deserialize;
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+use crate::metrics::PARQUET_EXPORT_ROWS_TOTAL;
+use crate::parquet::{Cell, Column, ColumnKind, ParquetWriter};
+use crate::store::Store;
+
+const ROW_GROUP_ROWS: usize = 65_536;
+
+// `flashwich export` and, when enabled, a periodic task: journal and index tables copied to
+// one Parquet file each under `dir`, read through a read-only connection so research never
+// touches the live database. Amounts stay exact decimal strings, as stored
+#[derive(Debug, Clone, Deserialize)]
+pub struct ParquetExportSettings {
+    // Export on a schedule while the bot runs; `flashwich export` works either way
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_dir")]
+    pub dir: String,
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+    #[serde(default = "default_tables")]
+    pub tables: Vec<String>,
+}
+
+fn default_dir() -> String {
+    "exports".to_string()
+}
+
+fn default_interval_secs() -> u64 {
+    3_600
+}
+
+fn default_tables() -> Vec<String> {
+    ["opportunities", "executions", "gas_spend", "callback_gas", "payouts", "pool_events", "tokens", "token_taxes"]
+        .map(String::from)
+        .to_vec()
+}
+
+impl Default for ParquetExportSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            dir: default_dir(),
+            interval_secs: default_interval_secs(),
+            tables: default_tables(),
+        }
+    }
+}
+
+fn cell(value: &Value) -> Cell {
+    match value {
+        Value::Null | Value::Blob(_) => Cell::Null,
+        Value::Integer(v) => Cell::Int(*v),
+        Value::Real(v) => Cell::Real(*v),
+        Value::Text(v) => Cell::Text(v.clone()),
+    }
+}
+
+// Writes `table` to `dir/<table>.parquet` through a temporary file, so readers never see a
+// half-written export; returns the rows written
+pub fn export_table(store: &Store, table: &str, dir: &Path) -> Result<usize> {
+    let columns: Vec<Column> = store
+        .table_columns(table)?
+        .into_iter()
+        .map(|(name, declared)| Column { name, kind: ColumnKind::from_declared(&declared) })
+        .collect();
+    if columns.is_empty() {
+        return Err(anyhow!("No table named {table}"));
+    }
+
+    let path = dir.join(format!("{table}.parquet"));
+    let partial = dir.join(format!("{table}.parquet.partial"));
+    let mut writer = ParquetWriter::new(BufWriter::new(File::create(&partial)?), columns)?;
+    let mut rows = 0;
+    store.scan_table(table, ROW_GROUP_ROWS, |batch| {
+        let cells: Vec<Vec<Cell>> = batch.iter().map(|row| row.iter().map(cell).collect()).collect();
+        writer.write_group(&cells)?;
+        rows += batch.len();
+        Ok(())
+    })?;
+    writer.finish()?;
+    std::fs::rename(&partial, &path)?;
+    PARQUET_EXPORT_ROWS_TOTAL.with_label_values(&[table]).inc_by(rows as u64);
+    Ok(rows)
+}
+
+// Every table of `tables` into `dir`, created if missing; (file, rows) per table
+pub fn export(store_path: &str, dir: &str, tables: &[String]) -> Result<Vec<(PathBuf, usize)>> {
+    let store = Store::open_read_only(store_path)?;
+    let dir = Path::new(dir);
+    std::fs::create_dir_all(dir)?;
+    tables
+        .iter()
+        .map(|table| Ok((dir.join(format!("{table}.parquet")), export_table(&store, table, dir)?)))
+        .collect()
+}
+
+// Re-exports every `interval_secs`; a failed round is logged and retried at the next one
+pub async fn run(store_path: String, settings: ParquetExportSettings) {
+    let mut interval = tokio::time::interval(Duration::from_secs(settings.interval_secs.max(60)));
+    loop {
+        interval.tick().await;
+        let (path, export_settings) = (store_path.clone(), settings.clone());
+        let exported =
+            tokio::task::spawn_blocking(move || export(&path, &export_settings.dir, &export_settings.tables)).await;
+        match exported {
+            Ok(Ok(files)) => {
+                let rows: usize = files.iter().map(|(_, rows)| rows).sum();
+                info!("Exported {} rows in {} tables to {}", rows, files.len(), settings.dir);
+            }
+            Ok(Err(e)) => error!("Parquet export failed: {:#}", e),
+            Err(e) => error!("Parquet export task failed: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::GasSpendRecord;
+    use ethers::types::{H256, U256};
+
+    #[test]
+    fn test_tables_export_from_a_read_only_connection() {
+        let dir = std::env::temp_dir().join(format!("flashwich-export-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db = dir.join("journal.db");
+        let store = Store::open(&db).unwrap();
+        for block_number in [10, 11] {
+            let spend = GasSpendRecord {
+                tx_hash: H256::from_low_u64_be(block_number),
+                block_number,
+                gas_used: U256::from(210_000u64),
+                cost: U256::exp10(16),
+            };
+            store.record_gas_spend(&spend).unwrap();
+        }
+
+        let out = dir.join("out");
+        let tables = vec!["gas_spend".to_string(), "opportunities".to_string()];
+        let files = export(db.to_str().unwrap(), out.to_str().unwrap(), &tables).unwrap();
+        assert_eq!(files.iter().map(|(_, rows)| *rows).collect::<Vec<_>>(), vec![2, 0]);
+        let file = std::fs::read(out.join("gas_spend.parquet")).unwrap();
+        assert!(file.starts_with(b"PAR1") && file.ends_with(b"PAR1"));
+        assert!(file.windows(17).any(|w| w == b"10000000000000000"));
+        assert!(!out.join("gas_spend.parquet.partial").exists());
+
+        assert!(export(db.to_str().unwrap(), out.to_str().unwrap(), &["missing".to_string()]).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
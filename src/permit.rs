@@ -0,0 +1,309 @@
+// src/permit.rs
+use anyhow::{anyhow, Result};
+use ethers::{
+    abi::{self, ParamType, Token},
+    contract::abigen,
+    providers::Middleware,
+    signers::{LocalWallet, Signer},
+    types::{Address, Bytes, H256, U256},
+    utils::keccak256,
+};
+use log::debug;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+abigen!(
+    IERC20Permit,
+    r#"[
+        function nonces(address owner) external view returns (uint256)
+        function DOMAIN_SEPARATOR() external view returns (bytes32)
+    ]"#
+);
+
+abigen!(
+    IPermit2,
+    r#"[
+        function allowance(address owner, address token, address spender) external view returns (uint160, uint48, uint48)
+        function DOMAIN_SEPARATOR() external view returns (bytes32)
+    ]"#
+);
+
+const PERMIT_TYPE: &str = "Permit(address owner,address spender,uint256 value,uint256 nonce,uint256 deadline)";
+const PERMIT_DETAILS_TYPE: &str = "PermitDetails(address token,uint160 amount,uint48 expiration,uint48 nonce)";
+const PERMIT_SINGLE_TYPE: &str = "PermitSingle(PermitDetails details,address spender,uint256 sigDeadline)";
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PermitSettings {
+    // Sign allowances for the executor instead of relying on on-chain approvals
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_deadline_secs")]
+    pub deadline_secs: u64,
+    // Tokens implementing EIP-2612 permit(); everything else goes through Permit2
+    #[serde(default)]
+    pub eip2612_tokens: Vec<Address>,
+    // Needs a one-time approve(permit2, max) per token by the owner
+    #[serde(default)]
+    pub permit2: bool,
+    #[serde(default = "default_permit2_address")]
+    pub permit2_address: Address,
+}
+
+fn default_deadline_secs() -> u64 {
+    120
+}
+
+// Canonical Permit2 deployment, same address on every chain
+fn default_permit2_address() -> Address {
+    "0x000000000022D473030F116dDEE9F6B43aC78BA3".parse().unwrap()
+}
+
+impl Default for PermitSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            deadline_secs: default_deadline_secs(),
+            eip2612_tokens: Vec::new(),
+            permit2: false,
+            permit2_address: default_permit2_address(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PermitKind {
+    Eip2612,
+    Permit2,
+}
+
+// A signed allowance for the executor, passed in calldata as
+// (uint8 kind, address owner, address token, uint256 amount, uint256 deadline, uint256 nonce, bytes signature).
+// The executor calls token.permit() or Permit2.permit() with it before pulling funds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Permit {
+    pub kind: PermitKind,
+    pub owner: Address,
+    pub token: Address,
+    pub amount: U256,
+    pub deadline: U256,
+    pub nonce: U256,
+    // 65 bytes, r || s || v
+    pub signature: Bytes,
+}
+
+impl Permit {
+    pub fn param_type() -> ParamType {
+        ParamType::Tuple(vec![
+            ParamType::Uint(8),
+            ParamType::Address,
+            ParamType::Address,
+            ParamType::Uint(256),
+            ParamType::Uint(256),
+            ParamType::Uint(256),
+            ParamType::Bytes,
+        ])
+    }
+
+    pub fn to_token(&self) -> Token {
+        let kind = match self.kind {
+            PermitKind::Eip2612 => 0u8,
+            PermitKind::Permit2 => 1u8,
+        };
+        Token::Tuple(vec![
+            Token::Uint(U256::from(kind)),
+            Token::Address(self.owner),
+            Token::Address(self.token),
+            Token::Uint(self.amount),
+            Token::Uint(self.deadline),
+            Token::Uint(self.nonce),
+            Token::Bytes(self.signature.to_vec()),
+        ])
+    }
+}
+
+fn typed_data_digest(domain_separator: [u8; 32], struct_hash: [u8; 32]) -> H256 {
+    let mut message = Vec::with_capacity(66);
+    message.extend_from_slice(b"\x19\x01");
+    message.extend_from_slice(&domain_separator);
+    message.extend_from_slice(&struct_hash);
+    H256(keccak256(message))
+}
+
+pub fn eip2612_digest(
+    domain_separator: [u8; 32],
+    owner: Address,
+    spender: Address,
+    value: U256,
+    nonce: U256,
+    deadline: U256,
+) -> H256 {
+    let struct_hash = keccak256(abi::encode(&[
+        Token::FixedBytes(keccak256(PERMIT_TYPE).to_vec()),
+        Token::Address(owner),
+        Token::Address(spender),
+        Token::Uint(value),
+        Token::Uint(nonce),
+        Token::Uint(deadline),
+    ]));
+    typed_data_digest(domain_separator, struct_hash)
+}
+
+// PermitSingle with `expiration` and `sigDeadline` both set to `deadline`
+pub fn permit2_digest(
+    domain_separator: [u8; 32],
+    token: Address,
+    spender: Address,
+    amount: U256,
+    nonce: U256,
+    deadline: U256,
+) -> H256 {
+    let details = keccak256(abi::encode(&[
+        Token::FixedBytes(keccak256(PERMIT_DETAILS_TYPE).to_vec()),
+        Token::Address(token),
+        Token::Uint(amount),
+        Token::Uint(deadline),
+        Token::Uint(nonce),
+    ]));
+    let single_type = format!("{PERMIT_SINGLE_TYPE}{PERMIT_DETAILS_TYPE}");
+    let struct_hash = keccak256(abi::encode(&[
+        Token::FixedBytes(keccak256(single_type).to_vec()),
+        Token::FixedBytes(details.to_vec()),
+        Token::Address(spender),
+        Token::Uint(deadline),
+    ]));
+    typed_data_digest(domain_separator, struct_hash)
+}
+
+// Signs permits with the owner wallet; domain separators are cached per verifying contract
+#[derive(Debug, Clone)]
+pub struct PermitSigner<M> {
+    provider: Arc<M>,
+    wallet: LocalWallet,
+    settings: PermitSettings,
+    domains: Arc<Mutex<HashMap<Address, [u8; 32]>>>,
+}
+
+impl<M: Middleware + 'static> PermitSigner<M> {
+    pub fn new(provider: Arc<M>, wallet: LocalWallet, settings: PermitSettings) -> Self {
+        Self {
+            provider,
+            wallet,
+            settings,
+            domains: Arc::default(),
+        }
+    }
+
+    pub fn kind(&self, token: Address) -> Option<PermitKind> {
+        if !self.settings.enabled {
+            None
+        } else if self.settings.eip2612_tokens.contains(&token) {
+            Some(PermitKind::Eip2612)
+        } else if self.settings.permit2 {
+            Some(PermitKind::Permit2)
+        } else {
+            None
+        }
+    }
+
+    async fn domain_separator(&self, kind: PermitKind, token: Address) -> Result<[u8; 32]> {
+        let verifying = match kind {
+            PermitKind::Eip2612 => token,
+            PermitKind::Permit2 => self.settings.permit2_address,
+        };
+        if let Some(domain) = self.domains.lock().unwrap().get(&verifying) {
+            return Ok(*domain);
+        }
+        let domain = match kind {
+            PermitKind::Eip2612 => IERC20Permit::new(verifying, self.provider.clone()).domain_separator().call().await,
+            PermitKind::Permit2 => IPermit2::new(verifying, self.provider.clone()).domain_separator().call().await,
+        }
+        .map_err(|e| anyhow!("DOMAIN_SEPARATOR on {:?} failed: {e}", verifying))?;
+        self.domains.lock().unwrap().insert(verifying, domain);
+        Ok(domain)
+    }
+
+    async fn nonce(&self, kind: PermitKind, token: Address, spender: Address) -> Result<U256> {
+        let owner = self.owner();
+        match kind {
+            PermitKind::Eip2612 => IERC20Permit::new(token, self.provider.clone())
+                .nonces(owner)
+                .call()
+                .await
+                .map_err(|e| anyhow!("nonces on {:?} failed: {e}", token)),
+            PermitKind::Permit2 => IPermit2::new(self.settings.permit2_address, self.provider.clone())
+                .allowance(owner, token, spender)
+                .call()
+                .await
+                .map(|(_, _, nonce)| U256::from(nonce))
+                .map_err(|e| anyhow!("Permit2 allowance for {:?} failed: {e}", token)),
+        }
+    }
+
+    pub fn owner(&self) -> Address {
+        self.wallet.address()
+    }
+
+    // A permit letting `spender` pull `amount` of `token`, or None for tokens without
+    // permit support
+    pub async fn sign(&self, token: Address, spender: Address, amount: U256) -> Result<Option<Permit>> {
+        let Some(kind) = self.kind(token) else {
+            return Ok(None);
+        };
+        let now = SystemTime::now().duration_since(UNIX_EPOCH)?.as_secs();
+        let deadline = U256::from(now + self.settings.deadline_secs);
+        let domain = self.domain_separator(kind, token).await?;
+        let nonce = self.nonce(kind, token, spender).await?;
+
+        let digest = match kind {
+            PermitKind::Eip2612 => eip2612_digest(domain, self.owner(), spender, amount, nonce, deadline),
+            PermitKind::Permit2 => {
+                // Permit2 amounts are uint160
+                let amount = amount.min((U256::one() << 160) - 1);
+                permit2_digest(domain, token, spender, amount, nonce, deadline)
+            }
+        };
+        let signature = self.wallet.sign_hash(digest)?;
+        debug!("Signed {:?} permit for {:?} (nonce {})", kind, token, nonce);
+
+        Ok(Some(Permit {
+            kind,
+            owner: self.owner(),
+            token,
+            amount,
+            deadline,
+            nonce,
+            signature: signature.to_vec().into(),
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::types::Signature;
+
+    #[test]
+    fn test_permit_typehashes_and_signature() {
+        // Typehashes as hardcoded in Uniswap's Permit2 and OpenZeppelin's ERC20Permit
+        assert_eq!(
+            H256(keccak256(PERMIT_TYPE)),
+            "0x6e71edae12b1b97f4d1f60370fef10105fa2faae0126114a169c64845d6126c9".parse().unwrap()
+        );
+        assert_eq!(
+            H256(keccak256(format!("{PERMIT_SINGLE_TYPE}{PERMIT_DETAILS_TYPE}"))),
+            "0xf3841cd1ff0085026a6327b620b67997ce40f282c88a8e905a7a5626e310f3d0".parse().unwrap()
+        );
+
+        let wallet: LocalWallet = "0000000000000000000000000000000000000000000000000000000000000001"
+            .parse()
+            .unwrap();
+        let owner = wallet.address();
+        let digest = eip2612_digest([7u8; 32], owner, Address::from_low_u64_be(2), U256::from(5u64), U256::zero(), U256::from(99u64));
+        let signature = wallet.sign_hash(digest).unwrap();
+        let bytes = signature.to_vec();
+        assert_eq!(bytes.len(), 65);
+        assert_eq!(Signature::try_from(bytes.as_slice()).unwrap().recover(digest).unwrap(), owner);
+    }
+}
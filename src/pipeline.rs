@@ -0,0 +1,306 @@
+// src/pipeline.rs
+use ethers::types::Address;
+use serde::Deserialize;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use tokio::sync::{mpsc, Notify};
+
+use crate::mempool::{now_ms, PendingSwap};
+use crate::metrics::{PIPELINE_DROPS_TOTAL, PIPELINE_QUEUE_DEPTH};
+use crate::profit_token::swap_input_token;
+
+// Pending txs pass ingest -> decode -> filter -> simulate, each stage behind its own bounded
+// queue. A burst evicts the oldest queued txs instead of stalling the sources, and every
+// stage skips txs already older than its cutoff, so the simulator works on fresh ones
+#[derive(Debug, Clone, Deserialize)]
+pub struct PipelineSettings {
+    // Txs queued in front of each stage
+    #[serde(default = "default_capacity")]
+    pub capacity: usize,
+    // Age since first seen past which a stage drops a tx unprocessed
+    #[serde(default = "default_decode_max_age_ms")]
+    pub decode_max_age_ms: u64,
+    #[serde(default = "default_filter_max_age_ms")]
+    pub filter_max_age_ms: u64,
+    #[serde(default = "default_simulate_max_age_ms")]
+    pub simulate_max_age_ms: u64,
+}
+
+fn default_capacity() -> usize {
+    512
+}
+
+fn default_decode_max_age_ms() -> u64 {
+    1_000
+}
+
+fn default_filter_max_age_ms() -> u64 {
+    1_500
+}
+
+fn default_simulate_max_age_ms() -> u64 {
+    2_000
+}
+
+impl Default for PipelineSettings {
+    fn default() -> Self {
+        Self {
+            capacity: default_capacity(),
+            decode_max_age_ms: default_decode_max_age_ms(),
+            filter_max_age_ms: default_filter_max_age_ms(),
+            simulate_max_age_ms: default_simulate_max_age_ms(),
+        }
+    }
+}
+
+// The stage a queue feeds
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Stage {
+    Decode,
+    Filter,
+    Simulate,
+}
+
+impl Stage {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Stage::Decode => "decode",
+            Stage::Filter => "filter",
+            Stage::Simulate => "simulate",
+        }
+    }
+}
+
+pub trait Aged {
+    // Milliseconds since the tx was first seen
+    fn age_ms(&self) -> u64;
+}
+
+impl Aged for PendingSwap {
+    fn age_ms(&self) -> u64 {
+        now_ms().saturating_sub(self.first_seen_ms)
+    }
+}
+
+// A pending tx with its call decoded
+#[derive(Debug, Clone)]
+pub struct DecodedTx {
+    pub pending: PendingSwap,
+    // None for plain transfers
+    pub selector: Option<[u8; 4]>,
+    // Token sold, for V2 router swaps
+    pub input_token: Option<Address>,
+}
+
+impl Aged for DecodedTx {
+    fn age_ms(&self) -> u64 {
+        self.pending.age_ms()
+    }
+}
+
+pub fn decode(pending: PendingSwap) -> DecodedTx {
+    let selector = pending.tx.input.get(..4).map(|s| [s[0], s[1], s[2], s[3]]);
+    let input_token = swap_input_token(&pending.tx);
+    DecodedTx {
+        pending,
+        selector,
+        input_token,
+    }
+}
+
+// Only contract calls can move a pool
+pub fn keep(decoded: &DecodedTx) -> bool {
+    decoded.pending.tx.to.is_some() && decoded.selector.is_some()
+}
+
+#[derive(Debug)]
+struct QueueState<T> {
+    items: VecDeque<T>,
+    closed: bool,
+}
+
+// Bounded single-consumer queue that evicts its oldest item when full
+#[derive(Debug)]
+pub struct StageQueue<T> {
+    stage: Stage,
+    capacity: usize,
+    state: Arc<Mutex<QueueState<T>>>,
+    notify: Arc<Notify>,
+}
+
+impl<T> Clone for StageQueue<T> {
+    fn clone(&self) -> Self {
+        Self {
+            stage: self.stage,
+            capacity: self.capacity,
+            state: self.state.clone(),
+            notify: self.notify.clone(),
+        }
+    }
+}
+
+impl<T: Aged> StageQueue<T> {
+    pub fn new(stage: Stage, capacity: usize) -> Self {
+        Self {
+            stage,
+            capacity: capacity.max(1),
+            state: Arc::new(Mutex::new(QueueState {
+                items: VecDeque::new(),
+                closed: false,
+            })),
+            notify: Arc::new(Notify::new()),
+        }
+    }
+
+    pub fn push(&self, item: T) {
+        let mut state = self.state.lock().unwrap();
+        if state.closed {
+            return;
+        }
+        if state.items.len() >= self.capacity {
+            state.items.pop_front();
+            PIPELINE_DROPS_TOTAL.with_label_values(&[self.stage.as_str(), "overflow"]).inc();
+        }
+        state.items.push_back(item);
+        PIPELINE_QUEUE_DEPTH.with_label_values(&[self.stage.as_str()]).set(state.items.len() as i64);
+        drop(state);
+        self.notify.notify_one();
+    }
+
+    // Ends the stage once the queued items are taken
+    pub fn close(&self) {
+        self.state.lock().unwrap().closed = true;
+        self.notify.notify_one();
+    }
+
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().items.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // The next item not older than `max_age_ms`; None once closed and drained
+    pub async fn pop_fresh(&self, max_age_ms: u64) -> Option<T> {
+        loop {
+            {
+                let mut state = self.state.lock().unwrap();
+                while let Some(item) = state.items.pop_front() {
+                    PIPELINE_QUEUE_DEPTH.with_label_values(&[self.stage.as_str()]).set(state.items.len() as i64);
+                    if item.age_ms() <= max_age_ms {
+                        return Some(item);
+                    }
+                    PIPELINE_DROPS_TOTAL.with_label_values(&[self.stage.as_str(), "stale"]).inc();
+                }
+                if state.closed {
+                    return None;
+                }
+            }
+            self.notify.notified().await;
+        }
+    }
+}
+
+async fn run_stage<I: Aged, O: Aged>(
+    input: StageQueue<I>,
+    output: StageQueue<O>,
+    max_age_ms: u64,
+    step: impl Fn(I) -> Option<O>,
+) {
+    while let Some(item) = input.pop_fresh(max_age_ms).await {
+        match step(item) {
+            Some(item) => output.push(item),
+            None => PIPELINE_DROPS_TOTAL.with_label_values(&[input.stage.as_str(), "filtered"]).inc(),
+        }
+    }
+    output.close();
+}
+
+// Runs ingest, decode and filter on their own tasks; the returned queue feeds the simulator,
+// which takes from it with `simulate_max_age_ms`. `observe` sees every tx as it arrives
+pub fn spawn(
+    settings: &PipelineSettings,
+    mut feed: mpsc::Receiver<PendingSwap>,
+    observe: impl Fn(&PendingSwap) + Send + 'static,
+) -> StageQueue<DecodedTx> {
+    let decoding = StageQueue::new(Stage::Decode, settings.capacity);
+    let filtering = StageQueue::new(Stage::Filter, settings.capacity);
+    let simulating = StageQueue::new(Stage::Simulate, settings.capacity);
+
+    // Ingest only moves txs off the source channel, so sources never wait on the simulator
+    let ingested = decoding.clone();
+    tokio::spawn(async move {
+        while let Some(pending) = feed.recv().await {
+            observe(&pending);
+            ingested.push(pending);
+        }
+        ingested.close();
+    });
+    tokio::spawn(run_stage(decoding, filtering.clone(), settings.decode_max_age_ms, |pending| {
+        Some(decode(pending))
+    }));
+    tokio::spawn(run_stage(filtering, simulating.clone(), settings.filter_max_age_ms, |decoded| {
+        keep(&decoded).then_some(decoded)
+    }));
+    simulating
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{address, pending_swap, swap_tx};
+    use crate::mempool::MempoolSourceKind;
+    use ethers::types::{Bytes, Transaction, H256, U256};
+
+    fn pending(n: u8, age_ms: u64, input: &[u8]) -> PendingSwap {
+        let tx = Transaction {
+            hash: H256::repeat_byte(n),
+            to: Some(address(1)),
+            input: Bytes::from(input.to_vec()),
+            ..Default::default()
+        };
+        pending_swap(tx, MempoolSourceKind::Node, now_ms() - age_ms)
+    }
+
+    #[tokio::test]
+    async fn test_queues_evict_the_oldest_and_skip_stale_items() {
+        let queue = StageQueue::new(Stage::Simulate, 2);
+        for n in 1..=4 {
+            queue.push(pending(n, 0, &[]));
+        }
+        assert_eq!(queue.len(), 2);
+        queue.close();
+        // The burst evicted 1 and 2
+        assert_eq!(queue.pop_fresh(1_000).await.unwrap().tx.hash, H256::repeat_byte(3));
+        assert_eq!(queue.pop_fresh(1_000).await.unwrap().tx.hash, H256::repeat_byte(4));
+        assert!(queue.pop_fresh(1_000).await.is_none());
+
+        let queue = StageQueue::new(Stage::Simulate, 8);
+        queue.push(pending(5, 5_000, &[]));
+        queue.push(pending(6, 0, &[]));
+        assert_eq!(queue.pop_fresh(1_000).await.unwrap().tx.hash, H256::repeat_byte(6));
+    }
+
+    #[tokio::test]
+    async fn test_pipeline_decodes_filters_and_drops_stale_txs() {
+        let (sender, feed) = mpsc::channel(16);
+        let simulating = spawn(&PipelineSettings::default(), feed, |_| {});
+        let swap = swap_tx(H256::repeat_byte(9), address(1), U256::exp10(18), &[address(2), address(3)]);
+        let selector = [0xde, 0xad, 0xbe, 0xef];
+        sender.send(pending(1, 0, &[])).await.unwrap();
+        sender.send(pending_swap(swap.clone(), MempoolSourceKind::Node, now_ms())).await.unwrap();
+        sender.send(pending(2, 10_000, &selector)).await.unwrap();
+        sender.send(pending(3, 0, &selector)).await.unwrap();
+        drop(sender);
+
+        let decoded = simulating.pop_fresh(2_000).await.unwrap();
+        assert_eq!(decoded.pending.tx.hash, swap.hash);
+        assert_eq!(decoded.input_token, Some(address(2)));
+        let decoded = simulating.pop_fresh(2_000).await.unwrap();
+        assert_eq!(decoded.pending.tx.hash, H256::repeat_byte(3));
+        assert_eq!(decoded.selector, Some(selector));
+        assert!(decoded.input_token.is_none());
+        assert!(simulating.pop_fresh(2_000).await.is_none());
+    }
+}
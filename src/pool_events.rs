@@ -0,0 +1,92 @@
+// src/pool_events.rs
+use ethers::{
+    contract::{parse_log, EthEvent},
+    types::{Address, Log, H256, U256},
+};
+
+// UniswapV2-style pair events shared by the indexer and the drift monitor
+
+#[derive(Clone, Debug, EthEvent)]
+#[ethevent(name = "Swap", abi = "Swap(address,uint256,uint256,uint256,uint256,address)")]
+pub struct V2SwapEvent {
+    #[ethevent(indexed)]
+    pub sender: Address,
+    pub amount_0_in: U256,
+    pub amount_1_in: U256,
+    pub amount_0_out: U256,
+    pub amount_1_out: U256,
+    #[ethevent(indexed)]
+    pub to: Address,
+}
+
+#[derive(Clone, Debug, EthEvent)]
+#[ethevent(name = "Sync", abi = "Sync(uint112,uint112)")]
+pub struct V2SyncEvent {
+    pub reserve_0: u128,
+    pub reserve_1: u128,
+}
+
+#[derive(Clone, Debug, EthEvent)]
+#[ethevent(name = "Mint", abi = "Mint(address,uint256,uint256)")]
+pub struct V2MintEvent {
+    #[ethevent(indexed)]
+    pub sender: Address,
+    pub amount_0: U256,
+    pub amount_1: U256,
+}
+
+#[derive(Clone, Debug, EthEvent)]
+#[ethevent(name = "Burn", abi = "Burn(address,uint256,uint256,address)")]
+pub struct V2BurnEvent {
+    #[ethevent(indexed)]
+    pub sender: Address,
+    pub amount_0: U256,
+    pub amount_1: U256,
+    #[ethevent(indexed)]
+    pub to: Address,
+}
+
+#[derive(Clone, Debug)]
+pub enum PoolEvent {
+    Swap(V2SwapEvent),
+    Sync(V2SyncEvent),
+    Mint(V2MintEvent),
+    Burn(V2BurnEvent),
+}
+
+impl PoolEvent {
+    pub fn kind(&self) -> &'static str {
+        match self {
+            PoolEvent::Swap(_) => "swap",
+            PoolEvent::Sync(_) => "sync",
+            PoolEvent::Mint(_) => "mint",
+            PoolEvent::Burn(_) => "burn",
+        }
+    }
+}
+
+// topic0 of every event the indexer subscribes to
+pub fn event_signatures() -> Vec<H256> {
+    vec![
+        V2SwapEvent::signature(),
+        V2SyncEvent::signature(),
+        V2MintEvent::signature(),
+        V2BurnEvent::signature(),
+    ]
+}
+
+pub fn decode_pool_event(log: &Log) -> Option<PoolEvent> {
+    let topic0 = *log.topics.first()?;
+
+    if topic0 == V2SwapEvent::signature() {
+        parse_log::<V2SwapEvent>(log.clone()).ok().map(PoolEvent::Swap)
+    } else if topic0 == V2SyncEvent::signature() {
+        parse_log::<V2SyncEvent>(log.clone()).ok().map(PoolEvent::Sync)
+    } else if topic0 == V2MintEvent::signature() {
+        parse_log::<V2MintEvent>(log.clone()).ok().map(PoolEvent::Mint)
+    } else if topic0 == V2BurnEvent::signature() {
+        parse_log::<V2BurnEvent>(log.clone()).ok().map(PoolEvent::Burn)
+    } else {
+        None
+    }
+}
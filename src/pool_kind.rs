@@ -0,0 +1,391 @@
+// src/pool_kind.rs
+use anyhow::{bail, Result};
+use ethers::{
+    abi::{self, ParamType, Token},
+    providers::Middleware,
+    types::{transaction::eip2718::TypedTransaction, Address, BlockId, Bytes, TransactionRequest, U256},
+    utils::id,
+};
+use log::debug;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use crate::amm_math::{get_amount_out, FeeSettings};
+use crate::metrics::POOL_KINDS_DETECTED_TOTAL;
+use crate::v3_state::{fetch_pool_state, V3PoolKind, V3Settings};
+
+const WORD: usize = 32;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PoolKind {
+    UniswapV2,
+    UniswapV3,
+    Algebra,
+    Curve,
+    Balancer,
+}
+
+impl PoolKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PoolKind::UniswapV2 => "uniswap_v2",
+            PoolKind::UniswapV3 => "uniswap_v3",
+            PoolKind::Algebra => "algebra",
+            PoolKind::Curve => "curve",
+            PoolKind::Balancer => "balancer",
+        }
+    }
+
+    fn v3_kind(&self) -> Option<V3PoolKind> {
+        match self {
+            PoolKind::UniswapV3 => Some(V3PoolKind::UniswapV3),
+            PoolKind::Algebra => Some(V3PoolKind::Algebra),
+            _ => None,
+        }
+    }
+}
+
+// Pools met in calldata without coming from a known factory (aggregator hops mostly) are
+// classified by probing their interface, then quoted with the matching backend
+#[derive(Debug, Clone, Deserialize)]
+pub struct PoolDetectionSettings {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    // Balancer pools are quoted through the vault holding their balances
+    #[serde(default = "default_balancer_vault")]
+    pub balancer_vault: Address,
+    // Curve coin indexes tried when looking up a token
+    #[serde(default = "default_curve_max_coins")]
+    pub curve_max_coins: u64,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_balancer_vault() -> Address {
+    "0xBA12222222228d8Ba445958a75a0704d566BF2C8".parse().unwrap()
+}
+
+fn default_curve_max_coins() -> u64 {
+    8
+}
+
+impl Default for PoolDetectionSettings {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            balancer_vault: default_balancer_vault(),
+            curve_max_coins: default_curve_max_coins(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PoolQuote {
+    pub kind: PoolKind,
+    pub amount_out: U256,
+}
+
+#[derive(Debug)]
+pub struct PoolClassifier<M> {
+    provider: Arc<M>,
+    settings: PoolDetectionSettings,
+    fees: FeeSettings,
+    v3: V3Settings,
+    // A pool's type never changes; addresses that matched nothing are remembered as None
+    kinds: RwLock<HashMap<Address, Option<PoolKind>>>,
+}
+
+impl<M: Middleware + 'static> PoolClassifier<M> {
+    pub fn new(provider: Arc<M>, settings: PoolDetectionSettings, fees: FeeSettings, v3: V3Settings) -> Self {
+        Self {
+            provider,
+            settings,
+            fees,
+            v3,
+            kinds: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub async fn classify(&self, pool: Address, block: BlockId) -> Option<PoolKind> {
+        if !self.settings.enabled {
+            return None;
+        }
+        if let Some(kind) = self.kinds.read().unwrap().get(&pool) {
+            return *kind;
+        }
+
+        let kind = self.probe(pool, block).await;
+        POOL_KINDS_DETECTED_TOTAL
+            .with_label_values(&[kind.map_or("unknown", |kind| kind.as_str())])
+            .inc();
+        debug!("Pool {:?} detected as {}", pool, kind.map_or("unknown", |kind| kind.as_str()));
+        self.kinds.write().unwrap().insert(pool, kind);
+        kind
+    }
+
+    // Cheapest and most common interfaces first. Each probe checks the exact shape of the
+    // answer, so a contract with a permissive fallback does not pass for a pool
+    async fn probe(&self, pool: Address, block: BlockId) -> Option<PoolKind> {
+        // getReserves() -> (uint112, uint112, uint32)
+        if self.read(pool, "getReserves()", &[], block).await.is_some_and(|out| out.len() == 3 * WORD) {
+            return Some(PoolKind::UniswapV2);
+        }
+        // Both price slots pack seven words; Algebra has no slot0 and Uniswap no globalState
+        if self.read(pool, "globalState()", &[], block).await.is_some_and(|out| out.len() == 7 * WORD) {
+            return Some(PoolKind::Algebra);
+        }
+        if self.read(pool, "slot0()", &[], block).await.is_some_and(|out| out.len() == 7 * WORD) {
+            return Some(PoolKind::UniswapV3);
+        }
+        // A Balancer pool id starts with the pool's own address
+        if let Some(out) = self.read(pool, "getPoolId()", &[], block).await {
+            if out.len() == WORD && out[..20] == pool[..] {
+                return Some(PoolKind::Balancer);
+            }
+        }
+        self.curve_coin(pool, 0, block).await.map(|_| PoolKind::Curve)
+    }
+
+    // Output of `token_in` -> `token_out` through `pool` at `block`; None when the pool
+    // type is unknown or the pool does not trade the pair
+    pub async fn quote(
+        &self,
+        pool: Address,
+        token_in: Address,
+        token_out: Address,
+        amount_in: U256,
+        block_number: u64,
+    ) -> Result<Option<PoolQuote>> {
+        let block = BlockId::Number(block_number.into());
+        let Some(kind) = self.classify(pool, block).await else {
+            return Ok(None);
+        };
+        let amount_out = match kind {
+            PoolKind::UniswapV2 => self.quote_v2(pool, token_in, token_out, amount_in, block).await?,
+            PoolKind::UniswapV3 | PoolKind::Algebra => {
+                let v3_kind = kind.v3_kind().unwrap_or_default();
+                let state = fetch_pool_state(
+                    self.provider.clone(),
+                    pool,
+                    v3_kind,
+                    self.v3.bitmap_words,
+                    block,
+                    block_number,
+                )
+                .await?;
+                let token_out_matches = [state.token0, state.token1].contains(&token_out) && token_in != token_out;
+                state
+                    .quote(token_in, amount_in, self.v3.max_ticks_crossed)
+                    .filter(|quote| token_out_matches && quote.exhausted.is_none())
+                    .map(|quote| quote.amount_out)
+            }
+            PoolKind::Curve => self.quote_curve(pool, token_in, token_out, amount_in, block).await,
+            PoolKind::Balancer => self.quote_balancer(pool, token_in, token_out, amount_in, block).await?,
+        };
+        Ok(amount_out.map(|amount_out| PoolQuote { kind, amount_out }))
+    }
+
+    async fn quote_v2(
+        &self,
+        pool: Address,
+        token_in: Address,
+        token_out: Address,
+        amount_in: U256,
+        block: BlockId,
+    ) -> Result<Option<U256>> {
+        let (Some(token0), Some(token1)) = (
+            self.read_address(pool, "token0()", &[], block).await,
+            self.read_address(pool, "token1()", &[], block).await,
+        ) else {
+            bail!("V2 pool {:?} has no token pair", pool);
+        };
+        let Some(reserves) = self.read(pool, "getReserves()", &[], block).await.filter(|out| out.len() == 3 * WORD) else {
+            bail!("V2 pool {:?} reserves read failed", pool);
+        };
+        let reserve0 = U256::from_big_endian(&reserves[..WORD]);
+        let reserve1 = U256::from_big_endian(&reserves[WORD..2 * WORD]);
+        let (reserve_in, reserve_out) = match (token_in, token_out) {
+            (a, b) if a == token0 && b == token1 => (reserve0, reserve1),
+            (a, b) if a == token1 && b == token0 => (reserve1, reserve0),
+            _ => return Ok(None),
+        };
+        let fee = self.fees.fee(&pool, None);
+        Ok(Some(get_amount_out(amount_in, reserve_in, reserve_out, fee)))
+    }
+
+    async fn quote_curve(
+        &self,
+        pool: Address,
+        token_in: Address,
+        token_out: Address,
+        amount_in: U256,
+        block: BlockId,
+    ) -> Option<U256> {
+        let (mut i, mut j) = (None, None);
+        for index in 0..self.settings.curve_max_coins {
+            let Some(coin) = self.curve_coin(pool, index, block).await else {
+                break;
+            };
+            if coin == token_in {
+                i = Some(index);
+            } else if coin == token_out {
+                j = Some(index);
+            }
+            if i.is_some() && j.is_some() {
+                break;
+            }
+        }
+        let args = [Token::Int(i?.into()), Token::Int(j?.into()), Token::Uint(amount_in)];
+        // Stable pools take int128 indexes, crypto pools uint256
+        for signature in ["get_dy(int128,int128,uint256)", "get_dy(uint256,uint256,uint256)"] {
+            if let Some(out) = self.read(pool, signature, &args, block).await.filter(|out| out.len() == WORD) {
+                return Some(U256::from_big_endian(&out));
+            }
+        }
+        None
+    }
+
+    // Older Curve pools index coins with int128, newer ones with uint256
+    async fn curve_coin(&self, pool: Address, index: u64, block: BlockId) -> Option<Address> {
+        let args = [Token::Uint(index.into())];
+        match self.read_address(pool, "coins(uint256)", &args, block).await {
+            Some(coin) => Some(coin),
+            None => self.read_address(pool, "coins(int128)", &args, block).await,
+        }
+    }
+
+    // A single GIVEN_IN swap through Vault.queryBatchSwap, simulated with eth_call
+    async fn quote_balancer(
+        &self,
+        pool: Address,
+        token_in: Address,
+        token_out: Address,
+        amount_in: U256,
+        block: BlockId,
+    ) -> Result<Option<U256>> {
+        let Some(pool_id) = self.read(pool, "getPoolId()", &[], block).await.filter(|out| out.len() == WORD) else {
+            bail!("Balancer pool {:?} id read failed", pool);
+        };
+        let swap = Token::Tuple(vec![
+            Token::FixedBytes(pool_id.to_vec()),
+            Token::Uint(U256::zero()),
+            Token::Uint(U256::one()),
+            Token::Uint(amount_in),
+            Token::Bytes(Vec::new()),
+        ]);
+        let funds = Token::Tuple(vec![
+            Token::Address(Address::zero()),
+            Token::Bool(false),
+            Token::Address(Address::zero()),
+            Token::Bool(false),
+        ]);
+        let args = [
+            Token::Uint(U256::zero()),
+            Token::Array(vec![swap]),
+            Token::Array(vec![Token::Address(token_in), Token::Address(token_out)]),
+            funds,
+        ];
+        let signature = "queryBatchSwap(uint8,(bytes32,uint256,uint256,uint256,bytes)[],address[],(address,bool,address,bool))";
+        let Some(out) = self.read(self.settings.balancer_vault, signature, &args, block).await else {
+            // The vault reverts for tokens the pool does not hold
+            return Ok(None);
+        };
+        // Vault deltas: what it receives is positive, what it pays out negative
+        let deltas = abi::decode(&[ParamType::Array(Box::new(ParamType::Int(256)))], &out)?;
+        let delta_out = deltas
+            .into_iter()
+            .next()
+            .and_then(Token::into_array)
+            .and_then(|deltas| deltas.get(1).cloned())
+            .and_then(Token::into_int);
+        Ok(delta_out
+            .map(|delta| (!delta).overflowing_add(U256::one()).0)
+            .filter(|amount| !amount.is_zero() && amount.bits() < 256))
+    }
+
+    async fn read_address(&self, to: Address, signature: &str, args: &[Token], block: BlockId) -> Option<Address> {
+        let out = self.read(to, signature, args, block).await?;
+        if out.len() != WORD || out[..12].iter().any(|byte| *byte != 0) {
+            return None;
+        }
+        Some(Address::from_slice(&out[12..])).filter(|address| !address.is_zero())
+    }
+
+    // Reverts, missing methods and transport errors alike read as "no answer"
+    async fn read(&self, to: Address, signature: &str, args: &[Token], block: BlockId) -> Option<Bytes> {
+        let data = [&id(signature)[..], &abi::encode(args)].concat();
+        let call: TypedTransaction = TransactionRequest::new().to(to).data(data).into();
+        self.provider.call(&call, Some(block)).await.ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{address, call_output, reserves_output, MockChain};
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_pools_are_classified_once_and_quoted_by_their_backend() {
+        let chain = MockChain::new();
+        let classifier = PoolClassifier::new(
+            chain.provider(),
+            PoolDetectionSettings::default(),
+            FeeSettings::default(),
+            V3Settings::default(),
+        );
+        let (v2, balancer, wallet) = (address(100), address(200), address(300));
+        let (token0, token1) = (address(1), address(2));
+        let mut pool_id = [0u8; 32];
+        pool_id[..20].copy_from_slice(balancer.as_bytes());
+        let empty = json!("0x");
+        chain.script(vec![
+            // V2: answers getReserves, then the quote reads its tokens and reserves
+            reserves_output(1_000_000, 2_000_000),
+            call_output(&[Token::Address(token0)]),
+            call_output(&[Token::Address(token1)]),
+            reserves_output(1_000_000, 2_000_000),
+            // Balancer: no reserves nor price slots, a pool id with its own address
+            empty.clone(),
+            empty.clone(),
+            empty.clone(),
+            call_output(&[Token::FixedBytes(pool_id.to_vec())]),
+            // A wallet answers nothing
+            empty.clone(),
+            empty.clone(),
+            empty.clone(),
+            empty.clone(),
+            empty.clone(),
+            empty,
+        ]);
+
+        let quote = classifier
+            .quote(v2, token0, token1, U256::from(10_000u64), 1)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(quote.kind, PoolKind::UniswapV2);
+        assert_eq!(
+            quote.amount_out,
+            get_amount_out(
+                10_000u64.into(),
+                1_000_000u64.into(),
+                2_000_000u64.into(),
+                FeeSettings::default().fee(&v2, None)
+            )
+        );
+
+        let block = BlockId::Number(1u64.into());
+        assert_eq!(classifier.classify(balancer, block).await, Some(PoolKind::Balancer));
+        assert_eq!(classifier.classify(wallet, block).await, None);
+
+        // Cached: the script is spent, so these would fail if probed again
+        assert_eq!(classifier.classify(v2, block).await, Some(PoolKind::UniswapV2));
+        assert_eq!(classifier.classify(balancer, block).await, Some(PoolKind::Balancer));
+        assert!(classifier.quote(wallet, token0, token1, U256::one(), 1).await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,250 @@
+// src/post_mortem.rs
+use anyhow::{anyhow, Result};
+use ethers::{
+    providers::Middleware,
+    types::{Address, Filter, H256},
+    utils::keccak256,
+};
+use log::info;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::sync::Arc;
+
+use crate::metrics::BUNDLE_MISSES_TOTAL;
+
+const TRANSFER: &str = "Transfer(address,address,uint256)";
+const V2_SWAP: &str = "Swap(address,uint256,uint256,uint256,uint256,address)";
+// Uniswap V3 and Algebra pools emit the same Swap
+const V3_SWAP: &str = "Swap(address,address,int256,int256,uint160,uint128,int24)";
+
+// Inspects the target blocks of a bundle that was not included and journals why: the
+// victim never landed, a competitor took the spread, or the spread was gone regardless
+#[derive(Debug, Clone, Deserialize)]
+pub struct PostMortemSettings {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl Default for PostMortemSettings {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MissReason {
+    // The backrun target was not mined within the targeted blocks, so the spread never opened
+    VictimDropped,
+    // Another tx swapped through every token of the route in the same blocks
+    Outbid,
+    // Nobody traded the route; the spread closed or never was what the simulation saw
+    StaleSpread,
+}
+
+impl MissReason {
+    // Doubles as the journal status of the opportunity
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            MissReason::VictimDropped => "victim_dropped",
+            MissReason::Outbid => "outbid",
+            MissReason::StaleSpread => "stale_spread",
+        }
+    }
+}
+
+// Error returned for a bundle that missed its blocks, carrying the classified reason
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MissedBundle {
+    pub first_block: u64,
+    pub last_block: u64,
+    pub reason: MissReason,
+    pub victim_block: Option<u64>,
+    pub competitors: Vec<H256>,
+}
+
+impl fmt::Display for MissedBundle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Bundle for blocks {}..={} not included: ", self.first_block, self.last_block)?;
+        match self.reason {
+            MissReason::VictimDropped => match self.victim_block {
+                Some(block) => write!(f, "victim landed in block {}", block),
+                None => write!(f, "victim was not mined"),
+            },
+            MissReason::Outbid => write!(f, "outbid by {:?}", self.competitors),
+            MissReason::StaleSpread => write!(f, "stale spread, no competing trade"),
+        }
+    }
+}
+
+impl std::error::Error for MissedBundle {}
+
+pub struct BundlePostMortem<M> {
+    provider: Arc<M>,
+    settings: PostMortemSettings,
+}
+
+impl<M: Middleware + 'static> BundlePostMortem<M> {
+    pub fn new(provider: Arc<M>, settings: PostMortemSettings) -> Self {
+        Self { provider, settings }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.settings.enabled
+    }
+
+    // Why none of `ours`, offered for blocks `first..=last`, was included in a trade along
+    // `path`; `victim` is the pending tx the trade backran, if any
+    pub async fn analyze(
+        &self,
+        path: &[Address],
+        victim: Option<H256>,
+        ours: &[H256],
+        first: u64,
+        last: u64,
+    ) -> Result<MissedBundle> {
+        let mut missed = MissedBundle {
+            first_block: first,
+            last_block: last,
+            reason: MissReason::StaleSpread,
+            victim_block: None,
+            competitors: Vec::new(),
+        };
+        let (mut from, mut to) = (first, last);
+        if let Some(victim) = victim {
+            let receipt = self
+                .provider
+                .get_transaction_receipt(victim)
+                .await
+                .map_err(|e| anyhow!("Receipt lookup of victim {:?} failed: {e}", victim))?;
+            missed.victim_block = receipt.and_then(|receipt| receipt.block_number).map(|number| number.as_u64());
+            match missed.victim_block {
+                // A competitor backrunning it sits in the same block
+                Some(block) if (first..=last).contains(&block) => (from, to) = (block, block),
+                _ => missed.reason = MissReason::VictimDropped,
+            }
+        }
+
+        if missed.reason != MissReason::VictimDropped {
+            let mut excluded: HashSet<H256> = ours.iter().copied().collect();
+            excluded.extend(victim);
+            missed.competitors = self.competitors(path, &excluded, from, to).await?;
+            if !missed.competitors.is_empty() {
+                missed.reason = MissReason::Outbid;
+            }
+        }
+        BUNDLE_MISSES_TOTAL.with_label_values(&[missed.reason.as_str()]).inc();
+        info!("{}", missed);
+        Ok(missed)
+    }
+
+    // Txs in `from..=to` that swapped at least twice and moved every token of `path`: an
+    // arbitrage over the same route
+    async fn competitors(&self, path: &[Address], excluded: &HashSet<H256>, from: u64, to: u64) -> Result<Vec<H256>> {
+        let tokens: HashSet<Address> = path.iter().copied().collect();
+        let transfers = Filter::new()
+            .address(tokens.iter().copied().collect::<Vec<_>>())
+            .topic0(H256(keccak256(TRANSFER)))
+            .from_block(from)
+            .to_block(to);
+        let swaps = Filter::new()
+            .topic0(vec![H256(keccak256(V2_SWAP)), H256(keccak256(V3_SWAP))])
+            .from_block(from)
+            .to_block(to);
+
+        let mut moved: HashMap<H256, HashSet<Address>> = HashMap::new();
+        for log in self
+            .provider
+            .get_logs(&transfers)
+            .await
+            .map_err(|e| anyhow!("Transfer log query {}..={} failed: {e}", from, to))?
+        {
+            if let Some(tx) = log.transaction_hash.filter(|tx| !excluded.contains(tx)) {
+                moved.entry(tx).or_default().insert(log.address);
+            }
+        }
+        let mut swapped: HashMap<H256, usize> = HashMap::new();
+        for log in self
+            .provider
+            .get_logs(&swaps)
+            .await
+            .map_err(|e| anyhow!("Swap log query {}..={} failed: {e}", from, to))?
+        {
+            if let Some(tx) = log.transaction_hash.filter(|tx| moved.get(tx).is_some_and(|moved| *moved == tokens)) {
+                *swapped.entry(tx).or_default() += 1;
+            }
+        }
+
+        let mut competitors: Vec<H256> =
+            swapped.into_iter().filter(|&(_, swaps)| swaps >= 2).map(|(tx, _)| tx).collect();
+        competitors.sort();
+        Ok(competitors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{address, MockChain};
+    use ethers::types::{Log, TransactionReceipt};
+    use serde_json::{json, Value};
+
+    fn receipt(block: u64) -> Value {
+        serde_json::to_value(TransactionReceipt {
+            block_number: Some(block.into()),
+            ..Default::default()
+        })
+        .unwrap()
+    }
+
+    fn logs(entries: &[(Address, H256)]) -> Value {
+        let logs: Vec<Log> = entries
+            .iter()
+            .map(|&(address, tx)| Log {
+                address,
+                transaction_hash: Some(tx),
+                ..Default::default()
+            })
+            .collect();
+        serde_json::to_value(logs).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_misses_are_classified_from_the_target_blocks() {
+        let chain = MockChain::new();
+        let analyzer = BundlePostMortem::new(chain.provider(), PostMortemSettings::default());
+        let (wmatic, usdc) = (address(1), address(2));
+        let path = [wmatic, usdc, wmatic];
+        let (victim, ours) = (H256::repeat_byte(0xaa), H256::repeat_byte(0xbb));
+        let (rival, bystander) = (H256::repeat_byte(0xcc), H256::repeat_byte(0xdd));
+
+        // Victim never mined
+        chain.script(vec![json!(null)]);
+        let missed = analyzer.analyze(&path, Some(victim), &[ours], 100, 102).await.unwrap();
+        assert_eq!(missed.reason, MissReason::VictimDropped);
+
+        // Victim in block 101, where a rival cycled both tokens through two pools; the
+        // bystander only swapped once and our own tx does not count
+        chain.script(vec![
+            receipt(101),
+            logs(&[(wmatic, rival), (usdc, rival), (usdc, bystander), (wmatic, bystander), (wmatic, ours), (usdc, ours)]),
+            logs(&[(address(10), rival), (address(11), rival), (address(10), bystander), (address(10), ours), (address(11), ours)]),
+        ]);
+        let missed = analyzer.analyze(&path, Some(victim), &[ours], 100, 102).await.unwrap();
+        assert_eq!(missed.reason, MissReason::Outbid);
+        assert_eq!(missed.victim_block, Some(101));
+        assert_eq!(missed.competitors, vec![rival]);
+
+        // No victim to check and nobody else traded the route
+        chain.script(vec![logs(&[(wmatic, bystander)]), logs(&[(address(10), bystander), (address(11), bystander)])]);
+        let missed = analyzer.analyze(&path, None, &[ours], 100, 102).await.unwrap();
+        assert_eq!(missed.reason, MissReason::StaleSpread);
+        assert!(missed.competitors.is_empty());
+    }
+}
@@ -0,0 +1,289 @@
+// src/prefetch.rs
+use anyhow::{anyhow, Result};
+use ethers::{
+    contract::abigen,
+    providers::Middleware,
+    types::{Address, BlockId, U256},
+};
+use futures::{stream, StreamExt};
+use log::{info, warn};
+use serde::Deserialize;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::reserve_cache::ReserveCache;
+use crate::token_registry::TokenRegistry;
+use crate::bindings::IUniswapV2Pair;
+
+abigen!(
+    IUniswapV2Factory,
+    r#"[
+        function getPair(address tokenA, address tokenB) external view returns (address pair)
+    ]"#
+);
+
+// Progress is logged each time this share of the lookups completes
+const PROGRESS_STEP_PERCENT: usize = 10;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PrefetchSettings {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    // RPC lookups in flight at once
+    #[serde(default = "default_concurrency")]
+    pub concurrency: usize,
+    // Token universe; empty means every token in tokens.json
+    #[serde(default)]
+    pub tokens: Vec<Address>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_concurrency() -> usize {
+    16
+}
+
+impl Default for PrefetchSettings {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            concurrency: default_concurrency(),
+            tokens: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PrefetchedPair {
+    pub pair: Address,
+    pub factory: Address,
+    pub token0: Address,
+    pub token1: Address,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct PrefetchReport {
+    pub pairs: Vec<PrefetchedPair>,
+    pub lookups: usize,
+    // Lookups that errored; those pairs are left to regular discovery
+    pub failed: usize,
+    pub elapsed: Duration,
+}
+
+// Completed/total lookups, reporting a line each time another step is crossed
+#[derive(Debug)]
+pub struct Progress {
+    total: usize,
+    done: usize,
+    reported_step: usize,
+}
+
+impl Progress {
+    pub fn new(total: usize) -> Self {
+        Self {
+            total,
+            done: 0,
+            reported_step: 0,
+        }
+    }
+
+    pub fn percent(&self) -> usize {
+        (self.done * 100).checked_div(self.total).unwrap_or(100)
+    }
+
+    // Some(percent) when this lookup crossed into a new step
+    pub fn advance(&mut self) -> Option<usize> {
+        self.done += 1;
+        let step = self.percent() / PROGRESS_STEP_PERCENT;
+        if step > self.reported_step {
+            self.reported_step = step;
+            return Some(step * PROGRESS_STEP_PERCENT);
+        }
+        None
+    }
+}
+
+// Startup warm-up: token metadata, then every factory pair between tokens of the universe
+// and its reserves, looked up in parallel so the first block already has a full pool graph
+pub struct PairPrefetcher<M> {
+    provider: Arc<M>,
+    settings: PrefetchSettings,
+}
+
+impl<M: Middleware + 'static> PairPrefetcher<M> {
+    pub fn new(provider: Arc<M>, settings: PrefetchSettings) -> Self {
+        Self { provider, settings }
+    }
+
+    pub fn universe(&self, tokens: &TokenRegistry) -> Vec<Address> {
+        let mut universe = if self.settings.tokens.is_empty() {
+            tokens.addresses()
+        } else {
+            self.settings.tokens.clone()
+        };
+        universe.sort();
+        universe.dedup();
+        universe
+    }
+
+    pub async fn run(
+        &self,
+        factories: &[Address],
+        tokens: &TokenRegistry,
+        cache: &ReserveCache,
+        block_number: u64,
+    ) -> Result<PrefetchReport> {
+        let started = Instant::now();
+        let universe = self.universe(tokens);
+        // Decimals come from tokens.json or one multicall for the rest
+        if let Err(e) = tokens.resolve(self.provider.clone(), &universe).await {
+            warn!("Token metadata prefetch failed: {:?}", e);
+        }
+
+        // Sorted universe, so each candidate is already in token0 < token1 order
+        let mut candidates = Vec::new();
+        for &factory in factories {
+            for (i, &token0) in universe.iter().enumerate() {
+                candidates.extend(universe[i + 1..].iter().map(|&token1| (factory, token0, token1)));
+            }
+        }
+        info!(
+            "Prefetching pairs of {} tokens on {} factories ({} lookups, {} in flight)",
+            universe.len(),
+            factories.len(),
+            candidates.len(),
+            self.settings.concurrency
+        );
+
+        let block = BlockId::Number(block_number.into());
+        let mut progress = Progress::new(candidates.len());
+        let mut report = PrefetchReport {
+            lookups: candidates.len(),
+            ..Default::default()
+        };
+        let mut lookups = stream::iter(candidates)
+            .map(|(factory, token0, token1)| self.lookup(factory, token0, token1, block))
+            .buffer_unordered(self.settings.concurrency.max(1));
+
+        while let Some(result) = lookups.next().await {
+            match result {
+                Ok(Some((pair, reserve0, reserve1))) => {
+                    cache.update(pair.pair, reserve0, reserve1, block_number);
+                    report.pairs.push(pair);
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    report.failed += 1;
+                    warn!("Pair prefetch lookup failed: {:?}", e);
+                }
+            }
+            if let Some(percent) = progress.advance() {
+                info!(
+                    "Prefetch {}% ({}/{}), {} pairs found, {:.1}s",
+                    percent,
+                    progress.done,
+                    progress.total,
+                    report.pairs.len(),
+                    started.elapsed().as_secs_f64()
+                );
+            }
+        }
+
+        report.elapsed = started.elapsed();
+        Ok(report)
+    }
+
+    // The factory's pair for two tokens with its reserves, None when there is none
+    async fn lookup(
+        &self,
+        factory: Address,
+        token0: Address,
+        token1: Address,
+        block: BlockId,
+    ) -> Result<Option<(PrefetchedPair, U256, U256)>> {
+        let pair = IUniswapV2Factory::new(factory, self.provider.clone())
+            .get_pair(token0, token1)
+            .block(block)
+            .call()
+            .await
+            .map_err(|e| anyhow!("getPair on {:?} failed: {e}", factory))?;
+        if pair.is_zero() {
+            return Ok(None);
+        }
+
+        let (reserve0, reserve1, _) = IUniswapV2Pair::new(pair, self.provider.clone())
+            .get_reserves()
+            .block(block)
+            .call()
+            .await
+            .map_err(|e| anyhow!("getReserves on {:?} failed: {e}", pair))?;
+        Ok(Some((
+            PrefetchedPair {
+                pair,
+                factory,
+                token0,
+                token1,
+            },
+            reserve0.into(),
+            reserve1.into(),
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{address, call_output, reserves_output, MockChain};
+    use crate::token_registry::TokenInfo;
+    use ethers::abi::Token;
+
+    #[tokio::test]
+    async fn test_prefetch_finds_pairs_and_warms_reserves() {
+        let (wmatic, usdc, dai, factory) = (address(1), address(2), address(3), address(0xfac));
+        let tokens = TokenRegistry::new();
+        for (token, symbol) in [(wmatic, "WMATIC"), (usdc, "USDC"), (dai, "DAI")] {
+            tokens
+                .insert(TokenInfo {
+                    address: token,
+                    name: symbol.to_string(),
+                    symbol: symbol.to_string(),
+                    decimals: 18,
+                    tags: Vec::new(),
+                })
+                .unwrap();
+        }
+
+        // One lookup in flight keeps the scripted responses in candidate order
+        let chain = MockChain::new();
+        chain.script(vec![
+            call_output(&[Token::Address(address(0x12))]),
+            reserves_output(100, 200),
+            call_output(&[Token::Address(Address::zero())]),
+            call_output(&[Token::Address(address(0x23))]),
+            reserves_output(300, 400),
+        ]);
+        let settings = PrefetchSettings {
+            concurrency: 1,
+            ..Default::default()
+        };
+        let cache = ReserveCache::new();
+        let report = PairPrefetcher::new(chain.provider(), settings)
+            .run(&[factory], &tokens, &cache, 42)
+            .await
+            .unwrap();
+
+        assert_eq!(report.lookups, 3);
+        assert_eq!(report.failed, 0);
+        let found: Vec<(Address, Address, Address)> =
+            report.pairs.iter().map(|pair| (pair.pair, pair.token0, pair.token1)).collect();
+        assert_eq!(found, vec![(address(0x12), wmatic, usdc), (address(0x23), usdc, dai)]);
+        let reserves = cache.get(&address(0x23)).unwrap();
+        assert_eq!((reserves.reserve0, reserves.block_number), (U256::from(300u64), 42));
+
+        let mut progress = Progress::new(4);
+        let steps: Vec<Option<usize>> = (0..4).map(|_| progress.advance()).collect();
+        assert_eq!(steps, vec![Some(20), Some(50), Some(70), Some(100)]);
+    }
+}
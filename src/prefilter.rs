@@ -0,0 +1,179 @@
+// src/prefilter.rs
+use ethers::{
+    types::{Address, Transaction, U256},
+    utils::id,
+};
+use serde::Deserialize;
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use crate::metrics::PREFILTER_TOTAL;
+use crate::routers::{quickswap::QUICKSWAP_ROUTER, sushiswap::SUSHISWAP_ROUTER, uniswap_v3::UNISWAP_V3_ROUTER};
+
+// Uniswap V3 SwapRouter02, same calls as the V3 router plus the V2 ones
+const SWAP_ROUTER_02: &str = "0x68b3465833fb72A70ecDF485E0e4C7bD8665Fc45";
+
+// Cheap checks on a pending tx's envelope before it is deduplicated, decoded and simulated;
+// most of the mempool is transfers, approvals and calls to contracts we don't price
+#[derive(Debug, Clone, Deserialize)]
+pub struct PrefilterSettings {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    // Txs to any other address are dropped; empty lets every destination through
+    #[serde(default = "default_routers")]
+    pub routers: Vec<Address>,
+    // Function signatures of the swaps worth simulating; empty lets every call through
+    #[serde(default = "default_selectors")]
+    pub selectors: Vec<String>,
+    // Below this gas limit a tx cannot get through a single pool swap
+    #[serde(default = "default_min_gas_limit")]
+    pub min_gas_limit: u64,
+    // Swaps sending more native MATIC than this are dropped; 0 disables the check
+    #[serde(default)]
+    pub max_value_matic: u64,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_routers() -> Vec<Address> {
+    [QUICKSWAP_ROUTER, SUSHISWAP_ROUTER, UNISWAP_V3_ROUTER, SWAP_ROUTER_02]
+        .iter()
+        .filter_map(|router| Address::from_str(router).ok())
+        .collect()
+}
+
+fn default_selectors() -> Vec<String> {
+    [
+        "swapExactTokensForTokens(uint256,uint256,address[],address,uint256)",
+        "swapTokensForExactTokens(uint256,uint256,address[],address,uint256)",
+        "swapExactETHForTokens(uint256,address[],address,uint256)",
+        "swapTokensForExactETH(uint256,uint256,address[],address,uint256)",
+        "swapExactTokensForETH(uint256,uint256,address[],address,uint256)",
+        "swapETHForExactTokens(uint256,address[],address,uint256)",
+        "swapExactTokensForTokensSupportingFeeOnTransferTokens(uint256,uint256,address[],address,uint256)",
+        "swapExactETHForTokensSupportingFeeOnTransferTokens(uint256,address[],address,uint256)",
+        "swapExactTokensForETHSupportingFeeOnTransferTokens(uint256,uint256,address[],address,uint256)",
+        "exactInputSingle((address,address,uint24,address,uint256,uint256,uint256,uint160))",
+        "exactInput((bytes,address,uint256,uint256,uint256))",
+        "exactOutputSingle((address,address,uint24,address,uint256,uint256,uint256,uint160))",
+        "exactOutput((bytes,address,uint256,uint256,uint256))",
+        "multicall(bytes[])",
+        "multicall(uint256,bytes[])",
+    ]
+    .iter()
+    .map(|signature| signature.to_string())
+    .collect()
+}
+
+fn default_min_gas_limit() -> u64 {
+    80_000
+}
+
+impl Default for PrefilterSettings {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            routers: default_routers(),
+            selectors: default_selectors(),
+            min_gas_limit: default_min_gas_limit(),
+            max_value_matic: 0,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TxPrefilter {
+    settings: PrefilterSettings,
+    routers: HashSet<Address>,
+    selectors: HashSet<[u8; 4]>,
+}
+
+impl TxPrefilter {
+    pub fn new(settings: PrefilterSettings) -> Self {
+        Self {
+            routers: settings.routers.iter().copied().collect(),
+            selectors: settings.selectors.iter().map(id).collect(),
+            settings,
+        }
+    }
+
+    // None when the tx may open a spread, else the check it failed
+    pub fn reject(&self, tx: &Transaction) -> Option<&'static str> {
+        if !self.settings.enabled {
+            return None;
+        }
+        let Some(to) = tx.to else {
+            return Some("contract_creation");
+        };
+        if !self.routers.is_empty() && !self.routers.contains(&to) {
+            return Some("not_router");
+        }
+        let Some(selector) = tx.input.get(..4).and_then(|selector| <[u8; 4]>::try_from(selector).ok()) else {
+            return Some("no_calldata");
+        };
+        if !self.selectors.is_empty() && !self.selectors.contains(&selector) {
+            return Some("not_swap");
+        }
+        if tx.gas < U256::from(self.settings.min_gas_limit) {
+            return Some("low_gas");
+        }
+        let max_value = U256::from(self.settings.max_value_matic) * U256::exp10(18);
+        if !max_value.is_zero() && tx.value > max_value {
+            return Some("high_value");
+        }
+        None
+    }
+
+    // Counts the verdict, so the metric gives the hit rate of each check
+    pub fn admit(&self, tx: &Transaction) -> bool {
+        let verdict = self.reject(tx);
+        PREFILTER_TOTAL.with_label_values(&[verdict.unwrap_or("passed")]).inc();
+        verdict.is_none()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{address, swap_tx};
+    use ethers::types::H256;
+
+    #[test]
+    fn test_envelope_checks() {
+        let filter = TxPrefilter::new(PrefilterSettings::default());
+        let router = Address::from_str(QUICKSWAP_ROUTER).unwrap();
+        let mut swap = swap_tx(H256::repeat_byte(1), router, U256::exp10(18), &[address(2), address(3)]);
+        swap.gas = 200_000u64.into();
+        assert_eq!(filter.reject(&swap), None);
+        assert!(filter.admit(&swap));
+
+        let elsewhere = Transaction { to: Some(address(9)), ..swap.clone() };
+        assert_eq!(filter.reject(&elsewhere), Some("not_router"));
+        let approve = Transaction {
+            input: id("approve(address,uint256)").to_vec().into(),
+            ..swap.clone()
+        };
+        assert_eq!(filter.reject(&approve), Some("not_swap"));
+        let transfer = Transaction { input: Default::default(), ..swap.clone() };
+        assert_eq!(filter.reject(&transfer), Some("no_calldata"));
+        let starved = Transaction { gas: 50_000u64.into(), ..swap.clone() };
+        assert_eq!(filter.reject(&starved), Some("low_gas"));
+
+        let capped = TxPrefilter::new(PrefilterSettings {
+            max_value_matic: 1,
+            ..Default::default()
+        });
+        let whale = Transaction { value: U256::exp10(20), ..swap.clone() };
+        assert_eq!(capped.reject(&whale), Some("high_value"));
+
+        let open = TxPrefilter::new(PrefilterSettings {
+            routers: Vec::new(),
+            selectors: Vec::new(),
+            ..Default::default()
+        });
+        assert_eq!(open.reject(&elsewhere), None);
+        assert_eq!(open.reject(&approve), None);
+    }
+}
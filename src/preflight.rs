@@ -0,0 +1,240 @@
+// src/preflight.rs
+use anyhow::{anyhow, Result};
+use ethers::{
+    providers::{call_raw::{spoof, RawCall}, Provider},
+    types::{transaction::eip2718::TypedTransaction, Address, BlockId, Bytes, H256, U256},
+    utils::keccak256,
+};
+use log::debug;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::rpc_usage::MeteredWs;
+
+// Storage slots of the ERC20 `balanceOf` / `allowance` mappings.
+// OpenZeppelin ERC20 uses 0 and 1; proxies such as bridged USDC differ.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct TokenStorageLayout {
+    #[serde(default = "default_balance_slot")]
+    pub balance_slot: u64,
+    #[serde(default = "default_allowance_slot")]
+    pub allowance_slot: u64,
+}
+
+fn default_balance_slot() -> u64 {
+    0
+}
+
+fn default_allowance_slot() -> u64 {
+    1
+}
+
+impl Default for TokenStorageLayout {
+    fn default() -> Self {
+        Self {
+            balance_slot: default_balance_slot(),
+            allowance_slot: default_allowance_slot(),
+        }
+    }
+}
+
+impl TokenStorageLayout {
+    // allowance[owner][spender]: keccak(spender . keccak(owner . allowance_slot))
+    pub fn allowance_of(&self, owner: Address, spender: Address) -> H256 {
+        let inner = mapping_slot(address_word(owner), u64_word(self.allowance_slot));
+        mapping_slot(address_word(spender), inner)
+    }
+
+    // balanceOf[owner]: keccak(owner . balance_slot)
+    pub fn balance_of(&self, owner: Address) -> H256 {
+        mapping_slot(address_word(owner), u64_word(self.balance_slot))
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct PreflightSettings {
+    // Tokens whose mappings are not at the default slots, by token address
+    #[serde(default)]
+    pub layouts: HashMap<Address, TokenStorageLayout>,
+}
+
+// Fake allowance granted to `spender` on `owner`'s tokens during the simulated call
+#[derive(Debug, Clone)]
+pub struct AllowanceOverride {
+    pub token: Address,
+    pub owner: Address,
+    pub spender: Address,
+}
+
+// Fake token balance for `owner` during the simulated call
+#[derive(Debug, Clone)]
+pub struct BalanceOverride {
+    pub token: Address,
+    pub owner: Address,
+    pub amount: U256,
+}
+
+#[derive(Debug, Clone)]
+pub struct PreflightSimulator {
+    provider: Arc<Provider<MeteredWs>>,
+    layouts: HashMap<Address, TokenStorageLayout>,
+}
+
+// keccak256(pad32(key) ++ pad32(slot)) -- Solidity mapping slot derivation
+pub(crate) fn mapping_slot(key: H256, slot: H256) -> H256 {
+    let mut buf = [0u8; 64];
+    buf[..32].copy_from_slice(key.as_bytes());
+    buf[32..].copy_from_slice(slot.as_bytes());
+    H256::from(keccak256(buf))
+}
+
+pub(crate) fn address_word(address: Address) -> H256 {
+    H256::from(address)
+}
+
+pub(crate) fn u64_word(value: u64) -> H256 {
+    H256::from_low_u64_be(value)
+}
+
+fn u256_word(value: U256) -> H256 {
+    let mut buf = [0u8; 32];
+    value.to_big_endian(&mut buf);
+    H256::from(buf)
+}
+
+impl PreflightSimulator {
+    pub fn new(provider: Arc<Provider<MeteredWs>>) -> Self {
+        Self {
+            provider,
+            layouts: HashMap::new(),
+        }
+    }
+
+    pub fn with_layout(mut self, token: Address, layout: TokenStorageLayout) -> Self {
+        self.layouts.insert(token, layout);
+        self
+    }
+
+    fn layout(&self, token: &Address) -> TokenStorageLayout {
+        self.layouts.get(token).copied().unwrap_or_default()
+    }
+
+    pub fn allowance_slot(&self, token: &Address, owner: Address, spender: Address) -> H256 {
+        self.layout(token).allowance_of(owner, spender)
+    }
+
+    pub fn balance_slot(&self, token: &Address, owner: Address) -> H256 {
+        self.layout(token).balance_of(owner)
+    }
+
+    pub fn build_state(
+        &self,
+        allowances: &[AllowanceOverride],
+        balances: &[BalanceOverride],
+    ) -> spoof::State {
+        let mut state = spoof::state();
+
+        for allowance in allowances {
+            let slot = self.allowance_slot(&allowance.token, allowance.owner, allowance.spender);
+            state
+                .account(allowance.token)
+                .store(slot, u256_word(U256::MAX));
+        }
+
+        for balance in balances {
+            let slot = self.balance_slot(&balance.token, balance.owner);
+            state
+                .account(balance.token)
+                .store(slot, u256_word(balance.amount));
+        }
+
+        state
+    }
+
+    // eth_call `tx` with faked allowances/balances; a revert surfaces as an error
+    pub async fn simulate(
+        &self,
+        tx: &TypedTransaction,
+        block: Option<BlockId>,
+        allowances: &[AllowanceOverride],
+        balances: &[BalanceOverride],
+    ) -> Result<Bytes> {
+        let state = self.build_state(allowances, balances);
+
+        let call = self.provider.call_raw(tx).state(&state);
+        let call = match block {
+            Some(block) => call.block(block),
+            None => call,
+        };
+
+        let output = call
+            .await
+            .map_err(|e| anyhow!("Preflight simulation reverted: {e}"))?;
+        debug!("Preflight simulation succeeded ({} bytes returned)", output.len());
+        Ok(output)
+    }
+
+    // Executor contract approving every router for every token on the route
+    pub async fn simulate_without_approvals(
+        &self,
+        tx: &TypedTransaction,
+        block: Option<BlockId>,
+        executor: Address,
+        tokens: &[Address],
+        routers: &[Address],
+    ) -> Result<Bytes> {
+        let allowances: Vec<AllowanceOverride> = tokens
+            .iter()
+            .flat_map(|&token| {
+                routers.iter().map(move |&spender| AllowanceOverride {
+                    token,
+                    owner: executor,
+                    spender,
+                })
+            })
+            .collect();
+
+        self.simulate(tx, block, &allowances, &[]).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::address;
+    use config::{Config, File, FileFormat};
+    use ethers::abi::{self, Token};
+
+    #[test]
+    fn test_override_slots_follow_the_tokens_layout() {
+        let (owner, spender) = (address(3), address(4));
+        let layout = TokenStorageLayout::default();
+
+        // Solidity's keccak256(abi.encode(key, slot)) for a mapping entry
+        let entry = |key: Token, slot: Token| H256::from(keccak256(abi::encode(&[key, slot])));
+        let balance = entry(Token::Address(owner), Token::Uint(U256::zero()));
+        let allowance = entry(
+            Token::Address(spender),
+            Token::FixedBytes(entry(Token::Address(owner), Token::Uint(U256::one())).as_bytes().to_vec()),
+        );
+        assert_eq!(layout.balance_of(owner), balance);
+        assert_eq!(layout.allowance_of(owner, spender), allowance);
+        // balanceOf(0x0) of an OpenZeppelin token, a well-known slot
+        let zero = "0xad3228b676f7d3cd4284a5443f17f1962b36e491b30a40b2405849e597ba5fb5".parse::<H256>().unwrap();
+        assert_eq!(layout.balance_of(Address::zero()), zero);
+
+        // A configured proxy keeps the default for the slot it doesn't override
+        let yaml = format!("layouts:\n  \"{:?}\":\n    balance_slot: 51\n", address(2));
+        let settings: PreflightSettings = Config::builder()
+            .add_source(File::from_str(&yaml, FileFormat::Yaml))
+            .build()
+            .unwrap()
+            .try_deserialize()
+            .unwrap();
+        let proxy = settings.layouts[&address(2)];
+        assert_eq!(proxy, TokenStorageLayout { balance_slot: 51, allowance_slot: 1 });
+        assert_eq!(proxy.balance_of(owner), entry(Token::Address(owner), Token::Uint(U256::from(51u64))));
+        assert_eq!(proxy.allowance_of(owner, spender), allowance);
+    }
+}
@@ -0,0 +1,126 @@
+// src/profit_threshold.rs
+use ethers::types::U256;
+use serde::Deserialize;
+
+use crate::callback_gas::{CallbackGasModel, FlashProvider};
+use crate::gas_oracle::{GasCost, GasSnapshot};
+use crate::store::CallbackGasRecord;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProfitThresholdSettings {
+    // Required profit as a multiple of the all-in execution cost
+    #[serde(default = "default_margin_multiplier")]
+    pub margin_multiplier: f64,
+    // Fixed transaction overhead (intrinsic gas, executor entry)
+    #[serde(default = "default_base_gas")]
+    pub base_gas: u64,
+    // Router swap gas per hop
+    #[serde(default = "default_gas_per_hop")]
+    pub gas_per_hop: u64,
+    // Flash-loan callback (approvals, transfers, repayment) per provider and route length
+    #[serde(default)]
+    pub callback_gas: CallbackGasModel,
+}
+
+fn default_margin_multiplier() -> f64 {
+    1.5
+}
+
+fn default_base_gas() -> u64 {
+    60_000
+}
+
+fn default_gas_per_hop() -> u64 {
+    75_000
+}
+
+impl Default for ProfitThresholdSettings {
+    fn default() -> Self {
+        Self {
+            margin_multiplier: default_margin_multiplier(),
+            base_gas: default_base_gas(),
+            gas_per_hop: default_gas_per_hop(),
+            callback_gas: CallbackGasModel::default(),
+        }
+    }
+}
+
+impl ProfitThresholdSettings {
+    // Gas outside the flash-loan callback: the transaction itself and the router swaps
+    pub fn swap_gas(&self, hops: usize) -> U256 {
+        U256::from(self.base_gas + self.gas_per_hop * hops as u64)
+    }
+
+    pub fn route_gas(&self, provider: FlashProvider, hops: usize) -> U256 {
+        self.swap_gas(hops) + U256::from(self.callback_gas.overhead(provider, hops))
+    }
+
+    // Expected cost of a route at the current gas regime, before refunds
+    pub fn route_cost(&self, gas: &GasSnapshot, provider: FlashProvider, hops: usize, bid: U256) -> GasCost {
+        gas.cost(self.route_gas(provider, hops), U256::zero(), bid)
+    }
+
+    // (base fee + tip) * route gas + bid, scaled by the margin multiplier
+    pub fn min_profit(&self, gas: &GasSnapshot, provider: FlashProvider, hops: usize, bid: U256) -> U256 {
+        let cost = self.route_cost(gas, provider, hops, bid).total();
+        let multiplier = (self.margin_multiplier * 1_000.0).round() as u64;
+        cost * U256::from(multiplier) / U256::from(1_000u64)
+    }
+
+    // Calibrates the callback gas from a successful execution's receipt: whatever it used
+    // beyond the modeled swap gas is attributed to the callback
+    pub fn observe_receipt(&self, provider: FlashProvider, hops: usize, gas_used: U256) {
+        let callback = gas_used.saturating_sub(self.swap_gas(hops));
+        self.callback_gas.observe(provider, hops, callback.low_u64());
+    }
+
+    // Replays journaled executions, oldest first, e.g. at startup
+    pub fn calibrate(&self, records: &[CallbackGasRecord]) {
+        for record in records {
+            if let Some(provider) = FlashProvider::parse(&record.provider) {
+                self.observe_receipt(provider, record.hops as usize, record.gas_used);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_threshold_scales_with_base_fee() {
+        let settings = ProfitThresholdSettings::default();
+        let calm = GasSnapshot {
+            block_number: 1,
+            base_fee: U256::from(30_000_000_000u64),
+            priority_fee: U256::from(2_000_000_000u64),
+        };
+        let busy = GasSnapshot {
+            base_fee: U256::from(300_000_000_000u64),
+            ..calm
+        };
+
+        let single = FlashProvider::UniswapV3;
+
+        // 32 gwei * 350k gas * 1.5
+        assert_eq!(
+            settings.min_profit(&calm, single, 2, U256::zero()),
+            U256::from(16_800_000_000_000_000u64)
+        );
+        assert!(
+            settings.min_profit(&busy, single, 2, U256::zero()) > settings.min_profit(&calm, single, 2, U256::zero())
+        );
+        assert!(
+            settings.min_profit(&calm, single, 2, U256::from(1u64)) > settings.min_profit(&calm, single, 2, U256::zero())
+        );
+
+        // Borrowing both sides costs a second transfer and repayment in the callback
+        assert!(settings.route_gas(FlashProvider::UniswapV3Dual, 2) > settings.route_gas(single, 2));
+        // 410k used on a 2-hop route leaves 200k for the callback once calibrated
+        for _ in 0..settings.callback_gas.settings().min_samples {
+            settings.observe_receipt(single, 2, U256::from(410_000u64));
+        }
+        assert!(settings.route_gas(single, 2) > U256::from(350_000u64));
+    }
+}
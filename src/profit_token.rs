@@ -0,0 +1,189 @@
+// src/profit_token.rs
+use ethers::{
+    abi::{self, ParamType, Token},
+    types::{Address, Transaction},
+    utils::id,
+};
+use serde::Deserialize;
+use std::collections::HashMap;
+
+use crate::opportunity_class::OpportunityClass;
+
+const WMATIC: &str = "0x0d500B1d8E8eF31E21C99d1Db9A6444d3ADf1270";
+const USDC: &str = "0x3c499c542cEF5E3811e1192ce70d8cC03d5c3359";
+const USDC_E: &str = "0x2791Bca1f2de4661ED88A30C99A7a9449Aa84174";
+const USDT: &str = "0xc2132D05D31c914a87C6611C10748AEb04B58e8F";
+const DAI: &str = "0x8f3Cf7ad23Cd3CaDbD9735AFf958023239c6A063";
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct TokenPreference {
+    // Borrow and settlement tokens, most preferred first
+    #[serde(default)]
+    pub borrow: Vec<Address>,
+    // Borrow whatever the triggering pending swap sells, ahead of `borrow`
+    #[serde(default)]
+    pub victim_input: bool,
+}
+
+// Which token each opportunity class flash-borrows and books its profit in
+#[derive(Debug, Clone, Deserialize)]
+pub struct ProfitTokenSettings {
+    // For classes without a preference of their own, or none of whose tokens are on the route
+    #[serde(default = "default_borrow")]
+    pub default: Vec<Address>,
+    #[serde(default = "default_classes")]
+    pub classes: HashMap<OpportunityClass, TokenPreference>,
+}
+
+fn parse(addresses: &[&str]) -> Vec<Address> {
+    addresses.iter().map(|address| address.parse().unwrap()).collect()
+}
+
+fn default_borrow() -> Vec<Address> {
+    parse(&[WMATIC])
+}
+
+fn default_classes() -> HashMap<OpportunityClass, TokenPreference> {
+    HashMap::from([
+        (
+            OpportunityClass::StableDepeg,
+            TokenPreference {
+                borrow: parse(&[USDC, USDC_E, USDT, DAI]),
+                victim_input: false,
+            },
+        ),
+        (
+            OpportunityClass::Sandwich,
+            TokenPreference {
+                borrow: Vec::new(),
+                victim_input: true,
+            },
+        ),
+    ])
+}
+
+impl Default for ProfitTokenSettings {
+    fn default() -> Self {
+        Self {
+            default: default_borrow(),
+            classes: default_classes(),
+        }
+    }
+}
+
+impl ProfitTokenSettings {
+    // Token to borrow for a `class` route through `tokens`: the victim's input when the class
+    // asks for it, then the class's list, then the defaults; the route's first token otherwise
+    pub fn borrow_token(
+        &self,
+        class: OpportunityClass,
+        tokens: &[Address],
+        victim_input: Option<Address>,
+    ) -> Option<Address> {
+        let preference = self.classes.get(&class);
+        let victim = victim_input.filter(|_| preference.is_some_and(|preference| preference.victim_input));
+        victim
+            .into_iter()
+            .chain(
+                preference
+                    .into_iter()
+                    .flat_map(|preference| preference.borrow.iter().copied()),
+            )
+            .chain(self.default.iter().copied())
+            .find(|token| tokens.contains(token))
+            .or_else(|| tokens.first().copied())
+    }
+}
+
+// Token a pending V2 router swap sells, None for calls that aren't one
+pub fn swap_input_token(tx: &Transaction) -> Option<Address> {
+    if tx.input.len() < 4 {
+        return None;
+    }
+    let (selector, args) = tx.input.split_at(4);
+    let exact_in = [
+        ParamType::Uint(256),
+        ParamType::Uint(256),
+        path_type(),
+        ParamType::Address,
+        ParamType::Uint(256),
+    ];
+    let from_native = [
+        ParamType::Uint(256),
+        path_type(),
+        ParamType::Address,
+        ParamType::Uint(256),
+    ];
+
+    let (types, path_index): (&[ParamType], usize) = match selector {
+        s if s == id("swapExactTokensForTokens(uint256,uint256,address[],address,uint256)")
+            || s == id("swapTokensForExactTokens(uint256,uint256,address[],address,uint256)")
+            || s == id("swapExactTokensForETH(uint256,uint256,address[],address,uint256)")
+            || s == id("swapTokensForExactETH(uint256,uint256,address[],address,uint256)")
+            || s == id(
+                "swapExactTokensForTokensSupportingFeeOnTransferTokens(uint256,uint256,address[],address,uint256)",
+            ) =>
+        {
+            (&exact_in[..], 2)
+        }
+        s if s == id("swapExactETHForTokens(uint256,address[],address,uint256)") => (&from_native[..], 1),
+        s if s == id("swapETHForExactTokens(uint256,address[],address,uint256)") => (&from_native[..], 1),
+        _ => return None,
+    };
+
+    match abi::decode(types, args).ok()?.swap_remove(path_index) {
+        Token::Array(path) => path.into_iter().next()?.into_address(),
+        _ => None,
+    }
+}
+
+fn path_type() -> ParamType {
+    ParamType::Array(Box::new(ParamType::Address))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{address, swap_tx};
+    use ethers::types::{H256, U256};
+
+    #[test]
+    fn test_borrow_token_follows_class_preferences() {
+        let settings = ProfitTokenSettings::default();
+        let (wmatic, usdc, usdt, weth) = (
+            WMATIC.parse().unwrap(),
+            USDC.parse().unwrap(),
+            USDT.parse().unwrap(),
+            address(7),
+        );
+
+        // Stable routes borrow USDC even when the cycle lists USDT first
+        assert_eq!(
+            settings.borrow_token(OpportunityClass::StableDepeg, &[usdt, usdc], None),
+            Some(usdc)
+        );
+        // Without a class preference WMATIC is borrowed as before, else the route's first token
+        assert_eq!(
+            settings.borrow_token(OpportunityClass::CrossDex, &[usdc, wmatic], None),
+            Some(wmatic)
+        );
+        assert_eq!(
+            settings.borrow_token(OpportunityClass::Triangular, &[weth, usdc], None),
+            Some(weth)
+        );
+
+        // Sandwiches take the victim's input token; other classes ignore it
+        let swap = swap_tx(H256::zero(), address(1), U256::exp10(18), &[weth, wmatic, usdc]);
+        let victim = swap_input_token(&swap);
+        assert_eq!(victim, Some(weth));
+        assert_eq!(
+            settings.borrow_token(OpportunityClass::Sandwich, &[wmatic, weth], victim),
+            Some(weth)
+        );
+        assert_eq!(
+            settings.borrow_token(OpportunityClass::Backrun, &[wmatic, weth], victim),
+            Some(wmatic)
+        );
+        assert_eq!(swap_input_token(&Transaction::default()), None);
+    }
+}
@@ -0,0 +1,210 @@
+// src/propagation.rs
+use ethers::types::{Block, H256};
+use log::debug;
+use serde::Deserialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+
+use crate::lab::LatencySummary;
+use crate::mempool::{MempoolSourceKind, PendingSwap};
+use crate::metrics::PROPAGATION_LATENCY_MS;
+
+// Pending hashes remembered while waiting for inclusion
+const PENDING_CAPACITY: usize = 100_000;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PropagationSettings {
+    // Inclusion latencies kept per source
+    #[serde(default = "default_window")]
+    pub window: usize,
+    // Percentile of first-seen -> inclusion latency a bundle must cover
+    #[serde(default = "default_target_percentile")]
+    pub target_percentile: usize,
+    #[serde(default = "default_min_samples")]
+    pub min_samples: usize,
+    #[serde(default = "default_max_blocks_ahead")]
+    pub max_blocks_ahead: u64,
+}
+
+fn default_window() -> usize {
+    2000
+}
+
+fn default_target_percentile() -> usize {
+    50
+}
+
+fn default_min_samples() -> usize {
+    50
+}
+
+fn default_max_blocks_ahead() -> u64 {
+    3
+}
+
+impl Default for PropagationSettings {
+    fn default() -> Self {
+        Self {
+            window: default_window(),
+            target_percentile: default_target_percentile(),
+            min_samples: default_min_samples(),
+            max_blocks_ahead: default_max_blocks_ahead(),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Inner {
+    pending: HashMap<H256, (MempoolSourceKind, u64)>,
+    order: VecDeque<H256>,
+    latencies: HashMap<MempoolSourceKind, VecDeque<u64>>,
+    last_block_timestamp: Option<u64>,
+    // Exponential average of the block interval
+    block_interval_ms: Option<f64>,
+}
+
+// First-seen vs inclusion timestamp, per mempool source
+#[derive(Debug, Clone, Default)]
+pub struct PropagationTracker {
+    settings: PropagationSettings,
+    inner: Arc<Mutex<Inner>>,
+}
+
+impl PropagationTracker {
+    pub fn new(settings: PropagationSettings) -> Self {
+        Self {
+            settings,
+            inner: Arc::default(),
+        }
+    }
+
+    pub fn observe_pending(&self, pending: &PendingSwap) {
+        let mut inner = self.inner.lock().unwrap();
+        if inner.pending.contains_key(&pending.tx.hash) {
+            return;
+        }
+        inner.pending.insert(pending.tx.hash, (pending.source, pending.first_seen_ms));
+        inner.order.push_back(pending.tx.hash);
+        if inner.order.len() > PENDING_CAPACITY {
+            if let Some(oldest) = inner.order.pop_front() {
+                inner.pending.remove(&oldest);
+            }
+        }
+    }
+
+    // Block timestamps have second resolution, so latencies are accurate to ~1 s
+    pub fn observe_block(&self, block: &Block<H256>) {
+        let included_ms = block.timestamp.as_u64() * 1000;
+        let mut inner = self.inner.lock().unwrap();
+
+        if let Some(previous) = inner.last_block_timestamp {
+            if included_ms > previous {
+                let interval = (included_ms - previous) as f64;
+                inner.block_interval_ms = Some(match inner.block_interval_ms {
+                    Some(average) => average * 0.9 + interval * 0.1,
+                    None => interval,
+                });
+            }
+        }
+        inner.last_block_timestamp = Some(included_ms);
+
+        let mut matched = 0;
+        for hash in &block.transactions {
+            let Some((source, first_seen_ms)) = inner.pending.remove(hash) else {
+                continue;
+            };
+            let latency = included_ms.saturating_sub(first_seen_ms);
+            PROPAGATION_LATENCY_MS
+                .with_label_values(&[source.as_str()])
+                .observe(latency as f64);
+
+            let samples = inner.latencies.entry(source).or_default();
+            samples.push_back(latency);
+            if samples.len() > self.settings.window {
+                samples.pop_front();
+            }
+            matched += 1;
+        }
+        debug!("Block {:?}: {} tracked pending txs included", block.number, matched);
+    }
+
+    pub fn summary(&self, source: MempoolSourceKind) -> LatencySummary {
+        let inner = self.inner.lock().unwrap();
+        let samples: Vec<u64> = inner
+            .latencies
+            .get(&source)
+            .map(|samples| samples.iter().copied().collect())
+            .unwrap_or_default();
+        LatencySummary::from_samples(&samples)
+    }
+
+    // How far ahead bundles should target: the percentile of inclusion latency over all
+    // sources in block intervals, at least one block
+    pub fn blocks_ahead(&self) -> u64 {
+        let inner = self.inner.lock().unwrap();
+        let Some(interval) = inner.block_interval_ms.filter(|interval| *interval > 0.0) else {
+            return 1;
+        };
+        let mut samples: Vec<u64> = inner.latencies.values().flatten().copied().collect();
+        if samples.len() < self.settings.min_samples.max(1) {
+            return 1;
+        }
+
+        samples.sort_unstable();
+        let percentile = self.settings.target_percentile.min(100);
+        let latency = samples[((samples.len() - 1) * percentile) / 100] as f64;
+        ((latency / interval).ceil() as u64).clamp(1, self.settings.max_blocks_ahead.max(1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{address, pending_swap, swap_tx};
+    use ethers::types::U256;
+
+    fn pending(hash: H256, source: MempoolSourceKind, first_seen_ms: u64) -> PendingSwap {
+        let tx = swap_tx(hash, address(0xa5), U256::exp10(18), &[address(1), address(2)]);
+        pending_swap(tx, source, first_seen_ms)
+    }
+
+    fn block(timestamp: u64, transactions: Vec<H256>) -> Block<H256> {
+        Block {
+            timestamp: timestamp.into(),
+            transactions,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_latency_per_source_and_blocks_ahead() {
+        let settings = PropagationSettings { min_samples: 2, ..Default::default() };
+        let tracker = PropagationTracker::new(settings);
+        let (a, b, c) = (H256::from_low_u64_be(1), H256::from_low_u64_be(2), H256::from_low_u64_be(3));
+
+        tracker.observe_pending(&pending(a, MempoolSourceKind::Node, 96_000));
+        tracker.observe_pending(&pending(b, MempoolSourceKind::Stream, 99_500));
+        tracker.observe_pending(&pending(c, MempoolSourceKind::Stream, 99_000));
+        // Already tracked from the stream; the later duplicate is ignored
+        tracker.observe_pending(&pending(b, MempoolSourceKind::Node, 99_900));
+
+        assert_eq!(tracker.blocks_ahead(), 1);
+        tracker.observe_block(&block(98, vec![]));
+        tracker.observe_block(&block(100, vec![a, b, c]));
+
+        assert_eq!(tracker.summary(MempoolSourceKind::Node).max_ms, 4_000);
+        assert_eq!(tracker.summary(MempoolSourceKind::Stream).p50_ms, 500);
+        // Median latency 1 s over 2 s blocks still fits the next block
+        assert_eq!(tracker.blocks_ahead(), 1);
+
+        let slow = PropagationTracker::new(PropagationSettings {
+            min_samples: 1,
+            target_percentile: 100,
+            ..Default::default()
+        });
+        slow.observe_pending(&pending(a, MempoolSourceKind::Node, 90_000));
+        slow.observe_block(&block(98, vec![]));
+        slow.observe_block(&block(100, vec![a]));
+        assert_eq!(slow.blocks_ahead(), 3);
+    }
+}
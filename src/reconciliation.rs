@@ -0,0 +1,451 @@
+// src/reconciliation.rs
+use anyhow::{anyhow, Result};
+use ethers::{
+    providers::Middleware,
+    types::{Address, BlockId, U256},
+};
+use log::{debug, info, warn};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::alerts::{AlertLevel, Alerts};
+use crate::erc20::IERC20;
+use crate::metrics::BALANCE_DISCREPANCIES_TOTAL;
+use crate::store::{BalanceCheckpoint, ExecutionRecord, GasSpendRecord, PayoutRecord, Store};
+
+// Token key of the native balance in checkpoints
+pub const NATIVE: Address = Address::zero();
+
+// Store checkpoint of the executor event listener; executions are booked up to it
+const EXECUTIONS_CHECKPOINT: &str = "executor_events";
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReconciliationSettings {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+    // Blocks behind the executor event checkpoint that are reconciled, so receipts of the
+    // latest executions are journaled before their balances are compared
+    #[serde(default = "default_lag_blocks")]
+    pub lag_blocks: u64,
+    // Tokens checked besides MATIC and every token profit was ever booked in
+    #[serde(default)]
+    pub tokens: Vec<Address>,
+    // Unexplained MATIC up to this many wei is ignored: gas of txs outside the journal
+    // (nonce gap fillers, manual setup)
+    #[serde(default = "default_native_tolerance_wei")]
+    pub native_tolerance_wei: u64,
+    // Per-token tolerance in raw units; tokens not listed must match exactly
+    #[serde(default)]
+    pub tolerance: HashMap<Address, u64>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_interval_secs() -> u64 {
+    600
+}
+
+fn default_lag_blocks() -> u64 {
+    10
+}
+
+fn default_native_tolerance_wei() -> u64 {
+    100_000_000_000_000_000
+}
+
+impl Default for ReconciliationSettings {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            interval_secs: default_interval_secs(),
+            lag_blocks: default_lag_blocks(),
+            tokens: Vec::new(),
+            native_tolerance_wei: default_native_tolerance_wei(),
+            tolerance: HashMap::new(),
+        }
+    }
+}
+
+impl ReconciliationSettings {
+    fn tolerance(&self, token: Address) -> U256 {
+        if token == NATIVE {
+            return U256::from(self.native_tolerance_wei);
+        }
+        U256::from(self.tolerance.get(&token).copied().unwrap_or_default())
+    }
+}
+
+// What moves a balance; one address can hold several roles
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Holder {
+    // Signs the executions and payouts and pays their gas
+    Signer,
+    // Receives the profit the executor forwards to its owner, pays the payee shares
+    Beneficiary,
+    // Forwards every profit within the execution, so its balances never move
+    Executor,
+}
+
+impl Holder {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Holder::Signer => "signer",
+            Holder::Beneficiary => "beneficiary",
+            Holder::Executor => "executor",
+        }
+    }
+}
+
+// Journal entries after the oldest checkpoint, shared by every balance of one run
+#[derive(Debug, Clone, Default)]
+pub struct Journal {
+    pub executions: Vec<ExecutionRecord>,
+    pub gas_spend: Vec<GasSpendRecord>,
+    pub payouts: Vec<PayoutRecord>,
+}
+
+impl Journal {
+    pub fn load(store: &Store, from_block: u64) -> Result<Self> {
+        Ok(Self {
+            executions: store.executions_since(from_block)?,
+            gas_spend: store.gas_spend_since(from_block)?,
+            payouts: store.payouts_since(from_block)?,
+        })
+    }
+
+    // Balance the journal explains at `to_block` for an address with `roles`, starting
+    // from `checkpoint`
+    pub fn expected(&self, roles: &[Holder], checkpoint: &BalanceCheckpoint, to_block: u64) -> U256 {
+        let in_range = |block: u64| block > checkpoint.block_number && block <= to_block;
+        let (mut credits, mut debits) = (U256::zero(), U256::zero());
+        if roles.contains(&Holder::Beneficiary) {
+            for execution in &self.executions {
+                let booked = execution.status == "executed" && execution.token0 == checkpoint.token;
+                if booked && in_range(execution.block_number) {
+                    credits = credits.saturating_add(execution.profit);
+                }
+            }
+            for payout in &self.payouts {
+                if in_range(payout.block_number) && payout.token == checkpoint.token {
+                    debits = debits.saturating_add(payout.amount);
+                }
+            }
+        }
+        if roles.contains(&Holder::Signer) && checkpoint.token == NATIVE {
+            for spend in &self.gas_spend {
+                if in_range(spend.block_number) {
+                    debits = debits.saturating_add(spend.cost);
+                }
+            }
+        }
+        checkpoint.balance.saturating_add(credits).saturating_sub(debits)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Discrepancy {
+    pub holder: Holder,
+    pub address: Address,
+    pub token: Address,
+    pub block_number: u64,
+    pub expected: U256,
+    pub actual: U256,
+}
+
+impl Discrepancy {
+    pub fn shortfall(&self) -> bool {
+        self.actual < self.expected
+    }
+
+    // Likely causes, for the alert
+    pub fn hint(&self) -> &'static str {
+        match (self.holder, self.shortfall()) {
+            (Holder::Executor, true) => "funds left the executor: leaked approval or external withdrawal",
+            (Holder::Executor, false) => "executor holds funds it should have forwarded or received a deposit",
+            (Holder::Beneficiary, true) => "profit wallet sent more than the journaled payouts: external withdrawal",
+            (Holder::Beneficiary, false) => "profit wallet received more than journaled: missed trades or a deposit",
+            (Holder::Signer, true) => "signer spent more than journaled: untracked tx or external withdrawal",
+            (Holder::Signer, false) => "signer received an unjournaled deposit",
+        }
+    }
+}
+
+// Periodically recomputes the wallet and executor balances the journal explains since the
+// last agreed checkpoint and compares them with the chain. Each discrepancy is alerted
+// once: the on-chain balance becomes the next checkpoint either way
+#[derive(Debug, Clone)]
+pub struct BalanceReconciler<M> {
+    provider: Arc<M>,
+    store: Store,
+    settings: ReconciliationSettings,
+    // Each checked address with its roles, the one named in alerts first
+    accounts: Vec<(Address, Vec<Holder>)>,
+    alerts: Alerts,
+}
+
+impl<M: Middleware + 'static> BalanceReconciler<M> {
+    pub fn new(
+        provider: Arc<M>,
+        store: Store,
+        settings: ReconciliationSettings,
+        signer: Address,
+        beneficiary: Address,
+        executor: Address,
+        alerts: Alerts,
+    ) -> Self {
+        let mut accounts: Vec<(Address, Vec<Holder>)> = Vec::new();
+        for (address, role) in [
+            (executor, Holder::Executor),
+            (beneficiary, Holder::Beneficiary),
+            (signer, Holder::Signer),
+        ] {
+            match accounts.iter_mut().find(|(account, _)| *account == address) {
+                Some((_, roles)) => roles.push(role),
+                None => accounts.push((address, vec![role])),
+            }
+        }
+        Self {
+            provider,
+            store,
+            settings,
+            accounts,
+            alerts,
+        }
+    }
+
+    pub async fn run(&self) {
+        let mut interval = tokio::time::interval(Duration::from_secs(self.settings.interval_secs.max(1)));
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.reconcile_once().await {
+                warn!("Balance reconciliation failed: {:?}", e);
+            }
+        }
+    }
+
+    fn tokens(&self) -> Result<Vec<Address>> {
+        let mut tokens = vec![NATIVE];
+        tokens.extend(self.settings.tokens.iter().copied());
+        tokens.extend(self.store.realized_profit(0)?.into_iter().map(|(token, _)| token));
+        tokens.sort();
+        tokens.dedup();
+        Ok(tokens)
+    }
+
+    async fn balance(&self, holder: Address, token: Address, block: BlockId) -> Result<U256> {
+        if token == NATIVE {
+            return self
+                .provider
+                .get_balance(holder, Some(block))
+                .await
+                .map_err(|e| anyhow!("Failed to fetch MATIC balance of {:?}: {e}", holder));
+        }
+        IERC20::new(token, self.provider.clone())
+            .balance_of(holder)
+            .block(block)
+            .call()
+            .await
+            .map_err(|e| anyhow!("Failed to fetch {:?} balance of {:?}: {e}", token, holder))
+    }
+
+    // Returns the discrepancies found; balances without a checkpoint only get one
+    pub async fn reconcile_once(&self) -> Result<Vec<Discrepancy>> {
+        // The journal is only complete up to the executor event listener's progress
+        let Some(indexed) = self.store.last_indexed_block(EXECUTIONS_CHECKPOINT)? else {
+            debug!("Executor events not indexed yet, skipping balance reconciliation");
+            return Ok(Vec::new());
+        };
+        let head = self
+            .provider
+            .get_block_number()
+            .await
+            .map_err(|e| anyhow!("Failed to fetch block number: {e}"))?
+            .as_u64();
+        let block_number = indexed.min(head).saturating_sub(self.settings.lag_blocks);
+        let block = BlockId::Number(block_number.into());
+
+        let tokens = self.tokens()?;
+        let mut checkpoints = Vec::new();
+        for (address, roles) in &self.accounts {
+            for &token in &tokens {
+                checkpoints.push((*address, roles, token, self.store.balance_checkpoint(*address, token)?));
+            }
+        }
+        let from_block = checkpoints
+            .iter()
+            .filter_map(|(_, _, _, checkpoint)| checkpoint.map(|checkpoint| checkpoint.block_number + 1))
+            .min()
+            .unwrap_or(block_number);
+        let journal = Journal::load(&self.store, from_block)?;
+
+        let mut discrepancies = Vec::new();
+        for (address, roles, token, checkpoint) in checkpoints {
+            if checkpoint.is_some_and(|checkpoint| checkpoint.block_number >= block_number) {
+                continue;
+            }
+            let actual = self.balance(address, token, block).await?;
+            if let Some(checkpoint) = checkpoint {
+                let expected = journal.expected(roles, &checkpoint, block_number);
+                let difference = if actual > expected { actual - expected } else { expected - actual };
+                if difference > self.settings.tolerance(token) {
+                    let discrepancy = Discrepancy {
+                        holder: roles[0],
+                        address,
+                        token,
+                        block_number,
+                        expected,
+                        actual,
+                    };
+                    self.report(&discrepancy, checkpoint.block_number);
+                    discrepancies.push(discrepancy);
+                }
+            }
+            self.store.set_balance_checkpoint(&BalanceCheckpoint {
+                holder: address,
+                token,
+                block_number,
+                balance: actual,
+            })?;
+        }
+
+        if discrepancies.is_empty() {
+            info!("Balances reconciled against the journal at block {}", block_number);
+        }
+        Ok(discrepancies)
+    }
+
+    fn report(&self, discrepancy: &Discrepancy, since_block: u64) {
+        let direction = if discrepancy.shortfall() { "shortfall" } else { "surplus" };
+        BALANCE_DISCREPANCIES_TOTAL
+            .with_label_values(&[discrepancy.holder.as_str(), direction])
+            .inc();
+        let level = match discrepancy.holder {
+            Holder::Executor if discrepancy.shortfall() => AlertLevel::Critical,
+            _ => AlertLevel::Warning,
+        };
+        let asset = if discrepancy.token == NATIVE {
+            "MATIC".to_string()
+        } else {
+            format!("{:?}", discrepancy.token)
+        };
+        self.alerts.emit(
+            level,
+            "reconciliation",
+            format!(
+                "{} {:?} {} balance is {} at block {}, journal since block {} explains {}: {}",
+                discrepancy.holder.as_str(),
+                discrepancy.address,
+                asset,
+                discrepancy.actual,
+                discrepancy.block_number,
+                since_block,
+                discrepancy.expected,
+                discrepancy.hint()
+            ),
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{address, call_output, quantity, MockChain};
+    use ethers::abi::Token;
+    use ethers::types::H256;
+
+    #[tokio::test]
+    async fn test_unexplained_balance_changes_are_flagged() {
+        let (wallet, executor, usdc) = (address(1), address(2), address(10));
+        let store = Store::open_in_memory().unwrap();
+        store.set_last_indexed_block(EXECUTIONS_CHECKPOINT, 120).unwrap();
+        let checkpoint = |holder, token, balance: u64| BalanceCheckpoint {
+            holder,
+            token,
+            block_number: 100,
+            balance: U256::from(balance),
+        };
+        for (holder, token, balance) in [
+            (wallet, NATIVE, 1_000_000),
+            (wallet, usdc, 500),
+            (executor, NATIVE, 0),
+            (executor, usdc, 50),
+        ] {
+            store.set_balance_checkpoint(&checkpoint(holder, token, balance)).unwrap();
+        }
+
+        // Profit of 300 USDC, 100 of it paid out, 40k wei of gas
+        store
+            .insert_execution(&ExecutionRecord {
+                block_number: 105,
+                log_index: 0,
+                tx_hash: H256::repeat_byte(1),
+                status: "executed".to_string(),
+                token0: usdc,
+                token1: address(11),
+                amount0: U256::from(10_000u64),
+                amount1: U256::zero(),
+                profit: U256::from(300u64),
+                reason: None,
+            })
+            .unwrap();
+        store
+            .record_payout(&PayoutRecord {
+                tx_hash: H256::repeat_byte(2),
+                block_number: 108,
+                payee: address(3),
+                token: usdc,
+                amount: U256::from(100u64),
+            })
+            .unwrap();
+        store
+            .record_gas_spend(&GasSpendRecord {
+                tx_hash: H256::repeat_byte(1),
+                block_number: 105,
+                gas_used: U256::from(400u64),
+                cost: U256::from(40_000u64),
+            })
+            .unwrap();
+
+        let settings = ReconciliationSettings {
+            native_tolerance_wei: 1_000,
+            ..Default::default()
+        };
+        let chain = MockChain::new();
+        let reconciler =
+            BalanceReconciler::new(chain.provider(), store.clone(), settings, wallet, wallet, executor, Alerts::default());
+        // The wallet signs and receives the profit and matches the journal; the executor,
+        // checked first, lost its 50 USDC
+        chain.script(vec![
+            quantity(130u64),
+            quantity(0u64),
+            call_output(&[Token::Uint(U256::zero())]),
+            quantity(960_500u64),
+            call_output(&[Token::Uint(U256::from(700u64))]),
+        ]);
+        let discrepancies = reconciler.reconcile_once().await.unwrap();
+        assert_eq!(
+            discrepancies,
+            vec![Discrepancy {
+                holder: Holder::Executor,
+                address: executor,
+                token: usdc,
+                block_number: 110,
+                expected: U256::from(50u64),
+                actual: U256::zero(),
+            }]
+        );
+        assert!(discrepancies[0].hint().contains("leaked approval"));
+        let rebased = store.balance_checkpoint(executor, usdc).unwrap().unwrap();
+        assert_eq!((rebased.block_number, rebased.balance), (110, U256::zero()));
+
+        // Nothing new to compare until the journal moves past the checkpoint
+        chain.script(vec![quantity(130u64)]);
+        assert!(reconciler.reconcile_once().await.unwrap().is_empty());
+    }
+}
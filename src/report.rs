@@ -0,0 +1,510 @@
+// src/report.rs
+use anyhow::Result;
+use clap::ValueEnum;
+use ethers::types::{Address, H256, U256};
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write;
+
+use crate::store::Store;
+use crate::token_registry::{format_units, TokenRegistry};
+
+const HOUR: u64 = 3_600;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum ReportFormat {
+    Html,
+    Csv,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PairStats {
+    pub opportunities: u64,
+    // Journal entries that went to execution (submitted or failed)
+    pub attempted: u64,
+    // Submissions whose ArbitrageExecuted event was booked
+    pub included: u64,
+    // Summed in token0 of the pair
+    pub expected_profit: U256,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GasTotals {
+    pub submissions: u64,
+    pub gas_used: U256,
+    pub cost: U256,
+}
+
+// Journal aggregates behind `flashwich report`, used to tune the token allowlist
+#[derive(Debug, Clone, Default)]
+pub struct Report {
+    pub since: u64,
+    pub opportunities: usize,
+    // (token0, token1) -> start of the UTC hour -> journal entries
+    pub heatmap: BTreeMap<(Address, Address), BTreeMap<u64, u64>>,
+    pub pairs: BTreeMap<(Address, Address), PairStats>,
+    pub statuses: BTreeMap<String, u64>,
+    pub realized: BTreeMap<Address, U256>,
+    pub failures: BTreeMap<String, u64>,
+    pub gas: GasTotals,
+    // Submissions whose executor event is still short of its confirmation depth
+    pub pending_finality: u64,
+}
+
+impl Report {
+    // Opportunities detected at or after `since` (unix seconds). Executor events and gas
+    // spend are keyed by block, so they are taken from the window's first journaled block
+    pub fn build(store: &Store, since: u64) -> Result<Self> {
+        let opportunities = store.opportunities_since(since, None)?;
+        let mut report = Self {
+            since,
+            opportunities: opportunities.len(),
+            ..Default::default()
+        };
+        let Some(from_block) = opportunities.iter().map(|o| o.block_number).min() else {
+            return Ok(report);
+        };
+
+        let executions = store.executions_since(from_block)?;
+        let executed: HashSet<H256> = executions
+            .iter()
+            .filter(|e| e.status == "executed")
+            .map(|e| e.tx_hash)
+            .collect();
+        let pending: HashSet<H256> = store
+            .pending_executions()?
+            .into_iter()
+            .map(|(execution, _)| execution.tx_hash)
+            .collect();
+        for execution in &executions {
+            match &execution.reason {
+                None => {
+                    let total = report.realized.entry(execution.token0).or_default();
+                    *total = total.saturating_add(execution.profit);
+                }
+                Some(reason) => *report.failures.entry(reason.clone()).or_default() += 1,
+            }
+        }
+
+        for opportunity in &opportunities {
+            let pair = (opportunity.token0, opportunity.token1);
+            let hour = opportunity.detected_at / HOUR * HOUR;
+            *report.heatmap.entry(pair).or_default().entry(hour).or_default() += 1;
+            *report.statuses.entry(opportunity.status.clone()).or_default() += 1;
+
+            let stats = report.pairs.entry(pair).or_default();
+            stats.opportunities += 1;
+            stats.expected_profit = stats.expected_profit.saturating_add(opportunity.expected_profit);
+            match opportunity.status.as_str() {
+                "submitted" | "included" | "reorged" => {
+                    stats.attempted += 1;
+                    if opportunity.tx_hash.is_some_and(|hash| executed.contains(&hash)) {
+                        stats.included += 1;
+                    } else if opportunity.tx_hash.is_some_and(|hash| pending.contains(&hash)) {
+                        report.pending_finality += 1;
+                    } else if opportunity.status == "reorged" {
+                        *report.failures.entry("reorged out".to_string()).or_default() += 1;
+                    } else {
+                        *report
+                            .failures
+                            .entry("no ArbitrageExecuted event".to_string())
+                            .or_default() += 1;
+                    }
+                }
+                "failed" => {
+                    stats.attempted += 1;
+                    *report.failures.entry("submission error".to_string()).or_default() += 1;
+                }
+                "victim_dropped" | "outbid" | "stale_spread" => {
+                    stats.attempted += 1;
+                    let reason = format!("bundle missed: {}", opportunity.status.replace('_', " "));
+                    *report.failures.entry(reason).or_default() += 1;
+                }
+                _ => {}
+            }
+        }
+
+        for spend in store.gas_spend_since(from_block)? {
+            report.gas.submissions += 1;
+            report.gas.gas_used += spend.gas_used;
+            report.gas.cost += spend.cost;
+        }
+        Ok(report)
+    }
+
+    pub fn attempted(&self) -> u64 {
+        self.pairs.values().map(|stats| stats.attempted).sum()
+    }
+
+    pub fn included(&self) -> u64 {
+        self.pairs.values().map(|stats| stats.included).sum()
+    }
+
+    // Every hour between the first and last journaled one, so gaps show as empty columns
+    fn hours(&self) -> Vec<u64> {
+        let seen = self.heatmap.values().flat_map(|hours| hours.keys().copied());
+        let (first, last) = seen.fold((u64::MAX, 0), |(first, last), hour| (first.min(hour), last.max(hour)));
+        if first > last {
+            return Vec::new();
+        }
+        (first..=last).step_by(HOUR as usize).collect()
+    }
+
+    // Busiest pairs first
+    fn ranked_pairs(&self) -> Vec<(&(Address, Address), &PairStats)> {
+        let mut pairs: Vec<_> = self.pairs.iter().collect();
+        pairs.sort_by_key(|(_, stats)| std::cmp::Reverse(stats.opportunities));
+        pairs
+    }
+
+    pub fn to_csv(&self, tokens: &TokenRegistry) -> String {
+        let mut out = String::new();
+        writeln!(out, "# opportunities per pair per hour (UTC)").unwrap();
+        writeln!(out, "pair,hour,opportunities").unwrap();
+        for (pair, _) in self.ranked_pairs() {
+            for (hour, count) in &self.heatmap[pair] {
+                writeln!(
+                    out,
+                    "{},{},{}",
+                    csv_field(&pair_label(tokens, pair)),
+                    utc_hour(*hour),
+                    count
+                )
+                .unwrap();
+            }
+        }
+
+        writeln!(out, "\n# pairs").unwrap();
+        writeln!(
+            out,
+            "pair,opportunities,attempted,included,inclusion_rate,expected_profit"
+        )
+        .unwrap();
+        for (pair, stats) in self.ranked_pairs() {
+            writeln!(
+                out,
+                "{},{},{},{},{},{}",
+                csv_field(&pair_label(tokens, pair)),
+                stats.opportunities,
+                stats.attempted,
+                stats.included,
+                rate(stats.included, stats.attempted),
+                units(tokens, &pair.0, stats.expected_profit)
+            )
+            .unwrap();
+        }
+
+        writeln!(out, "\n# realized pnl").unwrap();
+        writeln!(out, "token,profit").unwrap();
+        for (token, profit) in &self.realized {
+            writeln!(
+                out,
+                "{},{}",
+                csv_field(&tokens.symbol(token)),
+                units(tokens, token, *profit)
+            )
+            .unwrap();
+        }
+
+        writeln!(out, "\n# failure reasons").unwrap();
+        writeln!(out, "reason,count").unwrap();
+        for (reason, count) in &self.failures {
+            writeln!(out, "{},{}", csv_field(reason), count).unwrap();
+        }
+
+        writeln!(out, "\n# statuses").unwrap();
+        writeln!(out, "status,count").unwrap();
+        for (status, count) in &self.statuses {
+            writeln!(out, "{},{}", csv_field(status), count).unwrap();
+        }
+
+        writeln!(out, "\n# gas and inclusion").unwrap();
+        writeln!(
+            out,
+            "submissions,gas_used,cost_matic,attempted,included,inclusion_rate,pending_finality"
+        )
+        .unwrap();
+        writeln!(
+            out,
+            "{},{},{},{},{},{},{}",
+            self.gas.submissions,
+            self.gas.gas_used,
+            format_units(self.gas.cost, 18),
+            self.attempted(),
+            self.included(),
+            rate(self.included(), self.attempted()),
+            self.pending_finality
+        )
+        .unwrap();
+        out
+    }
+
+    pub fn to_html(&self, tokens: &TokenRegistry) -> String {
+        let mut out = String::new();
+        out.push_str(
+            "<!DOCTYPE html>\n<html><head><meta charset=\"utf-8\"><title>Flashwich report</title>\n\
+             <style>body{font-family:sans-serif}table{border-collapse:collapse;margin-bottom:2em}\
+             td,th{border:1px solid #ccc;padding:2px 6px;text-align:right}th{background:#eee}</style>\n\
+             </head><body>\n",
+        );
+        writeln!(
+            out,
+            "<h1>Flashwich report</h1>\n<p>{} opportunities since {} UTC</p>",
+            self.opportunities,
+            utc_hour(self.since)
+        )
+        .unwrap();
+
+        let hours = self.hours();
+        let busiest = self
+            .heatmap
+            .values()
+            .flat_map(|hours| hours.values())
+            .max()
+            .copied()
+            .unwrap_or(1);
+        out.push_str("<h2>Opportunities per pair per hour (UTC)</h2>\n<table><tr><th>pair</th>");
+        for hour in &hours {
+            write!(out, "<th>{}</th>", &utc_hour(*hour)[11..]).unwrap();
+        }
+        out.push_str("</tr>\n");
+        for (pair, _) in self.ranked_pairs() {
+            write!(out, "<tr><th>{}</th>", escape(&pair_label(tokens, pair))).unwrap();
+            for hour in &hours {
+                match self.heatmap[pair].get(hour) {
+                    Some(count) => write!(
+                        out,
+                        "<td style=\"background:rgba(214,39,40,{:.2})\">{}</td>",
+                        *count as f64 / busiest as f64,
+                        count
+                    )
+                    .unwrap(),
+                    None => out.push_str("<td></td>"),
+                }
+            }
+            out.push_str("</tr>\n");
+        }
+        out.push_str("</table>\n");
+
+        out.push_str(
+            "<h2>Pairs</h2>\n<table><tr><th>pair</th><th>opportunities</th><th>attempted</th>\
+             <th>included</th><th>inclusion rate</th><th>expected profit</th></tr>\n",
+        );
+        for (pair, stats) in self.ranked_pairs() {
+            writeln!(
+                out,
+                "<tr><th>{}</th><td>{}</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>",
+                escape(&pair_label(tokens, pair)),
+                stats.opportunities,
+                stats.attempted,
+                stats.included,
+                rate(stats.included, stats.attempted),
+                escape(&tokens.format_amount(&pair.0, stats.expected_profit))
+            )
+            .unwrap();
+        }
+        out.push_str("</table>\n");
+
+        out.push_str("<h2>Realized PnL</h2>\n<table><tr><th>token</th><th>profit</th></tr>\n");
+        for (token, profit) in &self.realized {
+            writeln!(
+                out,
+                "<tr><th>{}</th><td>{}</td></tr>",
+                escape(&tokens.symbol(token)),
+                escape(&tokens.format_amount(token, *profit))
+            )
+            .unwrap();
+        }
+        out.push_str("</table>\n");
+
+        out.push_str("<h2>Failure reasons</h2>\n<table><tr><th>reason</th><th>count</th></tr>\n");
+        for (reason, count) in &self.failures {
+            writeln!(out, "<tr><th>{}</th><td>{}</td></tr>", escape(reason), count).unwrap();
+        }
+        out.push_str("</table>\n");
+
+        out.push_str("<h2>Journal statuses</h2>\n<table><tr><th>status</th><th>count</th></tr>\n");
+        for (status, count) in &self.statuses {
+            writeln!(out, "<tr><th>{}</th><td>{}</td></tr>", escape(status), count).unwrap();
+        }
+        out.push_str("</table>\n");
+
+        writeln!(
+            out,
+            "<h2>Gas and inclusion</h2>\n<table><tr><th>submissions</th><th>gas used</th>\
+             <th>cost</th><th>attempted</th><th>included</th><th>inclusion rate</th><th>pending finality</th></tr>\n\
+             <tr><td>{}</td><td>{}</td><td>{} MATIC</td><td>{}</td><td>{}</td><td>{}</td><td>{}</td></tr>\n</table>",
+            self.gas.submissions,
+            self.gas.gas_used,
+            format_units(self.gas.cost, 18),
+            self.attempted(),
+            self.included(),
+            rate(self.included(), self.attempted()),
+            self.pending_finality
+        )
+        .unwrap();
+        out.push_str("</body></html>\n");
+        out
+    }
+}
+
+fn pair_label(tokens: &TokenRegistry, pair: &(Address, Address)) -> String {
+    format!("{}/{}", tokens.symbol(&pair.0), tokens.symbol(&pair.1))
+}
+
+// Decimal amount for known tokens, raw units otherwise
+fn units(tokens: &TokenRegistry, token: &Address, amount: U256) -> String {
+    match tokens.get(token) {
+        Some(info) => format_units(amount, info.decimals),
+        None => amount.to_string(),
+    }
+}
+
+fn rate(included: u64, attempted: u64) -> String {
+    if attempted == 0 {
+        return "-".to_string();
+    }
+    format!("{:.1}%", included as f64 * 100.0 / attempted as f64)
+}
+
+// "2026-10-15 13:00" for a unix timestamp, without pulling in a date crate
+pub fn utc_hour(timestamp: u64) -> String {
+    // Days to civil date, after Howard Hinnant's algorithm
+    let z = timestamp / 86_400 + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:00",
+        year,
+        month,
+        day,
+        timestamp % 86_400 / HOUR
+    )
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+// Symbols and revert strings come from the chain
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::address;
+    use crate::store::{ExecutionRecord, GasSpendRecord, OpportunityRecord};
+    use crate::token_registry::TokenInfo;
+
+    #[test]
+    fn test_report_aggregates_journal() {
+        let store = Store::open_in_memory().unwrap();
+        let (usdc, wmatic) = (address(1), address(2));
+        let (included, reverted) = (H256::from_low_u64_be(1), H256::from_low_u64_be(2));
+        // 2023-11-14 22:13:20 UTC
+        let at = 1_700_000_000;
+        let opportunity = |detected_at: u64, status: &str, tx_hash: Option<H256>| OpportunityRecord {
+            detected_at,
+            block_number: 100,
+            class: "backrun".to_string(),
+            token0: usdc,
+            token1: wmatic,
+            expected_profit: U256::from(2_000_000u64),
+            min_profit: U256::zero(),
+            status: status.to_string(),
+            tx_hash,
+            opportunity_id: None,
+        };
+        store
+            .record_opportunity(&opportunity(at, "submitted", Some(included)))
+            .unwrap();
+        store
+            .record_opportunity(&opportunity(at + 60, "submitted", Some(reverted)))
+            .unwrap();
+        store
+            .record_opportunity(&opportunity(at + HOUR, "below_threshold", None))
+            .unwrap();
+        store
+            .record_opportunity(&opportunity(at + 2 * HOUR, "failed", None))
+            .unwrap();
+        // Outside the window
+        store
+            .record_opportunity(&opportunity(at - 2 * HOUR, "detected", None))
+            .unwrap();
+
+        store
+            .insert_execution(&ExecutionRecord {
+                block_number: 101,
+                log_index: 0,
+                tx_hash: included,
+                status: "executed".to_string(),
+                token0: usdc,
+                token1: wmatic,
+                amount0: U256::from(1_000_000_000u64),
+                amount1: U256::zero(),
+                profit: U256::from(1_500_000u64),
+                reason: None,
+            })
+            .unwrap();
+        for (tx_hash, cost) in [(included, 3u64), (reverted, 2u64)] {
+            store
+                .record_gas_spend(&GasSpendRecord {
+                    tx_hash,
+                    block_number: 101,
+                    gas_used: U256::from(300_000u64),
+                    cost: U256::exp10(17) * cost,
+                })
+                .unwrap();
+        }
+
+        let report = Report::build(&store, at - HOUR).unwrap();
+        let pair = (usdc, wmatic);
+        assert_eq!(report.opportunities, 4);
+        assert_eq!(
+            report.heatmap[&pair].values().copied().collect::<Vec<_>>(),
+            vec![2, 1, 1]
+        );
+        assert_eq!(report.hours().len(), 3);
+        assert_eq!((report.attempted(), report.included()), (3, 1));
+        assert_eq!(report.realized[&usdc], U256::from(1_500_000u64));
+        assert_eq!(report.failures["no ArbitrageExecuted event"], 1);
+        assert_eq!(report.failures["submission error"], 1);
+        assert_eq!(report.gas.cost, U256::exp10(17) * 5);
+
+        let tokens = TokenRegistry::new();
+        tokens
+            .insert(TokenInfo {
+                address: usdc,
+                name: "USD Coin".to_string(),
+                symbol: "USDC".to_string(),
+                decimals: 6,
+                tags: Vec::new(),
+            })
+            .unwrap();
+        let csv = report.to_csv(&tokens);
+        assert!(csv.contains("USDC/0x0000…0002,2023-11-14 22:00,2\n"));
+        assert!(csv.contains("\nUSDC,1.5\n"));
+        assert!(csv.contains("\n2,600000,0.5,3,1,33.3%,0\n"));
+        assert!(report.to_html(&tokens).contains("<td>0.5 MATIC</td>"));
+
+        assert_eq!(utc_hour(0), "1970-01-01 00:00");
+        assert_eq!(utc_hour(1_709_210_000), "2024-02-29 12:00");
+        assert_eq!(csv_field("a, \"b\""), "\"a, \"\"b\"\"\"");
+        assert!(Report::build(&store, at + 3 * HOUR).unwrap().pairs.is_empty());
+    }
+}
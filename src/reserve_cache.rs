@@ -0,0 +1,192 @@
+// src/reserve_cache.rs
+use arc_swap::ArcSwap;
+use ethers::types::{Address, U256};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+
+// Pools are spread over shards by the first byte of their address
+const SHARDS: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolReserves {
+    pub reserve0: U256,
+    pub reserve1: U256,
+    pub block_number: u64,
+}
+
+// Immutable view of the whole cache; reads take no lock at all
+#[derive(Debug, Clone, Default)]
+pub struct ReserveSnapshot {
+    reserves: Arc<HashMap<Address, PoolReserves>>,
+    version: u64,
+}
+
+impl ReserveSnapshot {
+    pub fn get(&self, pool: &Address) -> Option<PoolReserves> {
+        self.reserves.get(pool).copied()
+    }
+
+    pub fn len(&self) -> usize {
+        self.reserves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.reserves.is_empty()
+    }
+}
+
+#[derive(Debug)]
+struct Inner {
+    shards: Vec<RwLock<HashMap<Address, PoolReserves>>>,
+    // Bumped after every write; a snapshot older than this is rebuilt on demand
+    version: AtomicU64,
+    snapshot: ArcSwap<ReserveSnapshot>,
+    rebuild: Mutex<()>,
+}
+
+impl Default for Inner {
+    fn default() -> Self {
+        Self {
+            shards: (0..SHARDS).map(|_| RwLock::default()).collect(),
+            version: AtomicU64::new(0),
+            snapshot: ArcSwap::default(),
+            rebuild: Mutex::new(()),
+        }
+    }
+}
+
+// Shared last-known reserves per pool, written by RPC reads and event streams and read by
+// the simulators. Writers only contend within a shard; hot loops price from `snapshot()`
+#[derive(Debug, Clone, Default)]
+pub struct ReserveCache {
+    inner: Arc<Inner>,
+}
+
+impl ReserveCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn shard(&self, pool: &Address) -> &RwLock<HashMap<Address, PoolReserves>> {
+        &self.inner.shards[pool.as_bytes()[0] as usize % SHARDS]
+    }
+
+    fn written(&self) {
+        self.inner.version.fetch_add(1, Ordering::Release);
+    }
+
+    pub fn get(&self, pool: &Address) -> Option<PoolReserves> {
+        self.shard(pool).read().unwrap().get(pool).copied()
+    }
+
+    // Ignores updates older than what is already cached
+    pub fn update(&self, pool: Address, reserve0: U256, reserve1: U256, block_number: u64) {
+        {
+            let mut shard = self.shard(&pool).write().unwrap();
+            let entry = shard.entry(pool).or_insert(PoolReserves {
+                reserve0,
+                reserve1,
+                block_number,
+            });
+
+            if block_number >= entry.block_number {
+                *entry = PoolReserves {
+                    reserve0,
+                    reserve1,
+                    block_number,
+                };
+            }
+        }
+        self.written();
+    }
+
+    // Unconditional overwrite, used when a cached entry is known to be wrong
+    pub fn replace(&self, pool: Address, reserves: PoolReserves) {
+        self.shard(&pool).write().unwrap().insert(pool, reserves);
+        self.written();
+    }
+
+    pub fn remove(&self, pool: &Address) {
+        self.shard(pool).write().unwrap().remove(pool);
+        self.written();
+    }
+
+    // Current state as an immutable map. Returns the last snapshot without locking when
+    // nothing was written since; otherwise it is rebuilt shard by shard once and shared
+    pub fn snapshot(&self) -> ReserveSnapshot {
+        let version = self.inner.version.load(Ordering::Acquire);
+        let current = self.inner.snapshot.load();
+        if current.version == version {
+            return ReserveSnapshot::clone(&current);
+        }
+
+        // Concurrent callers wait for one rebuild instead of each copying every shard
+        let _rebuild = self.inner.rebuild.lock().unwrap();
+        let version = self.inner.version.load(Ordering::Acquire);
+        let current = self.inner.snapshot.load();
+        if current.version == version {
+            return ReserveSnapshot::clone(&current);
+        }
+        let mut reserves = HashMap::with_capacity(current.len());
+        for shard in &self.inner.shards {
+            reserves.extend(shard.read().unwrap().iter().map(|(&pool, &entry)| (pool, entry)));
+        }
+        let snapshot = ReserveSnapshot {
+            reserves: Arc::new(reserves),
+            version,
+        };
+        self.inner.snapshot.store(Arc::new(snapshot.clone()));
+        snapshot
+    }
+
+    pub fn pools(&self) -> Vec<Address> {
+        self.inner
+            .shards
+            .iter()
+            .flat_map(|shard| shard.read().unwrap().keys().copied().collect::<Vec<_>>())
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.shards.iter().map(|shard| shard.read().unwrap().len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_snapshot_follows_writes_across_shards() {
+        let cache = ReserveCache::new();
+        assert!(cache.snapshot().is_empty());
+
+        let pools: Vec<Address> = (0..64u8).map(Address::repeat_byte).collect();
+        for (i, &pool) in pools.iter().enumerate() {
+            cache.update(pool, U256::from(i), U256::from(i), 10);
+        }
+        assert_eq!(cache.len(), 64);
+
+        let snapshot = cache.snapshot();
+        assert_eq!(snapshot.len(), 64);
+        assert_eq!(snapshot.get(&pools[5]).unwrap().reserve0, U256::from(5u64));
+
+        // Stale updates are dropped; newer ones reach the next snapshot, not the old one
+        cache.update(pools[5], U256::from(1u64), U256::from(1u64), 9);
+        cache.update(pools[6], U256::from(99u64), U256::from(99u64), 11);
+        cache.remove(&pools[7]);
+        let fresh = cache.snapshot();
+        assert_eq!(fresh.get(&pools[5]).unwrap().reserve0, U256::from(5u64));
+        assert_eq!(fresh.get(&pools[6]).unwrap().block_number, 11);
+        assert!(fresh.get(&pools[7]).is_none());
+        assert!(snapshot.get(&pools[7]).is_some());
+
+        // Unchanged cache hands out the same map
+        assert!(Arc::ptr_eq(&fresh.reserves, &cache.snapshot().reserves));
+    }
+}
@@ -0,0 +1,237 @@
+// src/reserve_watchdog.rs
+use anyhow::{anyhow, Result};
+use ethers::{
+    providers::Middleware,
+    types::{Address, BlockId, U256},
+};
+use log::{debug, warn};
+use rand::seq::SliceRandom;
+use serde::Deserialize;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::alerts::{AlertLevel, Alerts};
+use crate::metrics::{
+    RESERVE_CHECKS_TOTAL, RESERVE_DIVERGENCES_TOTAL, RESERVE_DIVERGENCE_BPS, RESERVE_RESYNCS_TOTAL,
+};
+use crate::reserve_cache::{PoolReserves, ReserveCache};
+use crate::bindings::IUniswapV2Pair;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ReserveWatchdogSettings {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+    // Pools re-read from RPC per check
+    #[serde(default = "default_sample_size")]
+    pub sample_size: usize,
+    #[serde(default = "default_tolerance_bps")]
+    pub tolerance_bps: f64,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_interval_secs() -> u64 {
+    30
+}
+
+fn default_sample_size() -> usize {
+    10
+}
+
+fn default_tolerance_bps() -> f64 {
+    10.0
+}
+
+impl Default for ReserveWatchdogSettings {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            interval_secs: default_interval_secs(),
+            sample_size: default_sample_size(),
+            tolerance_bps: default_tolerance_bps(),
+        }
+    }
+}
+
+// Largest relative difference of the two reserves, in basis points of the fresh value
+pub fn divergence_bps(cached: &PoolReserves, fresh: &PoolReserves) -> f64 {
+    let side = |cached: U256, fresh: U256| {
+        if fresh.is_zero() {
+            return if cached.is_zero() { 0.0 } else { f64::INFINITY };
+        }
+        let diff = if cached > fresh { cached - fresh } else { fresh - cached };
+        (diff.saturating_mul(U256::from(10_000u64)) / fresh).low_u128() as f64
+    };
+    side(cached.reserve0, fresh.reserve0).max(side(cached.reserve1, fresh.reserve1))
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct WatchdogReport {
+    pub checked: usize,
+    pub diverged: usize,
+    pub full_resync: bool,
+}
+
+// Periodically spot-checks cached reserves against the chain. Diverged pools are
+// overwritten with the fresh read; when most of the sample is off (missed events,
+// a lagging provider) every cached pool is re-read.
+pub struct ReserveWatchdog<M> {
+    provider: Arc<M>,
+    cache: ReserveCache,
+    settings: ReserveWatchdogSettings,
+    alerts: Alerts,
+}
+
+impl<M: Middleware + 'static> ReserveWatchdog<M> {
+    pub fn new(provider: Arc<M>, cache: ReserveCache, settings: ReserveWatchdogSettings, alerts: Alerts) -> Self {
+        Self {
+            provider,
+            cache,
+            settings,
+            alerts,
+        }
+    }
+
+    pub async fn run(&self) {
+        let mut interval = tokio::time::interval(Duration::from_secs(self.settings.interval_secs.max(1)));
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.check_once().await {
+                warn!("Reserve watchdog check failed: {:?}", e);
+            }
+        }
+    }
+
+    async fn fetch(&self, pool: Address, block: BlockId, block_number: u64) -> Result<PoolReserves> {
+        let pair = IUniswapV2Pair::new(pool, self.provider.clone());
+        let (reserve0, reserve1, _) = pair
+            .get_reserves()
+            .block(block)
+            .call()
+            .await
+            .map_err(|e| anyhow!("getReserves on {:?} failed: {e}", pool))?;
+        Ok(PoolReserves {
+            reserve0: reserve0.into(),
+            reserve1: reserve1.into(),
+            block_number,
+        })
+    }
+
+    pub async fn check_once(&self) -> Result<WatchdogReport> {
+        let mut pools = self.cache.pools();
+        pools.shuffle(&mut rand::thread_rng());
+        pools.truncate(self.settings.sample_size);
+
+        let block_number = self.provider.get_block_number().await
+            .map_err(|e| anyhow!("Failed to fetch block number: {e}"))?
+            .as_u64();
+        let block = BlockId::Number(block_number.into());
+
+        let mut report = WatchdogReport::default();
+        for pool in pools {
+            let Some(cached) = self.cache.get(&pool) else {
+                continue;
+            };
+            let fresh = self.fetch(pool, block, block_number).await?;
+            report.checked += 1;
+            RESERVE_CHECKS_TOTAL.inc();
+
+            let divergence = divergence_bps(&cached, &fresh);
+            RESERVE_DIVERGENCE_BPS.observe(divergence.min(1e6));
+            if divergence > self.settings.tolerance_bps {
+                report.diverged += 1;
+                RESERVE_DIVERGENCES_TOTAL.inc();
+                self.cache.replace(pool, fresh);
+                self.alerts.emit(
+                    AlertLevel::Warning,
+                    "reserve_watchdog",
+                    format!(
+                        "Pool {:?} reserves diverged by {:.1} bps (cached at block {}, fresh at {})",
+                        pool, divergence, cached.block_number, block_number
+                    ),
+                );
+            }
+        }
+
+        if report.diverged * 2 > report.checked {
+            report.full_resync = true;
+            RESERVE_RESYNCS_TOTAL.inc();
+            self.alerts.emit(
+                AlertLevel::Critical,
+                "reserve_watchdog",
+                format!(
+                    "{} of {} sampled pools diverged, resyncing the whole reserve cache",
+                    report.diverged, report.checked
+                ),
+            );
+            self.resync(block, block_number).await?;
+        }
+
+        debug!(
+            "Reserve watchdog: {} checked, {} diverged at block {}",
+            report.checked, report.diverged, block_number
+        );
+        Ok(report)
+    }
+
+    async fn resync(&self, block: BlockId, block_number: u64) -> Result<()> {
+        for pool in self.cache.pools() {
+            match self.fetch(pool, block, block_number).await {
+                Ok(fresh) => self.cache.replace(pool, fresh),
+                Err(e) => {
+                    // Better to re-read on next use than to price from a stale entry
+                    warn!("Dropping {:?} from the reserve cache: {:?}", pool, e);
+                    self.cache.remove(&pool);
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{address, quantity, reserves_output, MockChain};
+
+    fn reserves(reserve0: u64, reserve1: u64) -> PoolReserves {
+        PoolReserves {
+            reserve0: U256::from(reserve0),
+            reserve1: U256::from(reserve1),
+            block_number: 1,
+        }
+    }
+
+    #[test]
+    fn test_divergence_uses_worst_side() {
+        assert_eq!(divergence_bps(&reserves(100, 200), &reserves(100, 200)), 0.0);
+        assert_eq!(divergence_bps(&reserves(100, 210), &reserves(100, 200)), 500.0);
+        assert_eq!(divergence_bps(&reserves(99, 200), &reserves(100, 200)), 100.0);
+        assert!(divergence_bps(&reserves(1, 1), &reserves(0, 1)).is_infinite());
+    }
+
+    #[tokio::test]
+    async fn test_check_replaces_diverged_pool() {
+        let chain = MockChain::new();
+        let pool = address(10);
+        let cache = ReserveCache::new();
+        cache.update(pool, U256::from(1_000u64), U256::from(2_000u64), 1);
+
+        // Block number, the sampled read, then the resync a lone diverged pool triggers
+        chain.script(vec![
+            quantity(5u64),
+            reserves_output(1_100, 2_000),
+            reserves_output(1_100, 2_000),
+        ]);
+        let watchdog = ReserveWatchdog::new(chain.provider(), cache.clone(), ReserveWatchdogSettings::default(), Alerts::default());
+        let report = watchdog.check_once().await.unwrap();
+
+        assert_eq!((report.checked, report.diverged, report.full_resync), (1, 1, true));
+        let fresh = cache.get(&pool).unwrap();
+        assert_eq!((fresh.reserve0, fresh.block_number), (U256::from(1_100u64), 5));
+    }
+}
@@ -0,0 +1,119 @@
+// src/round_trip.rs
+use ethers::types::U256;
+use serde::Deserialize;
+
+use crate::amm_math::FEE_DENOMINATOR;
+use crate::token_registry::TokenTax;
+
+const BPS: u64 = 10_000;
+
+// Settlement of a single-token flash loan the way the executor does it: after the last hop
+// the borrow token balance has to cover the borrowed amount plus the lender's premium, sent
+// back in one transfer, and whatever is left over goes to the owner. Routes are priced net
+// of that round trip so a spread that only pays before the premium is not sent
+#[derive(Debug, Clone, Deserialize)]
+pub struct RoundTripSettings {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    // Fee tier of the V3 pool the executor flashes from; its flash premium is the same fee
+    #[serde(default = "default_flash_pool_fee")]
+    pub flash_pool_fee: u32,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_flash_pool_fee() -> u32 {
+    3000
+}
+
+impl Default for RoundTripSettings {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            flash_pool_fee: default_flash_pool_fee(),
+        }
+    }
+}
+
+// UniswapV3 `flash` charges `mulDivRoundingUp(amount, fee, 1e6)`
+pub fn flash_premium(amount: U256, fee: u32) -> U256 {
+    let scaled = amount * U256::from(fee);
+    let denominator = U256::from(FEE_DENOMINATOR);
+    scaled / denominator + if (scaled % denominator).is_zero() { 0 } else { 1 }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RoundTrip {
+    pub borrowed: U256,
+    pub premium: U256,
+    // Sent back to the lender so it receives borrowed + premium after the token's transfer tax
+    pub repayment: U256,
+    // Borrow token held after the last hop
+    pub end_balance: U256,
+}
+
+impl RoundTrip {
+    pub fn new(borrowed: U256, end_balance: U256, flash_fee: u32, tax: TokenTax) -> Self {
+        let premium = flash_premium(borrowed, flash_fee);
+        let owed = borrowed + premium;
+        let kept = BPS.saturating_sub(tax.sell_bps as u64);
+        let repayment = if kept == 0 {
+            U256::MAX
+        } else {
+            (owed * U256::from(BPS) + U256::from(kept - 1)) / U256::from(kept)
+        };
+        Self {
+            borrowed,
+            premium,
+            repayment,
+            end_balance,
+        }
+    }
+
+    // What the round trip costs on top of the borrowed amount
+    pub fn cost(&self) -> U256 {
+        self.repayment.saturating_sub(self.borrowed)
+    }
+
+    // Transferred to the owner; zero when the executor could not repay
+    pub fn profit(&self) -> U256 {
+        self.end_balance.saturating_sub(self.repayment)
+    }
+
+    // The executor's checks: the lender is repaid and at least `min_profit` is left
+    pub fn clears(&self, min_profit: U256) -> bool {
+        self.end_balance >= self.repayment.saturating_add(min_profit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_repays_premium_and_tax_before_profit() {
+        let ether = U256::exp10(18);
+        // 0.3% of 100 tokens, and a rounding unit on amounts that do not divide evenly
+        assert_eq!(flash_premium(ether * 100, 3000), ether * 3 / 10);
+        assert_eq!(flash_premium(U256::from(1_001u64), 3000), U256::from(4u64));
+
+        // The hops gain 0.5%, the premium takes 0.3% of it
+        let trip = RoundTrip::new(ether * 100, ether * 1005 / 10, 3000, TokenTax::default());
+        assert_eq!(trip.repayment, ether * 1003 / 10);
+        assert_eq!(trip.profit(), ether / 5);
+        assert!(trip.clears(ether / 5));
+        assert!(!trip.clears(ether / 5 + 1));
+
+        // A 1% transfer tax on the borrow token shorts the lender unless grossed up
+        let taxed = TokenTax {
+            sell_bps: 100,
+            ..Default::default()
+        };
+        let trip = RoundTrip::new(ether * 100, ether * 1005 / 10, 3000, taxed);
+        assert!(taxed.after_sell(trip.repayment) >= ether * 1003 / 10);
+        assert_eq!(trip.profit(), U256::zero());
+        assert!(!trip.clears(U256::zero()));
+    }
+}
@@ -0,0 +1,392 @@
+// src/route_search.rs
+use ethers::types::{Address, U256};
+use log::debug;
+use serde::Deserialize;
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap};
+
+use crate::amm_math::{get_amount_out, FEE_DENOMINATOR};
+
+// One directed swap through a pool
+#[derive(Debug, Clone, Copy)]
+pub struct PoolEdge {
+    pub pool: Address,
+    pub token_in: Address,
+    pub token_out: Address,
+    pub reserve_in: U256,
+    pub reserve_out: U256,
+    pub fee: u32,
+}
+
+impl PoolEdge {
+    // Fee-adjusted spot rate; an upper bound on the executed rate at any size
+    fn spot_rate(&self) -> f64 {
+        if self.reserve_in.is_zero() {
+            return 0.0;
+        }
+        let fee_factor = (FEE_DENOMINATOR - self.fee) as f64 / FEE_DENOMINATOR as f64;
+        self.reserve_out.as_u128() as f64 / self.reserve_in.as_u128() as f64 * fee_factor
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TokenGraph {
+    edges: HashMap<Address, Vec<PoolEdge>>,
+}
+
+impl TokenGraph {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Adds both swap directions of a pool
+    pub fn add_pool(
+        &mut self,
+        pool: Address,
+        token0: Address,
+        token1: Address,
+        reserve0: U256,
+        reserve1: U256,
+        fee: u32,
+    ) {
+        self.edges.entry(token0).or_default().push(PoolEdge {
+            pool,
+            token_in: token0,
+            token_out: token1,
+            reserve_in: reserve0,
+            reserve_out: reserve1,
+            fee,
+        });
+        self.edges.entry(token1).or_default().push(PoolEdge {
+            pool,
+            token_in: token1,
+            token_out: token0,
+            reserve_in: reserve1,
+            reserve_out: reserve0,
+            fee,
+        });
+    }
+
+    pub fn edges_from(&self, token: &Address) -> &[PoolEdge] {
+        self.edges.get(token).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    pub fn tokens(&self) -> impl Iterator<Item = &Address> {
+        self.edges.keys()
+    }
+}
+
+// Exponentially weighted spread (bps) observed per pool in recent blocks
+#[derive(Debug, Clone, Default)]
+pub struct SpreadHistory {
+    scores: HashMap<Address, f64>,
+}
+
+impl SpreadHistory {
+    const ALPHA: f64 = 0.2;
+
+    pub fn record(&mut self, pool: Address, spread_bps: f64) {
+        let score = self.scores.entry(pool).or_insert(spread_bps);
+        *score = Self::ALPHA * spread_bps + (1.0 - Self::ALPHA) * *score;
+    }
+
+    pub fn score(&self, pool: &Address) -> f64 {
+        self.scores.get(pool).copied().unwrap_or(0.0)
+    }
+
+    // Widest recent spread among the pools trading `token`
+    pub fn strongest(&self, graph: &TokenGraph, token: &Address) -> f64 {
+        graph.edges_from(token).iter().map(|edge| self.score(&edge.pool)).fold(0.0, f64::max)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RouteCandidate {
+    pub tokens: Vec<Address>, // start .. start
+    pub pools: Vec<Address>,
+    pub amount_in: U256,
+    pub amount_out: U256,
+}
+
+impl RouteCandidate {
+    pub fn profit(&self) -> U256 {
+        self.amount_out.saturating_sub(self.amount_in)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SearchStats {
+    pub nodes_expanded: usize,
+    pub truncated: bool,
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    upper_bound: f64, // optimistic final amount of the start token
+    spread_score: f64,
+    token: Address,
+    amount: U256,
+    tokens: Vec<Address>,
+    pools: Vec<Address>,
+}
+
+impl PartialEq for Node {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Node {}
+
+impl PartialOrd for Node {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Node {
+    // Highest upper bound first, recent spread activity breaks ties
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.upper_bound
+            .total_cmp(&other.upper_bound)
+            .then(self.spread_score.total_cmp(&other.spread_score))
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RouteSearchSettings {
+    // Ceiling; each search's limit is set by the optimizer from the gas price and spread
+    #[serde(default = "default_max_hops")]
+    pub max_hops: usize,
+    #[serde(default = "default_max_nodes_per_block")]
+    pub max_nodes_per_block: usize,
+    // Best local candidates re-simulated on-chain per token pair
+    #[serde(default = "default_verify_top")]
+    pub verify_top: usize,
+}
+
+fn default_max_hops() -> usize {
+    4
+}
+
+fn default_max_nodes_per_block() -> usize {
+    5_000
+}
+
+fn default_verify_top() -> usize {
+    3
+}
+
+impl Default for RouteSearchSettings {
+    fn default() -> Self {
+        Self {
+            max_hops: default_max_hops(),
+            max_nodes_per_block: default_max_nodes_per_block(),
+            verify_top: default_verify_top(),
+        }
+    }
+}
+
+// Best-first cyclic route search with a per-block expansion budget
+#[derive(Debug, Clone)]
+pub struct RouteSearch {
+    pub max_hops: usize,
+    pub max_nodes_per_block: usize,
+    current_block: u64,
+    nodes_used: usize,
+}
+
+impl RouteSearch {
+    pub fn new(max_hops: usize, max_nodes_per_block: usize) -> Self {
+        Self {
+            max_hops,
+            max_nodes_per_block,
+            current_block: 0,
+            nodes_used: 0,
+        }
+    }
+
+    // Resets the expansion budget when a new block starts
+    pub fn begin_block(&mut self, block: u64) {
+        if block != self.current_block {
+            self.current_block = block;
+            self.nodes_used = 0;
+        }
+    }
+
+    pub fn remaining_budget(&self) -> usize {
+        self.max_nodes_per_block.saturating_sub(self.nodes_used)
+    }
+
+    // best_rate[r][token]: max product of spot rates from `token` back to `start` in <= r hops.
+    // Executed AMM rates never beat spot, so this never underestimates what a path can return.
+    fn closing_bounds(&self, graph: &TokenGraph, start: Address) -> Vec<HashMap<Address, f64>> {
+        let mut bounds: Vec<HashMap<Address, f64>> = vec![HashMap::new(); self.max_hops + 1];
+        bounds[0].insert(start, 1.0);
+
+        for hops in 1..=self.max_hops {
+            let mut layer = bounds[hops - 1].clone();
+            for token in graph.tokens() {
+                for edge in graph.edges_from(token) {
+                    if let Some(&next) = bounds[hops - 1].get(&edge.token_out) {
+                        let candidate = edge.spot_rate() * next;
+                        let best = layer.entry(*token).or_insert(0.0);
+                        if candidate > *best {
+                            *best = candidate;
+                        }
+                    }
+                }
+            }
+            bounds[hops] = layer;
+        }
+
+        bounds
+    }
+
+    pub fn search(
+        &mut self,
+        graph: &TokenGraph,
+        history: &SpreadHistory,
+        start: Address,
+        amount_in: U256,
+    ) -> (Vec<RouteCandidate>, SearchStats) {
+        let bounds = self.closing_bounds(graph, start);
+        let mut stats = SearchStats::default();
+        let mut found: Vec<RouteCandidate> = Vec::new();
+        let mut best_out = amount_in.as_u128() as f64;
+
+        let mut heap = BinaryHeap::new();
+        heap.push(Node {
+            upper_bound: f64::MAX,
+            spread_score: 0.0,
+            token: start,
+            amount: amount_in,
+            tokens: vec![start],
+            pools: Vec::new(),
+        });
+
+        while let Some(node) = heap.pop() {
+            // Admissible bound: nothing left in the queue can beat the best cycle found
+            if node.upper_bound <= best_out {
+                break;
+            }
+            if self.nodes_used >= self.max_nodes_per_block {
+                stats.truncated = true;
+                break;
+            }
+            self.nodes_used += 1;
+            stats.nodes_expanded += 1;
+
+            let remaining = self.max_hops - node.pools.len();
+            for edge in graph.edges_from(&node.token) {
+                if node.pools.contains(&edge.pool) {
+                    continue;
+                }
+
+                let amount = get_amount_out(node.amount, edge.reserve_in, edge.reserve_out, edge.fee);
+                if amount.is_zero() {
+                    continue;
+                }
+
+                let mut tokens = node.tokens.clone();
+                tokens.push(edge.token_out);
+                let mut pools = node.pools.clone();
+                pools.push(edge.pool);
+
+                if edge.token_out == start {
+                    if amount > amount_in {
+                        best_out = best_out.max(amount.as_u128() as f64);
+                        found.push(RouteCandidate {
+                            tokens,
+                            pools,
+                            amount_in,
+                            amount_out: amount,
+                        });
+                    }
+                    continue;
+                }
+
+                if remaining <= 1 {
+                    continue;
+                }
+
+                let closing = bounds[remaining - 1].get(&edge.token_out).copied().unwrap_or(0.0);
+                let upper_bound = amount.as_u128() as f64 * closing;
+                if upper_bound <= best_out {
+                    continue;
+                }
+
+                heap.push(Node {
+                    upper_bound,
+                    spread_score: node.spread_score + history.score(&edge.pool),
+                    token: edge.token_out,
+                    amount,
+                    tokens,
+                    pools,
+                });
+            }
+        }
+
+        found.sort_by_key(|route| Reverse(route.amount_out));
+        debug!(
+            "Route search from {:?}: {} candidates, {} nodes expanded{}",
+            start,
+            found.len(),
+            stats.nodes_expanded,
+            if stats.truncated { " (budget exhausted)" } else { "" }
+        );
+        (found, stats)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::amm_math::FeeSettings;
+    use crate::fixtures::{address as addr, PoolSet};
+
+    #[test]
+    fn test_finds_triangular_cycle_within_budget() {
+        let (a, b, c) = (addr(1), addr(2), addr(3));
+        let e18 = U256::exp10(18);
+        // a -> b -> c -> a is mispriced by ~5%
+        let graph = PoolSet::new()
+            .pool(addr(10), a, b, e18 * 1_000, e18 * 2_000)
+            .pool(addr(11), b, c, e18 * 2_000, e18 * 4_000)
+            .pool(addr(12), c, a, e18 * 4_000, e18 * 1_050)
+            .graph(&FeeSettings::default());
+
+        let mut search = RouteSearch::new(3, 100);
+        search.begin_block(1);
+        let (routes, stats) = search.search(&graph, &SpreadHistory::default(), a, e18);
+
+        assert!(!stats.truncated);
+        let best = &routes[0];
+        assert_eq!(best.pools, vec![addr(10), addr(11), addr(12)]);
+        assert!(best.profit() > U256::zero());
+
+        // Budget is shared across the block
+        let mut tight = RouteSearch::new(3, 1);
+        tight.begin_block(1);
+        let (_, stats) = tight.search(&graph, &SpreadHistory::default(), a, e18);
+        assert!(stats.truncated || stats.nodes_expanded <= 1);
+    }
+
+    #[test]
+    fn test_cheaper_fork_fee_wins_the_route() {
+        let (a, b) = (addr(1), addr(2));
+        let e18 = U256::exp10(18);
+        // Two identical a -> b -> a cycles; only the fees of pool 20 differ
+        let pools = PoolSet::new()
+            .pool(addr(10), a, b, e18 * 1_000, e18 * 2_100)
+            .pool(addr(11), b, a, e18 * 2_000, e18 * 1_000)
+            .pool(addr(20), a, b, e18 * 1_000, e18 * 2_100);
+        let mut fees = FeeSettings::default();
+        fees.pools.insert(addr(20), 2000);
+
+        let mut search = RouteSearch::new(2, 100);
+        search.begin_block(1);
+        let (routes, _) = search.search(&pools.graph(&fees), &SpreadHistory::default(), a, e18);
+        assert_eq!(routes[0].pools, vec![addr(20), addr(11)]);
+    }
+}
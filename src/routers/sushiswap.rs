@@ -1,9 +1,11 @@
 use ethers::{
     abi::Abi,
     prelude::*,
-    types::{Address, Bytes, U256},
+    types::{Address, BlockId, Bytes, U256},
 };
 use std::sync::Arc;
+
+use crate::rpc_usage::MeteredWs;
 use anyhow::Result;
 use serde_json;
 
@@ -14,11 +16,11 @@ pub const DEFAULT_FEE: u32 = 3000; // 0.3%
 #[derive(Debug, Clone)]
 pub struct SushiswapRouter {
     pub address: Address,
-    provider: Arc<Provider<Ws>>,
+    provider: Arc<Provider<MeteredWs>>,
 }
 
 impl SushiswapRouter {
-    pub fn new(provider: Arc<Provider<Ws>>) -> Self {
+    pub fn new(provider: Arc<Provider<MeteredWs>>) -> Self {
         Self {
             address: SUSHISWAP_ROUTER.parse().unwrap(),
             provider,
@@ -36,6 +38,7 @@ impl SushiswapRouter {
         &self,
         amount_in: U256,
         path: &[Address],
+        block: BlockId,
     ) -> Result<Vec<U256>> {
         let abi = Self::load_sushiswap_abi()?;
         let contract = Contract::new(
@@ -46,6 +49,7 @@ impl SushiswapRouter {
 
         let amounts: Vec<U256> = contract
             .method::<_, Vec<U256>>("getAmountsOut", (amount_in, path.to_vec()))?
+            .block(block)
             .call()
             .await?;
 
@@ -60,19 +64,18 @@ impl SushiswapRouter {
         to: Address,
         deadline: U256,
     ) -> Result<Bytes> {
-        let abi = Self::load_sushiswap_abi()?;
-        let contract = Contract::new(
-            self.address,
-            abi,
-            self.provider.clone(),
-        );
+        Self::encode_swap_exact_tokens_for_tokens(amount_in, amount_out_min, path, to, deadline)
+    }
 
-        Ok(contract
-            .method::<_, Bytes>(
-                "swapExactTokensForTokens",
-                (amount_in, amount_out_min, path, to, deadline),
-            )?
-            .calldata()
-            .unwrap())
+    // Calldata only, no provider involved; covered by the golden tests in routers/mod.rs
+    pub fn encode_swap_exact_tokens_for_tokens(
+        amount_in: U256,
+        amount_out_min: U256,
+        path: Vec<Address>,
+        to: Address,
+        deadline: U256,
+    ) -> Result<Bytes> {
+        let contract = BaseContract::from(Self::load_sushiswap_abi()?);
+        Ok(contract.encode("swapExactTokensForTokens", (amount_in, amount_out_min, path, to, deadline))?)
     }
 }
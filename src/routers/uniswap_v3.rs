@@ -4,6 +4,8 @@ use ethers::{
     types::{Address, Bytes, U256},
 };
 use std::sync::Arc;
+
+use crate::rpc_usage::MeteredWs;
 use anyhow::Result;
 use serde_json;
 
@@ -15,11 +17,13 @@ pub const FEE_TIERS: [u32; 3] = [500, 3000, 10000];
 #[derive(Debug, Clone)]
 pub struct UniswapV3Router {
     pub address: Address,
-    provider: Arc<Provider<Ws>>,
+    // Calldata is encoded without it; kept for on-chain quoting like the V2 adapters
+    #[allow(dead_code)]
+    provider: Arc<Provider<MeteredWs>>,
 }
 
 impl UniswapV3Router {
-    pub fn new(provider: Arc<Provider<Ws>>) -> Self {
+    pub fn new(provider: Arc<Provider<MeteredWs>>) -> Self {
         Self {
             address: UNISWAP_V3_ROUTER.parse().unwrap(),
             provider,
@@ -37,31 +41,7 @@ impl UniswapV3Router {
         &self,
         params: ExactInputSingleParams,
     ) -> Result<Bytes> {
-        let abi = Self::load_uniswap_v3_abi()?;
-        let contract = Contract::new(
-            self.address,
-            abi,
-            self.provider.clone(),
-        );
-
-        // Instead of passing the struct directly, pass individual parameters
-        // This avoids the Tokenizable trait requirement
-        Ok(contract
-            .method::<_, Bytes>(
-                "exactInputSingle",
-                (
-                    params.token_in,
-                    params.token_out,
-                    params.fee,
-                    params.recipient,
-                    params.deadline,
-                    params.amount_in,
-                    params.amount_out_minimum,
-                    params.sqrt_price_limit_x96,
-                ),
-            )?
-            .calldata()
-            .unwrap())
+        Self::encode_exact_input_single(&params)
     }
 
     // Alternative method that takes individual parameters
@@ -76,29 +56,33 @@ impl UniswapV3Router {
         amount_out_minimum: U256,
         sqrt_price_limit_x96: U256,
     ) -> Result<Bytes> {
-        let abi = Self::load_uniswap_v3_abi()?;
-        let contract = Contract::new(
-            self.address,
-            abi,
-            self.provider.clone(),
-        );
+        Self::encode_exact_input_single(&ExactInputSingleParams {
+            token_in,
+            token_out,
+            fee,
+            recipient,
+            deadline,
+            amount_in,
+            amount_out_minimum,
+            sqrt_price_limit_x96,
+        })
+    }
 
-        Ok(contract
-            .method::<_, Bytes>(
-                "exactInputSingle",
-                (
-                    token_in,
-                    token_out,
-                    fee,
-                    recipient,
-                    deadline,
-                    amount_in,
-                    amount_out_minimum,
-                    sqrt_price_limit_x96,
-                ),
-            )?
-            .calldata()
-            .unwrap())
+    // Calldata only, no provider involved; covered by the golden tests in routers/mod.rs.
+    // The router takes the parameters as one struct, so they are encoded as a single tuple
+    pub fn encode_exact_input_single(params: &ExactInputSingleParams) -> Result<Bytes> {
+        let contract = BaseContract::from(Self::load_uniswap_v3_abi()?);
+        let params = (
+            params.token_in,
+            params.token_out,
+            params.fee,
+            params.recipient,
+            params.deadline,
+            params.amount_in,
+            params.amount_out_minimum,
+            params.sqrt_price_limit_x96,
+        );
+        Ok(contract.encode("exactInputSingle", (params,))?)
     }
 }
 
@@ -0,0 +1,224 @@
+// src/rpc_batch.rs
+use anyhow::{anyhow, bail, Result};
+use ethers::types::{Transaction, H256};
+use futures::{SinkExt, StreamExt};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::time::Duration;
+use tokio::net::TcpStream;
+use tokio::sync::Mutex;
+use tokio_tungstenite::{tungstenite::Message, MaybeTlsStream, WebSocketStream};
+
+use crate::metrics::{RPC_BATCH_SIZE, RPC_CALLS_TOTAL};
+
+// Pending tx bodies fetched as JSON-RPC batches instead of one eth_getTransactionByHash per
+// hash. The batch size adapts to the node: it grows while full batches come back within
+// `target_latency_ms` and halves when one does not
+#[derive(Debug, Clone, Deserialize)]
+pub struct BatchSettings {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    // Batch endpoint; empty uses POLYGON_WS_URL
+    #[serde(default)]
+    pub url: String,
+    #[serde(default = "default_min_size")]
+    pub min_size: usize,
+    #[serde(default = "default_max_size")]
+    pub max_size: usize,
+    #[serde(default = "default_target_latency_ms")]
+    pub target_latency_ms: u64,
+    // Longest a hash waits for its batch to fill
+    #[serde(default = "default_max_wait_ms")]
+    pub max_wait_ms: u64,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_min_size() -> usize {
+    4
+}
+
+fn default_max_size() -> usize {
+    100
+}
+
+fn default_target_latency_ms() -> u64 {
+    150
+}
+
+fn default_max_wait_ms() -> u64 {
+    20
+}
+
+impl Default for BatchSettings {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            url: String::new(),
+            min_size: default_min_size(),
+            max_size: default_max_size(),
+            target_latency_ms: default_target_latency_ms(),
+            max_wait_ms: default_max_wait_ms(),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct BatchSizer {
+    settings: BatchSettings,
+    size: usize,
+}
+
+impl BatchSizer {
+    pub fn new(settings: BatchSettings) -> Self {
+        let size = settings.min_size.max(1);
+        RPC_BATCH_SIZE.set(size as f64);
+        Self { settings, size }
+    }
+
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    // Only full batches say the node could take more
+    pub fn observe(&mut self, len: usize, latency: Duration) {
+        let (min, max) = (self.settings.min_size.max(1), self.settings.max_size.max(1));
+        if latency > Duration::from_millis(self.settings.target_latency_ms) {
+            self.size = (self.size / 2).max(min);
+        } else if len >= self.size {
+            self.size = (self.size + (self.size / 4).max(1)).min(max);
+        }
+        RPC_BATCH_SIZE.set(self.size as f64);
+    }
+}
+
+type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+// Its own connection, since the ws transport of the provider sends single requests only;
+// reconnects on the call after a failure
+pub struct BatchClient {
+    url: String,
+    socket: Mutex<Option<Socket>>,
+    next_id: Mutex<u64>,
+}
+
+impl BatchClient {
+    pub fn new(url: String) -> Self {
+        Self {
+            url,
+            socket: Mutex::new(None),
+            next_id: Mutex::new(1),
+        }
+    }
+
+    // Bodies in the order of `hashes`, None for txs the node no longer has
+    pub async fn get_transactions(&self, hashes: &[H256]) -> Result<Vec<Option<Transaction>>> {
+        if hashes.is_empty() {
+            return Ok(Vec::new());
+        }
+        let first_id = {
+            let mut next_id = self.next_id.lock().await;
+            let first_id = *next_id;
+            *next_id += hashes.len() as u64;
+            first_id
+        };
+        let requests: Vec<Value> = hashes
+            .iter()
+            .zip(first_id..)
+            .map(|(hash, id)| json!({"jsonrpc": "2.0", "id": id, "method": "eth_getTransactionByHash", "params": [hash]}))
+            .collect();
+        RPC_CALLS_TOTAL
+            .with_label_values(&["ws", "eth_getTransactionByHash"])
+            .inc_by(hashes.len() as u64);
+
+        let mut socket = self.socket.lock().await;
+        let response = match self.exchange(&mut socket, &Value::Array(requests)).await {
+            Ok(response) => response,
+            Err(e) => {
+                *socket = None;
+                return Err(e);
+            }
+        };
+        batch_results(&response, first_id, hashes.len())
+    }
+
+    async fn exchange(&self, socket: &mut Option<Socket>, batch: &Value) -> Result<String> {
+        if socket.is_none() {
+            let (connected, _) = tokio_tungstenite::connect_async(self.url.as_str()).await?;
+            *socket = Some(connected);
+        }
+        let connection = socket.as_mut().ok_or_else(|| anyhow!("Batch connection unavailable"))?;
+        connection.send(Message::Text(batch.to_string())).await?;
+        while let Some(message) = connection.next().await {
+            match message? {
+                // Some nodes answer a batch they reject with a single error object
+                Message::Text(text) if text.trim_start().starts_with('[') => return Ok(text),
+                Message::Text(text) => bail!("Batch request rejected: {}", text),
+                Message::Ping(payload) => connection.send(Message::Pong(payload)).await?,
+                Message::Close(frame) => bail!("Batch connection closed: {:?}", frame),
+                _ => {}
+            }
+        }
+        bail!("Batch connection ended")
+    }
+}
+
+// Batch responses may come back in any order; results are matched to requests by id
+pub fn batch_results(response: &str, first_id: u64, len: usize) -> Result<Vec<Option<Transaction>>> {
+    let responses: Vec<Value> = serde_json::from_str(response)?;
+    let mut results = vec![None; len];
+    for response in responses {
+        let Some(index) = response
+            .get("id")
+            .and_then(Value::as_u64)
+            .and_then(|id| id.checked_sub(first_id))
+            .filter(|&index| index < len as u64)
+        else {
+            continue;
+        };
+        if let Some(result) = response.get("result").filter(|result| !result.is_null()) {
+            results[index as usize] = serde_json::from_value(result.clone()).ok();
+        }
+    }
+    Ok(results)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_batch_size_adapts_to_latency_and_results_match_by_id() {
+        let mut sizer = BatchSizer::new(BatchSettings::default());
+        assert_eq!(sizer.size(), 4);
+        // Full and fast: grows by a quarter, at least one
+        sizer.observe(4, Duration::from_millis(40));
+        assert_eq!(sizer.size(), 5);
+        for _ in 0..30 {
+            sizer.observe(sizer.size(), Duration::from_millis(40));
+        }
+        assert_eq!(sizer.size(), 100);
+        // A partial batch says nothing about capacity
+        sizer.observe(10, Duration::from_millis(40));
+        assert_eq!(sizer.size(), 100);
+        sizer.observe(100, Duration::from_millis(400));
+        assert_eq!(sizer.size(), 50);
+
+        let tx = Transaction {
+            hash: H256::repeat_byte(7),
+            ..Default::default()
+        };
+        let response = json!([
+            {"jsonrpc": "2.0", "id": 12, "result": tx},
+            {"jsonrpc": "2.0", "id": 10, "result": null},
+            {"jsonrpc": "2.0", "id": 11, "error": {"code": -32000, "message": "limit"}},
+            {"jsonrpc": "2.0", "id": 99, "result": tx},
+        ]);
+        let results = batch_results(&response.to_string(), 10, 3).unwrap();
+        assert_eq!(results.len(), 3);
+        assert!(results[0].is_none() && results[1].is_none());
+        assert_eq!(results[2].as_ref().map(|tx| tx.hash), Some(tx.hash));
+    }
+}
@@ -0,0 +1,282 @@
+// src/rpc_usage.rs
+use async_trait::async_trait;
+use ethers::providers::{Http, JsonRpcClient, Provider, ProviderError, PubsubClient, Ws};
+use ethers::types::U256;
+use log::{info, warn};
+use prometheus::core::Collector;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::str::FromStr;
+use std::time::{Duration, Instant};
+
+use crate::metrics::{RPC_CALLS_TOTAL, RPC_PROJECTED_MONTHLY_COST, RPC_PROJECTED_MONTHLY_UNITS};
+
+const MONTH: Duration = Duration::from_secs(30 * 24 * 3600);
+
+pub type MeteredWs = MeteredTransport<Ws>;
+pub type MeteredHttp = MeteredTransport<Http>;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct RpcUsageSettings {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default = "default_check_interval_secs")]
+    pub check_interval_secs: u64,
+    // Plan per provider (ws, http, private); providers without one are only counted
+    #[serde(default)]
+    pub plans: HashMap<String, RpcPlan>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_check_interval_secs() -> u64 {
+    3600
+}
+
+impl Default for RpcUsageSettings {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            check_interval_secs: default_check_interval_secs(),
+            plans: HashMap::new(),
+        }
+    }
+}
+
+// A monthly plan in the provider's billing units: plain requests, or compute units
+// weighted per method
+#[derive(Debug, Clone, Deserialize)]
+pub struct RpcPlan {
+    pub monthly_units: u64,
+    #[serde(default)]
+    pub monthly_price: f64,
+    // Price of a million units beyond the plan; zero for plans that cut off instead
+    #[serde(default)]
+    pub overage_per_million: f64,
+    #[serde(default = "default_units")]
+    pub default_units: u64,
+    #[serde(default)]
+    pub method_units: HashMap<String, u64>,
+}
+
+fn default_units() -> u64 {
+    1
+}
+
+impl RpcPlan {
+    fn units(&self, method: &str) -> u64 {
+        self.method_units.get(method).copied().unwrap_or(self.default_units)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Projection {
+    pub provider: String,
+    pub calls: u64,
+    // Usage extrapolated to a 30-day month
+    pub monthly_units: u64,
+    pub monthly_cost: f64,
+    pub limit: Option<u64>,
+}
+
+impl Projection {
+    pub fn exceeds_plan(&self) -> bool {
+        self.limit.is_some_and(|limit| self.monthly_units > limit)
+    }
+}
+
+impl RpcUsageSettings {
+    // Projects (provider, method, calls) counted over `elapsed` onto a month of each plan
+    pub fn project(&self, calls: &[(String, String, u64)], elapsed: Duration) -> Vec<Projection> {
+        let scale = MONTH.as_secs_f64() / elapsed.as_secs_f64().max(1.0);
+        let mut per_provider: HashMap<&str, (u64, u64)> = HashMap::new();
+        for (provider, method, count) in calls {
+            let units = self.plans.get(provider).map_or(1, |plan| plan.units(method));
+            let entry = per_provider.entry(provider).or_default();
+            entry.0 += count;
+            entry.1 += count * units;
+        }
+
+        let mut projections: Vec<Projection> = per_provider
+            .into_iter()
+            .map(|(provider, (calls, units))| {
+                let monthly_units = (units as f64 * scale) as u64;
+                let plan = self.plans.get(provider);
+                let monthly_cost = plan.map_or(0.0, |plan| {
+                    let overage = monthly_units.saturating_sub(plan.monthly_units) as f64 / 1e6;
+                    plan.monthly_price + overage * plan.overage_per_million
+                });
+                Projection {
+                    provider: provider.to_string(),
+                    calls,
+                    monthly_units,
+                    monthly_cost,
+                    limit: plan.map(|plan| plan.monthly_units),
+                }
+            })
+            .collect();
+        projections.sort_by(|a, b| a.provider.cmp(&b.provider));
+        projections
+    }
+}
+
+// Periodically projects the calls counted so far onto the configured plans, publishing the
+// projections as gauges and warning about providers headed over their plan
+pub struct RpcCostEstimator {
+    settings: RpcUsageSettings,
+    started: Instant,
+}
+
+impl RpcCostEstimator {
+    pub fn new(settings: RpcUsageSettings) -> Self {
+        Self {
+            settings,
+            started: Instant::now(),
+        }
+    }
+
+    pub async fn run(self) {
+        let mut interval = tokio::time::interval(Duration::from_secs(self.settings.check_interval_secs.max(1)));
+        // The first tick is immediate and has nothing to extrapolate from
+        interval.tick().await;
+        loop {
+            interval.tick().await;
+            for projection in self.settings.project(&counted_calls(), self.started.elapsed()) {
+                RPC_PROJECTED_MONTHLY_UNITS
+                    .with_label_values(&[&projection.provider])
+                    .set(projection.monthly_units as f64);
+                RPC_PROJECTED_MONTHLY_COST
+                    .with_label_values(&[&projection.provider])
+                    .set(projection.monthly_cost);
+                if projection.exceeds_plan() {
+                    warn!(
+                        "RPC provider {} is projected at {} units this month, over its plan of {} (cost {:.2})",
+                        projection.provider,
+                        projection.monthly_units,
+                        projection.limit.unwrap_or_default(),
+                        projection.monthly_cost
+                    );
+                } else {
+                    info!(
+                        "RPC provider {}: {} calls, {} units projected this month (cost {:.2})",
+                        projection.provider, projection.calls, projection.monthly_units, projection.monthly_cost
+                    );
+                }
+            }
+        }
+    }
+}
+
+// (provider, method, calls) as counted by the metered transports
+fn counted_calls() -> Vec<(String, String, u64)> {
+    let mut calls = Vec::new();
+    for family in RPC_CALLS_TOTAL.collect() {
+        for metric in family.get_metric() {
+            let label = |name: &str| {
+                metric
+                    .get_label()
+                    .iter()
+                    .find(|pair| pair.get_name() == name)
+                    .map(|pair| pair.get_value().to_string())
+                    .unwrap_or_default()
+            };
+            calls.push((label("provider"), label("method"), metric.get_counter().get_value() as u64));
+        }
+    }
+    calls
+}
+
+// Counts every request by provider and method before handing it to the inner transport.
+// Subscription notifications are pushed by the node and not counted
+#[derive(Debug, Clone)]
+pub struct MeteredTransport<T> {
+    inner: T,
+    provider: &'static str,
+}
+
+impl<T> MeteredTransport<T> {
+    pub fn new(inner: T, provider: &'static str) -> Self {
+        Self { inner, provider }
+    }
+}
+
+#[async_trait]
+impl<T: JsonRpcClient> JsonRpcClient for MeteredTransport<T> {
+    type Error = T::Error;
+
+    async fn request<P, R>(&self, method: &str, params: P) -> Result<R, Self::Error>
+    where
+        P: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        RPC_CALLS_TOTAL.with_label_values(&[self.provider, method]).inc();
+        self.inner.request(method, params).await
+    }
+}
+
+impl<T: PubsubClient> PubsubClient for MeteredTransport<T> {
+    type NotificationStream = T::NotificationStream;
+
+    fn subscribe<I: Into<U256>>(&self, id: I) -> Result<Self::NotificationStream, Self::Error> {
+        self.inner.subscribe(id)
+    }
+
+    fn unsubscribe<I: Into<U256>>(&self, id: I) -> Result<(), Self::Error> {
+        self.inner.unsubscribe(id)
+    }
+}
+
+pub async fn connect_ws(url: &str, provider: &'static str) -> Result<Provider<MeteredWs>, ProviderError> {
+    Ok(Provider::new(MeteredTransport::new(Ws::connect(url).await?, provider)))
+}
+
+pub fn http(url: &str, provider: &'static str) -> anyhow::Result<Provider<MeteredHttp>> {
+    Ok(Provider::new(MeteredTransport::new(Http::from_str(url)?, provider)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_usage_is_projected_onto_a_month_and_priced_against_the_plan() {
+        let plan = RpcPlan {
+            monthly_units: 1_000_000,
+            monthly_price: 49.0,
+            overage_per_million: 2.0,
+            default_units: 10,
+            method_units: HashMap::from([("eth_call".to_string(), 26)]),
+        };
+        let settings = RpcUsageSettings {
+            plans: HashMap::from([("ws".to_string(), plan)]),
+            ..Default::default()
+        };
+        let calls = vec![
+            ("ws".to_string(), "eth_call".to_string(), 100),
+            ("ws".to_string(), "eth_blockNumber".to_string(), 40),
+            ("private".to_string(), "eth_sendRawTransaction".to_string(), 3),
+        ];
+
+        // One day of traffic: (100 * 26 + 40 * 10) units a day, thirty days a month
+        let projections = settings.project(&calls, Duration::from_secs(24 * 3600));
+        assert_eq!(projections.len(), 2);
+        let private = &projections[0];
+        assert_eq!((private.provider.as_str(), private.monthly_units, private.limit), ("private", 90, None));
+        assert!(!private.exceeds_plan());
+
+        let ws = &projections[1];
+        assert_eq!(ws.calls, 140);
+        assert_eq!(ws.monthly_units, 90_000);
+        assert!(!ws.exceeds_plan());
+        assert_eq!(ws.monthly_cost, 49.0);
+
+        // The same calls in an hour blow through the plan and are billed as overage
+        let ws = settings.project(&calls, Duration::from_secs(3600)).remove(1);
+        assert_eq!(ws.monthly_units, 2_160_000);
+        assert!(ws.exceeds_plan());
+        assert!((ws.monthly_cost - (49.0 + 1.16 * 2.0)).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,252 @@
+// src/self_trade.rs
+use ethers::types::{Address, U256};
+use log::debug;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::metrics::SELF_TRADE_TOTAL;
+
+// Reservations for target blocks this far behind the newest one are pruned
+const HORIZON_BLOCKS: u64 = 4;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SelfTradeSettings {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    // A more profitable trade takes the venues over from one that has not been sent yet
+    #[serde(default = "default_preempt")]
+    pub preempt: bool,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_preempt() -> bool {
+    true
+}
+
+impl Default for SelfTradeSettings {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            preempt: default_preempt(),
+        }
+    }
+}
+
+// A pool as both detectors can name it: the router trading it and its two tokens. The block
+// scan knows pool addresses, mempool detections only routers and the token path
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Venue {
+    router: Address,
+    tokens: (Address, Address),
+}
+
+impl Venue {
+    pub fn new(router: Address, token_a: Address, token_b: Address) -> Self {
+        Self {
+            router,
+            tokens: (token_a.min(token_b), token_a.max(token_b)),
+        }
+    }
+}
+
+// Venues of a route given as routers and token path: one hop per router when the path has
+// a token more, otherwise every router trades the path's first and last token
+pub fn route_venues(routers: &[Address], path: &[Address]) -> Vec<Venue> {
+    if path.len() == routers.len() + 1 {
+        return routers
+            .iter()
+            .zip(path.windows(2))
+            .map(|(&router, pair)| Venue::new(router, pair[0], pair[1]))
+            .collect();
+    }
+    match (path.first(), path.last()) {
+        (Some(&first), Some(&last)) => routers.iter().map(|&router| Venue::new(router, first, last)).collect(),
+        _ => Vec::new(),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Reservation {
+    venues: Vec<Venue>,
+    target_block: u64,
+    generation: u64,
+}
+
+// Every trade of the one strategy holding a venue, per target block
+type Held = HashMap<(Venue, u64), Vec<Holder>>;
+
+#[derive(Debug, Clone, Copy)]
+struct Holder {
+    strategy: &'static str,
+    expected_profit: U256,
+    generation: u64,
+    committed: bool,
+}
+
+// Coordinates strategies that trade the same venues for the same target block, e.g. a
+// sandwich front-running a pool a cycle arbitrages: whichever lands second trades against
+// state the first already moved and the two eat each other's profit. Each venue is held by
+// one strategy per target block; a strategy's own trades are sequenced by its detector
+#[derive(Debug, Clone, Default)]
+pub struct SelfTradeGuard {
+    settings: SelfTradeSettings,
+    held: Arc<Mutex<Held>>,
+    generations: Arc<AtomicU64>,
+}
+
+impl SelfTradeGuard {
+    pub fn new(settings: SelfTradeSettings) -> Self {
+        Self {
+            settings,
+            ..Default::default()
+        }
+    }
+
+    // None when another strategy holds one of the venues for `target_block` and keeps it
+    pub fn reserve(
+        &self,
+        venues: &[Venue],
+        target_block: u64,
+        strategy: &'static str,
+        expected_profit: U256,
+    ) -> Option<Reservation> {
+        let mut venues = venues.to_vec();
+        venues.sort();
+        venues.dedup();
+        let generation = self.generations.fetch_add(1, Ordering::Relaxed);
+        let reservation = Reservation {
+            venues,
+            target_block,
+            generation,
+        };
+        if !self.settings.enabled {
+            return Some(reservation);
+        }
+
+        let mut held = self.held.lock().unwrap();
+        held.retain(|(_, block), _| target_block.saturating_sub(*block) <= HORIZON_BLOCKS);
+        let rivals: Vec<Holder> = reservation
+            .venues
+            .iter()
+            .filter_map(|venue| held.get(&(*venue, target_block)))
+            .flatten()
+            .filter(|holder| holder.strategy != strategy)
+            .copied()
+            .collect();
+        if let Some(rival) = rivals.iter().find(|rival| {
+            rival.committed || !self.settings.preempt || rival.expected_profit >= expected_profit
+        }) {
+            debug!(
+                "{} trade for block {} blocked: {} holds a shared venue",
+                strategy, target_block, rival.strategy
+            );
+            SELF_TRADE_TOTAL.with_label_values(&["blocked"]).inc();
+            return None;
+        }
+        if !rivals.is_empty() {
+            debug!(
+                "{} trade for block {} preempts {} uncommitted rival(s)",
+                strategy,
+                target_block,
+                rivals.len()
+            );
+            SELF_TRADE_TOTAL.with_label_values(&["preempted"]).inc();
+            // The rivals lose every venue, their commit fails
+            let preempted: Vec<u64> = rivals.iter().map(|rival| rival.generation).collect();
+            release(&mut held, |holder| preempted.contains(&holder.generation));
+        }
+
+        for venue in &reservation.venues {
+            held.entry((*venue, target_block)).or_default().push(Holder {
+                strategy,
+                expected_profit,
+                generation,
+                committed: false,
+            });
+        }
+        SELF_TRADE_TOTAL.with_label_values(&["reserved"]).inc();
+        Some(reservation)
+    }
+
+    // Locks the venues in right before sending; false if a more profitable trade took them
+    pub fn commit(&self, reservation: &Reservation) -> bool {
+        if !self.settings.enabled {
+            return true;
+        }
+        let mut held = self.held.lock().unwrap();
+        let ours = |holder: &Holder| holder.generation == reservation.generation;
+        let owned = reservation.venues.iter().all(|venue| {
+            held.get(&(*venue, reservation.target_block))
+                .is_some_and(|holders| holders.iter().any(ours))
+        });
+        if owned {
+            for venue in &reservation.venues {
+                for holder in held.entry((*venue, reservation.target_block)).or_default() {
+                    holder.committed |= ours(holder);
+                }
+            }
+        }
+        owned
+    }
+
+    // Frees the venues of a trade that was not sent
+    pub fn release(&self, reservation: &Reservation) {
+        release(&mut self.held.lock().unwrap(), |holder| holder.generation == reservation.generation);
+    }
+}
+
+fn release(held: &mut Held, drop: impl Fn(&Holder) -> bool) {
+    held.retain(|_, holders| {
+        holders.retain(|holder| !drop(holder));
+        !holders.is_empty()
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::address;
+
+    #[test]
+    fn test_strategies_do_not_trade_shared_venues_in_one_block() {
+        let guard = SelfTradeGuard::default();
+        let (quickswap, sushiswap) = (address(1), address(2));
+        let (wmatic, usdc, weth) = (address(10), address(11), address(12));
+        let cross_dex = route_venues(&[quickswap, sushiswap], &[wmatic, usdc]);
+        let sandwich = route_venues(&[sushiswap], &[usdc, wmatic]);
+        let triangular = route_venues(&[quickswap, quickswap], &[weth, usdc, weth]);
+        assert_eq!(sandwich, vec![Venue::new(sushiswap, wmatic, usdc)]);
+        assert_eq!(triangular[0], Venue::new(quickswap, usdc, weth));
+
+        let arb = guard.reserve(&cross_dex, 100, "cross_dex", U256::from(50u64)).unwrap();
+        // A smaller sandwich on the same pool waits; other blocks and venues are free
+        assert!(guard.reserve(&sandwich, 100, "sandwich", U256::from(40u64)).is_none());
+        assert!(guard.reserve(&sandwich, 101, "sandwich", U256::from(40u64)).is_some());
+        assert!(guard.reserve(&triangular, 100, "triangular", U256::from(1u64)).is_some());
+        // The same strategy sequences its own trades; both keep their venues
+        let second = guard.reserve(&cross_dex, 100, "cross_dex", U256::from(10u64)).unwrap();
+        assert!(guard.commit(&second));
+        guard.release(&second);
+
+        // A bigger one takes over what was not sent yet
+        let bigger = guard.reserve(&sandwich, 100, "sandwich", U256::from(90u64)).unwrap();
+        assert!(!guard.commit(&arb));
+        assert!(guard.commit(&bigger));
+        assert!(guard.reserve(&cross_dex, 100, "cross_dex", U256::from(500u64)).is_none());
+
+        guard.release(&bigger);
+        assert!(guard.reserve(&cross_dex, 100, "cross_dex", U256::from(5u64)).is_some());
+
+        let disabled = SelfTradeGuard::new(SelfTradeSettings {
+            enabled: false,
+            ..Default::default()
+        });
+        disabled.reserve(&sandwich, 100, "sandwich", U256::zero()).unwrap();
+        assert!(disabled.reserve(&sandwich, 100, "cross_dex", U256::zero()).is_some());
+    }
+}
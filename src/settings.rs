@@ -0,0 +1,268 @@
+// src/settings.rs
+use anyhow::Result;
+use config::{Config, Environment, File};
+use ethers::types::Address;
+use serde::Deserialize;
+
+use crate::access_list::AccessListSettings;
+use crate::amm_math::{DualAssetSettings, FeeSettings};
+use crate::bid_market::BidMarketSettings;
+use crate::bundle_fanout::FanoutSettings;
+use crate::control_api::ControlApiSettings;
+use crate::cooldown::CooldownSettings;
+use crate::dashboard::DashboardSettings;
+use crate::deploy::DeploySettings;
+use crate::execution_mode::ExecutionModeSettings;
+use crate::executor::ExecutorSettings;
+use crate::executor_events::ExecutorEventSettings;
+use crate::gas_oracle::{GasCeilingSettings, GasForecastSettings};
+use crate::indexer::IndexerSettings;
+use crate::logging::LogSettings;
+use crate::mempool::MempoolSettings;
+use crate::nonce_watchdog::NonceWatchdogSettings;
+use crate::permit::PermitSettings;
+use crate::preflight::PreflightSettings;
+use crate::prefetch::PrefetchSettings;
+use crate::opportunity_class::ClassSettings;
+use crate::opportunity_queue::QueueSettings;
+use crate::optimizer::OptimizerSettings;
+use crate::token_tax::TokenTaxSettings;
+use crate::pool_kind::PoolDetectionSettings;
+use crate::two_phase::TwoPhaseSettings;
+use crate::sim_cache::SimCacheSettings;
+use crate::profit_threshold::ProfitThresholdSettings;
+use crate::profit_token::ProfitTokenSettings;
+use crate::propagation::PropagationSettings;
+use crate::reconciliation::ReconciliationSettings;
+use crate::reserve_watchdog::ReserveWatchdogSettings;
+use crate::route_search::RouteSearchSettings;
+use crate::rpc_usage::RpcUsageSettings;
+use crate::self_trade::SelfTradeSettings;
+use crate::setup::{SetupSettings, StartupCheckSettings};
+use crate::doctor::DoctorSettings;
+use crate::split_route::SplitRouteSettings;
+use crate::volatility::VolatilitySettings;
+use crate::store::StoreSettings;
+use crate::submission::SubmissionSettings;
+use crate::top_of_book::TopOfBookSettings;
+use crate::treasury::TreasurySettings;
+use crate::v3_discovery::V3DiscoverySettings;
+use crate::v3_positions::V3PositionSettings;
+use crate::yield_wrap::YieldWrapSettings;
+use crate::bundle_simulation::BundleSimulationSettings;
+use crate::v3_state::V3Settings;
+use crate::wallet_watcher::WalletWatcherSettings;
+use crate::admin_watch::AdminWatchSettings;
+use crate::flash_source::FlashSourceSettings;
+use crate::post_mortem::PostMortemSettings;
+use crate::pair_focus::FocusSettings;
+use crate::slippage::SlippageSettings;
+use crate::spread_filter::SpreadFilterSettings;
+use crate::round_trip::RoundTripSettings;
+use crate::shared_reserves::SharedReserveSettings;
+use crate::toxic_flow::ToxicFlowSettings;
+use crate::block_pipeline::BlockPipelineSettings;
+use crate::executor_whitelist::ExecutorWhitelistSettings;
+use crate::exposure::ExposureSettings;
+use crate::parquet_export::ParquetExportSettings;
+use crate::spread_predictor::SpreadPredictorSettings;
+use crate::shadow::ShadowSettings;
+use crate::fee_watch::FeeWatchSettings;
+
+pub const DEFAULT_CONFIG_PATH: &str = "config.yaml";
+
+// Typed view over config.yaml; sections the bot doesn't consume yet are ignored
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Settings {
+    #[serde(default)]
+    pub discovery: DiscoverySettings,
+    #[serde(default)]
+    pub prefetch: PrefetchSettings,
+    #[serde(default)]
+    pub logging: LogSettings,
+    #[serde(default)]
+    pub control_api: ControlApiSettings,
+    #[serde(default)]
+    pub profit_threshold: ProfitThresholdSettings,
+    #[serde(default)]
+    pub execution_mode: ExecutionModeSettings,
+    #[serde(default)]
+    pub route_search: RouteSearchSettings,
+    #[serde(default)]
+    pub shadow: ShadowSettings,
+    #[serde(default)]
+    pub store: StoreSettings,
+    #[serde(default)]
+    pub parquet_export: ParquetExportSettings,
+    #[serde(default)]
+    pub indexer: IndexerSettings,
+    #[serde(default)]
+    pub executor: ExecutorSettings,
+    #[serde(default)]
+    pub reserve_watchdog: ReserveWatchdogSettings,
+    #[serde(default)]
+    pub setup: SetupSettings,
+    #[serde(default)]
+    pub volatility: VolatilitySettings,
+    #[serde(default)]
+    pub access_list: AccessListSettings,
+    #[serde(default)]
+    pub mempool: MempoolSettings,
+    #[serde(default)]
+    pub propagation: PropagationSettings,
+    #[serde(default)]
+    pub classes: ClassSettings,
+    #[serde(default)]
+    pub submission: SubmissionSettings,
+    #[serde(default)]
+    pub bundle_fanout: FanoutSettings,
+    #[serde(default)]
+    pub spread_predictor: SpreadPredictorSettings,
+    #[serde(default)]
+    pub post_mortem: PostMortemSettings,
+    #[serde(default)]
+    pub queue: QueueSettings,
+    #[serde(default)]
+    pub shared_reserves: SharedReserveSettings,
+    #[serde(default)]
+    pub two_phase: TwoPhaseSettings,
+    #[serde(default)]
+    pub sim_cache: SimCacheSettings,
+    #[serde(default)]
+    pub pair_focus: FocusSettings,
+    #[serde(default)]
+    pub slippage: SlippageSettings,
+    #[serde(default)]
+    pub spread_filter: SpreadFilterSettings,
+    #[serde(default)]
+    pub toxic_flow: ToxicFlowSettings,
+    #[serde(default)]
+    pub block_pipeline: BlockPipelineSettings,
+    #[serde(default)]
+    pub exposure: ExposureSettings,
+    #[serde(default)]
+    pub rpc_usage: RpcUsageSettings,
+    #[serde(default)]
+    pub nonce_watchdog: NonceWatchdogSettings,
+    #[serde(default)]
+    pub wallet_watcher: WalletWatcherSettings,
+    #[serde(default)]
+    pub admin_watch: AdminWatchSettings,
+    #[serde(default)]
+    pub flash_sources: FlashSourceSettings,
+    #[serde(default)]
+    pub split_route: SplitRouteSettings,
+    #[serde(default)]
+    pub permits: PermitSettings,
+    #[serde(default)]
+    pub preflight: PreflightSettings,
+    #[serde(default)]
+    pub fees: FeeSettings,
+    #[serde(default)]
+    pub fee_watch: FeeWatchSettings,
+    #[serde(default)]
+    pub top_of_book: TopOfBookSettings,
+    #[serde(default)]
+    pub v3: V3Settings,
+    #[serde(default)]
+    pub v3_discovery: V3DiscoverySettings,
+    #[serde(default)]
+    pub v3_positions: V3PositionSettings,
+    #[serde(default)]
+    pub yield_wrap: YieldWrapSettings,
+    #[serde(default)]
+    pub bundle_simulation: BundleSimulationSettings,
+    #[serde(default)]
+    pub pool_detection: PoolDetectionSettings,
+    #[serde(default)]
+    pub treasury: TreasurySettings,
+    #[serde(default)]
+    pub dashboard: DashboardSettings,
+    #[serde(default)]
+    pub cooldown: CooldownSettings,
+    #[serde(default)]
+    pub self_trade: SelfTradeSettings,
+    #[serde(default)]
+    pub gas_ceiling: GasCeilingSettings,
+    #[serde(default)]
+    pub gas_forecast: GasForecastSettings,
+    #[serde(default)]
+    pub deploy: DeploySettings,
+    #[serde(default)]
+    pub startup_checks: StartupCheckSettings,
+    #[serde(default)]
+    pub executor_whitelist: ExecutorWhitelistSettings,
+    #[serde(default)]
+    pub doctor: DoctorSettings,
+    #[serde(default)]
+    pub executor_events: ExecutorEventSettings,
+    #[serde(default)]
+    pub reconciliation: ReconciliationSettings,
+    #[serde(default)]
+    pub bid_market: BidMarketSettings,
+    #[serde(default)]
+    pub dual_asset: DualAssetSettings,
+    #[serde(default)]
+    pub round_trip: RoundTripSettings,
+    #[serde(default)]
+    pub optimizer: OptimizerSettings,
+    #[serde(default)]
+    pub token_tax: TokenTaxSettings,
+    #[serde(default)]
+    pub profit_tokens: ProfitTokenSettings,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DiscoveryMode {
+    // Walk every pair of the configured factories
+    #[default]
+    All,
+    // Only track the pools listed in `allowlist`
+    Allowlist,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct DiscoverySettings {
+    #[serde(default)]
+    pub mode: DiscoveryMode,
+    #[serde(default)]
+    pub allowlist: Vec<Address>,
+}
+
+impl Settings {
+    // Load from a YAML file, with FLASHWICH__SECTION__KEY env vars taking precedence
+    pub fn load(path: &str) -> Result<Self> {
+        let settings = Config::builder()
+            .add_source(File::with_name(path))
+            .add_source(Environment::with_prefix("FLASHWICH").separator("__"))
+            .build()?
+            .try_deserialize()?;
+        Ok(settings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use config::FileFormat;
+
+    #[test]
+    fn test_allowlist_mode_parses() {
+        let yaml = r#"
+discovery:
+  mode: "allowlist"
+  allowlist:
+    - "0x6e7a5FAFcec6BB1e78bAE2A1F0B612012BF14827"
+"#;
+        let settings: Settings = Config::builder()
+            .add_source(File::from_str(yaml, FileFormat::Yaml))
+            .build()
+            .unwrap()
+            .try_deserialize()
+            .unwrap();
+
+        assert_eq!(settings.discovery.mode, DiscoveryMode::Allowlist);
+        assert_eq!(settings.discovery.allowlist.len(), 1);
+    }
+}
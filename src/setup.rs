@@ -0,0 +1,439 @@
+// src/setup.rs
+use anyhow::{anyhow, Result};
+use ethers::{
+    abi::{Abi, Function, ParamType, Token},
+    contract::Contract,
+    providers::Middleware,
+    types::{Address, U256},
+};
+use log::info;
+use serde::Deserialize;
+use std::fmt;
+use std::sync::Arc;
+
+use crate::erc20::Erc20;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SetupSettings {
+    // (address token, address router[, uint256 amount]); skipped if the executor ABI lacks it
+    #[serde(default = "default_approve_method")]
+    pub approve_method: String,
+    // (address token[, bool allowed]); skipped if the executor ABI lacks it
+    #[serde(default = "default_whitelist_method")]
+    pub whitelist_method: String,
+    #[serde(default)]
+    pub routers: Vec<Address>,
+    #[serde(default)]
+    pub tokens: Vec<Address>,
+}
+
+fn default_approve_method() -> String {
+    "approveRouter".to_string()
+}
+
+fn default_whitelist_method() -> String {
+    "whitelistToken".to_string()
+}
+
+impl Default for SetupSettings {
+    fn default() -> Self {
+        Self {
+            approve_method: default_approve_method(),
+            whitelist_method: default_whitelist_method(),
+            routers: Vec::new(),
+            tokens: Vec::new(),
+        }
+    }
+}
+
+// Read-only executor checks run by `flashwich run` before monitoring starts
+#[derive(Debug, Clone, Deserialize)]
+pub struct StartupCheckSettings {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    // Accepted executor owner besides the signing wallet, e.g. a cold wallet
+    #[serde(default)]
+    pub owner: Option<Address>,
+    // () -> bool; skipped if the executor ABI lacks it
+    #[serde(default = "default_paused_method")]
+    pub paused_method: String,
+    // (address router) -> bool, asked for every `setup.routers` entry; skipped if the ABI lacks it
+    #[serde(default = "default_router_method")]
+    pub router_method: String,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_paused_method() -> String {
+    "paused".to_string()
+}
+
+fn default_router_method() -> String {
+    "whitelistedRouters".to_string()
+}
+
+impl Default for StartupCheckSettings {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            owner: None,
+            paused_method: default_paused_method(),
+            router_method: default_router_method(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CheckStatus {
+    Ok,
+    Skipped,
+    Failed,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReadinessCheck {
+    pub name: &'static str,
+    pub status: CheckStatus,
+    pub detail: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ReadinessReport {
+    pub checks: Vec<ReadinessCheck>,
+}
+
+impl ReadinessReport {
+    pub fn push(&mut self, name: &'static str, status: CheckStatus, detail: impl Into<String>) {
+        self.checks.push(ReadinessCheck {
+            name,
+            status,
+            detail: detail.into(),
+        });
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.checks.iter().all(|check| check.status != CheckStatus::Failed)
+    }
+}
+
+impl fmt::Display for ReadinessReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Readiness report")?;
+        for check in &self.checks {
+            let status = match check.status {
+                CheckStatus::Ok => "ok",
+                CheckStatus::Skipped => "skip",
+                CheckStatus::Failed => "FAIL",
+            };
+            writeln!(f, "  [{:>4}] {:<12} {}", status, check.name, check.detail)?;
+        }
+        write!(f, "{}", if self.is_ready() { "Ready" } else { "NOT ready" })
+    }
+}
+
+// Arguments for an approval call `method(token, router[, amount])`, None if the shape differs
+fn approval_args(function: &Function, token: Address, router: Address) -> Option<Vec<Token>> {
+    let kinds: Vec<&ParamType> = function.inputs.iter().map(|input| &input.kind).collect();
+    match kinds.as_slice() {
+        [ParamType::Address, ParamType::Address] => Some(vec![Token::Address(token), Token::Address(router)]),
+        [ParamType::Address, ParamType::Address, ParamType::Uint(_)] => Some(vec![
+            Token::Address(token),
+            Token::Address(router),
+            Token::Uint(U256::MAX),
+        ]),
+        _ => None,
+    }
+}
+
+// Arguments for a whitelist call `method(token[, true])`, None if the shape differs
+fn whitelist_args(function: &Function, token: Address) -> Option<Vec<Token>> {
+    let kinds: Vec<&ParamType> = function.inputs.iter().map(|input| &input.kind).collect();
+    match kinds.as_slice() {
+        [ParamType::Address] => Some(vec![Token::Address(token)]),
+        [ParamType::Address, ParamType::Bool] => Some(vec![Token::Address(token), Token::Bool(true)]),
+        _ => None,
+    }
+}
+
+// One-time executor setup: ownership check, router approvals, token whitelist.
+// `client` must sign as the executor owner.
+pub struct ExecutorSetup<M> {
+    client: Arc<M>,
+    operator: Address,
+    executor: Contract<M>,
+    settings: SetupSettings,
+}
+
+impl<M: Middleware + 'static> ExecutorSetup<M> {
+    pub fn new(client: Arc<M>, operator: Address, executor: Address, abi: Abi, settings: SetupSettings) -> Self {
+        Self {
+            executor: Contract::new(executor, abi, client.clone()),
+            client,
+            operator,
+            settings,
+        }
+    }
+
+    async fn send(&self, function: &Function, args: Vec<Token>) -> Result<()> {
+        let call = self
+            .executor
+            .method_hash::<_, ()>(function.short_signature(), args.as_slice())
+            .map_err(|e| anyhow!("Failed to encode {}: {e}", function.name))?;
+        let pending = call.send().await.map_err(|e| anyhow!("{} failed: {e}", function.name))?;
+        let receipt = pending.await?.ok_or_else(|| anyhow!("{} dropped from mempool", function.name))?;
+        if receipt.status != Some(1.into()) {
+            return Err(anyhow!("{} reverted in {:?}", function.name, receipt.transaction_hash));
+        }
+        Ok(())
+    }
+
+    // Startup checks: nothing is sent, a failed check means the first trade would revert
+    pub async fn verify(&self, checks: &StartupCheckSettings) -> Result<ReadinessReport> {
+        let mut report = ReadinessReport::default();
+        let abi = self.executor.abi();
+
+        match abi.function("owner") {
+            Ok(_) => {
+                let owner: Address = self.executor.method("owner", ())?.call().await
+                    .map_err(|e| anyhow!("owner() failed: {e}"))?;
+                if owner == self.operator || Some(owner) == checks.owner {
+                    report.push("ownership", CheckStatus::Ok, format!("owned by {:?}", owner));
+                } else {
+                    let expected = match checks.owner {
+                        Some(configured) => format!("{:?} or {:?}", self.operator, configured),
+                        None => format!("{:?}", self.operator),
+                    };
+                    report.push(
+                        "ownership",
+                        CheckStatus::Failed,
+                        format!("executor owned by {:?}, expected {}", owner, expected),
+                    );
+                }
+            }
+            Err(_) => report.push("ownership", CheckStatus::Skipped, "executor has no owner()"),
+        }
+
+        match abi.function(&checks.paused_method) {
+            Ok(function) if function.inputs.is_empty() => {
+                let paused: bool = self.executor.method(&checks.paused_method, ())?.call().await
+                    .map_err(|e| anyhow!("{}() failed: {e}", checks.paused_method))?;
+                if paused {
+                    report.push("paused", CheckStatus::Failed, "executor is paused");
+                } else {
+                    report.push("paused", CheckStatus::Ok, "executor is live");
+                }
+            }
+            _ => report.push(
+                "paused",
+                CheckStatus::Skipped,
+                format!("executor has no {}()", checks.paused_method),
+            ),
+        }
+
+        match abi.function(&checks.router_method) {
+            Ok(function) if matches!(function.inputs.as_slice(), [input] if input.kind == ParamType::Address) => {
+                let mut missing = Vec::new();
+                for &router in &self.settings.routers {
+                    let allowed: bool = self.executor.method(&checks.router_method, router)?.call().await
+                        .map_err(|e| anyhow!("{}({:?}) failed: {e}", checks.router_method, router))?;
+                    if !allowed {
+                        missing.push(format!("{:?}", router));
+                    }
+                }
+                if missing.is_empty() {
+                    report.push(
+                        "routers",
+                        CheckStatus::Ok,
+                        format!("{} routers whitelisted", self.settings.routers.len()),
+                    );
+                } else {
+                    report.push(
+                        "routers",
+                        CheckStatus::Failed,
+                        format!("not whitelisted: {} (run `flashwich setup`)", missing.join(", ")),
+                    );
+                }
+            }
+            _ => report.push(
+                "routers",
+                CheckStatus::Skipped,
+                format!("executor has no {}(address)", checks.router_method),
+            ),
+        }
+
+        Ok(report)
+    }
+
+    pub async fn run(&self) -> Result<ReadinessReport> {
+        let mut report = ReadinessReport::default();
+        let abi = self.executor.abi();
+
+        // Ownership gates everything else: nothing is sent for a contract we don't own
+        match abi.function("owner") {
+            Ok(_) => {
+                let owner: Address = self.executor.method("owner", ())?.call().await
+                    .map_err(|e| anyhow!("owner() failed: {e}"))?;
+                if owner != self.operator {
+                    report.push(
+                        "ownership",
+                        CheckStatus::Failed,
+                        format!("executor owned by {:?}, operator is {:?}", owner, self.operator),
+                    );
+                    return Ok(report);
+                }
+                report.push("ownership", CheckStatus::Ok, format!("owned by {:?}", owner));
+            }
+            Err(_) => report.push("ownership", CheckStatus::Skipped, "executor has no owner()"),
+        }
+
+        match abi.function(&self.settings.approve_method) {
+            Ok(function) => {
+                let mut sent = 0;
+                let mut present = 0;
+                for &token in &self.settings.tokens {
+                    for &router in &self.settings.routers {
+                        let allowance = Erc20::new(token, self.client.clone())
+                            .allowance(self.executor.address(), router)
+                            .await
+                            .unwrap_or_default();
+                        if allowance > U256::MAX / 2 {
+                            present += 1;
+                            continue;
+                        }
+
+                        let args = approval_args(function, token, router).ok_or_else(|| {
+                            anyhow!("{} has an unsupported signature", function.name)
+                        })?;
+                        info!("Approving router {:?} for token {:?}", router, token);
+                        self.send(function, args).await?;
+                        sent += 1;
+                    }
+                }
+                report.push(
+                    "approvals",
+                    CheckStatus::Ok,
+                    format!("{} sent, {} already in place", sent, present),
+                );
+            }
+            Err(_) => report.push(
+                "approvals",
+                CheckStatus::Skipped,
+                format!("executor has no {}()", self.settings.approve_method),
+            ),
+        }
+
+        match abi.function(&self.settings.whitelist_method) {
+            Ok(function) => {
+                for &token in &self.settings.tokens {
+                    let args = whitelist_args(function, token).ok_or_else(|| {
+                        anyhow!("{} has an unsupported signature", function.name)
+                    })?;
+                    info!("Whitelisting token {:?}", token);
+                    self.send(function, args).await?;
+                }
+                report.push(
+                    "whitelist",
+                    CheckStatus::Ok,
+                    format!("{} tokens registered", self.settings.tokens.len()),
+                );
+            }
+            Err(_) => report.push(
+                "whitelist",
+                CheckStatus::Skipped,
+                format!("executor has no {}()", self.settings.whitelist_method),
+            ),
+        }
+
+        let balance = self.client.get_balance(self.operator, None).await
+            .map_err(|e| anyhow!("Failed to fetch operator balance: {e}"))?;
+        let status = if balance.is_zero() { CheckStatus::Failed } else { CheckStatus::Ok };
+        report.push("gas", status, format!("operator balance {} wei", balance));
+
+        // Informational: flash loans don't need inventory, but leftovers show up here
+        let mut holdings = Vec::new();
+        for &token in &self.settings.tokens {
+            let erc20 = Erc20::new(token, self.client.clone());
+            if let Ok(amount) = erc20.balance_of(self.executor.address()).await {
+                if !amount.is_zero() {
+                    holdings.push(format!("{} of {:?}", amount, token));
+                }
+            }
+        }
+        let holdings = if holdings.is_empty() { "none".to_string() } else { holdings.join(", ") };
+        report.push("inventory", CheckStatus::Ok, format!("executor holds {}", holdings));
+
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{address, call_output, MockChain};
+    use ethers::abi::parse_abi;
+
+    #[test]
+    fn test_setup_method_shapes() {
+        let abi = parse_abi(&[
+            "function approveRouter(address token, address router, uint256 amount)",
+            "function whitelistToken(address token, bool allowed)",
+            "function pause()",
+        ])
+        .unwrap();
+        let (token, router) = (Address::from_low_u64_be(1), Address::from_low_u64_be(2));
+
+        let approve = approval_args(abi.function("approveRouter").unwrap(), token, router).unwrap();
+        assert_eq!(approve[2], Token::Uint(U256::MAX));
+        let whitelist = whitelist_args(abi.function("whitelistToken").unwrap(), token).unwrap();
+        assert_eq!(whitelist[1], Token::Bool(true));
+        assert!(whitelist_args(abi.function("pause").unwrap(), token).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_startup_checks_fail_on_foreign_owner_and_missing_router() {
+        let chain = MockChain::new();
+        let abi = parse_abi(&[
+            "function owner() view returns (address)",
+            "function paused() view returns (bool)",
+            "function whitelistedRouters(address) view returns (bool)",
+        ])
+        .unwrap();
+        let (operator, cold, executor) = (address(1), address(2), address(3));
+        let settings = SetupSettings {
+            routers: vec![address(10), address(11)],
+            ..Default::default()
+        };
+        let setup = ExecutorSetup::new(chain.provider(), operator, executor, abi, settings);
+
+        // Owned by the cold wallet, live, second router missing
+        let script = || {
+            chain.script(vec![
+                call_output(&[Token::Address(cold)]),
+                call_output(&[Token::Bool(false)]),
+                call_output(&[Token::Bool(true)]),
+                call_output(&[Token::Bool(false)]),
+            ])
+        };
+        script();
+        let report = setup.verify(&StartupCheckSettings::default()).await.unwrap();
+        let failed: Vec<_> = report
+            .checks
+            .iter()
+            .filter(|check| check.status == CheckStatus::Failed)
+            .map(|check| check.name)
+            .collect();
+        assert_eq!(failed, vec!["ownership", "routers"]);
+
+        // A configured cold owner is accepted
+        script();
+        let checks = StartupCheckSettings {
+            owner: Some(cold),
+            ..Default::default()
+        };
+        let report = setup.verify(&checks).await.unwrap();
+        assert_eq!(report.checks[0].status, CheckStatus::Ok);
+        assert!(!report.is_ready());
+    }
+}
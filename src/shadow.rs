@@ -0,0 +1,171 @@
+// src/shadow.rs
+use ethers::types::{Address, U256};
+use log::{debug, info};
+use serde::Deserialize;
+use std::sync::{Arc, Mutex};
+
+use crate::metrics::SHADOW_COMPARISONS_TOTAL;
+use crate::optimizer::OptimizerSettings;
+use crate::route_search::{RouteSearch, RouteSearchSettings};
+
+const BPS: u64 = 10_000;
+
+// A second engine configuration run beside the live one, for rolling out engine changes:
+// for every pair the block engine checks, the shadow searches and sizes with its own
+// optimizer and route search on the same cached reserves. Both picks are priced alike and
+// divergences are logged and counted; the shadow never executes or touches live state
+#[derive(Debug, Clone, Deserialize)]
+pub struct ShadowSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    // Replace the live sections in the shadow; a missing one runs as it does live
+    #[serde(default)]
+    pub optimizer: Option<OptimizerSettings>,
+    #[serde(default)]
+    pub route_search: Option<RouteSearchSettings>,
+    // Predicted profits further apart than this, relative to the larger one, diverge
+    #[serde(default = "default_min_divergence_bps")]
+    pub min_divergence_bps: u64,
+}
+
+fn default_min_divergence_bps() -> u64 {
+    100
+}
+
+impl Default for ShadowSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            optimizer: None,
+            route_search: None,
+            min_divergence_bps: default_min_divergence_bps(),
+        }
+    }
+}
+
+// One engine's pick for a pair: route as [pool_1, .., pool_n, token_in], empty for none
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EnginePick {
+    pub route: Vec<Address>,
+    pub amount: U256,
+    pub profit: U256,
+}
+
+impl EnginePick {
+    fn detected(&self) -> bool {
+        self.route.len() >= 2 && !self.profit.is_zero()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Divergence {
+    // Only one engine found a profitable route
+    LiveOnly,
+    ShadowOnly,
+    // Both did, through different pools
+    Route,
+    // Same route, predicted profits apart by more than min_divergence_bps
+    Profit,
+}
+
+impl Divergence {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Divergence::LiveOnly => "live_only",
+            Divergence::ShadowOnly => "shadow_only",
+            Divergence::Route => "route",
+            Divergence::Profit => "profit",
+        }
+    }
+}
+
+pub fn compare(live: &EnginePick, shadow: &EnginePick, min_divergence_bps: u64) -> Option<Divergence> {
+    match (live.detected(), shadow.detected()) {
+        (false, false) => None,
+        (true, false) => Some(Divergence::LiveOnly),
+        (false, true) => Some(Divergence::ShadowOnly),
+        (true, true) if live.route != shadow.route => Some(Divergence::Route),
+        (true, true) => {
+            let gap = live.profit.max(shadow.profit) - live.profit.min(shadow.profit);
+            let allowed = live.profit.max(shadow.profit) * U256::from(min_divergence_bps) / U256::from(BPS);
+            (gap > allowed).then_some(Divergence::Profit)
+        }
+    }
+}
+
+// The shadow's settings, resolved against the live ones, and its own search budget
+#[derive(Debug, Clone)]
+pub struct ShadowEngine {
+    pub optimizer: OptimizerSettings,
+    pub route_search_settings: RouteSearchSettings,
+    pub route_search: Arc<Mutex<RouteSearch>>,
+    min_divergence_bps: u64,
+}
+
+impl ShadowEngine {
+    pub fn new(settings: ShadowSettings, optimizer: &OptimizerSettings, route_search: &RouteSearchSettings) -> Self {
+        let route_search_settings = settings.route_search.unwrap_or_else(|| route_search.clone());
+        Self {
+            optimizer: settings.optimizer.unwrap_or_else(|| optimizer.clone()),
+            route_search: Arc::new(Mutex::new(RouteSearch::new(
+                route_search_settings.max_hops,
+                route_search_settings.max_nodes_per_block,
+            ))),
+            route_search_settings,
+            min_divergence_bps: settings.min_divergence_bps,
+        }
+    }
+
+    // Logs and counts how the picks for the pair starting at `token_in` compare
+    pub fn record(&self, token_in: Address, live: &EnginePick, shadow: &EnginePick) -> Option<Divergence> {
+        let divergence = compare(live, shadow, self.min_divergence_bps);
+        let outcome = divergence.map_or("agree", |divergence| divergence.as_str());
+        SHADOW_COMPARISONS_TOTAL.with_label_values(&[outcome]).inc();
+        let describe = |pick: &EnginePick| {
+            if pick.detected() {
+                format!("{} on {} via {:?}", pick.profit, pick.amount, &pick.route[..pick.route.len() - 1])
+            } else {
+                "nothing".to_string()
+            }
+        };
+        match divergence {
+            Some(divergence) => info!(
+                "Shadow engine diverges from {:?} ({}): live {}, shadow {}",
+                token_in,
+                divergence.as_str(),
+                describe(live),
+                describe(shadow)
+            ),
+            None => debug!("Shadow engine agrees from {:?}: {}", token_in, describe(live)),
+        }
+        divergence
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::address;
+
+    #[test]
+    fn test_picks_diverge_on_detection_route_and_profit_gap() {
+        let (pool_a, pool_b, token) = (address(1), address(2), address(10));
+        let pick = |pools: &[Address], profit: u64| {
+            let mut route = pools.to_vec();
+            route.push(token);
+            EnginePick {
+                route,
+                amount: U256::exp10(18),
+                profit: U256::from(profit),
+            }
+        };
+        let live = pick(&[pool_a, pool_b], 1_000);
+
+        assert_eq!(compare(&live, &pick(&[pool_a, pool_b], 995), 100), None);
+        assert_eq!(compare(&live, &pick(&[pool_a, pool_b], 980), 100), Some(Divergence::Profit));
+        assert_eq!(compare(&live, &pick(&[pool_b, pool_a], 1_000), 100), Some(Divergence::Route));
+        assert_eq!(compare(&live, &EnginePick::default(), 100), Some(Divergence::LiveOnly));
+        assert_eq!(compare(&pick(&[pool_a, pool_b], 0), &live, 100), Some(Divergence::ShadowOnly));
+        assert_eq!(compare(&EnginePick::default(), &EnginePick::default(), 100), None);
+    }
+}
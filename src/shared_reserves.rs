@@ -0,0 +1,219 @@
+// src/shared_reserves.rs
+use anyhow::{bail, Context, Result};
+use ethers::types::{Address, U256};
+use log::{debug, info, warn};
+use redis::aio::MultiplexedConnection;
+use redis::AsyncCommands;
+use serde::Deserialize;
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+use crate::metrics::SHARED_RESERVES_TOTAL;
+use crate::opportunity_queue::{redis_client, QueueEndpoint};
+use crate::reserve_cache::{PoolReserves, ReserveCache};
+
+// Reserve cache shared by several bot instances through one Redis hash. The instance running
+// the pool event indexer writes every Sync through to it; the others pull it into their own
+// cache instead of each following the pools' logs over a WS subscription
+#[derive(Debug, Clone, Deserialize)]
+pub struct SharedReserveSettings {
+    // redis://[:password@]host[:port]; unset keeps the reserve cache per instance
+    #[serde(default)]
+    pub url: Option<String>,
+    // Hash of pool -> "block:reserve0:reserve1"
+    #[serde(default = "default_key")]
+    pub key: String,
+    #[serde(default = "default_poll_interval_ms")]
+    pub poll_interval_ms: u64,
+}
+
+fn default_key() -> String {
+    "flashwich.reserves".to_string()
+}
+
+fn default_poll_interval_ms() -> u64 {
+    1_000
+}
+
+impl Default for SharedReserveSettings {
+    fn default() -> Self {
+        Self {
+            url: None,
+            key: default_key(),
+            poll_interval_ms: default_poll_interval_ms(),
+        }
+    }
+}
+
+fn encode(reserves: &PoolReserves) -> String {
+    format!("{}:{}:{}", reserves.block_number, reserves.reserve0, reserves.reserve1)
+}
+
+fn decode(value: &[u8]) -> Option<PoolReserves> {
+    let value = std::str::from_utf8(value).ok()?;
+    let mut parts = value.split(':');
+    let block_number = parts.next()?.parse().ok()?;
+    let reserve0 = U256::from_dec_str(parts.next()?).ok()?;
+    let reserve1 = U256::from_dec_str(parts.next()?).ok()?;
+    Some(PoolReserves {
+        reserve0,
+        reserve1,
+        block_number,
+    })
+}
+
+// One connection, reopened on the call after a failure
+#[derive(Debug)]
+pub struct SharedReserves {
+    address: String,
+    client: redis::Client,
+    settings: SharedReserveSettings,
+    connection: Mutex<Option<MultiplexedConnection>>,
+}
+
+impl SharedReserves {
+    // None without a url
+    pub fn new(settings: SharedReserveSettings) -> Result<Option<Self>> {
+        let Some(url) = &settings.url else {
+            return Ok(None);
+        };
+        let QueueEndpoint::Redis { address, password, .. } = QueueEndpoint::parse(url)? else {
+            bail!("shared reserve cache url {url} is not a redis:// url");
+        };
+        Ok(Some(Self {
+            client: redis_client(&address, password.as_deref())?,
+            address,
+            settings,
+            connection: Mutex::new(None),
+        }))
+    }
+
+    async fn connection(&self) -> Result<MultiplexedConnection> {
+        let mut connection = self.connection.lock().await;
+        if let Some(connected) = connection.as_ref() {
+            return Ok(connected.clone());
+        }
+        let connected = self
+            .client
+            .get_multiplexed_tokio_connection()
+            .await
+            .with_context(|| format!("shared reserve cache at {} unreachable", self.address))?;
+        *connection = Some(connected.clone());
+        Ok(connected)
+    }
+
+    // Drops the connection after a failed command so the next call reconnects
+    async fn checked<T>(&self, result: redis::RedisResult<T>) -> Result<T> {
+        if result.is_err() {
+            *self.connection.lock().await = None;
+        }
+        Ok(result?)
+    }
+
+    // Writes the reserves through in one HSET. Instances only ever apply newer blocks, so a
+    // late write from a second indexer cannot roll anyone back
+    pub async fn publish(&self, updates: &[(Address, PoolReserves)]) -> Result<()> {
+        if updates.is_empty() {
+            return Ok(());
+        }
+        let fields: Vec<(String, String)> = updates
+            .iter()
+            .map(|(pool, reserves)| (format!("{:?}", pool), encode(reserves)))
+            .collect();
+        let written = async {
+            let mut connection = self.connection().await?;
+            let result = connection.hset_multiple::<_, _, _, ()>(&self.settings.key, &fields).await;
+            self.checked(result).await
+        };
+        match written.await {
+            Ok(_) => {
+                SHARED_RESERVES_TOTAL.with_label_values(&["published"]).inc_by(updates.len() as u64);
+                Ok(())
+            }
+            Err(e) => {
+                SHARED_RESERVES_TOTAL.with_label_values(&["publish_failed"]).inc();
+                Err(e)
+            }
+        }
+    }
+
+    // Applies the shared state to `cache`; returns how many pools it holds
+    pub async fn pull(&self, cache: &ReserveCache) -> Result<usize> {
+        let read = async {
+            let mut connection = self.connection().await?;
+            let result = connection.hgetall::<_, Vec<(Vec<u8>, Vec<u8>)>>(&self.settings.key).await;
+            self.checked(result).await
+        };
+        let entries = read.await.inspect_err(|_| {
+            SHARED_RESERVES_TOTAL.with_label_values(&["pull_failed"]).inc();
+        })?;
+        let mut pulled = 0;
+        for (pool, value) in entries {
+            let pool = std::str::from_utf8(&pool).ok().and_then(|pool| pool.parse::<Address>().ok());
+            let (Some(pool), Some(reserves)) = (pool, decode(&value)) else {
+                debug!("Skipping unreadable shared reserve entry");
+                continue;
+            };
+            cache.update(pool, reserves.reserve0, reserves.reserve1, reserves.block_number);
+            pulled += 1;
+        }
+        SHARED_RESERVES_TOTAL.with_label_values(&["pulled"]).inc_by(pulled as u64);
+        Ok(pulled)
+    }
+
+    pub async fn run(&self, cache: ReserveCache) {
+        info!("Pulling shared reserves from {} every {}ms", self.address, self.settings.poll_interval_ms);
+        let mut interval = tokio::time::interval(Duration::from_millis(self.settings.poll_interval_ms.max(50)));
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.pull(&cache).await {
+                warn!("Shared reserve pull failed: {:?}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{address, redis_server};
+
+    #[tokio::test]
+    async fn test_indexer_writes_through_and_other_instances_pull_newer_reserves() {
+        // AUTH and the write, then HGETALL
+        let hash = format!(
+            "*6\r\n$42\r\n{:?}\r\n$7\r\n12:5:50\r\n$42\r\n{:?}\r\n$7\r\n10:7:70\r\n$5\r\nnoise\r\n$3\r\n1:2\r\n",
+            address(1),
+            address(2)
+        );
+        let (port, server) = redis_server(vec![b"+OK\r\n".to_vec(), b"+OK\r\n".to_vec(), hash.into_bytes()]);
+
+        let shared = SharedReserves::new(SharedReserveSettings {
+            url: Some(format!("redis://:secret@127.0.0.1:{port}")),
+            ..Default::default()
+        })
+        .unwrap()
+        .unwrap();
+        let reserves = PoolReserves {
+            reserve0: U256::from(5u64),
+            reserve1: U256::from(50u64),
+            block_number: 12,
+        };
+        shared.publish(&[(address(1), reserves)]).await.unwrap();
+
+        // This instance already saw pool 2 at a later block
+        let cache = ReserveCache::new();
+        cache.update(address(2), U256::from(8u64), U256::from(80u64), 11);
+        assert_eq!(shared.pull(&cache).await.unwrap(), 2);
+        assert_eq!(cache.get(&address(1)), Some(reserves));
+        assert_eq!(cache.get(&address(2)).unwrap().reserve0, U256::from(8u64));
+
+        let commands = server.join().unwrap();
+        let field = format!("{:?}", address(1));
+        assert_eq!(
+            commands[1],
+            [&b"HMSET"[..], b"flashwich.reserves", field.as_bytes(), b"12:5:50"].map(<[u8]>::to_vec)
+        );
+        assert_eq!(commands[2][0], b"HGETALL");
+    }
+}
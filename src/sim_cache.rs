@@ -0,0 +1,158 @@
+// src/sim_cache.rs
+use ethers::types::{Address, U256};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::metrics::SIM_CACHE_LOOKUPS_TOTAL;
+
+// Per-block memo of route simulations. Candidate routes come back through several pairs
+// and re-pricing passes within a block, each costing a reserve read per hop
+#[derive(Debug, Clone, Deserialize)]
+pub struct SimCacheSettings {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    // Significant bits kept of an amount; sizes agreeing on them share a result, 12 bits
+    // puts neighbouring buckets about 0.02% apart
+    #[serde(default = "default_precision_bits")]
+    pub precision_bits: u32,
+    // Results kept per block; the block is simulated uncached beyond it
+    #[serde(default = "default_max_entries")]
+    pub max_entries: usize,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_precision_bits() -> u32 {
+    12
+}
+
+fn default_max_entries() -> usize {
+    10_000
+}
+
+impl Default for SimCacheSettings {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            precision_bits: default_precision_bits(),
+            max_entries: default_max_entries(),
+        }
+    }
+}
+
+// Pools in hop order and the token going in fix both the pool set and its direction
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SimKey {
+    pools: Vec<Address>,
+    token_in: Address,
+    bucket: U256,
+    // Drift- and tax-adjusted quotes are cached apart from raw ones
+    adjusted: bool,
+}
+
+#[derive(Debug, Default)]
+struct Entries {
+    block: u64,
+    results: HashMap<SimKey, U256>,
+}
+
+impl Entries {
+    // Results never outlive their block
+    fn advance(&mut self, block: u64) {
+        if block > self.block {
+            self.block = block;
+            self.results.clear();
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct SimulationCache {
+    settings: SimCacheSettings,
+    entries: Mutex<Entries>,
+}
+
+impl SimulationCache {
+    pub fn new(settings: SimCacheSettings) -> Self {
+        Self {
+            settings,
+            entries: Mutex::new(Entries::default()),
+        }
+    }
+
+    pub fn key(&self, pools: &[Address], token_in: Address, amount: U256, adjusted: bool) -> SimKey {
+        SimKey {
+            pools: pools.to_vec(),
+            token_in,
+            bucket: bucket(amount, self.settings.precision_bits),
+            adjusted,
+        }
+    }
+
+    pub fn get(&self, block: u64, key: &SimKey) -> Option<U256> {
+        if !self.settings.enabled {
+            return None;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        entries.advance(block);
+        let result = entries.results.get(key).copied().filter(|_| entries.block == block);
+        SIM_CACHE_LOOKUPS_TOTAL
+            .with_label_values(&[if result.is_some() { "hit" } else { "miss" }])
+            .inc();
+        result
+    }
+
+    pub fn insert(&self, block: u64, key: SimKey, profit: U256) {
+        if !self.settings.enabled {
+            return;
+        }
+        let mut entries = self.entries.lock().unwrap();
+        entries.advance(block);
+        // A late result from an earlier block is not worth keeping
+        if entries.block == block && entries.results.len() < self.settings.max_entries {
+            entries.results.insert(key, profit);
+        }
+    }
+}
+
+// The amount with everything below its top `precision_bits` bits cleared
+fn bucket(amount: U256, precision_bits: u32) -> U256 {
+    let shift = (amount.bits() as u32).saturating_sub(precision_bits.max(1));
+    (amount >> shift) << shift
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::address;
+
+    #[test]
+    fn test_results_are_shared_within_a_bucket_and_dropped_on_the_next_block() {
+        let cache = SimulationCache::new(SimCacheSettings::default());
+        let pools = [address(1), address(2)];
+        let one = U256::exp10(18);
+
+        let key = cache.key(&pools, address(10), one, true);
+        assert_eq!(cache.get(100, &key), None);
+        cache.insert(100, key.clone(), U256::from(42u64));
+        assert_eq!(cache.get(100, &key), Some(U256::from(42u64)));
+
+        // A hair more lands in the same bucket, a tenth more does not
+        let close = cache.key(&pools, address(10), one + U256::from(1_000u64), true);
+        assert_eq!(cache.get(100, &close), Some(U256::from(42u64)));
+        assert_eq!(cache.get(100, &cache.key(&pools, address(10), one * 11 / 10, true)), None);
+
+        // Other direction, other quote kind, other pool order
+        assert_eq!(cache.get(100, &cache.key(&pools, address(11), one, true)), None);
+        assert_eq!(cache.get(100, &cache.key(&pools, address(10), one, false)), None);
+        assert_eq!(cache.get(100, &cache.key(&[address(2), address(1)], address(10), one, true)), None);
+
+        assert_eq!(cache.get(101, &key), None);
+        cache.insert(100, key.clone(), U256::one());
+        assert_eq!(cache.get(101, &key), None);
+        assert_eq!(cache.get(100, &key), None);
+    }
+}
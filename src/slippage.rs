@@ -0,0 +1,262 @@
+// src/slippage.rs
+use ethers::types::{Address, U256};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use crate::amm_math::get_amount_out;
+use crate::metrics::SLIPPAGE_CURVES;
+use crate::reserve_cache::ReserveSnapshot;
+use crate::v3_state::{V3Settings, V3StateMirror};
+
+// Fixed-point scale of the sampled reserve fractions
+const FRACTION_SCALE: u64 = 1_000_000_000_000;
+
+// Output against input size of every tracked pool, sampled once per block from the reserve
+// cache and the V3 mirror. The optimizer sizes routes by interpolating these instead of
+// re-running the swap math or reading reserves for every candidate amount
+#[derive(Debug, Clone, Deserialize)]
+pub struct SlippageSettings {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    // Samples per curve, geometric from `min_fraction` of the input reserve up to all of it
+    #[serde(default = "default_points")]
+    pub points: usize,
+    #[serde(default = "default_min_fraction")]
+    pub min_fraction: f64,
+    // Curves over reserves older than this many blocks are not used for sizing
+    #[serde(default = "default_max_age_blocks")]
+    pub max_age_blocks: u64,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_points() -> usize {
+    24
+}
+
+fn default_min_fraction() -> f64 {
+    0.000_001
+}
+
+fn default_max_age_blocks() -> u64 {
+    1
+}
+
+impl Default for SlippageSettings {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            points: default_points(),
+            min_fraction: default_min_fraction(),
+            max_age_blocks: default_max_age_blocks(),
+        }
+    }
+}
+
+impl SlippageSettings {
+    // Sample sizes for a pool holding `reserve_in` of the input token
+    fn inputs(&self, reserve_in: U256) -> Vec<U256> {
+        let points = self.points.max(2);
+        let min = self.min_fraction.clamp(1e-12, 1.0);
+        let ratio = (1.0 / min).powf(1.0 / (points - 1) as f64);
+        let mut inputs: Vec<U256> = (0..points)
+            .map(|i| {
+                let fraction = (min * ratio.powi(i as i32)).min(1.0);
+                reserve_in * U256::from((fraction * FRACTION_SCALE as f64) as u64) / U256::from(FRACTION_SCALE)
+            })
+            .filter(|input| !input.is_zero())
+            .collect();
+        inputs.dedup();
+        inputs
+    }
+}
+
+// (amount_in, amount_out) samples of one pool, token0 going in like the hop quotes
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SlippageCurve {
+    points: Vec<(U256, U256)>,
+    // Block of the reserves the curve was sampled from
+    pub block_number: u64,
+}
+
+impl SlippageCurve {
+    // Outputs are kept non-decreasing, so a capped quote flattens the curve instead of bending it
+    pub fn sample(inputs: &[U256], block_number: u64, quote: impl Fn(U256) -> U256) -> Self {
+        let mut points = Vec::with_capacity(inputs.len());
+        let mut highest = U256::zero();
+        for &input in inputs {
+            highest = highest.max(quote(input));
+            points.push((input, highest));
+        }
+        Self { points, block_number }
+    }
+
+    pub fn constant_product(inputs: &[U256], reserve_in: U256, reserve_out: U256, fee: u32, block_number: u64) -> Self {
+        Self::sample(inputs, block_number, |input| get_amount_out(input, reserve_in, reserve_out, fee))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    // Linear between samples and from the origin to the first; chords sit under a concave
+    // curve, so the estimate never overstates the pool. Past the last sample it stays flat
+    pub fn amount_out(&self, amount_in: U256) -> U256 {
+        let Some(&(last_in, last_out)) = self.points.last() else {
+            return U256::zero();
+        };
+        if amount_in >= last_in {
+            return last_out;
+        }
+        let next = self.points.partition_point(|&(input, _)| input <= amount_in);
+        let (x0, y0) = if next == 0 { (U256::zero(), U256::zero()) } else { self.points[next - 1] };
+        let (x1, y1) = self.points[next];
+        y0 + (y1 - y0) * (amount_in - x0) / (x1 - x0)
+    }
+}
+
+// `route_profit` over curves instead of reserves
+pub fn curve_profit<F>(amount_in: U256, curves: &[SlippageCurve], adjust: F) -> U256
+where
+    F: Fn(usize, U256) -> Option<U256>,
+{
+    let mut current = amount_in;
+    for (i, curve) in curves.iter().enumerate() {
+        current = match adjust(i, curve.amount_out(current)) {
+            Some(adjusted) => adjusted,
+            None => return U256::zero(),
+        };
+    }
+    current.saturating_sub(amount_in)
+}
+
+// Curves of the current block, shared by the scan tasks
+#[derive(Debug, Clone)]
+pub struct SlippageCurves {
+    settings: SlippageSettings,
+    curves: Arc<RwLock<HashMap<Address, SlippageCurve>>>,
+}
+
+impl SlippageCurves {
+    pub fn new(settings: SlippageSettings) -> Self {
+        Self {
+            settings,
+            curves: Arc::default(),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.settings.enabled
+    }
+
+    // Resamples `pools` from the reserve snapshot and every mirrored V3 pool; returns how
+    // many curves the block has
+    pub fn rebuild(
+        &self,
+        pools: &[Address],
+        reserves: &ReserveSnapshot,
+        fee: impl Fn(&Address) -> u32,
+        v3_pools: &V3StateMirror,
+        v3: &V3Settings,
+    ) -> usize {
+        if !self.settings.enabled {
+            return 0;
+        }
+        let mut curves = HashMap::with_capacity(pools.len() + v3_pools.len());
+        for pool in pools {
+            let Some(cached) = reserves.get(pool).filter(|cached| !cached.reserve0.is_zero()) else {
+                continue;
+            };
+            let inputs = self.settings.inputs(cached.reserve0);
+            let curve = SlippageCurve::constant_product(
+                &inputs,
+                cached.reserve0,
+                cached.reserve1,
+                fee(pool),
+                cached.block_number,
+            );
+            curves.insert(*pool, curve);
+        }
+        let v2 = curves.len();
+        for pool in v3_pools.pools() {
+            let Some(state) = v3_pools.get(&pool).filter(|state| !state.sqrt_price_x96.is_zero()) else {
+                continue;
+            };
+            // Virtual token0 reserve of the active range, L / sqrt(P), sets the scale
+            let reserve0 = U256::from(state.liquidity) * (U256::one() << 96) / state.sqrt_price_x96;
+            let inputs = self.settings.inputs(reserve0);
+            let curve = SlippageCurve::sample(&inputs, state.block_number, |input| {
+                v3_pools
+                    .quote(&pool, state.token0, input, v3)
+                    .map_or(U256::zero(), |quote| quote.amount_out)
+            });
+            if !curve.is_empty() {
+                curves.insert(pool, curve);
+            }
+        }
+        SLIPPAGE_CURVES.with_label_values(&["v2"]).set(v2 as i64);
+        SLIPPAGE_CURVES.with_label_values(&["v3"]).set((curves.len() - v2) as i64);
+        let count = curves.len();
+        *self.curves.write().unwrap() = curves;
+        count
+    }
+
+    // Curves of every pool of the route, or None when one is missing or older than
+    // `max_age_blocks` at `block`
+    pub fn route(&self, pools: &[Address], block: u64) -> Option<Vec<SlippageCurve>> {
+        if !self.settings.enabled {
+            return None;
+        }
+        let curves = self.curves.read().unwrap();
+        pools
+            .iter()
+            .map(|pool| {
+                curves
+                    .get(pool)
+                    .filter(|curve| curve.block_number + self.settings.max_age_blocks >= block)
+                    .cloned()
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::amm_math::route_profit;
+    use crate::fixtures::address;
+    use crate::reserve_cache::ReserveCache;
+
+    #[test]
+    fn test_curves_track_the_swap_math_without_overstating_it() {
+        let (pool_a, pool_b) = (address(1), address(2));
+        let reserve_cache = ReserveCache::default();
+        let ether = U256::exp10(18);
+        reserve_cache.update(pool_a, ether * 1_000, ether * 2_000, 100);
+        reserve_cache.update(pool_b, ether * 1_900, ether * 1_000, 98);
+
+        let curves = SlippageCurves::new(SlippageSettings {
+            points: 96,
+            ..Default::default()
+        });
+        let (snapshot, v3_pools) = (reserve_cache.snapshot(), V3StateMirror::new());
+        let built = curves.rebuild(&[pool_a, pool_b], &snapshot, |_| 30, &v3_pools, &V3Settings::default());
+        assert_eq!(built, 2);
+        // pool_b is two blocks behind
+        assert!(curves.route(&[pool_a, pool_b], 100).is_none());
+        let route = curves.route(&[pool_a, pool_b], 99).unwrap();
+
+        let hops = [(ether * 1_000, ether * 2_000, 30), (ether * 1_900, ether * 1_000, 30)];
+        for amount in [ether, ether * 5, ether * 10] {
+            let exact = route_profit(amount, &hops, |_, out| Some(out));
+            let interpolated = curve_profit(amount, &route, |_, out| Some(out));
+            assert!(!exact.is_zero() && interpolated <= exact);
+            assert!(exact - interpolated <= exact / 100);
+        }
+        // Beyond the pool the output stays at the whole reserve's quote
+        assert_eq!(route[0].amount_out(ether * 5_000), route[0].amount_out(ether * 1_000));
+    }
+}
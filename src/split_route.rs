@@ -0,0 +1,390 @@
+// src/split_route.rs
+use anyhow::{anyhow, Result};
+use ethers::{
+    abi::{ParamType, Token},
+    contract::abigen,
+    providers::Middleware,
+    types::{Address, BlockId, U256},
+};
+use serde::Deserialize;
+use std::sync::Arc;
+
+use crate::amm_math::{get_amount_out, FeeSettings};
+use crate::metrics::SPLIT_ROUTES_TOTAL;
+use crate::v3_state::{fetch_pool_state, V3PoolKind, V3Settings};
+
+abigen!(
+    ISplitV2Pool,
+    r#"[
+        function getPair(address tokenA, address tokenB) external view returns (address pair)
+        function token0() external view returns (address)
+        function getReserves() external view returns (uint112 reserve0, uint112 reserve1, uint32 blockTimestampLast)
+    ]"#
+);
+
+abigen!(
+    ISplitV3Factory,
+    r#"[
+        function getPool(address tokenA, address tokenB, uint24 fee) external view returns (address pool)
+    ]"#
+);
+
+const BPS: u64 = 10_000;
+
+// A large first hop moves a single pool's price against itself; when the pair trades on both
+// a V2 and a V3 pool, selling part of it into each often returns more. The ratio is the one
+// that equalizes the two pools' marginal output, and the legs go to executors taking a
+// `split_legs` argument, (router, pool, amountIn, amountOutMin)[]
+#[derive(Debug, Clone, Deserialize)]
+pub struct SplitRouteSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_v2_factory")]
+    pub v2_factory: Address,
+    #[serde(default = "default_v2_router")]
+    pub v2_router: Address,
+    #[serde(default = "default_v3_factory")]
+    pub v3_factory: Address,
+    #[serde(default = "default_v3_router")]
+    pub v3_router: Address,
+    // V3 fee tiers looked up for the pair; the one splitting best is used
+    #[serde(default = "default_v3_fees")]
+    pub v3_fees: Vec<u32>,
+    // The search stops once the ratio is pinned to this share of the input
+    #[serde(default = "default_precision_bps")]
+    pub precision_bps: u64,
+    // Output the split must add over the best single pool to pay for the second leg's gas
+    #[serde(default = "default_min_gain_bps")]
+    pub min_gain_bps: u64,
+    // Below each leg's quoted output it may fill before the executor reverts
+    #[serde(default = "default_slippage_bps")]
+    pub slippage_bps: u64,
+}
+
+// QuickSwap
+fn default_v2_factory() -> Address {
+    "0x5757371414417b8C6CAad45bAeF941aBc7d3Ab32".parse().unwrap()
+}
+
+fn default_v2_router() -> Address {
+    "0xa5E0829CaCEd8fFDD4De3c43696c57F7D7A678ff".parse().unwrap()
+}
+
+// Uniswap V3
+fn default_v3_factory() -> Address {
+    "0x1F98431c8aD98523631AE4a59f267346ea31F984".parse().unwrap()
+}
+
+fn default_v3_router() -> Address {
+    "0xE592427A0AEce92De3Edee1F18E0157C05861564".parse().unwrap()
+}
+
+fn default_v3_fees() -> Vec<u32> {
+    vec![500, 3000]
+}
+
+fn default_precision_bps() -> u64 {
+    10
+}
+
+fn default_min_gain_bps() -> u64 {
+    5
+}
+
+fn default_slippage_bps() -> u64 {
+    50
+}
+
+impl Default for SplitRouteSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            v2_factory: default_v2_factory(),
+            v2_router: default_v2_router(),
+            v3_factory: default_v3_factory(),
+            v3_router: default_v3_router(),
+            v3_fees: default_v3_fees(),
+            precision_bps: default_precision_bps(),
+            min_gain_bps: default_min_gain_bps(),
+            slippage_bps: default_slippage_bps(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SplitLeg {
+    pub router: Address,
+    pub pool: Address,
+    pub amount_in: U256,
+    pub amount_out_min: U256,
+}
+
+impl SplitLeg {
+    pub fn param_type() -> ParamType {
+        ParamType::Tuple(vec![ParamType::Address, ParamType::Address, ParamType::Uint(256), ParamType::Uint(256)])
+    }
+
+    pub fn to_token(&self) -> Token {
+        Token::Tuple(vec![
+            Token::Address(self.router),
+            Token::Address(self.pool),
+            Token::Uint(self.amount_in),
+            Token::Uint(self.amount_out_min),
+        ])
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SplitPlan {
+    pub legs: Vec<SplitLeg>,
+    // Quoted output of both legs together, and of the best pool taking everything
+    pub amount_out: U256,
+    pub single_out: U256,
+}
+
+impl SplitPlan {
+    // Share of the input sent through the first leg
+    pub fn ratio_bps(&self) -> u64 {
+        let total = self.legs.iter().fold(U256::zero(), |total, leg| total + leg.amount_in);
+        if total.is_zero() {
+            return 0;
+        }
+        (self.legs[0].amount_in * U256::from(BPS) / total).as_u64()
+    }
+}
+
+// Input of `quote_a` maximizing quote_a(x) + quote_b(amount_in - x), with the total. Both
+// quotes are concave in the input, so the total is too and the sign of its slope brackets
+// the peak
+pub fn best_split<A, B>(amount_in: U256, quote_a: A, quote_b: B, precision_bps: u64) -> (U256, U256)
+where
+    A: Fn(U256) -> U256,
+    B: Fn(U256) -> U256,
+{
+    let total = |amount_a: U256| quote_a(amount_a) + quote_b(amount_in - amount_a);
+    let precision = (amount_in * U256::from(precision_bps.max(1)) / U256::from(BPS)).max(U256::from(2u64));
+    let step = precision / 2;
+    let (mut lo, mut hi) = (U256::zero(), amount_in);
+    while hi - lo > precision {
+        let mid = lo + (hi - lo) / 2;
+        if total(mid + step) > total(mid) {
+            lo = mid + step;
+        } else {
+            hi = mid;
+        }
+    }
+    [lo, hi, U256::zero(), amount_in]
+        .into_iter()
+        .map(|amount_a| (amount_a, total(amount_a)))
+        .max_by_key(|(_, out)| *out)
+        .unwrap_or_default()
+}
+
+struct SplitVenue<'a> {
+    router: Address,
+    pool: Address,
+    quote: Box<dyn Fn(U256) -> U256 + Send + Sync + 'a>,
+}
+
+// Collects the pools trading one hop and builds its two legs
+pub struct SplitRouter<'a> {
+    settings: &'a SplitRouteSettings,
+    venues: Vec<SplitVenue<'a>>,
+}
+
+impl<'a> SplitRouter<'a> {
+    pub fn new(settings: &'a SplitRouteSettings) -> Self {
+        Self {
+            settings,
+            venues: Vec::new(),
+        }
+    }
+
+    // `quote` returns the pool's output for an input of the hop's token
+    pub fn venue(mut self, router: Address, pool: Address, quote: impl Fn(U256) -> U256 + Send + Sync + 'a) -> Self {
+        self.venues.push(SplitVenue {
+            router,
+            pool,
+            quote: Box::new(quote),
+        });
+        self
+    }
+
+    // Two legs at the best ratio over the best pair of venues; None when no split beats the
+    // best single venue by `min_gain_bps`
+    pub fn build(&self, amount_in: U256) -> Option<SplitPlan> {
+        let single_out = self.venues.iter().map(|venue| (venue.quote)(amount_in)).max()?;
+        let mut best: Option<(usize, usize, U256, U256)> = None;
+        for (i, a) in self.venues.iter().enumerate() {
+            for (j, b) in self.venues.iter().enumerate().skip(i + 1) {
+                let (amount_a, out) = best_split(amount_in, &a.quote, &b.quote, self.settings.precision_bps);
+                if best.is_none_or(|(.., best_out)| out > best_out) {
+                    best = Some((i, j, amount_a, out));
+                }
+            }
+        }
+        let (i, j, amount_a, amount_out) = best?;
+        let threshold = single_out + single_out * U256::from(self.settings.min_gain_bps) / U256::from(BPS);
+        if amount_a.is_zero() || amount_a == amount_in || amount_out <= threshold {
+            return None;
+        }
+
+        let floor = U256::from(BPS.saturating_sub(self.settings.slippage_bps));
+        let leg = |venue: &SplitVenue, amount: U256| SplitLeg {
+            router: venue.router,
+            pool: venue.pool,
+            amount_in: amount,
+            amount_out_min: (venue.quote)(amount) * floor / U256::from(BPS),
+        };
+        Some(SplitPlan {
+            legs: vec![leg(&self.venues[i], amount_a), leg(&self.venues[j], amount_in - amount_a)],
+            amount_out,
+            single_out,
+        })
+    }
+}
+
+// Reads the pair's V2 pool and V3 pools at execution time and plans the hop's split
+#[derive(Debug)]
+pub struct SplitPlanner<M> {
+    provider: Arc<M>,
+    settings: SplitRouteSettings,
+    fees: FeeSettings,
+    v3: V3Settings,
+}
+
+impl<M: Middleware + 'static> SplitPlanner<M> {
+    pub fn new(provider: Arc<M>, settings: SplitRouteSettings, fees: FeeSettings, v3: V3Settings) -> Self {
+        Self {
+            provider,
+            settings,
+            fees,
+            v3,
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.settings.enabled
+    }
+
+    // Legs of `amount_in` of `token_in` sold for `token_out` at `block_number`
+    pub async fn plan(
+        &self,
+        token_in: Address,
+        token_out: Address,
+        amount_in: U256,
+        block_number: u64,
+    ) -> Result<Option<SplitPlan>> {
+        let block = BlockId::Number(block_number.into());
+        let v2_factory = ISplitV2Pool::new(self.settings.v2_factory, self.provider.clone());
+        let pair = v2_factory
+            .get_pair(token_in, token_out)
+            .block(block)
+            .call()
+            .await
+            .map_err(|e| anyhow!("getPair on {:?} failed: {e}", self.settings.v2_factory))?;
+        if pair.is_zero() {
+            SPLIT_ROUTES_TOTAL.with_label_values(&["no_pair"]).inc();
+            return Ok(None);
+        }
+        let v2 = ISplitV2Pool::new(pair, self.provider.clone());
+        let read = |e| anyhow!("V2 pool {:?} read failed: {e}", pair);
+        let token0 = v2.token_0().block(block).call().await.map_err(read)?;
+        let (reserve0, reserve1, _) = v2.get_reserves().block(block).call().await.map_err(read)?;
+        let (reserve_in, reserve_out) = if token_in == token0 {
+            (U256::from(reserve0), U256::from(reserve1))
+        } else {
+            (U256::from(reserve1), U256::from(reserve0))
+        };
+        let fee = self.fees.fee(&pair, Some(&self.settings.v2_factory));
+
+        let v3_factory = ISplitV3Factory::new(self.settings.v3_factory, self.provider.clone());
+        let mut states = Vec::new();
+        for &tier in &self.settings.v3_fees {
+            let pool = v3_factory
+                .get_pool(token_in, token_out, tier)
+                .block(block)
+                .call()
+                .await
+                .map_err(|e| anyhow!("getPool on {:?} failed: {e}", self.settings.v3_factory))?;
+            if pool.is_zero() {
+                continue;
+            }
+            let kind = V3PoolKind::UniswapV3;
+            let state = fetch_pool_state(self.provider.clone(), pool, kind, self.v3.bitmap_words, block, block_number)
+                .await?;
+            states.push((pool, state));
+        }
+
+        let max_ticks = self.v3.max_ticks_crossed;
+        let mut router = SplitRouter::new(&self.settings).venue(self.settings.v2_router, pair, |amount| {
+            get_amount_out(amount, reserve_in, reserve_out, fee)
+        });
+        for (pool, state) in &states {
+            router = router.venue(self.settings.v3_router, *pool, move |amount| {
+                // A quote running out of mirrored ticks is not trusted at all
+                state
+                    .quote(token_in, amount, max_ticks)
+                    .filter(|quote| quote.exhausted.is_none())
+                    .map_or(U256::zero(), |quote| quote.amount_out)
+            });
+        }
+        let plan = router.build(amount_in);
+        let outcome = match (&plan, states.is_empty()) {
+            (Some(_), _) => "split",
+            (None, true) => "no_pair",
+            (None, false) => "single",
+        };
+        SPLIT_ROUTES_TOTAL.with_label_values(&[outcome]).inc();
+        Ok(plan)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::address;
+    use crate::v3_math::{sqrt_ratio_at_tick, TickRange, quote_exact_input};
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_a_large_hop_splits_between_v2_and_v3_at_equal_marginal_output() {
+        let e18 = U256::exp10(18);
+        let (reserve_in, reserve_out) = (e18 * 1_000, e18 * 1_000);
+        let depth = 10u128.pow(21);
+        let ticks = BTreeMap::from([(-12_000, depth as i128), (12_000, -(depth as i128))]);
+        let range = TickRange {
+            sqrt_price_x96: sqrt_ratio_at_tick(0),
+            tick: 0,
+            liquidity: depth,
+            fee: 500,
+            ticks: &ticks,
+            range_lower: -12_000,
+            range_upper: 12_000,
+        };
+        let v2 = |amount| get_amount_out(amount, reserve_in, reserve_out, 3000);
+        let v3 = |amount| quote_exact_input(&range, amount, true, 8).map_or(U256::zero(), |quote| quote.amount_out);
+
+        let settings = SplitRouteSettings::default();
+        let (v2_router, v3_router) = (settings.v2_router, settings.v3_router);
+        let router = SplitRouter::new(&settings).venue(v2_router, address(1), v2).venue(v3_router, address(2), v3);
+
+        // A small trade stays in the cheaper pool
+        assert_eq!(router.build(e18), None);
+
+        let amount = e18 * 200;
+        let plan = router.build(amount).unwrap();
+        assert!(plan.amount_out > plan.single_out);
+        assert!(plan.amount_out >= v2(plan.legs[0].amount_in) + v3(plan.legs[1].amount_in));
+        assert_eq!(plan.legs[0].amount_in + plan.legs[1].amount_in, amount);
+        assert!((1..9_999).contains(&plan.ratio_bps()));
+        assert_eq!(plan.legs[1].router, v3_router);
+        assert_eq!(plan.legs[1].amount_out_min, v3(plan.legs[1].amount_in) * 9_950 / 10_000);
+
+        // Shifting a sliver either way loses output
+        let nudge = amount / 100;
+        let at = |amount_a: U256| v2(amount_a) + v3(amount - amount_a);
+        assert!(at(plan.legs[0].amount_in + nudge) <= plan.amount_out);
+        assert!(at(plan.legs[0].amount_in - nudge) <= plan.amount_out);
+    }
+}
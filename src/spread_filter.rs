@@ -0,0 +1,206 @@
+// src/spread_filter.rs
+use ethers::types::{Address, U256};
+use serde::Deserialize;
+use std::sync::Arc;
+
+use crate::token_registry::TokenRegistry;
+
+// Sanity checks on the pool price spreads that start a route search. Only two pools of the
+// same ordered token pair quote comparable prices; gaps too wide to be arbitrage and pools
+// between two tokens of the same asset (per their tokens.json tags) trading far off 1:1 are
+// taken for stale, drained or mis-scaled pools instead of being simulated
+#[derive(Debug, Clone, Deserialize)]
+pub struct SpreadFilterSettings {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    // Gap between the two pools' prices worth a route search
+    #[serde(default = "default_min_spread_bps")]
+    pub min_spread_bps: f64,
+    #[serde(default = "default_max_spread_bps")]
+    pub max_spread_bps: f64,
+    // Tokens sharing one of these tags track the same asset
+    #[serde(default = "default_correlated_tags")]
+    pub correlated_tags: Vec<String>,
+    // How far such a pair may trade from 1:1 in whole units
+    #[serde(default = "default_max_peg_deviation_bps")]
+    pub max_peg_deviation_bps: f64,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_min_spread_bps() -> f64 {
+    100.0
+}
+
+fn default_max_spread_bps() -> f64 {
+    5_000.0
+}
+
+fn default_correlated_tags() -> Vec<String> {
+    vec!["stable".into(), "btc".into(), "eth".into()]
+}
+
+fn default_max_peg_deviation_bps() -> f64 {
+    500.0
+}
+
+impl Default for SpreadFilterSettings {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            min_spread_bps: default_min_spread_bps(),
+            max_spread_bps: default_max_spread_bps(),
+            correlated_tags: default_correlated_tags(),
+            max_peg_deviation_bps: default_max_peg_deviation_bps(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SpreadCheck {
+    // Wide enough to search, in bps
+    Spread(f64),
+    Narrow(f64),
+    Implausible(f64),
+    // A correlated pair trading off 1:1 in one of the pools
+    OffPeg,
+}
+
+impl SpreadCheck {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SpreadCheck::Spread(_) => "spread",
+            SpreadCheck::Narrow(_) => "narrow",
+            SpreadCheck::Implausible(_) => "implausible",
+            SpreadCheck::OffPeg => "off_peg",
+        }
+    }
+
+    // Spread worth recording in the route search's history
+    pub fn spread_bps(&self) -> Option<f64> {
+        match *self {
+            SpreadCheck::Spread(bps) | SpreadCheck::Narrow(bps) => Some(bps),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SpreadFilter {
+    settings: SpreadFilterSettings,
+    // Decimals and tags for the peg check; without it only the spread bounds apply
+    tokens: Option<Arc<TokenRegistry>>,
+}
+
+impl SpreadFilter {
+    pub fn new(settings: SpreadFilterSettings, tokens: Option<Arc<TokenRegistry>>) -> Self {
+        Self { settings, tokens }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.settings.enabled
+    }
+
+    // Whether two pools' prices can be compared at all; disabled, any two pools are
+    pub fn comparable(&self, tokens_a: Option<&(Address, Address)>, tokens_b: Option<&(Address, Address)>) -> bool {
+        !self.settings.enabled || (tokens_a.is_some() && tokens_a == tokens_b)
+    }
+
+    // Spread between two pools of `pair`, reserves in token0/token1 order
+    pub fn check(&self, pair: (Address, Address), reserves_a: (U256, U256), reserves_b: (U256, U256)) -> SpreadCheck {
+        let zero = |(reserve0, reserve1): (U256, U256)| reserve0.is_zero() || reserve1.is_zero();
+        if self.settings.enabled && (zero(reserves_a) || zero(reserves_b)) {
+            return SpreadCheck::Implausible(f64::INFINITY);
+        }
+        let price_a = reserves_a.0.as_u128() as f64 / reserves_a.1.as_u128() as f64;
+        let price_b = reserves_b.0.as_u128() as f64 / reserves_b.1.as_u128() as f64;
+        let spread_bps = (price_a - price_b).abs() / price_a * 10_000.0;
+
+        if self.settings.enabled {
+            if spread_bps > self.settings.max_spread_bps {
+                return SpreadCheck::Implausible(spread_bps);
+            }
+            if !self.on_peg(pair, reserves_a) || !self.on_peg(pair, reserves_b) {
+                return SpreadCheck::OffPeg;
+            }
+        }
+        if spread_bps > self.settings.min_spread_bps {
+            SpreadCheck::Spread(spread_bps)
+        } else {
+            SpreadCheck::Narrow(spread_bps)
+        }
+    }
+
+    fn on_peg(&self, (token0, token1): (Address, Address), (reserve0, reserve1): (U256, U256)) -> bool {
+        let Some(tokens) = &self.tokens else {
+            return true;
+        };
+        let (Some(info0), Some(info1)) = (tokens.get(&token0), tokens.get(&token1)) else {
+            return true;
+        };
+        let correlated = self
+            .settings
+            .correlated_tags
+            .iter()
+            .any(|tag| info0.tags.contains(tag) && info1.tags.contains(tag));
+        if !correlated {
+            return true;
+        }
+        // token1 per token0 in whole units
+        let units = |reserve: U256, decimals: u8| reserve.as_u128() as f64 / 10f64.powi(decimals as i32);
+        let price = units(reserve1, info1.decimals) / units(reserve0, info0.decimals);
+        (price - 1.0).abs() * 10_000.0 <= self.settings.max_peg_deviation_bps
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::address;
+    use crate::token_registry::TokenInfo;
+
+    #[test]
+    fn test_only_same_pair_pools_with_plausible_spreads_pass() {
+        let (usdc, dai, wbtc) = (address(1), address(2), address(3));
+        let tokens = TokenRegistry::new();
+        for (token, symbol, decimals, tag) in [(usdc, "USDC", 6, "stable"), (dai, "DAI", 18, "stable"), (wbtc, "WBTC", 8, "btc")] {
+            tokens
+                .insert(TokenInfo {
+                    address: token,
+                    name: symbol.into(),
+                    symbol: symbol.into(),
+                    decimals,
+                    tags: vec![tag.into()],
+                })
+                .unwrap();
+        }
+        let filter = SpreadFilter::new(SpreadFilterSettings::default(), Some(Arc::new(tokens)));
+
+        let pair = (usdc, dai);
+        assert!(filter.comparable(Some(&pair), Some(&(usdc, dai))));
+        assert!(!filter.comparable(Some(&pair), Some(&(dai, usdc))));
+        assert!(!filter.comparable(Some(&pair), Some(&(usdc, wbtc))));
+        assert!(!filter.comparable(None, None));
+
+        // 1M USDC against 1M DAI, and 2% apart
+        let usd = |amount: u64, decimals: u32| U256::from(amount) * U256::exp10(decimals as usize);
+        let pegged = (usd(1_000_000, 6), usd(1_000_000, 18));
+        let cheaper = (usd(1_000_000, 6), usd(1_020_000, 18));
+        assert!(matches!(filter.check(pair, pegged, cheaper), SpreadCheck::Spread(bps) if bps > 190.0));
+        assert!(matches!(filter.check(pair, pegged, pegged), SpreadCheck::Narrow(_)));
+        // Pricing DAI at 18 decimals as if it had 6 is not a 1:1 pair
+        let mis_scaled = (usd(1_000_000, 6), usd(1_020_000, 6));
+        let rescaled = (usd(1_000_000, 6), usd(1_000_000, 6));
+        assert_eq!(filter.check(pair, rescaled, mis_scaled), SpreadCheck::OffPeg);
+        let drained = (usd(1_000_000, 6), usd(3_000_000, 18));
+        assert!(matches!(filter.check(pair, pegged, drained), SpreadCheck::Implausible(_)));
+        assert!(matches!(filter.check(pair, pegged, (U256::zero(), U256::one())), SpreadCheck::Implausible(_)));
+
+        // Without the filter any gap over the minimum counts, as before
+        let disabled = SpreadFilter::new(SpreadFilterSettings { enabled: false, ..Default::default() }, None);
+        assert!(disabled.comparable(Some(&pair), Some(&(usdc, wbtc))));
+        assert!(matches!(disabled.check(pair, pegged, drained), SpreadCheck::Spread(_)));
+    }
+}
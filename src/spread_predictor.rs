@@ -0,0 +1,141 @@
+// src/spread_predictor.rs
+use ethers::types::U256;
+use serde::Deserialize;
+use std::fmt;
+
+use crate::metrics::SPREAD_PERSISTENCE_BLOCKS;
+use crate::opportunity_class::OpportunityClass;
+
+// What a predictor sees of a detected spread
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpreadFeatures {
+    pub class: OpportunityClass,
+    pub hops: usize,
+    // Expected profit over the input amount
+    pub spread_bps: f64,
+    // Blocks the spread has already stayed open; 0 when just detected
+    pub age_blocks: u64,
+}
+
+impl SpreadFeatures {
+    pub fn new(class: OpportunityClass, hops: usize, amount_in: U256, profit: U256, age_blocks: u64) -> Self {
+        let spread_bps = if amount_in.is_zero() {
+            0.0
+        } else {
+            (profit.saturating_mul(U256::from(10_000u64)) / amount_in).min(U256::from(u64::MAX)).as_u64() as f64
+        };
+        Self {
+            class,
+            hops,
+            spread_bps,
+            age_blocks,
+        }
+    }
+}
+
+// Blocks a detected spread is expected to stay open, at least 1. The fan-out offers a trade
+// for no more blocks than that, and tracked routes expected to close soonest execute first.
+// A model of one's own plugs in behind this trait through `MevBot::with_spread_predictor`
+pub trait SpreadPredictor: fmt::Debug + Send + Sync {
+    fn persistence_blocks(&self, features: &SpreadFeatures) -> f64;
+}
+
+// Default predictor: wide spreads draw other searchers and close fast, a spread that has
+// already survived a few blocks likely survives a few more, and a backrun only ever has
+// its victim's block
+#[derive(Debug, Clone, Deserialize)]
+pub struct SpreadPredictorSettings {
+    // Persistence of a fresh spread of reference_bps
+    #[serde(default = "default_base_blocks")]
+    pub base_blocks: f64,
+    #[serde(default = "default_reference_bps")]
+    pub reference_bps: f64,
+    // Extra persistence per block already survived, as a fraction of the estimate
+    #[serde(default = "default_age_weight")]
+    pub age_weight: f64,
+    #[serde(default = "default_max_blocks")]
+    pub max_blocks: f64,
+}
+
+fn default_base_blocks() -> f64 {
+    2.0
+}
+
+fn default_reference_bps() -> f64 {
+    30.0
+}
+
+fn default_age_weight() -> f64 {
+    0.5
+}
+
+fn default_max_blocks() -> f64 {
+    6.0
+}
+
+impl Default for SpreadPredictorSettings {
+    fn default() -> Self {
+        Self {
+            base_blocks: default_base_blocks(),
+            reference_bps: default_reference_bps(),
+            age_weight: default_age_weight(),
+            max_blocks: default_max_blocks(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct HeuristicPredictor {
+    settings: SpreadPredictorSettings,
+}
+
+impl HeuristicPredictor {
+    pub fn new(settings: SpreadPredictorSettings) -> Self {
+        Self { settings }
+    }
+}
+
+impl SpreadPredictor for HeuristicPredictor {
+    fn persistence_blocks(&self, features: &SpreadFeatures) -> f64 {
+        if features.class == OpportunityClass::Backrun {
+            return 1.0;
+        }
+        let width = (self.settings.reference_bps / features.spread_bps.max(1.0)).clamp(0.5, 2.0);
+        let survived = 1.0 + features.age_blocks as f64 * self.settings.age_weight;
+        (self.settings.base_blocks * width * survived).clamp(1.0, self.settings.max_blocks.max(1.0))
+    }
+}
+
+// Asks `predictor`, keeping the answer within [1, ∞) whatever the model returns
+pub fn predict(predictor: &dyn SpreadPredictor, features: &SpreadFeatures) -> f64 {
+    let blocks = predictor.persistence_blocks(features);
+    let blocks = if blocks.is_finite() { blocks.max(1.0) } else { 1.0 };
+    SPREAD_PERSISTENCE_BLOCKS.observe(blocks);
+    blocks
+}
+
+// Execution order: profit per block the spread is expected to last, so the ones about to
+// close go before those that can wait for the next round
+pub fn priority(profit: U256, persistence_blocks: f64) -> f64 {
+    profit.min(U256::from(u128::MAX)).as_u128() as f64 / persistence_blocks.max(1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wide_fresh_spreads_close_sooner_than_narrow_survivors() {
+        let predictor = HeuristicPredictor::default();
+        let amount = U256::exp10(18);
+        let wide = SpreadFeatures::new(OpportunityClass::CrossDex, 2, amount, amount / 50, 0);
+        let narrow = SpreadFeatures::new(OpportunityClass::CrossDex, 2, amount, amount / 1_000, 0);
+        assert_eq!(wide.spread_bps, 200.0);
+
+        assert_eq!(predict(&predictor, &wide), 1.0);
+        assert_eq!(predict(&predictor, &narrow), 4.0);
+        assert_eq!(predict(&predictor, &SpreadFeatures { age_blocks: 2, ..narrow }), 6.0);
+        assert_eq!(predict(&predictor, &SpreadFeatures { class: OpportunityClass::Backrun, ..narrow }), 1.0);
+        assert!(priority(U256::from(100u64), 1.0) > priority(U256::from(150u64), 2.0));
+    }
+}
@@ -0,0 +1,1111 @@
+// src/store.rs
+use anyhow::{anyhow, Result};
+use ethers::types::{Address, H256, U256};
+use rusqlite::{params, types::Value, Connection, OpenFlags, OptionalExtension, ToSql};
+use serde::Deserialize;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use crate::opportunity_id::OpportunityId;
+use crate::pool_events::PoolEvent;
+use crate::token_registry::{TokenInfo, TokenTax};
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct StoreSettings {
+    #[serde(default = "default_store_path")]
+    pub path: String,
+}
+
+fn default_store_path() -> String {
+    "flashwich.db".to_string()
+}
+
+impl Default for StoreSettings {
+    fn default() -> Self {
+        Self {
+            path: default_store_path(),
+        }
+    }
+}
+
+// One indexed pool log. Amount columns depend on `kind`:
+//   swap: amount0In, amount1In, amount0Out, amount1Out
+//   sync: reserve0, reserve1
+//   mint/burn: amount0, amount1
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PoolEventRecord {
+    pub block_number: u64,
+    pub log_index: u64,
+    pub tx_hash: H256,
+    pub pool: Address,
+    pub timestamp: Option<u64>,
+    pub kind: String,
+    pub amounts: [U256; 4],
+}
+
+impl PoolEventRecord {
+    pub fn from_event(
+        event: &PoolEvent,
+        pool: Address,
+        block_number: u64,
+        log_index: u64,
+        tx_hash: H256,
+        timestamp: Option<u64>,
+    ) -> Self {
+        let amounts = match event {
+            PoolEvent::Swap(e) => [e.amount_0_in, e.amount_1_in, e.amount_0_out, e.amount_1_out],
+            PoolEvent::Sync(e) => [e.reserve_0.into(), e.reserve_1.into(), U256::zero(), U256::zero()],
+            PoolEvent::Mint(e) => [e.amount_0, e.amount_1, U256::zero(), U256::zero()],
+            PoolEvent::Burn(e) => [e.amount_0, e.amount_1, U256::zero(), U256::zero()],
+        };
+
+        Self {
+            block_number,
+            log_index,
+            tx_hash,
+            pool,
+            timestamp,
+            kind: event.kind().to_string(),
+            amounts,
+        }
+    }
+}
+
+// One row of the opportunity journal; `status` is detected, below_threshold, disabled,
+// submitted or failed, and a submission becomes included (or reorged) once its executor
+// event is confirmations deep. A bundle that missed its blocks is failed with the reason
+// found for it: victim_dropped, outbid or stale_spread
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OpportunityRecord {
+    pub detected_at: u64,
+    pub block_number: u64,
+    pub class: String,
+    pub token0: Address,
+    pub token1: Address,
+    pub expected_profit: U256,
+    pub min_profit: U256,
+    pub status: String,
+    pub tx_hash: Option<H256>,
+    // None for entries journaled before opportunities had ids
+    pub opportunity_id: Option<OpportunityId>,
+}
+
+// One booked executor event; `status` is executed or failed. For failed flash loans
+// token0 holds the pool and `reason` the revert string
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExecutionRecord {
+    pub block_number: u64,
+    pub log_index: u64,
+    pub tx_hash: H256,
+    pub status: String,
+    pub token0: Address,
+    pub token1: Address,
+    pub amount0: U256,
+    pub amount1: U256,
+    pub profit: U256,
+    pub reason: Option<String>,
+}
+
+// Gas actually paid for one mined submission, in wei
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GasSpendRecord {
+    pub tx_hash: H256,
+    pub block_number: u64,
+    pub gas_used: U256,
+    pub cost: U256,
+}
+
+// Gas of one successful flash-loan execution, with the provider and route length it
+// calibrates the callback gas model for
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CallbackGasRecord {
+    pub tx_hash: H256,
+    pub block_number: u64,
+    pub provider: String,
+    pub hops: u64,
+    pub gas_used: U256,
+}
+
+// One mined payout transfer out of the wallet
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PayoutRecord {
+    pub tx_hash: H256,
+    pub block_number: u64,
+    pub payee: Address,
+    pub token: Address,
+    pub amount: U256,
+}
+
+// On-chain balance of `holder` in `token` (Address::zero() for MATIC) the last
+// reconciliation agreed on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BalanceCheckpoint {
+    pub holder: Address,
+    pub token: Address,
+    pub block_number: u64,
+    pub balance: U256,
+}
+
+// SQLite persistence shared by the indexer, journal and analytics
+#[derive(Debug, Clone)]
+pub struct Store {
+    conn: Arc<Mutex<Connection>>,
+}
+
+fn hex<T: std::fmt::Debug>(value: &T) -> String {
+    format!("{:?}", value)
+}
+
+fn parse_u256(value: String) -> Result<U256> {
+    U256::from_dec_str(&value).map_err(|e| anyhow!("Invalid stored amount '{value}': {e}"))
+}
+
+impl Store {
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let conn = Connection::open(path)?;
+        Self::from_connection(conn)
+    }
+
+    // A connection that can only read, for exports next to a running bot; nothing is migrated
+    pub fn open_read_only<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let conn = Connection::open_with_flags(path, OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    pub fn open_in_memory() -> Result<Self> {
+        Self::from_connection(Connection::open_in_memory()?)
+    }
+
+    fn from_connection(conn: Connection) -> Result<Self> {
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        let store = Self {
+            conn: Arc::new(Mutex::new(conn)),
+        };
+        store.migrate()?;
+        Ok(store)
+    }
+
+    fn migrate(&self) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS pool_events (
+                block_number INTEGER NOT NULL,
+                log_index    INTEGER NOT NULL,
+                tx_hash      TEXT NOT NULL,
+                pool         TEXT NOT NULL,
+                timestamp    INTEGER,
+                kind         TEXT NOT NULL,
+                amount0      TEXT NOT NULL,
+                amount1      TEXT NOT NULL,
+                amount2      TEXT NOT NULL,
+                amount3      TEXT NOT NULL,
+                PRIMARY KEY (tx_hash, log_index)
+            );
+            CREATE INDEX IF NOT EXISTS pool_events_pool_block
+                ON pool_events (pool, block_number);
+            CREATE INDEX IF NOT EXISTS pool_events_pool_time
+                ON pool_events (pool, timestamp);
+
+            CREATE TABLE IF NOT EXISTS indexer_state (
+                name       TEXT PRIMARY KEY,
+                last_block INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS tokens (
+                address  TEXT PRIMARY KEY,
+                name     TEXT NOT NULL,
+                symbol   TEXT NOT NULL,
+                decimals INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS token_taxes (
+                token       TEXT PRIMARY KEY,
+                buy_bps     INTEGER NOT NULL,
+                sell_bps    INTEGER NOT NULL,
+                measured_at INTEGER NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS opportunities (
+                id              INTEGER PRIMARY KEY AUTOINCREMENT,
+                detected_at     INTEGER NOT NULL,
+                block_number    INTEGER NOT NULL,
+                class           TEXT NOT NULL,
+                token0          TEXT NOT NULL,
+                token1          TEXT NOT NULL,
+                expected_profit TEXT NOT NULL,
+                min_profit      TEXT NOT NULL,
+                status          TEXT NOT NULL,
+                tx_hash         TEXT,
+                opportunity_id  TEXT
+            );
+            CREATE INDEX IF NOT EXISTS opportunities_class_time
+                ON opportunities (class, detected_at);
+
+            CREATE TABLE IF NOT EXISTS payouts_owed (
+                payee  TEXT NOT NULL,
+                token  TEXT NOT NULL,
+                amount TEXT NOT NULL,
+                PRIMARY KEY (payee, token)
+            );
+
+            CREATE TABLE IF NOT EXISTS executions (
+                block_number INTEGER NOT NULL,
+                log_index    INTEGER NOT NULL,
+                tx_hash      TEXT NOT NULL,
+                status       TEXT NOT NULL,
+                token0       TEXT NOT NULL,
+                token1       TEXT NOT NULL,
+                amount0      TEXT NOT NULL,
+                amount1      TEXT NOT NULL,
+                profit       TEXT NOT NULL,
+                reason       TEXT,
+                PRIMARY KEY (tx_hash, log_index)
+            );
+            CREATE INDEX IF NOT EXISTS executions_block
+                ON executions (block_number);
+
+            CREATE TABLE IF NOT EXISTS pending_executions (
+                block_number INTEGER NOT NULL,
+                block_hash   TEXT NOT NULL,
+                log_index    INTEGER NOT NULL,
+                tx_hash      TEXT NOT NULL,
+                status       TEXT NOT NULL,
+                token0       TEXT NOT NULL,
+                token1       TEXT NOT NULL,
+                amount0      TEXT NOT NULL,
+                amount1      TEXT NOT NULL,
+                profit       TEXT NOT NULL,
+                reason       TEXT,
+                PRIMARY KEY (tx_hash, log_index)
+            );
+
+            CREATE TABLE IF NOT EXISTS gas_spend (
+                tx_hash      TEXT PRIMARY KEY,
+                block_number INTEGER NOT NULL,
+                gas_used     TEXT NOT NULL,
+                cost         TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS callback_gas (
+                tx_hash      TEXT PRIMARY KEY,
+                block_number INTEGER NOT NULL,
+                provider     TEXT NOT NULL,
+                hops         INTEGER NOT NULL,
+                gas_used     TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS payouts (
+                tx_hash      TEXT PRIMARY KEY,
+                block_number INTEGER NOT NULL,
+                payee        TEXT NOT NULL,
+                token        TEXT NOT NULL,
+                amount       TEXT NOT NULL
+            );
+
+            CREATE TABLE IF NOT EXISTS balance_checkpoints (
+                holder       TEXT NOT NULL,
+                token        TEXT NOT NULL,
+                block_number INTEGER NOT NULL,
+                balance      TEXT NOT NULL,
+                PRIMARY KEY (holder, token)
+            );",
+        )?;
+        // Journals created before opportunity ids
+        let columns: Vec<String> = conn
+            .prepare("PRAGMA table_info(opportunities)")?
+            .query_map([], |row| row.get::<_, String>(1))?
+            .collect::<rusqlite::Result<_>>()?;
+        if !columns.iter().any(|column| column == "opportunity_id") {
+            conn.execute("ALTER TABLE opportunities ADD COLUMN opportunity_id TEXT", [])?;
+        }
+        conn.execute("CREATE INDEX IF NOT EXISTS opportunities_id ON opportunities (opportunity_id)", [])?;
+        Ok(())
+    }
+
+    // Idempotent: re-ingesting an already indexed log is a no-op
+    pub fn insert_pool_events(&self, records: &[PoolEventRecord]) -> Result<usize> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let mut inserted = 0;
+        {
+            let mut stmt = tx.prepare_cached(
+                "INSERT OR IGNORE INTO pool_events
+                    (block_number, log_index, tx_hash, pool, timestamp, kind,
+                     amount0, amount1, amount2, amount3)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            )?;
+            for record in records {
+                inserted += stmt.execute(params![
+                    record.block_number as i64,
+                    record.log_index as i64,
+                    hex(&record.tx_hash),
+                    hex(&record.pool),
+                    record.timestamp.map(|t| t as i64),
+                    record.kind,
+                    record.amounts[0].to_string(),
+                    record.amounts[1].to_string(),
+                    record.amounts[2].to_string(),
+                    record.amounts[3].to_string(),
+                ])?;
+            }
+        }
+        tx.commit()?;
+        Ok(inserted)
+    }
+
+    pub fn pool_events(
+        &self,
+        pool: Address,
+        from_block: u64,
+        kind: Option<&str>,
+    ) -> Result<Vec<PoolEventRecord>> {
+        self.select_pool_events(
+            "pool = ?1 AND block_number >= ?2 AND (?3 IS NULL OR kind = ?3)",
+            params![hex(&pool), from_block as i64, kind],
+        )
+    }
+
+    // Events with a block timestamp at or after `since` (unix seconds)
+    pub fn pool_events_since(
+        &self,
+        pool: Address,
+        since: u64,
+        kind: Option<&str>,
+    ) -> Result<Vec<PoolEventRecord>> {
+        self.select_pool_events(
+            "pool = ?1 AND timestamp >= ?2 AND (?3 IS NULL OR kind = ?3)",
+            params![hex(&pool), since as i64, kind],
+        )
+    }
+
+    fn select_pool_events(&self, filter: &str, params: &[&dyn ToSql]) -> Result<Vec<PoolEventRecord>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare_cached(&format!(
+            "SELECT block_number, log_index, tx_hash, pool, timestamp, kind,
+                    amount0, amount1, amount2, amount3
+             FROM pool_events
+             WHERE {filter}
+             ORDER BY block_number, log_index"
+        ))?;
+
+        let rows = stmt.query_map(params, |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, Option<i64>>(4)?,
+                row.get::<_, String>(5)?,
+                [
+                    row.get::<_, String>(6)?,
+                    row.get::<_, String>(7)?,
+                    row.get::<_, String>(8)?,
+                    row.get::<_, String>(9)?,
+                ],
+            ))
+        })?;
+
+        let mut records = Vec::new();
+        for row in rows {
+            let (block_number, log_index, tx_hash, pool, timestamp, kind, amounts) = row?;
+            let [a0, a1, a2, a3] = amounts;
+            records.push(PoolEventRecord {
+                block_number: block_number as u64,
+                log_index: log_index as u64,
+                tx_hash: tx_hash.parse()?,
+                pool: pool.parse()?,
+                timestamp: timestamp.map(|t| t as u64),
+                kind,
+                amounts: [parse_u256(a0)?, parse_u256(a1)?, parse_u256(a2)?, parse_u256(a3)?],
+            });
+        }
+        Ok(records)
+    }
+
+    // Total (token0, token1) swapped into the pool since `from_block`
+    pub fn swap_volume(&self, pool: Address, from_block: u64) -> Result<(U256, U256)> {
+        let swaps = self.pool_events(pool, from_block, Some("swap"))?;
+        Ok(swaps.iter().fold((U256::zero(), U256::zero()), |(v0, v1), swap| {
+            (v0 + swap.amounts[0], v1 + swap.amounts[1])
+        }))
+    }
+
+    pub fn last_indexed_block(&self, name: &str) -> Result<Option<u64>> {
+        let conn = self.conn.lock().unwrap();
+        let block = conn
+            .query_row(
+                "SELECT last_block FROM indexer_state WHERE name = ?1",
+                params![name],
+                |row| row.get::<_, i64>(0),
+            )
+            .optional()?;
+        Ok(block.map(|b| b as u64))
+    }
+
+    pub fn set_last_indexed_block(&self, name: &str, block: u64) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO indexer_state (name, last_block) VALUES (?1, ?2)
+             ON CONFLICT(name) DO UPDATE SET last_block = excluded.last_block",
+            params![name, block as i64],
+        )?;
+        Ok(())
+    }
+
+    // Returns the journal id used to update the entry's status later
+    pub fn record_opportunity(&self, record: &OpportunityRecord) -> Result<i64> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO opportunities
+                (detected_at, block_number, class, token0, token1,
+                 expected_profit, min_profit, status, tx_hash, opportunity_id)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                record.detected_at as i64,
+                record.block_number as i64,
+                record.class,
+                hex(&record.token0),
+                hex(&record.token1),
+                record.expected_profit.to_string(),
+                record.min_profit.to_string(),
+                record.status,
+                record.tx_hash.as_ref().map(hex),
+                record.opportunity_id.map(|id| id.to_string()),
+            ],
+        )?;
+        Ok(conn.last_insert_rowid())
+    }
+
+    pub fn set_opportunity_status(&self, id: i64, status: &str, tx_hash: Option<H256>) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "UPDATE opportunities SET status = ?2, tx_hash = COALESCE(?3, tx_hash) WHERE id = ?1",
+            params![id, status, tx_hash.as_ref().map(hex)],
+        )?;
+        Ok(())
+    }
+
+    // Moves the journal entries of `tx_hash` from one of `from` to `status`; returns how many moved
+    pub fn set_opportunity_status_by_tx(&self, tx_hash: H256, from: &[&str], status: &str) -> Result<usize> {
+        let conn = self.conn.lock().unwrap();
+        let mut updated = 0;
+        for previous in from {
+            updated += conn.execute(
+                "UPDATE opportunities SET status = ?3 WHERE tx_hash = ?1 AND status = ?2",
+                params![hex(&tx_hash), previous, status],
+            )?;
+        }
+        Ok(updated)
+    }
+
+    // (name, declared type) of every column of `table`; empty for a table that doesn't exist
+    pub fn table_columns(&self, table: &str) -> Result<Vec<(String, String)>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT name, type FROM pragma_table_info(?1) ORDER BY cid")?;
+        let columns = stmt
+            .query_map([table], |row| Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?)))?
+            .collect::<rusqlite::Result<_>>()?;
+        Ok(columns)
+    }
+
+    // Every row of `table` in insertion order, handed to `visit` `batch` rows at a time
+    pub fn scan_table<F>(&self, table: &str, batch: usize, mut visit: F) -> Result<()>
+    where
+        F: FnMut(&[Vec<Value>]) -> Result<()>,
+    {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(&format!("SELECT * FROM \"{}\" ORDER BY rowid", table.replace('"', "\"\"")))?;
+        let width = stmt.column_count();
+        let mut rows = stmt.query([])?;
+        let mut chunk = Vec::with_capacity(batch);
+        while let Some(row) = rows.next()? {
+            chunk.push((0..width).map(|i| row.get::<_, Value>(i)).collect::<rusqlite::Result<Vec<_>>>()?);
+            if chunk.len() >= batch {
+                visit(&chunk)?;
+                chunk.clear();
+            }
+        }
+        if !chunk.is_empty() {
+            visit(&chunk)?;
+        }
+        Ok(())
+    }
+
+    // Journal entries detected at or after `since` (unix seconds), optionally for one class
+    pub fn opportunities_since(&self, since: u64, class: Option<&str>) -> Result<Vec<OpportunityRecord>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare_cached(
+            "SELECT detected_at, block_number, class, token0, token1,
+                    expected_profit, min_profit, status, tx_hash, opportunity_id
+             FROM opportunities
+             WHERE detected_at >= ?1 AND (?2 IS NULL OR class = ?2)
+             ORDER BY id",
+        )?;
+        let rows = stmt.query_map(params![since as i64, class], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, String>(4)?,
+                row.get::<_, String>(5)?,
+                row.get::<_, String>(6)?,
+                row.get::<_, String>(7)?,
+                row.get::<_, Option<String>>(8)?,
+                row.get::<_, Option<String>>(9)?,
+            ))
+        })?;
+
+        let mut records = Vec::new();
+        for row in rows {
+            let (detected_at, block_number, class, token0, token1, expected, min, status, tx_hash, id) = row?;
+            records.push(OpportunityRecord {
+                detected_at: detected_at as u64,
+                block_number: block_number as u64,
+                class,
+                token0: token0.parse()?,
+                token1: token1.parse()?,
+                expected_profit: parse_u256(expected)?,
+                min_profit: parse_u256(min)?,
+                status,
+                tx_hash: tx_hash.map(|hash| hash.parse()).transpose()?,
+                opportunity_id: id.map(|id| id.parse()).transpose()?,
+            });
+        }
+        Ok(records)
+    }
+
+    // False when the event was already booked
+    pub fn insert_execution(&self, record: &ExecutionRecord) -> Result<bool> {
+        let conn = self.conn.lock().unwrap();
+        let inserted = conn.execute(
+            "INSERT OR IGNORE INTO executions
+                (block_number, log_index, tx_hash, status, token0, token1,
+                 amount0, amount1, profit, reason)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)",
+            params![
+                record.block_number as i64,
+                record.log_index as i64,
+                hex(&record.tx_hash),
+                record.status,
+                hex(&record.token0),
+                hex(&record.token1),
+                record.amount0.to_string(),
+                record.amount1.to_string(),
+                record.profit.to_string(),
+                record.reason,
+            ],
+        )?;
+        Ok(inserted > 0)
+    }
+
+    // An executor event seen in block `block_hash` but not yet final; a sighting of the same
+    // log in another block (after a reorg) replaces the earlier one
+    pub fn upsert_pending_execution(&self, record: &ExecutionRecord, block_hash: H256) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO pending_executions
+                (block_number, block_hash, log_index, tx_hash, status, token0, token1,
+                 amount0, amount1, profit, reason)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            params![
+                record.block_number as i64,
+                hex(&block_hash),
+                record.log_index as i64,
+                hex(&record.tx_hash),
+                record.status,
+                hex(&record.token0),
+                hex(&record.token1),
+                record.amount0.to_string(),
+                record.amount1.to_string(),
+                record.profit.to_string(),
+                record.reason,
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn remove_pending_execution(&self, tx_hash: H256, log_index: u64) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "DELETE FROM pending_executions WHERE tx_hash = ?1 AND log_index = ?2",
+            params![hex(&tx_hash), log_index as i64],
+        )?;
+        Ok(())
+    }
+
+    // Executor events awaiting their confirmation depth, with the block hash they were seen
+    // in, in chain order
+    pub fn pending_executions(&self) -> Result<Vec<(ExecutionRecord, H256)>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare_cached(
+            "SELECT block_number, log_index, tx_hash, status, token0, token1,
+                    amount0, amount1, profit, reason, block_hash
+             FROM pending_executions
+             ORDER BY block_number, log_index",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, String>(4)?,
+                row.get::<_, String>(5)?,
+                row.get::<_, String>(6)?,
+                row.get::<_, String>(7)?,
+                row.get::<_, String>(8)?,
+                row.get::<_, Option<String>>(9)?,
+                row.get::<_, String>(10)?,
+            ))
+        })?;
+
+        let mut records = Vec::new();
+        for row in rows {
+            let (block_number, log_index, tx_hash, status, token0, token1, amount0, amount1, profit, reason, block_hash) =
+                row?;
+            let record = ExecutionRecord {
+                block_number: block_number as u64,
+                log_index: log_index as u64,
+                tx_hash: tx_hash.parse()?,
+                status,
+                token0: token0.parse()?,
+                token1: token1.parse()?,
+                amount0: parse_u256(amount0)?,
+                amount1: parse_u256(amount1)?,
+                profit: parse_u256(profit)?,
+                reason,
+            };
+            records.push((record, block_hash.parse()?));
+        }
+        Ok(records)
+    }
+
+    // Profit of executed events since `from_block`, summed per token
+    pub fn realized_profit(&self, from_block: u64) -> Result<Vec<(Address, U256)>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare_cached(
+            "SELECT token0, profit FROM executions
+             WHERE status = 'executed' AND block_number >= ?1
+             ORDER BY token0",
+        )?;
+        let rows = stmt.query_map(params![from_block as i64], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+
+        let mut totals: Vec<(Address, U256)> = Vec::new();
+        for row in rows {
+            let (token, profit) = row?;
+            let (token, profit) = (token.parse::<Address>()?, parse_u256(profit)?);
+            match totals.last_mut() {
+                Some((last, total)) if *last == token => *total = total.saturating_add(profit),
+                _ => totals.push((token, profit)),
+            }
+        }
+        Ok(totals)
+    }
+
+    // Booked executor events since `from_block`, in chain order
+    pub fn executions_since(&self, from_block: u64) -> Result<Vec<ExecutionRecord>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare_cached(
+            "SELECT block_number, log_index, tx_hash, status, token0, token1,
+                    amount0, amount1, profit, reason
+             FROM executions
+             WHERE block_number >= ?1
+             ORDER BY block_number, log_index",
+        )?;
+        let rows = stmt.query_map(params![from_block as i64], |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, String>(4)?,
+                row.get::<_, String>(5)?,
+                row.get::<_, String>(6)?,
+                row.get::<_, String>(7)?,
+                row.get::<_, String>(8)?,
+                row.get::<_, Option<String>>(9)?,
+            ))
+        })?;
+
+        let mut records = Vec::new();
+        for row in rows {
+            let (block_number, log_index, tx_hash, status, token0, token1, amount0, amount1, profit, reason) = row?;
+            records.push(ExecutionRecord {
+                block_number: block_number as u64,
+                log_index: log_index as u64,
+                tx_hash: tx_hash.parse()?,
+                status,
+                token0: token0.parse()?,
+                token1: token1.parse()?,
+                amount0: parse_u256(amount0)?,
+                amount1: parse_u256(amount1)?,
+                profit: parse_u256(profit)?,
+                reason,
+            });
+        }
+        Ok(records)
+    }
+
+    pub fn record_gas_spend(&self, record: &GasSpendRecord) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO gas_spend (tx_hash, block_number, gas_used, cost)
+             VALUES (?1, ?2, ?3, ?4)",
+            params![
+                hex(&record.tx_hash),
+                record.block_number as i64,
+                record.gas_used.to_string(),
+                record.cost.to_string(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn gas_spend_since(&self, from_block: u64) -> Result<Vec<GasSpendRecord>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare_cached(
+            "SELECT tx_hash, block_number, gas_used, cost FROM gas_spend
+             WHERE block_number >= ?1
+             ORDER BY block_number",
+        )?;
+        let rows = stmt.query_map(params![from_block as i64], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+            ))
+        })?;
+
+        let mut records = Vec::new();
+        for row in rows {
+            let (tx_hash, block_number, gas_used, cost) = row?;
+            records.push(GasSpendRecord {
+                tx_hash: tx_hash.parse()?,
+                block_number: block_number as u64,
+                gas_used: parse_u256(gas_used)?,
+                cost: parse_u256(cost)?,
+            });
+        }
+        Ok(records)
+    }
+
+    pub fn record_callback_gas(&self, record: &CallbackGasRecord) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO callback_gas (tx_hash, block_number, provider, hops, gas_used)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                hex(&record.tx_hash),
+                record.block_number as i64,
+                record.provider,
+                record.hops as i64,
+                record.gas_used.to_string(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    // The latest `limit` executions, oldest first so they replay in order
+    pub fn recent_callback_gas(&self, limit: usize) -> Result<Vec<CallbackGasRecord>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare_cached(
+            "SELECT tx_hash, block_number, provider, hops, gas_used FROM callback_gas
+             ORDER BY block_number DESC
+             LIMIT ?1",
+        )?;
+        let rows = stmt.query_map(params![limit as i64], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, i64>(3)?,
+                row.get::<_, String>(4)?,
+            ))
+        })?;
+
+        let mut records = Vec::new();
+        for row in rows {
+            let (tx_hash, block_number, provider, hops, gas_used) = row?;
+            records.push(CallbackGasRecord {
+                tx_hash: tx_hash.parse()?,
+                block_number: block_number as u64,
+                provider,
+                hops: hops as u64,
+                gas_used: parse_u256(gas_used)?,
+            });
+        }
+        records.reverse();
+        Ok(records)
+    }
+
+    pub fn record_payout(&self, record: &PayoutRecord) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT OR REPLACE INTO payouts (tx_hash, block_number, payee, token, amount)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                hex(&record.tx_hash),
+                record.block_number as i64,
+                hex(&record.payee),
+                hex(&record.token),
+                record.amount.to_string(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn payouts_since(&self, from_block: u64) -> Result<Vec<PayoutRecord>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare_cached(
+            "SELECT tx_hash, block_number, payee, token, amount FROM payouts
+             WHERE block_number >= ?1
+             ORDER BY block_number",
+        )?;
+        let rows = stmt.query_map(params![from_block as i64], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+                row.get::<_, String>(4)?,
+            ))
+        })?;
+
+        let mut records = Vec::new();
+        for row in rows {
+            let (tx_hash, block_number, payee, token, amount) = row?;
+            records.push(PayoutRecord {
+                tx_hash: tx_hash.parse()?,
+                block_number: block_number as u64,
+                payee: payee.parse()?,
+                token: token.parse()?,
+                amount: parse_u256(amount)?,
+            });
+        }
+        Ok(records)
+    }
+
+    pub fn balance_checkpoint(&self, holder: Address, token: Address) -> Result<Option<BalanceCheckpoint>> {
+        let conn = self.conn.lock().unwrap();
+        let row = conn
+            .query_row(
+                "SELECT block_number, balance FROM balance_checkpoints WHERE holder = ?1 AND token = ?2",
+                params![hex(&holder), hex(&token)],
+                |row| Ok((row.get::<_, i64>(0)?, row.get::<_, String>(1)?)),
+            )
+            .optional()?;
+        row.map(|(block_number, balance)| {
+            Ok(BalanceCheckpoint {
+                holder,
+                token,
+                block_number: block_number as u64,
+                balance: parse_u256(balance)?,
+            })
+        })
+        .transpose()
+    }
+
+    pub fn set_balance_checkpoint(&self, checkpoint: &BalanceCheckpoint) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO balance_checkpoints (holder, token, block_number, balance) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(holder, token) DO UPDATE SET
+                block_number = excluded.block_number, balance = excluded.balance",
+            params![
+                hex(&checkpoint.holder),
+                hex(&checkpoint.token),
+                checkpoint.block_number as i64,
+                checkpoint.balance.to_string(),
+            ],
+        )?;
+        Ok(())
+    }
+
+    pub fn upsert_token(&self, token: &TokenInfo) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO tokens (address, name, symbol, decimals) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(address) DO UPDATE SET
+                name = excluded.name, symbol = excluded.symbol, decimals = excluded.decimals",
+            params![hex(&token.address), token.name, token.symbol, token.decimals],
+        )?;
+        Ok(())
+    }
+
+    pub fn upsert_token_tax(&self, token: Address, tax: &TokenTax) -> Result<()> {
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "INSERT INTO token_taxes (token, buy_bps, sell_bps, measured_at) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT(token) DO UPDATE SET
+                buy_bps = excluded.buy_bps, sell_bps = excluded.sell_bps, measured_at = excluded.measured_at",
+            params![hex(&token), tax.buy_bps, tax.sell_bps, tax.measured_at as i64],
+        )?;
+        Ok(())
+    }
+
+    pub fn token_taxes(&self) -> Result<Vec<(Address, TokenTax)>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare_cached("SELECT token, buy_bps, sell_bps, measured_at FROM token_taxes")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, u32>(1)?,
+                row.get::<_, u32>(2)?,
+                row.get::<_, i64>(3)?,
+            ))
+        })?;
+
+        let mut taxes = Vec::new();
+        for row in rows {
+            let (token, buy_bps, sell_bps, measured_at) = row?;
+            taxes.push((
+                token.parse()?,
+                TokenTax {
+                    buy_bps,
+                    sell_bps,
+                    measured_at: measured_at as u64,
+                },
+            ));
+        }
+        Ok(taxes)
+    }
+
+    // Amounts are U256 text, so the read-modify-write happens in one transaction
+    fn adjust_payout(&self, payee: Address, token: Address, adjust: impl FnOnce(U256) -> U256) -> Result<U256> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let current = tx
+            .query_row(
+                "SELECT amount FROM payouts_owed WHERE payee = ?1 AND token = ?2",
+                params![hex(&payee), hex(&token)],
+                |row| row.get::<_, String>(0),
+            )
+            .optional()?
+            .map(parse_u256)
+            .transpose()?
+            .unwrap_or_default();
+        let updated = adjust(current);
+        tx.execute(
+            "INSERT INTO payouts_owed (payee, token, amount) VALUES (?1, ?2, ?3)
+             ON CONFLICT(payee, token) DO UPDATE SET amount = excluded.amount",
+            params![hex(&payee), hex(&token), updated.to_string()],
+        )?;
+        tx.commit()?;
+        Ok(updated)
+    }
+
+    // Returns what is owed to `payee` in `token` after the accrual
+    pub fn accrue_payout(&self, payee: Address, token: Address, amount: U256) -> Result<U256> {
+        self.adjust_payout(payee, token, |owed| owed.saturating_add(amount))
+    }
+
+    // Called once a settlement transfer is mined
+    pub fn settle_payout(&self, payee: Address, token: Address, amount: U256) -> Result<U256> {
+        self.adjust_payout(payee, token, |owed| owed.saturating_sub(amount))
+    }
+
+    // (payee, token, amount) for every non-zero balance
+    pub fn payouts_owed(&self) -> Result<Vec<(Address, Address, U256)>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare_cached("SELECT payee, token, amount FROM payouts_owed ORDER BY payee, token")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?, row.get::<_, String>(2)?))
+        })?;
+
+        let mut owed = Vec::new();
+        for row in rows {
+            let (payee, token, amount) = row?;
+            let amount = parse_u256(amount)?;
+            if !amount.is_zero() {
+                owed.push((payee.parse()?, token.parse()?, amount));
+            }
+        }
+        Ok(owed)
+    }
+
+    pub fn tokens(&self) -> Result<Vec<TokenInfo>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare_cached("SELECT address, name, symbol, decimals FROM tokens")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, u8>(3)?,
+            ))
+        })?;
+
+        let mut tokens = Vec::new();
+        for row in rows {
+            let (address, name, symbol, decimals) = row?;
+            tokens.push(TokenInfo {
+                address: address.parse()?,
+                name,
+                symbol,
+                decimals,
+                tags: Vec::new(),
+            });
+        }
+        Ok(tokens)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pool_events_round_trip() {
+        let store = Store::open_in_memory().unwrap();
+        let pool = Address::from_low_u64_be(7);
+        let swap = PoolEventRecord {
+            block_number: 100,
+            log_index: 3,
+            tx_hash: H256::from_low_u64_be(1),
+            pool,
+            timestamp: Some(1_700_000_000),
+            kind: "swap".to_string(),
+            amounts: [U256::from(5u64), U256::zero(), U256::zero(), U256::from(9u64)],
+        };
+
+        assert_eq!(store.insert_pool_events(&[swap.clone(), swap.clone()]).unwrap(), 1);
+        assert_eq!(store.pool_events(pool, 0, Some("swap")).unwrap(), vec![swap]);
+        assert_eq!(store.swap_volume(pool, 0).unwrap(), (U256::from(5u64), U256::zero()));
+
+        store.set_last_indexed_block("pools", 100).unwrap();
+        store.set_last_indexed_block("pools", 120).unwrap();
+        assert_eq!(store.last_indexed_block("pools").unwrap(), Some(120));
+    }
+
+    #[test]
+    fn test_opportunity_journal() {
+        let store = Store::open_in_memory().unwrap();
+        let mut record = OpportunityRecord {
+            detected_at: 1_700_000_000,
+            block_number: 50,
+            class: "triangular".to_string(),
+            token0: Address::from_low_u64_be(1),
+            token1: Address::from_low_u64_be(2),
+            expected_profit: U256::from(10u64),
+            min_profit: U256::from(4u64),
+            status: "detected".to_string(),
+            tx_hash: None,
+            opportunity_id: Some(OpportunityId(H256::from_low_u64_be(7))),
+        };
+        let id = store.record_opportunity(&record).unwrap();
+        store.record_opportunity(&OpportunityRecord { class: "backrun".to_string(), ..record.clone() }).unwrap();
+
+        let hash = H256::from_low_u64_be(9);
+        store.set_opportunity_status(id, "submitted", Some(hash)).unwrap();
+        record.status = "submitted".to_string();
+        record.tx_hash = Some(hash);
+        assert_eq!(store.opportunities_since(0, Some("triangular")).unwrap(), vec![record]);
+        assert_eq!(store.opportunities_since(0, None).unwrap().len(), 2);
+    }
+}
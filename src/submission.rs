@@ -0,0 +1,232 @@
+// src/submission.rs
+use anyhow::{anyhow, bail, Result};
+use ethers::{
+    providers::{Middleware, PendingTransaction, Provider},
+    types::{
+        transaction::eip2718::TypedTransaction, Address, BlockNumber, Bytes, TransactionReceipt, TransactionRequest,
+        H256, U64,
+    },
+    utils::keccak256,
+};
+use log::{debug, info, warn};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::metrics::FANOUT_BUNDLES_TOTAL;
+use crate::rpc_usage::{self, MeteredHttp};
+use crate::tx_sender::TxSender;
+
+// Receipt polling of fanned-out variants
+const FANOUT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SubmissionSettings {
+    // Env var with a private RPC (eth_sendRawTransaction that skips the public mempool)
+    #[serde(default = "default_private_rpc_env")]
+    pub private_rpc_env: String,
+    // Fall back to the public mempool when the private endpoint is down, but only if
+    // the executor enforces minProfit on-chain
+    #[serde(default = "default_public_failover")]
+    pub public_failover: bool,
+    // View on the executor returning true (or non-zero) when it reverts below minProfit
+    #[serde(default = "default_revert_protection_method")]
+    pub revert_protection_method: String,
+}
+
+fn default_private_rpc_env() -> String {
+    "PRIVATE_RPC_URL".to_string()
+}
+
+fn default_public_failover() -> bool {
+    true
+}
+
+fn default_revert_protection_method() -> String {
+    "enforcesMinProfit".to_string()
+}
+
+impl Default for SubmissionSettings {
+    fn default() -> Self {
+        Self {
+            private_rpc_env: default_private_rpc_env(),
+            public_failover: default_public_failover(),
+            revert_protection_method: default_revert_protection_method(),
+        }
+    }
+}
+
+// A 32-byte word that is not all zeros; anything else (empty, revert data) means "no"
+pub fn decode_flag(output: &Bytes) -> bool {
+    output.len() == 32 && output.iter().any(|byte| *byte != 0)
+}
+
+// Calls `method()` on the executor; missing methods count as unprotected
+pub async fn has_revert_protection<M: Middleware>(
+    provider: &M,
+    executor: Address,
+    method: &str,
+) -> bool {
+    let selector = keccak256(format!("{method}()").as_bytes())[..4].to_vec();
+    let call: TypedTransaction = TransactionRequest::new().to(executor).data(selector).into();
+    match provider.call(&call, None).await {
+        Ok(output) => decode_flag(&output),
+        Err(_) => false,
+    }
+}
+
+// Sends signed txs privately, failing over to the public mempool only when it is safe
+#[derive(Debug)]
+pub struct Submitter<M> {
+    sender: TxSender<M>,
+    private: Option<Provider<MeteredHttp>>,
+    public_allowed: bool,
+    // The executor reverts below minProfit
+    protected: bool,
+}
+
+impl<M: Middleware + 'static> Submitter<M> {
+    pub async fn new(
+        provider: Arc<M>,
+        sender: TxSender<M>,
+        executor: Address,
+        settings: &SubmissionSettings,
+    ) -> Result<Self> {
+        let private = match std::env::var(&settings.private_rpc_env) {
+            Ok(url) if !url.is_empty() => Some(rpc_usage::http(&url, "private")?),
+            _ => None,
+        };
+        let protected = has_revert_protection(provider.as_ref(), executor, &settings.revert_protection_method).await;
+        let public_allowed = settings.public_failover && protected;
+
+        match (&private, public_allowed) {
+            (None, false) => warn!(
+                "No private RPC ({}) and executor {:?} does not enforce minProfit: submissions will be refused",
+                settings.private_rpc_env, executor
+            ),
+            (Some(_), false) => info!("Public mempool failover disabled (executor revert protection: {})", protected),
+            (_, true) => info!("Executor enforces minProfit on-chain; public mempool failover allowed"),
+        }
+
+        Ok(Self {
+            sender,
+            private,
+            public_allowed,
+            protected,
+        })
+    }
+
+    pub fn public_allowed(&self) -> bool {
+        self.public_allowed
+    }
+
+    pub fn sender(&self) -> &TxSender<M> {
+        &self.sender
+    }
+
+    // Bundles need the private relay, and the executor's minProfit must stop every variant
+    // but one from executing
+    pub fn can_fan_out(&self) -> bool {
+        self.private.is_some() && self.protected
+    }
+
+    // One bundle per (target block, tx) through `method` on the private relay. The variants
+    // are signed with one nonce, so whichever is mined first invalidates the others.
+    // Returns the hashes the relay accepted
+    pub async fn submit_fanout(&self, variants: Vec<(U64, TypedTransaction)>, method: &str) -> Result<Vec<H256>> {
+        let Some(private) = self.private.as_ref().filter(|_| self.protected) else {
+            bail!("Bundle fan-out needs the private relay and an executor enforcing minProfit");
+        };
+        let nonce = self
+            .sender
+            .provider()
+            .get_transaction_count(self.sender.address(), Some(BlockNumber::Pending.into()))
+            .await
+            .map_err(|e| anyhow!("Failed to fetch nonce: {e}"))?;
+
+        let mut accepted = Vec::new();
+        for (target_block, mut tx) in variants {
+            tx.set_nonce(nonce);
+            let raw = self.sender.sign(tx).await?;
+            let hash = H256::from(keccak256(&raw));
+            let bundle = json!({ "txs": [raw], "blockNumber": format!("{:#x}", target_block.as_u64()) });
+            match private.request::<_, Value>(method, [bundle]).await {
+                Ok(_) => {
+                    debug!("Bundle {:?} sent for block {}", hash, target_block);
+                    FANOUT_BUNDLES_TOTAL.with_label_values(&["sent"]).inc();
+                    accepted.push(hash);
+                }
+                Err(e) => {
+                    warn!("Relay rejected the bundle for block {}: {}", target_block, e);
+                    FANOUT_BUNDLES_TOTAL.with_label_values(&["rejected"]).inc();
+                }
+            }
+        }
+        if accepted.is_empty() {
+            bail!("Relay accepted none of the bundles");
+        }
+        Ok(accepted)
+    }
+
+    // Receipt of whichever of `hashes` is mined by `last_block`, None when none was
+    pub async fn await_any(&self, hashes: &[H256], last_block: U64) -> Result<Option<TransactionReceipt>> {
+        let provider = self.sender.provider();
+        loop {
+            for hash in hashes {
+                if let Some(receipt) = provider
+                    .get_transaction_receipt(*hash)
+                    .await
+                    .map_err(|e| anyhow!("Receipt lookup failed: {e}"))?
+                {
+                    FANOUT_BUNDLES_TOTAL.with_label_values(&["landed"]).inc();
+                    return Ok(Some(receipt));
+                }
+            }
+            let head = provider
+                .get_block_number()
+                .await
+                .map_err(|e| anyhow!("Block number lookup failed: {e}"))?;
+            if head > last_block {
+                FANOUT_BUNDLES_TOTAL.with_label_values(&["missed"]).inc();
+                return Ok(None);
+            }
+            tokio::time::sleep(FANOUT_POLL_INTERVAL).await;
+        }
+    }
+
+    pub async fn submit(&self, tx: TypedTransaction) -> Result<PendingTransaction<'_, M::Provider>> {
+        let raw = self.sender.sign(tx).await?;
+
+        if let Some(private) = &self.private {
+            match private.send_raw_transaction(raw.clone()).await {
+                Ok(pending) => return Ok(self.sender.pending(pending.tx_hash())),
+                Err(e) => warn!("Private submission failed: {}", e),
+            }
+        }
+        if !self.public_allowed {
+            bail!("Private submission unavailable and executor lacks on-chain minProfit; refusing public mempool");
+        }
+
+        warn!("Submitting through the public mempool");
+        self.sender
+            .broadcast(raw)
+            .await
+            .map_err(|e| anyhow!("Public submission failed: {e}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_decode_flag() {
+        let mut word = [0u8; 32];
+        assert!(!decode_flag(&Bytes::from(word.to_vec())));
+        word[31] = 1;
+        assert!(decode_flag(&Bytes::from(word.to_vec())));
+        assert!(!decode_flag(&Bytes::new()));
+        assert!(!decode_flag(&Bytes::from(vec![1u8; 4])));
+    }
+}
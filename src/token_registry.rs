@@ -0,0 +1,341 @@
+// src/token_registry.rs
+use anyhow::{anyhow, bail, Result};
+use ethers::{
+    abi::Token,
+    contract::Multicall,
+    providers::Middleware,
+    types::{Address, U256},
+};
+use log::{debug, warn};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Arc, RwLock};
+
+use crate::erc20::Erc20;
+use crate::store::Store;
+
+pub const DEFAULT_TOKENS_PATH: &str = "./src/tokens.json";
+
+// tokens.json as of the build, used when the file is missing
+const EMBEDDED_TOKENS: &str = include_str!("tokens.json");
+
+// Highest decimals accepted from tokens.json; real ERC-20s stay far below
+const MAX_DECIMALS: u8 = 36;
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+pub struct TokenInfo {
+    pub address: Address,
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8,
+    // Labels from tokens.json, e.g. "stable"; none for tokens resolved on-chain
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+// One tokens.json entry, keyed by its own address. Unknown fields are rejected so a typo
+// does not silently drop a value
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TokenEntry {
+    address: Address,
+    name: String,
+    symbol: String,
+    decimals: u8,
+    #[serde(default)]
+    tags: Vec<String>,
+    #[serde(default = "default_network")]
+    network: String,
+}
+
+fn default_network() -> String {
+    "polygon".to_string()
+}
+
+// Parses and checks a tokens.json document; every invalid entry is reported at once,
+// `source` names the document in the errors
+pub fn parse_tokens(source: &str, content: &str) -> Result<Vec<TokenInfo>> {
+    let entries: BTreeMap<String, TokenEntry> =
+        serde_json::from_str(content).map_err(|e| anyhow!("{source} does not match the token schema: {e}"))?;
+    let mut problems = Vec::new();
+    let mut seen = HashSet::new();
+    for (key, entry) in &entries {
+        match key.parse::<Address>() {
+            Ok(address) if address == entry.address => {}
+            Ok(_) => problems.push(format!("{key}: listed under another address than its own {:?}", entry.address)),
+            Err(_) => problems.push(format!("{key}: key is not an address")),
+        }
+        if !seen.insert(entry.address) {
+            problems.push(format!("{key}: {:?} is listed twice", entry.address));
+        }
+        if entry.symbol.trim().is_empty() {
+            problems.push(format!("{key}: symbol is empty"));
+        }
+        if entry.decimals > MAX_DECIMALS {
+            problems.push(format!("{key}: {} decimals, at most {} are supported", entry.decimals, MAX_DECIMALS));
+        }
+        if entry.network != "polygon" {
+            problems.push(format!("{key}: network is \"{}\", only polygon is supported", entry.network));
+        }
+        if let Some(tag) = entry.tags.iter().find(|tag| tag.trim().is_empty()) {
+            problems.push(format!("{key}: empty tag {:?}", tag));
+        }
+    }
+    if !problems.is_empty() {
+        bail!("{source} has {} invalid entr(ies):\n  {}", problems.len(), problems.join("\n  "));
+    }
+    Ok(entries
+        .into_values()
+        .map(|entry| TokenInfo {
+            address: entry.address,
+            name: entry.name,
+            symbol: entry.symbol,
+            decimals: entry.decimals,
+            tags: entry.tags,
+        })
+        .collect())
+}
+
+// Transfer tax of a token as measured against one of its pools, in basis points. Buying
+// (pool -> trader) and selling (trader -> pool) are often taxed differently
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct TokenTax {
+    pub buy_bps: u32,
+    pub sell_bps: u32,
+    // Unix seconds of the measurement
+    pub measured_at: u64,
+}
+
+impl TokenTax {
+    pub fn is_taxed(&self) -> bool {
+        self.buy_bps > 0 || self.sell_bps > 0
+    }
+
+    // What arrives of `amount` sent out of a pool
+    pub fn after_buy(&self, amount: U256) -> U256 {
+        deduct_bps(amount, self.buy_bps)
+    }
+
+    // What arrives of `amount` sent into a pool
+    pub fn after_sell(&self, amount: U256) -> U256 {
+        deduct_bps(amount, self.sell_bps)
+    }
+}
+
+fn deduct_bps(amount: U256, bps: u32) -> U256 {
+    amount - amount * U256::from(bps.min(10_000)) / U256::from(10_000u64)
+}
+
+// Token metadata from tokens.json, the persistent store and, as a last resort, the chain
+#[derive(Debug, Default)]
+pub struct TokenRegistry {
+    tokens: RwLock<HashMap<Address, TokenInfo>>,
+    taxes: RwLock<HashMap<Address, TokenTax>>,
+    store: Option<Store>,
+}
+
+impl TokenRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // A missing file falls back to the copy compiled into the binary
+    pub fn from_json_file(path: &str) -> Result<Self> {
+        let tokens = match std::fs::read_to_string(path) {
+            Ok(content) => parse_tokens(path, &content)?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+                warn!("{} not found, using the token list built into the binary", path);
+                parse_tokens("embedded tokens.json", EMBEDDED_TOKENS)?
+            }
+            Err(e) => bail!("Failed to read {path}: {e}"),
+        };
+        let tokens = tokens.into_iter().map(|token| (token.address, token)).collect();
+        Ok(Self {
+            tokens: RwLock::new(tokens),
+            ..Default::default()
+        })
+    }
+
+    // Tokens resolved on earlier runs are loaded back; tokens.json entries win
+    pub fn with_store(mut self, store: Store) -> Result<Self> {
+        {
+            let mut tokens = self.tokens.write().unwrap();
+            for token in store.tokens()? {
+                tokens.entry(token.address).or_insert(token);
+            }
+        }
+        self.taxes.write().unwrap().extend(store.token_taxes()?);
+        self.store = Some(store);
+        Ok(self)
+    }
+
+    pub fn get(&self, token: &Address) -> Option<TokenInfo> {
+        self.tokens.read().unwrap().get(token).cloned()
+    }
+
+    pub fn addresses(&self) -> Vec<Address> {
+        self.tokens.read().unwrap().keys().copied().collect()
+    }
+
+    pub fn insert(&self, token: TokenInfo) -> Result<()> {
+        if let Some(store) = &self.store {
+            store.upsert_token(&token)?;
+        }
+        self.tokens.write().unwrap().insert(token.address, token);
+        Ok(())
+    }
+
+    // None for tokens whose tax was never measured
+    pub fn tax(&self, token: &Address) -> Option<TokenTax> {
+        self.taxes.read().unwrap().get(token).copied()
+    }
+
+    pub fn set_tax(&self, token: Address, tax: TokenTax) -> Result<()> {
+        if let Some(store) = &self.store {
+            store.upsert_token_tax(token, &tax)?;
+        }
+        self.taxes.write().unwrap().insert(token, tax);
+        Ok(())
+    }
+
+    // Fetch metadata for every token not known yet in a single multicall
+    pub async fn resolve<M: Middleware + 'static>(
+        &self,
+        provider: Arc<M>,
+        tokens: &[Address],
+    ) -> Result<Vec<TokenInfo>> {
+        let mut missing: Vec<Address> = tokens.iter().copied().filter(|t| self.get(t).is_none()).collect();
+        missing.sort();
+        missing.dedup();
+
+        if !missing.is_empty() {
+            let mut multicall = Multicall::new(provider.clone(), None)
+                .await
+                .map_err(|e| anyhow!("Failed to set up multicall: {e}"))?;
+            for &token in &missing {
+                let erc20 = Erc20::new(token, provider.clone());
+                let contract = erc20.contract();
+                multicall
+                    .add_call(contract.name(), true)
+                    .add_call(contract.symbol(), true)
+                    .add_call(contract.decimals(), true);
+            }
+
+            let results = multicall
+                .call_raw()
+                .await
+                .map_err(|e| anyhow!("Token metadata multicall failed: {e}"))?;
+
+            for (token, chunk) in missing.iter().zip(results.chunks(3)) {
+                let name = match &chunk[0] {
+                    Ok(Token::String(name)) => name.clone(),
+                    _ => String::new(),
+                };
+                let symbol = match &chunk[1] {
+                    Ok(Token::String(symbol)) => symbol.clone(),
+                    _ => short_address(token),
+                };
+                let decimals = match &chunk[2] {
+                    Ok(Token::Uint(decimals)) => decimals.low_u32() as u8,
+                    _ => {
+                        warn!("Token {:?} has no decimals(), assuming 18", token);
+                        18
+                    }
+                };
+
+                debug!("Resolved token {:?}: {} ({} decimals)", token, symbol, decimals);
+                self.insert(TokenInfo {
+                    address: *token,
+                    name,
+                    symbol,
+                    decimals,
+                    tags: Vec::new(),
+                })?;
+            }
+        }
+
+        Ok(tokens.iter().filter_map(|t| self.get(t)).collect())
+    }
+
+    // "12.5 USDC"; unknown tokens fall back to the raw amount and a short address
+    pub fn format_amount(&self, token: &Address, amount: U256) -> String {
+        match self.get(token) {
+            Some(info) => format!("{} {}", format_units(amount, info.decimals), info.symbol),
+            None => format!("{} {}", amount, short_address(token)),
+        }
+    }
+
+    // Symbol for labels, a short address for unknown tokens
+    pub fn symbol(&self, token: &Address) -> String {
+        self.get(token).map_or_else(|| short_address(token), |info| info.symbol)
+    }
+}
+
+fn short_address(address: &Address) -> String {
+    let hex = format!("{:?}", address);
+    format!("{}…{}", &hex[..6], &hex[hex.len() - 4..])
+}
+
+// Exact decimal rendering without trailing zeros
+pub fn format_units(amount: U256, decimals: u8) -> String {
+    let base = U256::exp10(decimals as usize);
+    let whole = amount / base;
+    let fraction = amount % base;
+    if fraction.is_zero() {
+        return whole.to_string();
+    }
+
+    let fraction = format!("{:0>width$}", fraction.to_string(), width = decimals as usize);
+    format!("{}.{}", whole, fraction.trim_end_matches('0'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_amount_is_human_readable() {
+        let usdc = Address::from_low_u64_be(1);
+        let registry = TokenRegistry::new();
+        registry
+            .insert(TokenInfo {
+                address: usdc,
+                name: "USD Coin".to_string(),
+                symbol: "USDC".to_string(),
+                decimals: 6,
+                tags: Vec::new(),
+            })
+            .unwrap();
+
+        assert_eq!(registry.format_amount(&usdc, U256::from(12_500_000u64)), "12.5 USDC");
+        assert_eq!(format_units(U256::from(1_000_001u64), 6), "1.000001");
+        assert_eq!(format_units(U256::exp10(18) * 3, 18), "3");
+        assert!(registry
+            .format_amount(&Address::from_low_u64_be(2), U256::from(7u64))
+            .starts_with("7 0x0000"));
+    }
+
+    #[test]
+    fn test_tokens_json_is_typed_and_validated() {
+        let tokens = parse_tokens("embedded", EMBEDDED_TOKENS).unwrap();
+        let usdc = tokens.iter().find(|token| token.symbol == "USDC").unwrap();
+        assert_eq!(usdc.decimals, 6);
+        assert!(usdc.tags.contains(&"stable".to_string()));
+
+        let usdc = "0x3c499c542cef5e3811e1192ce70d8cc03d5c3359";
+        let dai = "0x8f3cf7ad23cd3cadbd9735aff958023239c6a063";
+        let document = format!(
+            r#"{{"{usdc}": {{"address": "{dai}", "name": "Dai", "symbol": " ", "decimals": 60}},
+                "0x12": {{"address": "{dai}", "name": "Dai", "symbol": "DAI", "decimals": 18, "network": "ethereum"}}}}"#
+        );
+        let error = parse_tokens("tokens.json", &document).unwrap_err().to_string();
+        assert!(error.starts_with("tokens.json has 6 invalid"), "{error}");
+        for problem in ["another address", "key is not an address", "listed twice", "symbol is empty", "60 decimals"] {
+            assert!(error.contains(problem), "{problem} missing from {error}");
+        }
+        // Schema errors name the field
+        let typo = format!(r#"{{"{dai}": {{"address": "{dai}", "name": "Dai", "symbol": "DAI", "decimal": 18}}}}"#);
+        let error = parse_tokens("tokens.json", &typo).unwrap_err().to_string();
+        assert!(error.contains("unknown field `decimal`"), "{error}");
+    }
+}
@@ -0,0 +1,263 @@
+// src/token_tax.rs
+use anyhow::{anyhow, bail, Result};
+use ethers::{
+    abi::Token,
+    contract::{Multicall, MULTICALL_ADDRESS},
+    providers::{call_raw::spoof, Middleware},
+    types::{Address, H256, U256},
+};
+use log::{debug, info, warn};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::erc20::Erc20;
+use crate::metrics::TOKEN_TAX_PROBES_TOTAL;
+use crate::preflight::{address_word, mapping_slot, u64_word};
+use crate::token_registry::{TokenRegistry, TokenTax};
+
+const BPS: u64 = 10_000;
+
+// Receives the probe's "buy"; an address no token exempts from its tax
+const PROBE: Address = Address::repeat_byte(0x7a);
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TokenTaxSettings {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    // Share of the pool's balance moved by the probe transfers
+    #[serde(default = "default_probe_bps")]
+    pub probe_bps: u64,
+    // Rates are re-measured after this long; owners of tax tokens change them at will
+    #[serde(default = "default_max_age_hours")]
+    pub max_age_hours: u64,
+    // Tokens measured per block, so a fresh universe doesn't stall the block loop
+    #[serde(default = "default_per_block")]
+    pub per_block: usize,
+    // `allowance` mapping slot of tokens not laid out like OpenZeppelin's ERC20 (slot 1)
+    #[serde(default)]
+    pub allowance_slots: HashMap<Address, u64>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_probe_bps() -> u64 {
+    10
+}
+
+fn default_max_age_hours() -> u64 {
+    24
+}
+
+fn default_per_block() -> usize {
+    5
+}
+
+impl Default for TokenTaxSettings {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            probe_bps: default_probe_bps(),
+            max_age_hours: default_max_age_hours(),
+            per_block: default_per_block(),
+            allowance_slots: HashMap::new(),
+        }
+    }
+}
+
+// Measures transfer taxes with one eth_call: with faked allowances, Multicall3 moves tokens
+// out of a pool to a probe address ("buy") and part of them back in ("sell"), reading the
+// balances that actually arrived after each leg
+#[derive(Debug, Clone)]
+pub struct TaxProbe<M> {
+    provider: Arc<M>,
+    tokens: Arc<TokenRegistry>,
+    settings: TokenTaxSettings,
+    // Last attempt per token, failed ones included
+    attempted: Arc<Mutex<HashMap<Address, u64>>>,
+}
+
+impl<M: Middleware + 'static> TaxProbe<M> {
+    pub fn new(provider: Arc<M>, tokens: Arc<TokenRegistry>, settings: TokenTaxSettings) -> Self {
+        Self {
+            provider,
+            tokens,
+            settings,
+            attempted: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    pub fn tokens(&self) -> &TokenRegistry {
+        &self.tokens
+    }
+
+    // Measures tokens of `pools` (pool -> (token0, token1)) that have no fresh rate yet.
+    // Returns how many were measured; failures are only retried once the rate would expire
+    pub async fn refresh(&self, pools: &HashMap<Address, (Address, Address)>) -> usize {
+        if !self.settings.enabled {
+            return 0;
+        }
+        let now = unix_now();
+        let max_age = self.settings.max_age_hours * 3600;
+        let mut due: Vec<(Address, Address)> = Vec::new();
+        {
+            let attempted = self.attempted.lock().unwrap();
+            let mut sorted: Vec<_> = pools.iter().collect();
+            sorted.sort();
+            for (&pool, &(token0, token1)) in sorted {
+                for token in [token0, token1] {
+                    let last = attempted
+                        .get(&token)
+                        .copied()
+                        .or_else(|| self.tokens.tax(&token).map(|tax| tax.measured_at));
+                    let fresh = last.is_some_and(|last| now.saturating_sub(last) < max_age);
+                    if !fresh && !due.iter().any(|&(queued, _)| queued == token) {
+                        due.push((token, pool));
+                    }
+                }
+            }
+        }
+        due.truncate(self.settings.per_block);
+
+        let mut measured = 0;
+        for (token, pool) in due {
+            self.attempted.lock().unwrap().insert(token, now);
+            match self.measure(token, pool).await {
+                Ok(tax) => {
+                    let outcome = if tax.is_taxed() { "taxed" } else { "untaxed" };
+                    TOKEN_TAX_PROBES_TOTAL.with_label_values(&[outcome]).inc();
+                    if tax.is_taxed() {
+                        info!(
+                            "{} has a transfer tax: {} bps on buys, {} bps on sells",
+                            self.tokens.symbol(&token),
+                            tax.buy_bps,
+                            tax.sell_bps
+                        );
+                    }
+                    if let Err(e) = self.tokens.set_tax(token, tax) {
+                        warn!("Failed to store the tax of {:?}: {}", token, e);
+                    }
+                    measured += 1;
+                }
+                Err(e) => {
+                    TOKEN_TAX_PROBES_TOTAL.with_label_values(&["failed"]).inc();
+                    debug!("Tax probe of {:?} via {:?} failed: {}", token, pool, e);
+                }
+            }
+        }
+        measured
+    }
+
+    pub async fn measure(&self, token: Address, pool: Address) -> Result<TokenTax> {
+        let erc20 = Erc20::new(token, self.provider.clone());
+        let pool_balance = erc20.balance_of(pool).await?;
+        let amount = pool_balance * U256::from(self.settings.probe_bps) / U256::from(BPS);
+        if amount.is_zero() {
+            bail!("pool {:?} holds too little {:?} to probe", pool, token);
+        }
+        // Anything above a 50% buy tax fails the sell leg, which is as good as untradeable
+        let sell_amount = amount / 2;
+
+        let mut state = spoof::state();
+        for owner in [pool, PROBE] {
+            state
+                .account(token)
+                .store(self.allowance_slot(token, owner), H256::repeat_byte(0xff));
+        }
+        let contract = erc20.contract();
+        let mut multicall = Multicall::new(self.provider.clone(), Some(MULTICALL_ADDRESS))
+            .await
+            .map_err(|e| anyhow!("Failed to set up multicall: {e}"))?
+            .state(state);
+        multicall
+            .add_call(contract.transfer_from(pool, PROBE, amount), false)
+            .add_call(contract.balance_of(PROBE), false)
+            .add_call(contract.transfer_from(PROBE, pool, sell_amount), false)
+            .add_call(contract.balance_of(pool), false);
+        let results = multicall
+            .call_raw()
+            .await
+            .map_err(|e| anyhow!("probe transfers reverted: {e}"))?;
+        let balance = |i: usize| match results.get(i) {
+            Some(Ok(Token::Uint(balance))) => Ok(*balance),
+            _ => Err(anyhow!("balanceOf #{i} returned nothing")),
+        };
+
+        let bought = balance(1)?;
+        let sold = balance(3)?.saturating_sub(pool_balance - amount);
+        Ok(TokenTax {
+            buy_bps: tax_bps(amount, bought),
+            sell_bps: tax_bps(sell_amount, sold),
+            measured_at: unix_now(),
+        })
+    }
+
+    // allowance[owner][Multicall3]
+    fn allowance_slot(&self, token: Address, owner: Address) -> H256 {
+        let slot = self.settings.allowance_slots.get(&token).copied().unwrap_or(1);
+        let inner = mapping_slot(address_word(owner), u64_word(slot));
+        mapping_slot(address_word(MULTICALL_ADDRESS), inner)
+    }
+}
+
+// Share of `sent` that did not arrive, in basis points
+fn tax_bps(sent: U256, received: U256) -> u32 {
+    if sent.is_zero() {
+        return 0;
+    }
+    ((sent - received.min(sent)) * U256::from(BPS) / sent).as_u32()
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{address, call_output, MockChain};
+    use crate::store::Store;
+    use ethers::abi::encode;
+
+    #[tokio::test]
+    async fn test_probe_measures_buy_and_sell_tax_into_the_registry() {
+        let chain = MockChain::new();
+        let store = Store::open_in_memory().unwrap();
+        let tokens = Arc::new(TokenRegistry::new().with_store(store.clone()).unwrap());
+        let settings = TokenTaxSettings {
+            per_block: 1,
+            ..Default::default()
+        };
+        let probe = TaxProbe::new(chain.provider(), tokens.clone(), settings);
+        let (pool, taxed, wmatic) = (address(10), address(1), address(2));
+
+        // 10 bps of 1M is 1_000 out, 5% lost on the way; 500 back in, 10% lost again
+        let result = |data: Vec<u8>| Token::Tuple(vec![Token::Bool(true), Token::Bytes(data)]);
+        let uint = |value: u64| encode(&[Token::Uint(value.into())]);
+        let aggregate = call_output(&[Token::Array(vec![
+            result(encode(&[Token::Bool(true)])),
+            result(uint(950)),
+            result(encode(&[Token::Bool(true)])),
+            result(uint(1_000_000 - 1_000 + 450)),
+        ])]);
+        chain.script(vec![call_output(&[Token::Uint(1_000_000u64.into())]), aggregate]);
+
+        let pools = HashMap::from([(pool, (taxed, wmatic))]);
+        assert_eq!(probe.refresh(&pools).await, 1);
+        let tax = tokens.tax(&taxed).unwrap();
+        assert_eq!((tax.buy_bps, tax.sell_bps), (500, 1_000));
+        assert_eq!(tax.after_buy(U256::from(1_000u64)), U256::from(950u64));
+        assert_eq!(tax.after_sell(U256::from(1_000u64)), U256::from(900u64));
+
+        // Persisted, and not measured again while fresh; the untaxed side is next
+        let reloaded = TokenRegistry::new().with_store(store).unwrap();
+        assert_eq!(reloaded.tax(&taxed), Some(tax));
+        assert!(tokens.tax(&wmatic).is_none());
+        assert_eq!(tax_bps(U256::from(100u64), U256::from(120u64)), 0);
+    }
+}
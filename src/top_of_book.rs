@@ -0,0 +1,232 @@
+// src/top_of_book.rs
+use ethers::types::{Address, U256};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+use tokio::sync::broadcast;
+
+use crate::amm_math::FEE_DENOMINATOR;
+use crate::reserve_cache::ReserveSnapshot;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BookPair {
+    pub base: Address,
+    pub quote: Address,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TopOfBookSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    // Prices are quoted as `quote` per `base`
+    #[serde(default)]
+    pub pairs: Vec<BookPair>,
+    // Snapshots a slow subscriber may fall behind before it skips ahead
+    #[serde(default = "default_buffer")]
+    pub buffer: usize,
+}
+
+fn default_buffer() -> usize {
+    64
+}
+
+impl Default for TopOfBookSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            pairs: Vec::new(),
+            buffer: default_buffer(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct PoolQuote {
+    pub pool: Address,
+    // Marginal price for selling / buying an infinitesimal amount of base, fee included
+    pub bid: f64,
+    pub ask: f64,
+    // Block the reserves behind this quote were read at
+    pub block_number: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BookEntry {
+    #[serde(flatten)]
+    pub pair: BookPair,
+    pub best_bid: Option<PoolQuote>,
+    pub best_ask: Option<PoolQuote>,
+    // Pools quoting the pair
+    pub depth: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct BookSnapshot {
+    pub block_number: u64,
+    pub entries: Vec<BookEntry>,
+}
+
+// Marginal (bid, ask) of a V2 pool in human units of quote per base
+pub fn pool_quote(
+    reserve_base: U256,
+    reserve_quote: U256,
+    fee: u32,
+    base_decimals: u8,
+    quote_decimals: u8,
+) -> Option<(f64, f64)> {
+    if reserve_base.is_zero() || reserve_quote.is_zero() {
+        return None;
+    }
+    let scale = 10f64.powi(base_decimals as i32 - quote_decimals as i32);
+    let mid = reserve_quote.low_u128() as f64 / reserve_base.low_u128() as f64 * scale;
+    let keep = (FEE_DENOMINATOR - fee.min(FEE_DENOMINATOR - 1)) as f64 / FEE_DENOMINATOR as f64;
+    Some((mid * keep, mid / keep))
+}
+
+// Best bid/ask per configured pair from the reserve cache, published to every subscriber
+#[derive(Debug, Clone)]
+pub struct TopOfBook {
+    pairs: Vec<BookPair>,
+    decimals: Arc<RwLock<HashMap<Address, u8>>>,
+    latest: Arc<RwLock<Option<Arc<BookSnapshot>>>>,
+    sender: broadcast::Sender<Arc<BookSnapshot>>,
+}
+
+impl TopOfBook {
+    pub fn new(settings: &TopOfBookSettings) -> Self {
+        let (sender, _) = broadcast::channel(settings.buffer.max(1));
+        Self {
+            pairs: settings.pairs.clone(),
+            decimals: Arc::default(),
+            latest: Arc::default(),
+            sender,
+        }
+    }
+
+    pub fn pairs(&self) -> &[BookPair] {
+        &self.pairs
+    }
+
+    // Tokens without known decimals are treated as 18
+    pub fn set_decimals(&self, token: Address, decimals: u8) {
+        self.decimals.write().unwrap().insert(token, decimals);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<Arc<BookSnapshot>> {
+        self.sender.subscribe()
+    }
+
+    pub fn latest(&self) -> Option<Arc<BookSnapshot>> {
+        self.latest.read().unwrap().clone()
+    }
+
+    pub fn build<F>(
+        &self,
+        block_number: u64,
+        pools: &HashMap<Address, (Address, Address)>,
+        cache: &ReserveSnapshot,
+        fee: F,
+    ) -> BookSnapshot
+    where
+        F: Fn(&Address) -> u32,
+    {
+        let decimals = self.decimals.read().unwrap();
+        let decimals_of = |token: &Address| decimals.get(token).copied().unwrap_or(18);
+
+        let entries = self
+            .pairs
+            .iter()
+            .map(|pair| {
+                let mut entry = BookEntry {
+                    pair: *pair,
+                    best_bid: None,
+                    best_ask: None,
+                    depth: 0,
+                };
+                for (pool, &(token0, token1)) in pools {
+                    let Some(reserves) = cache.get(pool) else {
+                        continue;
+                    };
+                    let (reserve_base, reserve_quote) = if (token0, token1) == (pair.base, pair.quote) {
+                        (reserves.reserve0, reserves.reserve1)
+                    } else if (token1, token0) == (pair.base, pair.quote) {
+                        (reserves.reserve1, reserves.reserve0)
+                    } else {
+                        continue;
+                    };
+                    let Some((bid, ask)) = pool_quote(
+                        reserve_base,
+                        reserve_quote,
+                        fee(pool),
+                        decimals_of(&pair.base),
+                        decimals_of(&pair.quote),
+                    ) else {
+                        continue;
+                    };
+
+                    let quote = PoolQuote {
+                        pool: *pool,
+                        bid,
+                        ask,
+                        block_number: reserves.block_number,
+                    };
+                    entry.depth += 1;
+                    if entry.best_bid.is_none_or(|best| bid > best.bid) {
+                        entry.best_bid = Some(quote);
+                    }
+                    if entry.best_ask.is_none_or(|best| ask < best.ask) {
+                        entry.best_ask = Some(quote);
+                    }
+                }
+                entry
+            })
+            .collect();
+
+        BookSnapshot { block_number, entries }
+    }
+
+    pub fn publish(&self, snapshot: BookSnapshot) {
+        let snapshot = Arc::new(snapshot);
+        *self.latest.write().unwrap() = Some(snapshot.clone());
+        // No subscribers is fine, `latest` still serves polling clients
+        let _ = self.sender.send(snapshot);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::amm_math::DEFAULT_V2_FEE;
+    use crate::fixtures::{address, PoolSet};
+
+    #[test]
+    fn test_best_bid_and_ask_across_pools() {
+        let (wmatic, usdc) = (address(1), address(2));
+        let (quick, sushi) = (address(10), address(11));
+        let e18 = U256::exp10(18);
+        let e6 = U256::exp10(6);
+
+        // 0.50 and 0.52 USDC per WMATIC; sushi lists the tokens the other way round
+        let set = PoolSet::new()
+            .pool(quick, wmatic, usdc, e18 * 1_000, e6 * 500)
+            .pool(sushi, usdc, wmatic, e6 * 520, e18 * 1_000);
+        let (cache, pools) = (set.cache(8), set.tokens());
+
+        let book = TopOfBook::new(&TopOfBookSettings {
+            enabled: true,
+            pairs: vec![BookPair { base: wmatic, quote: usdc }],
+            ..Default::default()
+        });
+        book.set_decimals(usdc, 6);
+        let mut feed = book.subscribe();
+        book.publish(book.build(8, &pools, &cache.snapshot(), |_| DEFAULT_V2_FEE));
+
+        let snapshot = feed.try_recv().unwrap();
+        let entry = &snapshot.entries[0];
+        assert_eq!(entry.depth, 2);
+        assert_eq!(entry.best_bid.unwrap().pool, sushi);
+        assert_eq!(entry.best_ask.unwrap().pool, quick);
+        assert!((entry.best_bid.unwrap().bid - 0.52 * 0.997).abs() < 1e-9);
+        assert_eq!(book.latest().unwrap().block_number, 8);
+    }
+}
@@ -0,0 +1,155 @@
+// src/toxic_flow.rs
+use ethers::types::{Address, U256};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use crate::metrics::TOXIC_FLOW_TOTAL;
+
+const BPS: u64 = 10_000;
+
+// Spreads that open right after a large swap are often closed by other searchers inside the
+// same block, before a next-block trade lands. Routes through a pool whose reserves just
+// moved by `move_bps` or more are scored at a discount that fades over `window_blocks`;
+// trades that can target the disturbing block itself (mempool backruns) are not discounted
+#[derive(Debug, Clone, Deserialize)]
+pub struct ToxicFlowSettings {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    // Reserve change since the pool was last read that marks it disturbed
+    #[serde(default = "default_move_bps")]
+    pub move_bps: u64,
+    // Discount in the block after the move
+    #[serde(default = "default_max_discount_bps")]
+    pub max_discount_bps: u64,
+    #[serde(default = "default_window_blocks")]
+    pub window_blocks: u64,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_move_bps() -> u64 {
+    200
+}
+
+fn default_max_discount_bps() -> u64 {
+    5_000
+}
+
+fn default_window_blocks() -> u64 {
+    3
+}
+
+impl Default for ToxicFlowSettings {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            move_bps: default_move_bps(),
+            max_discount_bps: default_max_discount_bps(),
+            window_blocks: default_window_blocks(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct PoolActivity {
+    reserve0: U256,
+    block_number: u64,
+    // Block of the last move of at least `move_bps`
+    disturbed_at: Option<u64>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ToxicFlow {
+    settings: ToxicFlowSettings,
+    pools: Arc<RwLock<HashMap<Address, PoolActivity>>>,
+}
+
+impl ToxicFlow {
+    pub fn new(settings: ToxicFlowSettings) -> Self {
+        Self {
+            settings,
+            pools: Arc::default(),
+        }
+    }
+
+    // Reserves of `pool` read at `block_number`; true when they mark it disturbed
+    pub fn observe(&self, pool: Address, reserve0: U256, block_number: u64) -> bool {
+        if !self.settings.enabled {
+            return false;
+        }
+        let mut pools = self.pools.write().unwrap();
+        let activity = pools.entry(pool).or_insert(PoolActivity {
+            reserve0,
+            block_number,
+            disturbed_at: None,
+        });
+        if block_number <= activity.block_number {
+            return false;
+        }
+        let previous = std::mem::replace(&mut activity.reserve0, reserve0);
+        activity.block_number = block_number;
+        let moved = previous.abs_diff(reserve0) * U256::from(BPS) >= previous * U256::from(self.settings.move_bps);
+        if !previous.is_zero() && moved {
+            activity.disturbed_at = Some(block_number);
+            TOXIC_FLOW_TOTAL.with_label_values(&["disturbed"]).inc();
+            return true;
+        }
+        false
+    }
+
+    // Discount of a route through `pools` executed in `target_block`, in bps; the most
+    // recently disturbed pool sets it
+    pub fn discount_bps(&self, pools: &[Address], target_block: u64, same_block: bool) -> u64 {
+        if !self.settings.enabled || same_block || self.settings.window_blocks == 0 {
+            return 0;
+        }
+        let window = self.settings.window_blocks;
+        let tracked = self.pools.read().unwrap();
+        pools
+            .iter()
+            .filter_map(|pool| tracked.get(pool)?.disturbed_at)
+            .map(|disturbed_at| {
+                // One block after the move is the earliest a block-driven trade lands
+                let age = target_block.saturating_sub(disturbed_at).max(1) - 1;
+                self.settings.max_discount_bps * window.saturating_sub(age) / window
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    // `profit` as it scores against the threshold
+    pub fn score(&self, pools: &[Address], target_block: u64, same_block: bool, profit: U256) -> U256 {
+        let discount = self.discount_bps(pools, target_block, same_block).min(BPS);
+        profit * U256::from(BPS - discount) / U256::from(BPS)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::address;
+
+    #[test]
+    fn test_routes_through_just_disturbed_pools_are_discounted_until_calm() {
+        let flow = ToxicFlow::new(ToxicFlowSettings::default());
+        let (quiet, hit) = (address(1), address(2));
+        let reserve = U256::exp10(24);
+        assert!(!flow.observe(quiet, reserve, 100));
+        assert!(!flow.observe(hit, reserve, 100));
+        // 1% drift is ordinary flow, a 5% swing is a giant swap
+        assert!(!flow.observe(quiet, reserve * 101 / 100, 101));
+        assert!(flow.observe(hit, reserve * 95 / 100, 101));
+
+        let profit = U256::from(1_000u64);
+        let route = [quiet, hit];
+        assert_eq!(flow.score(&[quiet], 102, false, profit), profit);
+        assert_eq!(flow.score(&route, 102, false, profit), U256::from(500u64));
+        assert_eq!(flow.score(&route, 103, false, profit), U256::from(666u64));
+        assert_eq!(flow.score(&route, 105, false, profit), profit);
+        // A backrun landing in the disturbing block races no one to it
+        assert_eq!(flow.score(&route, 101, true, profit), profit);
+    }
+}
@@ -0,0 +1,253 @@
+// src/treasury.rs
+use anyhow::{anyhow, bail, Result};
+use ethers::{
+    providers::Middleware,
+    types::{Address, U256},
+};
+use log::{info, warn};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use crate::erc20::IERC20;
+use crate::gas_oracle::GasSnapshot;
+use crate::store::{GasSpendRecord, PayoutRecord, Store};
+use crate::tx_sender::TxSender;
+
+const BPS: u32 = 10_000;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Payout {
+    pub address: Address,
+    // Share of each realized profit, in basis points
+    pub bps: u32,
+    #[serde(default)]
+    pub label: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct TreasurySettings {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub payouts: Vec<Payout>,
+    // Shares accrue in the store and are paid out together at most this often
+    #[serde(default = "default_settle_interval_blocks")]
+    pub settle_interval_blocks: u64,
+    // Per-token minimum worth a transfer; tokens not listed settle any non-zero balance
+    #[serde(default)]
+    pub min_settle: HashMap<Address, u64>,
+}
+
+fn default_settle_interval_blocks() -> u64 {
+    1_800
+}
+
+impl Default for TreasurySettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            payouts: Vec::new(),
+            settle_interval_blocks: default_settle_interval_blocks(),
+            min_settle: HashMap::new(),
+        }
+    }
+}
+
+impl TreasurySettings {
+    pub fn validate(&self) -> Result<()> {
+        let total: u32 = self.payouts.iter().map(|payout| payout.bps).sum();
+        if total > BPS {
+            bail!("Payout shares add up to {} bps, more than the whole profit", total);
+        }
+        Ok(())
+    }
+}
+
+// Each payee's share of `profit`, rounded down; the remainder stays with the wallet
+pub fn split(profit: U256, payouts: &[Payout]) -> Vec<(Address, U256)> {
+    payouts
+        .iter()
+        .map(|payout| (payout.address, profit * U256::from(payout.bps) / U256::from(BPS)))
+        .filter(|(_, share)| !share.is_zero())
+        .collect()
+}
+
+// Splits realized profits between the configured payees and settles them in batches:
+// shares accrue per payee and token across trades, one transfer pays off each balance
+#[derive(Debug, Clone)]
+pub struct Treasury<M> {
+    sender: TxSender<M>,
+    store: Store,
+    settings: TreasurySettings,
+    last_settled: Arc<Mutex<Option<u64>>>,
+}
+
+impl<M: Middleware + 'static> Treasury<M> {
+    pub fn new(sender: TxSender<M>, store: Store, settings: TreasurySettings) -> Result<Self> {
+        settings.validate()?;
+        Ok(Self {
+            sender,
+            store,
+            settings,
+            last_settled: Arc::default(),
+        })
+    }
+
+    // Fed by the executor's ArbitrageExecuted events, profit is in the borrowed token0
+    pub fn record_profit(&self, token: Address, profit: U256) -> Result<()> {
+        for (payee, share) in split(profit, &self.settings.payouts) {
+            let owed = self.store.accrue_payout(payee, token, share)?;
+            info!("Accrued {} of {:?} for {:?}, now owed {}", share, token, payee, owed);
+        }
+        Ok(())
+    }
+
+    // Makes the next settlement due regardless of the interval (`flashwich ctl sweep`)
+    pub fn request_settlement(&self) {
+        *self.last_settled.lock().unwrap() = None;
+    }
+
+    pub fn settlement_due(&self, block_number: u64) -> bool {
+        self.last_settled
+            .lock()
+            .unwrap()
+            .is_none_or(|last| block_number.saturating_sub(last) >= self.settings.settle_interval_blocks)
+    }
+
+    // Pays out every balance above its token's minimum; returns the number of transfers mined
+    pub async fn settle(&self, block_number: u64, gas: &GasSnapshot) -> Result<usize> {
+        if !self.settlement_due(block_number) {
+            return Ok(0);
+        }
+        *self.last_settled.lock().unwrap() = Some(block_number);
+
+        let mut settled = 0;
+        for (payee, token, amount) in self.store.payouts_owed()? {
+            let minimum = U256::from(self.settings.min_settle.get(&token).copied().unwrap_or(1));
+            if amount < minimum {
+                continue;
+            }
+            // One failed payee must not block the others; its balance stays owed
+            match self.transfer(token, payee, amount, gas).await {
+                Ok(()) => {
+                    self.store.settle_payout(payee, token, amount)?;
+                    settled += 1;
+                }
+                Err(e) => warn!("Payout of {} {:?} to {:?} failed: {:?}", amount, token, payee, e),
+            }
+        }
+        Ok(settled)
+    }
+
+    async fn transfer(&self, token: Address, to: Address, amount: U256, gas: &GasSnapshot) -> Result<()> {
+        let data = IERC20::new(token, self.sender.provider().clone())
+            .transfer(to, amount)
+            .calldata()
+            .ok_or_else(|| anyhow!("transfer produced no calldata"))?;
+        let tx = self.sender.request(token, data, U256::zero(), gas, None);
+        let receipt = self
+            .sender
+            .send(tx)
+            .await?
+            .await?
+            .ok_or_else(|| anyhow!("Payout transfer dropped from the mempool"))?;
+        if receipt.status != Some(1u64.into()) {
+            bail!("Payout transfer reverted in {:?}", receipt.transaction_hash);
+        }
+        info!(
+            "Paid {} of {:?} to {:?} in {:?}",
+            amount, token, to, receipt.transaction_hash
+        );
+
+        // Both legs leave the wallet, the reconciliation ledger books them. The transfer is
+        // mined, so a journal failure must not leave the balance owed
+        let block_number = receipt.block_number.unwrap_or_default().as_u64();
+        let payout = PayoutRecord {
+            tx_hash: receipt.transaction_hash,
+            block_number,
+            payee: to,
+            token,
+            amount,
+        };
+        if let Err(e) = self.store.record_payout(&payout) {
+            warn!("Failed to journal payout {:?}: {:?}", receipt.transaction_hash, e);
+        }
+        if let Some(gas_used) = receipt.gas_used {
+            let spend = GasSpendRecord {
+                tx_hash: receipt.transaction_hash,
+                block_number,
+                gas_used,
+                cost: gas_used * receipt.effective_gas_price.unwrap_or_default(),
+            };
+            if let Err(e) = self.store.record_gas_spend(&spend) {
+                warn!("Failed to journal payout gas {:?}: {:?}", receipt.transaction_hash, e);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{address, MockChain};
+    use ethers::signers::LocalWallet;
+
+    #[test]
+    fn test_profit_shares_accrue_per_payee_and_token() {
+        let (fund, partner, usdc) = (address(1), address(2), address(10));
+        let payouts = vec![
+            Payout {
+                address: fund,
+                bps: 1_000,
+                label: "infra".to_string(),
+            },
+            Payout {
+                address: partner,
+                bps: 250,
+                label: String::new(),
+            },
+        ];
+        assert_eq!(
+            split(U256::from(1_001u64), &payouts),
+            vec![(fund, U256::from(100u64)), (partner, U256::from(25u64))]
+        );
+        assert!(split(U256::from(3u64), &payouts).is_empty());
+
+        let settings = TreasurySettings {
+            enabled: true,
+            payouts,
+            ..Default::default()
+        };
+        let wallet: LocalWallet = "0000000000000000000000000000000000000000000000000000000000000001"
+            .parse()
+            .unwrap();
+        let treasury = Treasury::new(
+            TxSender::new(MockChain::new().provider(), wallet),
+            Store::open_in_memory().unwrap(),
+            settings.clone(),
+        )
+        .unwrap();
+        treasury.record_profit(usdc, U256::from(1_000u64)).unwrap();
+        treasury.record_profit(usdc, U256::from(2_000u64)).unwrap();
+        assert_eq!(
+            treasury.store.payouts_owed().unwrap(),
+            vec![(fund, usdc, U256::from(300u64)), (partner, usdc, U256::from(75u64))]
+        );
+
+        treasury.store.settle_payout(fund, usdc, U256::from(300u64)).unwrap();
+        assert_eq!(treasury.store.payouts_owed().unwrap().len(), 1);
+        assert!(treasury.settlement_due(100));
+
+        let oversubscribed = TreasurySettings {
+            payouts: vec![Payout {
+                address: fund,
+                bps: 10_001,
+                label: String::new(),
+            }],
+            ..settings
+        };
+        assert!(oversubscribed.validate().is_err());
+    }
+}
@@ -0,0 +1,129 @@
+// src/two_phase.rs
+use ethers::types::U256;
+use serde::Deserialize;
+
+use crate::amm_math::get_amount_out;
+
+const BPS: u64 = 10_000;
+
+// Execution in two phases: everything that needs the node (nonce, gas limit, access list)
+// first, then, without yielding until the signature, the route is re-priced from the
+// reserve cache and its hop amounts and bid are rewritten to match
+#[derive(Debug, Clone, Deserialize)]
+pub struct TwoPhaseSettings {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    // Each hop after the first spends the previous hop's re-quoted output less this, so a
+    // worse fill reverts instead of trading on
+    #[serde(default = "default_slippage_bps")]
+    pub slippage_bps: u64,
+    // Priority fee capped at this share of the re-quoted profit; 0 keeps the oracle's fee
+    #[serde(default = "default_max_bid_bps")]
+    pub max_bid_bps: u64,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_slippage_bps() -> u64 {
+    30
+}
+
+fn default_max_bid_bps() -> u64 {
+    5_000
+}
+
+impl Default for TwoPhaseSettings {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            slippage_bps: default_slippage_bps(),
+            max_bid_bps: default_max_bid_bps(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Commit {
+    // Input of every hop, the borrowed amount first
+    pub amounts: Vec<U256>,
+    // What every hop is quoted to return for its input, before the next hop's haircut
+    pub outputs: Vec<U256>,
+    pub profit: U256,
+    pub priority_fee: U256,
+}
+
+impl TwoPhaseSettings {
+    // Re-prices `amount_in` through `hops` (reserve_in, reserve_out, fee); None when the
+    // profit no longer clears `min_profit`
+    pub fn recheck(
+        &self,
+        amount_in: U256,
+        hops: &[(U256, U256, u32)],
+        gas_limit: U256,
+        priority_fee: U256,
+        min_profit: U256,
+    ) -> Option<Commit> {
+        let kept = U256::from(BPS.saturating_sub(self.slippage_bps.min(BPS)));
+        let mut amounts = Vec::with_capacity(hops.len());
+        let mut outputs = Vec::with_capacity(hops.len());
+        let mut current = amount_in;
+        for (i, &(reserve_in, reserve_out, fee)) in hops.iter().enumerate() {
+            if reserve_in.is_zero() || reserve_out.is_zero() {
+                return None;
+            }
+            amounts.push(current);
+            current = get_amount_out(current, reserve_in, reserve_out, fee);
+            outputs.push(current);
+            if i + 1 < hops.len() {
+                current = current * kept / U256::from(BPS);
+            }
+        }
+        let profit = current.saturating_sub(amount_in);
+        if profit.is_zero() || profit < min_profit {
+            return None;
+        }
+
+        let priority_fee = if self.max_bid_bps == 0 || gas_limit.is_zero() {
+            priority_fee
+        } else {
+            priority_fee.min(profit * U256::from(self.max_bid_bps) / U256::from(BPS) / gas_limit)
+        };
+        Some(Commit {
+            amounts,
+            outputs,
+            profit,
+            priority_fee,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recheck_chains_hop_inputs_and_caps_the_bid() {
+        let settings = TwoPhaseSettings::default();
+        let e18 = U256::exp10(18);
+        let hops = [(e18 * 1_000, e18 * 2_000, 3000), (e18 * 1_900, e18 * 1_000, 3000)];
+        let amount_in = e18;
+
+        let commit = settings
+            .recheck(amount_in, &hops, U256::from(3_000_000u64), U256::from(30_000_000_000u64), U256::zero())
+            .unwrap();
+        let first_out = get_amount_out(amount_in, hops[0].0, hops[0].1, 3000);
+        assert_eq!(commit.amounts, vec![amount_in, first_out * 9_970 / 10_000]);
+        let last_out = get_amount_out(commit.amounts[1], hops[1].0, hops[1].1, 3000);
+        assert_eq!(commit.profit, last_out - amount_in);
+        assert_eq!(commit.outputs, vec![first_out, last_out]);
+        // Half the profit spread over the gas limit is below the oracle's 30 gwei
+        assert_eq!(commit.priority_fee, commit.profit / 2 / 3_000_000u64);
+
+        // The spread moved under the route: reserves equalized, nothing left to take
+        let moved = [(e18 * 1_000, e18 * 1_000, 3000), (e18 * 1_000, e18 * 1_000, 3000)];
+        assert!(settings.recheck(amount_in, &moved, U256::one(), U256::one(), U256::zero()).is_none());
+        assert!(settings.recheck(amount_in, &hops, U256::one(), U256::one(), commit.profit + 1).is_none());
+    }
+}
@@ -0,0 +1,219 @@
+// src/tx_sender.rs
+use anyhow::{anyhow, Result};
+use ethers::{
+    providers::{Middleware, PendingTransaction},
+    signers::{LocalWallet, Signer},
+    types::{
+        transaction::{eip2718::TypedTransaction, eip2930::AccessList},
+        Address, BlockNumber, Bytes, Eip1559TransactionRequest, H256, U256,
+    },
+    utils::keccak256,
+};
+use log::debug;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+
+use crate::gas_oracle::GasSnapshot;
+
+// Hashes of recently signed txs remembered; far more than can be pending at once
+const SIGNED_HASHES: usize = 1024;
+
+// Sign `tx` locally and return the raw EIP-2718 envelope for eth_sendRawTransaction
+pub fn sign_raw(wallet: &LocalWallet, tx: &TypedTransaction) -> Result<Bytes> {
+    let signature = wallet.sign_transaction_sync(tx)?;
+    Ok(tx.rlp_signed(&signature))
+}
+
+// Builds EIP-1559 transactions, signs them with the local wallet and broadcasts the raw
+// bytes, so providers that reject eth_sendTransaction (or add a round trip) are fine
+#[derive(Debug, Clone)]
+pub struct TxSender<M> {
+    provider: Arc<M>,
+    wallet: LocalWallet,
+    // Highest nonce signed so far, shared by clones; lets the nonce watchdog spot gaps
+    highest_nonce: Arc<Mutex<Option<U256>>>,
+    // Recently signed tx hashes, so the wallet watcher can tell our txs from anyone else's
+    signed: Arc<Mutex<VecDeque<H256>>>,
+}
+
+impl<M: Middleware + 'static> TxSender<M> {
+    // The wallet's chain id is stamped on every transaction
+    pub fn new(provider: Arc<M>, wallet: LocalWallet) -> Self {
+        Self {
+            provider,
+            wallet,
+            highest_nonce: Arc::default(),
+            signed: Arc::default(),
+        }
+    }
+
+    pub fn address(&self) -> Address {
+        self.wallet.address()
+    }
+
+    pub fn provider(&self) -> &Arc<M> {
+        &self.provider
+    }
+
+    pub fn highest_nonce(&self) -> Option<U256> {
+        *self.highest_nonce.lock().unwrap()
+    }
+
+    // Whether `hash` is a tx this sender (or a clone) signed recently
+    pub fn is_own(&self, hash: H256) -> bool {
+        self.signed.lock().unwrap().contains(&hash)
+    }
+
+    // A nonce spent outside the bot; counted as signed so it is not taken for a gap
+    pub fn observe_nonce(&self, nonce: U256) {
+        let mut highest = self.highest_nonce.lock().unwrap();
+        *highest = Some(highest.map_or(nonce, |current| current.max(nonce)));
+    }
+
+    // Fee caps leave room for two full base fee increases before the tx is priced out
+    pub fn request(
+        &self,
+        to: Address,
+        data: Bytes,
+        value: U256,
+        gas: &GasSnapshot,
+        access_list: Option<AccessList>,
+    ) -> TypedTransaction {
+        let mut request = Eip1559TransactionRequest::new()
+            .from(self.wallet.address())
+            .to(to)
+            .data(data)
+            .value(value)
+            .chain_id(self.wallet.chain_id())
+            .max_priority_fee_per_gas(gas.priority_fee)
+            .max_fee_per_gas(gas.base_fee * 2 + gas.priority_fee);
+        if let Some(access_list) = access_list {
+            request = request.access_list(access_list);
+        }
+        request.into()
+    }
+
+    // Fills nonce and gas limit when missing and signs; the raw bytes can go to any endpoint
+    pub async fn sign(&self, mut tx: TypedTransaction) -> Result<Bytes> {
+        self.prepare(&mut tx).await?;
+        self.sign_prepared(&tx)
+    }
+
+    // The node round trips of `sign`, for callers that still edit the tx before signing
+    pub async fn prepare(&self, tx: &mut TypedTransaction) -> Result<()> {
+        tx.set_from(self.wallet.address());
+        tx.set_chain_id(self.wallet.chain_id());
+
+        if tx.nonce().is_none() {
+            let nonce = self.provider
+                .get_transaction_count(self.wallet.address(), Some(BlockNumber::Pending.into()))
+                .await
+                .map_err(|e| anyhow!("Failed to fetch nonce: {e}"))?;
+            tx.set_nonce(nonce);
+        }
+        if tx.gas().is_none() {
+            let gas = self.provider
+                .estimate_gas(tx, None)
+                .await
+                .map_err(|e| anyhow!("Gas estimation failed: {e}"))?;
+            tx.set_gas(gas);
+        }
+        Ok(())
+    }
+
+    // Signs without touching the node
+    pub fn sign_prepared(&self, tx: &TypedTransaction) -> Result<Bytes> {
+        let raw = sign_raw(&self.wallet, tx)?;
+        if let Some(&nonce) = tx.nonce() {
+            self.observe_nonce(nonce);
+        }
+        let mut signed = self.signed.lock().unwrap();
+        if signed.len() >= SIGNED_HASHES {
+            signed.pop_front();
+        }
+        signed.push_back(H256(keccak256(&raw)));
+        debug!("Signed tx nonce {:?} ({} bytes)", tx.nonce(), raw.len());
+        Ok(raw)
+    }
+
+    // Broadcasts through our own node, i.e. the public mempool
+    pub async fn broadcast(&self, raw: Bytes) -> Result<PendingTransaction<'_, M::Provider>> {
+        self.provider
+            .send_raw_transaction(raw)
+            .await
+            .map_err(|e| anyhow!("eth_sendRawTransaction failed: {e}"))
+    }
+
+    pub async fn send(&self, tx: TypedTransaction) -> Result<PendingTransaction<'_, M::Provider>> {
+        let raw = self.sign(tx).await?;
+        self.broadcast(raw).await
+    }
+
+    // Pending handle for a tx broadcast elsewhere, polled through our provider
+    pub fn pending(&self, hash: H256) -> PendingTransaction<'_, M::Provider> {
+        PendingTransaction::new(hash, self.provider.provider())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{address, quantity, MockChain};
+    use ethers::{
+        types::transaction::eip2930::AccessListItem,
+        utils::rlp::Rlp,
+    };
+
+    #[test]
+    fn test_raw_tx_round_trips_with_chain_id_and_access_list() {
+        let wallet: LocalWallet = "0000000000000000000000000000000000000000000000000000000000000001"
+            .parse::<LocalWallet>()
+            .unwrap()
+            .with_chain_id(137u64);
+        let access_list = AccessList(vec![AccessListItem {
+            address: Address::from_low_u64_be(7),
+            storage_keys: vec![Default::default()],
+        }]);
+        let tx: TypedTransaction = Eip1559TransactionRequest::new()
+            .to(Address::from_low_u64_be(1))
+            .nonce(3u64)
+            .gas(21_000u64)
+            .chain_id(137u64)
+            .max_fee_per_gas(50u64)
+            .max_priority_fee_per_gas(2u64)
+            .access_list(access_list.clone())
+            .into();
+
+        let raw = sign_raw(&wallet, &tx).unwrap();
+        let (decoded, signature) = TypedTransaction::decode_signed(&Rlp::new(&raw)).unwrap();
+
+        assert_eq!(decoded.chain_id(), Some(137u64.into()));
+        assert_eq!(decoded.access_list(), Some(&access_list));
+        assert_eq!(signature.recover(decoded.sighash()).unwrap(), wallet.address());
+    }
+
+    #[tokio::test]
+    async fn test_sign_fills_nonce_and_gas() {
+        let chain = MockChain::new();
+        let wallet: LocalWallet = "0000000000000000000000000000000000000000000000000000000000000001"
+            .parse::<LocalWallet>()
+            .unwrap()
+            .with_chain_id(137u64);
+        let sender = TxSender::new(chain.provider(), wallet);
+
+        chain.script(vec![quantity(9u64), quantity(60_000u64)]);
+        let tx: TypedTransaction = Eip1559TransactionRequest::new()
+            .to(address(1))
+            .max_fee_per_gas(50u64)
+            .max_priority_fee_per_gas(2u64)
+            .into();
+        let raw = sender.sign(tx).await.unwrap();
+        let (decoded, _) = TypedTransaction::decode_signed(&Rlp::new(&raw)).unwrap();
+
+        assert_eq!(decoded.nonce(), Some(&U256::from(9u64)));
+        assert_eq!(decoded.gas(), Some(&U256::from(60_000u64)));
+        assert_eq!(sender.highest_nonce(), Some(U256::from(9u64)));
+        assert!(sender.is_own(H256(keccak256(&raw))));
+        assert!(!sender.is_own(H256::repeat_byte(1)));
+    }
+}
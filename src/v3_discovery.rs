@@ -0,0 +1,294 @@
+// src/v3_discovery.rs
+use anyhow::{anyhow, Result};
+use ethers::{
+    contract::{parse_log, EthEvent},
+    providers::Middleware,
+    types::{Address, BlockId, Filter, Log},
+};
+use log::{debug, info, warn};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::metrics::V3_POOLS_DISCOVERED_TOTAL;
+use crate::v3_state::{fetch_pool_state, IUniswapV3Pool, V3PoolKind, V3Settings, V3StateMirror};
+
+#[derive(Clone, Debug, EthEvent)]
+#[ethevent(name = "PoolCreated", abi = "PoolCreated(address,address,uint24,int24,address)")]
+pub struct V3PoolCreatedEvent {
+    #[ethevent(indexed)]
+    pub token_0: Address,
+    #[ethevent(indexed)]
+    pub token_1: Address,
+    #[ethevent(indexed)]
+    pub fee: u32,
+    pub tick_spacing: i32,
+    pub pool: Address,
+}
+
+#[derive(Clone, Debug, EthEvent)]
+#[ethevent(name = "Pool", abi = "Pool(address,address,address)")]
+pub struct AlgebraPoolEvent {
+    #[ethevent(indexed)]
+    pub token_0: Address,
+    #[ethevent(indexed)]
+    pub token_1: Address,
+    pub pool: Address,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct V3Factory {
+    pub address: Address,
+    #[serde(default)]
+    pub kind: V3PoolKind,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct V3DiscoverySettings {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_factories")]
+    pub factories: Vec<V3Factory>,
+    // In-range liquidity a new pool needs before it is mirrored
+    #[serde(default = "default_min_liquidity")]
+    pub min_liquidity: u64,
+    // New pools are created empty; this is how long one is rechecked before being dropped
+    #[serde(default = "default_watch_blocks")]
+    pub watch_blocks: u64,
+    // eth_getLogs span per poll, kept under typical provider limits
+    #[serde(default = "default_max_block_range")]
+    pub max_block_range: u64,
+}
+
+fn default_factories() -> Vec<V3Factory> {
+    vec![
+        V3Factory {
+            address: "0x1F98431c8aD98523631AE4a59f267346ea31F984".parse().unwrap(), // Uniswap V3
+            kind: V3PoolKind::UniswapV3,
+        },
+        V3Factory {
+            address: "0x411b0fAcC3489691f28ad58c47006AF5E3Ab3A28".parse().unwrap(), // QuickSwap V3 (Algebra)
+            kind: V3PoolKind::Algebra,
+        },
+    ]
+}
+
+fn default_min_liquidity() -> u64 {
+    1_000_000_000_000
+}
+
+fn default_watch_blocks() -> u64 {
+    1_800
+}
+
+fn default_max_block_range() -> u64 {
+    500
+}
+
+impl Default for V3DiscoverySettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            factories: default_factories(),
+            min_liquidity: default_min_liquidity(),
+            watch_blocks: default_watch_blocks(),
+            max_block_range: default_max_block_range(),
+        }
+    }
+}
+
+// The pool a factory creation log announces
+pub fn decode_pool_created(log: &Log, kind: V3PoolKind) -> Option<Address> {
+    match kind {
+        V3PoolKind::UniswapV3 => parse_log::<V3PoolCreatedEvent>(log.clone())
+            .ok()
+            .map(|event| event.pool),
+        V3PoolKind::Algebra => parse_log::<AlgebraPoolEvent>(log.clone()).ok().map(|event| event.pool),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct Candidate {
+    kind: V3PoolKind,
+    created_at: u64,
+}
+
+// Follows the factories' creation logs block by block and seeds the mirror with new
+// pools once they hold `min_liquidity`; polling get_logs works over HTTP as well as WS
+#[derive(Debug, Clone)]
+pub struct V3Discovery<M> {
+    provider: Arc<M>,
+    settings: V3DiscoverySettings,
+    v3: V3Settings,
+    mirror: V3StateMirror,
+    next_block: Option<u64>,
+    candidates: HashMap<Address, Candidate>,
+}
+
+impl<M: Middleware + 'static> V3Discovery<M> {
+    pub fn new(provider: Arc<M>, settings: V3DiscoverySettings, v3: V3Settings, mirror: V3StateMirror) -> Self {
+        Self {
+            provider,
+            settings,
+            v3,
+            mirror,
+            next_block: None,
+            candidates: HashMap::new(),
+        }
+    }
+
+    pub fn candidates(&self) -> usize {
+        self.candidates.len()
+    }
+
+    // Scans creation logs up to `block_number` and returns the pools seeded this round.
+    // The first poll starts at `block_number`; existing pools are not backfilled.
+    pub async fn poll(&mut self, block_number: u64) -> Result<Vec<Address>> {
+        let from = self.next_block.unwrap_or(block_number);
+        if from <= block_number {
+            let to = block_number.min(from + self.settings.max_block_range.max(1) - 1);
+            self.scan(from, to).await?;
+            self.next_block = Some(to + 1);
+        }
+
+        let block = BlockId::Number(block_number.into());
+        let mut seeded = Vec::new();
+        for (pool, candidate) in self.candidates.clone() {
+            if block_number.saturating_sub(candidate.created_at) > self.settings.watch_blocks {
+                debug!("V3 pool {:?} never reached the liquidity threshold", pool);
+                V3_POOLS_DISCOVERED_TOTAL.with_label_values(&["expired"]).inc();
+                self.candidates.remove(&pool);
+                continue;
+            }
+
+            // One cheap read per block until the pool is worth a full tick mirror
+            let liquidity = match IUniswapV3Pool::new(pool, self.provider.clone())
+                .liquidity()
+                .block(block)
+                .call()
+                .await
+            {
+                Ok(liquidity) => liquidity,
+                Err(e) => {
+                    warn!("Liquidity read for V3 pool {:?} failed: {:?}", pool, e);
+                    continue;
+                }
+            };
+            if liquidity < self.settings.min_liquidity as u128 {
+                continue;
+            }
+
+            match fetch_pool_state(
+                self.provider.clone(),
+                pool,
+                candidate.kind,
+                self.v3.bitmap_words,
+                block,
+                block_number,
+            )
+            .await
+            {
+                Ok(state) => {
+                    info!(
+                        "Seeding V3 pool {:?} ({:?}/{:?}, fee {})",
+                        pool, state.token0, state.token1, state.fee
+                    );
+                    self.mirror.update(pool, state);
+                    self.candidates.remove(&pool);
+                    V3_POOLS_DISCOVERED_TOTAL.with_label_values(&["seeded"]).inc();
+                    seeded.push(pool);
+                }
+                Err(e) => warn!("Failed to mirror V3 pool {:?}: {:?}", pool, e),
+            }
+        }
+        Ok(seeded)
+    }
+
+    async fn scan(&mut self, from: u64, to: u64) -> Result<()> {
+        let kinds: HashMap<Address, V3PoolKind> = self
+            .settings
+            .factories
+            .iter()
+            .map(|factory| (factory.address, factory.kind))
+            .collect();
+        let filter = Filter::new()
+            .address(kinds.keys().copied().collect::<Vec<_>>())
+            .topic0(vec![V3PoolCreatedEvent::signature(), AlgebraPoolEvent::signature()])
+            .from_block(from)
+            .to_block(to);
+        let logs = self
+            .provider
+            .get_logs(&filter)
+            .await
+            .map_err(|e| anyhow!("Factory log query {}..{} failed: {e}", from, to))?;
+
+        for log in logs {
+            let Some(&kind) = kinds.get(&log.address) else {
+                continue;
+            };
+            let Some(pool) = decode_pool_created(&log, kind) else {
+                continue;
+            };
+            if self.mirror.get(&pool).is_some() {
+                continue;
+            }
+            let created_at = log.block_number.map_or(to, |number| number.as_u64());
+            debug!(
+                "New V3 pool {:?} from factory {:?} at block {}",
+                pool, log.address, created_at
+            );
+            V3_POOLS_DISCOVERED_TOTAL.with_label_values(&["created"]).inc();
+            self.candidates.insert(pool, Candidate { kind, created_at });
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{address, call_output, MockChain};
+    use ethers::abi::{self, Token};
+    use ethers::types::{H256, U256};
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_empty_pool_waits_for_liquidity_then_expires() {
+        let chain = MockChain::new();
+        let factory = default_factories()[0].address;
+        let pool = address(40);
+        let topic = |address: Address| H256::from(address);
+        let log = Log {
+            address: factory,
+            topics: vec![
+                V3PoolCreatedEvent::signature(),
+                topic(address(1)),
+                topic(address(2)),
+                H256::from_low_u64_be(500),
+            ],
+            data: abi::encode(&[Token::Int(U256::from(10u64)), Token::Address(pool)]).into(),
+            block_number: Some(100u64.into()),
+            ..Default::default()
+        };
+        assert_eq!(decode_pool_created(&log, V3PoolKind::UniswapV3), Some(pool));
+
+        let settings = V3DiscoverySettings {
+            enabled: true,
+            watch_blocks: 10,
+            ..Default::default()
+        };
+        let mirror = V3StateMirror::new();
+        let mut discovery = V3Discovery::new(chain.provider(), settings, V3Settings::default(), mirror.clone());
+
+        // Created empty: it is kept as a candidate, not mirrored
+        chain.script(vec![json!([log]), call_output(&[Token::Uint(U256::zero())])]);
+        assert!(discovery.poll(100).await.unwrap().is_empty());
+        assert_eq!(discovery.candidates(), 1);
+
+        // Past the watch window it is dropped without another pool read
+        chain.script(vec![json!([])]);
+        assert!(discovery.poll(111).await.unwrap().is_empty());
+        assert_eq!(discovery.candidates(), 0);
+        assert!(mirror.is_empty());
+    }
+}
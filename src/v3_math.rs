@@ -0,0 +1,380 @@
+// src/v3_math.rs
+//
+// Local concentrated-liquidity quoter: Uniswap V3 TickMath and SwapMath ported to U256,
+// walking a mirrored tick range so trades that run out of liquidity are detected instead
+// of being priced as if the active range were infinite.
+use ethers::types::{U256, U512};
+use std::collections::BTreeMap;
+
+use crate::amm_math::FEE_DENOMINATOR;
+
+pub const MIN_TICK: i32 = -887_272;
+pub const MAX_TICK: i32 = 887_272;
+
+// sqrt(1.0001^-2^i) as Q128.128, from TickMath.getSqrtRatioAtTick
+const TICK_RATIOS: [&str; 20] = [
+    "fffcb933bd6fad37aa2d162d1a594001",
+    "fff97272373d413259a46990580e213a",
+    "fff2e50f5f656932ef12357cf3c7fdcc",
+    "ffe5caca7e10e4e61c3624eaa0941cd0",
+    "ffcb9843d60f6159c9db58835c926644",
+    "ff973b41fa98c081472e6896dfb254c0",
+    "ff2ea16466c96a3843ec78b326b52861",
+    "fe5dee046a99a2a811c461f1969c3053",
+    "fcbe86c7900a88aedcffc83b479aa3a4",
+    "f987a7253ac413176f2b074cf7815e54",
+    "f3392b0822b70005940c7a398e4b70f3",
+    "e7159475a2c29b7443b29c7fa6e889d9",
+    "d097f3bdfd2022b8845ad8f792aa5825",
+    "a9f746462d870fdf8a65dc1f90e061e5",
+    "70d869a156d2a1b890bb3df62baf32f7",
+    "31be135f97d08fd981231505542fcfa6",
+    "9aa508b5b7a84e1c677de54f3e99bc9",
+    "5d6af8dedb81196699c329225ee604",
+    "2216e584f5fa1ea926041bedfe98",
+    "48a170391f7dc42444e8fa2",
+];
+
+fn q96() -> U256 {
+    U256::one() << 96
+}
+
+// sqrt(1.0001^tick) as Q64.96, rounded up like the on-chain version
+pub fn sqrt_ratio_at_tick(tick: i32) -> U256 {
+    let tick = tick.clamp(MIN_TICK, MAX_TICK);
+    let abs_tick = tick.unsigned_abs();
+
+    let mut ratio = U256::one() << 128;
+    for (bit, constant) in TICK_RATIOS.iter().enumerate() {
+        if abs_tick & (1 << bit) != 0 {
+            let constant = U256::from_str_radix(constant, 16).unwrap();
+            ratio = (ratio * constant) >> 128;
+        }
+    }
+    if tick > 0 {
+        ratio = U256::MAX / ratio;
+    }
+
+    let rounding = if ratio.low_u32() == 0 {
+        U256::zero()
+    } else {
+        U256::one()
+    };
+    (ratio >> 32) + rounding
+}
+
+// Greatest tick whose sqrt ratio is at or below `sqrt_price_x96`, TickMath.getTickAtSqrtRatio
+pub fn tick_at_sqrt_ratio(sqrt_price_x96: U256) -> i32 {
+    let (mut low, mut high) = (MIN_TICK, MAX_TICK);
+    while low < high {
+        let mid = low + (high - low + 1) / 2;
+        if sqrt_ratio_at_tick(mid) <= sqrt_price_x96 {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+    low
+}
+
+pub(crate) fn mul_div(a: U256, b: U256, denominator: U256) -> Option<U256> {
+    if denominator.is_zero() {
+        return None;
+    }
+    U256::try_from(a.full_mul(b) / U512::from(denominator)).ok()
+}
+
+fn mul_div_up(a: U256, b: U256, denominator: U256) -> Option<U256> {
+    if denominator.is_zero() {
+        return None;
+    }
+    let product = a.full_mul(b);
+    let denominator = U512::from(denominator);
+    let quotient = product / denominator;
+    let rounded = if (product % denominator).is_zero() {
+        quotient
+    } else {
+        quotient + 1
+    };
+    U256::try_from(rounded).ok()
+}
+
+fn div_up(a: U256, b: U256) -> U256 {
+    let quotient = a / b;
+    if (a % b).is_zero() {
+        quotient
+    } else {
+        quotient + 1
+    }
+}
+
+// Token0 moved between two sqrt prices at liquidity `liquidity`
+pub(crate) fn amount0_delta(sqrt_a: U256, sqrt_b: U256, liquidity: u128, round_up: bool) -> Option<U256> {
+    let (lower, upper) = if sqrt_a < sqrt_b {
+        (sqrt_a, sqrt_b)
+    } else {
+        (sqrt_b, sqrt_a)
+    };
+    if lower.is_zero() {
+        return None;
+    }
+    let numerator = U256::from(liquidity) << 96;
+    let spread = upper - lower;
+    if round_up {
+        Some(div_up(mul_div_up(numerator, spread, upper)?, lower))
+    } else {
+        Some(mul_div(numerator, spread, upper)? / lower)
+    }
+}
+
+// Token1 moved between two sqrt prices at liquidity `liquidity`
+pub(crate) fn amount1_delta(sqrt_a: U256, sqrt_b: U256, liquidity: u128, round_up: bool) -> Option<U256> {
+    let spread = if sqrt_a < sqrt_b {
+        sqrt_b - sqrt_a
+    } else {
+        sqrt_a - sqrt_b
+    };
+    if round_up {
+        mul_div_up(U256::from(liquidity), spread, q96())
+    } else {
+        mul_div(U256::from(liquidity), spread, q96())
+    }
+}
+
+// Price after adding `amount_in` of the input token, rounded against the trader
+fn next_sqrt_price_from_input(sqrt_price: U256, liquidity: u128, amount_in: U256, zero_for_one: bool) -> Option<U256> {
+    if amount_in.is_zero() {
+        return Some(sqrt_price);
+    }
+    let liquidity = U256::from(liquidity);
+    if zero_for_one {
+        let numerator = liquidity << 96;
+        let denominator = numerator.checked_add(amount_in.checked_mul(sqrt_price)?)?;
+        mul_div_up(numerator, sqrt_price, denominator)
+    } else {
+        sqrt_price.checked_add(mul_div(amount_in, q96(), liquidity)?)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct SwapStep {
+    sqrt_price_next: U256,
+    amount_in: U256,
+    amount_out: U256,
+    fee_amount: U256,
+}
+
+// SwapMath.computeSwapStep for exact input; `fee` in FEE_DENOMINATOR units
+fn swap_step(sqrt_price: U256, sqrt_target: U256, liquidity: u128, remaining: U256, fee: u32) -> Option<SwapStep> {
+    let zero_for_one = sqrt_price >= sqrt_target;
+    let fee_complement = U256::from(FEE_DENOMINATOR - fee);
+    let remaining_less_fee = mul_div(remaining, fee_complement, U256::from(FEE_DENOMINATOR))?;
+
+    let to_target = if zero_for_one {
+        amount0_delta(sqrt_target, sqrt_price, liquidity, true)?
+    } else {
+        amount1_delta(sqrt_price, sqrt_target, liquidity, true)?
+    };
+    let sqrt_price_next = if remaining_less_fee >= to_target {
+        sqrt_target
+    } else {
+        next_sqrt_price_from_input(sqrt_price, liquidity, remaining_less_fee, zero_for_one)?
+    };
+    let reached = sqrt_price_next == sqrt_target;
+
+    let (amount_in, amount_out) = if zero_for_one {
+        let amount_in = if reached {
+            to_target
+        } else {
+            amount0_delta(sqrt_price_next, sqrt_price, liquidity, true)?
+        };
+        (amount_in, amount1_delta(sqrt_price_next, sqrt_price, liquidity, false)?)
+    } else {
+        let amount_in = if reached {
+            to_target
+        } else {
+            amount1_delta(sqrt_price, sqrt_price_next, liquidity, true)?
+        };
+        (amount_in, amount0_delta(sqrt_price, sqrt_price_next, liquidity, false)?)
+    };
+    let fee_amount = if reached {
+        mul_div_up(amount_in, U256::from(fee), fee_complement)?
+    } else {
+        remaining - amount_in
+    };
+
+    Some(SwapStep {
+        sqrt_price_next,
+        amount_in,
+        amount_out,
+        fee_amount,
+    })
+}
+
+// Why a quote could not fill the whole input
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Exhaustion {
+    // Liquidity ran out before the edge of the known tick range
+    Depleted,
+    // More initialized ticks would have to be crossed than allowed
+    TickLimit,
+}
+
+impl Exhaustion {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Exhaustion::Depleted => "depleted",
+            Exhaustion::TickLimit => "tick_limit",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct V3Quote {
+    // Input actually consumed, fees included; below the request when exhausted
+    pub amount_in: U256,
+    pub amount_out: U256,
+    pub sqrt_price_after: U256,
+    pub ticks_crossed: usize,
+    pub exhausted: Option<Exhaustion>,
+}
+
+// Pool state a quote walks. `ticks` holds liquidityNet for every initialized tick in
+// [range_lower, range_upper], the part of the tick bitmap that was mirrored; nothing is
+// known about liquidity outside it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TickRange<'a> {
+    pub sqrt_price_x96: U256,
+    pub tick: i32,
+    pub liquidity: u128,
+    pub fee: u32,
+    pub ticks: &'a BTreeMap<i32, i128>,
+    pub range_lower: i32,
+    pub range_upper: i32,
+}
+
+// Exact-input swap through `range`, stopping early when liquidity is exhausted
+pub fn quote_exact_input(
+    range: &TickRange,
+    amount_in: U256,
+    zero_for_one: bool,
+    max_ticks_crossed: usize,
+) -> Option<V3Quote> {
+    let mut sqrt_price = range.sqrt_price_x96;
+    let mut tick = range.tick;
+    let mut liquidity = range.liquidity;
+    let mut remaining = amount_in;
+    let mut amount_out = U256::zero();
+    let mut ticks_crossed = 0;
+    let mut exhausted = None;
+    let fee = range.fee.min(FEE_DENOMINATOR - 1);
+
+    while !remaining.is_zero() {
+        // Next initialized tick in the swap direction, or the edge of what we know
+        let next = if zero_for_one {
+            (tick >= range.range_lower)
+                .then(|| range.ticks.range(range.range_lower..=tick).next_back())
+                .flatten()
+        } else {
+            (tick < range.range_upper)
+                .then(|| range.ticks.range(tick + 1..=range.range_upper).next())
+                .flatten()
+        };
+        let (target_tick, liquidity_net) = match next {
+            Some((&tick, &net)) => (tick, Some(net)),
+            None if zero_for_one => (range.range_lower.max(MIN_TICK), None),
+            None => (range.range_upper.min(MAX_TICK), None),
+        };
+        let sqrt_target = sqrt_ratio_at_tick(target_tick);
+
+        if liquidity > 0 {
+            let step = swap_step(sqrt_price, sqrt_target, liquidity, remaining, fee)?;
+            remaining -= step.amount_in + step.fee_amount;
+            amount_out += step.amount_out;
+            sqrt_price = step.sqrt_price_next;
+        } else {
+            // No active liquidity, the price moves freely to the next range
+            sqrt_price = sqrt_target;
+        }
+        if sqrt_price != sqrt_target || remaining.is_zero() {
+            break;
+        }
+
+        let Some(net) = liquidity_net else {
+            exhausted = Some(Exhaustion::Depleted);
+            break;
+        };
+        if ticks_crossed >= max_ticks_crossed {
+            exhausted = Some(Exhaustion::TickLimit);
+            break;
+        }
+        ticks_crossed += 1;
+        // Crossing downwards removes the liquidity that starts at this tick
+        let signed = if zero_for_one { -net } else { net };
+        liquidity = (liquidity as i128).saturating_add(signed).max(0) as u128;
+        tick = if zero_for_one { target_tick - 1 } else { target_tick };
+    }
+
+    Some(V3Quote {
+        amount_in: amount_in - remaining,
+        amount_out,
+        sqrt_price_after: sqrt_price,
+        ticks_crossed,
+        exhausted,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sqrt_ratio_matches_tick_math() {
+        assert_eq!(sqrt_ratio_at_tick(0), q96());
+        assert_eq!(sqrt_ratio_at_tick(MIN_TICK), U256::from(4_295_128_739u64));
+        assert_eq!(
+            sqrt_ratio_at_tick(MAX_TICK),
+            U256::from_dec_str("1461446703485210103287273052203988822378723970342").unwrap()
+        );
+        assert_eq!(
+            sqrt_ratio_at_tick(-23_028),
+            U256::from_dec_str("25052894984021797146183221489").unwrap()
+        );
+        assert_eq!(tick_at_sqrt_ratio(sqrt_ratio_at_tick(-23_028)), -23_028);
+        assert_eq!(tick_at_sqrt_ratio(sqrt_ratio_at_tick(61) - 1), 60);
+    }
+
+    #[test]
+    fn test_single_position_is_exhausted_past_its_range() {
+        // One position over [-60, 60] around price 1.0, mirrored range [-120, 120]
+        let liquidity = 10u128.pow(18);
+        let ticks = BTreeMap::from([(-60, liquidity as i128), (60, -(liquidity as i128))]);
+        let range = TickRange {
+            sqrt_price_x96: q96(),
+            tick: 0,
+            liquidity,
+            fee: 3000,
+            ticks: &ticks,
+            range_lower: -120,
+            range_upper: 120,
+        };
+
+        let small = quote_exact_input(&range, U256::exp10(14), true, 8).unwrap();
+        assert_eq!(small.exhausted, None);
+        assert_eq!(small.amount_in, U256::exp10(14));
+        assert!(small.amount_out < U256::exp10(14) * 997 / 1000);
+        assert!(small.amount_out > U256::exp10(14) * 996 / 1000);
+
+        // The position holds ~3e15 of token1 below the current price
+        let huge = quote_exact_input(&range, U256::exp10(18), true, 8).unwrap();
+        assert_eq!(huge.exhausted, Some(Exhaustion::Depleted));
+        assert_eq!(huge.ticks_crossed, 1);
+        assert!(huge.amount_in < U256::exp10(16));
+        let held = amount1_delta(sqrt_ratio_at_tick(-60), q96(), liquidity, false).unwrap();
+        assert_eq!(huge.amount_out, held);
+
+        let limited = quote_exact_input(&range, U256::exp10(18), true, 0).unwrap();
+        assert_eq!(limited.exhausted, Some(Exhaustion::TickLimit));
+        assert_eq!(limited.amount_out, held);
+    }
+}
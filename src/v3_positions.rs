@@ -0,0 +1,297 @@
+// src/v3_positions.rs
+use anyhow::{Context, Result};
+use ethers::{
+    abi::AbiEncode,
+    contract::abigen,
+    providers::Middleware,
+    types::{Address, BlockId, BlockNumber, Bytes, U256},
+};
+use serde::Deserialize;
+use std::sync::Arc;
+
+use crate::v3_math::{amount0_delta, amount1_delta, mul_div, sqrt_ratio_at_tick, tick_at_sqrt_ratio, MAX_TICK, MIN_TICK};
+
+abigen!(
+    INonfungiblePositionManager,
+    r#"[
+        struct MintParams { address token0; address token1; uint24 fee; int24 tickLower; int24 tickUpper; uint256 amount0Desired; uint256 amount1Desired; uint256 amount0Min; uint256 amount1Min; address recipient; uint256 deadline; }
+        struct DecreaseLiquidityParams { uint256 tokenId; uint128 liquidity; uint256 amount0Min; uint256 amount1Min; uint256 deadline; }
+        struct CollectParams { uint256 tokenId; address recipient; uint128 amount0Max; uint128 amount1Max; }
+        function mint(MintParams params) external payable returns (uint256 tokenId, uint128 liquidity, uint256 amount0, uint256 amount1)
+        function decreaseLiquidity(DecreaseLiquidityParams params) external payable returns (uint256 amount0, uint256 amount1)
+        function collect(CollectParams params) external payable returns (uint256 amount0, uint256 amount1)
+        function burn(uint256 tokenId) external payable
+        function multicall(bytes[] data) external payable returns (bytes[] results)
+        function positions(uint256 tokenId) external view returns (uint96, address, address, address, uint24, int24, int24, uint128, uint256, uint256, uint128, uint128)
+    ]"#
+);
+
+const BPS: u64 = 10_000;
+
+// Just-in-time liquidity around a pending V3 swap: a position minted in the bundle right
+// before the victim, covering exactly the ticks its swap walks through, then drained, its
+// fees collected and the NFT burned right after it in one multicall
+#[derive(Debug, Clone, Deserialize)]
+pub struct V3PositionSettings {
+    // Uniswap V3 NonfungiblePositionManager
+    #[serde(default = "default_position_manager")]
+    pub position_manager: Address,
+    // Tick spacings added on each side of the victim's price path
+    #[serde(default)]
+    pub margin_spacings: i32,
+    // Below the planned amounts the mint may take before it reverts
+    #[serde(default = "default_mint_slippage_bps")]
+    pub mint_slippage_bps: u64,
+    #[serde(default = "default_deadline_secs")]
+    pub deadline_secs: u64,
+}
+
+// Same deployment on Polygon as on mainnet
+fn default_position_manager() -> Address {
+    "0xC36442b4a4522E871399CD717aBDD847Ab11FE88".parse().unwrap()
+}
+
+fn default_mint_slippage_bps() -> u64 {
+    50
+}
+
+fn default_deadline_secs() -> u64 {
+    120
+}
+
+impl Default for V3PositionSettings {
+    fn default() -> Self {
+        Self {
+            position_manager: default_position_manager(),
+            margin_spacings: 0,
+            mint_slippage_bps: default_mint_slippage_bps(),
+            deadline_secs: default_deadline_secs(),
+        }
+    }
+}
+
+// A position is in range while tick_lower <= tick < tick_upper
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TickBounds {
+    pub tick_lower: i32,
+    pub tick_upper: i32,
+}
+
+impl TickBounds {
+    // Narrowest spacing-aligned bounds holding every tick the price passes between `tick`
+    // and `sqrt_price_after`, widened by `margin` spacings
+    pub fn around_swap(tick: i32, sqrt_price_after: U256, tick_spacing: i32, margin: i32) -> Self {
+        let spacing = tick_spacing.max(1);
+        let tick_after = tick_at_sqrt_ratio(sqrt_price_after);
+        let (low, high) = (tick.min(tick_after), tick.max(tick_after));
+        let lowest = MIN_TICK.div_euclid(spacing) * spacing + spacing;
+        let highest = MAX_TICK.div_euclid(spacing) * spacing;
+        let margin = margin.max(0) * spacing;
+        Self {
+            tick_lower: (low.div_euclid(spacing) * spacing - margin).max(lowest),
+            tick_upper: ((high.div_euclid(spacing) + 1) * spacing + margin).min(highest),
+        }
+    }
+
+    fn sqrt_ratios(&self) -> (U256, U256) {
+        (sqrt_ratio_at_tick(self.tick_lower), sqrt_ratio_at_tick(self.tick_upper))
+    }
+
+    // LiquidityAmounts.getLiquidityForAmounts: the most liquidity the two budgets can back
+    pub fn liquidity_for_amounts(&self, sqrt_price: U256, amount0: U256, amount1: U256) -> Option<u128> {
+        let (sqrt_lower, sqrt_upper) = self.sqrt_ratios();
+        let q96 = U256::one() << 96;
+        let from0 = |lower: U256| {
+            let intermediate = mul_div(lower, sqrt_upper, q96)?;
+            mul_div(amount0, intermediate, sqrt_upper - lower)
+        };
+        let from1 = |upper: U256| mul_div(amount1, q96, upper - sqrt_lower);
+        let liquidity = if sqrt_price <= sqrt_lower {
+            from0(sqrt_lower)?
+        } else if sqrt_price < sqrt_upper {
+            from0(sqrt_price)?.min(from1(sqrt_price)?)
+        } else {
+            from1(sqrt_upper)?
+        };
+        (liquidity <= U256::from(u128::MAX)).then(|| liquidity.as_u128())
+    }
+
+    // Tokens the pool takes to mint `liquidity` at `sqrt_price`, rounded up as it does
+    pub fn amounts_for_liquidity(&self, sqrt_price: U256, liquidity: u128) -> Option<(U256, U256)> {
+        let (sqrt_lower, sqrt_upper) = self.sqrt_ratios();
+        let price = sqrt_price.clamp(sqrt_lower, sqrt_upper);
+        let amount0 = if price < sqrt_upper {
+            amount0_delta(price, sqrt_upper, liquidity, true)?
+        } else {
+            U256::zero()
+        };
+        let amount1 = if price > sqrt_lower {
+            amount1_delta(sqrt_lower, price, liquidity, true)?
+        } else {
+            U256::zero()
+        };
+        Some((amount0, amount1))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct JitPosition {
+    pub token0: Address,
+    pub token1: Address,
+    pub fee: u32,
+    pub bounds: TickBounds,
+    pub liquidity: u128,
+    // What the mint takes at the pre-swap price
+    pub amount0: U256,
+    pub amount1: U256,
+}
+
+impl JitPosition {
+    // The largest position the budgets fund over `bounds` at `sqrt_price`; None when they
+    // fund nothing
+    pub fn plan(
+        (token0, token1): (Address, Address),
+        fee: u32,
+        bounds: TickBounds,
+        sqrt_price: U256,
+        (budget0, budget1): (U256, U256),
+    ) -> Option<Self> {
+        let liquidity = bounds.liquidity_for_amounts(sqrt_price, budget0, budget1)?;
+        if liquidity == 0 {
+            return None;
+        }
+        let (amount0, amount1) = bounds.amounts_for_liquidity(sqrt_price, liquidity)?;
+        Some(Self {
+            token0,
+            token1,
+            fee,
+            bounds,
+            liquidity,
+            amount0,
+            amount1,
+        })
+    }
+
+    fn mint_params(&self, recipient: Address, deadline: U256, slippage_bps: u64) -> MintParams {
+        let floor = |amount: U256| amount * U256::from(BPS.saturating_sub(slippage_bps)) / U256::from(BPS);
+        MintParams {
+            token_0: self.token0,
+            token_1: self.token1,
+            fee: self.fee,
+            tick_lower: self.bounds.tick_lower,
+            tick_upper: self.bounds.tick_upper,
+            amount_0_desired: self.amount0,
+            amount_1_desired: self.amount1,
+            amount_0_min: floor(self.amount0),
+            amount_1_min: floor(self.amount1),
+            recipient,
+            deadline,
+        }
+    }
+
+    // Calldata of the transaction placed before the victim
+    pub fn mint_calldata(&self, recipient: Address, deadline: U256, slippage_bps: u64) -> Bytes {
+        MintCall {
+            params: self.mint_params(recipient, deadline, slippage_bps),
+        }
+        .encode()
+        .into()
+    }
+
+    // Calldata of the transaction placed after the victim: pulls the liquidity, collects it
+    // with the fees earned to `recipient`, and burns the emptied NFT
+    pub fn exit_calldata(&self, token_id: U256, recipient: Address, deadline: U256) -> Bytes {
+        let decrease = DecreaseLiquidityCall {
+            params: DecreaseLiquidityParams {
+                token_id,
+                liquidity: self.liquidity,
+                amount_0_min: U256::zero(),
+                amount_1_min: U256::zero(),
+                deadline,
+            },
+        };
+        let collect = CollectCall {
+            params: CollectParams {
+                token_id,
+                recipient,
+                amount_0_max: u128::MAX,
+                amount_1_max: u128::MAX,
+            },
+        };
+        let burn = BurnCall { token_id };
+        MulticallCall {
+            data: vec![decrease.encode().into(), collect.encode().into(), burn.encode().into()],
+        }
+        .encode()
+        .into()
+    }
+}
+
+// Token id the bundle's mint will be given, from the mint simulated against the pending
+// block; the manager's id counter is private. Needs the owner's approvals in place
+pub async fn next_token_id<M: Middleware + 'static>(
+    client: Arc<M>,
+    settings: &V3PositionSettings,
+    position: &JitPosition,
+    owner: Address,
+    deadline: U256,
+) -> Result<U256> {
+    let manager = INonfungiblePositionManager::new(settings.position_manager, client);
+    let (token_id, ..) = manager
+        .mint(position.mint_params(owner, deadline, settings.mint_slippage_bps))
+        .from(owner)
+        .block(BlockId::Number(BlockNumber::Pending))
+        .call()
+        .await
+        .context("simulated JIT mint reverted")?;
+    Ok(token_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::address;
+    use crate::v3_math::{quote_exact_input, TickRange};
+    use ethers::abi::AbiDecode;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_jit_position_brackets_the_victim_swap() {
+        // One wide position around price 1.0 and a victim selling token0 into it
+        let depth = 10u128.pow(21);
+        let ticks = BTreeMap::from([(-6_000, depth as i128), (6_000, -(depth as i128))]);
+        let range = TickRange {
+            sqrt_price_x96: sqrt_ratio_at_tick(5),
+            tick: 5,
+            liquidity: depth,
+            fee: 3000,
+            ticks: &ticks,
+            range_lower: -6_000,
+            range_upper: 6_000,
+        };
+        let victim = quote_exact_input(&range, U256::exp10(19), true, 8).unwrap();
+        let tick_after = tick_at_sqrt_ratio(victim.sqrt_price_after);
+        assert!(tick_after < -60);
+
+        let bounds = TickBounds::around_swap(5, victim.sqrt_price_after, 60, 0);
+        assert_eq!(bounds.tick_upper, 60);
+        assert_eq!(bounds.tick_lower, tick_after.div_euclid(60) * 60);
+        assert!(bounds.tick_lower > tick_after - 60);
+        let wider = TickBounds::around_swap(5, victim.sqrt_price_after, 60, 1);
+        assert_eq!((wider.tick_lower, wider.tick_upper), (bounds.tick_lower - 60, 120));
+
+        // The mint never asks for more than the budgets
+        let budget = (U256::exp10(18), U256::exp10(18));
+        let jit = JitPosition::plan((address(1), address(2)), 3000, bounds, range.sqrt_price_x96, budget).unwrap();
+        assert!(jit.liquidity > 0);
+        assert!(jit.amount0 <= budget.0 && jit.amount1 <= budget.1);
+        assert!(jit.amount1 > budget.1 * 99 / 100);
+
+        let mint = MintCall::decode(jit.mint_calldata(address(9), U256::from(100u64), 50)).unwrap();
+        assert_eq!(mint.params.tick_lower, bounds.tick_lower);
+        assert_eq!(mint.params.amount_1_min, jit.amount1 * 9_950 / 10_000);
+        let exit = MulticallCall::decode(jit.exit_calldata(U256::from(7u64), address(9), U256::from(100u64))).unwrap();
+        assert_eq!(exit.data.len(), 3);
+        assert_eq!(BurnCall::decode(&exit.data[2]).unwrap().token_id, U256::from(7u64));
+    }
+}
@@ -0,0 +1,370 @@
+// src/v3_state.rs
+use anyhow::{anyhow, Result};
+use ethers::{
+    contract::abigen,
+    providers::Middleware,
+    types::{Address, BlockId, U256},
+};
+use log::debug;
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, RwLock};
+
+use crate::metrics::V3_EXHAUSTED_TOTAL;
+use crate::v3_math::{quote_exact_input, TickRange, V3Quote, MAX_TICK, MIN_TICK};
+
+abigen!(
+    IUniswapV3Pool,
+    r#"[
+        function token0() external view returns (address)
+        function token1() external view returns (address)
+        function fee() external view returns (uint24)
+        function tickSpacing() external view returns (int24)
+        function liquidity() external view returns (uint128)
+        function slot0() external view returns (uint160, int24, uint16, uint16, uint16, uint8, bool)
+        function tickBitmap(int16 wordPosition) external view returns (uint256)
+        function ticks(int24 tick) external view returns (uint128, int128, uint256, uint256, int56, uint160, uint32, bool)
+    ]"#
+);
+
+// Algebra V1 (QuickSwap V3) keeps the price and its dynamic fee in globalState and the
+// bitmap in tickTable; everything else matches the Uniswap V3 layout
+abigen!(
+    IAlgebraPool,
+    r#"[
+        function globalState() external view returns (uint160, int24, uint16, uint16, uint8, uint8, bool)
+        function tickTable(int16 wordPosition) external view returns (uint256)
+    ]"#
+);
+
+// Algebra V1 pools all share one spacing, it is a constant rather than a pool field
+const ALGEBRA_TICK_SPACING: i32 = 60;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum V3PoolKind {
+    #[default]
+    UniswapV3,
+    Algebra,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ExhaustionPolicy {
+    // Trade only what the mirrored liquidity can fill
+    Cap,
+    // Drop the route altogether
+    Reject,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct V3Settings {
+    // Tick bitmap words mirrored on each side of the current one; a word spans 256 * tickSpacing ticks
+    #[serde(default = "default_bitmap_words")]
+    pub bitmap_words: i16,
+    // Quotes crossing more initialized ticks than this are treated as exhausted
+    #[serde(default = "default_max_ticks_crossed")]
+    pub max_ticks_crossed: usize,
+    #[serde(default = "default_exhaustion")]
+    pub exhaustion: ExhaustionPolicy,
+}
+
+fn default_bitmap_words() -> i16 {
+    2
+}
+
+fn default_max_ticks_crossed() -> usize {
+    20
+}
+
+fn default_exhaustion() -> ExhaustionPolicy {
+    ExhaustionPolicy::Cap
+}
+
+impl Default for V3Settings {
+    fn default() -> Self {
+        Self {
+            bitmap_words: default_bitmap_words(),
+            max_ticks_crossed: default_max_ticks_crossed(),
+            exhaustion: default_exhaustion(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct V3PoolState {
+    pub token0: Address,
+    pub token1: Address,
+    pub fee: u32,
+    pub tick_spacing: i32,
+    pub sqrt_price_x96: U256,
+    pub tick: i32,
+    pub liquidity: u128,
+    // liquidityNet of every initialized tick in [range_lower, range_upper]
+    pub ticks: BTreeMap<i32, i128>,
+    pub range_lower: i32,
+    pub range_upper: i32,
+    pub block_number: u64,
+}
+
+impl V3PoolState {
+    pub fn range(&self) -> TickRange<'_> {
+        TickRange {
+            sqrt_price_x96: self.sqrt_price_x96,
+            tick: self.tick,
+            liquidity: self.liquidity,
+            fee: self.fee,
+            ticks: &self.ticks,
+            range_lower: self.range_lower,
+            range_upper: self.range_upper,
+        }
+    }
+
+    // None when `token_in` is not in the pool
+    pub fn quote(&self, token_in: Address, amount_in: U256, max_ticks_crossed: usize) -> Option<V3Quote> {
+        let zero_for_one = if token_in == self.token0 {
+            true
+        } else if token_in == self.token1 {
+            false
+        } else {
+            return None;
+        };
+        quote_exact_input(&self.range(), amount_in, zero_for_one, max_ticks_crossed)
+    }
+}
+
+// Reads the price, liquidity and the initialized ticks within `bitmap_words` words of the
+// current tick, all pinned to `block`
+pub async fn fetch_pool_state<M: Middleware + 'static>(
+    provider: Arc<M>,
+    pool: Address,
+    kind: V3PoolKind,
+    bitmap_words: i16,
+    block: BlockId,
+    block_number: u64,
+) -> Result<V3PoolState> {
+    let contract = IUniswapV3Pool::new(pool, provider.clone());
+    let algebra = IAlgebraPool::new(pool, provider);
+    let read = |e: ethers::contract::ContractError<M>| anyhow!("V3 pool {:?} read failed: {e}", pool);
+
+    // Same call order for both kinds: price slot, liquidity, spacing and fee, tokens, bitmap, ticks
+    let (sqrt_price_x96, tick, tick_spacing, fee) = match kind {
+        V3PoolKind::UniswapV3 => {
+            let (sqrt_price_x96, tick, ..) = contract.slot_0().block(block).call().await.map_err(read)?;
+            (sqrt_price_x96, tick, None, None)
+        }
+        V3PoolKind::Algebra => {
+            let (sqrt_price_x96, tick, fee, ..) = algebra.global_state().block(block).call().await.map_err(read)?;
+            (sqrt_price_x96, tick, Some(ALGEBRA_TICK_SPACING), Some(fee as u32))
+        }
+    };
+    let liquidity = contract.liquidity().block(block).call().await.map_err(read)?;
+    let tick_spacing = match tick_spacing {
+        Some(tick_spacing) => tick_spacing,
+        None => contract.tick_spacing().block(block).call().await.map_err(read)?,
+    };
+    let fee = match fee {
+        Some(fee) => fee,
+        None => contract.fee().block(block).call().await.map_err(read)?,
+    };
+    let token0 = contract.token_0().block(block).call().await.map_err(read)?;
+    let token1 = contract.token_1().block(block).call().await.map_err(read)?;
+    if tick_spacing <= 0 {
+        return Err(anyhow!("V3 pool {:?} reports tick spacing {}", pool, tick_spacing));
+    }
+
+    // Bitmap words index ticks compressed by the spacing, rounded towards negative infinity
+    let word = (tick.div_euclid(tick_spacing) >> 8) as i16;
+    let (first_word, last_word) = (word.saturating_sub(bitmap_words), word.saturating_add(bitmap_words));
+    let mut initialized = Vec::new();
+    for position in first_word..=last_word {
+        let bitmap = match kind {
+            V3PoolKind::UniswapV3 => contract.tick_bitmap(position).block(block).call().await,
+            V3PoolKind::Algebra => algebra.tick_table(position).block(block).call().await,
+        }
+        .map_err(read)?;
+        for bit in (0..256).filter(|&bit| bitmap.bit(bit)) {
+            initialized.push(((position as i32) * 256 + bit as i32) * tick_spacing);
+        }
+    }
+
+    let mut ticks = BTreeMap::new();
+    for initialized_tick in initialized {
+        let (_, liquidity_net, ..) = contract
+            .ticks(initialized_tick)
+            .block(block)
+            .call()
+            .await
+            .map_err(read)?;
+        ticks.insert(initialized_tick, liquidity_net);
+    }
+    debug!("Mirrored {} initialized ticks of V3 pool {:?}", ticks.len(), pool);
+
+    Ok(V3PoolState {
+        token0,
+        token1,
+        fee,
+        tick_spacing,
+        sqrt_price_x96,
+        tick,
+        liquidity,
+        ticks,
+        range_lower: (first_word as i32 * 256 * tick_spacing).max(MIN_TICK),
+        range_upper: ((last_word as i32 * 256 + 255) * tick_spacing).min(MAX_TICK),
+        block_number,
+    })
+}
+
+// Shared V3 pool states, the concentrated-liquidity counterpart of the reserve cache
+#[derive(Debug, Clone, Default)]
+pub struct V3StateMirror {
+    inner: Arc<RwLock<HashMap<Address, V3PoolState>>>,
+}
+
+impl V3StateMirror {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, pool: &Address) -> Option<V3PoolState> {
+        self.inner.read().unwrap().get(pool).cloned()
+    }
+
+    // Ignores states older than what is already mirrored
+    pub fn update(&self, pool: Address, state: V3PoolState) {
+        let mut inner = self.inner.write().unwrap();
+        if inner
+            .get(&pool)
+            .is_none_or(|current| state.block_number >= current.block_number)
+        {
+            inner.insert(pool, state);
+        }
+    }
+
+    pub fn pools(&self) -> Vec<Address> {
+        self.inner.read().unwrap().keys().copied().collect()
+    }
+
+    // A dynamic fee read since the pool's state was mirrored
+    pub fn set_fee(&self, pool: &Address, fee: u32) {
+        if let Some(state) = self.inner.write().unwrap().get_mut(pool) {
+            state.fee = fee;
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.inner.read().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // Quote with the exhaustion policy applied: a capped quote consumes less than
+    // `amount_in`, a rejected one is None
+    pub fn quote(&self, pool: &Address, token_in: Address, amount_in: U256, settings: &V3Settings) -> Option<V3Quote> {
+        let quote = self
+            .inner
+            .read()
+            .unwrap()
+            .get(pool)?
+            .quote(token_in, amount_in, settings.max_ticks_crossed)?;
+
+        let Some(exhaustion) = quote.exhausted else {
+            return Some(quote);
+        };
+        V3_EXHAUSTED_TOTAL.with_label_values(&[exhaustion.as_str()]).inc();
+        debug!(
+            "V3 pool {:?} exhausted ({}) after {} of {} in",
+            pool,
+            exhaustion.as_str(),
+            quote.amount_in,
+            amount_in
+        );
+        match settings.exhaustion {
+            ExhaustionPolicy::Cap if !quote.amount_out.is_zero() => Some(quote),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{address, call_output, MockChain};
+    use ethers::abi::Token;
+
+    fn int(value: i128) -> Token {
+        let magnitude = U256::from(value.unsigned_abs());
+        Token::Int(if value < 0 {
+            (!magnitude).overflowing_add(U256::one()).0
+        } else {
+            magnitude
+        })
+    }
+
+    #[tokio::test]
+    async fn test_mirrored_pool_caps_or_rejects_past_its_liquidity() {
+        let chain = MockChain::new();
+        let (pool, token0, token1) = (address(30), address(1), address(2));
+        let liquidity = 10i128.pow(18);
+        let uint = |value: U256| Token::Uint(value);
+        let ticks_output = |net: i128| {
+            call_output(&[
+                uint(U256::zero()),
+                int(net),
+                uint(U256::zero()),
+                uint(U256::zero()),
+                int(0),
+                uint(U256::zero()),
+                uint(U256::zero()),
+                Token::Bool(true),
+            ])
+        };
+
+        // A single position over [-60, 60]: compressed ticks -1 and 1, in words -1 and 0
+        chain.script(vec![
+            call_output(&[
+                uint(U256::one() << 96),
+                int(0),
+                uint(U256::zero()),
+                uint(U256::zero()),
+                uint(U256::zero()),
+                uint(U256::zero()),
+                Token::Bool(true),
+            ]),
+            call_output(&[uint(U256::from(liquidity))]),
+            call_output(&[int(60)]),
+            call_output(&[uint(U256::from(3000u64))]),
+            call_output(&[Token::Address(token0)]),
+            call_output(&[Token::Address(token1)]),
+            call_output(&[uint(U256::one() << 255)]),
+            call_output(&[uint(U256::from(2u64))]),
+            call_output(&[uint(U256::zero())]),
+            ticks_output(liquidity),
+            ticks_output(-liquidity),
+        ]);
+        let state = fetch_pool_state(chain.provider(), pool, V3PoolKind::UniswapV3, 1, BlockId::Number(7u64.into()), 7)
+            .await
+            .unwrap();
+        assert_eq!(state.ticks, BTreeMap::from([(-60, liquidity), (60, -liquidity)]));
+        assert_eq!((state.range_lower, state.range_upper), (-256 * 60, 511 * 60));
+
+        let mirror = V3StateMirror::new();
+        mirror.update(pool, state);
+        let mut settings = V3Settings::default();
+        let amount = U256::exp10(18);
+
+        let capped = mirror.quote(&pool, token0, amount, &settings).unwrap();
+        assert!(capped.amount_in < amount);
+        assert!(mirror
+            .quote(&pool, token1, U256::exp10(14), &settings)
+            .unwrap()
+            .exhausted
+            .is_none());
+
+        settings.exhaustion = ExhaustionPolicy::Reject;
+        assert_eq!(mirror.quote(&pool, token0, amount, &settings), None);
+        assert_eq!(mirror.quote(&pool, address(3), amount, &settings), None);
+    }
+}
@@ -0,0 +1,264 @@
+// src/validation.rs
+use anyhow::{anyhow, bail, Result};
+use ethers::{types::Address, utils::to_checksum};
+use std::fmt;
+
+// Env vars holding contract and wallet addresses, checked when set
+pub const ADDRESS_ENV_VARS: &[&str] = &[
+    "FLASH_LOAN_CONTRACT",
+    "FASTLANE_CONTRACT",
+    "FASTLANE_SENDER_CONTRACT",
+    "ARBITRAGE_EXECUTOR_CONTRACT",
+    "WALLET_ADDRESS",
+];
+
+// Prefix of the env overrides of config.yaml, see Settings::load
+const SETTINGS_ENV_PREFIX: &str = "FLASHWICH__";
+
+// Hex runs this close to 40 digits are taken for mistyped addresses; shorter and longer
+// ones are selectors, hashes or keys
+const NEAR_ADDRESS: std::ops::RangeInclusive<usize> = 36..=44;
+
+// One bad address in a config input, with where it came from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AddressIssue {
+    pub source: String,
+    pub line: Option<usize>,
+    pub field: String,
+    pub value: String,
+    pub problem: String,
+}
+
+impl fmt::Display for AddressIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.line {
+            Some(line) => write!(f, "{}:{}", self.source, line)?,
+            None => write!(f, "{}", self.source)?,
+        }
+        if !self.field.is_empty() {
+            write!(f, " `{}`", self.field)?;
+        }
+        write!(f, ": {} {}", self.value, self.problem)
+    }
+}
+
+// Parses a 0x-prefixed address. Mixed case must be a valid EIP-55 checksum; all-lower
+// and all-upper case carry no checksum and are accepted as is
+pub fn check_address(value: &str) -> std::result::Result<Address, String> {
+    let Some(hex) = value.strip_prefix("0x").or_else(|| value.strip_prefix("0X")) else {
+        return Err("is missing the 0x prefix".to_string());
+    };
+    if hex.len() != 40 {
+        return Err(format!("has {} hex digits, an address has 40", hex.len()));
+    }
+    if let Some(bad) = hex.chars().find(|c| !c.is_ascii_hexdigit()) {
+        return Err(format!("contains '{}', which is not a hex digit", bad));
+    }
+    let address: Address = value.parse().map_err(|e| format!("is not an address: {e}"))?;
+
+    let mixed = hex.chars().any(|c| c.is_ascii_lowercase()) && hex.chars().any(|c| c.is_ascii_uppercase());
+    let checksummed = to_checksum(&address, None);
+    if mixed && checksummed[2..] != *hex {
+        return Err(format!("has a bad checksum, expected {}", checksummed));
+    }
+    Ok(address)
+}
+
+// Address of a required env var, with an error naming the var instead of a panic
+pub fn env_address(name: &str) -> Result<Address> {
+    let value = std::env::var(name).map_err(|_| anyhow!("{} must be set in .env", name))?;
+    check_address(value.trim()).map_err(|problem| anyhow!("{} (.env): {} {}", name, value, problem))
+}
+
+// Address-like values of a YAML or pretty-printed JSON file, located by line and key path.
+// Commented-out lines are skipped
+pub fn scan_text(source: &str, content: &str) -> Vec<AddressIssue> {
+    let mut issues = Vec::new();
+    // (indent, key) of the enclosing mappings
+    let mut path: Vec<(usize, String)> = Vec::new();
+
+    for (number, line) in content.lines().enumerate() {
+        let trimmed = line.trim_start();
+        if trimmed.is_empty() || trimmed.starts_with('#') || trimmed.starts_with("//") {
+            continue;
+        }
+        let indent = line.len() - trimmed.len();
+        let code = trimmed.split(" #").next().unwrap_or(trimmed);
+        let (item, code) = match code.strip_prefix("- ") {
+            Some(rest) => (true, rest),
+            None => (false, code),
+        };
+
+        let key = code
+            .split_once(": ")
+            .map(|(key, _)| key)
+            .or_else(|| code.trim_end().strip_suffix(':'))
+            .or_else(|| {
+                code.trim_end()
+                    .strip_suffix('{')
+                    .and_then(|k| k.trim_end().strip_suffix(':'))
+            })
+            .map(|key| key.trim().trim_matches(|c| c == '"' || c == '\'').to_string());
+
+        // A list item may sit at the same indent as its parent key
+        path.retain(|(depth, _)| if item { *depth <= indent } else { *depth < indent });
+        let parent: Vec<&str> = path.iter().map(|(_, key)| key.as_str()).collect();
+        let field = match &key {
+            // Address-keyed maps (fee overrides, tokens.json) report the map itself
+            Some(key) if !key.starts_with("0x") => parent
+                .iter()
+                .copied()
+                .chain([key.as_str()])
+                .collect::<Vec<_>>()
+                .join("."),
+            _ => parent.join("."),
+        };
+        if let Some(key) = key {
+            path.push((if item { indent + 2 } else { indent }, key));
+        }
+
+        for value in address_candidates(code) {
+            if let Err(problem) = check_address(value) {
+                issues.push(AddressIssue {
+                    source: source.to_string(),
+                    line: Some(number + 1),
+                    field: field.clone(),
+                    value: value.to_string(),
+                    problem,
+                });
+            }
+        }
+    }
+    issues
+}
+
+// 0x-prefixed runs of roughly address length; they end at a quote, space or separator
+fn address_candidates(text: &str) -> Vec<&str> {
+    let mut candidates = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("0x") {
+        let tail = &rest[start..];
+        let end = tail
+            .find(|c: char| c.is_whitespace() || matches!(c, '"' | '\'' | ',' | ':' | ']' | '}'))
+            .unwrap_or(tail.len());
+        let preceded_by_hex = rest[..start]
+            .chars()
+            .next_back()
+            .is_some_and(|c| c.is_ascii_alphanumeric());
+        if !preceded_by_hex && NEAR_ADDRESS.contains(&(end - 2)) {
+            candidates.push(&tail[..end]);
+        }
+        rest = &tail[end.max(2)..];
+    }
+    candidates
+}
+
+// Address env vars and FLASHWICH__ overrides that are set
+pub fn scan_env() -> Vec<AddressIssue> {
+    let mut issues = Vec::new();
+    for (name, value) in std::env::vars() {
+        let named = ADDRESS_ENV_VARS.contains(&name.as_str());
+        if !named && !name.starts_with(SETTINGS_ENV_PREFIX) {
+            continue;
+        }
+        let candidates = if named {
+            vec![value.trim()]
+        } else {
+            address_candidates(&value)
+        };
+        for candidate in candidates {
+            if let Err(problem) = check_address(candidate) {
+                issues.push(AddressIssue {
+                    source: "environment".to_string(),
+                    line: None,
+                    field: name.clone(),
+                    value: candidate.to_string(),
+                    problem,
+                });
+            }
+        }
+    }
+    issues
+}
+
+// Checks every user-provided address before anything connects: the config file, tokens.json
+// and the environment. All problems are reported together
+pub fn validate_config(config_path: &str, tokens_path: &str) -> Result<()> {
+    let mut issues = Vec::new();
+    for path in [config_path, tokens_path] {
+        // The config loader also accepts the path without its extension
+        let file = [path.to_string(), format!("{path}.yaml"), format!("{path}.yml")]
+            .into_iter()
+            .find(|file| std::path::Path::new(file).is_file());
+        let Some(file) = file else {
+            // The token registry falls back to its built-in list
+            if path == tokens_path {
+                continue;
+            }
+            bail!("{} not found", path);
+        };
+        let content = std::fs::read_to_string(&file).map_err(|e| anyhow!("Failed to read {file}: {e}"))?;
+        issues.extend(scan_text(&file, &content));
+    }
+    issues.extend(scan_env());
+
+    if issues.is_empty() {
+        return Ok(());
+    }
+    let report: Vec<String> = issues.iter().map(|issue| format!("  {issue}")).collect();
+    bail!("Invalid addresses in the configuration:\n{}", report.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_addresses_are_checked_with_their_location() {
+        let wmatic = "0x0d500B1d8E8eF31E21C99d1Db9A6444d3ADf1270";
+        assert!(check_address(wmatic).is_ok());
+        assert!(check_address(&wmatic.to_lowercase()).is_ok());
+        assert!(check_address("0x0D500B1D8E8EF31E21C99D1DB9A6444D3ADF1270").is_ok());
+        let bad_checksum = "0x0d500b1D8E8eF31E21C99d1Db9A6444d3ADf1270";
+        assert_eq!(
+            check_address(bad_checksum),
+            Err(format!("has a bad checksum, expected {}", wmatic))
+        );
+        assert!(check_address("0x0d500B1d8E8eF31E21C99d1Db9A6444d3ADf127").is_err());
+        assert!(check_address("0x0d500B1d8E8eF31E21C99d1Db9A6444d3ADf127Z").is_err());
+
+        let yaml = format!(
+            "contracts:\n  flash_loan: \"{bad_checksum}\" # executor\n# legacy: \"0x1\"\nfees:\n  factories:\n    \
+             \"0x5757371414417b8C6CAad45bAeF941aBc7d3Ab3\": 3000\ndiscovery:\n  allowlist:\n  - \"{wmatic}\"\n  - \
+             \"{bad_checksum}\"\n  selector: \"0x12345678\"\n"
+        );
+        let issues = scan_text("config.yaml", &yaml);
+        let located: Vec<(Option<usize>, &str)> =
+            issues.iter().map(|issue| (issue.line, issue.field.as_str())).collect();
+        assert_eq!(
+            located,
+            vec![
+                (Some(2), "contracts.flash_loan"),
+                (Some(6), "fees.factories"),
+                (Some(10), "discovery.allowlist"),
+            ]
+        );
+        assert_eq!(
+            issues[0].to_string(),
+            format!("config.yaml:2 `contracts.flash_loan`: {bad_checksum} has a bad checksum, expected {wmatic}")
+        );
+
+        let json =
+            format!("{{\n  \"{wmatic}\": {{\n    \"address\": \"{bad_checksum}\",\n    \"decimals\": 18\n  }}\n}}\n");
+        let issues = scan_text("tokens.json", &json);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(
+            (issues[0].line, issues[0].field.clone()),
+            (Some(3), format!("{wmatic}.address"))
+        );
+
+        // What ships in the repo must pass
+        assert!(scan_text("config.yaml", include_str!("../config.yaml")).is_empty());
+        assert!(scan_text("tokens.json", include_str!("tokens.json")).is_empty());
+    }
+}
@@ -0,0 +1,302 @@
+// src/volatility.rs
+use anyhow::Result;
+use ethers::types::{Address, U256};
+use log::debug;
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::RwLock;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::store::Store;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct VolatilitySettings {
+    #[serde(default)]
+    pub enabled: bool,
+    // Look-back for TWAP and realized volatility
+    #[serde(default = "default_horizon_secs")]
+    pub horizon_secs: u64,
+    #[serde(default = "default_candle_secs")]
+    pub candle_secs: u64,
+    // Volatility (bps per candle) at which sizing and margins are left untouched
+    #[serde(default = "default_reference_bps")]
+    pub reference_bps: f64,
+    #[serde(default = "default_min_size_factor")]
+    pub min_size_factor: f64,
+    #[serde(default = "default_max_margin_factor")]
+    pub max_margin_factor: f64,
+    // How long a computed estimate is reused before hitting the store again
+    #[serde(default = "default_refresh_secs")]
+    pub refresh_secs: u64,
+}
+
+fn default_horizon_secs() -> u64 {
+    900
+}
+
+fn default_candle_secs() -> u64 {
+    60
+}
+
+fn default_reference_bps() -> f64 {
+    20.0
+}
+
+fn default_min_size_factor() -> f64 {
+    0.25
+}
+
+fn default_max_margin_factor() -> f64 {
+    3.0
+}
+
+fn default_refresh_secs() -> u64 {
+    30
+}
+
+impl Default for VolatilitySettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            horizon_secs: default_horizon_secs(),
+            candle_secs: default_candle_secs(),
+            reference_bps: default_reference_bps(),
+            min_size_factor: default_min_size_factor(),
+            max_margin_factor: default_max_margin_factor(),
+            refresh_secs: default_refresh_secs(),
+        }
+    }
+}
+
+// Pool price (token1 per token0, raw units) observed at a timestamp
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PriceSample {
+    pub timestamp: u64,
+    pub price: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Candle {
+    pub start: u64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+}
+
+// Samples must be sorted by timestamp
+pub fn candles(samples: &[PriceSample], candle_secs: u64) -> Vec<Candle> {
+    let candle_secs = candle_secs.max(1);
+    let mut out: Vec<Candle> = Vec::new();
+
+    for sample in samples {
+        let start = sample.timestamp - sample.timestamp % candle_secs;
+        match out.last_mut() {
+            Some(candle) if candle.start == start => {
+                candle.high = candle.high.max(sample.price);
+                candle.low = candle.low.min(sample.price);
+                candle.close = sample.price;
+            }
+            _ => out.push(Candle {
+                start,
+                open: sample.price,
+                high: sample.price,
+                low: sample.price,
+                close: sample.price,
+            }),
+        }
+    }
+    out
+}
+
+// Time-weighted average over [from, to]; each price holds until the next sample
+pub fn twap(samples: &[PriceSample], from: u64, to: u64) -> Option<f64> {
+    if to <= from {
+        return None;
+    }
+
+    let mut weighted = 0.0;
+    let mut covered = 0u64;
+    for (i, sample) in samples.iter().enumerate() {
+        let start = sample.timestamp.max(from);
+        let end = samples.get(i + 1).map(|next| next.timestamp).unwrap_or(to).min(to);
+        if end > start {
+            weighted += sample.price * (end - start) as f64;
+            covered += end - start;
+        }
+    }
+
+    (covered > 0).then(|| weighted / covered as f64)
+}
+
+// Standard deviation of close-to-close log returns, in bps per candle
+pub fn realized_volatility_bps(candles: &[Candle]) -> f64 {
+    let returns: Vec<f64> = candles
+        .windows(2)
+        .filter(|pair| pair[0].close > 0.0 && pair[1].close > 0.0)
+        .map(|pair| (pair[1].close / pair[0].close).ln())
+        .collect();
+    if returns.len() < 2 {
+        return 0.0;
+    }
+
+    let mean = returns.iter().sum::<f64>() / returns.len() as f64;
+    let variance = returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (returns.len() - 1) as f64;
+    variance.sqrt() * 10_000.0
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct PoolVolatility {
+    pub twap: Option<f64>,
+    pub volatility_bps: f64,
+}
+
+// Multipliers applied to trade size and required profit margin
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RiskAdjustment {
+    pub size_factor: f64,
+    pub margin_factor: f64,
+}
+
+impl Default for RiskAdjustment {
+    fn default() -> Self {
+        Self {
+            size_factor: 1.0,
+            margin_factor: 1.0,
+        }
+    }
+}
+
+impl RiskAdjustment {
+    pub fn for_volatility(volatility_bps: f64, settings: &VolatilitySettings) -> Self {
+        if settings.reference_bps <= 0.0 || volatility_bps <= settings.reference_bps {
+            return Self::default();
+        }
+        let ratio = volatility_bps / settings.reference_bps;
+        Self {
+            size_factor: (1.0 / ratio).max(settings.min_size_factor),
+            margin_factor: ratio.min(settings.max_margin_factor),
+        }
+    }
+
+    pub fn scale_size(&self, amount: U256) -> U256 {
+        scale(amount, self.size_factor)
+    }
+
+    pub fn scale_margin(&self, min_profit: U256) -> U256 {
+        scale(min_profit, self.margin_factor)
+    }
+}
+
+fn scale(amount: U256, factor: f64) -> U256 {
+    // Parts-per-million keeps the arithmetic in U256
+    let ppm = (factor * 1_000_000.0).round() as u64;
+    amount * U256::from(ppm) / U256::from(1_000_000u64)
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+// Per-pool TWAP and volatility from indexed Sync events, cached for `refresh_secs`
+#[derive(Debug)]
+pub struct VolatilityTracker {
+    store: Store,
+    settings: VolatilitySettings,
+    cache: RwLock<HashMap<Address, (Instant, PoolVolatility)>>,
+}
+
+impl VolatilityTracker {
+    pub fn new(store: Store, settings: VolatilitySettings) -> Self {
+        Self {
+            store,
+            settings,
+            cache: RwLock::new(HashMap::new()),
+        }
+    }
+
+    pub fn pool(&self, pool: Address) -> Result<PoolVolatility> {
+        let max_age = Duration::from_secs(self.settings.refresh_secs);
+        if let Some((computed, estimate)) = self.cache.read().unwrap().get(&pool) {
+            if computed.elapsed() < max_age {
+                return Ok(*estimate);
+            }
+        }
+
+        let to = now_secs();
+        let from = to.saturating_sub(self.settings.horizon_secs);
+        let samples: Vec<PriceSample> = self
+            .store
+            .pool_events_since(pool, from, Some("sync"))?
+            .into_iter()
+            .filter(|sync| !sync.amounts[0].is_zero())
+            .filter_map(|sync| {
+                Some(PriceSample {
+                    timestamp: sync.timestamp?,
+                    price: sync.amounts[1].as_u128() as f64 / sync.amounts[0].as_u128() as f64,
+                })
+            })
+            .collect();
+
+        let estimate = PoolVolatility {
+            twap: twap(&samples, from, to),
+            volatility_bps: realized_volatility_bps(&candles(&samples, self.settings.candle_secs)),
+        };
+        debug!("Pool {:?}: {} syncs, volatility {:.1} bps", pool, samples.len(), estimate.volatility_bps);
+
+        self.cache.write().unwrap().insert(pool, (Instant::now(), estimate));
+        Ok(estimate)
+    }
+
+    // The jumpiest pool of a route decides its adjustment
+    pub fn route_adjustment(&self, pools: &[Address]) -> RiskAdjustment {
+        let volatility = pools
+            .iter()
+            .filter_map(|&pool| self.pool(pool).ok())
+            .map(|estimate| estimate.volatility_bps)
+            .fold(0.0, f64::max);
+        RiskAdjustment::for_volatility(volatility, &self.settings)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(timestamp: u64, price: f64) -> PriceSample {
+        PriceSample { timestamp, price }
+    }
+
+    #[test]
+    fn test_candles_twap_and_volatility() {
+        let samples = [sample(0, 1.0), sample(30, 1.2), sample(60, 1.1), sample(130, 1.1)];
+
+        let candles = candles(&samples, 60);
+        assert_eq!(candles.len(), 3);
+        assert_eq!((candles[0].open, candles[0].high, candles[0].close), (1.0, 1.2, 1.2));
+
+        // 1.0 for 30s, 1.2 for 30s
+        assert!((twap(&samples, 0, 60).unwrap() - 1.1).abs() < 1e-9);
+
+        assert!(realized_volatility_bps(&candles) > 0.0);
+        let flat = [sample(0, 1.0), sample(60, 1.0), sample(120, 1.0)];
+        assert_eq!(realized_volatility_bps(&super::candles(&flat, 60)), 0.0);
+    }
+
+    #[test]
+    fn test_jumpy_pairs_shrink_size_and_widen_margin() {
+        let settings = VolatilitySettings::default();
+        assert_eq!(RiskAdjustment::for_volatility(10.0, &settings), RiskAdjustment::default());
+
+        let jumpy = RiskAdjustment::for_volatility(40.0, &settings);
+        assert_eq!(jumpy.scale_size(U256::from(1_000u64)), U256::from(500u64));
+        assert_eq!(jumpy.scale_margin(U256::from(1_000u64)), U256::from(2_000u64));
+
+        let extreme = RiskAdjustment::for_volatility(1_000.0, &settings);
+        assert_eq!(extreme.size_factor, settings.min_size_factor);
+        assert_eq!(extreme.margin_factor, settings.max_margin_factor);
+    }
+}
@@ -0,0 +1,217 @@
+// src/wallet_watcher.rs
+use anyhow::{anyhow, Result};
+use ethers::{
+    providers::Middleware,
+    types::{BlockNumber, H256, U256},
+};
+use log::{debug, warn};
+use serde::Deserialize;
+use std::time::Duration;
+use tokio::sync::Mutex;
+
+use crate::alerts::{AlertLevel, Alerts};
+use crate::metrics::FOREIGN_TXS_TOTAL;
+use crate::reconciliation::BalanceReconciler;
+use crate::token_registry::format_units;
+use crate::tx_sender::TxSender;
+
+// Watches mined blocks for transactions from the signing wallet that the bot did not sign,
+// e.g. the same key used from a browser wallet, which shifts nonces and balances under it
+#[derive(Debug, Clone, Deserialize)]
+pub struct WalletWatcherSettings {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(default = "default_interval_secs")]
+    pub interval_secs: u64,
+    // Blocks scanned per check; after a longer outage only the most recent are looked at
+    #[serde(default = "default_max_blocks")]
+    pub max_blocks: u64,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+fn default_interval_secs() -> u64 {
+    5
+}
+
+fn default_max_blocks() -> u64 {
+    50
+}
+
+impl Default for WalletWatcherSettings {
+    fn default() -> Self {
+        Self {
+            enabled: default_enabled(),
+            interval_secs: default_interval_secs(),
+            max_blocks: default_max_blocks(),
+        }
+    }
+}
+
+pub struct WalletWatcher<M> {
+    sender: TxSender<M>,
+    settings: WalletWatcherSettings,
+    alerts: Alerts,
+    // Re-checks the ledger once foreign spending moved the balances
+    reconciler: Option<BalanceReconciler<M>>,
+    // Last block scanned; the first check starts at the head
+    scanned: Mutex<Option<u64>>,
+}
+
+impl<M: Middleware + 'static> WalletWatcher<M> {
+    pub fn new(sender: TxSender<M>, settings: WalletWatcherSettings, alerts: Alerts) -> Self {
+        Self {
+            sender,
+            settings,
+            alerts,
+            reconciler: None,
+            scanned: Mutex::new(None),
+        }
+    }
+
+    pub fn with_reconciler(mut self, reconciler: Option<BalanceReconciler<M>>) -> Self {
+        self.reconciler = reconciler;
+        self
+    }
+
+    pub async fn run(&self) {
+        let mut interval = tokio::time::interval(Duration::from_secs(self.settings.interval_secs.max(1)));
+        loop {
+            interval.tick().await;
+            if let Err(e) = self.check_once().await {
+                warn!("Wallet watcher check failed: {:?}", e);
+            }
+        }
+    }
+
+    // Returns the foreign txs found in the blocks mined since the last check
+    pub async fn check_once(&self) -> Result<Vec<H256>> {
+        let provider = self.sender.provider();
+        let head = provider
+            .get_block_number()
+            .await
+            .map_err(|e| anyhow!("Failed to fetch block number: {e}"))?
+            .as_u64();
+        let mut scanned = self.scanned.lock().await;
+        let Some(last) = *scanned else {
+            *scanned = Some(head);
+            return Ok(Vec::new());
+        };
+        let from = (last + 1).max(head.saturating_sub(self.settings.max_blocks.max(1)) + 1);
+
+        let address = self.sender.address();
+        let mut foreign = Vec::new();
+        let mut highest_nonce = None;
+        for number in from..=head {
+            let block = provider
+                .get_block_with_txs(number)
+                .await
+                .map_err(|e| anyhow!("Failed to fetch block {number}: {e}"))?
+                .ok_or_else(|| anyhow!("Block {number} not found"))?;
+            for tx in block.transactions {
+                if tx.from != address || self.sender.is_own(tx.hash) {
+                    continue;
+                }
+                warn!("Foreign tx {:?} from the signing wallet in block {} (nonce {})", tx.hash, number, tx.nonce);
+                FOREIGN_TXS_TOTAL.inc();
+                highest_nonce = Some(highest_nonce.map_or(tx.nonce, |current: U256| current.max(tx.nonce)));
+                foreign.push(tx.hash);
+            }
+            *scanned = Some(number);
+        }
+        let Some(nonce) = highest_nonce else {
+            debug!("No foreign wallet activity in blocks {}..={}", from, head);
+            return Ok(foreign);
+        };
+
+        // Our next tx fetches the pending nonce anyway; this keeps the nonce watchdog from
+        // taking the spent nonces for a gap
+        self.sender.observe_nonce(nonce);
+        let balance = provider
+            .get_balance(address, Some(BlockNumber::Latest.into()))
+            .await
+            .map_err(|e| anyhow!("Failed to fetch MATIC balance of {:?}: {e}", address))?;
+        self.alerts.emit(
+            AlertLevel::Critical,
+            "wallet_watcher",
+            format!(
+                "{} tx(s) from {:?} not sent by the bot, latest {:?} at nonce {}; balance now {} MATIC. \
+                 Is the key in use elsewhere?",
+                foreign.len(),
+                address,
+                foreign.last().unwrap_or(&H256::zero()),
+                nonce,
+                format_units(balance, 18)
+            ),
+        );
+        if let Some(reconciler) = &self.reconciler {
+            if let Err(e) = reconciler.reconcile_once().await {
+                warn!("Balance reconciliation after foreign wallet activity failed: {:?}", e);
+            }
+        }
+        Ok(foreign)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::fixtures::{address, quantity, MockChain};
+    use ethers::signers::{LocalWallet, Signer};
+    use ethers::types::{transaction::eip2718::TypedTransaction, Block, Eip1559TransactionRequest, Transaction};
+    use ethers::utils::keccak256;
+    use serde_json::json;
+
+    #[tokio::test]
+    async fn test_foreign_txs_are_flagged_and_the_nonce_resynced() {
+        let chain = MockChain::new();
+        let wallet: LocalWallet = "0000000000000000000000000000000000000000000000000000000000000001"
+            .parse::<LocalWallet>()
+            .unwrap()
+            .with_chain_id(137u64);
+        let sender = TxSender::new(chain.provider(), wallet);
+        let wallet_address = sender.address();
+        let watcher = WalletWatcher::new(sender.clone(), WalletWatcherSettings::default(), Alerts::default());
+
+        // The first check only marks where scanning starts
+        chain.script(vec![quantity(100u64)]);
+        assert!(watcher.check_once().await.unwrap().is_empty());
+
+        let ours: TypedTransaction = Eip1559TransactionRequest::new()
+            .to(wallet_address)
+            .nonce(4u64)
+            .gas(21_000u64)
+            .max_fee_per_gas(50u64)
+            .max_priority_fee_per_gas(2u64)
+            .into();
+        let own_hash = H256(keccak256(sender.sign_prepared(&ours).unwrap()));
+        let tx = |hash: H256, from, nonce: u64| Transaction {
+            hash,
+            from,
+            nonce: nonce.into(),
+            ..Default::default()
+        };
+        let foreign_hash = H256::repeat_byte(0xee);
+        let block = |transactions| {
+            serde_json::to_value(Block::<Transaction> {
+                transactions,
+                ..Default::default()
+            })
+            .unwrap()
+        };
+        chain.script(vec![
+            quantity(102u64),
+            block(vec![tx(own_hash, wallet_address, 4), tx(H256::repeat_byte(1), address(2), 9)]),
+            block(vec![tx(foreign_hash, wallet_address, 5)]),
+            json!("0xde0b6b3a7640000"),
+        ]);
+        assert_eq!(watcher.check_once().await.unwrap(), vec![foreign_hash]);
+        assert_eq!(sender.highest_nonce(), Some(U256::from(5u64)));
+
+        // Nothing new mined: no block is fetched again
+        chain.script(vec![quantity(102u64)]);
+        assert!(watcher.check_once().await.unwrap().is_empty());
+    }
+}
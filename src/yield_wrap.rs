@@ -0,0 +1,155 @@
+// src/yield_wrap.rs
+use ethers::types::{Address, U256};
+use serde::Deserialize;
+
+// Aave aTokens are redeemable 1:1 for their underlying through the lending pool, so a route
+// can wrap or unwrap one as a step instead of swapping through a thin aToken pool. Each
+// adapter enters the route search as a fee-free pseudo-pool, keyed by the aToken address,
+// whose reserves are deep enough to quote 1:1 at any size; the executor routes that hop
+// through the lending pool's supply/withdraw
+#[derive(Debug, Clone, Deserialize)]
+pub struct YieldWrapSettings {
+    #[serde(default)]
+    pub enabled: bool,
+    // Aave V3 Pool
+    #[serde(default = "default_lending_pool")]
+    pub lending_pool: Address,
+    // Gas of supply/withdraw on top of the swap hop the step is counted as
+    #[serde(default = "default_wrap_gas")]
+    pub wrap_gas: u64,
+    #[serde(default = "default_unwrap_gas")]
+    pub unwrap_gas: u64,
+    #[serde(default)]
+    pub assets: Vec<WrappedAsset>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub struct WrappedAsset {
+    pub a_token: Address,
+    pub underlying: Address,
+}
+
+fn default_lending_pool() -> Address {
+    "0x794a61358D6845594F94dc1DB02A252b5b4814aD".parse().unwrap()
+}
+
+fn default_wrap_gas() -> u64 {
+    120_000
+}
+
+fn default_unwrap_gas() -> u64 {
+    100_000
+}
+
+impl Default for YieldWrapSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            lending_pool: default_lending_pool(),
+            wrap_gas: default_wrap_gas(),
+            unwrap_gas: default_unwrap_gas(),
+            assets: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct YieldWraps {
+    settings: YieldWrapSettings,
+}
+
+impl YieldWraps {
+    pub fn new(settings: YieldWrapSettings) -> Self {
+        Self { settings }
+    }
+
+    // Pseudo-pool reserves: 1:1 and deep enough that no route size moves them
+    pub fn depth() -> U256 {
+        U256::exp10(36)
+    }
+
+    fn asset(&self, pool: &Address) -> Option<&WrappedAsset> {
+        if !self.settings.enabled {
+            return None;
+        }
+        self.settings.assets.iter().find(|asset| asset.a_token == *pool)
+    }
+
+    pub fn is_adapter(&self, pool: &Address) -> bool {
+        self.asset(pool).is_some()
+    }
+
+    // (pseudo-pool, underlying as token0, aToken as token1) per adapter
+    pub fn adapters(&self) -> Vec<(Address, Address, Address)> {
+        if !self.settings.enabled {
+            return Vec::new();
+        }
+        self.settings
+            .assets
+            .iter()
+            .map(|asset| (asset.a_token, asset.underlying, asset.a_token))
+            .collect()
+    }
+
+    pub fn reserves(&self, pool: &Address) -> Option<(U256, U256)> {
+        self.asset(pool).map(|_| (Self::depth(), Self::depth()))
+    }
+
+    // What the executor calls for the step instead of a swap router
+    pub fn router(&self, pool: &Address) -> Option<Address> {
+        self.asset(pool).map(|_| self.settings.lending_pool)
+    }
+
+    // Extra gas of passing through `pool` with `token_in`; zero for real pools
+    pub fn step_gas(&self, pool: &Address, token_in: &Address) -> u64 {
+        match self.asset(pool) {
+            Some(asset) if asset.underlying == *token_in => self.settings.wrap_gas,
+            Some(_) => self.settings.unwrap_gas,
+            None => 0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::amm_math::get_amount_out;
+    use crate::fixtures::address;
+    use crate::route_search::{RouteSearch, SpreadHistory, TokenGraph};
+
+    #[test]
+    fn test_unwrap_step_closes_a_cycle_through_an_atoken_pool() {
+        let (usdc, am_usdc, weth) = (address(1), address(2), address(3));
+        let wraps = YieldWraps::new(YieldWrapSettings {
+            enabled: true,
+            assets: vec![WrappedAsset {
+                a_token: am_usdc,
+                underlying: usdc,
+            }],
+            ..Default::default()
+        });
+        assert_eq!(wraps.step_gas(&am_usdc, &usdc), 120_000);
+        assert_eq!(wraps.step_gas(&am_usdc, &am_usdc), 100_000);
+        assert_eq!(wraps.step_gas(&address(9), &usdc), 0);
+
+        // 1:1 at any route size, to within a unit per 10^12
+        let amount = U256::exp10(24);
+        let (reserve_in, reserve_out) = wraps.reserves(&am_usdc).unwrap();
+        assert!(get_amount_out(amount, reserve_in, reserve_out, 0) >= amount - amount / U256::exp10(12));
+
+        // WETH is cheaper against amUSDC than against USDC; only the unwrap closes the cycle
+        let units = |amount: u64| U256::from(amount) * U256::exp10(18);
+        let mut graph = TokenGraph::new();
+        graph.add_pool(address(10), usdc, weth, units(2_000_000), units(1_000), 3000);
+        graph.add_pool(address(11), weth, am_usdc, units(1_000), units(2_100_000), 3000);
+        let (without, _) = RouteSearch::new(4, 1_000).search(&graph, &SpreadHistory::default(), usdc, units(1_000));
+        assert!(without.is_empty());
+
+        for (pool, token0, token1) in wraps.adapters() {
+            graph.add_pool(pool, token0, token1, YieldWraps::depth(), YieldWraps::depth(), 0);
+        }
+        let (with, _) = RouteSearch::new(4, 1_000).search(&graph, &SpreadHistory::default(), usdc, units(1_000));
+        assert_eq!(with[0].pools, vec![address(10), address(11), am_usdc]);
+        assert_eq!(wraps.router(&am_usdc), Some(default_lending_pool()));
+    }
+}